@@ -0,0 +1,25 @@
+/** A problem found in a program, with a 1-based position (0 if unknown) */
+export interface Diagnostic {
+  severity: "error" | "warning" | "note";
+  message: string;
+  line: number;
+  column: number;
+  code: string;
+  args: unknown[];
+}
+
+/** What a run printed, and why it stopped if it did not finish */
+export interface Result {
+  ok: boolean;
+  output: string;
+  diagnostics: Diagnostic[];
+}
+
+/** Return the diagnostics for source without running it */
+export function check(source: string, options?: { name?: string }): Diagnostic[];
+
+/** Parse and run source, returning its output and any diagnostics */
+export function run(source: string, options?: { input?: string; name?: string }): Result;
+
+/** Return source laid out in the house style; throws if it does not parse */
+export function format(source: string): string;
@@ -0,0 +1,91 @@
+// Run Cambridge Pseudocode from Node.js.
+//
+// A thin wrapper over the libcambridge shared library (`make build-node`
+// puts it next to this file; set CAMBRIDGE_LIB to use one elsewhere):
+//
+//     const cambridge = require("cambridge-lang");
+//
+//     const result = cambridge.run(source, { input: "Ada\n" });
+//     if (result.ok) console.log(result.output);
+//     for (const d of result.diagnostics) console.log(`${d.line}:${d.column}: ${d.message}`);
+
+"use strict";
+
+const path = require("path");
+const koffi = require("koffi");
+
+function libraryPath() {
+  if (process.env.CAMBRIDGE_LIB) return process.env.CAMBRIDGE_LIB;
+  const name = { win32: "libcambridge.dll", darwin: "libcambridge.dylib" }[process.platform] || "libcambridge.so";
+  return path.join(__dirname, name);
+}
+
+const lib = koffi.load(libraryPath());
+const newHandle = lib.func("uintptr_t cambridge_new()");
+const load = lib.func("int cambridge_load(uintptr_t, const char *, const char *)");
+const setInput = lib.func("void cambridge_set_input(uintptr_t, const char *)");
+const runHandle = lib.func("int cambridge_run(uintptr_t)");
+// Returned strings are kept as pointers so they can be freed
+const output = lib.func("void *cambridge_output(uintptr_t)");
+const diagnostics = lib.func("void *cambridge_diagnostics(uintptr_t)");
+const formatSource = lib.func("void *cambridge_format(const char *)");
+const freeString = lib.func("void cambridge_free_string(void *)");
+const free = lib.func("void cambridge_free(uintptr_t)");
+
+function takeString(pointer) {
+  try {
+    return koffi.decode(pointer, "char", -1);
+  } finally {
+    freeString(pointer);
+  }
+}
+
+// withDefaults fills in the fields JSON leaves out when they are empty
+function withDefaults(d) {
+  return { severity: d.severity, message: d.message, line: d.line || 0, column: d.column || 0, code: d.code || "", args: d.args || [] };
+}
+
+function loadProgram(source, name) {
+  const handle = newHandle();
+  const errors = load(handle, name, source);
+  return { handle, errors };
+}
+
+function readDiagnostics(handle) {
+  return JSON.parse(takeString(diagnostics(handle))).map(withDefaults);
+}
+
+// check returns the diagnostics for source without running it
+function check(source, { name = "main.pseudo" } = {}) {
+  const { handle } = loadProgram(source, name);
+  try {
+    return readDiagnostics(handle);
+  } finally {
+    free(handle);
+  }
+}
+
+// run parses and runs source, returning its output and any diagnostics
+function run(source, { input = "", name = "main.pseudo" } = {}) {
+  const { handle, errors } = loadProgram(source, name);
+  try {
+    if (errors > 0) {
+      return { ok: false, output: "", diagnostics: readDiagnostics(handle) };
+    }
+    setInput(handle, input);
+    const failed = runHandle(handle);
+    return { ok: failed === 0, output: takeString(output(handle)), diagnostics: readDiagnostics(handle) };
+  } finally {
+    free(handle);
+  }
+}
+
+// format returns source laid out in the house style, throwing if it does
+// not parse
+function format(source) {
+  const pointer = formatSource(source);
+  if (!pointer) throw new Error("cannot format a program with errors");
+  return takeString(pointer);
+}
+
+module.exports = { check, run, format };
@@ -0,0 +1,204 @@
+"""Run Cambridge Pseudocode from Python.
+
+A thin wrapper over the libcambridge shared library (``make build-python``
+puts it next to this file; set CAMBRIDGE_LIB to use one elsewhere):
+
+    import cambridge_lang
+
+    result = cambridge_lang.run(source, input="Ada\\n")
+    if result.ok:
+        print(result.output)
+    for d in result.diagnostics:
+        print(f"{d.line}:{d.column}: {d.message}")
+"""
+
+import ctypes
+import json
+import os
+import sys
+from dataclasses import dataclass, field
+
+__version__ = "0.2.0"
+
+__all__ = ["Diagnostic", "Result", "Program", "Session", "parse", "check", "run", "format"]
+
+
+def _library_path():
+    if os.environ.get("CAMBRIDGE_LIB"):
+        return os.environ["CAMBRIDGE_LIB"]
+    if sys.platform == "win32":
+        name = "libcambridge.dll"
+    elif sys.platform == "darwin":
+        name = "libcambridge.dylib"
+    else:
+        name = "libcambridge.so"
+    return os.path.join(os.path.dirname(os.path.abspath(__file__)), name)
+
+
+_lib = ctypes.CDLL(_library_path())
+_lib.cambridge_new.restype = ctypes.c_size_t
+_lib.cambridge_load.argtypes = [ctypes.c_size_t, ctypes.c_char_p, ctypes.c_char_p]
+_lib.cambridge_load.restype = ctypes.c_int
+_lib.cambridge_set_input.argtypes = [ctypes.c_size_t, ctypes.c_char_p]
+_lib.cambridge_run.argtypes = [ctypes.c_size_t]
+_lib.cambridge_run.restype = ctypes.c_int
+_lib.cambridge_eval.argtypes = [ctypes.c_size_t]
+_lib.cambridge_eval.restype = ctypes.c_int
+# Returned strings are kept as pointers so they can be freed
+_lib.cambridge_output.argtypes = [ctypes.c_size_t]
+_lib.cambridge_output.restype = ctypes.c_void_p
+_lib.cambridge_diagnostics.argtypes = [ctypes.c_size_t]
+_lib.cambridge_diagnostics.restype = ctypes.c_void_p
+_lib.cambridge_format.argtypes = [ctypes.c_char_p]
+_lib.cambridge_format.restype = ctypes.c_void_p
+_lib.cambridge_free_string.argtypes = [ctypes.c_void_p]
+_lib.cambridge_free.argtypes = [ctypes.c_size_t]
+
+
+def _take_string(pointer):
+    try:
+        return ctypes.string_at(pointer).decode("utf-8")
+    finally:
+        _lib.cambridge_free_string(pointer)
+
+
+def _diagnostics(handle):
+    found = json.loads(_take_string(_lib.cambridge_diagnostics(handle)))
+    return [
+        Diagnostic(
+            severity=d["severity"],
+            message=d["message"],
+            line=d.get("line", 0),
+            column=d.get("column", 0),
+            code=d.get("code", ""),
+            args=d.get("args", []),
+        )
+        for d in found
+    ]
+
+
+@dataclass
+class Diagnostic:
+    """A problem found in a program, with a 1-based position (0 if unknown)."""
+
+    severity: str
+    message: str
+    line: int = 0
+    column: int = 0
+    code: str = ""
+    args: list = field(default_factory=list)
+
+
+@dataclass
+class Result:
+    """What a run printed, and why it stopped if it did not finish."""
+
+    ok: bool
+    output: str
+    diagnostics: list
+
+
+class Program:
+    """Parsed source that can be run any number of times."""
+
+    def __init__(self, source, name="main.pseudo"):
+        self.name = name
+        self._handle = _lib.cambridge_new()
+        self._errors = _lib.cambridge_load(self._handle, name.encode("utf-8"), source.encode("utf-8"))
+        self.diagnostics = _diagnostics(self._handle)
+
+    @property
+    def ok(self):
+        """Whether the program parsed without errors."""
+        return self._errors == 0
+
+    def run(self, input=""):
+        """Run from the start with input as what INPUT reads."""
+        _lib.cambridge_set_input(self._handle, input.encode("utf-8"))
+        failed = _lib.cambridge_run(self._handle)
+        output = _take_string(_lib.cambridge_output(self._handle))
+        return Result(ok=failed == 0, output=output, diagnostics=_diagnostics(self._handle))
+
+    def close(self):
+        """Free the interpreter; the program cannot be run afterwards."""
+        if getattr(self, "_handle", 0):
+            _lib.cambridge_free(self._handle)
+            self._handle = 0
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *exc):
+        self.close()
+
+    def __del__(self):
+        self.close()
+
+
+class Session:
+    """Runs pieces of source one after another in the same variables.
+
+    Each piece may use the variables, routines and types of those before
+    it, as the cells of a notebook do.
+    """
+
+    def __init__(self, name="cell"):
+        self.name = name
+        self.count = 0
+        self._handle = _lib.cambridge_new()
+
+    def run(self, source, input=""):
+        """Run source after the pieces run before it."""
+        self.count += 1
+        name = f"{self.name}[{self.count}]"
+        if _lib.cambridge_load(self._handle, name.encode("utf-8"), source.encode("utf-8")) > 0:
+            return Result(ok=False, output="", diagnostics=_diagnostics(self._handle))
+        _lib.cambridge_set_input(self._handle, input.encode("utf-8"))
+        failed = _lib.cambridge_eval(self._handle)
+        output = _take_string(_lib.cambridge_output(self._handle))
+        return Result(ok=failed == 0, output=output, diagnostics=_diagnostics(self._handle))
+
+    def close(self):
+        """Free the session and its variables."""
+        if getattr(self, "_handle", 0):
+            _lib.cambridge_free(self._handle)
+            self._handle = 0
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *exc):
+        self.close()
+
+    def __del__(self):
+        self.close()
+
+
+def parse(source, name="main.pseudo"):
+    """Parse source, returning a Program whose diagnostics list any errors."""
+    return Program(source, name)
+
+
+def check(source, name="main.pseudo"):
+    """Return the diagnostics for source without running it."""
+    with Program(source, name) as program:
+        return program.diagnostics
+
+
+def run(source, input="", name="main.pseudo"):
+    """Parse and run source, returning its output and any diagnostics."""
+    with Program(source, name) as program:
+        if not program.ok:
+            return Result(ok=False, output="", diagnostics=program.diagnostics)
+        return program.run(input)
+
+
+def format(source):
+    """Return source laid out in the house style.
+
+    Raises ValueError if source does not parse.
+    """
+    pointer = _lib.cambridge_format(source.encode("utf-8"))
+    if not pointer:
+        raise ValueError("cannot format a program with errors")
+    return _take_string(pointer)
@@ -0,0 +1,143 @@
+"""A Jupyter kernel that runs Cambridge Pseudocode cells.
+
+Install it for the current user with
+
+    python -m cambridge_lang.kernel install
+
+and pick "Cambridge Pseudocode" as a notebook's kernel. Variables, routines
+and types carry over from one cell to the next. A cell that reads INPUT
+takes its input from %input lines at the top:
+
+    %input Ada
+    DECLARE Name : STRING
+    INPUT Name
+    OUTPUT "Hello, ", Name
+"""
+
+import json
+import os
+import sys
+import tempfile
+
+from ipykernel.kernelbase import Kernel
+
+from . import Session, __version__
+
+INPUT_MAGIC = "%input"
+
+RED = "\x1b[31m"
+YELLOW = "\x1b[33m"
+BOLD = "\x1b[1m"
+RESET = "\x1b[0m"
+
+
+def split_input(code):
+    """Separate the %input lines at the top of a cell from its source.
+
+    They are blanked rather than removed, so that line numbers in errors
+    match the cell.
+    """
+    lines = code.split("\n")
+    values = []
+    for idx, line in enumerate(lines):
+        stripped = line.strip()
+        if stripped.startswith(INPUT_MAGIC):
+            values.append(stripped[len(INPUT_MAGIC):].strip())
+            lines[idx] = ""
+        elif stripped:
+            break
+    text = "".join(value + "\n" for value in values)
+    return "\n".join(lines), text
+
+
+def describe(diagnostic, name, lines):
+    """Lay out a diagnostic as traceback lines: where it is, then the line
+    of the cell it refers to with a caret under the column."""
+    colour = RED if diagnostic.severity == "error" else YELLOW
+    code = f"[{diagnostic.code}]" if diagnostic.code else ""
+    where = f"{name}:{diagnostic.line}:{diagnostic.column}" if diagnostic.line else name
+    described = [f"{BOLD}{where}: {colour}{diagnostic.severity}{code}{RESET}{BOLD}: {diagnostic.message}{RESET}"]
+    if 0 < diagnostic.line <= len(lines):
+        described.append(f"    {lines[diagnostic.line - 1]}")
+        if diagnostic.column > 0:
+            described.append(f"    {' ' * (diagnostic.column - 1)}{colour}^{RESET}")
+    return described
+
+
+class CambridgeKernel(Kernel):
+    implementation = "cambridge_lang"
+    implementation_version = __version__
+    language = "cambridge"
+    language_version = "9618"
+    language_info = {
+        "name": "cambridge",
+        "mimetype": "text/x-cambridge-pseudocode",
+        "file_extension": ".pseudo",
+    }
+    banner = "Cambridge International AS & A Level pseudocode"
+
+    def __init__(self, **kwargs):
+        super().__init__(**kwargs)
+        self.session = Session()
+
+    def do_execute(self, code, silent, store_history=True, user_expressions=None, allow_stdin=False):
+        source, text = split_input(code)
+        result = self.session.run(source, input=text)
+        if result.output and not silent:
+            self.send_response(self.iopub_socket, "stream", {"name": "stdout", "text": result.output})
+
+        name = f"{self.session.name}[{self.session.count}]"
+        lines = source.split("\n")
+        errors = [d for d in result.diagnostics if d.severity == "error"]
+        others = [d for d in result.diagnostics if d.severity != "error"]
+        if others and not silent:
+            text = "\n".join(line for d in others for line in describe(d, name, lines)) + "\n"
+            self.send_response(self.iopub_socket, "stream", {"name": "stderr", "text": text})
+
+        if result.ok:
+            return {"status": "ok", "execution_count": self.execution_count, "payload": [], "user_expressions": {}}
+
+        first = errors[0] if errors else None
+        reply = {
+            "status": "error",
+            "ename": first.code if first and first.code else "Error",
+            "evalue": first.message if first else "the program stopped",
+            "traceback": [line for d in errors for line in describe(d, name, lines)],
+        }
+        if not silent:
+            self.send_response(self.iopub_socket, "error", reply)
+        return {**reply, "execution_count": self.execution_count}
+
+    def do_shutdown(self, restart):
+        self.session.close()
+        if restart:
+            self.session = Session()
+        return {"status": "ok", "restart": restart}
+
+
+def install(user=True, prefix=None):
+    """Register the kernel with Jupyter."""
+    from jupyter_client.kernelspec import KernelSpecManager
+
+    spec = {
+        "argv": [sys.executable, "-m", "cambridge_lang.kernel", "-f", "{connection_file}"],
+        "display_name": "Cambridge Pseudocode",
+        "language": "cambridge",
+    }
+    with tempfile.TemporaryDirectory() as directory:
+        with open(os.path.join(directory, "kernel.json"), "w") as f:
+            json.dump(spec, f, indent=2)
+        KernelSpecManager().install_kernel_spec(directory, "cambridge", user=user, prefix=prefix)
+
+
+def main():
+    if sys.argv[1:2] == ["install"]:
+        install(user="--sys-prefix" not in sys.argv, prefix=sys.prefix if "--sys-prefix" in sys.argv else None)
+        return
+    from ipykernel.kernelapp import IPKernelApp
+
+    IPKernelApp.launch_instance(kernel_class=CambridgeKernel)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,41 @@
+package main
+
+import (
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/cache"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+)
+
+// analysis is what requests about one version of a document start from:
+// an editor asks for hints, lenses, folding and highlighting after each
+// edit, and they share a single parse
+// It is shared between requests, so must not be changed.
+type analysis struct {
+	program  *ast.Program
+	table    *symbols.Table
+	problems []diagnostic.Diagnostic // found while parsing
+}
+
+// parsed reports whether the program parsed without errors
+func (a *analysis) parsed() bool {
+	return len(a.problems) == 0
+}
+
+var analysisCache = cache.NewMemory[*analysis](cachedAnalyses)
+
+// analyse parses text and builds its symbol table, or returns those kept
+// from the last time text was seen
+func analyse(text string) *analysis {
+	key := cache.Key(text)
+	if a, ok := analysisCache.Get(key); ok {
+		return a
+	}
+	p := parser.New(lexer.New(text))
+	prog := p.ParseProgram()
+	a := &analysis{program: prog, table: symbols.Build(prog, text), problems: p.Diagnostics()}
+	analysisCache.Put(key, a)
+	return a
+}
@@ -0,0 +1,124 @@
+package main
+
+import (
+	"github.com/andrinoff/cambridge-lang/pkg/callgraph"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+)
+
+// prepareCallHierarchy returns the routine named at a 0-based line and
+// character in the document uri, as the root of its call hierarchy, or
+// nil if no routine is named there
+func prepareCallHierarchy(uri, text string, line, character int) interface{} {
+	table, sym := symbolAt(text, line, character)
+	if sym == nil || sym.Kind != symbols.Procedure && sym.Kind != symbols.Function {
+		return nil
+	}
+	return []map[string]interface{}{hierarchyItem(uri, text, table, sym)}
+}
+
+// incomingCalls returns the routines that call the one item stands for,
+// with the main program as a caller of its own, and where each calls it
+func incomingCalls(uri, text string, item map[string]interface{}) []map[string]interface{} {
+	calls := []map[string]interface{}{}
+	table := analyse(text).table
+	sym := hierarchySymbol(table, item)
+	if sym == nil {
+		return calls
+	}
+	callers := map[*symbols.Symbol]int{}
+	for _, ref := range table.Uses(sym) {
+		caller, _ := enclosingRoutine(table, ref.Pos)
+		idx, seen := callers[caller]
+		if !seen {
+			idx = len(calls)
+			callers[caller] = idx
+			calls = append(calls, map[string]interface{}{"from": hierarchyItem(uri, text, table, caller), "fromRanges": []map[string]interface{}{}})
+		}
+		calls[idx]["fromRanges"] = append(calls[idx]["fromRanges"].([]map[string]interface{}), nameRange(text, ref.Pos, sym.Name))
+	}
+	return calls
+}
+
+// outgoingCalls returns the routines the one item stands for calls, and
+// where it calls each
+func outgoingCalls(uri, text string, item map[string]interface{}) []map[string]interface{} {
+	calls := []map[string]interface{}{}
+	table := analyse(text).table
+	sym := hierarchySymbol(table, item)
+	if sym == nil && !isMain(item) {
+		return calls
+	}
+	callees := map[*symbols.Symbol]int{}
+	for _, ref := range table.References {
+		callee := ref.Symbol
+		if callee.Kind != symbols.Procedure && callee.Kind != symbols.Function {
+			continue
+		}
+		if caller, _ := enclosingRoutine(table, ref.Pos); caller != sym {
+			continue
+		}
+		idx, seen := callees[callee]
+		if !seen {
+			idx = len(calls)
+			callees[callee] = idx
+			calls = append(calls, map[string]interface{}{"to": hierarchyItem(uri, text, table, callee), "fromRanges": []map[string]interface{}{}})
+		}
+		calls[idx]["fromRanges"] = append(calls[idx]["fromRanges"].([]map[string]interface{}), nameRange(text, ref.Pos, callee.Name))
+	}
+	return calls
+}
+
+// hierarchyItem describes the routine sym, or the main program if sym is
+// nil, remembering where it is declared so that later requests find it
+func hierarchyItem(uri, text string, table *symbols.Table, sym *symbols.Symbol) map[string]interface{} {
+	if sym == nil {
+		return map[string]interface{}{
+			"name":           callgraph.Main,
+			"kind":           SymbolFile,
+			"uri":            uri,
+			"range":          documentRange(text),
+			"selectionRange": nameRange(text, symbols.Position{Line: 1, Column: 1}, ""),
+			"data":           map[string]interface{}{"main": true},
+		}
+	}
+	_, inClass := enclosingRoutine(table, sym.Pos)
+	return map[string]interface{}{
+		"name":           sym.Name,
+		"detail":         routineSignature(sym)["label"],
+		"kind":           outlineKind(sym, inClass),
+		"uri":            uri,
+		"range":          spanRange(text, sym),
+		"selectionRange": nameRange(text, sym.Pos, sym.Name),
+		"data":           map[string]interface{}{"line": sym.Pos.Line, "column": sym.Pos.Column},
+	}
+}
+
+// hierarchySymbol finds the routine item was made for again
+func hierarchySymbol(table *symbols.Table, item map[string]interface{}) *symbols.Symbol {
+	data, _ := item["data"].(map[string]interface{})
+	line, _ := data["line"].(float64)
+	column, _ := data["column"].(float64)
+	sym := table.SymbolAt(symbols.Position{Line: int(line), Column: int(column)})
+	if sym == nil || sym.Kind != symbols.Procedure && sym.Kind != symbols.Function {
+		return nil
+	}
+	return sym
+}
+
+func isMain(item map[string]interface{}) bool {
+	data, _ := item["data"].(map[string]interface{})
+	main, _ := data["main"].(bool)
+	return main
+}
+
+// enclosingRoutine returns the procedure or function pos is in, or nil in
+// the main program, and whether it is a member of a class
+func enclosingRoutine(table *symbols.Table, pos symbols.Position) (*symbols.Symbol, bool) {
+	for scope := table.At(pos); scope != nil; scope = scope.Parent {
+		if owner := scope.Owner; owner != nil && (owner.Kind == symbols.Procedure || owner.Kind == symbols.Function) {
+			inClass := scope.Parent != nil && scope.Parent.Owner != nil && scope.Parent.Owner.Kind == symbols.Class
+			return owner, inClass
+		}
+	}
+	return nil, false
+}
@@ -0,0 +1,294 @@
+package main
+
+import (
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/check"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// continuations are the keywords that carry a block on at its own
+// indentation rather than ending it
+var continuations = map[token.Type]string{token.IF: "ELSE", token.CASE: "OTHERWISE"}
+
+// codeActions returns quick fixes for the problems in text on the 0-based
+// lines first to last: closing a block left open, assigning where = was
+// written, declaring a name or correcting its spelling, writing <> for !=,
+// and setting the program out as the pseudocode guide does
+func codeActions(uri string, documents map[string]string, first, last int) []map[string]interface{} {
+	text := documents[uri]
+	a := analyse(text)
+	found := a.problems
+	if a.parsed() {
+		found = append(checkProgram(uri, a.program, text, openDocuments(documents).read), check.Style(a.program, text, config.style)...)
+	}
+	lines := strings.Split(text, "\n")
+	widths := tokenWidths(text)
+	crowded := map[int]bool{} // lines holding more than one statement
+	for _, d := range found {
+		if d.Code == diagnostic.SeveralStatements {
+			crowded[d.Line] = true
+		}
+	}
+
+	actions := []map[string]interface{}{}
+	for _, d := range found {
+		if d.Line-1 < first || d.Line-1 > last {
+			continue
+		}
+		act := func(title string, preferred bool, edit map[string]interface{}) {
+			actions = append(actions, map[string]interface{}{
+				"title":       title,
+				"kind":        "quickfix",
+				"diagnostics": []map[string]interface{}{lspDiagnostic(text, d, widths)},
+				"isPreferred": preferred,
+				"edit":        edit,
+			})
+		}
+		fix := func(title string, preferred bool, edits ...map[string]interface{}) {
+			act(title, preferred, map[string]interface{}{"changes": map[string]interface{}{uri: edits}})
+		}
+		pos := symbols.Position{Line: d.Line, Column: d.Column}
+
+		switch d.Code {
+		case diagnostic.MissingBlockEnd:
+			if edit, keyword, ok := closeBlock(text, lines, d); ok {
+				fix("Insert "+keyword, true, edit)
+			}
+		case diagnostic.ComparisonStatement:
+			if edit, ok := assignInstead(text, lines, d); ok {
+				fix("Assign with "+edit["newText"].(string), true, edit)
+			}
+		case diagnostic.PossibleTypo, diagnostic.UndeclaredVariable:
+			name := d.Args[0].(string)
+			if d.Code == diagnostic.PossibleTypo {
+				suggestion := d.Args[1].(string)
+				fix("Change to "+suggestion, true, map[string]interface{}{"range": nameRange(text, pos, name), "newText": suggestion})
+			}
+			if edit, typ, ok := declaration(a.program, a.table, text, lines, pos, name); ok {
+				fix("Declare "+name+" : "+typ, d.Code == diagnostic.UndeclaredVariable, edit)
+			}
+		case diagnostic.KeywordCase:
+			keyword, upper := d.Args[0].(string), d.Args[1].(string)
+			fix("Write "+upper, true, map[string]interface{}{"range": nameRange(text, pos, keyword), "newText": upper})
+		case diagnostic.IdentifierCase:
+			suggestion := d.Args[1].(string)
+			// The edit renames the name in the files that include this
+			// one, or that it includes, as well
+			if renamed, err := rename(uri, documents, d.Line-1, d.Column-1, suggestion); err == nil {
+				if changes, _ := renamed.(map[string]interface{})["changes"].(map[string]interface{}); len(changes) > 0 {
+					act("Rename to "+suggestion, true, renamed.(map[string]interface{}))
+				}
+			}
+		case diagnostic.Misindented:
+			if d.Line <= len(lines) {
+				indent := strings.Repeat(config.style.Indent, d.Args[0].(int))
+				at := symbols.Position{Line: d.Line, Column: 1}
+				fix("Indent as the block", true, map[string]interface{}{"range": nameRange(text, at, indentation(lines[d.Line-1])), "newText": indent})
+			}
+		case diagnostic.SeveralStatements:
+			if edit, ok := splitLine(text, lines, d); ok {
+				fix("Move to a line of its own", true, edit)
+			}
+		case diagnostic.DeclarationPlacement:
+			if edits, ok := moveDeclaration(text, lines, d); ok && !crowded[d.Line] {
+				fix("Move to the other declarations", true, edits...)
+			}
+		}
+	}
+	return append(actions, notEqualFixes(uri, text, first, last)...)
+}
+
+// closeBlock returns an edit adding the keyword that closes the block d
+// reports open in text, split into lines, after the last line indented
+// within it
+// UNTIL needs a condition only the author can give, so is not offered.
+func closeBlock(text string, lines []string, d diagnostic.Diagnostic) (map[string]interface{}, string, bool) {
+	start, _ := d.Args[0].(token.Type)
+	end, _ := d.Args[1].(token.Type)
+	opener := d.Line - 1
+	if end == "" || end == token.UNTIL || opener >= len(lines) {
+		return nil, "", false
+	}
+	indent := indentation(lines[opener])
+	keyword := closing(lines[opener][d.Column-1:], end)
+
+	// The block ends before the first line after its body that is indented
+	// no further than the line opening it
+	cont := continuations[start]
+	at, body := len(lines), false
+	for idx := opener + 1; idx < len(lines); idx++ {
+		content := strings.TrimSpace(lines[idx])
+		if content == "" {
+			continue
+		}
+		continues := cont != "" && (content == cont || strings.HasPrefix(content, cont+" "))
+		if body && len(indentation(lines[idx])) <= len(indent) && !continues {
+			at = idx
+			break
+		}
+		body = true
+	}
+	for at > opener+1 && strings.TrimSpace(lines[at-1]) == "" {
+		at--
+	}
+
+	if at == len(lines) {
+		last := len(lines) - 1
+		return insertion(lspPosition(text, last, len(lines[last])), "\n"+indent+keyword), keyword, true
+	}
+	return insertion(lspPosition(text, at, 0), indent+keyword+"\n"), keyword, true
+}
+
+// closing is the keyword, written out, that ends the block opening at the
+// start of opening; NEXT is followed by the loop variable it counts
+func closing(opening string, end token.Type) string {
+	keyword := string(end)
+	if end == token.NEXT {
+		l := lexer.New(opening)
+		l.NextToken()
+		if tok := l.NextToken(); tok.Type == token.IDENT {
+			keyword += " " + tok.Literal
+		}
+	}
+	return keyword
+}
+
+// assignInstead returns an edit turning the = after the target d reports
+// into an assignment arrow, written as the rest of text writes them
+func assignInstead(text string, lines []string, d diagnostic.Diagnostic) (map[string]interface{}, bool) {
+	if d.Line > len(lines) {
+		return nil, false
+	}
+	arrow := "←"
+	if strings.Contains(text, "<-") && !strings.Contains(text, "←") {
+		arrow = "<-"
+	}
+	depth := 0
+	l := lexer.New(lines[d.Line-1][d.Column-1:])
+	for tok := l.NextToken(); tok.Type != token.EOF && tok.Type != token.NEWLINE; tok = l.NextToken() {
+		switch tok.Type {
+		case token.LPAREN, token.LBRACKET:
+			depth++
+		case token.RPAREN, token.RBRACKET:
+			depth--
+		case token.EQ:
+			if depth == 0 {
+				pos := symbols.Position{Line: d.Line, Column: d.Column + tok.Column - 1}
+				return map[string]interface{}{"range": nameRange(text, pos, "="), "newText": arrow}, true
+			}
+		}
+	}
+	return nil, false
+}
+
+// declaration returns an edit declaring name, used undeclared at pos of
+// text, at the top of the routine using it or else of the program
+// Its type is that of the first value assigned to it, or STRING if it is
+// first read by INPUT; if neither says, nothing is offered.
+func declaration(prog *ast.Program, table *symbols.Table, text string, lines []string, pos symbols.Position, name string) (map[string]interface{}, string, bool) {
+	var typ string
+	ast.Inspect(prog, func(node ast.Node) bool {
+		switch n := node.(type) {
+		case *ast.AssignmentStatement:
+			if id, ok := n.Name.(*ast.Identifier); ok && id.Value == name {
+				typ = check.TypeOf(table.At(symbols.Position{Line: id.Token.Line, Column: id.Token.Column}), n.Value)
+			}
+		case *ast.InputStatement:
+			if id, ok := n.Variable.(*ast.Identifier); ok && id.Value == name {
+				typ = "STRING"
+			}
+		}
+		return typ == ""
+	})
+	if typ == "" || len(prog.Statements) == 0 {
+		return nil, "", false
+	}
+
+	first, _ := ast.Position(prog.Statements[0])
+	line, indent := max(first-1, 0), ""
+	for scope := table.At(pos); scope != nil; scope = scope.Parent {
+		if owner := scope.Owner; owner != nil && (owner.Kind == symbols.Procedure || owner.Kind == symbols.Function) {
+			line, indent = owner.Start.Line, indentation(lines[owner.Start.Line-1])+config.style.Indent
+			break
+		}
+	}
+	return insertion(lspPosition(text, line, 0), indent+"DECLARE "+name+" : "+typ+"\n"), typ, true
+}
+
+// splitLine returns an edit starting the statement d reports in text on a
+// line of its own, indented as the one it shared
+func splitLine(text string, lines []string, d diagnostic.Diagnostic) (map[string]interface{}, bool) {
+	if d.Line > len(lines) || d.Column-1 > len(lines[d.Line-1]) {
+		return nil, false
+	}
+	line := lines[d.Line-1]
+	before := line[:d.Column-1]
+	end := len(strings.TrimRight(before, " \t"))
+	at := symbols.Position{Line: d.Line, Column: end + 1}
+	return map[string]interface{}{"range": nameRange(text, at, before[end:]), "newText": "\n" + indentation(line)}, true
+}
+
+// moveDeclaration returns the edits moving the DECLARE d reports in text
+// from its line to just before the line it belongs before, at that line's
+// indentation
+// Only a DECLARE starting its line is moved.
+func moveDeclaration(text string, lines []string, d diagnostic.Diagnostic) ([]map[string]interface{}, bool) {
+	target, _ := d.Args[1].(int)
+	if d.Line > len(lines) || target < 1 || target >= d.Line || len(indentation(lines[d.Line-1])) != d.Column-1 {
+		return nil, false
+	}
+	declaration := strings.TrimSpace(lines[d.Line-1])
+	remove := map[string]interface{}{
+		"range": map[string]interface{}{
+			"start": lspPosition(text, d.Line-1, 0),
+			"end":   lspPosition(text, d.Line, 0),
+		},
+		"newText": "",
+	}
+	return []map[string]interface{}{
+		insertion(lspPosition(text, target-1, 0), indentation(lines[target-1])+declaration+"\n"),
+		remove,
+	}, true
+}
+
+// notEqualFixes returns fixes replacing each != on lines first to last
+// with <>, the way pseudocode writes not equal to
+func notEqualFixes(uri, text string, first, last int) []map[string]interface{} {
+	var actions []map[string]interface{}
+	var prev token.Token
+	l := lexer.New(text)
+	for tok := l.NextToken(); tok.Type != token.EOF; tok = l.NextToken() {
+		if tok.Type == token.EQ && prev.Type == token.ILLEGAL && prev.Literal == "!" && prev.Line == tok.Line && prev.Column+1 == tok.Column && tok.Line-1 >= first && tok.Line-1 <= last {
+			edit := map[string]interface{}{
+				"range":   nameRange(text, symbols.Position{Line: prev.Line, Column: prev.Column}, "!="),
+				"newText": "<>",
+			}
+			actions = append(actions, map[string]interface{}{
+				"title":       "Replace != with <>",
+				"kind":        "quickfix",
+				"isPreferred": true,
+				"edit":        map[string]interface{}{"changes": map[string]interface{}{uri: []map[string]interface{}{edit}}},
+			})
+		}
+		prev = tok
+	}
+	return actions
+}
+
+// insertion is an edit inserting text at the LSP position at
+func insertion(at map[string]int, text string) map[string]interface{} {
+	return map[string]interface{}{
+		"range":   map[string]interface{}{"start": at, "end": at},
+		"newText": text,
+	}
+}
+
+// indentation returns the whitespace line starts with
+func indentation(line string) string {
+	return line[:len(line)-len(strings.TrimLeft(line, " \t"))]
+}
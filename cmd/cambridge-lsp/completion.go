@@ -0,0 +1,313 @@
+package main
+
+import (
+	"sort"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// LSP completion item kinds
+const (
+	CompletionFunction   = 3
+	CompletionField      = 5
+	CompletionVariable   = 6
+	CompletionClass      = 7
+	CompletionKeyword    = 14
+	CompletionSnippet    = 15
+	CompletionEnumMember = 20
+	CompletionConstant   = 21
+	CompletionStruct     = 22
+)
+
+// Completions are ranked by a sortText prefix: the keyword that closes the
+// block being written first, then the program's own names, then the rest
+const (
+	rankCloser     = "0"
+	rankIdentifier = "1"
+	rankKeyword    = "2"
+)
+
+// symbolKinds maps each kind of symbol to the completion item kind shown
+// for it
+var symbolKinds = map[symbols.Kind]int{
+	symbols.Variable:  CompletionVariable,
+	symbols.Constant:  CompletionConstant,
+	symbols.Parameter: CompletionVariable,
+	symbols.Procedure: CompletionFunction,
+	symbols.Function:  CompletionFunction,
+	symbols.Type:      CompletionStruct,
+	symbols.Class:     CompletionClass,
+	symbols.Field:     CompletionField,
+	symbols.EnumValue: CompletionEnumMember,
+}
+
+// blockKeywords maps each keyword opening a block to the keyword closing it
+var blockKeywords = map[token.Type]token.Type{
+	token.IF:        token.ENDIF,
+	token.CASE:      token.ENDCASE,
+	token.FOR:       token.NEXT,
+	token.WHILE:     token.ENDWHILE,
+	token.REPEAT:    token.UNTIL,
+	token.PROCEDURE: token.ENDPROCEDURE,
+	token.FUNCTION:  token.ENDFUNCTION,
+	token.CLASS:     token.ENDCLASS,
+	token.TYPE:      token.ENDTYPE,
+}
+
+// snippetFormat is the LSP insertTextFormat for text with tab stops
+const snippetFormat = 2
+
+// snippets expand the keyword opening a block into the whole structure,
+// laid out as the formatter would, with tab stops for the parts to fill in
+// Each leading tab in a line stands for one level of indentation.
+var snippets = []struct {
+	keyword string
+	lines   []string
+}{
+	{"IF", []string{"IF ${1:Condition} THEN", "\t$2", "ELSE", "\t$0", "ENDIF"}},
+	{"FOR", []string{"FOR ${1:Index} <- ${2:1} TO ${3:10}", "\t$0", "NEXT $1"}},
+	{"WHILE", []string{"WHILE ${1:Condition}", "\t$0", "ENDWHILE"}},
+	{"REPEAT", []string{"REPEAT", "\t$0", "UNTIL ${1:Condition}"}},
+	{"CASE", []string{"CASE OF ${1:Identifier}", "\t${2:Value} : $3", "\tOTHERWISE : $0", "ENDCASE"}},
+	{"PROCEDURE", []string{"PROCEDURE ${1:Name}($2)", "\t$0", "ENDPROCEDURE"}},
+	{"FUNCTION", []string{"FUNCTION ${1:Name}($2) RETURNS ${3:INTEGER}", "\t$0", "ENDFUNCTION"}},
+	{"TYPE", []string{"TYPE ${1:Name}", "\tDECLARE ${2:Field} : ${3:STRING}", "ENDTYPE"}},
+	{"CLASS", []string{"CLASS ${1:Name}", "\tPRIVATE DECLARE ${2:Field} : ${3:STRING}", "\tPUBLIC PROCEDURE NEW($4)", "\t\t$0", "\tENDPROCEDURE", "ENDCLASS"}},
+}
+
+// innerKeywords are only offered inside a block of the given kind
+var innerKeywords = map[token.Type]token.Type{
+	token.ELSE:      token.IF,
+	token.OTHERWISE: token.CASE,
+}
+
+// closers is the set of keywords that end a block
+var closers = func() map[token.Type]bool {
+	set := make(map[token.Type]bool, len(blockKeywords))
+	for _, closer := range blockKeywords {
+		set[closer] = true
+	}
+	return set
+}()
+
+// completion returns the items to offer at a 0-based line and character in
+// text: the names visible there, keywords that fit the blocks open there and
+// every builtin function; or, after a dot, the members of what comes before
+func completion(text string, line, character int) []map[string]interface{} {
+	scope := analyse(text).table.At(symbols.Position{Line: line + 1, Column: character + 1})
+	if members, ok := memberCompletion(scope, text, line, character); ok {
+		return members
+	}
+
+	open := openBlocks(text, line, character)
+	var innermost token.Type
+	if len(open) > 0 {
+		innermost = open[len(open)-1]
+	}
+
+	items := []map[string]interface{}{}
+	for keyword, typ := range token.Keywords {
+		rank := rankKeyword
+		if closers[typ] {
+			if len(open) == 0 || blockKeywords[innermost] != typ {
+				continue
+			}
+			rank = rankCloser
+		}
+		if block, ok := innerKeywords[typ]; ok && innermost != block {
+			continue
+		}
+		items = append(items, map[string]interface{}{
+			"label":    keyword,
+			"kind":     CompletionKeyword,
+			"detail":   "keyword",
+			"sortText": rank + keyword,
+		})
+	}
+
+	for _, snippet := range snippets {
+		lines := make([]string, len(snippet.lines))
+		for i, line := range snippet.lines {
+			body := strings.TrimLeft(line, "\t")
+			lines[i] = strings.Repeat(config.style.Indent, len(line)-len(body)) + body
+		}
+		items = append(items, map[string]interface{}{
+			"label":            snippet.keyword,
+			"kind":             CompletionSnippet,
+			"detail":           snippet.keyword + " block",
+			"insertText":       strings.Join(lines, "\n"),
+			"insertTextFormat": snippetFormat,
+			"sortText":         rankKeyword + snippet.keyword + " ",
+		})
+	}
+
+	for _, sym := range scope.Visible() {
+		items = append(items, map[string]interface{}{
+			"label":    sym.Name,
+			"kind":     symbolKinds[sym.Kind],
+			"detail":   sym.Detail,
+			"sortText": rankIdentifier + sym.Name,
+		})
+	}
+
+	for name, sig := range builtins.Signatures {
+		items = append(items, map[string]interface{}{
+			"label":         name,
+			"kind":          CompletionFunction,
+			"detail":        sig.String(),
+			"documentation": sig.Doc,
+			"sortText":      rankKeyword + name,
+		})
+	}
+
+	sortItems(items)
+	return items
+}
+
+// memberCompletion returns the fields or members to offer after a dot at a
+// 0-based line and character, and false if the cursor does not follow a
+// dot or what comes before it has no members
+// PRIVATE members are only offered inside their own class.
+func memberCompletion(scope *symbols.Scope, text string, line, character int) ([]map[string]interface{}, bool) {
+	toks := tokensBefore(text, line, character)
+	// A member name being typed is replaced, so look past it
+	if n := len(toks); n > 1 && toks[n-1].Type == token.IDENT && toks[n-2].Type == token.DOT {
+		toks = toks[:n-1]
+	}
+	dot := len(toks) - 1
+	if dot < 1 || toks[dot].Type != token.DOT {
+		return nil, false
+	}
+	custom, ok := receiverType(scope, text, toks, dot)
+	if !ok {
+		return nil, false
+	}
+
+	class := ""
+	for s := scope; s != nil; s = s.Parent {
+		if s.Owner != nil && s.Owner.Kind == symbols.Class {
+			class = s.Owner.Name
+		}
+	}
+	items := []map[string]interface{}{}
+	for _, m := range scope.Members(custom.Name) {
+		if m.Access == "PRIVATE" && class != custom.Name || m.Name == "NEW" {
+			continue
+		}
+		items = append(items, map[string]interface{}{
+			"label":    m.Name,
+			"kind":     symbolKinds[m.Kind],
+			"detail":   m.Detail,
+			"sortText": rankIdentifier + m.Name,
+		})
+	}
+	sortItems(items)
+	return items, true
+}
+
+// tokensBefore returns the tokens of text that start before a 0-based line
+// and character
+func tokensBefore(text string, line, character int) []token.Token {
+	var toks []token.Token
+	l := lexer.New(text)
+	for tok := l.NextToken(); tok.Type != token.EOF; tok = l.NextToken() {
+		if tok.Line-1 > line || tok.Line-1 == line && tok.Column-1 >= character {
+			break
+		}
+		toks = append(toks, tok)
+	}
+	return toks
+}
+
+// receiverType returns the declared type of what comes before the dot at
+// toks[dot], if it is a record type or class
+func receiverType(scope *symbols.Scope, text string, toks []token.Token, dot int) (*ast.CustomType, bool) {
+	first := accessStart(toks, dot)
+	lines := strings.Split(text, "\n")
+	if first < 0 || toks[first].Line != toks[dot].Line || toks[dot].Line > len(lines) {
+		return nil, false
+	}
+	source := lines[toks[dot].Line-1][toks[first].Column-1 : toks[dot].Column-1]
+	stmts := parser.New(lexer.New(source)).ParseProgram().Statements
+	if len(stmts) != 1 {
+		return nil, false
+	}
+	stmt, ok := stmts[0].(*ast.ExpressionStatement)
+	if !ok {
+		return nil, false
+	}
+	custom, ok := scope.TypeOf(stmt.Expression).(*ast.CustomType)
+	return custom, ok
+}
+
+// accessStart returns the index of the first token of the names, indexes
+// and calls chained by dots that end just before the dot at toks[dot], or
+// -1 if something else comes before it
+func accessStart(toks []token.Token, dot int) int {
+	idx := dot - 1
+	for {
+		// Skip back over [indexes] and (arguments)
+		for idx >= 0 && (toks[idx].Type == token.RBRACKET || toks[idx].Type == token.RPAREN) {
+			depth := 0
+			for ; idx >= 0; idx-- {
+				switch toks[idx].Type {
+				case token.RBRACKET, token.RPAREN:
+					depth++
+				case token.LBRACKET, token.LPAREN:
+					depth--
+				}
+				if depth == 0 {
+					break
+				}
+			}
+			idx--
+		}
+		if idx < 0 || toks[idx].Type != token.IDENT {
+			return -1
+		}
+		if idx == 0 || toks[idx-1].Type != token.DOT {
+			return idx
+		}
+		idx -= 2
+	}
+}
+
+// sortItems orders completion items by their sortText
+func sortItems(items []map[string]interface{}) {
+	sort.Slice(items, func(i, j int) bool {
+		return items[i]["sortText"].(string) < items[j]["sortText"].(string)
+	})
+}
+
+// openBlocks returns the blocks that are open before a 0-based line and
+// character, innermost last
+func openBlocks(text string, line, character int) []token.Type {
+	var open []token.Type
+	typeLine := 0 // the line of an open TYPE, which = on the same line closes
+	l := lexer.New(text)
+	for tok := l.NextToken(); tok.Type != token.EOF; tok = l.NextToken() {
+		if tok.Line-1 > line || tok.Line-1 == line && tok.Column-1 >= character {
+			break
+		}
+		switch {
+		case tok.Type == token.EQ && len(open) > 0 && open[len(open)-1] == token.TYPE && tok.Line == typeLine:
+			open = open[:len(open)-1]
+		case blockKeywords[tok.Type] != "":
+			open = append(open, tok.Type)
+			if tok.Type == token.TYPE {
+				typeLine = tok.Line
+			}
+		case closers[tok.Type]:
+			if len(open) > 0 && blockKeywords[open[len(open)-1]] == tok.Type {
+				open = open[:len(open)-1]
+			}
+		}
+	}
+	return open
+}
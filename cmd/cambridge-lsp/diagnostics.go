@@ -0,0 +1,278 @@
+package main
+
+import (
+	"sort"
+	"strings"
+	"sync"
+	"time"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/cache"
+	"github.com/andrinoff/cambridge-lang/pkg/check"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/include"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// diagnosticsDelay is how long typing must pause before a changed document
+// is checked again, so that a burst of keystrokes costs one parse
+const diagnosticsDelay = 200 * time.Millisecond
+
+// LSP diagnostic severities
+const (
+	SeverityError       = 1
+	SeverityWarning     = 2
+	SeverityInformation = 3
+)
+
+// tagUnnecessary marks a diagnostic about code that could be removed
+const tagUnnecessary = 1
+
+var (
+	pendingMu          sync.Mutex
+	pendingDiagnostics = map[string]*time.Timer{}
+)
+
+// scheduleDiagnostics publishes diagnostics for text once typing in uri
+// pauses, replacing any check still waiting for an earlier version
+func scheduleDiagnostics(uri, text string) {
+	opts := config
+	pendingMu.Lock()
+	defer pendingMu.Unlock()
+	if timer, ok := pendingDiagnostics[uri]; ok {
+		timer.Stop()
+	}
+	pendingDiagnostics[uri] = time.AfterFunc(diagnosticsDelay, func() {
+		pendingMu.Lock()
+		delete(pendingDiagnostics, uri)
+		pendingMu.Unlock()
+		publishDiagnostics(uri, text, opts)
+	})
+}
+
+// clearDiagnostics cancels any pending check of uri and removes what the
+// editor shows for it, as it has been closed
+func clearDiagnostics(uri string) {
+	pendingMu.Lock()
+	if timer, ok := pendingDiagnostics[uri]; ok {
+		timer.Stop()
+		delete(pendingDiagnostics, uri)
+	}
+	pendingMu.Unlock()
+	sendDiagnostics(uri, []map[string]interface{}{})
+}
+
+// cancelDiagnostics cancels every pending check, as the editor they were
+// for has gone
+func cancelDiagnostics() {
+	pendingMu.Lock()
+	defer pendingMu.Unlock()
+	for uri, timer := range pendingDiagnostics {
+		timer.Stop()
+		delete(pendingDiagnostics, uri)
+	}
+}
+
+// diagnosticsMu guards diagnosticsArena, as checks scheduled for different
+// documents may run at once
+var diagnosticsMu sync.Mutex
+
+// diagnosticsArena is reused for every re-parse: only the errors are kept,
+// so the previous tree is never needed after diagnostics are published
+var diagnosticsArena = ast.NewArena()
+
+// publishDiagnostics checks text, the document uri, and sends what it finds
+// A program that includes others is checked afresh each time, as the files
+// it includes may have changed since.
+func publishDiagnostics(uri, text string, opts settings) {
+	key := cache.Key(text) + " " + opts.checking()
+	diagnostics, ok := diagnosticCache.Get(key)
+	if !ok {
+		var includes bool
+		diagnostics, includes = computeDiagnostics(uri, text, opts)
+		if !includes {
+			diagnosticCache.Put(key, diagnostics)
+		}
+	}
+
+	logger.Debug("published diagnostics", "uri", uri, "count", len(diagnostics), "cached", ok)
+	sendDiagnostics(uri, diagnostics)
+}
+
+func sendDiagnostics(uri string, diagnostics []map[string]interface{}) {
+	writeMessage(map[string]interface{}{
+		"jsonrpc": "2.0",
+		"method":  "textDocument/publishDiagnostics",
+		"params": map[string]interface{}{
+			"uri":         uri,
+			"diagnostics": diagnostics,
+		},
+	})
+}
+
+// computeDiagnostics checks text, the document uri, as opts say to, giving
+// each problem the span of the token it was found at and its code from the
+// diagnostic catalogue, and reports whether the program includes others
+// Only a program that parses is checked further, as the warnings about a
+// tree missing the statements that failed to parse would be misleading.
+// The files it includes are read as saved, since the check runs apart from
+// the requests that change the open documents.
+func computeDiagnostics(uri, text string, opts settings) ([]map[string]interface{}, bool) {
+	diagnosticsMu.Lock()
+	defer diagnosticsMu.Unlock()
+	diagnosticsArena.Reset()
+	p := parser.NewWithArena(lexer.New(text), diagnosticsArena)
+	prog := p.ParseProgram()
+
+	found := p.Diagnostics()
+	if len(found) == 0 {
+		found = append(checkProgram(uri, prog, text, include.ReadFile), check.Dialect(prog, opts.dialect)...)
+		found = append(found, check.Insert(prog, opts.insert)...)
+		found = append(found, check.Style(prog, text, opts.style)...)
+		sort.SliceStable(found, func(i, j int) bool {
+			return found[i].Line < found[j].Line || found[i].Line == found[j].Line && found[i].Column < found[j].Column
+		})
+	}
+	ignored := ignores(text)
+	widths := tokenWidths(text)
+	diagnostics := []map[string]interface{}{}
+	for _, d := range opts.judge(found) {
+		if codes := ignored[d.Line]; codes[""] || codes[d.Code] {
+			continue
+		}
+		diagnostics = append(diagnostics, lspDiagnostic(text, d, widths))
+	}
+	return diagnostics, hasIncludes(prog)
+}
+
+// checkProgram runs check.Program on prog, parsed from text, the document
+// uri, with the files it includes, which read is given the names of, in
+// place of each INCLUDE, so that the names they declare are known
+// Only the problems in the document are kept. If an included file cannot
+// be loaded, the document is checked alone, with the INCLUDE reported.
+func checkProgram(uri string, prog *ast.Program, text string, read func(name string) (string, error)) []diagnostic.Diagnostic {
+	if !hasIncludes(prog) {
+		return check.Program(prog, text)
+	}
+	loaded := include.Load(uriToPath(uri), text, read)
+	found := loaded.Diagnostics
+	if loaded.OK() {
+		found = check.Program(loaded.AST, loaded.Text())
+	}
+	var kept []diagnostic.Diagnostic
+	for _, d := range found {
+		if d, src := loaded.Attribute(d); src == loaded.Main() {
+			kept = append(kept, d)
+		}
+	}
+	if !loaded.OK() {
+		kept = append(kept, check.Program(prog, text)...)
+	}
+	return kept
+}
+
+// ignoreDirective begins a comment listing the codes of the problems not
+// to report on its line, or on the next if the comment is all the line
+// holds; with no codes, nothing there is reported
+// It lets a teacher show a snippet breaking a rule on purpose.
+const ignoreDirective = "cambridge-ignore"
+
+// ignores returns the codes each ignoreDirective in text suppresses, by
+// 1-based line, with "" standing for every code
+func ignores(text string) map[int]map[diagnostic.Code]bool {
+	found := map[int]map[diagnostic.Code]bool{}
+	for idx, line := range strings.Split(text, "\n") {
+		at, ok := commentStart(line)
+		if !ok {
+			continue
+		}
+		words := strings.FieldsFunc(line[at+2:], func(r rune) bool { return r == ' ' || r == '\t' || r == ',' })
+		if len(words) == 0 || words[0] != ignoreDirective {
+			continue
+		}
+		target := idx + 1
+		if strings.TrimSpace(line[:at]) == "" {
+			target++
+		}
+		if found[target] == nil {
+			found[target] = map[diagnostic.Code]bool{}
+		}
+		if len(words) == 1 {
+			found[target][""] = true
+		}
+		for _, code := range words[1:] {
+			found[target][diagnostic.Code(code)] = true
+		}
+	}
+	return found
+}
+
+// commentStart returns where the // comment in line begins, if it has one
+// outside a string
+func commentStart(line string) (int, bool) {
+	var quote rune
+	for i, ch := range line {
+		switch {
+		case quote != 0:
+			if ch == quote {
+				quote = 0
+			}
+		case ch == '"' || ch == '\'':
+			quote = ch
+		case strings.HasPrefix(line[i:], "//"):
+			return i, true
+		}
+	}
+	return 0, false
+}
+
+// lspDiagnostic converts d, found in text and which may have no location,
+// to an LSP diagnostic; widths gives the length of the token starting at
+// each place
+func lspDiagnostic(text string, d diagnostic.Diagnostic, widths map[symbols.Position]int) map[string]interface{} {
+	line, col := max(d.Line-1, 0), max(d.Column-1, 0)
+	width := max(widths[symbols.Position{Line: d.Line, Column: d.Column}], 1)
+	item := map[string]interface{}{
+		"range": map[string]interface{}{
+			"start": lspPosition(text, line, col),
+			"end":   lspPosition(text, line, col+width),
+		},
+		"severity": lspSeverity(d.Severity),
+		"source":   "cambridge",
+		"message":  d.Message,
+	}
+	if d.Code != "" {
+		item["code"] = string(d.Code)
+	}
+	if d.Code == diagnostic.UnusedVariable {
+		item["tags"] = []int{tagUnnecessary} // editors fade the name out
+	}
+	return item
+}
+
+func lspSeverity(s diagnostic.Severity) int {
+	switch s {
+	case diagnostic.Warning:
+		return SeverityWarning
+	case diagnostic.Note:
+		return SeverityInformation
+	}
+	return SeverityError
+}
+
+// tokenWidths maps the 1-based line and column of each token in text to
+// its length; line breaks and the end of input are given none, so that
+// problems found there are marked a single character wide
+func tokenWidths(text string) map[symbols.Position]int {
+	widths := map[symbols.Position]int{}
+	l := lexer.New(text)
+	for tok := l.NextToken(); tok.Type != token.EOF; tok = l.NextToken() {
+		if tok.Type != token.NEWLINE {
+			widths[symbols.Position{Line: tok.Line, Column: tok.Column}] = len(tok.Literal)
+		}
+	}
+	return widths
+}
@@ -0,0 +1,71 @@
+package main
+
+import (
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// foldingRanges returns the 0-based lines each block in text can be folded
+// between, keeping the line that ends it in view, and each run of comment
+// lines; an IF with an ELSE folds as its two branches
+func foldingRanges(text string) []map[string]interface{} {
+	ranges := []map[string]interface{}{}
+	fold := func(start, end int, kind string) {
+		if end > start {
+			item := map[string]interface{}{"startLine": start, "endLine": end}
+			if kind != "" {
+				item["kind"] = kind
+			}
+			ranges = append(ranges, item)
+		}
+	}
+
+	type open struct {
+		kind token.Type
+		line int // 0-based, where the current part of the block starts
+	}
+	var stack []open
+	prev := token.NEWLINE
+	l := lexer.New(text)
+	for tok := l.NextToken(); tok.Type != token.EOF; prev, tok = tok.Type, l.NextToken() {
+		// Block keywords start their line, with only an access modifier
+		// before them; FOR also appears within OPENFILE
+		if prev != token.NEWLINE && prev != token.PUBLIC && prev != token.PRIVATE {
+			if tok.Type == token.EQ && len(stack) > 0 && stack[len(stack)-1].kind == token.TYPE && stack[len(stack)-1].line == tok.Line-1 {
+				stack = stack[:len(stack)-1] // an enumeration, on one line
+			}
+			continue
+		}
+		line := tok.Line - 1
+		switch {
+		case blockKeywords[tok.Type] != "":
+			stack = append(stack, open{kind: tok.Type, line: line})
+		case tok.Type == token.ELSE && len(stack) > 0 && stack[len(stack)-1].kind == token.IF:
+			fold(stack[len(stack)-1].line, line-1, "")
+			stack[len(stack)-1].line = line
+		case closers[tok.Type]:
+			if len(stack) > 0 && blockKeywords[stack[len(stack)-1].kind] == tok.Type {
+				fold(stack[len(stack)-1].line, line-1, "")
+				stack = stack[:len(stack)-1]
+			}
+		}
+	}
+
+	start := -1
+	lines := strings.Split(text, "\n")
+	for idx := 0; idx <= len(lines); idx++ {
+		if idx < len(lines) && strings.HasPrefix(strings.TrimSpace(lines[idx]), "//") {
+			if start < 0 {
+				start = idx
+			}
+			continue
+		}
+		if start >= 0 {
+			fold(start, idx-1, "comment")
+			start = -1
+		}
+	}
+	return ranges
+}
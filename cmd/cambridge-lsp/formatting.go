@@ -0,0 +1,115 @@
+package main
+
+import (
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// formatting returns the edits laying text out as cambridge fmt does, with
+// the configured indentation: one replacing the whole document, or none if
+// it is already laid out or does not parse, so that formatting on save
+// never mangles a broken program
+func formatting(text string) []map[string]interface{} {
+	formatted, err := config.style.Source(text)
+	if err != nil || formatted == text {
+		return []map[string]interface{}{}
+	}
+	return []map[string]interface{}{{"range": documentRange(text), "newText": formatted}}
+}
+
+// documentRange is the LSP range covering all of text
+func documentRange(text string) map[string]interface{} {
+	lines := strings.Split(text, "\n")
+	return map[string]interface{}{
+		"start": lspPosition(text, 0, 0),
+		"end":   lspPosition(text, len(lines)-1, len(lines[len(lines)-1])),
+	}
+}
+
+// rangeFormatting returns the edit laying out the 0-based lines first to
+// last of text as they would be in the whole formatted document, leaving
+// every other line as it is
+func rangeFormatting(text string, first, last int) []map[string]interface{} {
+	lines := strings.Split(text, "\n")
+	first, last = max(first, 0), min(last, len(lines)-1)
+	if first > last {
+		return []map[string]interface{}{}
+	}
+	formatted, err := config.style.Range(text, first+1, last+1)
+	if err != nil || formatted == strings.Join(lines[first:last+1], "\n") {
+		return []map[string]interface{}{}
+	}
+	return []map[string]interface{}{{
+		"range": map[string]interface{}{
+			"start": lspPosition(text, first, 0),
+			"end":   lspPosition(text, last, len(lines[last])),
+		},
+		"newText": formatted,
+	}}
+}
+
+// onTypeFormatting returns the edits to make as ch is typed, leaving the
+// cursor on the 0-based line given: on Enter after a line opening a block
+// that has no body or end yet, the new line is indented into the block and
+// the block is closed below it, unless it is a REPEAT, whose UNTIL
+// condition only the author can give
+func onTypeFormatting(text string, line int, ch string) []map[string]interface{} {
+	lines := strings.Split(text, "\n")
+	if ch != "\n" || line < 1 || line >= len(lines) {
+		return []map[string]interface{}{}
+	}
+	opening := lines[line-1]
+	opener, ok := blockOpened(opening)
+	end := blockKeywords[opener]
+	if !ok || end == token.UNTIL {
+		return []map[string]interface{}{}
+	}
+
+	indent := indentation(opening)
+	for _, next := range lines[line+1:] {
+		content := strings.TrimSpace(next)
+		if content == "" {
+			continue
+		}
+		first := lexer.New(content).NextToken()
+		closed := first.Type == end || first.Literal == continuations[opener]
+		if len(indentation(next)) > len(indent) || len(indentation(next)) == len(indent) && closed {
+			return []map[string]interface{}{} // the block already has a body or an end
+		}
+		break
+	}
+
+	current := lines[line]
+	return []map[string]interface{}{
+		{
+			"range": map[string]interface{}{
+				"start": lspPosition(text, line, 0),
+				"end":   lspPosition(text, line, len(indentation(current))),
+			},
+			"newText": indent + config.style.Indent,
+		},
+		insertion(lspPosition(text, line, len(current)), "\n"+indent+closing(strings.TrimLeft(opening, " \t"), end)),
+	}
+}
+
+// blockOpened returns the keyword of the block line opens, if it opens one
+// whose end is written on a later line
+func blockOpened(line string) (token.Type, bool) {
+	l := lexer.New(line)
+	tok := l.NextToken()
+	if tok.Type == token.PUBLIC || tok.Type == token.PRIVATE {
+		tok = l.NextToken()
+	}
+	opener := tok.Type
+	if _, ok := blockKeywords[opener]; !ok {
+		return "", false
+	}
+	for ; tok.Type != token.EOF; tok = l.NextToken() {
+		if opener == token.TYPE && tok.Type == token.EQ {
+			return "", false // an enumeration, complete on one line
+		}
+	}
+	return opener, true
+}
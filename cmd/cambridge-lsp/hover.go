@@ -0,0 +1,134 @@
+package main
+
+import (
+	"fmt"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/optimize"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// hover returns what to show at a 0-based line and character in text, or
+// nil if there is nothing to say: for a name, how the program declares it
+// or else the builtin it names; for an operator, the value of the
+// expression if it only involves constants
+// Values are only worked out for programs that parse.
+func hover(text string, line, character int) interface{} {
+	tok, ok := tokenAt(text, line, character)
+	if !ok {
+		return nil
+	}
+
+	a := analyse(text)
+	var constants map[string]ast.Expression
+	if a.parsed() {
+		// Folding rewrites the tree, so it gets a copy of its own
+		constants = optimize.Constants(parser.New(lexer.New(text)).ParseProgram())
+	}
+
+	var doc string
+	if tok.Type == token.IDENT || tok.Type == token.NEW {
+		if sym := a.table.SymbolAt(symbols.Position{Line: tok.Line, Column: tok.Column}); sym != nil {
+			doc = symbolDoc(sym, a.table, constants)
+		} else if sig, ok := builtins.Signatures[tok.Literal]; ok {
+			doc = builtinDoc(sig)
+		}
+	} else if constants != nil {
+		doc = operatorDoc(a.program, tok, constants)
+	}
+	if doc == "" {
+		return nil
+	}
+	return map[string]interface{}{
+		"contents": markdown(doc),
+		"range":    tokenRange(text, tok),
+	}
+}
+
+// symbolDoc describes a name the program declares: its declaration, its
+// value if it is a CONSTANT that can be worked out, and where it is
+func symbolDoc(sym *symbols.Symbol, table *symbols.Table, constants map[string]ast.Expression) string {
+	doc := codeBlock(sym.Declaration())
+	if value, ok := constants[sym.Name]; ok && sym.Kind == symbols.Constant && table.Program.Lookup(sym.Name) == sym {
+		doc += "Value: `" + value.String() + "`\n\n"
+	}
+	return doc + fmt.Sprintf("%s declared on line %d", capitalize(string(sym.Kind)), sym.Pos.Line)
+}
+
+// operatorDoc gives the value of the expression whose operator is tok, or
+// "" if it is not made only of literals and constants
+func operatorDoc(prog *ast.Program, tok token.Token, constants map[string]ast.Expression) string {
+	var expr *ast.InfixExpression
+	ast.Inspect(prog, func(node ast.Node) bool {
+		if infix, ok := node.(*ast.InfixExpression); ok && infix.Token.Line == tok.Line && infix.Token.Column == tok.Column {
+			expr = infix
+		}
+		return expr == nil
+	})
+	if expr == nil {
+		return ""
+	}
+	written := expr.String()
+	value, ok := optimize.Fold(expr, constants)
+	if !ok {
+		return ""
+	}
+	return codeBlock(written) + "Value: `" + value.String() + "`"
+}
+
+func capitalize(s string) string {
+	if s == "" {
+		return s
+	}
+	return strings.ToUpper(s[:1]) + s[1:]
+}
+
+// builtinDoc describes a builtin as the syllabus insert does: its
+// signature, what it does, what each parameter means and an example
+func builtinDoc(sig builtins.Signature) string {
+	var b strings.Builder
+	b.WriteString(codeBlock(sig.String()))
+	b.WriteString(sig.Doc + "\n")
+	if len(sig.Params) > 0 {
+		b.WriteString("\n")
+		for _, p := range sig.Params {
+			b.WriteString("- `" + p.Name + "`: " + p.Doc + "\n")
+		}
+	}
+	b.WriteString("\nExample: `" + sig.Example + "`")
+	return b.String()
+}
+
+// codeBlock fences pseudocode for a markdown hover
+func codeBlock(code string) string {
+	return "```cambridge-pseudo\n" + code + "\n```\n"
+}
+
+func markdown(value string) map[string]interface{} {
+	return map[string]interface{}{"kind": "markdown", "value": value}
+}
+
+// tokenAt returns the token covering a 0-based line and character
+func tokenAt(text string, line, character int) (token.Token, bool) {
+	l := lexer.New(text)
+	for tok := l.NextToken(); tok.Type != token.EOF; tok = l.NextToken() {
+		if tok.Line-1 > line {
+			break
+		}
+		if tok.Line-1 == line && tok.Column-1 <= character && character < tok.Column-1+len(tok.Literal) {
+			return tok, true
+		}
+	}
+	return token.Token{}, false
+}
+
+// tokenRange is the LSP range a token of text spans, which never crosses a
+// line
+func tokenRange(text string, tok token.Token) map[string]interface{} {
+	return nameRange(text, symbols.Position{Line: tok.Line, Column: tok.Column}, tok.Literal)
+}
@@ -0,0 +1,228 @@
+package main
+
+import (
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/check"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// LSP inlay hint kinds
+const (
+	InlayHintType      = 1
+	InlayHintParameter = 2
+)
+
+// bracketed is a pair of brackets in the source and, for parentheses, where
+// each argument between them starts
+type bracketed struct {
+	end  symbols.Position
+	args []symbols.Position
+}
+
+// inlayHints returns the hints to show on the 0-based lines first to last
+// of text, as opts allows: the type of each constant and of each loop
+// variable not declared elsewhere after its name, and of each function
+// result assigned after the call giving it; and the parameter each
+// argument of a call is passed as before it
+func inlayHints(text string, first, last int, opts settings) []map[string]interface{} {
+	hints := []map[string]interface{}{}
+	if !opts.typeHints && !opts.parameterHints {
+		return hints
+	}
+	a := analyse(text)
+	prog, table := a.program, a.table
+	brackets := bracketPairs(text)
+
+	hint := func(pos symbols.Position, label string, kind int) {
+		if pos.Line-1 < first || pos.Line-1 > last {
+			return
+		}
+		item := map[string]interface{}{
+			"position": lspPosition(text, pos.Line-1, pos.Column-1),
+			"label":    label,
+			"kind":     kind,
+		}
+		if kind == InlayHintType {
+			item["paddingLeft"] = true
+		} else {
+			item["paddingRight"] = true
+		}
+		hints = append(hints, item)
+	}
+	typeAfter := func(id *ast.Identifier, typ string) {
+		if opts.typeHints && typ != "" {
+			hint(symbols.Position{Line: id.Token.Line, Column: id.Token.Column + len(id.Value)}, ": "+typ, InlayHintType)
+		}
+	}
+	arguments := func(tok token.Token, params []string) {
+		open, ok := openingParen(brackets, tok)
+		if !opts.parameterHints || len(params) == 0 || !ok {
+			return
+		}
+		for idx, start := range brackets[open].args {
+			if idx < len(params) && params[idx] != "" {
+				hint(start, params[idx], InlayHintParameter)
+			}
+		}
+	}
+
+	ast.Inspect(prog, func(node ast.Node) bool {
+		switch n := node.(type) {
+		case *ast.ConstantStatement:
+			if n.Name != nil && !ast.IsNil(n.Value) {
+				typeAfter(n.Name, check.TypeOf(table.At(symbols.Position{Line: n.Name.Token.Line, Column: n.Name.Token.Column}), n.Value))
+			}
+		case *ast.ForStatement:
+			if n.Variable != nil {
+				loop := table.At(symbols.Position{Line: n.Variable.Token.Line, Column: n.Variable.Token.Column})
+				if loop.Parent == nil || loop.Parent.Lookup(n.Variable.Value) == nil {
+					typeAfter(n.Variable, "INTEGER")
+				}
+			}
+		case *ast.AssignmentStatement:
+			call, ok := n.Value.(*ast.CallExpression)
+			if !ok || !opts.typeHints {
+				break
+			}
+			open := symbols.Position{Line: call.Token.Line, Column: call.Token.Column}
+			scope := table.At(open)
+			typ := check.TypeOf(scope, call)
+			if typ == "" {
+				if t := scope.TypeOf(call); !ast.IsNil(t) {
+					typ = t.String()
+				}
+			}
+			if pair, ok := brackets[open]; ok && typ != "" {
+				hint(symbols.Position{Line: pair.end.Line, Column: pair.end.Column + 1}, ": "+typ, InlayHintType)
+			}
+		case *ast.CallStatement:
+			arguments(n.Token, parameterNames(table, n.Token, n.Name, n.Arguments))
+		case *ast.CallExpression:
+			arguments(n.Token, parameterNames(table, n.Token, n.Function, n.Arguments))
+		case *ast.NewExpression:
+			scope := table.At(symbols.Position{Line: n.Token.Line, Column: n.Token.Column})
+			arguments(n.Token, routineParameters(scope.Member(n.ClassName, "NEW"), n.Arguments))
+		}
+		return true
+	})
+	return hints
+}
+
+// parameterNames returns the label for each argument of a call to callee at
+// tok, naming its parameter and whether it is passed BYREF
+func parameterNames(table *symbols.Table, tok token.Token, callee ast.Expression, args []ast.Expression) []string {
+	scope := table.At(symbols.Position{Line: tok.Line, Column: tok.Column})
+	switch c := callee.(type) {
+	case *ast.Identifier:
+		if sym := scope.Lookup(c.Value); sym != nil {
+			return routineParameters(sym, args)
+		}
+		var names []string
+		for idx, p := range builtins.Signatures[c.Value].Params {
+			names = append(names, hintName(p.Name, "", args, idx))
+		}
+		return names
+	case *ast.MemberAccess:
+		if custom, ok := scope.TypeOf(c.Object).(*ast.CustomType); ok {
+			return routineParameters(scope.Member(custom.Name, c.Member), args)
+		}
+	}
+	return nil
+}
+
+// routineParameters returns the label for each argument of a call to sym,
+// or nil if sym is not a routine
+func routineParameters(sym *symbols.Symbol, args []ast.Expression) []string {
+	if sym == nil || sym.Kind != symbols.Procedure && sym.Kind != symbols.Function {
+		return nil
+	}
+	var names []string
+	for idx, p := range sym.Params {
+		byRef := ""
+		if strings.HasPrefix(p.Detail, "BYREF ") {
+			byRef = "BYREF "
+		}
+		names = append(names, hintName(p.Name, byRef, args, idx))
+	}
+	return names
+}
+
+// hintName is the label for the argument at idx passed as the parameter
+// name; a variable of that name needs only any BYREF, so may have none
+func hintName(name, byRef string, args []ast.Expression, idx int) string {
+	if idx < len(args) {
+		if id, ok := args[idx].(*ast.Identifier); ok && strings.EqualFold(id.Value, name) {
+			return strings.TrimSpace(byRef)
+		}
+	}
+	return byRef + name + ":"
+}
+
+// openingParen returns the place of the first ( at or after tok on its
+// line, where the arguments of the call tok is part of are written
+func openingParen(brackets map[symbols.Position]bracketed, tok token.Token) (symbols.Position, bool) {
+	var found symbols.Position
+	ok := false
+	for pos := range brackets {
+		if pos.Line == tok.Line && pos.Column >= tok.Column && (!ok || pos.Column < found.Column) {
+			found, ok = pos, true
+		}
+	}
+	return found, ok
+}
+
+// bracketPairs maps the place of each ( in text to the ) closing it and
+// the starts of the arguments between them
+// Arguments are placed by the text after the ( or comma before them, as
+// the lexer places string literals after their closing quote.
+func bracketPairs(text string) map[symbols.Position]bracketed {
+	lines := strings.Split(text, "\n")
+	argument := func(tok token.Token) (symbols.Position, bool) {
+		rest := lines[tok.Line-1][tok.Column:]
+		trimmed := strings.TrimLeft(rest, " \t")
+		if trimmed == "" || trimmed[0] == ')' {
+			return symbols.Position{}, false
+		}
+		return symbols.Position{Line: tok.Line, Column: tok.Column + 1 + len(rest) - len(trimmed)}, true
+	}
+
+	pairs := map[symbols.Position]bracketed{}
+	type open struct {
+		pos   symbols.Position
+		paren bool
+		args  []symbols.Position
+	}
+	var stack []open
+	l := lexer.New(text)
+	for tok := l.NextToken(); tok.Type != token.EOF; tok = l.NextToken() {
+		switch tok.Type {
+		case token.LPAREN, token.LBRACKET:
+			o := open{pos: symbols.Position{Line: tok.Line, Column: tok.Column}, paren: tok.Type == token.LPAREN}
+			if start, ok := argument(tok); ok {
+				o.args = append(o.args, start)
+			}
+			stack = append(stack, o)
+		case token.COMMA:
+			if len(stack) > 0 {
+				if start, ok := argument(tok); ok {
+					stack[len(stack)-1].args = append(stack[len(stack)-1].args, start)
+				}
+			}
+		case token.RPAREN, token.RBRACKET:
+			if len(stack) == 0 {
+				break
+			}
+			o := stack[len(stack)-1]
+			stack = stack[:len(stack)-1]
+			if o.paren {
+				pairs[o.pos] = bracketed{end: symbols.Position{Line: tok.Line, Column: tok.Column}, args: o.args}
+			}
+		}
+	}
+	return pairs
+}
@@ -1,79 +1,229 @@
 package main
 
 import (
-	"bufio"
 	"encoding/json"
+	"errors"
+	"flag"
 	"fmt"
 	"io"
-	"net/textproto"
+	"net"
 	"os"
-	"sort"
 	"strconv"
 	"strings"
+	"sync"
 
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/cache"
+	"github.com/andrinoff/cambridge-lang/pkg/dap"
 	"github.com/andrinoff/cambridge-lang/pkg/lexer"
-	"github.com/andrinoff/cambridge-lang/pkg/parser"
+	"github.com/andrinoff/cambridge-lang/pkg/logging"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
 	"github.com/andrinoff/cambridge-lang/pkg/token"
 )
 
 // LSP Types
 const (
-	TokenKeyword  = 0
-	TokenString   = 1
-	TokenNumber   = 2
-	TokenOperator = 3
-	TokenVariable = 4
-	TokenComment  = 5
+	TokenKeyword   = 0
+	TokenString    = 1
+	TokenNumber    = 2
+	TokenOperator  = 3
+	TokenVariable  = 4
+	TokenComment   = 5
+	TokenFunction  = 6
+	TokenParameter = 7
+	TokenType      = 8
+	TokenClass     = 9
+	TokenProperty  = 10
+	TokenEnumValue = 11
 )
 
 var tokenTypes = []string{
 	"keyword", "string", "number", "operator", "variable", "comment",
+	"function", "parameter", "type", "class", "property", "enumMember",
 }
 
+// LSP token modifiers, as bits
+const (
+	ModifierDeclaration    = 1 << 0
+	ModifierReadonly       = 1 << 1
+	ModifierDefaultLibrary = 1 << 2
+)
+
+var tokenModifiers = []string{"declaration", "readonly", "defaultLibrary"}
+
+// symbolTokens maps each kind of symbol to the token type it is
+// highlighted as
+var symbolTokens = map[symbols.Kind]int{
+	symbols.Variable:  TokenVariable,
+	symbols.Constant:  TokenVariable,
+	symbols.Parameter: TokenParameter,
+	symbols.Procedure: TokenFunction,
+	symbols.Function:  TokenFunction,
+	symbols.Type:      TokenType,
+	symbols.Class:     TokenClass,
+	symbols.Field:     TokenProperty,
+	symbols.EnumValue: TokenEnumValue,
+}
+
+var logger = logging.For("lsp")
+
+// VERSION is the release of the language server, the same as the CLI's
+const VERSION = "0.2.0"
+
+const usage = `cambridge-lsp - language server for Cambridge pseudocode
+
+Usage:
+  cambridge-lsp [options]
+
+By default the server talks to one editor over stdin and stdout. With
+--tcp or --websocket it listens on the port given instead, serving the
+editors that connect one at a time; a WebSocket server also answers
+GET /health, and refuses pages on other sites unless --allow-origin
+names them. With --dap it is a debug adapter rather than a language
+server, over stdio or --tcp.
+
+Options:
+`
+
 func main() {
-	reader := bufio.NewReader(os.Stdin)
+	// stdout carries the protocol, so logs go to stderr (the editor's
+	// language server log) or a file
+	verbose := flag.Bool("verbose", false, "log requests and analysis at debug level")
+	logFile := flag.String("log-file", "", "write the log to this file instead of stderr")
+	tcpPort := flag.Int("tcp", 0, "listen for editors on this TCP `port`")
+	wsPort := flag.Int("websocket", 0, "listen for web editors on this WebSocket `port`")
+	host := flag.String("host", "127.0.0.1", "the address --tcp and --websocket listen on")
+	allowOrigin := flag.String("allow-origin", "", "let web editors served from these comma-separated `origins`, such as https://editor.example, connect over --websocket")
+	debugAdapter := flag.Bool("dap", false, "speak the Debug Adapter Protocol, running programs under the editor's debugger")
+	version := flag.Bool("version", false, "print the version and exit")
+	flag.Usage = func() {
+		fmt.Fprint(flag.CommandLine.Output(), usage)
+		flag.PrintDefaults()
+	}
+	flag.Parse()
+
+	if *version {
+		fmt.Printf("cambridge-lsp v%s\n", VERSION)
+		return
+	}
+	if flag.NArg() > 0 || *tcpPort != 0 && *wsPort != 0 || *debugAdapter && *wsPort != 0 {
+		flag.Usage()
+		os.Exit(2)
+	}
+
+	closeLog, err := logging.Setup(logging.Options{Verbose: *verbose, File: *logFile})
+	if err != nil {
+		fmt.Fprintf(os.Stderr, "cannot set up logging: %v\n", err)
+		os.Exit(1)
+	}
+	defer closeLog()
+
+	switch {
+	case *debugAdapter && *tcpPort != 0:
+		err = listenTCP(net.JoinHostPort(*host, strconv.Itoa(*tcpPort)), debug)
+	case *debugAdapter:
+		err = dap.Serve(os.Stdin, os.Stdout)
+	case *tcpPort != 0:
+		err = listenTCP(net.JoinHostPort(*host, strconv.Itoa(*tcpPort)), func(conn net.Conn) {
+			serve(newStream(conn, conn))
+		})
+	case *wsPort != 0:
+		err = listenWebSocket(net.JoinHostPort(*host, strconv.Itoa(*wsPort)), *allowOrigin)
+	default:
+		serve(newStream(os.Stdin, os.Stdout))
+	}
+	if err != nil {
+		logger.Error("cannot serve", "error", err)
+		fmt.Fprintf(os.Stderr, "cambridge-lsp: %v\n", err)
+		os.Exit(1)
+	}
+}
+
+// serve answers the requests of one editor until it disconnects, starting
+// from the default settings with no documents open
+func serve(conn connection) {
+	outputMu.Lock()
+	client = conn
+	outputMu.Unlock()
+	config = defaultSettings
 	documents := make(map[string]string) // Cache document content
+	defer cancelDiagnostics()
 
 	for {
-		// Read Header
-		header, err := textproto.NewReader(reader).ReadMIMEHeader()
+		body, err := conn.read()
+		if errors.Is(err, errMalformed) {
+			logger.Warn("cannot read message", "error", err)
+			continue
+		}
 		if err != nil {
 			if err == io.EOF {
-				return
+				logger.Info("client closed the connection")
+			} else {
+				logger.Warn("connection failed", "error", err)
 			}
-			continue
-		}
-		length, _ := strconv.Atoi(header.Get("Content-Length"))
-
-		// Read Body
-		body := make([]byte, length)
-		if _, err := io.ReadFull(reader, body); err != nil {
-			continue
+			return
 		}
 
 		// Handle Request
 		var request map[string]interface{}
-		json.Unmarshal(body, &request)
+		if err := json.Unmarshal(body, &request); err != nil {
+			logger.Warn("invalid JSON message", "error", err)
+		}
 
 		method, _ := request["method"].(string)
+		logger.Debug("message", "method", method, "id", request["id"])
 
 		// --- INITIALIZE ---
 		if method == "initialize" {
+			params, _ := request["params"].(map[string]interface{})
+			setWorkspaceRoots(params)
+			config.project(workspaceRoots)
+			config.update(params["initializationOptions"])
 			sendResponse(request["id"], map[string]interface{}{
 				"capabilities": map[string]interface{}{
-					"textDocumentSync": 1, // Full sync
+					"positionEncoding": positionEncoding(params),
+					"textDocumentSync": 2, // Incremental sync
 					"completionProvider": map[string]interface{}{
 						"triggerCharacters": []string{"."},
 					},
+					"signatureHelpProvider": map[string]interface{}{
+						"triggerCharacters": []string{"(", ","},
+					},
+					"hoverProvider":                   true,
+					"definitionProvider":              true,
+					"referencesProvider":              true,
+					"documentHighlightProvider":       true,
+					"callHierarchyProvider":           true,
+					"documentSymbolProvider":          true,
+					"workspaceSymbolProvider":         true,
+					"documentFormattingProvider":      true,
+					"documentRangeFormattingProvider": true,
+					"documentOnTypeFormattingProvider": map[string]interface{}{
+						"firstTriggerCharacter": "\n",
+					},
+					"codeActionProvider": map[string]interface{}{
+						"codeActionKinds": []string{"quickfix"},
+					},
+					"renameProvider": map[string]interface{}{
+						"prepareProvider": true,
+					},
+					"inlayHintProvider":      true,
+					"foldingRangeProvider":   true,
+					"selectionRangeProvider": true,
+					"codeLensProvider":       map[string]interface{}{},
+					"executeCommandProvider": map[string]interface{}{
+						"commands": []string{commandRun, commandDebug},
+					},
 					"semanticTokensProvider": map[string]interface{}{
 						"legend": map[string]interface{}{
 							"tokenTypes":     tokenTypes,
-							"tokenModifiers": []string{},
+							"tokenModifiers": tokenModifiers,
 						},
 						"range": true,
 						"full":  true,
 					},
 				},
+				"serverInfo": map[string]interface{}{"name": "cambridge-lsp", "version": VERSION},
 			})
 		}
 
@@ -84,39 +234,233 @@ func main() {
 			uri := doc["uri"].(string)
 			text := doc["text"].(string)
 			documents[uri] = text
-			publishDiagnostics(uri, text)
+			publishDiagnostics(uri, text, config)
 		} else if method == "textDocument/didChange" {
 			params := request["params"].(map[string]interface{})
 			doc := params["textDocument"].(map[string]interface{})
 			uri := doc["uri"].(string)
-			changes := params["contentChanges"].([]interface{})
+			changes, _ := params["contentChanges"].([]interface{})
 			if len(changes) > 0 {
-				lastChange := changes[len(changes)-1].(map[string]interface{})
-				text := lastChange["text"].(string)
+				text := applyChanges(documents[uri], changes)
 				documents[uri] = text
-				publishDiagnostics(uri, text)
+				scheduleDiagnostics(uri, text)
+			}
+		} else if method == "textDocument/didClose" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			delete(documents, uri)
+			clearDiagnostics(uri)
+		}
+
+		// --- CONFIGURATION ---
+		if method == "workspace/didChangeConfiguration" {
+			params, _ := request["params"].(map[string]interface{})
+			changed, _ := params["settings"].(map[string]interface{})
+			config.update(changed["cambridge"])
+			for uri, text := range documents {
+				scheduleDiagnostics(uri, text)
 			}
 		}
 
 		// --- COMPLETION ---
 		if method == "textDocument/completion" {
-			items := []map[string]interface{}{}
-
-			// Add Keywords
-			for k := range token.Keywords {
-				items = append(items, map[string]interface{}{
-					"label":  k,
-					"kind":   14, // Keyword
-					"detail": "keyword",
-				})
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			line, character := position(documents[uri], params)
+			sendResponse(request["id"], completion(documents[uri], line, character))
+		}
+
+		// --- HOVER ---
+		if method == "textDocument/hover" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			line, character := position(documents[uri], params)
+			sendResponse(request["id"], hover(documents[uri], line, character))
+		}
+
+		// --- SIGNATURE HELP ---
+		if method == "textDocument/signatureHelp" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			line, character := position(documents[uri], params)
+			sendResponse(request["id"], signatureHelp(documents[uri], line, character))
+		}
+
+		// --- CODE ACTIONS ---
+		if method == "textDocument/codeAction" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			rng, _ := params["range"].(map[string]interface{})
+			start, _ := rng["start"].(map[string]interface{})
+			end, _ := rng["end"].(map[string]interface{})
+			first, _ := start["line"].(float64)
+			last, _ := end["line"].(float64)
+			sendResponse(request["id"], codeActions(uri, documents, int(first), int(last)))
+		}
+
+		// --- FORMATTING ---
+		if method == "textDocument/formatting" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			sendResponse(request["id"], formatting(documents[uri]))
+		}
+
+		if method == "textDocument/rangeFormatting" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			rng, _ := params["range"].(map[string]interface{})
+			start, _ := rng["start"].(map[string]interface{})
+			end, _ := rng["end"].(map[string]interface{})
+			first, _ := start["line"].(float64)
+			last, _ := end["line"].(float64)
+			if character, _ := end["character"].(float64); character == 0 && last > first {
+				last-- // a selection of whole lines ends at the start of the next
 			}
+			sendResponse(request["id"], rangeFormatting(documents[uri], int(first), int(last)))
+		}
 
-			// Sort for consistency
-			sort.Slice(items, func(i, j int) bool {
-				return items[i]["label"].(string) < items[j]["label"].(string)
-			})
+		if method == "textDocument/onTypeFormatting" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			line, _ := position(documents[uri], params)
+			ch, _ := params["ch"].(string)
+			sendResponse(request["id"], onTypeFormatting(documents[uri], line, ch))
+		}
 
-			sendResponse(request["id"], items)
+		// --- RUNNING ---
+		if method == "textDocument/codeLens" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			sendResponse(request["id"], codeLenses(uri, documents[uri]))
+		}
+
+		if method == "workspace/executeCommand" {
+			params := request["params"].(map[string]interface{})
+			command, _ := params["command"].(string)
+			args, _ := params["arguments"].([]interface{})
+			var uri, name string
+			if len(args) > 0 {
+				uri, _ = args[0].(string)
+			}
+			if len(args) > 1 {
+				name, _ = args[1].(string)
+			}
+			text, open := documents[uri]
+			switch {
+			case command != commandRun && command != commandDebug:
+				sendError(request["id"], requestFailed, "unknown command "+command)
+			case !open:
+				sendError(request["id"], requestFailed, "the document is not open")
+			default:
+				if err := execute(command, uri, text, name); err != nil {
+					sendError(request["id"], requestFailed, err.Error())
+				} else {
+					sendResponse(request["id"], nil)
+				}
+			}
+		}
+
+		// --- FOLDING ---
+		if method == "textDocument/foldingRange" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			sendResponse(request["id"], foldingRanges(documents[uri]))
+		}
+
+		// --- SELECTION RANGES ---
+		if method == "textDocument/selectionRange" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			positions, _ := params["positions"].([]interface{})
+			sendResponse(request["id"], selectionRanges(documents[uri], positions))
+		}
+
+		// --- INLAY HINTS ---
+		if method == "textDocument/inlayHint" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			rng, _ := params["range"].(map[string]interface{})
+			start, _ := rng["start"].(map[string]interface{})
+			end, _ := rng["end"].(map[string]interface{})
+			first, _ := start["line"].(float64)
+			last, _ := end["line"].(float64)
+			sendResponse(request["id"], inlayHints(documents[uri], int(first), int(last), config))
+		}
+
+		// --- GO TO DEFINITION ---
+		if method == "textDocument/definition" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			line, character := position(documents[uri], params)
+			sendResponse(request["id"], definition(uri, documents, line, character))
+		}
+
+		// --- FIND REFERENCES ---
+		if method == "textDocument/references" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			line, character := position(documents[uri], params)
+			context, _ := params["context"].(map[string]interface{})
+			include, _ := context["includeDeclaration"].(bool)
+			sendResponse(request["id"], references(uri, documents, line, character, include))
+		}
+
+		if method == "textDocument/documentHighlight" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			line, character := position(documents[uri], params)
+			sendResponse(request["id"], highlights(documents[uri], line, character))
+		}
+
+		// --- CALL HIERARCHY ---
+		if method == "textDocument/prepareCallHierarchy" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			line, character := position(documents[uri], params)
+			sendResponse(request["id"], prepareCallHierarchy(uri, documents[uri], line, character))
+		}
+
+		if method == "callHierarchy/incomingCalls" || method == "callHierarchy/outgoingCalls" {
+			params := request["params"].(map[string]interface{})
+			item, _ := params["item"].(map[string]interface{})
+			uri, _ := item["uri"].(string)
+			if method == "callHierarchy/incomingCalls" {
+				sendResponse(request["id"], incomingCalls(uri, documents[uri], item))
+			} else {
+				sendResponse(request["id"], outgoingCalls(uri, documents[uri], item))
+			}
+		}
+
+		// --- RENAME ---
+		if method == "textDocument/prepareRename" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			line, character := position(documents[uri], params)
+			sendResponse(request["id"], prepareRename(uri, documents, line, character))
+		} else if method == "textDocument/rename" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			line, character := position(documents[uri], params)
+			newName, _ := params["newName"].(string)
+			if edit, err := rename(uri, documents, line, character, newName); err != nil {
+				sendError(request["id"], requestFailed, err.Error())
+			} else {
+				sendResponse(request["id"], edit)
+			}
+		}
+
+		// --- OUTLINE ---
+		if method == "textDocument/documentSymbol" {
+			params := request["params"].(map[string]interface{})
+			uri := params["textDocument"].(map[string]interface{})["uri"].(string)
+			sendResponse(request["id"], documentSymbols(documents[uri]))
+		}
+
+		// --- WORKSPACE SYMBOLS ---
+		if method == "workspace/symbol" {
+			params := request["params"].(map[string]interface{})
+			query, _ := params["query"].(string)
+			sendResponse(request["id"], workspaceSymbols(query, documents))
 		}
 
 		// --- SEMANTIC TOKENS (HIGHLIGHTING) ---
@@ -137,8 +481,41 @@ func main() {
 	}
 }
 
+// Editors ask for diagnostics and highlighting again for text they have
+// already sent, such as after an undo or when a file is reopened, so the
+// results are kept for the most recent versions of each document
+const cachedAnalyses = 64
+
+var (
+	tokenCache      = cache.NewMemory[[]int](cachedAnalyses)
+	diagnosticCache = cache.NewMemory[[]map[string]interface{}](cachedAnalyses)
+)
+
 func computeSemanticTokens(text string) []int {
+	key := cache.Key(text)
+	if data, ok := tokenCache.Get(key); ok {
+		return data
+	}
+	data := semanticTokens(text)
+	tokenCache.Put(key, data)
+	return data
+}
+
+// semanticTokens highlights text by what each token is; names by what they
+// resolve to, so that a misspelt keyword or an undeclared variable, which
+// resolves to nothing, is left plain and stands out
+func semanticTokens(text string) []int {
+	table := analyse(text).table
+	names := map[symbols.Position]*symbols.Symbol{}
+	for _, sym := range table.Symbols() {
+		names[sym.Pos] = sym
+	}
+	for _, ref := range table.References {
+		names[ref.Pos] = ref.Symbol
+	}
+
 	l := lexer.New(text)
+	lines := strings.Split(text, "\n")
 	var data []int
 
 	lastLine := 0
@@ -146,6 +523,7 @@ func computeSemanticTokens(text string) []int {
 
 	for tok := l.NextToken(); tok.Type != token.EOF; tok = l.NextToken() {
 		tokenType := -1
+		modifiers := 0
 
 		// Map Token Type to LSP Token Type
 		if _, isKeyword := token.Keywords[tok.Literal]; isKeyword {
@@ -157,7 +535,18 @@ func computeSemanticTokens(text string) []int {
 			case token.STRING_LIT, token.CHAR_LIT:
 				tokenType = TokenString
 			case token.IDENT:
-				tokenType = TokenVariable
+				pos := symbols.Position{Line: tok.Line, Column: tok.Column}
+				if sym, ok := names[pos]; ok {
+					tokenType = symbolTokens[sym.Kind]
+					if pos == sym.Pos {
+						modifiers |= ModifierDeclaration
+					}
+					if sym.Kind == symbols.Constant || sym.Kind == symbols.EnumValue {
+						modifiers |= ModifierReadonly
+					}
+				} else if _, ok := builtins.Signatures[tok.Literal]; ok {
+					tokenType, modifiers = TokenFunction, ModifierDefaultLibrary
+				}
 			case token.ASSIGN, token.PLUS, token.MINUS, token.ASTERISK, token.SLASH,
 				token.EQ, token.NOT_EQ, token.LT, token.GT, token.LT_EQ, token.GT_EQ:
 				tokenType = TokenOperator
@@ -171,7 +560,10 @@ func computeSemanticTokens(text string) []int {
 		// Calculate LSP Delta Encoding
 		// LSP uses 0-based lines and columns. Lexer provides 1-based.
 		line := tok.Line - 1
-		col := tok.Column - 1
+		if line >= len(lines) {
+			continue
+		}
+		col := lspCharacter(lines[line], tok.Column-1)
 
 		deltaLine := line - lastLine
 		deltaStart := col
@@ -179,9 +571,9 @@ func computeSemanticTokens(text string) []int {
 			deltaStart = col - lastStart
 		}
 
-		length := len(tok.Literal)
+		length := lspCharacter(lines[line], tok.Column-1+len(tok.Literal)) - col
 
-		data = append(data, deltaLine, deltaStart, length, tokenType, 0)
+		data = append(data, deltaLine, deltaStart, length, tokenType, modifiers)
 
 		lastLine = line
 		lastStart = col
@@ -190,53 +582,43 @@ func computeSemanticTokens(text string) []int {
 	return data
 }
 
-func publishDiagnostics(uri, text string) {
-	l := lexer.New(text)
-	p := parser.New(l)
-	p.ParseProgram()
-	diagnostics := []map[string]interface{}{}
-
-	for _, errStr := range p.Errors() {
-		parts := strings.SplitN(errStr, ": ", 2)
-		if len(parts) < 2 {
-			continue
-		}
-
-		locParts := strings.Split(parts[0], ",")
-		lineStr := strings.TrimPrefix(strings.TrimSpace(locParts[0]), "line ")
-		colStr := strings.TrimPrefix(strings.TrimSpace(locParts[1]), "column ")
+func sendResponse(id interface{}, result interface{}) {
+	resp := map[string]interface{}{
+		"jsonrpc": "2.0",
+		"id":      id,
+		"result":  result,
+	}
+	writeMessage(resp)
+}
 
-		line, _ := strconv.Atoi(lineStr)
-		col, _ := strconv.Atoi(colStr)
+// outputMu keeps messages sent from other goroutines, such as diagnostics
+// published after a pause in typing, from interleaving
+var outputMu sync.Mutex
 
-		diagnostics = append(diagnostics, map[string]interface{}{
-			"range": map[string]interface{}{
-				"start": map[string]int{"line": line - 1, "character": col - 1},
-				"end":   map[string]int{"line": line - 1, "character": col + 10},
-			},
-			"severity": 1, // Error
-			"message":  parts[1],
-		})
-	}
+// client is the editor being served
+var client connection
 
-	notification := map[string]interface{}{
-		"jsonrpc": "2.0",
-		"method":  "textDocument/publishDiagnostics",
-		"params": map[string]interface{}{
-			"uri":         uri,
-			"diagnostics": diagnostics,
-		},
+func writeMessage(v interface{}) {
+	msg, _ := json.Marshal(v)
+	outputMu.Lock()
+	defer outputMu.Unlock()
+	if err := client.write(msg); err != nil {
+		logger.Warn("cannot send message", "error", err)
 	}
-	msg, _ := json.Marshal(notification)
-	fmt.Printf("Content-Length: %d\r\n\r\n%s", len(msg), msg)
 }
 
-func sendResponse(id interface{}, result interface{}) {
+// requestFailed is the LSP error code for a valid request that could not
+// be carried out, which editors show to the user
+const requestFailed = -32803
+
+func sendError(id interface{}, code int, message string) {
 	resp := map[string]interface{}{
 		"jsonrpc": "2.0",
 		"id":      id,
-		"result":  result,
+		"error": map[string]interface{}{
+			"code":    code,
+			"message": message,
+		},
 	}
-	msg, _ := json.Marshal(resp)
-	fmt.Printf("Content-Length: %d\r\n\r\n%s", len(msg), msg)
+	writeMessage(resp)
 }
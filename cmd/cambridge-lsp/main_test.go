@@ -0,0 +1,170 @@
+package main
+
+import (
+	"reflect"
+	"strings"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+func TestPositions(t *testing.T) {
+	text := "OUTPUT \"é😀\", X\nY"
+	tests := []struct {
+		name      string
+		utf8      bool
+		line, col int // the byte column
+		character int
+	}{
+		{"before any wide character", false, 0, 7, 7},
+		{"after a two-byte character", false, 0, 10, 9},
+		{"after a surrogate pair", false, 0, 14, 11},
+		{"the name after them", false, 0, 17, 14},
+		{"counted in bytes", true, 0, 17, 17},
+		{"a later line", false, 1, 1, 1},
+	}
+
+	defer func(was bool) { utf8Positions = was }(utf8Positions)
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			utf8Positions = tt.utf8
+			if got := lspPosition(text, tt.line, tt.col)["character"]; got != tt.character {
+				t.Errorf("expected character %d, got %d", tt.character, got)
+			}
+			if got := byteColumn(lineAt(text, tt.line), tt.character); got != tt.col {
+				t.Errorf("expected column %d, got %d", tt.col, got)
+			}
+			params := map[string]interface{}{"position": map[string]interface{}{"line": float64(tt.line), "character": float64(tt.character)}}
+			if line, col := position(text, params); line != tt.line || col != tt.col {
+				t.Errorf("expected %d:%d, got %d:%d", tt.line, tt.col, line, col)
+			}
+		})
+	}
+}
+
+func TestRenameRefuses(t *testing.T) {
+	uri := "file:///project/main.cam"
+	documents := map[string]string{uri: "DECLARE Count : INTEGER\nCount <- 1\n"}
+	tests := []struct {
+		newName string
+		reason  string // in the error, or "" if it is allowed
+	}{
+		{"Total", ""},
+		{"WHILE", "keyword"},
+		{"if", "keyword"},
+		{"Endwhile", "keyword"},
+		{"LENGTH", "builtin"},
+		{"2nd", "not a valid name"},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.newName, func(t *testing.T) {
+			_, err := rename(uri, documents, 0, 8, tt.newName)
+			switch {
+			case tt.reason == "" && err != nil:
+				t.Errorf("unexpected error: %v", err)
+			case tt.reason != "" && (err == nil || !strings.Contains(err.Error(), tt.reason)):
+				t.Errorf("expected an error about the %s, got %v", tt.reason, err)
+			}
+		})
+	}
+}
+
+func TestCommentStart(t *testing.T) {
+	tests := []struct {
+		line string
+		at   int
+		ok   bool
+	}{
+		{"OUTPUT 1 // note", 9, true},
+		{"// the whole line", 0, true},
+		{"OUTPUT 'x' // a CHAR first", 11, true},
+		{"OUTPUT \"it's\" // an apostrophe in a STRING", 14, true},
+		{"OUTPUT \"a // b\"", 0, false},
+		{"OUTPUT 1 / 2", 0, false},
+	}
+
+	for _, tt := range tests {
+		if at, ok := commentStart(tt.line); at != tt.at || ok != tt.ok {
+			t.Errorf("%q: expected %d, %t, got %d, %t", tt.line, tt.at, tt.ok, at, ok)
+		}
+	}
+}
+
+func TestIgnores(t *testing.T) {
+	all := map[diagnostic.Code]bool{"": true}
+	tests := []struct {
+		name     string
+		text     string
+		expected map[int]map[diagnostic.Code]bool
+	}{
+		{"after a statement", "OUTPUT X // cambridge-ignore E0201", map[int]map[diagnostic.Code]bool{1: {"E0201": true}}},
+		{"several codes", "OUTPUT X // cambridge-ignore E0201, W0102", map[int]map[diagnostic.Code]bool{1: {"E0201": true, "W0102": true}}},
+		{"on a line of its own", "// cambridge-ignore\nOUTPUT X", map[int]map[diagnostic.Code]bool{2: all}},
+		{"indented on its own", "IF TRUE THEN\n    // cambridge-ignore\n    OUTPUT X\nENDIF", map[int]map[diagnostic.Code]bool{3: all}},
+		{"inside a string", "OUTPUT \"// cambridge-ignore\"", map[int]map[diagnostic.Code]bool{}},
+		{"not first in the comment", "OUTPUT X // see cambridge-ignore", map[int]map[diagnostic.Code]bool{}},
+	}
+
+	for _, tt := range tests {
+		if got := ignores(tt.text); !reflect.DeepEqual(got, tt.expected) {
+			t.Errorf("%s: expected %v, got %v", tt.name, tt.expected, got)
+		}
+	}
+}
+
+func TestBlockOpened(t *testing.T) {
+	tests := []struct {
+		line     string
+		expected token.Type
+		ok       bool
+	}{
+		{"IF X > 1 THEN", token.IF, true},
+		{"    WHILE TRUE", token.WHILE, true},
+		{"PRIVATE FUNCTION Area() RETURNS REAL", token.FUNCTION, true},
+		{"REPEAT", token.REPEAT, true},
+		{"TYPE Point", token.TYPE, true},
+		{"TYPE Season = (Spring, Summer)", "", false},
+		{"OUTPUT 1", "", false},
+	}
+
+	for _, tt := range tests {
+		if got, ok := blockOpened(tt.line); got != tt.expected || ok != tt.ok {
+			t.Errorf("%q: expected %s, %t, got %s, %t", tt.line, tt.expected, tt.ok, got, ok)
+		}
+	}
+}
+
+func TestOnTypeFormatting(t *testing.T) {
+	in := config.style.Indent
+	tests := []struct {
+		name     string
+		text     string
+		ch       string
+		expected []string // the new text of each edit
+	}{
+		{"closes an IF", "IF X > 1 THEN\n", "\n", []string{in, "\nENDIF"}},
+		{"names the loop variable", "FOR I <- 1 TO 3\n", "\n", []string{in, "\nNEXT I"}},
+		{"keeps the indentation", in + "PUBLIC PROCEDURE Go()\n", "\n", []string{in + in, "\n" + in + "ENDPROCEDURE"}},
+		{"before another statement", "WHILE X < 3\n\nOUTPUT X", "\n", []string{in, "\nENDWHILE"}},
+		{"a record type", "TYPE Point\n", "\n", []string{in, "\nENDTYPE"}},
+		{"leaves REPEAT open", "REPEAT\n", "\n", nil},
+		{"an enumeration", "TYPE Season = (Spring, Summer)\n", "\n", nil},
+		{"already has a body", "WHILE X < 3\n\n" + in + "X <- X + 1\nENDWHILE", "\n", nil},
+		{"already has an end", "WHILE X < 3\n\nENDWHILE", "\n", nil},
+		{"carried on by ELSE", "IF X THEN\n\nELSE", "\n", nil},
+		{"not a statement opening a block", "OUTPUT X\n", "\n", nil},
+		{"not Enter", "IF X THEN\n", ";", nil},
+	}
+
+	for _, tt := range tests {
+		var got []string
+		for _, edit := range onTypeFormatting(tt.text, 1, tt.ch) {
+			got = append(got, edit["newText"].(string))
+		}
+		if !reflect.DeepEqual(got, tt.expected) {
+			t.Errorf("%s: expected %q, got %q", tt.name, tt.expected, got)
+		}
+	}
+}
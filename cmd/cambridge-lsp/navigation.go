@@ -0,0 +1,255 @@
+package main
+
+import (
+	"fmt"
+	"path/filepath"
+	"regexp"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/include"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// symbolAt returns the symbol table of text and the symbol named at a
+// 0-based line and character, or a nil symbol if no name the program
+// declares is written there
+func symbolAt(text string, line, character int) (*symbols.Table, *symbols.Symbol) {
+	table := analyse(text).table
+	tok, ok := tokenAt(text, line, character)
+	if !ok || tok.Type != token.IDENT && tok.Type != token.NEW {
+		return table, nil
+	}
+	return table, table.SymbolAt(symbols.Position{Line: tok.Line, Column: tok.Column})
+}
+
+// view is a program the document a request is about is part of, with its
+// symbol table: the program the document is the main file of, with the
+// files it includes, or that of another file including it
+type view struct {
+	prog  *include.Program
+	file  *include.File // the document
+	table *symbols.Table
+	open  *openFiles
+}
+
+// views calls visit with each program the document uri is part of, its
+// own first, until visit returns false
+func views(uri string, documents map[string]string, visit func(v *view) bool) {
+	open := openDocuments(documents)
+	path := uriToPath(uri)
+	within := func(prog *include.Program) bool {
+		for _, f := range prog.Files {
+			if filepath.Clean(f.Name) == filepath.Clean(path) {
+				return visit(&view{prog: prog, file: f, table: symbols.Build(prog.AST, prog.Text()), open: open})
+			}
+		}
+		return true
+	}
+	if !within(include.Load(path, documents[uri], open.read)) {
+		return
+	}
+	includers(path, open, within)
+}
+
+// symbolAt returns the symbol named at a 0-based line and character of the
+// document, or nil
+func (v *view) symbolAt(line, character int) *symbols.Symbol {
+	tok, ok := tokenAt(v.file.Text, line, character)
+	if !ok || tok.Type != token.IDENT && tok.Type != token.NEW {
+		return nil
+	}
+	return v.table.SymbolAt(symbols.Position{Line: v.file.First + tok.Line - 1, Column: tok.Column})
+}
+
+// locate returns the file holding pos of the program and pos within it
+func (v *view) locate(pos symbols.Position) (*include.File, symbols.Position) {
+	file, line := v.prog.Locate(pos.Line)
+	return file, symbols.Position{Line: line, Column: pos.Column}
+}
+
+// location returns the LSP location of the name written at pos of the
+// program
+func (v *view) location(pos symbols.Position, name string) map[string]interface{} {
+	file, at := v.locate(pos)
+	return location(v.open.uriOf(file.Name), file.Text, at, name)
+}
+
+// places returns where sym is declared and used in the program
+func (v *view) places(sym *symbols.Symbol) []symbols.Position {
+	return append([]symbols.Position{sym.Pos}, positions(v.table.Uses(sym))...)
+}
+
+// definition returns the location where the name at a 0-based line and
+// character in the document uri is declared, or nil
+// The name may be declared in a file the document includes, or for a
+// library in a program including it.
+func definition(uri string, documents map[string]string, line, character int) interface{} {
+	var found interface{}
+	views(uri, documents, func(v *view) bool {
+		if sym := v.symbolAt(line, character); sym != nil {
+			found = v.location(sym.Pos, sym.Name)
+		}
+		return found == nil
+	})
+	return found
+}
+
+// references returns the locations where the name at a 0-based line and
+// character in the document uri is used, with where it is declared if
+// includeDeclaration is set
+// A routine in a library is found wherever it is used by the programs
+// including it.
+func references(uri string, documents map[string]string, line, character int, includeDeclaration bool) []map[string]interface{} {
+	locations := []map[string]interface{}{}
+	seen := map[string]bool{}
+	add := func(loc map[string]interface{}) {
+		if key := fmt.Sprint(loc); !seen[key] {
+			seen[key] = true
+			locations = append(locations, loc)
+		}
+	}
+	views(uri, documents, func(v *view) bool {
+		sym := v.symbolAt(line, character)
+		if sym == nil {
+			return true
+		}
+		if includeDeclaration {
+			add(v.location(sym.Pos, sym.Name))
+		}
+		for _, ref := range v.table.Uses(sym) {
+			add(v.location(ref.Pos, sym.Name))
+		}
+		return true
+	})
+	return locations
+}
+
+// LSP document highlight kinds
+const (
+	HighlightText  = 1
+	HighlightRead  = 2
+	HighlightWrite = 3
+)
+
+// highlights returns where the name at a 0-based line and character is
+// declared and used, telling the places it is given a value apart from
+// those it is read
+func highlights(text string, line, character int) []map[string]interface{} {
+	items := []map[string]interface{}{}
+	table, sym := symbolAt(text, line, character)
+	if sym == nil {
+		return items
+	}
+	items = append(items, map[string]interface{}{"range": nameRange(text, sym.Pos, sym.Name), "kind": HighlightText})
+	for _, ref := range table.Uses(sym) {
+		kind := HighlightRead
+		if ref.Write {
+			kind = HighlightWrite
+		}
+		items = append(items, map[string]interface{}{"range": nameRange(text, ref.Pos, sym.Name), "kind": kind})
+	}
+	return items
+}
+
+// identifierPattern is what a name can be spelled as
+var identifierPattern = regexp.MustCompile(`^[A-Za-z][A-Za-z0-9_]*$`)
+
+// prepareRename returns the range and current spelling of the name at a
+// 0-based line and character in the document uri, or nil if it cannot be
+// renamed
+func prepareRename(uri string, documents map[string]string, line, character int) interface{} {
+	var found interface{}
+	views(uri, documents, func(v *view) bool {
+		sym := v.symbolAt(line, character)
+		if sym == nil || sym.Name == "NEW" {
+			return true
+		}
+		for _, pos := range v.places(sym) {
+			file, at := v.locate(pos)
+			if file == v.file && at.Line-1 == line && at.Column-1 <= character && character < at.Column-1+len(sym.Name) {
+				found = map[string]interface{}{"range": nameRange(file.Text, at, sym.Name), "placeholder": sym.Name}
+			}
+		}
+		return found == nil
+	})
+	return found
+}
+
+// rename returns the edit calling the name at a 0-based line and character
+// in the document uri newName instead, where it is declared and everywhere
+// it is used, in every file of every program the document is part of, or
+// an error saying why it cannot
+func rename(uri string, documents map[string]string, line, character int, newName string) (interface{}, error) {
+	var found []*view
+	var syms []*symbols.Symbol // what the name is in each of found
+	views(uri, documents, func(v *view) bool {
+		if s := v.symbolAt(line, character); s != nil {
+			found, syms = append(found, v), append(syms, s)
+		}
+		return true
+	})
+	var sym *symbols.Symbol
+	if len(syms) > 0 {
+		sym = syms[0]
+	}
+	switch {
+	case sym == nil || sym.Name == "NEW":
+		return nil, fmt.Errorf("there is no name here that can be renamed")
+	case !identifierPattern.MatchString(newName):
+		return nil, fmt.Errorf("%q is not a valid name: use a letter followed by letters, digits or underscores", newName)
+	case token.LookupIdent(strings.ToUpper(newName)) != token.IDENT: // the lexer reads keywords in any case
+		return nil, fmt.Errorf("%s is a keyword", newName)
+	case builtins.GetBuiltins()[newName] != nil:
+		return nil, fmt.Errorf("%s is the name of a builtin function", newName)
+	}
+	if newName == sym.Name {
+		return map[string]interface{}{"changes": map[string]interface{}{}}, nil
+	}
+
+	changes := map[string]interface{}{}
+	seen := map[string]bool{}
+	for n, v := range found {
+		s := syms[n]
+		if other := v.table.Conflict(s, newName); other != nil {
+			file, at := v.locate(other.Pos)
+			if file == v.file {
+				return nil, fmt.Errorf("%s would clash with the %s %s declared on line %d", newName, other.Kind, other.Name, at.Line)
+			}
+			return nil, fmt.Errorf("%s would clash with the %s %s declared on line %d of %s", newName, other.Kind, other.Name, at.Line, filepath.Base(file.Name))
+		}
+		for _, pos := range v.places(s) {
+			file, at := v.locate(pos)
+			target := v.open.uriOf(file.Name)
+			if key := fmt.Sprint(target, at); !seen[key] {
+				seen[key] = true
+				edits, _ := changes[target].([]map[string]interface{})
+				changes[target] = append(edits, map[string]interface{}{"range": nameRange(file.Text, at, s.Name), "newText": newName})
+			}
+		}
+	}
+	return map[string]interface{}{"changes": changes}, nil
+}
+
+func positions(refs []symbols.Reference) []symbols.Position {
+	var out []symbols.Position
+	for _, ref := range refs {
+		out = append(out, ref.Pos)
+	}
+	return out
+}
+
+// location returns the LSP location of name written at pos of text, the
+// file uri
+func location(uri, text string, pos symbols.Position, name string) map[string]interface{} {
+	return map[string]interface{}{"uri": uri, "range": nameRange(text, pos, name)}
+}
+
+// nameRange is the LSP range of name written at pos of text
+func nameRange(text string, pos symbols.Position, name string) map[string]interface{} {
+	return map[string]interface{}{
+		"start": lspPosition(text, pos.Line-1, pos.Column-1),
+		"end":   lspPosition(text, pos.Line-1, pos.Column-1+len(name)),
+	}
+}
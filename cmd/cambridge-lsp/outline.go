@@ -0,0 +1,83 @@
+package main
+
+import (
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+)
+
+// LSP symbol kinds
+const (
+	SymbolFile        = 1
+	SymbolClass       = 5
+	SymbolMethod      = 6
+	SymbolField       = 8
+	SymbolConstructor = 9
+	SymbolEnum        = 10
+	SymbolFunction    = 12
+	SymbolVariable    = 13
+	SymbolConstant    = 14
+	SymbolStruct      = 23
+)
+
+// documentSymbols returns the outline of text: its types with their
+// fields, classes with their members, routines, constants and variables
+// Names local to routines are left out, as they would crowd the outline.
+func documentSymbols(text string) []map[string]interface{} {
+	return outline(text, analyse(text).table.Program.Symbols, false)
+}
+
+func outline(text string, syms []*symbols.Symbol, inClass bool) []map[string]interface{} {
+	items := []map[string]interface{}{}
+	for _, sym := range syms {
+		if sym.Kind == symbols.EnumValue {
+			continue // the detail of their type lists them
+		}
+		item := map[string]interface{}{
+			"name":           sym.Name,
+			"detail":         sym.Detail,
+			"kind":           outlineKind(sym, inClass),
+			"range":          spanRange(text, sym),
+			"selectionRange": nameRange(text, sym.Pos, sym.Name),
+		}
+		if len(sym.Members) > 0 {
+			item["children"] = outline(text, sym.Members, sym.Kind == symbols.Class)
+		}
+		items = append(items, item)
+	}
+	return items
+}
+
+// outlineKind is the LSP symbol kind for sym, which may be a member of a
+// class
+func outlineKind(sym *symbols.Symbol, inClass bool) int {
+	switch sym.Kind {
+	case symbols.Procedure, symbols.Function:
+		if inClass && sym.Name == "NEW" {
+			return SymbolConstructor
+		} else if inClass {
+			return SymbolMethod
+		}
+		return SymbolFunction
+	case symbols.Type:
+		if strings.Contains(sym.Detail, " = (") {
+			return SymbolEnum
+		}
+		return SymbolStruct
+	case symbols.Class:
+		return SymbolClass
+	case symbols.Field:
+		return SymbolField
+	case symbols.Constant:
+		return SymbolConstant
+	}
+	return SymbolVariable
+}
+
+// spanRange is the LSP range of the whole declaration of sym in text
+func spanRange(text string, sym *symbols.Symbol) map[string]interface{} {
+	return map[string]interface{}{
+		"start": lspPosition(text, sym.Start.Line-1, sym.Start.Column-1),
+		"end":   lspPosition(text, sym.End.Line-1, sym.End.Column-1),
+	}
+}
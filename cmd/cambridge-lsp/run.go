@@ -0,0 +1,224 @@
+package main
+
+import (
+	"fmt"
+	"path/filepath"
+	"strings"
+	"time"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/cambridge"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+)
+
+// The commands code lenses carry out through workspace/executeCommand,
+// each given the document's URI and optionally a routine to run alone
+const (
+	commandRun   = "cambridge.run"
+	commandDebug = "cambridge.debug"
+)
+
+// runTimeout is how long a program started from the editor may run, as
+// one stuck in a loop would otherwise run unseen until the server exits
+const runTimeout = 10 * time.Second
+
+// runFuel is how much a program runs between checks of runTimeout
+const runFuel = 10000
+
+// LSP message types
+const (
+	MessageError = 1
+	MessageInfo  = 3
+	MessageLog   = 4
+)
+
+// defaultArguments are the values a routine run from its code lens is
+// passed, by parameter type
+var defaultArguments = map[string]string{
+	"INTEGER": "0",
+	"REAL":    "0.0",
+	"STRING":  `""`,
+	"CHAR":    "' '",
+	"BOOLEAN": "FALSE",
+}
+
+// codeLenses returns the lenses running text, or stepping through it,
+// above its first statement and above each top-level routine that driver
+// can call
+func codeLenses(uri, text string) []map[string]interface{} {
+	lenses := []map[string]interface{}{}
+	a := analyse(text)
+	if !a.parsed() || len(a.program.Statements) == 0 {
+		return lenses
+	}
+	lens := func(line int, title, command string, args ...interface{}) {
+		at := lspPosition(text, line, 0)
+		lenses = append(lenses, map[string]interface{}{
+			"range":   map[string]interface{}{"start": at, "end": at},
+			"command": map[string]interface{}{"title": title, "command": command, "arguments": args},
+		})
+	}
+
+	first, _ := ast.Position(a.program.Statements[0])
+	lens(first-1, "Run", commandRun, uri)
+	lens(first-1, "Debug", commandDebug, uri)
+	for _, sym := range a.table.Program.Symbols {
+		if _, ok := driver(text, a, sym.Name); ok {
+			lens(sym.Start.Line-1, "Run "+sym.Name, commandRun, uri, sym.Name)
+			lens(sym.Start.Line-1, "Debug "+sym.Name, commandDebug, uri, sym.Name)
+		}
+	}
+	return lenses
+}
+
+// driver returns text with its main program taken out and a call to the
+// routine name added at the end, passing each parameter the default value
+// for its type; lines keep their numbers, so errors point into the file
+// A routine with a parameter no default suits, or named as something the
+// program declares, cannot be driven.
+func driver(text string, a *analysis, name string) (string, bool) {
+	sym := a.table.Program.Lookup(name)
+	if sym == nil || sym.Kind != symbols.Procedure && sym.Kind != symbols.Function {
+		return "", false
+	}
+	var setup, args []string
+	for _, param := range sym.Params {
+		typ := strings.TrimPrefix(param.Detail, "BYREF ")
+		value, ok := defaultArguments[typ]
+		if !ok || a.table.Program.Lookup(param.Name) != nil {
+			return "", false
+		}
+		setup = append(setup, "DECLARE "+param.Name+" : "+typ, param.Name+" <- "+value)
+		args = append(args, param.Name)
+	}
+	call := name + "(" + strings.Join(args, ", ") + ")"
+	if sym.Kind == symbols.Function {
+		setup = append(setup, "OUTPUT "+call)
+	} else {
+		setup = append(setup, "CALL "+call)
+	}
+
+	lines := strings.Split(text, "\n")
+	for idx, stmt := range a.program.Statements {
+		switch stmt.(type) {
+		case *ast.DeclareStatement, *ast.ConstantStatement, *ast.ProcedureStatement,
+			*ast.FunctionStatement, *ast.TypeStatement, *ast.ClassStatement:
+			continue
+		}
+		start, _ := ast.Position(stmt)
+		end := len(lines)
+		if idx+1 < len(a.program.Statements) {
+			next, _ := ast.Position(a.program.Statements[idx+1])
+			end = next - 1
+		}
+		for line := max(start-1, 0); line < end; line++ {
+			lines[line] = ""
+		}
+	}
+	return strings.Join(append(lines, setup...), "\n") + "\n", true
+}
+
+// editorFiles returns where OPENFILE finds files for a program run from
+// the editor: only in the folder of the document at uri, or in memory for
+// one never saved
+func editorFiles(uri string) interpreter.FileSystem {
+	if path := uriToPath(uri); path != uri && filepath.IsAbs(path) {
+		return interpreter.SandboxFiles{Root: filepath.Dir(path)}
+	}
+	return interpreter.NewMemoryFiles(nil)
+}
+
+// execute starts a run of the document at uri, or of the routine name in
+// it, sending what it outputs to the editor's log as it goes; to debug,
+// the log also shows each line that runs and the variables it changes
+// The run has no input, so INPUT stops it with an error, and OPENFILE
+// reaches only the files in the document's folder, as under --sandbox.
+func execute(command, uri, text, name string) error {
+	source, what := text, uriToPath(uri)
+	if name != "" {
+		var ok bool
+		if a := analyse(text); a.parsed() {
+			source, ok = driver(text, a, name)
+		}
+		if !ok {
+			return fmt.Errorf("cannot run %s on its own", name)
+		}
+		what = name
+	}
+	prog := cambridge.Parse(uriToPath(uri), source)
+	if !prog.OK() {
+		return fmt.Errorf("cannot run a program with errors")
+	}
+
+	interp := cambridge.New()
+	interp.SetFiles(editorFiles(uri))
+	go func() {
+		logMessage(MessageInfo, "Running "+what)
+		deadline := time.Now().Add(runTimeout)
+		if command == commandDebug {
+			for step := range interp.Steps(prog) {
+				var changed []string
+				for _, v := range step.Changed {
+					changed = append(changed, v.Name+" = "+v.Value.Inspect())
+				}
+				logMessage(MessageLog, strings.TrimSpace(fmt.Sprintf("line %d  %s", step.Line, strings.Join(changed, ", "))))
+				if step.Output != "" {
+					logMessage(MessageLog, strings.TrimSuffix(step.Output, "\n"))
+				}
+				if step.Err != nil {
+					showMessage(MessageError, fmt.Sprintf("%s stopped at line %d: %s", what, step.Err.Line, step.Err.Message))
+					return
+				}
+				if time.Now().After(deadline) {
+					showMessage(MessageError, fmt.Sprintf("%s was stopped after %s", what, runTimeout))
+					return
+				}
+			}
+		} else {
+			interp.SetOutput(editorOutput{})
+			run := interp.Start(prog)
+			for {
+				done, err := run.Continue(runFuel)
+				if err != nil {
+					showMessage(MessageError, err.Error())
+					return
+				}
+				if done {
+					break
+				}
+				if time.Now().After(deadline) {
+					run.Stop()
+					showMessage(MessageError, fmt.Sprintf("%s was stopped after %s", what, runTimeout))
+					return
+				}
+			}
+		}
+		logMessage(MessageInfo, "Finished "+what)
+	}()
+	return nil
+}
+
+// editorOutput writes what a program outputs to the editor's log
+type editorOutput struct{}
+
+func (editorOutput) Write(b []byte) (int, error) {
+	logMessage(MessageLog, strings.TrimSuffix(string(b), "\n"))
+	return len(b), nil
+}
+
+func logMessage(kind int, message string) {
+	writeMessage(map[string]interface{}{
+		"jsonrpc": "2.0",
+		"method":  "window/logMessage",
+		"params":  map[string]interface{}{"type": kind, "message": message},
+	})
+}
+
+func showMessage(kind int, message string) {
+	writeMessage(map[string]interface{}{
+		"jsonrpc": "2.0",
+		"method":  "window/showMessage",
+		"params":  map[string]interface{}{"type": kind, "message": message},
+	})
+}
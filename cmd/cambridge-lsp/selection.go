@@ -0,0 +1,201 @@
+package main
+
+import (
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// span is where a piece of source starts and ends, 1-based with the end
+// just past it
+type span struct {
+	start, end symbols.Position
+}
+
+func (s span) contains(pos symbols.Position) bool {
+	return !pos.Before(s.start) && !s.end.Before(pos)
+}
+
+// selectionRanges returns, for each 0-based position, the ranges expanding
+// the selection there steps through, innermost first: the name, each
+// expression around it, its statement, the blocks and routine it is in
+// and the whole document
+func selectionRanges(text string, positions []interface{}) []interface{} {
+	prog := analyse(text).program
+	spans := nodeSpans(text, prog)
+	ranges := []interface{}{}
+	for _, p := range positions {
+		at, _ := p.(map[string]interface{})
+		line, _ := at["line"].(float64)
+		character, _ := at["character"].(float64)
+		pos := symbols.Position{Line: int(line) + 1, Column: byteColumn(lineAt(text, int(line)), int(character)) + 1}
+
+		var chain []span
+		var node ast.Node = prog
+		for node != nil {
+			var inner ast.Node
+			for _, child := range children(node) {
+				if s, ok := spans[child]; ok && s.contains(pos) {
+					if len(chain) == 0 || chain[len(chain)-1] != s {
+						chain = append(chain, s)
+					}
+					inner = child
+					break
+				}
+			}
+			node = inner
+		}
+
+		selection := map[string]interface{}{"range": documentRange(text)}
+		for _, s := range chain {
+			selection = map[string]interface{}{
+				"range": map[string]interface{}{
+					"start": lspPosition(text, s.start.Line-1, s.start.Column-1),
+					"end":   lspPosition(text, s.end.Line-1, s.end.Column-1),
+				},
+				"parent": selection,
+			}
+		}
+		ranges = append(ranges, selection)
+	}
+	return ranges
+}
+
+// children returns the nodes directly inside node
+func children(node ast.Node) []ast.Node {
+	var found []ast.Node
+	ast.Inspect(node, func(n ast.Node) bool {
+		if n == node {
+			return true
+		}
+		found = append(found, n)
+		return false
+	})
+	return found
+}
+
+// nodeSpans works out where each statement and expression in prog starts
+// and ends
+// The tree keeps only where nodes start, so ends are found in the tokens:
+// an expression ends with its last operand and the brackets closing it, a
+// statement at the end of its last line, and a block with the line that
+// closes it.
+func nodeSpans(text string, prog *ast.Program) map[ast.Node]span {
+	var toks []token.Token
+	index := map[symbols.Position]int{}
+	lineEnds := map[int]symbols.Position{}
+	l := lexer.New(text)
+	for tok := l.NextToken(); tok.Type != token.EOF; tok = l.NextToken() {
+		if tok.Type != token.NEWLINE {
+			index[symbols.Position{Line: tok.Line, Column: tok.Column}] = len(toks)
+			toks = append(toks, tok)
+			lineEnds[tok.Line] = tokenSpan(tok).end
+		}
+	}
+
+	spans := map[ast.Node]span{}
+	var measure func(node ast.Node) (span, bool)
+	measure = func(node ast.Node) (span, bool) {
+		line, column := ast.Position(node)
+		first, ok := index[symbols.Position{Line: line, Column: column}]
+		if !ok {
+			return span{}, false
+		}
+		s := tokenSpan(toks[first])
+		last := first
+		for _, child := range children(node) {
+			if c, ok := measure(child); ok {
+				if c.start.Before(s.start) {
+					s.start = c.start
+				}
+				if s.end.Before(c.end) {
+					s.end = c.end
+				}
+			}
+		}
+		for last+1 < len(toks) && tokenSpan(toks[last+1]).start.Before(s.end) {
+			last++
+		}
+
+		if _, ok := node.(ast.Statement); ok {
+			if closer, ok := blockEnd(toks, first); ok {
+				s.end = tokenSpan(toks[closer]).end
+			}
+			s.end = lineEnds[s.end.Line]
+		} else {
+			// Take in the brackets left open on either side and the name of
+			// a member
+			depth := 0
+			for idx := first; idx <= last; idx++ {
+				switch toks[idx].Type {
+				case token.LPAREN, token.LBRACKET:
+					depth++
+				case token.RPAREN, token.RBRACKET:
+					depth--
+				}
+			}
+			for ; depth < 0 && first > 0 && toks[first-1].Type == token.LPAREN; depth++ {
+				first--
+				s.start = tokenSpan(toks[first]).start
+			}
+			for ; depth > 0 && last+1 < len(toks); depth-- {
+				if t := toks[last+1].Type; t != token.RPAREN && t != token.RBRACKET {
+					break
+				}
+				last++
+				s.end = tokenSpan(toks[last]).end
+			}
+			if _, ok := node.(*ast.MemberAccess); ok && last+2 < len(toks) && toks[last+1].Type == token.DOT {
+				s.end = tokenSpan(toks[last+2]).end
+			}
+		}
+		spans[node] = s
+		return s, true
+	}
+	for _, stmt := range prog.Statements {
+		measure(stmt)
+	}
+	return spans
+}
+
+// blockEnd returns the index of the keyword closing the block opened by
+// the statement starting at toks[first], if it is one
+func blockEnd(toks []token.Token, first int) (int, bool) {
+	opener := first
+	if t := toks[opener].Type; (t == token.PUBLIC || t == token.PRIVATE) && opener+1 < len(toks) {
+		opener++
+	}
+	open := toks[opener].Type
+	end, ok := blockKeywords[open]
+	if !ok {
+		return 0, false
+	}
+	depth := 0
+	for idx := opener; idx < len(toks); idx++ {
+		tok := toks[idx]
+		if open == token.TYPE && tok.Type == token.EQ && tok.Line == toks[opener].Line {
+			return 0, false // an enumeration, on one line
+		}
+		switch {
+		case tok.Type == open && (idx == opener || toks[idx-1].Line != tok.Line):
+			depth++ // only at the start of a line, as FOR is also part of OPENFILE
+		case tok.Type == end:
+			if depth--; depth == 0 {
+				return idx, true
+			}
+		}
+	}
+	return 0, false
+}
+
+// tokenSpan returns where tok is written; the lexer places string and
+// character literals after their closing quote, so they are measured back
+// from there
+func tokenSpan(tok token.Token) span {
+	at := symbols.Position{Line: tok.Line, Column: tok.Column}
+	if tok.Type == token.STRING_LIT || tok.Type == token.CHAR_LIT {
+		return span{start: symbols.Position{Line: tok.Line, Column: tok.Column - len(tok.Literal) - 2}, end: at}
+	}
+	return span{start: at, end: symbols.Position{Line: tok.Line, Column: tok.Column + len(tok.Literal)}}
+}
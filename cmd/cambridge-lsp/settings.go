@@ -0,0 +1,143 @@
+package main
+
+import (
+	"fmt"
+	"strings"
+
+	project "github.com/andrinoff/cambridge-lang/pkg/config"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/format"
+)
+
+// The values of the lint setting, from the most reported to the least
+const (
+	lintAll      = "all"      // errors, warnings and notes
+	lintWarnings = "warnings" // errors and warnings
+	lintErrors   = "errors"   // errors alone
+)
+
+// severityOff, given as the severity of a code, stops it being reported
+const severityOff = "off"
+
+// settings are the options an editor can set, in the initializationOptions
+// of initialize or the "cambridge" section of its configuration
+type settings struct {
+	dialect        string                     // the syllabus the program is checked against
+	insert         int                        // the year of the insert builtins are checked against, 0 for the latest
+	strict         bool                       // report warnings as errors
+	lint           string                     // which problems found by checking are reported
+	severities     map[diagnostic.Code]string // a severity, or "off", for each code given one
+	style          format.Style
+	typeHints      bool // show the types the program leaves to context
+	parameterHints bool // name the parameter each argument is passed as
+}
+
+// defaultSettings are those of an editor that sets none
+var defaultSettings = settings{
+	dialect:        project.Default().Language.Dialect,
+	lint:           lintAll,
+	style:          format.House,
+	typeHints:      true,
+	parameterHints: true,
+}
+
+// config is the settings in force, changed only by the main loop; a check
+// run later is given a copy of them as they were when it was scheduled
+var config = defaultSettings
+
+// project takes the dialect and insert from the cambridge.toml of the first
+// workspace root that has one, which the editor's settings then override
+func (s *settings) project(roots []string) {
+	for _, root := range roots {
+		cfg, err := project.Find(root)
+		if err != nil {
+			logger.Warn("cannot read project settings", "root", root, "error", err)
+			continue
+		}
+		if cfg.Path != "" {
+			s.dialect, s.insert = cfg.Language.Dialect, cfg.Language.Insert
+			return
+		}
+	}
+}
+
+// update applies the options in raw, keeping the current value of any it
+// leaves out or gives a value it cannot have, so that an editor need only
+// send what it changes
+func (s *settings) update(raw interface{}) {
+	options, _ := raw.(map[string]interface{})
+	switch dialect, _ := options["dialect"].(string); dialect {
+	case project.ALevel, project.IGCSE:
+		s.dialect = dialect
+	}
+	if year, ok := options["insert"].(float64); ok && year >= 0 {
+		s.insert = int(year)
+	}
+	if strict, ok := options["strict"].(bool); ok {
+		s.strict = strict
+	}
+	switch lint, _ := options["lint"].(string); lint {
+	case lintAll, lintWarnings, lintErrors:
+		s.lint = lint
+	}
+	if levels, ok := options["severities"].(map[string]interface{}); ok {
+		// Replaced rather than changed, as checks still running hold the old map
+		s.severities = map[diagnostic.Code]string{}
+		for code, raw := range levels {
+			var severity diagnostic.Severity
+			level, _ := raw.(string)
+			if level == severityOff || severity.UnmarshalText([]byte(level)) == nil {
+				s.severities[diagnostic.Code(code)] = level
+			}
+		}
+	}
+
+	layout, _ := options["format"].(map[string]interface{})
+	if width, ok := layout["indent"].(float64); ok && width >= 1 && width <= 8 {
+		s.style.Indent = strings.Repeat(" ", int(width))
+	}
+	if tabs, ok := layout["tabs"].(bool); ok && tabs {
+		s.style.Indent = "\t"
+	} else if ok && s.style.Indent == "\t" {
+		s.style.Indent = format.Indent
+	}
+
+	hints, _ := options["inlayHints"].(map[string]interface{})
+	if types, ok := hints["types"].(bool); ok {
+		s.typeHints = types
+	}
+	if parameters, ok := hints["parameters"].(bool); ok {
+		s.parameterHints = parameters
+	}
+}
+
+// checking identifies the settings diagnostics depend on, so that those
+// kept for a document are not reused once they change
+func (s settings) checking() string {
+	return fmt.Sprintf("%s %d %t %s %v %q", s.dialect, s.insert, s.strict, s.lint, s.severities, s.style.Indent)
+}
+
+// judge applies the severities set for codes, then the strict and lint
+// settings to the problems whose codes have none set
+func (s settings) judge(found []diagnostic.Diagnostic) []diagnostic.Diagnostic {
+	var kept []diagnostic.Diagnostic
+	for _, d := range found {
+		if level, ok := s.severities[d.Code]; ok {
+			if level != severityOff {
+				d.Severity.UnmarshalText([]byte(level))
+				kept = append(kept, d)
+			}
+			continue
+		}
+		switch {
+		case d.Severity == diagnostic.Note && s.lint != lintAll:
+			continue
+		case d.Severity == diagnostic.Warning && s.lint == lintErrors:
+			continue
+		case d.Severity == diagnostic.Warning && s.strict:
+			d.Severity = diagnostic.Error
+		}
+		kept = append(kept, d)
+	}
+	return kept
+}
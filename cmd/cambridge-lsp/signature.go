@@ -0,0 +1,139 @@
+package main
+
+import (
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// signatureHelp returns the signature of the routine whose arguments are
+// being written at a 0-based line and character in text, with the argument
+// the cursor is in as its active parameter, or nil if it is in none
+// Routines the program declares take precedence over builtins of the same
+// name, as they do when it runs.
+func signatureHelp(text string, line, character int) interface{} {
+	toks := tokensBefore(text, line, character)
+	open, active, ok := openCall(toks)
+	if !ok || open == 0 {
+		return nil
+	}
+
+	table := analyse(text).table
+	scope := table.At(symbols.Position{Line: line + 1, Column: character + 1})
+	name := toks[open-1]
+	var sig map[string]interface{}
+	switch {
+	case open > 1 && toks[open-2].Type == token.DOT && (name.Type == token.IDENT || name.Type == token.NEW):
+		if custom, ok := receiverType(scope, text, toks, open-2); ok {
+			sig = routineSignature(scope.Member(custom.Name, name.Literal))
+		}
+	case open > 1 && toks[open-2].Type == token.NEW && name.Type == token.IDENT:
+		sig = routineSignature(scope.Member(name.Literal, "NEW"))
+	case name.Type == token.IDENT:
+		if sym := scope.Lookup(name.Literal); sym != nil {
+			sig = routineSignature(sym)
+		} else if b, ok := builtins.Signatures[name.Literal]; ok {
+			sig = builtinSignature(b)
+		}
+	}
+	if sig == nil {
+		return nil
+	}
+	return map[string]interface{}{
+		"signatures":      []map[string]interface{}{sig},
+		"activeSignature": 0,
+		"activeParameter": active,
+	}
+}
+
+// openCall finds the bracket opening the argument list the end of toks is
+// in, returning its index and how many arguments come before the current
+// one; calls are looked for on the current line only
+func openCall(toks []token.Token) (int, int, bool) {
+	depth, commas := 0, 0
+	for idx := len(toks) - 1; idx >= 0; idx-- {
+		switch toks[idx].Type {
+		case token.NEWLINE:
+			return 0, 0, false
+		case token.RPAREN, token.RBRACKET:
+			depth++
+		case token.LBRACKET:
+			if depth == 0 {
+				return 0, 0, false // an index, not an argument
+			}
+			depth--
+		case token.LPAREN:
+			if depth == 0 {
+				return idx, commas, true
+			}
+			depth--
+		case token.COMMA:
+			if depth == 0 {
+				commas++
+			}
+		}
+	}
+	return 0, 0, false
+}
+
+// routineSignature describes a procedure or function the program declares,
+// or returns nil if sym is not one
+func routineSignature(sym *symbols.Symbol) map[string]interface{} {
+	if sym == nil || sym.Kind != symbols.Procedure && sym.Kind != symbols.Function {
+		return nil
+	}
+	var params []string
+	for _, p := range sym.Params {
+		typ, byRef := strings.CutPrefix(p.Detail, "BYREF ")
+		label := p.Name + " : " + typ
+		if byRef {
+			label = "BYREF " + label
+		}
+		params = append(params, label)
+	}
+	suffix := ")"
+	if sym.Kind == symbols.Function && sym.DataType != nil {
+		suffix += " RETURNS " + sym.DataType.String()
+	}
+	return signatureInformation(strings.ToUpper(string(sym.Kind))+" "+sym.Name+"(", params, nil, suffix, "")
+}
+
+// builtinSignature describes a builtin with what it and each of its
+// parameters mean
+func builtinSignature(sig builtins.Signature) map[string]interface{} {
+	var params, docs []string
+	for _, p := range sig.Params {
+		params = append(params, p.Name+" : "+p.Type)
+		docs = append(docs, p.Doc)
+	}
+	return signatureInformation(sig.Name+"(", params, docs, ") RETURNS "+sig.Returns, sig.Doc)
+}
+
+// signatureInformation builds an LSP signature from its parts, giving each
+// parameter as offsets into the label so that names appearing twice in it
+// are still told apart
+func signatureInformation(prefix string, params, docs []string, suffix, doc string) map[string]interface{} {
+	label := prefix
+	parameters := []map[string]interface{}{}
+	for idx, p := range params {
+		if idx > 0 {
+			label += ", "
+		}
+		param := map[string]interface{}{"label": []int{len(label), len(label) + len(p)}}
+		if idx < len(docs) {
+			param["documentation"] = docs[idx]
+		}
+		parameters = append(parameters, param)
+		label += p
+	}
+	info := map[string]interface{}{
+		"label":      label + suffix,
+		"parameters": parameters,
+	}
+	if doc != "" {
+		info["documentation"] = doc
+	}
+	return info
+}
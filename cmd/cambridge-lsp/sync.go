@@ -0,0 +1,133 @@
+package main
+
+import "strings"
+
+// utf8Positions is set when the editor agrees to count the characters of
+// a position in bytes, as the lexer counts columns, rather than in the
+// UTF-16 code units LSP counts by default
+var utf8Positions bool
+
+// positionEncoding picks the encoding of positions from the ones the
+// editor offers in initialize, preferring bytes
+func positionEncoding(params map[string]interface{}) string {
+	caps, _ := params["capabilities"].(map[string]interface{})
+	general, _ := caps["general"].(map[string]interface{})
+	offered, _ := general["positionEncodings"].([]interface{})
+	utf8Positions = false
+	for _, encoding := range offered {
+		if encoding == "utf-8" {
+			utf8Positions = true
+			return "utf-8"
+		}
+	}
+	return "utf-16"
+}
+
+// applyChanges returns text with the changes of a didChange notification
+// made in order; one with no range replaces the whole document
+func applyChanges(text string, changes []interface{}) string {
+	for _, c := range changes {
+		change, _ := c.(map[string]interface{})
+		newText, _ := change["text"].(string)
+		rng, ok := change["range"].(map[string]interface{})
+		if !ok {
+			text = newText
+			continue
+		}
+		start, end := offset(text, rng["start"]), offset(text, rng["end"])
+		if end < start {
+			start, end = end, start
+		}
+		text = text[:start] + newText + text[end:]
+	}
+	return text
+}
+
+// offset returns the byte offset in text of an LSP position, clamped to
+// the line it is on and to the end of text
+func offset(text string, pos interface{}) int {
+	p, _ := pos.(map[string]interface{})
+	line, _ := p["line"].(float64)
+	character, _ := p["character"].(float64)
+
+	at := 0
+	for n := 0; n < int(line); n++ {
+		next := strings.IndexByte(text[at:], '\n')
+		if next < 0 {
+			return len(text)
+		}
+		at += next + 1
+	}
+	return at + byteColumn(lineAt(text[at:], 0), int(character))
+}
+
+// position returns the 0-based line of the position a request is about in
+// text, and the byte column it names on that line
+func position(text string, params map[string]interface{}) (int, int) {
+	pos, _ := params["position"].(map[string]interface{})
+	line, _ := pos["line"].(float64)
+	character, _ := pos["character"].(float64)
+	return int(line), byteColumn(lineAt(text, int(line)), int(character))
+}
+
+// lspPosition returns the LSP position of the 0-based byte column col on
+// the 0-based line of text
+// Every position sent to the editor goes through it, as every one read
+// from it goes through byteColumn, so the two agree on the encoding.
+func lspPosition(text string, line, col int) map[string]int {
+	return map[string]int{"line": line, "character": lspCharacter(lineAt(text, line), col)}
+}
+
+// byteColumn returns the byte column of line an LSP character names,
+// clamped to the end of line
+func byteColumn(line string, character int) int {
+	if utf8Positions {
+		return min(max(character, 0), len(line))
+	}
+	units := 0
+	for idx, r := range line {
+		if units >= character {
+			return idx
+		}
+		units += utf16Units(r)
+	}
+	return len(line)
+}
+
+// lspCharacter returns the LSP character naming the byte column col of
+// line, clamped to the end of line
+func lspCharacter(line string, col int) int {
+	col = min(max(col, 0), len(line))
+	if utf8Positions {
+		return col
+	}
+	units := 0
+	for _, r := range line[:col] {
+		units += utf16Units(r)
+	}
+	return units
+}
+
+// utf16Units is how many UTF-16 code units r is written in
+func utf16Units(r rune) int {
+	if r > 0xFFFF {
+		return 2 // a surrogate pair
+	}
+	return 1
+}
+
+// lineAt returns the 0-based line of text without its newline, or "" past
+// the end of text
+func lineAt(text string, line int) string {
+	for ; line > 0; line-- {
+		next := strings.IndexByte(text, '\n')
+		if next < 0 {
+			return ""
+		}
+		text = text[next+1:]
+	}
+	if end := strings.IndexByte(text, '\n'); end >= 0 {
+		text = text[:end]
+	}
+	return text
+}
@@ -0,0 +1,136 @@
+package main
+
+import (
+	"bufio"
+	"errors"
+	"fmt"
+	"io"
+	"net"
+	"net/http"
+	"net/textproto"
+	"slices"
+	"strconv"
+	"strings"
+	"sync"
+
+	"github.com/andrinoff/cambridge-lang/pkg/dap"
+	"github.com/andrinoff/cambridge-lang/pkg/websocket"
+)
+
+// connection carries messages between the server and one editor
+type connection interface {
+	read() ([]byte, error)
+	write(msg []byte) error
+}
+
+// errMalformed is returned by read for a message it could not make sense
+// of but could read past, so that the messages after it can still be read
+var errMalformed = errors.New("malformed message")
+
+// stream is a connection over stdio or TCP, where each message is preceded
+// by a header giving its Content-Length
+type stream struct {
+	reader *bufio.Reader
+	writer io.Writer
+}
+
+func newStream(r io.Reader, w io.Writer) *stream {
+	return &stream{reader: bufio.NewReader(r), writer: w}
+}
+
+func (s *stream) read() ([]byte, error) {
+	header, err := textproto.NewReader(s.reader).ReadMIMEHeader()
+	if err != nil {
+		var protocol textproto.ProtocolError
+		if errors.As(err, &protocol) {
+			return nil, fmt.Errorf("%w: %v", errMalformed, err)
+		}
+		return nil, err
+	}
+	length, _ := strconv.Atoi(header.Get("Content-Length"))
+	body := make([]byte, length)
+	if _, err := io.ReadFull(s.reader, body); err != nil {
+		return nil, err
+	}
+	return body, nil
+}
+
+func (s *stream) write(msg []byte) error {
+	_, err := fmt.Fprintf(s.writer, "Content-Length: %d\r\n\r\n%s", len(msg), msg)
+	return err
+}
+
+// webSocket is a connection to a web editor such as Monaco, which sends
+// each message as a WebSocket message of its own, without a header
+type webSocket struct {
+	conn *websocket.Conn
+}
+
+func (ws webSocket) read() ([]byte, error) { return ws.conn.Read() }
+
+func (ws webSocket) write(msg []byte) error { return ws.conn.Write(msg) }
+
+// sessionMu lets one editor in at a time, as the documents and settings
+// the server keeps are those of a single session
+var sessionMu sync.Mutex
+
+// listenTCP hands each editor connecting to addr to handle in turn
+func listenTCP(addr string, handle func(conn net.Conn)) error {
+	listener, err := net.Listen("tcp", addr)
+	if err != nil {
+		return err
+	}
+	logger.Info("listening for TCP connections", "address", listener.Addr())
+	for {
+		conn, err := listener.Accept()
+		if err != nil {
+			return err
+		}
+		logger.Info("client connected", "address", conn.RemoteAddr())
+		handle(conn)
+		conn.Close()
+	}
+}
+
+// debug runs a debug adapter session for the editor at the other end of
+// conn
+func debug(conn net.Conn) {
+	if err := dap.Serve(conn, conn); err != nil {
+		logger.Warn("debug session failed", "error", err)
+	}
+}
+
+// listenWebSocket serves each editor opening a WebSocket to addr in turn,
+// and answers GET /health so that an extension can tell the server is up
+// A handshake from a web page is refused unless the page is one of the
+// comma-separated origins allowed, as any site the user visits could
+// otherwise drive the server and run programs on their machine.
+func listenWebSocket(addr, allowed string) error {
+	var origins []string
+	if allowed != "" {
+		origins = strings.Split(allowed, ",")
+	}
+	mux := http.NewServeMux()
+	mux.HandleFunc("/health", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprintf(w, "cambridge-lsp v%s\n", VERSION)
+	})
+	mux.HandleFunc("/", func(w http.ResponseWriter, r *http.Request) {
+		if !websocket.SameOrigin(r) && !slices.Contains(origins, r.Header.Get("Origin")) {
+			logger.Warn("refused WebSocket from another site", "address", r.RemoteAddr, "origin", r.Header.Get("Origin"))
+			http.Error(w, "connections from other sites are not allowed", http.StatusForbidden)
+			return
+		}
+		conn, err := websocket.Upgrade(w, r)
+		if err != nil {
+			logger.Warn("cannot open WebSocket", "address", r.RemoteAddr, "error", err)
+			return
+		}
+		defer conn.Close()
+		logger.Info("client connected", "address", r.RemoteAddr)
+		sessionMu.Lock()
+		defer sessionMu.Unlock()
+		serve(webSocket{conn})
+	})
+	logger.Info("listening for WebSocket connections", "address", addr)
+	return http.ListenAndServe(addr, mux)
+}
@@ -0,0 +1,259 @@
+package main
+
+import (
+	"net/url"
+	"os"
+	"path/filepath"
+	"sort"
+	"strings"
+	"time"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/include"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+)
+
+// sourceExtensions are the files indexed for workspace symbols; .txt is
+// left out as it is more often a data file a program reads
+var sourceExtensions = map[string]bool{".pseudo": true, ".cambridge": true, ".cam": true, ".psc": true}
+
+// maxWorkspaceSymbols caps the answer to a workspace/symbol query
+const maxWorkspaceSymbols = 200
+
+// workspaceRoots are the folders the editor opened, from initialize
+var workspaceRoots []string
+
+// indexedFile is what a file on disk declares, kept until it changes
+type indexedFile struct {
+	modTime  time.Time
+	size     int64
+	text     string // the source, which the positions of its entries are in
+	entries  []indexEntry
+	includes bool // whether it INCLUDEs other files
+}
+
+// indexEntry is a routine, type, class or class member found in a file
+type indexEntry struct {
+	sym       *symbols.Symbol
+	container string // the class a member belongs to
+}
+
+var workspaceIndex = map[string]*indexedFile{}
+
+// setWorkspaceRoots records the folders in an initialize request
+func setWorkspaceRoots(params map[string]interface{}) {
+	workspaceRoots = nil
+	if folders, ok := params["workspaceFolders"].([]interface{}); ok {
+		for _, f := range folders {
+			folder, _ := f.(map[string]interface{})
+			if uri, ok := folder["uri"].(string); ok {
+				workspaceRoots = append(workspaceRoots, uriToPath(uri))
+			}
+		}
+	}
+	if uri, ok := params["rootUri"].(string); ok && len(workspaceRoots) == 0 {
+		workspaceRoots = append(workspaceRoots, uriToPath(uri))
+	}
+}
+
+// workspaceSymbols returns the routines, types and classes declared in the
+// workspace whose names contain query, ignoring case; open documents are
+// searched as the editor has them rather than as saved
+func workspaceSymbols(query string, documents map[string]string) []map[string]interface{} {
+	query = strings.ToLower(query)
+	results := []map[string]interface{}{}
+	add := func(uri string, f *indexedFile) {
+		for _, e := range f.entries {
+			if len(results) < maxWorkspaceSymbols && strings.Contains(strings.ToLower(e.sym.Name), query) {
+				item := map[string]interface{}{
+					"name":     e.sym.Name,
+					"kind":     outlineKind(e.sym, e.container != ""),
+					"location": location(uri, f.text, e.sym.Pos, e.sym.Name),
+				}
+				if e.container != "" {
+					item["containerName"] = e.container
+				}
+				results = append(results, item)
+			}
+		}
+	}
+
+	open := openDocuments(documents)
+	for _, uri := range open.uris() {
+		add(uri, indexSource(documents[uri]))
+	}
+	walkWorkspace(open, func(path string, f *indexedFile) {
+		add(pathToURI(path), f)
+	})
+	return results
+}
+
+// walkWorkspace calls visit with each source file in the workspace folders
+// that is not open in the editor, and what it declares
+func walkWorkspace(open *openFiles, visit func(path string, f *indexedFile)) {
+	for _, root := range workspaceRoots {
+		_ = filepath.WalkDir(root, func(path string, d os.DirEntry, err error) error {
+			if err != nil {
+				return nil
+			}
+			if d.IsDir() {
+				if path != root && (strings.HasPrefix(d.Name(), ".") || d.Name() == "node_modules") {
+					return filepath.SkipDir
+				}
+				return nil
+			}
+			if _, ok := open.text[filepath.Clean(path)]; ok || !sourceExtensions[strings.ToLower(filepath.Ext(path))] {
+				return nil
+			}
+			if f, ok := indexFile(path); ok {
+				visit(path, f)
+			}
+			return nil
+		})
+	}
+}
+
+// indexFile returns what the file at path declares, parsing it again only
+// if it has changed since it was last indexed
+func indexFile(path string) (*indexedFile, bool) {
+	info, err := os.Stat(path)
+	if err != nil {
+		return nil, false
+	}
+	if f, ok := workspaceIndex[path]; ok && f.modTime.Equal(info.ModTime()) && f.size == info.Size() {
+		return f, true
+	}
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return nil, false
+	}
+	f := indexSource(string(data))
+	f.modTime, f.size = info.ModTime(), info.Size()
+	workspaceIndex[path] = f
+	return f, true
+}
+
+// indexSource returns the routines, types, classes and class members text
+// declares, and whether it includes other files
+func indexSource(text string) *indexedFile {
+	prog := parser.New(lexer.New(text)).ParseProgram()
+	table := symbols.Build(prog, text)
+	f := &indexedFile{text: text, includes: hasIncludes(prog)}
+	for _, sym := range table.Program.Symbols {
+		switch sym.Kind {
+		case symbols.Procedure, symbols.Function, symbols.Type:
+			f.entries = append(f.entries, indexEntry{sym: sym})
+		case symbols.Class:
+			f.entries = append(f.entries, indexEntry{sym: sym})
+			for _, m := range sym.Members {
+				if m.Kind == symbols.Procedure || m.Kind == symbols.Function {
+					f.entries = append(f.entries, indexEntry{sym: m, container: sym.Name})
+				}
+			}
+		}
+	}
+	return f
+}
+
+// hasIncludes reports whether prog has an INCLUDE at the top level
+func hasIncludes(prog *ast.Program) bool {
+	for _, stmt := range prog.Statements {
+		if _, ok := stmt.(*ast.IncludeStatement); ok {
+			return true
+		}
+	}
+	return false
+}
+
+// openFiles are the documents open in the editor, by cleaned path, which
+// are read as the editor has them rather than as saved
+type openFiles struct {
+	text map[string]string
+	uri  map[string]string
+}
+
+func openDocuments(documents map[string]string) *openFiles {
+	open := &openFiles{text: map[string]string{}, uri: map[string]string{}}
+	for uri, text := range documents {
+		path := filepath.Clean(uriToPath(uri))
+		open.text[path], open.uri[path] = text, uri
+	}
+	return open
+}
+
+// uris returns the URIs of the open documents in order
+func (o *openFiles) uris() []string {
+	var uris []string
+	for _, uri := range o.uri {
+		uris = append(uris, uri)
+	}
+	sort.Strings(uris)
+	return uris
+}
+
+// uriOf returns the URI of the file at path, as the editor names it if it
+// is open
+func (o *openFiles) uriOf(path string) string {
+	if uri, ok := o.uri[filepath.Clean(path)]; ok {
+		return uri
+	}
+	return pathToURI(path)
+}
+
+// read reads an included file for include.Load
+func (o *openFiles) read(name string) (string, error) {
+	if text, ok := o.text[filepath.Clean(name)]; ok {
+		return text, nil
+	}
+	return include.ReadFile(name)
+}
+
+// includers calls visit with each program in the workspace, open or on
+// disk, whose main file is not path but which includes it, directly or
+// through other files, until visit returns false
+func includers(path string, open *openFiles, visit func(prog *include.Program) bool) {
+	path = filepath.Clean(path)
+	more := true
+	load := func(main, text string) {
+		if !more || filepath.Clean(main) == path {
+			return
+		}
+		prog := include.Load(main, text, open.read)
+		for _, f := range prog.Files[1:] {
+			if filepath.Clean(f.Name) == path {
+				more = visit(prog)
+				return
+			}
+		}
+	}
+	for _, uri := range open.uris() {
+		main := filepath.Clean(uriToPath(uri))
+		if text := open.text[main]; hasIncludes(analyse(text).program) {
+			load(main, text)
+		}
+	}
+	walkWorkspace(open, func(main string, f *indexedFile) {
+		if f.includes && more {
+			if text, err := include.ReadFile(main); err == nil {
+				load(main, text)
+			}
+		}
+	})
+}
+
+func uriToPath(uri string) string {
+	u, err := url.Parse(uri)
+	if err != nil || u.Scheme != "file" {
+		return uri
+	}
+	return filepath.FromSlash(u.Path)
+}
+
+func pathToURI(path string) string {
+	if abs, err := filepath.Abs(path); err == nil {
+		path = abs
+	}
+	return (&url.URL{Scheme: "file", Path: filepath.ToSlash(path)}).String()
+}
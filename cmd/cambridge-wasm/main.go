@@ -0,0 +1,192 @@
+//go:build js && wasm
+
+// Command cambridge-wasm builds the interpreter as WebAssembly, so a page
+// can check and run programs without a server
+//
+//	GOOS=js GOARCH=wasm go build -o cambridge.wasm ./cmd/cambridge-wasm
+//
+// Once started with Go's wasm_exec.js it sets a global cambridge object:
+//
+//	cambridge.parse(source)          {ok, diagnostics, ast}
+//	cambridge.check(source)          diagnostics
+//	cambridge.run(source, options)   {ok, diagnostics, files}
+//	cambridge.format(source)         formatted source, or null if it does
+//	                                 not parse
+//
+// run calls options.output(text) for everything the program writes and
+// options.input() each time INPUT needs a line, which returns the line or
+// null at the end of input; options.input may instead be a string holding
+// all of it. options.seed makes RAND, RANDOM and TODAY repeatable, and
+// options.disable lists capabilities to turn off: "files", "random" or
+// "input".
+// There is no disk: OPENFILE sees only options.files, an object mapping
+// names to contents, and files in the result holds them after the run.
+// Diagnostics and syntax trees are plain objects in the shape their JSON
+// encoding gives.
+package main
+
+import (
+	"encoding/json"
+	"errors"
+	"io"
+	"strings"
+	"syscall/js"
+
+	"github.com/andrinoff/cambridge-lang/pkg/cambridge"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/format"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+)
+
+func main() {
+	js.Global().Set("cambridge", js.ValueOf(map[string]any{
+		"parse":  js.FuncOf(parse),
+		"check":  js.FuncOf(check),
+		"run":    js.FuncOf(run),
+		"format": js.FuncOf(formatSource),
+	}))
+	select {} // keep the functions alive for the life of the page
+}
+
+func parse(_ js.Value, args []js.Value) any {
+	prog := cambridge.Parse("main.pseudo", source(args))
+	tree, err := json.Marshal(prog.AST())
+	if err != nil {
+		tree = []byte("null")
+	}
+	return js.ValueOf(map[string]any{
+		"ok":          prog.OK(),
+		"diagnostics": toJS(diagnosticsOf(prog.Diagnostics())),
+		"ast":         fromJSON(tree),
+	})
+}
+
+func check(_ js.Value, args []js.Value) any {
+	return toJS(diagnosticsOf(cambridge.Parse("main.pseudo", source(args)).Diagnostics()))
+}
+
+func run(_ js.Value, args []js.Value) any {
+	prog := cambridge.Parse("main.pseudo", source(args))
+	options := js.Undefined()
+	if len(args) > 1 {
+		options = args[1]
+	}
+
+	interp := cambridge.New()
+	files := map[string]string{}
+	if options.Truthy() {
+		if input := options.Get("input"); input.Type() == js.TypeString {
+			interp.SetInput(strings.NewReader(input.String()))
+		} else if input.Type() == js.TypeFunction {
+			interp.SetInput(&inputReader{fn: input})
+		}
+		if output := options.Get("output"); output.Type() == js.TypeFunction {
+			interp.SetOutput(outputWriter{fn: output})
+		}
+		if seed := options.Get("seed"); seed.Type() == js.TypeNumber {
+			interp.SetDeterministic(int64(seed.Int()))
+		}
+		if disable := options.Get("disable"); disable.Truthy() {
+			for idx := 0; idx < disable.Length(); idx++ {
+				if c, err := interpreter.ParseCapability(disable.Index(idx).String()); err == nil {
+					interp.Disable(c)
+				}
+			}
+		}
+		if given := options.Get("files"); given.Type() == js.TypeObject {
+			names := js.Global().Get("Object").Call("keys", given)
+			for idx := 0; idx < names.Length(); idx++ {
+				name := names.Index(idx).String()
+				files[name] = given.Get(name).String()
+			}
+		}
+	}
+	memory := interpreter.NewMemoryFiles(files)
+	interp.SetFiles(memory)
+
+	diagnostics := diagnosticsOf(prog.Diagnostics())
+	var runErr *cambridge.Error
+	err := interp.Run(prog)
+	if errors.As(err, &runErr) && prog.OK() {
+		diagnostics = append(diagnostics, runErr.Diagnostic)
+	}
+	after := map[string]any{}
+	for name, contents := range memory.Files() {
+		after[name] = contents
+	}
+	return js.ValueOf(map[string]any{
+		"ok":          err == nil,
+		"diagnostics": toJS(diagnostics),
+		"files":       after,
+	})
+}
+
+func formatSource(_ js.Value, args []js.Value) any {
+	formatted, err := format.Source(source(args))
+	if err != nil {
+		return js.Null()
+	}
+	return formatted
+}
+
+// source returns the first argument as a string, or "" if there is none
+func source(args []js.Value) string {
+	if len(args) == 0 || args[0].Type() != js.TypeString {
+		return ""
+	}
+	return args[0].String()
+}
+
+// diagnosticsOf never returns nil, so that JavaScript always sees an array
+func diagnosticsOf(ds []diagnostic.Diagnostic) []diagnostic.Diagnostic {
+	if ds == nil {
+		return []diagnostic.Diagnostic{}
+	}
+	return ds
+}
+
+func toJS(ds []diagnostic.Diagnostic) js.Value {
+	data, err := json.Marshal(ds)
+	if err != nil {
+		data = []byte("[]")
+	}
+	return fromJSON(data)
+}
+
+func fromJSON(data []byte) js.Value {
+	return js.Global().Get("JSON").Call("parse", string(data))
+}
+
+// inputReader reads lines from a JavaScript function
+type inputReader struct {
+	fn      js.Value
+	pending string
+	done    bool
+}
+
+func (r *inputReader) Read(p []byte) (int, error) {
+	if r.pending == "" {
+		if r.done {
+			return 0, io.EOF
+		}
+		line := r.fn.Invoke()
+		if line.Type() != js.TypeString {
+			r.done = true
+			return 0, io.EOF
+		}
+		r.pending = line.String() + "\n"
+	}
+	n := copy(p, r.pending)
+	r.pending = r.pending[n:]
+	return n, nil
+}
+
+// outputWriter passes what a program writes to a JavaScript function
+type outputWriter struct {
+	fn js.Value
+}
+
+func (w outputWriter) Write(p []byte) (int, error) {
+	w.fn.Invoke(string(p))
+	return len(p), nil
+}
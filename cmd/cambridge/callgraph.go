@@ -0,0 +1,39 @@
+package main
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"os"
+
+	"github.com/andrinoff/cambridge-lang/pkg/callgraph"
+	"github.com/andrinoff/cambridge-lang/pkg/include"
+)
+
+// runCallGraph prints which routines call which as DOT or JSON
+func runCallGraph(args []string) {
+	fs := flag.NewFlagSet("callgraph", flag.ExitOnError)
+	format := fs.String("format", "dot", "output format: dot or json")
+	fs.Parse(args)
+
+	if fs.NArg() < 1 {
+		fmt.Println("Usage: cambridge callgraph [--format dot|json] <filename>")
+		os.Exit(1)
+	}
+
+	program, _ := parseFile(fs.Arg(0), include.ReadFile)
+	graph := callgraph.Build(program)
+
+	switch *format {
+	case "dot":
+		fmt.Print(graph.DOT())
+	case "json":
+		out, err := json.MarshalIndent(graph, "", "  ")
+		if err != nil {
+			fatal("cannot encode call graph: %v", err)
+		}
+		fmt.Println(string(out))
+	default:
+		fatal("unknown format %q (expected dot or json)", *format)
+	}
+}
@@ -0,0 +1,43 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"os"
+
+	"github.com/andrinoff/cambridge-lang/pkg/convert"
+)
+
+// runConvert rewrites operator symbols between Unicode and ASCII
+func runConvert(args []string) {
+	fs := flag.NewFlagSet("convert", flag.ExitOnError)
+	to := fs.String("to", "ascii", "target spelling: ascii or unicode")
+	write := fs.Bool("w", false, "write the result back to the file instead of stdout")
+	fs.Parse(args)
+
+	if fs.NArg() < 1 {
+		fmt.Println("Usage: cambridge convert [--to ascii|unicode] [-w] <filename>")
+		os.Exit(1)
+	}
+
+	dir, err := convert.ParseDirection(*to)
+	if err != nil {
+		fatal("%v", err)
+	}
+
+	filename := fs.Arg(0)
+	content, err := os.ReadFile(filename)
+	if err != nil {
+		fatal("cannot read file: %v", err)
+	}
+
+	converted := convert.Convert(string(content), dir)
+
+	if *write {
+		if err := os.WriteFile(filename, []byte(converted), 0644); err != nil {
+			fatal("cannot write file: %v", err)
+		}
+		return
+	}
+	fmt.Print(converted)
+}
@@ -0,0 +1,44 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"os"
+
+	"github.com/andrinoff/cambridge-lang/pkg/format"
+)
+
+// runFormat lays a file out in the house style, as the language server
+// does when the editor formats it
+func runFormat(args []string) {
+	fs := flag.NewFlagSet("fmt", flag.ExitOnError)
+	write := fs.Bool("w", false, "write the result back to the file instead of stdout")
+	fs.Parse(args)
+
+	if fs.NArg() < 1 {
+		fmt.Println("Usage: cambridge fmt [-w] <filename>")
+		os.Exit(1)
+	}
+
+	filename := fs.Arg(0)
+	content, err := os.ReadFile(filename)
+	if err != nil {
+		fatal("cannot read file: %v", err)
+	}
+
+	formatted, err := format.Source(string(content))
+	if err != nil {
+		fatal("%v", err)
+	}
+
+	if *write {
+		if formatted == string(content) {
+			return
+		}
+		if err := os.WriteFile(filename, []byte(formatted), 0644); err != nil {
+			fatal("cannot write file: %v", err)
+		}
+		return
+	}
+	fmt.Print(formatted)
+}
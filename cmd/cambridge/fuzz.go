@@ -0,0 +1,141 @@
+package main
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strconv"
+	"strings"
+	"time"
+
+	"github.com/andrinoff/cambridge-lang/pkg/fuzz"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+)
+
+// namedRanges holds flags such as --input, each NAME=RANGE
+type namedRanges map[string]fuzz.Range
+
+func (r namedRanges) String() string { return "" }
+
+func (r namedRanges) Set(text string) error {
+	name, spec, ok := strings.Cut(text, "=")
+	if !ok || name == "" {
+		return fmt.Errorf("expected NAME=RANGE, got %q", text)
+	}
+	rng, err := fuzz.ParseRange(spec)
+	if err != nil {
+		return err
+	}
+	r[name] = rng
+	return nil
+}
+
+// runFuzz runs a program on many sets of random input, reporting the ways
+// it goes wrong
+func runFuzz(args []string) {
+	fs := flag.NewFlagSet("fuzz", flag.ExitOnError)
+	runs := fs.Int("runs", 200, "how many sets of input to try")
+	seed := fs.Int64("seed", 1, "the seed for the input generated, so the same seed tries the same input again")
+	steps := fs.Int("steps", 100000, "stop a run as a timeout after this many statements")
+	timeout := fs.Duration("timeout", 2*time.Second, "stop a run as a timeout after this long")
+	ranges := namedRanges{}
+	fs.Var(ranges, "input", "generate what INPUT reads into a variable as `NAME=RANGE`: LOW..HIGH, string:LOW..HIGH or A|B|C (repeatable)")
+	assert := fs.String("assert", "", "report a run whose results do not make `expression` TRUE; it may name the program's variables and "+fuzz.OutputName)
+	jobs := fs.Int("jobs", 0, "number of runs at once (default: one per CPU)")
+	asJSON := fs.Bool("json", false, "output the report as JSON")
+	deterministic := fs.Bool("deterministic", false, "seed RAND and RANDOM with --seed and freeze TODAY in every run")
+	var sb sandbox
+	sb.addFlags(fs)
+	var caps capabilities
+	caps.addFlags(fs)
+	fs.Usage = func() {
+		fmt.Fprintln(os.Stderr, "Usage: cambridge fuzz [--runs N] [--seed N] [--steps N] [--timeout D] [--input NAME=RANGE]... [--assert expression] [--jobs N] [--json] [--deterministic] [--sandbox | --file-root dir] [--disable groups] <filename>")
+		fs.PrintDefaults()
+	}
+	fs.Parse(args)
+
+	if fs.NArg() != 1 || *runs < 1 {
+		fs.Usage()
+		os.Exit(1)
+	}
+	filename := fs.Arg(0)
+	program, _ := parseFile(filename, sb.includes(filepath.Dir(filename)))
+	interpreter.Resolve(program)
+
+	opts := fuzz.Options{
+		Runs:       *runs,
+		Seed:       *seed,
+		Steps:      *steps,
+		Timeout:    *timeout,
+		Inputs:     ranges,
+		Workers:    *jobs,
+		Arithmetic: arithmetic(),
+		Files:      sb.files(filepath.Dir(filename)),
+		Disabled:   caps.list(),
+		Builtins:   determinism{enabled: *deterministic, seed: *seed}.builtins,
+	}
+	if *assert != "" {
+		expr, err := fuzz.ParseAssertion(*assert)
+		if err != nil {
+			fatal("%v", err)
+		}
+		opts.Assert = expr
+	}
+
+	report, err := fuzz.Run(program, opts)
+	if err != nil {
+		fatal("%v", err)
+	}
+
+	if *asJSON {
+		out, err := json.MarshalIndent(report, "", "  ")
+		if err != nil {
+			fatal("cannot encode report: %v", err)
+		}
+		fmt.Println(string(out))
+	} else {
+		printFuzzReport(filename, report)
+	}
+	if len(report.Failures) > 0 {
+		os.Exit(1)
+	}
+}
+
+// printFuzzReport lists each way the program went wrong, with the input of
+// the first run to go wrong that way
+func printFuzzReport(filename string, report fuzz.Report) {
+	failed := 0
+	for _, f := range report.Failures {
+		failed += f.Count
+		where := ""
+		if f.Line > 0 && f.Outcome != fuzz.TimedOut {
+			where = fmt.Sprintf("line %d: ", f.Line)
+		}
+		fmt.Printf("%-10s %s%s (%d %s)\n", strings.ToUpper(string(f.Outcome)), where, f.Message, f.Count, plural(f.Count, "run", "runs"))
+
+		input := "no input"
+		if len(f.Input) > 0 {
+			quoted := make([]string, len(f.Input))
+			for n, line := range f.Input {
+				quoted[n] = strconv.Quote(line)
+			}
+			input = "input " + strings.Join(quoted, ", ")
+		}
+		fmt.Printf("%-10s first in run %d, on %s\n", "", f.Run, input)
+	}
+
+	if failed == 0 {
+		fmt.Printf("%s: no problems found in %d runs (seed %d)\n", filename, report.Runs, report.Seed)
+		return
+	}
+	fmt.Printf("\n%s: %d of %d runs went wrong (seed %d)\n", filename, failed, report.Runs, report.Seed)
+}
+
+func plural(n int, one, many string) string {
+	if n == 1 {
+		return one
+	}
+	return many
+}
@@ -0,0 +1,106 @@
+package main
+
+import (
+	"encoding/json"
+	"errors"
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+	"text/tabwriter"
+
+	"github.com/andrinoff/cambridge-lang/pkg/testrunner"
+)
+
+// runGrade runs a set of test cases against every submission in a folder
+func runGrade(args []string) {
+	fs := flag.NewFlagSet("grade", flag.ExitOnError)
+	jobs := fs.Int("jobs", 0, "number of cases to run at once (default: one per CPU)")
+	testsDir := fs.String("tests", "tests", "folder of NAME.in/NAME.out cases")
+	asJSON := fs.Bool("json", false, "output the full results as JSON")
+	summary := fs.String("summary", "", "also write a summary of the class naming no one to `file`, as HTML or, for a .csv file, CSV")
+	var det determinism
+	det.addFlags(fs)
+	fileRoot := fs.String("file-root", "", "let OPENFILE use only files in `dir` (default: each submission's folder)")
+	var caps capabilities
+	caps.addFlags(fs)
+	fs.Parse(args)
+
+	if fs.NArg() < 1 {
+		fmt.Println("Usage: cambridge grade [--tests DIR] [--jobs N] [--json] [--summary file] [--deterministic] [--seed N] [--file-root dir] [--disable groups] <directory>")
+		os.Exit(1)
+	}
+
+	cases, err := testrunner.LoadCases(*testsDir)
+	if err != nil {
+		fatal("cannot load test cases: %v", err)
+	}
+	if len(cases) == 0 {
+		fatal("no test cases found in %s", *testsDir)
+	}
+
+	subs, err := readSubmissions(fs.Arg(0))
+	if err != nil {
+		fatal("%v", err)
+	}
+
+	disabled := caps.list()
+	var progs []*testrunner.Program
+	for _, sub := range subs {
+		prog := compile(sub.Name, sub.Text, includeNothing)
+		prog.Arithmetic = arithmetic()
+		prog.Builtins = det.builtins
+		// Submissions are untrusted, so they are always kept to a folder
+		prog.Files = sandbox{enabled: true, root: *fileRoot}.files(sub.Dir)
+		prog.Disabled = disabled
+		progs = append(progs, prog)
+	}
+
+	reports := testrunner.Grade(progs, cases, *jobs)
+	if *summary != "" {
+		if err := writeSummary(*summary, testrunner.Summarize(reports)); err != nil {
+			fatal("cannot write summary: %v", err)
+		}
+	}
+
+	if *asJSON {
+		out, err := json.MarshalIndent(reports, "", "  ")
+		if err != nil {
+			fatal("cannot encode results: %v", err)
+		}
+		fmt.Println(string(out))
+		return
+	}
+
+	w := tabwriter.NewWriter(os.Stdout, 0, 0, 2, ' ', 0)
+	fmt.Fprintln(w, "SUBMISSION\tPASSED\tSCORE")
+	for _, r := range reports {
+		fmt.Fprintf(w, "%s\t%d/%d\t%.0f%%\n", r.Name, r.Passed, r.Total, 100*float64(r.Passed)/float64(r.Total))
+	}
+	w.Flush()
+}
+
+// writeSummary writes s to path, as CSV if it ends in .csv and HTML if not
+func writeSummary(path string, s testrunner.Summary) error {
+	f, err := os.Create(path)
+	if err != nil {
+		return err
+	}
+	if strings.EqualFold(filepath.Ext(path), ".csv") {
+		err = s.WriteCSV(f)
+	} else {
+		err = s.WriteHTML(f)
+	}
+	if err != nil {
+		f.Close()
+		return err
+	}
+	return f.Close()
+}
+
+// includeNothing refuses every INCLUDE, as a submission is graded as one
+// file, the files of a folder joined together
+func includeNothing(name string) (string, error) {
+	return "", errors.New("grade reads each submission as a single file")
+}
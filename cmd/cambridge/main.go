@@ -7,17 +7,78 @@ import (
 	"fmt"
 	"io"
 	"os"
+	"path/filepath"
 	"strings"
 
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
 	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/cache"
+	"github.com/andrinoff/cambridge-lang/pkg/config"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/include"
 	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
 	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/logging"
 	"github.com/andrinoff/cambridge-lang/pkg/parser"
 )
 
 const VERSION = "0.2.0"
 
+// stderr and stdout render errors and warnings; the REPL reports to stdout
+var stderr, stdout *diagnostic.Renderer
+
+// settings holds the nearest cambridge.toml, or the defaults
+var settings *config.Config
+
+// programCache keeps parsed programs between runs, nil if caching is off
+var programCache = cache.NewDisk(cache.DefaultDir())
+
 func main() {
+	// Output options apply to every command, so strip them before dispatching
+	noColor := false
+	lang := ""
+	logOpts := logging.Options{}
+	args := []string{os.Args[0]}
+	for n := 1; n < len(os.Args); n++ {
+		arg := os.Args[n]
+		switch {
+		case arg == "--no-color" || arg == "-no-color":
+			noColor = true
+		case strings.HasPrefix(arg, "--lang="):
+			lang = strings.TrimPrefix(arg, "--lang=")
+		case arg == "--lang" && n+1 < len(os.Args):
+			n++
+			lang = os.Args[n]
+		case arg == "--verbose":
+			logOpts.Verbose = true
+		case strings.HasPrefix(arg, "--log-file="):
+			logOpts.File = strings.TrimPrefix(arg, "--log-file=")
+		case arg == "--log-file" && n+1 < len(os.Args):
+			n++
+			logOpts.File = os.Args[n]
+		default:
+			args = append(args, arg)
+		}
+	}
+	os.Args = args
+	stderr = diagnostic.NewRenderer(os.Stderr, diagnostic.ColorEnabled(os.Stderr, noColor))
+	stdout = diagnostic.NewRenderer(os.Stdout, diagnostic.ColorEnabled(os.Stdout, noColor))
+
+	closeLog, err := logging.Setup(logOpts)
+	if err != nil {
+		fatal("cannot set up logging: %v", err)
+	}
+	defer closeLog()
+
+	settings, err = config.Find(".")
+	if err != nil {
+		stderr.Render(diagnostic.Diagnostic{Severity: diagnostic.Warning, Message: err.Error()}, nil)
+		settings = config.Default()
+	}
+	lang = diagnostic.Language(lang, settings.Diagnostics.Locale)
+	stderr.SetLanguage(lang)
+	stdout.SetLanguage(lang)
+
 	if len(os.Args) < 2 {
 		// Start REPL
 		startREPL()
@@ -26,13 +87,37 @@ func main() {
 
 	switch os.Args[1] {
 	case "run":
-		if len(os.Args) < 3 {
-			fmt.Println("Usage: cambridge run <filename>")
-			os.Exit(1)
-		}
-		runFile(os.Args[2])
+		runCommand(os.Args[2:])
 	case "repl":
 		startREPL()
+	case "new":
+		runNew(os.Args[2:])
+	case "similarity":
+		runSimilarity(os.Args[2:])
+	case "metrics":
+		runMetrics(os.Args[2:])
+	case "callgraph":
+		runCallGraph(os.Args[2:])
+	case "convert":
+		runConvert(os.Args[2:])
+	case "fmt":
+		runFormat(os.Args[2:])
+	case "test":
+		runTest(os.Args[2:])
+	case "grade":
+		runGrade(os.Args[2:])
+	case "fuzz":
+		runFuzz(os.Args[2:])
+	case "tracetable":
+		runTraceTable(os.Args[2:])
+	case "replay":
+		runReplay(os.Args[2:])
+	case "reproduce":
+		runReproduce(os.Args[2:])
+	case "serve":
+		runServe(os.Args[2:])
+	case "self-update":
+		runSelfUpdate(os.Args[2:])
 	case "version":
 		fmt.Printf("Cambridge Pseudocode v%s\n", VERSION)
 		fmt.Println("Based on Cambridge International AS & A Level Computer Science 9618")
@@ -40,40 +125,65 @@ func main() {
 		printHelp()
 	default:
 		// Assume it's a filename
-		runFile(os.Args[1])
+		runFile(os.Args[1], runOptions{optimize: true, maxDepth: interpreter.DefaultMaxDepth})
 	}
 }
 
-func runFile(filename string) {
+// parseFile reads and parses a source file and the files it includes,
+// which read is given the names of, exiting on any error
+func parseFile(filename string, read func(name string) (string, error)) (*ast.Program, *include.Program) {
 	content, err := os.ReadFile(filename)
 	if err != nil {
-		fmt.Fprintf(os.Stderr, "Error reading file: %v\n", err)
-		os.Exit(1)
+		fatal("cannot read file: %v", err)
 	}
+	text := string(content)
 
-	l := lexer.New(string(content))
-	p := parser.New(l)
-	program := p.ParseProgram()
+	// Only programs in a single file are cached, as the key covers one
+	key := cache.Key(text)
+	if program, ok := programCache.Load(key); ok {
+		logger.Debug("reusing cached parse", "file", filename)
+		return program, include.Single(filename, text, program)
+	}
 
-	if len(p.Errors()) > 0 {
-		for _, err := range p.Errors() {
-			fmt.Fprintf(os.Stderr, "Parse error: %s\n", err)
+	loaded := include.Load(filename, text, read)
+	if !loaded.OK() {
+		for _, d := range loaded.Diagnostics {
+			stderr.Render(loaded.Attribute(d))
 		}
 		os.Exit(1)
 	}
 
-	interp := interpreter.New()
-	interp.SetBuiltins(builtins.GetBuiltins())
+	if len(loaded.Files) == 1 {
+		if err := programCache.Store(key, loaded.AST); err != nil {
+			logger.Debug("cannot cache parse", "file", filename, "error", err)
+		}
+	}
+	return loaded.AST, loaded
+}
 
-	result := interp.Eval(program)
-	if result != nil {
-		if err, ok := result.(*interpreter.Error); ok {
-			fmt.Fprintf(os.Stderr, "%s\n", err.Inspect())
-			os.Exit(1)
+// renderRuntimeError reports err, in whichever of the program's files it
+// happened, followed by the calls that led to it
+func renderRuntimeError(r *diagnostic.Renderer, err *interpreter.Error, files *include.Program) {
+	r.Render(files.Attribute(err.Diagnostic()))
+	for _, call := range err.Calls {
+		file, line := files.Locate(call.Line)
+		where := fmt.Sprintf("line %d", line)
+		if &file.Source != files.Main() {
+			where += " of " + file.Name
 		}
+		r.Render(diagnostic.Diagnostic{
+			Severity: diagnostic.Note,
+			Message:  fmt.Sprintf("in %s %s called from %s", call.Kind, call.Name, where),
+		}, nil)
 	}
 }
 
+// fatal reports an error that stops the command and exits
+func fatal(format string, args ...interface{}) {
+	stderr.Errorf(format, args...)
+	os.Exit(1)
+}
+
 func startREPL() {
 	fmt.Printf("Cambridge Pseudocode v%s\n", VERSION)
 	fmt.Println("Based on Cambridge International AS & A Level Computer Science 9618")
@@ -82,6 +192,7 @@ func startREPL() {
 	reader := bufio.NewReader(os.Stdin)
 	interp := interpreter.New()
 	interp.SetBuiltins(builtins.GetBuiltins())
+	interp.SetArithmetic(arithmetic())
 
 	var multilineBuffer strings.Builder
 	inMultiline := false
@@ -99,7 +210,7 @@ func startREPL() {
 				fmt.Println("\nGoodbye!")
 				return
 			}
-			fmt.Fprintf(os.Stderr, "Error reading input: %v\n", err)
+			stderr.Errorf("cannot read input: %v", err)
 			continue
 		}
 
@@ -119,6 +230,7 @@ func startREPL() {
 		if upperLine == "CLEAR" {
 			interp = interpreter.New()
 			interp.SetBuiltins(builtins.GetBuiltins())
+			interp.SetArithmetic(arithmetic())
 			fmt.Println("Environment cleared.")
 			continue
 		}
@@ -153,18 +265,22 @@ func startREPL() {
 		p := parser.New(l)
 		program := p.ParseProgram()
 
-		if len(p.Errors()) > 0 {
-			for _, err := range p.Errors() {
-				fmt.Printf("Parse error: %s\n", err)
+		src := &diagnostic.Source{Text: line}
+		if len(p.Diagnostics()) > 0 {
+			for _, d := range p.Diagnostics() {
+				stdout.Render(d, src)
 			}
 			continue
 		}
 
+		interpreter.Resolve(program)
 		result := interp.Eval(program)
-		if result != nil {
-			if _, ok := result.(*interpreter.Null); !ok {
-				fmt.Println(result.Inspect())
-			}
+		switch result := result.(type) {
+		case nil, *interpreter.Null:
+		case *interpreter.Error:
+			renderRuntimeError(stdout, result, include.Single(src.Name, src.Text, program))
+		default:
+			fmt.Println(result.Inspect())
 		}
 	}
 }
@@ -202,26 +318,105 @@ func printHelp() {
 Usage:
   cambridge [command] [arguments]
 
+Options:
+  --no-color    Print diagnostics without colour (also set by NO_COLOR)
+  --lang <code> Language for error messages: en, es or fr
+                (defaults to cambridge.toml, then CAMBRIDGE_LANG or the locale)
+  --verbose     Log lexer, parser and interpreter activity to stderr
+                (or set CAMBRIDGE_LOG=debug|info|warn|error)
+  --log-file <path>
+                Write the log to a file (also CAMBRIDGE_LOG_FILE)
+
 Commands:
-  run <file>    Run a pseudocode file
+  run <file>    Run a pseudocode file (--vm to use the bytecode VM,
+                --emit-bytecode to print the compiled instructions,
+                --no-optimize to skip constant folding,
+                --max-depth N to limit recursion, 50000 by default,
+                --tail-calls to run self-recursive tail calls in one frame,
+                --bigint for INTEGER values beyond 64 bits,
+                --deterministic or --seed N for repeatable RAND and TODAY,
+                --sandbox or --file-root <dir> to keep OPENFILE and
+                INCLUDE to a folder,
+                --disable files,random,input to turn those off,
+                --trace <file> to record every step for replay,
+                --transcript <file> to record input and output for reproduce,
+                --call <name> to run one PROCEDURE on its own)
   repl          Start interactive REPL
+  new <name>    Create a starter project folder
+  similarity <dir>
+                Report pairwise similarity between submissions
+  metrics <file>
+                Show complexity metrics for each routine
+  callgraph <file>
+                Print the call graph as DOT or JSON
+  convert <file>
+                Rewrite operators between Unicode (←, ≠) and ASCII (<-, <>)
+  fmt <file>    Indent a file in the house style (-w to rewrite it in place)
+  test [file]   Run the project's tests/*.in and *.out cases
+                (--deterministic, --seed N, --sandbox, --file-root and
+                --disable as for run)
+  grade <dir>   Run test cases against every submission in a folder, each
+                kept to its own folder's files unless --file-root is given
+                (--deterministic, --seed N and --disable as for run;
+                --summary <file> for pass rates, common errors and times
+                per case across the class, as HTML or .csv)
+  fuzz <file>   Run a program on random input, reporting crashes, runs
+                still going after --steps N or --timeout D, and results
+                that do not make --assert <expression> TRUE
+                (--input NAME=LOW..HIGH, string:LOW..HIGH or A|B|C sets
+                what INPUT reads into NAME; --runs N, --seed N)
+  tracetable <file>
+                Write trace table exercises from a program, a blank worksheet
+                and its answers for each of --variants N, giving the constants
+                named by --vary NAME=RANGE and the input new values each time
+                (--input, --seed N and --steps N as for fuzz; --out <dir>)
+  replay <trace>
+                Show a run recorded with run --trace, without running it
+                (--step to step through it, back as well as forward)
+  reproduce <transcript>
+                Check a transcript recorded with run --transcript has not
+                been changed, and run its program again on its input to
+                show it reads and writes the same lines
+  serve         Serve a web page for writing and running programs, to the
+                network on --port N, 8080 by default (--host <address> to
+                listen on one; --steps N to stop a program after N
+                statements, --size N to limit strings and arrays to N
+                characters or elements, --max-depth N to limit recursion,
+                1000 by default, --runs N to limit how many run at once)
+  self-update   Install the latest release (--lsp for the language server too)
   version       Show version information
   help          Show this help message
 
 Examples:
   cambridge run program.pseudo
+  cambridge run --vm sort.cam
   cambridge repl
+  cambridge new coursework
 
 File Extensions:
-  .pseudo, .cambridge, .cam, .txt
+  .pseudo, .cambridge, .cam, .psc, .txt
 
 For more information, visit:
   https://github.com/andrinoff/cambridge-lang`)
 }
 
+// isSourceFile reports whether name has a pseudocode file extension
+func isSourceFile(name string) bool {
+	switch strings.ToLower(filepath.Ext(name)) {
+	case ".pseudo", ".cambridge", ".cam", ".psc", ".txt":
+		return true
+	}
+	return false
+}
+
 func printREPLHelp() {
 	fmt.Printf(`
-REPL Commands:
+REPL Options:
+  --no-color    Print diagnostics without colour (also set by NO_COLOR)
+  --lang <code> Language for error messages: en, es or fr
+                (defaults to cambridge.toml, then CAMBRIDGE_LANG or the locale)
+
+Commands:
   EXIT, QUIT    Exit the REPL
   HELP          Show this help
   CLEAR         Clear the environment
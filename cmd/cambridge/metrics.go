@@ -0,0 +1,44 @@
+package main
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"os"
+	"text/tabwriter"
+
+	"github.com/andrinoff/cambridge-lang/pkg/include"
+	"github.com/andrinoff/cambridge-lang/pkg/metrics"
+)
+
+// runMetrics prints per-routine complexity metrics as a table or JSON
+func runMetrics(args []string) {
+	fs := flag.NewFlagSet("metrics", flag.ExitOnError)
+	asJSON := fs.Bool("json", false, "output metrics as JSON")
+	fs.Parse(args)
+
+	if fs.NArg() < 1 {
+		fmt.Println("Usage: cambridge metrics [--json] <filename>")
+		os.Exit(1)
+	}
+
+	program, _ := parseFile(fs.Arg(0), include.ReadFile)
+	routines := metrics.Analyze(program)
+
+	if *asJSON {
+		out, err := json.MarshalIndent(routines, "", "  ")
+		if err != nil {
+			fatal("cannot encode metrics: %v", err)
+		}
+		fmt.Println(string(out))
+		return
+	}
+
+	w := tabwriter.NewWriter(os.Stdout, 0, 0, 2, ' ', 0)
+	fmt.Fprintln(w, "ROUTINE\tKIND\tLINE\tSTATEMENTS\tCYCLOMATIC\tNESTING\tPARAMS")
+	for _, r := range routines {
+		fmt.Fprintf(w, "%s\t%s\t%d\t%d\t%d\t%d\t%d\n",
+			r.Name, r.Kind, r.Line, r.Statements, r.Cyclomatic, r.Nesting, r.Parameters)
+	}
+	w.Flush()
+}
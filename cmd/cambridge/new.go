@@ -0,0 +1,27 @@
+package main
+
+import (
+	"fmt"
+	"os"
+	"path/filepath"
+
+	"github.com/andrinoff/cambridge-lang/pkg/scaffold"
+)
+
+// runNew creates a starter project folder
+func runNew(args []string) {
+	if len(args) < 1 {
+		fmt.Println("Usage: cambridge new <project-name>")
+		os.Exit(1)
+	}
+
+	dir := args[0]
+	if err := scaffold.Create(dir); err != nil {
+		fatal("cannot create project: %v", err)
+	}
+
+	fmt.Printf("Created project %s\n\n", filepath.Base(filepath.Clean(dir)))
+	fmt.Println("Next steps:")
+	fmt.Printf("  cd %s\n", dir)
+	fmt.Printf("  cambridge run %s\n", scaffold.EntryFile)
+}
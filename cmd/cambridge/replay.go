@@ -0,0 +1,127 @@
+package main
+
+import (
+	"bufio"
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"sort"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/trace"
+)
+
+// runReplay shows a trace recorded by run --trace, all at once or step by
+// step in either direction
+func runReplay(args []string) {
+	fs := flag.NewFlagSet("replay", flag.ExitOnError)
+	pause := fs.Bool("step", false, "step through the trace interactively, forwards and back")
+	fs.Usage = func() {
+		fmt.Fprintln(os.Stderr, "Usage: cambridge replay [--step] <trace>")
+		fs.PrintDefaults()
+	}
+	fs.Parse(args)
+
+	if fs.NArg() != 1 {
+		fs.Usage()
+		os.Exit(1)
+	}
+	file, err := os.Open(fs.Arg(0))
+	if err != nil {
+		fatal("cannot open trace: %v", err)
+	}
+	defer file.Close()
+	t, err := trace.Read(file)
+	if err != nil {
+		fatal("cannot read %s: %v", fs.Arg(0), err)
+	}
+
+	lines := strings.Split(t.Source, "\n")
+	fmt.Printf("trace of %s, %d steps\n", t.Program, len(t.Events))
+	c := t.Cursor()
+	if !*pause {
+		for c.Next() {
+			showEvent(os.Stdout, c, lines)
+		}
+		return
+	}
+
+	fmt.Println("Enter or n: next step, b: back a step, w NAME: back to where NAME was last set, v: all variables, q: quit")
+	keys := bufio.NewScanner(os.Stdin)
+	c.Next()
+	showEvent(os.Stdout, c, lines)
+	for {
+		fmt.Print("> ")
+		if !keys.Scan() {
+			return
+		}
+		command, arg, _ := strings.Cut(strings.TrimSpace(keys.Text()), " ")
+		switch strings.ToLower(command) {
+		case "", "n":
+			if !c.Next() {
+				fmt.Println("at the last step")
+				continue
+			}
+		case "b":
+			if !c.Back() {
+				fmt.Println("at the first step")
+				continue
+			}
+		case "w":
+			if !c.LastChange(strings.TrimSpace(arg)) {
+				fmt.Printf("%s has not been set by this step\n", strings.TrimSpace(arg))
+				continue
+			}
+		case "v":
+			showValues(os.Stdout, c.Values())
+			continue
+		case "q":
+			return
+		default:
+			fmt.Printf("unknown command %q\n", command)
+			continue
+		}
+		showEvent(os.Stdout, c, lines)
+	}
+}
+
+// showEvent prints the current event of c under the source line that ran
+func showEvent(w io.Writer, c *trace.Cursor, lines []string) {
+	event, ok := c.Event()
+	if !ok {
+		return
+	}
+	source := ""
+	if event.Line > 0 && event.Line <= len(lines) {
+		source = strings.TrimSpace(lines[event.Line-1])
+	}
+	fmt.Fprintf(w, "\nstep %d\n%4d | %s\n", c.Position()+1, event.Line, source)
+	if len(event.Calls) > 0 {
+		fmt.Fprintf(w, "       in %s\n", strings.Join(event.Calls, " > "))
+	}
+	for _, line := range event.Input {
+		fmt.Fprintf(w, "       input  %s\n", line)
+	}
+	showValues(w, event.Changed)
+	for _, line := range strings.SplitAfter(event.Output, "\n") {
+		if line != "" {
+			fmt.Fprintf(w, "       output %s", line)
+		}
+	}
+	if event.Error != "" {
+		fmt.Fprintf(w, "       error  %s\n", event.Error)
+	}
+}
+
+// showValues prints variables in name order
+func showValues(w io.Writer, values map[string]string) {
+	names := make([]string, 0, len(values))
+	for name := range values {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+	for _, name := range names {
+		fmt.Fprintf(w, "       %s = %s\n", name, values[name])
+	}
+}
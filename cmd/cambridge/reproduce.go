@@ -0,0 +1,90 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+	"github.com/andrinoff/cambridge-lang/pkg/transcript"
+)
+
+// runReproduce checks a transcript recorded by run --transcript, then runs
+// the program it holds again on the same input, reporting whether it reads
+// and writes exactly what was recorded
+func runReproduce(args []string) {
+	fs := flag.NewFlagSet("reproduce", flag.ExitOnError)
+	var sb sandbox
+	sb.addFlags(fs)
+	fs.Usage = func() {
+		fmt.Fprintln(os.Stderr, "Usage: cambridge reproduce [--sandbox | --file-root dir] <transcript>")
+		fs.PrintDefaults()
+	}
+	fs.Parse(args)
+
+	if fs.NArg() != 1 {
+		fs.Usage()
+		os.Exit(1)
+	}
+	path := fs.Arg(0)
+	file, err := os.Open(path)
+	if err != nil {
+		fatal("cannot open transcript: %v", err)
+	}
+	recorded, err := transcript.Read(file)
+	file.Close()
+	if err != nil {
+		fatal("cannot read %s: %v", path, err)
+	}
+	fmt.Printf("%s: %s, run of %s, is as recorded\n", path, recorded.Hash, recorded.Program)
+
+	p := parser.New(lexer.New(recorded.Source))
+	program := p.ParseProgram()
+	if errs := p.Errors(); len(errs) > 0 {
+		fatal("the recorded program does not parse: %s", errs[0])
+	}
+	interpreter.Resolve(program)
+	interp := interpreter.New()
+	if recorded.Seed != nil {
+		interp.SetBuiltins(builtins.Deterministic(*recorded.Seed))
+	} else {
+		interp.SetBuiltins(builtins.GetBuiltins())
+	}
+	input := ""
+	if lines := recorded.Inputs(); len(lines) > 0 {
+		input = strings.Join(lines, "\n") + "\n"
+	}
+	interp.SetInput(strings.NewReader(input))
+	interp.SetOutput(io.Discard)
+	interp.SetArithmetic(arithmetic())
+	interp.SetFiles(sb.files(filepath.Dir(path)))
+	replayed := transcript.New(recorded.Program, recorded.Source)
+	interp.SetTranscript(replayed)
+	interp.Eval(program)
+
+	n := transcript.Diff(recorded.Events, replayed.Events)
+	if n < 0 {
+		fmt.Printf("%s: run again, it reads and writes the same %d %s\n", path, len(recorded.Events), plural(len(recorded.Events), "line", "lines"))
+		return
+	}
+	fmt.Printf("%s: run again, it differs at line %d of the transcript\n", path, n+1)
+	fmt.Printf("  recorded: %s\n", describeEvent(recorded.Events, n))
+	fmt.Printf("  now:      %s\n", describeEvent(replayed.Events, n))
+	if recorded.Seed == nil {
+		fmt.Println("  (the run was not recorded with --deterministic, so RAND, RANDOM and TODAY may differ)")
+	}
+	os.Exit(1)
+}
+
+func describeEvent(events []transcript.Event, n int) string {
+	if n >= len(events) {
+		return "nothing more"
+	}
+	return fmt.Sprintf("%s %q", strings.ToUpper(events[n].Kind), events[n].Text)
+}
@@ -0,0 +1,352 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"runtime/debug"
+	"strconv"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/config"
+	"github.com/andrinoff/cambridge-lang/pkg/include"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/logging"
+	"github.com/andrinoff/cambridge-lang/pkg/optimize"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+	"github.com/andrinoff/cambridge-lang/pkg/trace"
+	"github.com/andrinoff/cambridge-lang/pkg/transcript"
+	"github.com/andrinoff/cambridge-lang/pkg/vm"
+)
+
+var logger = logging.For("cli")
+
+// runOptions selects how a program is executed
+type runOptions struct {
+	vm           bool
+	emitBytecode bool
+	optimize     bool
+	maxDepth     int
+	tailCalls    bool
+	bigIntegers  bool
+	determinism  determinism
+	sandbox      sandbox
+	capabilities capabilities
+	trace        string // file to record the run's steps in
+	transcript   string // file to record the run's input and output in
+	call         string // procedure to run instead of the main program
+}
+
+// determinism holds the --deterministic and --seed flags
+type determinism struct {
+	enabled bool
+	seed    int64
+}
+
+// addFlags registers the flags on a command that runs programs
+// Giving a seed implies --deterministic, as it would otherwise go unused.
+func (d *determinism) addFlags(fs *flag.FlagSet) {
+	d.seed = 1
+	fs.BoolVar(&d.enabled, "deterministic", false, "seed RAND and RANDOM and freeze TODAY, so every run gives the same output")
+	fs.Func("seed", "run as --deterministic does, seeding RAND and RANDOM with `N` rather than 1", func(value string) error {
+		seed, err := strconv.ParseInt(value, 10, 64)
+		if err != nil {
+			return err
+		}
+		d.seed, d.enabled = seed, true
+		return nil
+	})
+}
+
+// sandbox holds the --sandbox and --file-root flags
+type sandbox struct {
+	enabled bool
+	root    string
+}
+
+// addFlags registers the flags on a command that runs programs
+func (s *sandbox) addFlags(fs *flag.FlagSet) {
+	fs.BoolVar(&s.enabled, "sandbox", false, "let OPENFILE and INCLUDE use only files in the program's folder, refusing absolute paths and .. out of it")
+	fs.StringVar(&s.root, "file-root", "", "let OPENFILE and INCLUDE use only files in `dir` (implies --sandbox)")
+}
+
+// files returns where OPENFILE looks for a program in dir
+func (s sandbox) files(dir string) interpreter.FileSystem {
+	if !s.enabled && s.root == "" {
+		return interpreter.OSFiles{}
+	}
+	if s.root != "" {
+		dir = s.root
+	}
+	return interpreter.SandboxFiles{Root: dir}
+}
+
+// includes returns how INCLUDE reads the files of a program in dir, kept to
+// the same folder as OPENFILE
+func (s sandbox) includes(dir string) func(name string) (string, error) {
+	if !s.enabled && s.root == "" {
+		return include.ReadFile
+	}
+	if s.root != "" {
+		dir = s.root
+	}
+	return include.ReadSandboxed(dir)
+}
+
+// capabilities holds the --disable flag
+type capabilities struct {
+	disable string
+}
+
+// addFlags registers the flag on a command that runs programs
+func (c *capabilities) addFlags(fs *flag.FlagSet) {
+	fs.StringVar(&c.disable, "disable", "", "turn off `groups` of statements and builtins: any of files, random and input, separated by commas")
+}
+
+// list returns the capabilities turned off by the flag or cambridge.toml
+func (c capabilities) list() []interpreter.Capability {
+	names := settings.Capabilities.Disabled
+	if c.disable != "" {
+		names = append(names[:len(names):len(names)], strings.Split(c.disable, ",")...)
+	}
+	var caps []interpreter.Capability
+	for _, name := range names {
+		capability, err := interpreter.ParseCapability(strings.TrimSpace(name))
+		if err != nil {
+			fatal("%v", err)
+		}
+		caps = append(caps, capability)
+	}
+	return caps
+}
+
+// builtins returns a new set of builtin functions for one run
+func (d determinism) builtins() map[string]*interpreter.Builtin {
+	if d.enabled {
+		return builtins.Deterministic(d.seed)
+	}
+	return builtins.GetBuiltins()
+}
+
+// runCommand parses the flags of the run command
+func runCommand(args []string) {
+	fs := flag.NewFlagSet("run", flag.ExitOnError)
+	useVM := fs.Bool("vm", false, "compile to bytecode and run on the VM, falling back to the interpreter for unsupported programs")
+	emitBytecode := fs.Bool("emit-bytecode", false, "print the compiled bytecode with source line annotations instead of running")
+	noOptimize := fs.Bool("no-optimize", false, "run the program exactly as written, without folding constants or removing dead branches")
+	maxDepth := fs.Int("max-depth", interpreter.DefaultMaxDepth, "stop the program when routine calls nest deeper than this")
+	bigInt := fs.Bool("bigint", false, "let INTEGER values grow past 64 bits instead of stopping with an overflow error")
+	var det determinism
+	det.addFlags(fs)
+	var sb sandbox
+	sb.addFlags(fs)
+	var caps capabilities
+	caps.addFlags(fs)
+	tailCalls := fs.Bool("tail-calls", false, "reuse the frame when a routine ends by calling itself, so deep tail recursion does not hit --max-depth")
+	tracePath := fs.String("trace", "", "record every step, with the variables it changed and its input and output, to `file` for cambridge replay")
+	transcriptPath := fs.String("transcript", "", "record every line read and written, in order and with a hash, to `file` for cambridge reproduce")
+	call := fs.String("call", "", "run only the declarations and then the PROCEDURE `name`, which must take no parameters, instead of the main program")
+	fs.Usage = func() {
+		fmt.Fprintln(os.Stderr, "Usage: cambridge run [--vm] [--emit-bytecode] [--no-optimize] [--max-depth N] [--tail-calls] [--bigint] [--deterministic] [--seed N] [--sandbox | --file-root dir] [--disable groups] [--trace file] [--transcript file] [--call name] <filename>")
+		fs.PrintDefaults()
+	}
+	fs.Parse(args)
+
+	if fs.NArg() != 1 || *maxDepth < 1 {
+		fs.Usage()
+		os.Exit(1)
+	}
+	runFile(fs.Arg(0), runOptions{
+		vm:           *useVM,
+		emitBytecode: *emitBytecode,
+		optimize:     !*noOptimize,
+		maxDepth:     *maxDepth,
+		tailCalls:    *tailCalls,
+		bigIntegers:  *bigInt,
+		determinism:  det,
+		sandbox:      sb,
+		capabilities: caps,
+		trace:        *tracePath,
+		transcript:   *transcriptPath,
+		call:         *call,
+	})
+}
+
+// reserveStack lets the Go stack grow far enough for depth nested routine
+// calls, so that a --max-depth above the default, which Go's own limit
+// already fits, rather than the Go runtime stops a runaway recursion
+func reserveStack(depth int) {
+	need := interpreter.HostStack(depth)
+	if previous := debug.SetMaxStack(need); previous > need {
+		debug.SetMaxStack(previous)
+	}
+}
+
+func runFile(filename string, opts runOptions) {
+	program, files := parseFile(filename, opts.sandbox.includes(filepath.Dir(filename)))
+	src := files.Main()
+	if opts.call != "" {
+		program = callOnly(program, opts.call)
+	}
+	if opts.optimize {
+		optimize.Program(program)
+	}
+	builtinFns := opts.determinism.builtins()
+	arith := arithmetic()
+	arith.BigIntegers = opts.bigIntegers
+	disabled := opts.capabilities.list()
+
+	if opts.emitBytecode {
+		bytecode, err := vm.Compile(program, builtinFns)
+		if err != nil {
+			fatal("cannot compile to bytecode: %v", err)
+		}
+		vm.Disassemble(os.Stdout, bytecode, files.Text())
+		return
+	}
+
+	if opts.vm && opts.tailCalls {
+		logger.Info("running on the interpreter instead", "reason", "the VM does not reuse frames for tail calls")
+	} else if opts.vm && opts.trace != "" {
+		logger.Info("running on the interpreter instead", "reason", "the VM does not report steps for a trace")
+	} else if opts.vm && opts.transcript != "" {
+		logger.Info("running on the interpreter instead", "reason", "the VM does not report input and output for a transcript")
+	} else if opts.vm && len(disabled) > 0 {
+		logger.Info("running on the interpreter instead", "reason", "the VM cannot disable capabilities")
+	} else if opts.vm {
+		bytecode, err := vm.Compile(program, builtinFns)
+		if err == nil {
+			machine := vm.New(bytecode)
+			machine.SetBuiltins(builtinFns)
+			machine.SetMaxDepth(opts.maxDepth)
+			machine.SetArithmetic(arith)
+			if err := machine.Run(); err != nil {
+				renderRuntimeError(stderr, err, files)
+				os.Exit(1)
+			}
+			return
+		}
+		logger.Info("running on the interpreter instead", "reason", err)
+	}
+
+	interpreter.Resolve(program)
+	reserveStack(opts.maxDepth)
+	interp := interpreter.New()
+	interp.SetBuiltins(builtinFns)
+	interp.SetMaxDepth(opts.maxDepth)
+	interp.SetTailCalls(opts.tailCalls)
+	interp.SetArithmetic(arith)
+	interp.SetFiles(opts.sandbox.files(filepath.Dir(filename)))
+	interp.Disable(disabled...)
+	var log *transcript.Transcript
+	if opts.transcript != "" {
+		if len(files.Files) > 1 {
+			fatal("cannot record a transcript of a program that includes other files, as it keeps only %s to run again", src.Name)
+		}
+		log = transcript.New(src.Name, src.Text)
+		if opts.determinism.enabled {
+			log.Seed = &opts.determinism.seed
+		}
+		interp.SetTranscript(log)
+	}
+
+	var result interpreter.Object
+	if opts.trace != "" {
+		result = traceRun(interp, program, files, opts.trace)
+	} else {
+		result = interp.Eval(program)
+	}
+	if log != nil {
+		if err := writeTranscript(opts.transcript, log); err != nil {
+			fatal("cannot write transcript: %v", err)
+		}
+	}
+	if result != nil {
+		if err, ok := result.(*interpreter.Error); ok {
+			renderRuntimeError(stderr, err, files)
+			os.Exit(1)
+		}
+	}
+}
+
+// callOnly returns program with its main statements replaced by a call to
+// the procedure name, keeping the declarations the procedure may use
+func callOnly(program *ast.Program, name string) *ast.Program {
+	var proc *ast.ProcedureStatement
+	only := &ast.Program{}
+	for _, stmt := range program.Statements {
+		switch stmt := stmt.(type) {
+		case *ast.ProcedureStatement:
+			if stmt.Name == name {
+				proc = stmt
+			}
+			only.Statements = append(only.Statements, stmt)
+		case *ast.FunctionStatement, *ast.TypeStatement, *ast.ClassStatement, *ast.DeclareStatement, *ast.ConstantStatement:
+			only.Statements = append(only.Statements, stmt)
+		}
+	}
+	if proc == nil {
+		fatal("there is no PROCEDURE %s to call", name)
+	}
+	if len(proc.Parameters) > 0 {
+		fatal("cannot call %s on its own, as it takes parameters", name)
+	}
+	line, column := proc.Token.Line, proc.Token.Column
+	only.Statements = append(only.Statements, &ast.CallStatement{
+		Token: token.Token{Type: token.CALL, Literal: "CALL", Line: line, Column: column},
+		Name:  &ast.Identifier{Token: token.Token{Type: token.IDENT, Literal: name, Line: line, Column: column}, Value: name},
+	})
+	return only
+}
+
+// traceRun runs program one step at a time, recording each step to path,
+// and returns the error it stopped with, if any
+// Lines of included files are shown as they are numbered in the program.
+func traceRun(interp *interpreter.Interpreter, program *ast.Program, files *include.Program, path string) interpreter.Object {
+	file, err := os.Create(path)
+	if err != nil {
+		fatal("cannot create trace: %v", err)
+	}
+	w, err := trace.NewWriter(file, files.Main().Name, files.Text())
+	if err != nil {
+		fatal("cannot write trace: %v", err)
+	}
+
+	var result interpreter.Object
+	for step := range interp.Steps(program) {
+		if err := w.Record(step); err != nil {
+			fatal("cannot write trace: %v", err)
+		}
+		if step.Err != nil {
+			result = step.Err
+		}
+	}
+	if err := w.Close(); err != nil {
+		fatal("cannot write trace: %v", err)
+	}
+	if err := file.Close(); err != nil {
+		fatal("cannot write trace: %v", err)
+	}
+	return result
+}
+
+// writeTranscript writes t to path
+func writeTranscript(path string, t *transcript.Transcript) error {
+	file, err := os.Create(path)
+	if err != nil {
+		return err
+	}
+	if err := t.Write(file); err != nil {
+		file.Close()
+		return err
+	}
+	return file.Close()
+}
+
+// arithmetic returns the number semantics selected in cambridge.toml
+func arithmetic() interpreter.Arithmetic {
+	return interpreter.Arithmetic{DecimalReals: settings.Language.Real == config.DecimalReals}
+}
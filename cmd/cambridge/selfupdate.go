@@ -0,0 +1,67 @@
+package main
+
+import (
+	"context"
+	"flag"
+	"fmt"
+	"os"
+	"os/exec"
+	"path/filepath"
+
+	"github.com/andrinoff/cambridge-lang/pkg/selfupdate"
+)
+
+// runSelfUpdate replaces this binary, and optionally the language server,
+// with the latest release
+func runSelfUpdate(args []string) {
+	fs := flag.NewFlagSet("self-update", flag.ExitOnError)
+	check := fs.Bool("check", false, "only report whether an update is available")
+	withLSP := fs.Bool("lsp", false, "also update cambridge-lsp")
+	force := fs.Bool("force", false, "reinstall even if already up to date")
+	fs.Parse(args)
+
+	ctx := context.Background()
+	u := selfupdate.New()
+
+	release, err := u.Latest(ctx)
+	if err != nil {
+		fatal("cannot check for updates: %v", err)
+	}
+
+	if !selfupdate.Newer(VERSION, release.Version()) && !*force {
+		fmt.Printf("cambridge v%s is up to date\n", VERSION)
+		return
+	}
+	if *check {
+		fmt.Printf("Update available: v%s -> v%s\n", VERSION, release.Version())
+		return
+	}
+
+	exe, err := os.Executable()
+	if err != nil {
+		fatal("cannot locate the running binary: %v", err)
+	}
+	if exe, err = filepath.EvalSymlinks(exe); err != nil {
+		fatal("cannot locate the running binary: %v", err)
+	}
+
+	if err := u.Install(ctx, release, "cambridge", exe); err != nil {
+		fatal("cannot update cambridge: %v", err)
+	}
+	fmt.Printf("Updated cambridge to v%s\n", release.Version())
+
+	if !*withLSP {
+		return
+	}
+	lsp, err := exec.LookPath("cambridge-lsp")
+	if err != nil {
+		fatal("cannot find cambridge-lsp on PATH: %v", err)
+	}
+	if lsp, err = filepath.EvalSymlinks(lsp); err != nil {
+		fatal("cannot locate cambridge-lsp: %v", err)
+	}
+	if err := u.Install(ctx, release, "cambridge-lsp", lsp); err != nil {
+		fatal("cannot update cambridge-lsp: %v", err)
+	}
+	fmt.Printf("Updated cambridge-lsp to v%s\n", release.Version())
+}
@@ -0,0 +1,48 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"net"
+	"net/http"
+	"os"
+	"strconv"
+
+	"github.com/andrinoff/cambridge-lang/pkg/playground"
+)
+
+// runServe serves the playground, a web page for writing and running
+// programs, to the machines on the network
+func runServe(args []string) {
+	fs := flag.NewFlagSet("serve", flag.ExitOnError)
+	host := fs.String("host", "", "the address to listen on, every interface if empty so the rest of the class can connect")
+	port := fs.Int("port", 8080, "the port to listen on")
+	steps := fs.Int("steps", playground.DefaultSteps, "stop a program still running after this many statements")
+	size := fs.Int("size", playground.DefaultSize, "the most characters in a STRING, or elements in an ARRAY, a program may make")
+	maxDepth := fs.Int("max-depth", playground.DefaultDepth, "stop a program when routine calls nest deeper than this")
+	runs := fs.Int("runs", 0, "how many programs may run at once, the others waiting (one per CPU if 0)")
+	fs.Usage = func() {
+		fmt.Fprintln(os.Stderr, "Usage: cambridge serve [--host address] [--port N] [--steps N] [--size N] [--max-depth N] [--runs N]")
+		fs.PrintDefaults()
+	}
+	fs.Parse(args)
+
+	if fs.NArg() != 0 {
+		fs.Usage()
+		os.Exit(1)
+	}
+	addr := net.JoinHostPort(*host, strconv.Itoa(*port))
+	listener, err := net.Listen("tcp", addr)
+	if err != nil {
+		fatal("cannot listen on %s: %v", addr, err)
+	}
+	shown := *host
+	if shown == "" {
+		shown = "localhost"
+	}
+	fmt.Printf("Serving the playground at http://%s\n", net.JoinHostPort(shown, strconv.Itoa(*port)))
+	handler := playground.Handler(playground.Options{Steps: *steps, Size: *size, Depth: *maxDepth, Runs: *runs})
+	if err := http.Serve(listener, handler); err != nil {
+		fatal("%v", err)
+	}
+}
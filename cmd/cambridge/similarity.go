@@ -0,0 +1,128 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"sort"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/similarity"
+)
+
+// runSimilarity compares every submission in a directory against every other
+// Each entry is either a single source file or a folder of source files
+func runSimilarity(args []string) {
+	fs := flag.NewFlagSet("similarity", flag.ExitOnError)
+	threshold := fs.Float64("threshold", 0, "only report pairs at or above this percentage")
+	size := fs.Int("shingle", similarity.DefaultShingleSize, "number of consecutive tokens compared at once")
+	fs.Parse(args)
+
+	if fs.NArg() < 1 {
+		fmt.Println("Usage: cambridge similarity [--threshold N] <directory>")
+		os.Exit(1)
+	}
+	dir := fs.Arg(0)
+
+	sources, err := readSubmissions(dir)
+	if err != nil {
+		fatal("%v", err)
+	}
+
+	var subs []similarity.Submission
+	for _, src := range sources {
+		subs = append(subs, similarity.NewSubmission(src.Name, src.Text))
+	}
+
+	if len(subs) < 2 {
+		fmt.Println("Need at least two submissions to compare.")
+		return
+	}
+
+	pairs := similarity.Compare(subs, *size)
+	fmt.Printf("Similarity report for %d submissions (%d pairs)\n\n", len(subs), len(pairs))
+
+	for _, pair := range pairs {
+		percent := pair.Score * 100
+		if percent < *threshold {
+			continue
+		}
+		fmt.Printf("%6.1f%%  %s  <->  %s\n", percent, pair.A, pair.B)
+	}
+}
+
+// submission is the source code handed in by one student
+type submission struct {
+	Name string
+	Text string
+	Dir  string // the submission's own folder, or the one holding its file
+}
+
+// readSubmissions reads every submission in dir, in name order
+// Each entry is either a single source file or a folder of source files;
+// anything else, and empty submissions, are skipped.
+func readSubmissions(dir string) ([]submission, error) {
+	entries, err := os.ReadDir(dir)
+	if err != nil {
+		return nil, fmt.Errorf("cannot read directory: %v", err)
+	}
+
+	var subs []submission
+	for _, entry := range entries {
+		path := filepath.Join(dir, entry.Name())
+
+		var source string
+		subDir := dir
+		if entry.IsDir() {
+			subDir = path
+			source, err = readSourceTree(path)
+		} else if isSourceFile(entry.Name()) {
+			var content []byte
+			content, err = os.ReadFile(path)
+			source = string(content)
+		} else {
+			continue
+		}
+
+		if err != nil {
+			return nil, fmt.Errorf("cannot read %s: %v", path, err)
+		}
+		if strings.TrimSpace(source) == "" {
+			continue
+		}
+
+		subs = append(subs, submission{Name: entry.Name(), Text: source, Dir: subDir})
+	}
+	return subs, nil
+}
+
+// readSourceTree concatenates every source file under dir in a stable order
+func readSourceTree(dir string) (string, error) {
+	var files []string
+	err := filepath.WalkDir(dir, func(path string, d os.DirEntry, err error) error {
+		if err != nil {
+			return err
+		}
+		if !d.IsDir() && isSourceFile(d.Name()) {
+			files = append(files, path)
+		}
+		return nil
+	})
+	if err != nil {
+		return "", err
+	}
+
+	sort.Strings(files)
+
+	var out strings.Builder
+	for _, file := range files {
+		content, err := os.ReadFile(file)
+		if err != nil {
+			return "", err
+		}
+		out.Write(content)
+		out.WriteString("\n")
+	}
+	return out.String(), nil
+}
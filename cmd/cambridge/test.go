@@ -0,0 +1,118 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+	"time"
+
+	"github.com/andrinoff/cambridge-lang/pkg/cache"
+	"github.com/andrinoff/cambridge-lang/pkg/include"
+	"github.com/andrinoff/cambridge-lang/pkg/scaffold"
+	"github.com/andrinoff/cambridge-lang/pkg/testrunner"
+)
+
+// runTest runs a program against the project's recorded test cases
+func runTest(args []string) {
+	fs := flag.NewFlagSet("test", flag.ExitOnError)
+	jobs := fs.Int("jobs", 0, "number of cases to run at once (default: one per CPU)")
+	testsDir := fs.String("tests", "", "folder of NAME.in/NAME.out cases (default: tests/ in the project)")
+	var det determinism
+	det.addFlags(fs)
+	var sb sandbox
+	sb.addFlags(fs)
+	var caps capabilities
+	caps.addFlags(fs)
+	fs.Parse(args)
+
+	root := "."
+	if settings.Path != "" {
+		root = filepath.Dir(settings.Path)
+	}
+
+	entry := fs.Arg(0)
+	if entry == "" {
+		entry = settings.Project.Entry
+		if entry == "" {
+			entry = scaffold.EntryFile
+		}
+		entry = filepath.Join(root, entry)
+	}
+	if *testsDir == "" {
+		*testsDir = filepath.Join(root, "tests")
+	}
+
+	content, err := os.ReadFile(entry)
+	if err != nil {
+		fatal("cannot read file: %v", err)
+	}
+	cases, err := testrunner.LoadCases(*testsDir)
+	if err != nil {
+		fatal("cannot load test cases: %v", err)
+	}
+	if len(cases) == 0 {
+		fatal("no test cases found in %s", *testsDir)
+	}
+
+	prog := compile(entry, string(content), sb.includes(filepath.Dir(entry)))
+	prog.Arithmetic = arithmetic()
+	prog.Builtins = det.builtins
+	prog.Files = sb.files(filepath.Dir(entry))
+	prog.Disabled = caps.list()
+	results := testrunner.Run(prog, cases, *jobs)
+
+	failed := 0
+	for _, r := range results {
+		if r.Passed {
+			fmt.Printf("PASS  %s (%s)\n", r.Case, r.Duration.Round(time.Microsecond))
+			continue
+		}
+
+		failed++
+		fmt.Printf("FAIL  %s\n", r.Case)
+		if r.Error != "" {
+			fmt.Printf("      %s\n", r.Error)
+		}
+		if r.Expected != "" || r.Output != "" {
+			fmt.Printf("      expected:\n%s", indent(r.Expected))
+			fmt.Printf("      got:\n%s", indent(r.Output))
+		}
+	}
+
+	fmt.Printf("\n%d passed, %d failed\n", len(results)-failed, failed)
+	if failed > 0 {
+		os.Exit(1)
+	}
+}
+
+// indent prefixes every line of s for nesting under a test result
+func indent(s string) string {
+	if s == "" {
+		return "        (nothing)\n"
+	}
+	var out strings.Builder
+	for _, line := range strings.Split(strings.TrimRight(s, "\n"), "\n") {
+		out.WriteString("        " + line + "\n")
+	}
+	return out.String()
+}
+
+// compile prepares source for the test runner with the files it includes,
+// which read is given the names of, reusing a cached parse
+func compile(name, source string, read func(name string) (string, error)) *testrunner.Program {
+	key := cache.Key(source)
+	if tree, ok := programCache.Load(key); ok {
+		return testrunner.Prepare(name, tree)
+	}
+	loaded := include.Load(name, source, read)
+	prog := testrunner.Prepare(name, loaded.AST)
+	prog.ParseErrors = loaded.Errors()
+	if loaded.OK() && len(loaded.Files) == 1 {
+		if err := programCache.Store(key, prog.AST); err != nil {
+			logger.Debug("cannot cache parse", "file", name, "error", err)
+		}
+	}
+	return prog
+}
@@ -0,0 +1,74 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/include"
+	"github.com/andrinoff/cambridge-lang/pkg/tracetable"
+)
+
+// runTraceTable writes trace table exercises made from a program: for each
+// variant a blank worksheet and its answers
+func runTraceTable(args []string) {
+	fs := flag.NewFlagSet("tracetable", flag.ExitOnError)
+	vary := namedRanges{}
+	fs.Var(vary, "vary", "give a constant, or a variable where it is first assigned a literal, a new value each variant as `NAME=RANGE` (repeatable)")
+	ranges := namedRanges{}
+	fs.Var(ranges, "input", "generate what INPUT reads into a variable as `NAME=RANGE`: LOW..HIGH, string:LOW..HIGH or A|B|C (repeatable)")
+	variants := fs.Int("variants", 1, "how many variants to make")
+	seed := fs.Int64("seed", 1, "the seed of the first variant, each after it using the next, so the same seed makes the same exercises again")
+	steps := fs.Int("steps", 10000, "give up on a variant still running after this many statements")
+	out := fs.String("out", ".", "the folder to write the pages to")
+	fs.Usage = func() {
+		fmt.Fprintln(os.Stderr, "Usage: cambridge tracetable [--vary NAME=RANGE]... [--input NAME=RANGE]... [--variants N] [--seed N] [--steps N] [--out dir] <filename>")
+		fs.PrintDefaults()
+	}
+	fs.Parse(args)
+
+	if fs.NArg() != 1 || *variants < 1 {
+		fs.Usage()
+		os.Exit(1)
+	}
+	filename := fs.Arg(0)
+	_, files := parseFile(filename, include.ReadFile)
+	src := files.Main()
+	if err := os.MkdirAll(*out, 0o755); err != nil {
+		fatal("cannot create %s: %v", *out, err)
+	}
+
+	base := strings.TrimSuffix(filepath.Base(filename), filepath.Ext(filename))
+	for k := 1; k <= *variants; k++ {
+		variantSeed := *seed + int64(k-1)
+		table, err := tracetable.Generate(src.Text, tracetable.Options{Vary: vary, Inputs: ranges, Seed: variantSeed, Steps: *steps})
+		if err != nil {
+			fatal("variant %d (seed %d): %v", k, variantSeed, err)
+		}
+		title := fmt.Sprintf("%s, variant %d", base, k)
+		for _, page := range []struct {
+			suffix  string
+			answers bool
+		}{{"worksheet", false}, {"answers", true}} {
+			path := filepath.Join(*out, fmt.Sprintf("%s-%d-%s.html", base, k, page.suffix))
+			if err := writeTracePage(path, table, title, page.answers); err != nil {
+				fatal("cannot write %s: %v", path, err)
+			}
+			fmt.Println(path)
+		}
+	}
+}
+
+func writeTracePage(path string, table *tracetable.Table, title string, answers bool) error {
+	f, err := os.Create(path)
+	if err != nil {
+		return err
+	}
+	if err := table.WriteHTML(f, title, answers); err != nil {
+		f.Close()
+		return err
+	}
+	return f.Close()
+}
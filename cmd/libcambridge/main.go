@@ -0,0 +1,158 @@
+// Command libcambridge builds the interpreter as a C shared library, for
+// tools written in C, C++, C# or Delphi
+//
+//	go build -buildmode=c-shared -o libcambridge.so ./cmd/libcambridge
+//
+// writes libcambridge.so (or a .dll or .dylib) and the header
+// libcambridge.h declaring:
+//
+//	cambridge_new         create an interpreter, returning its handle
+//	cambridge_load        parse source, returning the number of errors
+//	cambridge_set_input   set the text INPUT reads
+//	cambridge_run         run the loaded program, returning 0 if it finished
+//	cambridge_eval        run it in the variables earlier evals left, as a
+//	                      notebook cell
+//	cambridge_output      what the last run wrote with OUTPUT
+//	cambridge_diagnostics the load and run errors, as a JSON array
+//	cambridge_format      source laid out in the house style, or NULL if
+//	                      it does not parse
+//	cambridge_free_string free a string returned by the three above
+//	cambridge_free        free an interpreter
+//
+// Strings are UTF-8 and NUL-terminated. A handle must not be used from two
+// threads at once, but separate handles may be.
+package main
+
+/*
+#include <stdlib.h>
+#include <stdint.h>
+*/
+import "C"
+
+import (
+	"bytes"
+	"encoding/json"
+	"errors"
+	"runtime/cgo"
+	"strings"
+	"unsafe"
+
+	"github.com/andrinoff/cambridge-lang/pkg/cambridge"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/format"
+)
+
+// engine is what a handle refers to
+type engine struct {
+	interp      *cambridge.Interpreter
+	program     *cambridge.Program
+	session     *cambridge.Session // created by the first cambridge_eval
+	input       string
+	output      bytes.Buffer
+	diagnostics []diagnostic.Diagnostic
+}
+
+func get(handle C.uintptr_t) *engine {
+	return cgo.Handle(handle).Value().(*engine)
+}
+
+//export cambridge_new
+func cambridge_new() C.uintptr_t {
+	e := &engine{interp: cambridge.New()}
+	e.interp.SetOutput(&e.output)
+	return C.uintptr_t(cgo.NewHandle(e))
+}
+
+//export cambridge_load
+func cambridge_load(handle C.uintptr_t, name, source *C.char) C.int {
+	e := get(handle)
+	e.program = cambridge.Parse(C.GoString(name), C.GoString(source))
+	e.diagnostics = e.program.Diagnostics()
+	errs := 0
+	for _, d := range e.diagnostics {
+		if d.Severity == diagnostic.Error {
+			errs++
+		}
+	}
+	return C.int(errs)
+}
+
+//export cambridge_set_input
+func cambridge_set_input(handle C.uintptr_t, input *C.char) {
+	get(handle).input = C.GoString(input)
+}
+
+//export cambridge_run
+func cambridge_run(handle C.uintptr_t) C.int {
+	e := get(handle)
+	return e.run(e.interp.Run)
+}
+
+//export cambridge_eval
+func cambridge_eval(handle C.uintptr_t) C.int {
+	e := get(handle)
+	if e.session == nil {
+		e.session = e.interp.NewSession()
+	}
+	return e.run(e.session.Run)
+}
+
+// run runs the loaded program with fn, collecting its output and errors
+func (e *engine) run(fn func(*cambridge.Program) error) C.int {
+	e.output.Reset()
+	if e.program == nil {
+		e.diagnostics = []diagnostic.Diagnostic{{Severity: diagnostic.Error, Message: "no program has been loaded"}}
+		return 1
+	}
+	e.diagnostics = e.program.Diagnostics()
+	e.interp.SetInput(strings.NewReader(e.input))
+
+	var runErr *cambridge.Error
+	if err := fn(e.program); errors.As(err, &runErr) {
+		if e.program.OK() {
+			e.diagnostics = append(e.diagnostics, runErr.Diagnostic)
+		}
+		return 1
+	}
+	return 0
+}
+
+//export cambridge_output
+func cambridge_output(handle C.uintptr_t) *C.char {
+	return C.CString(get(handle).output.String())
+}
+
+//export cambridge_diagnostics
+func cambridge_diagnostics(handle C.uintptr_t) *C.char {
+	e := get(handle)
+	diagnostics := e.diagnostics
+	if diagnostics == nil {
+		diagnostics = []diagnostic.Diagnostic{}
+	}
+	data, err := json.Marshal(diagnostics)
+	if err != nil {
+		data = []byte("[]")
+	}
+	return C.CString(string(data))
+}
+
+//export cambridge_format
+func cambridge_format(source *C.char) *C.char {
+	formatted, err := format.Source(C.GoString(source))
+	if err != nil {
+		return nil
+	}
+	return C.CString(formatted)
+}
+
+//export cambridge_free_string
+func cambridge_free_string(s *C.char) {
+	C.free(unsafe.Pointer(s))
+}
+
+//export cambridge_free
+func cambridge_free(handle C.uintptr_t) {
+	cgo.Handle(handle).Delete()
+}
+
+func main() {}
@@ -1,27 +1,69 @@
-import * as path from "path";
-import { workspace, ExtensionContext } from "vscode";
+import { commands, ExtensionContext, Uri, window, workspace } from "vscode";
 import {
   LanguageClient,
   LanguageClientOptions,
   ServerOptions,
   TransportKind,
 } from "vscode-languageclient/node";
+import { debugFile, LANGUAGE_ID, registerDebugger, runFile } from "./programs";
+import { errorMessage, serverPath } from "./server";
 
-let client: LanguageClient;
+let client: LanguageClient | undefined;
 
-export function activate(context: ExtensionContext) {
-  // Path to the compiled Go binary
-  const serverModule = context.asAbsolutePath(
-    path.join("bin", "cambridge-lsp"),
+export async function activate(context: ExtensionContext) {
+  registerDebugger(context);
+  context.subscriptions.push(
+    commands.registerCommand("cambridge.runFile", runFile),
+    commands.registerCommand("cambridge.debugFile", debugFile),
+    commands.registerCommand("cambridge.restartServer", async () => {
+      await client?.stop();
+      client = undefined;
+      await startClient(context);
+    }),
   );
+  await startClient(context);
+}
+
+export function deactivate(): Thenable<void> | undefined {
+  return client ? client.stop() : undefined;
+}
+
+async function startClient(context: ExtensionContext) {
+  let command: string;
+  try {
+    command = await serverPath(context);
+  } catch (err) {
+    window.showErrorMessage(
+      `Cannot start the Cambridge language server: ${errorMessage(err)}. ` +
+        "Set cambridge.server.path to a cambridge-lsp to run.",
+    );
+    return;
+  }
+  const args =
+    workspace.getConfiguration("cambridge").get<string[]>("server.arguments") ??
+    [];
 
   const serverOptions: ServerOptions = {
-    run: { command: serverModule, transport: TransportKind.stdio },
-    debug: { command: serverModule, transport: TransportKind.stdio },
+    run: { command, args, transport: TransportKind.stdio },
+    debug: { command, args, transport: TransportKind.stdio },
   };
 
+  // The server reads the settings under cambridge, such as strict and
+  // dialect, when it starts and whenever they change
   const clientOptions: LanguageClientOptions = {
-    documentSelector: [{ scheme: "file", language: "cambridge-pseudo" }],
+    documentSelector: [{ scheme: "file", language: LANGUAGE_ID }],
+    initializationOptions: workspace.getConfiguration().get("cambridge"),
+    synchronize: { configurationSection: "cambridge" },
+    middleware: {
+      // The Debug lens above a program starts the editor's debugger rather
+      // than the server's log of the program's steps
+      executeCommand: (name, lensArgs, next) => {
+        if (name === "cambridge.debug" && lensArgs.length === 1) {
+          return debugFile(Uri.parse(lensArgs[0]));
+        }
+        return next(name, lensArgs);
+      },
+    },
   };
 
   client = new LanguageClient(
@@ -30,9 +72,5 @@ export function activate(context: ExtensionContext) {
     serverOptions,
     clientOptions,
   );
-  client.start();
-}
-
-export function deactivate(): Thenable<void> | undefined {
-  return client ? client.stop() : undefined;
+  await client.start();
 }
@@ -0,0 +1,150 @@
+import * as path from "path";
+import {
+  DebugAdapterDescriptor,
+  DebugAdapterDescriptorFactory,
+  DebugAdapterExecutable,
+  DebugConfiguration,
+  DebugConfigurationProvider,
+  DebugSession,
+  ExtensionContext,
+  ProcessExecution,
+  Task,
+  TaskScope,
+  TextDocument,
+  Uri,
+  WorkspaceFolder,
+  debug,
+  tasks,
+  window,
+  workspace,
+} from "vscode";
+import { serverPath } from "./server";
+
+// The language's id in package.json
+export const LANGUAGE_ID = "cambridge-pseudo";
+
+// The debugger's type, and the task's, in package.json and launch.json
+const DEBUG_TYPE = "cambridge";
+
+/** Lets pseudocode be debugged by cambridge-lsp --dap */
+export function registerDebugger(context: ExtensionContext) {
+  context.subscriptions.push(
+    debug.registerDebugAdapterDescriptorFactory(
+      DEBUG_TYPE,
+      new AdapterFactory(context),
+    ),
+    debug.registerDebugConfigurationProvider(
+      DEBUG_TYPE,
+      new ConfigurationProvider(),
+    ),
+  );
+}
+
+/**
+ * Runs `cambridge run` on the file at uri, or the active one, in the
+ * terminal panel, where the program's INPUT can be typed
+ */
+export async function runFile(uri?: Uri) {
+  const document = await pseudocode(uri, "run");
+  if (!document) {
+    return;
+  }
+  const cli =
+    workspace.getConfiguration("cambridge").get<string>("cli.path") ||
+    "cambridge";
+  const file = document.fileName;
+  const task = new Task(
+    { type: DEBUG_TYPE, file },
+    workspace.getWorkspaceFolder(document.uri) ?? TaskScope.Workspace,
+    `run ${path.basename(file)}`,
+    "cambridge",
+    new ProcessExecution(cli, ["run", file], { cwd: path.dirname(file) }),
+  );
+  await tasks.executeTask(task);
+}
+
+/** Starts debugging the file at uri, or the active one */
+export async function debugFile(uri?: Uri) {
+  const document = await pseudocode(uri, "debug");
+  if (!document) {
+    return;
+  }
+  await debug.startDebugging(workspace.getWorkspaceFolder(document.uri), {
+    type: DEBUG_TYPE,
+    name: `Debug ${path.basename(document.fileName)}`,
+    request: "launch",
+    program: document.fileName,
+  });
+}
+
+/**
+ * Returns the saved pseudocode file at uri, or the active one, to run or
+ * debug, saying why if there is none
+ */
+async function pseudocode(
+  uri: Uri | undefined,
+  action: string,
+): Promise<TextDocument | undefined> {
+  const document = uri
+    ? await workspace.openTextDocument(uri)
+    : window.activeTextEditor?.document;
+  if (!document || document.languageId !== LANGUAGE_ID) {
+    window.showErrorMessage(`Open a pseudocode file to ${action} it`);
+    return undefined;
+  }
+  if (document.isDirty && !(await document.save())) {
+    return undefined;
+  }
+  if (document.isUntitled) {
+    window.showErrorMessage(`Save the program to ${action} it`);
+    return undefined;
+  }
+  return document;
+}
+
+/**
+ * Starts cambridge-lsp --dap for each debug session, in the folder set in
+ * the configuration's cwd or else the program's, where OPENFILE finds its
+ * files
+ */
+class AdapterFactory implements DebugAdapterDescriptorFactory {
+  constructor(private readonly context: ExtensionContext) {}
+
+  async createDebugAdapterDescriptor(
+    session: DebugSession,
+  ): Promise<DebugAdapterDescriptor> {
+    const command = await serverPath(this.context);
+    const { cwd, program } = session.configuration;
+    return new DebugAdapterExecutable(command, ["--dap"], {
+      cwd: cwd || (program ? path.dirname(program) : undefined),
+    });
+  }
+}
+
+/**
+ * Debugs the active pseudocode file when there is no launch.json, as
+ * pressing F5 on a program should do
+ */
+class ConfigurationProvider implements DebugConfigurationProvider {
+  resolveDebugConfiguration(
+    _folder: WorkspaceFolder | undefined,
+    config: DebugConfiguration,
+  ): DebugConfiguration | undefined {
+    if (!config.type && !config.request && !config.name) {
+      const document = window.activeTextEditor?.document;
+      if (document?.languageId === LANGUAGE_ID) {
+        config.type = DEBUG_TYPE;
+        config.name = `Debug ${path.basename(document.fileName)}`;
+        config.request = "launch";
+        config.program = document.fileName;
+      }
+    }
+    if (!config.program) {
+      window.showErrorMessage(
+        "Open a pseudocode file to debug, or set the program in launch.json",
+      );
+      return undefined;
+    }
+    return config;
+  }
+}
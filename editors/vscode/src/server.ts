@@ -0,0 +1,254 @@
+import * as crypto from "crypto";
+import * as fs from "fs";
+import * as https from "https";
+import * as path from "path";
+import * as zlib from "zlib";
+import { ExtensionContext, ProgressLocation, window, workspace } from "vscode";
+
+// The language server's name, both in releases and on PATH
+const SERVER_NAME = "cambridge-lsp";
+
+// The GitHub repository whose releases carry the language server
+const REPOSITORY = "andrinoff/cambridge-lang";
+
+// The release asset listing the SHA-256 checksum of every binary
+const CHECKSUMS = "checksums.txt";
+
+interface Release {
+  tag_name: string;
+  assets: { name: string; browser_download_url: string }[];
+}
+
+// The binary found by serverPath, shared by the language client and the
+// debug adapter so that it is only looked for, or downloaded, once
+let found: Promise<string> | undefined;
+
+/**
+ * Returns the path of the cambridge-lsp to run: the one set in
+ * cambridge.server.path, else one on PATH, else the latest release,
+ * downloaded into the extension's storage unless it has been already.
+ * If the latest release cannot be fetched, the newest one downloaded
+ * before is run until it can be.
+ */
+export function serverPath(context: ExtensionContext): Promise<string> {
+  const configured = workspace
+    .getConfiguration("cambridge")
+    .get<string>("server.path");
+  if (configured) {
+    return Promise.resolve(configured);
+  }
+  if (!found) {
+    found = findServer(context);
+    found.catch(() => (found = undefined));
+  }
+  return found;
+}
+
+async function findServer(context: ExtensionContext): Promise<string> {
+  const onPath = which(binaryName());
+  if (onPath) {
+    return onPath;
+  }
+  const storage = context.globalStorageUri.fsPath;
+  try {
+    return await installLatest(storage);
+  } catch (err) {
+    const fallback = newestInstalled(storage);
+    if (!fallback) {
+      throw err;
+    }
+    window.showWarningMessage(
+      `${errorMessage(err)}; running ${fallback} until it can be updated`,
+    );
+    return fallback;
+  }
+}
+
+/**
+ * Downloads the latest release of the language server into storage,
+ * unless it has been already, returning the path of its binary. The
+ * binary is only run once it matches the checksum published with it.
+ */
+async function installLatest(storage: string): Promise<string> {
+  const release: Release = JSON.parse(
+    (
+      await fetch(`https://api.github.com/repos/${REPOSITORY}/releases/latest`)
+    ).toString(),
+  );
+  const name = assetName();
+  const versionDir = path.join(storage, `${SERVER_NAME}-${release.tag_name}`);
+  const binary = path.join(versionDir, binaryName());
+  if (isFile(binary)) {
+    return binary;
+  }
+
+  const packed = release.assets.find((a) => a.name === `${name}.gz`);
+  const asset = packed ?? release.assets.find((a) => a.name === name);
+  const manifest = release.assets.find((a) => a.name === CHECKSUMS);
+  if (!asset || !manifest) {
+    throw new Error(
+      `Release ${release.tag_name} has no asset named ${asset ? CHECKSUMS : name}`,
+    );
+  }
+
+  await window.withProgress(
+    {
+      location: ProgressLocation.Notification,
+      title: `Downloading ${SERVER_NAME} ${release.tag_name}`,
+    },
+    async () => {
+      let contents = await fetch(asset.browser_download_url);
+      if (packed) {
+        contents = zlib.gunzipSync(contents);
+      }
+      const checksums = await fetch(manifest.browser_download_url);
+      verify(contents, checksums.toString(), name);
+      fs.mkdirSync(versionDir, { recursive: true });
+      fs.writeFileSync(binary, contents, { mode: 0o755 });
+    },
+  );
+  removeOtherVersions(storage, path.basename(versionDir));
+  return binary;
+}
+
+/**
+ * Checks that contents have the SHA-256 checksum listed for asset in the
+ * manifest, written by sha256sum.
+ */
+function verify(contents: Buffer, manifest: string, asset: string) {
+  const expected = manifest
+    .split("\n")
+    .map((line) => line.trim().split(/\s+/))
+    .find(([, file]) => file?.replace(/^\*/, "") === asset)?.[0]
+    ?.toLowerCase();
+  if (!expected) {
+    throw new Error(`${CHECKSUMS} lists no checksum for ${asset}`);
+  }
+  const actual = crypto.createHash("sha256").update(contents).digest("hex");
+  if (actual !== expected) {
+    throw new Error(
+      `Refusing to run ${asset}: its SHA-256 checksum is ${actual} but ${CHECKSUMS} lists ${expected}`,
+    );
+  }
+}
+
+/** Returns the body of url, following GitHub's redirects to its assets */
+function fetch(url: string, redirects = 5): Promise<Buffer> {
+  return new Promise((resolve, reject) => {
+    const headers = { "User-Agent": "cambridge-pseudo-vscode" };
+    https
+      .get(url, { headers }, (res) => {
+        const status = res.statusCode ?? 0;
+        const location = res.headers.location;
+        if (status >= 300 && status < 400 && location && redirects > 0) {
+          res.resume();
+          resolve(fetch(location, redirects - 1));
+          return;
+        }
+        if (status !== 200) {
+          res.resume();
+          reject(new Error(`Failed to download ${url}: HTTP ${status}`));
+          return;
+        }
+        const chunks: Buffer[] = [];
+        res.on("data", (chunk: Buffer) => chunks.push(chunk));
+        res.on("end", () => resolve(Buffer.concat(chunks)));
+        res.on("error", reject);
+      })
+      .on("error", (err) =>
+        reject(new Error(`Failed to download ${url}: ${err.message}`)),
+      );
+  });
+}
+
+/** Returns the name of the release asset built for this platform */
+function assetName(): string {
+  const assets: Record<string, string> = {
+    "darwin-arm64": "cambridge-lsp-macos-arm64",
+    "darwin-x64": "cambridge-lsp-macos-intel",
+    "linux-arm64": "cambridge-lsp-linux-arm64",
+    "linux-x64": "cambridge-lsp-linux",
+    "win32-arm64": "cambridge-lsp-windows-arm64.exe",
+    "win32-x64": "cambridge-lsp.exe",
+  };
+  const asset = assets[`${process.platform}-${process.arch}`];
+  if (!asset) {
+    throw new Error(
+      `No ${SERVER_NAME} is released for ${process.platform} on ${process.arch}; ` +
+        "set cambridge.server.path to a build of your own to use it here",
+    );
+  }
+  return asset;
+}
+
+/** Returns the file name the language server is kept under */
+function binaryName(): string {
+  return process.platform === "win32" ? `${SERVER_NAME}.exe` : SERVER_NAME;
+}
+
+/** Returns the newest version downloaded before into storage, if any */
+function newestInstalled(storage: string): string | undefined {
+  const prefix = `${SERVER_NAME}-`;
+  let entries: string[];
+  try {
+    entries = fs.readdirSync(storage);
+  } catch {
+    return undefined;
+  }
+  return entries
+    .filter((dir) => dir.startsWith(prefix))
+    .map((dir) => ({
+      version: versionNumbers(dir.slice(prefix.length)),
+      binary: path.join(storage, dir, binaryName()),
+    }))
+    .filter(({ binary }) => isFile(binary))
+    .sort((a, b) => compareVersions(b.version, a.version))[0]?.binary;
+}
+
+/** Deletes every version of the language server in storage but keep */
+function removeOtherVersions(storage: string, keep: string) {
+  for (const entry of fs.readdirSync(storage)) {
+    if (entry.startsWith(`${SERVER_NAME}-`) && entry !== keep) {
+      fs.rmSync(path.join(storage, entry), { recursive: true, force: true });
+    }
+  }
+}
+
+/** Returns the numbers in a version such as v0.2.10, for comparing them */
+function versionNumbers(version: string): number[] {
+  return version
+    .replace(/^v/, "")
+    .split(".")
+    .map((part) => parseInt(part, 10) || 0);
+}
+
+function compareVersions(a: number[], b: number[]): number {
+  for (let i = 0; i < Math.max(a.length, b.length); i++) {
+    const diff = (a[i] ?? 0) - (b[i] ?? 0);
+    if (diff !== 0) {
+      return diff;
+    }
+  }
+  return 0;
+}
+
+/** Returns the path of the executable name on PATH, if there is one */
+export function which(name: string): string | undefined {
+  return (process.env.PATH ?? "")
+    .split(path.delimiter)
+    .filter((dir) => dir !== "")
+    .map((dir) => path.join(dir, name))
+    .find(isFile);
+}
+
+function isFile(file: string): boolean {
+  try {
+    return fs.statSync(file).isFile();
+  } catch {
+    return false;
+  }
+}
+
+export function errorMessage(err: unknown): string {
+  return err instanceof Error ? err.message : String(err);
+}
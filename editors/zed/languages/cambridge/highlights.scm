@@ -38,6 +38,7 @@
   "READ"
   "WRITE"
   "APPEND"
+  "INCLUDE"
   "CLASS"
   "ENDCLASS"
   "INHERITS"
@@ -142,6 +143,10 @@
 (constant_declaration
   name: (identifier) @constant)
 
+; Included file paths
+(include_statement
+  path: (string) @string.special.path)
+
 ; Field access
 (member_access
   (identifier) @property)
@@ -0,0 +1,19 @@
+; Blocks indent their body up to the keyword closing them
+(if_statement "ENDIF" @end) @indent
+(case_statement "ENDCASE" @end) @indent
+(for_loop "NEXT" @end) @indent
+(while_loop "ENDWHILE" @end) @indent
+(repeat_loop "UNTIL" @end) @indent
+(procedure_declaration "ENDPROCEDURE" @end) @indent
+(function_declaration "ENDFUNCTION" @end) @indent
+(class_declaration "ENDCLASS" @end) @indent
+(type_declaration "ENDTYPE" @end) @indent
+
+; The statements of a CASE branch written on the lines after its value
+[
+  (case_branch)
+  (otherwise_branch)
+] @indent
+
+(_ "(" ")" @end) @indent
+(_ "[" "]" @end) @indent
@@ -0,0 +1,21 @@
+(procedure_declaration
+  (visibility)? @context
+  "PROCEDURE" @context
+  name: (identifier) @name) @item
+
+(function_declaration
+  (visibility)? @context
+  "FUNCTION" @context
+  name: (identifier) @name) @item
+
+(class_declaration
+  "CLASS" @context
+  name: (identifier) @name) @item
+
+(type_declaration
+  "TYPE" @context
+  name: (identifier) @name) @item
+
+(constant_declaration
+  "CONSTANT" @context
+  name: (identifier) @name) @item
@@ -0,0 +1,13 @@
+; The whole program, from the top of the file
+(
+  (source_file) @run
+  (#set! tag cambridge-file)
+)
+
+; A procedure on its own, for cambridge run --call
+(
+  (procedure_declaration
+    "PROCEDURE" @run
+    name: (identifier) @cambridge_procedure)
+  (#set! tag cambridge-procedure)
+)
@@ -1,75 +1,430 @@
+use sha2::{Digest, Sha256};
 use std::fs;
-use zed_extension_api::{self as zed, Result};
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use zed_extension_api::{
+    self as zed, serde_json::json, settings::LspSettings, DebugAdapterBinary, DebugConfig,
+    DebugRequest, DebugScenario, DebugTaskDefinition, Result, StartDebuggingRequestArguments,
+    StartDebuggingRequestArgumentsRequest, TaskTemplate, Worktree,
+};
+
+/// The language server's name, both in `extension.toml` and on PATH
+const SERVER_NAME: &str = "cambridge-lsp";
+
+/// The GitHub repository whose releases carry the language server
+const REPOSITORY: &str = "andrinoff/cambridge-lang";
+
+/// The release asset listing the SHA-256 checksum of every binary
+const CHECKSUMS: &str = "checksums.txt";
+
+/// The debug adapter's name in `extension.toml`, run as `cambridge-lsp --dap`
+const DEBUG_ADAPTER: &str = "Cambridge";
 
 struct CambridgeExtension {
     cached_binary_path: Option<String>,
 }
 
 impl CambridgeExtension {
+    /// Returns the path of the `cambridge-lsp` to run, showing how its
+    /// download is going under `language_server_id` unless it is run as the
+    /// debug adapter
     fn language_server_binary_path(
         &mut self,
-        language_server_id: &zed::LanguageServerId,
-        _worktree: &zed::Worktree,
+        language_server_id: Option<&zed::LanguageServerId>,
+        worktree: &zed::Worktree,
     ) -> Result<String> {
-        // 1. Check if we already have the path cached in memory
+        // 1. Use the binary the user configured, or one they installed on
+        // PATH, so that their own build is run instead of a download,
+        // warning if it is older than the latest release as it is not
+        // updated here
+        if let Some(path) = configured_binary_path(worktree).or_else(|| worktree.which(SERVER_NAME))
+        {
+            if let Some(id) = language_server_id {
+                warn_if_outdated(id, &path);
+            }
+            return Ok(path);
+        }
+
+        // 2. Check if we already have the path cached in memory
         if let Some(path) = &self.cached_binary_path {
             if fs::metadata(path).map(|m| m.is_file()).unwrap_or(false) {
                 return Ok(path.clone());
             }
         }
 
-        zed::set_language_server_installation_status(
+        // 3. Download the latest release, or if that cannot be done, run
+        // the newest version downloaded before until it can
+        match install_latest(language_server_id) {
+            Ok(path) => {
+                self.cached_binary_path = Some(path.clone());
+                set_status(
+                    language_server_id,
+                    &zed::LanguageServerInstallationStatus::None,
+                );
+                Ok(path)
+            }
+            Err(e) => {
+                let Some(path) = newest_installed() else {
+                    return Err(e);
+                };
+                let version = downloaded_version(&path).unwrap_or("an older version");
+                set_status(
+                    language_server_id,
+                    &zed::LanguageServerInstallationStatus::Failed(format!(
+                        "{}; running cambridge-lsp {} until it can be updated",
+                        e, version
+                    )),
+                );
+                Ok(path)
+            }
+        }
+    }
+}
+
+/// Downloads the latest release of the language server, unless it has been
+/// already, returning the path of its binary
+fn install_latest(language_server_id: Option<&zed::LanguageServerId>) -> Result<String> {
+    set_status(
+        language_server_id,
+        &zed::LanguageServerInstallationStatus::CheckingForUpdate,
+    );
+
+    let release = zed::latest_github_release(
+        REPOSITORY,
+        zed::GithubReleaseOptions {
+            require_assets: true,
+            pre_release: false,
+        },
+    )?;
+
+    let asset_name = asset_name()?;
+    let (asset, file_type) = find_binary_asset(&release, asset_name)?;
+    let manifest = find_asset(&release, CHECKSUMS)?;
+
+    // Each version is kept in its own directory, relative to the
+    // extension's installation folder (support dir)
+    let version_dir = format!("{}-{}", SERVER_NAME, release.version);
+    let binary_path = format!("{}/{}", version_dir, binary_name());
+
+    if !fs::metadata(&binary_path)
+        .map(|m| m.is_file())
+        .unwrap_or(false)
+    {
+        set_status(
             language_server_id,
-            &zed::LanguageServerInstallationStatus::CheckingForUpdate,
+            &zed::LanguageServerInstallationStatus::Downloading,
         );
 
-        let binary_name = "cambridge-lsp";
-        // This path is relative to the extension's installation folder (support dir)
-        let binary_path = format!("./{}", binary_name);
+        fs::create_dir_all(&version_dir)
+            .map_err(|e| format!("Failed to create {}: {}", version_dir, e))?;
 
-        if !fs::metadata(&binary_path)
-            .map(|m| m.is_file())
-            .unwrap_or(false)
-        {
-            zed::set_language_server_installation_status(
-                language_server_id,
-                &zed::LanguageServerInstallationStatus::Downloading,
-            );
+        // The binary is only moved to where it runs from once it
+        // matches the checksum published with it
+        let download_path = format!("{}.download", binary_path);
+        let manifest_path = format!("{}/{}", version_dir, CHECKSUMS);
+        download_binary(asset, file_type, &version_dir, &download_path)?;
+        zed::download_file(
+            &manifest.download_url,
+            &manifest_path,
+            zed::DownloadedFileType::Uncompressed,
+        )
+        .map_err(|e| format!("Failed to download {}: {}", CHECKSUMS, e))?;
 
-            let (platform, arch) = zed::current_platform();
+        if let Err(e) = verify(&download_path, &manifest_path, asset_name) {
+            fs::remove_dir_all(&version_dir).ok();
+            return Err(e);
+        }
+        fs::rename(&download_path, &binary_path)
+            .map_err(|e| format!("Failed to install LSP: {}", e))?;
+        zed::make_file_executable(&binary_path)?;
+        remove_other_versions(&version_dir);
+    }
 
-            let download_url = match (platform, arch) {
-                (zed::Os::Mac, zed::Architecture::Aarch64) =>
-                    "https://github.com/andrinoff/cambridge-lang/releases/download/v0.2.0/cambridge-lsp-macos-arm64",
-                (zed::Os::Mac, zed::Architecture::X8664) =>
-                    "https://github.com/andrinoff/cambridge-lang/releases/download/v0.2.0/cambridge-lsp-macos-intel",
-                (zed::Os::Linux, _) =>
-                    "https://github.com/andrinoff/cambridge-lang/releases/download/v0.2.0/cambridge-lsp-linux",
-                (zed::Os::Windows, _) =>
-                    "https://github.com/andrinoff/cambridge-lang/releases/download/v0.2.0/cambridge-lsp.exe",
-                _ => return Err("Unsupported platform".into()),
-            };
+    Ok(binary_path)
+}
 
-            zed::download_file(
-                &download_url,
-                &binary_path,
-                zed::DownloadedFileType::Uncompressed,
-            )
-            .map_err(|e| format!("Failed to download LSP: {}", e))?;
+/// Shows `status` as how the language server's installation is going, if it
+/// is being started as the language server
+fn set_status(
+    language_server_id: Option<&zed::LanguageServerId>,
+    status: &zed::LanguageServerInstallationStatus,
+) {
+    if let Some(id) = language_server_id {
+        zed::set_language_server_installation_status(id, status);
+    }
+}
 
-            zed::make_file_executable(&binary_path)?;
-        }
+/// Returns the binary of the newest version of the language server that
+/// was downloaded before, if any
+fn newest_installed() -> Option<String> {
+    let prefix = format!("{}-", SERVER_NAME);
+    fs::read_dir(".")
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.file_name().into_string().ok()?;
+            let version = version_numbers(dir.strip_prefix(&prefix)?);
+            let binary = format!("{}/{}", dir, binary_name());
+            fs::metadata(&binary)
+                .ok()?
+                .is_file()
+                .then_some((version, binary))
+        })
+        .max()
+        .map(|(_, binary)| binary)
+}
 
-        self.cached_binary_path = Some(binary_path.clone());
+/// Returns the version of a binary the extension downloaded, which is
+/// recorded in the name of the directory it was downloaded to
+fn downloaded_version(path: &str) -> Option<&str> {
+    let dir = Path::new(path).parent()?.to_str()?;
+    dir.strip_prefix(SERVER_NAME)?.strip_prefix('-')
+}
+
+/// Returns the version the `cambridge-lsp` at `path` reports with
+/// `--version`, such as v0.2.0
+fn installed_version(path: &str) -> Option<String> {
+    let output = zed::process::Command::new(path)
+        .arg("--version")
+        .output()
+        .ok()?;
+    if output.status != Some(0) {
+        return None;
+    }
+    String::from_utf8(output.stdout)
+        .ok()?
+        .split_whitespace()
+        .find(|word| word.starts_with('v'))
+        .map(str::to_string)
+}
 
+/// Shows a warning that the `cambridge-lsp` at `path` is older than the
+/// latest release, so that a machine running one with known bugs is
+/// noticed
+/// Nothing is shown if either version cannot be found out.
+fn warn_if_outdated(language_server_id: &zed::LanguageServerId, path: &str) {
+    let Some(installed) = installed_version(path) else {
+        return;
+    };
+    let Ok(release) = zed::latest_github_release(
+        REPOSITORY,
+        zed::GithubReleaseOptions {
+            require_assets: true,
+            pre_release: false,
+        },
+    ) else {
+        return;
+    };
+    if version_numbers(&release.version) > version_numbers(&installed) {
         zed::set_language_server_installation_status(
             language_server_id,
-            &zed::LanguageServerInstallationStatus::None,
+            &zed::LanguageServerInstallationStatus::Failed(format!(
+                "{} is cambridge-lsp {}, but {} has been released; update it to get its fixes",
+                path, installed, release.version
+            )),
         );
-        Ok(binary_path)
     }
 }
 
+/// Returns the numbers in a version such as v0.2.10, for comparing them
+fn version_numbers(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Returns the asset of `release` called `name`
+fn find_asset<'a>(
+    release: &'a zed::GithubRelease,
+    name: &str,
+) -> Result<&'a zed::GithubReleaseAsset> {
+    release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .ok_or_else(|| format!("Release {} has no asset named {}", release.version, name))
+}
+
+/// Returns the asset of `release` holding the binary `name`, gzipped,
+/// zipped or as it is, and how it is packed
+fn find_binary_asset<'a>(
+    release: &'a zed::GithubRelease,
+    name: &str,
+) -> Result<(&'a zed::GithubReleaseAsset, zed::DownloadedFileType)> {
+    let packings = [
+        (".gz", zed::DownloadedFileType::Gzip),
+        (".zip", zed::DownloadedFileType::Zip),
+        ("", zed::DownloadedFileType::Uncompressed),
+    ];
+    for (suffix, file_type) in packings {
+        let packed = format!("{}{}", name, suffix);
+        if let Some(asset) = release.assets.iter().find(|asset| asset.name == packed) {
+            return Ok((asset, file_type));
+        }
+    }
+    Err(format!(
+        "Release {} has no asset named {}",
+        release.version, name
+    ))
+}
+
+/// Downloads the binary in `asset` to `path`, unpacking it in `dir` first
+/// if it was zipped
+fn download_binary(
+    asset: &zed::GithubReleaseAsset,
+    file_type: zed::DownloadedFileType,
+    dir: &str,
+    path: &str,
+) -> Result<()> {
+    if !matches!(file_type, zed::DownloadedFileType::Zip) {
+        return zed::download_file(&asset.download_url, path, file_type)
+            .map_err(|e| format!("Failed to download LSP: {}", e));
+    }
+
+    let unpacked = format!("{}/unpacked", dir);
+    zed::download_file(&asset.download_url, &unpacked, file_type)
+        .map_err(|e| format!("Failed to download LSP: {}", e))?;
+    let names = [
+        asset.name.trim_end_matches(".zip").to_string(),
+        binary_name(),
+    ];
+    let found = find_file(Path::new(&unpacked), &names)
+        .ok_or_else(|| format!("{} holds no {}", asset.name, binary_name()));
+    let moved = found.and_then(|binary| {
+        fs::rename(binary, path).map_err(|e| format!("Failed to unpack LSP: {}", e))
+    });
+    fs::remove_dir_all(&unpacked).ok();
+    moved
+}
+
+/// Returns the first file under `dir` with one of `names`, looking in its
+/// subdirectories as well since archives often keep files in a folder
+fn find_file(dir: &Path, names: &[String]) -> Option<PathBuf> {
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            dirs.push(path);
+        } else if names
+            .iter()
+            .any(|name| entry.file_name().to_str() == Some(name))
+        {
+            return Some(path);
+        }
+    }
+    dirs.iter().find_map(|dir| find_file(dir, names))
+}
+
+/// Checks that the file at `path` has the SHA-256 checksum listed for
+/// `asset_name` in the manifest at `manifest_path`, written by `sha256sum`
+/// A compressed asset is checked once unpacked, against the checksum of
+/// the binary it holds.
+fn verify(path: &str, manifest_path: &str, asset_name: &str) -> Result<()> {
+    let manifest = fs::read_to_string(manifest_path)
+        .map_err(|e| format!("Failed to read {}: {}", CHECKSUMS, e))?;
+    let expected = manifest
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, name)| name.trim_start().trim_start_matches('*') == asset_name)
+        .map(|(checksum, _)| checksum.to_ascii_lowercase())
+        .ok_or_else(|| format!("{} lists no checksum for {}", CHECKSUMS, asset_name))?;
+
+    let binary = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let actual: String = Sha256::digest(&binary)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    if actual != expected {
+        return Err(format!(
+            "Refusing to run {}: its SHA-256 checksum is {} but {} lists {}",
+            asset_name, actual, CHECKSUMS, expected
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the name of the release asset built for this platform
+fn asset_name() -> Result<&'static str> {
+    let (platform, arch) = zed::current_platform();
+    platform_asset_name(platform, arch)
+}
+
+/// Returns the name of the release asset built for `platform` on `arch`
+fn platform_asset_name(platform: zed::Os, arch: zed::Architecture) -> Result<&'static str> {
+    Ok(match (platform, arch) {
+        (zed::Os::Mac, zed::Architecture::Aarch64) => "cambridge-lsp-macos-arm64",
+        (zed::Os::Mac, zed::Architecture::X8664) => "cambridge-lsp-macos-intel",
+        (zed::Os::Linux, zed::Architecture::Aarch64) => "cambridge-lsp-linux-arm64",
+        (zed::Os::Linux, zed::Architecture::X8664) => "cambridge-lsp-linux",
+        (zed::Os::Windows, zed::Architecture::Aarch64) => "cambridge-lsp-windows-arm64.exe",
+        (zed::Os::Windows, zed::Architecture::X8664) => "cambridge-lsp.exe",
+        _ => {
+            return Err(format!(
+                "No cambridge-lsp is released for {:?} on {:?}; it is built for macOS, \
+                 Linux and Windows on x86-64 and AArch64. Set lsp.cambridge-lsp.binary.path \
+                 to a build of your own to use it here.",
+                platform, arch
+            ))
+        }
+    })
+}
+
+/// Returns the file name the language server is kept under
+fn binary_name() -> String {
+    match zed::current_platform() {
+        (zed::Os::Windows, _) => format!("{}.exe", SERVER_NAME),
+        _ => SERVER_NAME.to_string(),
+    }
+}
+
+/// Deletes every version of the language server but the one in `keep`,
+/// and anything else left in the extension's folder by earlier releases
+fn remove_other_versions(keep: &str) {
+    let Ok(entries) = fs::read_dir(".") else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_str() == Some(keep) {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            fs::remove_dir_all(&path).ok();
+        } else {
+            fs::remove_file(&path).ok();
+        }
+    }
+}
+
+/// Returns the launch configuration the debug adapter is given for running
+/// `program`, a pseudocode file
+fn launch_config(program: &str, cwd: Option<&str>, stop_on_entry: bool) -> zed::serde_json::Value {
+    let mut config = json!({
+        "request": "launch",
+        "program": program,
+        "stopOnEntry": stop_on_entry,
+    });
+    if let Some(cwd) = cwd {
+        config["cwd"] = json!(cwd);
+    }
+    config
+}
+
+/// Returns the `lsp.cambridge-lsp` settings, or none if they cannot be read
+fn lsp_settings(worktree: &zed::Worktree) -> LspSettings {
+    LspSettings::for_worktree(SERVER_NAME, worktree).unwrap_or_default()
+}
+
+/// Returns the path set in the `lsp.cambridge-lsp.binary.path` setting, if any
+fn configured_binary_path(worktree: &zed::Worktree) -> Option<String> {
+    lsp_settings(worktree)
+        .binary?
+        .path
+        .filter(|path| !path.is_empty())
+}
+
 impl zed::Extension for CambridgeExtension {
     fn new() -> Self {
         Self {
@@ -82,14 +437,304 @@ impl zed::Extension for CambridgeExtension {
         language_server_id: &zed::LanguageServerId,
         worktree: &zed::Worktree,
     ) -> Result<zed::Command> {
-        let path = self.language_server_binary_path(language_server_id, worktree)?;
+        let path = self.language_server_binary_path(Some(language_server_id), worktree)?;
+
+        // Arguments such as --verbose and the environment are taken from
+        // `lsp.cambridge-lsp.binary`, whichever binary is run
+        let binary = lsp_settings(worktree).binary;
+        let args = binary
+            .as_ref()
+            .and_then(|binary| binary.arguments.clone())
+            .unwrap_or_default();
+        let mut env = worktree.shell_env();
+        if let Some(vars) = binary.and_then(|binary| binary.env) {
+            env.extend(vars);
+        }
 
         Ok(zed::Command {
             command: path,
-            args: vec![],
-            env: Default::default(),
+            args,
+            env,
+        })
+    }
+
+    /// Returns `lsp.cambridge-lsp.initialization_options`, or else the
+    /// server's settings, so that checking is set up right from the start
+    fn language_server_initialization_options(
+        &mut self,
+        _language_server_id: &zed::LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> Result<Option<zed::serde_json::Value>> {
+        let settings = lsp_settings(worktree);
+        Ok(settings.initialization_options.or(settings.settings))
+    }
+
+    /// Returns `lsp.cambridge-lsp.settings`, such as `strict` and `dialect`,
+    /// in the `cambridge` section the server reads its configuration from
+    fn language_server_workspace_configuration(
+        &mut self,
+        _language_server_id: &zed::LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> Result<Option<zed::serde_json::Value>> {
+        Ok(lsp_settings(worktree)
+            .settings
+            .map(|settings| json!({ "cambridge": settings })))
+    }
+
+    /// Runs `cambridge-lsp --dap`, the same binary as the language server
+    /// unless the user gave one for the debug adapter, over stdio or on
+    /// the TCP port the scenario asks for
+    fn get_dap_binary(
+        &mut self,
+        adapter_name: String,
+        config: DebugTaskDefinition,
+        user_provided_debug_adapter_path: Option<String>,
+        worktree: &Worktree,
+    ) -> Result<DebugAdapterBinary, String> {
+        let command = match user_provided_debug_adapter_path {
+            Some(path) => path,
+            None => self.language_server_binary_path(None, worktree)?,
+        };
+        let configuration: zed::serde_json::Value = zed::serde_json::from_str(&config.config)
+            .map_err(|e| format!("Invalid debug configuration: {}", e))?;
+        let request = self.dap_request_kind(adapter_name, configuration.clone())?;
+
+        let mut arguments = vec!["--dap".to_string()];
+        let connection = match config.tcp_connection {
+            Some(template) => {
+                let tcp = zed::resolve_tcp_template(template)?;
+                arguments.extend([
+                    "--tcp".to_string(),
+                    tcp.port.to_string(),
+                    "--host".to_string(),
+                    Ipv4Addr::from(tcp.host).to_string(),
+                ]);
+                Some(tcp)
+            }
+            None => None,
+        };
+
+        // The program runs in the folder given, so that OPENFILE finds the
+        // files next to it
+        let cwd = configuration
+            .get("cwd")
+            .and_then(|cwd| cwd.as_str())
+            .map(str::to_string)
+            .or_else(|| Some(worktree.root_path()));
+        let mut envs = worktree.shell_env();
+        if let Some(vars) = lsp_settings(worktree).binary.and_then(|binary| binary.env) {
+            envs.extend(vars);
+        }
+
+        Ok(DebugAdapterBinary {
+            command: Some(command),
+            arguments,
+            envs,
+            cwd,
+            connection,
+            request_args: StartDebuggingRequestArguments {
+                configuration: config.config,
+                request,
+            },
+        })
+    }
+
+    fn dap_request_kind(
+        &mut self,
+        _adapter_name: String,
+        config: zed::serde_json::Value,
+    ) -> Result<StartDebuggingRequestArgumentsRequest, String> {
+        match config.get("request").and_then(|request| request.as_str()) {
+            Some("launch") => Ok(StartDebuggingRequestArgumentsRequest::Launch),
+            Some("attach") => Ok(StartDebuggingRequestArgumentsRequest::Attach),
+            _ => Err(
+                "The debug configuration's request must be \"launch\" or \"attach\"".to_string(),
+            ),
+        }
+    }
+
+    /// Turns a program picked in the new session dialog into a scenario
+    /// launching it
+    /// A running program cannot be attached to, as each is run by the
+    /// adapter itself.
+    fn dap_config_to_scenario(&mut self, config: DebugConfig) -> Result<DebugScenario, String> {
+        let DebugRequest::Launch(launch) = config.request else {
+            return Err(format!(
+                "{} can only launch programs, not attach to them",
+                DEBUG_ADAPTER
+            ));
+        };
+        let launch = launch_config(
+            &launch.program,
+            launch.cwd.as_deref(),
+            config.stop_on_entry.unwrap_or(false),
+        );
+        Ok(DebugScenario {
+            label: config.label,
+            adapter: config.adapter,
+            build: None,
+            config: launch.to_string(),
+            tcp_connection: None,
+        })
+    }
+
+    /// Lets the `cambridge run <file>` task beside a program be debugged
+    /// Tasks running a single procedure, or with any other flags, are left
+    /// alone, since the debugger runs the whole program as it is.
+    fn dap_locator_create_scenario(
+        &mut self,
+        _locator_name: String,
+        build_task: TaskTemplate,
+        resolved_label: String,
+        debug_adapter_name: String,
+    ) -> Option<DebugScenario> {
+        if build_task.command != "cambridge" {
+            return None;
+        }
+        let [run, program] = build_task.args.as_slice() else {
+            return None;
+        };
+        if run != "run" || program.starts_with('-') {
+            return None;
+        }
+        let launch = launch_config(program, build_task.cwd.as_deref(), false);
+        Some(DebugScenario {
+            label: resolved_label,
+            adapter: debug_adapter_name,
+            build: None,
+            config: launch.to_string(),
+            tcp_connection: None,
         })
     }
 }
 
 zed::register_extension!(CambridgeExtension);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(names: &[&str]) -> zed::GithubRelease {
+        zed::GithubRelease {
+            version: "v0.2.0".to_string(),
+            assets: names
+                .iter()
+                .map(|name| zed::GithubReleaseAsset {
+                    name: name.to_string(),
+                    download_url: format!("https://example.com/{}", name),
+                })
+                .collect(),
+        }
+    }
+
+    /// Writes `contents` to a file of its own under the temporary folder
+    fn temp_file(name: &str, contents: &[u8]) -> String {
+        let dir = std::env::temp_dir().join(format!("cambridge-zed-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn version_numbers_compare_numerically() {
+        assert_eq!(version_numbers("v0.2.10"), vec![0, 2, 10]);
+        assert_eq!(version_numbers("1.x"), vec![1, 0]);
+        assert!(version_numbers("v0.10.0") > version_numbers("v0.9.3"));
+    }
+
+    #[test]
+    fn platform_asset_names() {
+        let cases = [
+            (
+                zed::Os::Mac,
+                zed::Architecture::Aarch64,
+                "cambridge-lsp-macos-arm64",
+            ),
+            (
+                zed::Os::Mac,
+                zed::Architecture::X8664,
+                "cambridge-lsp-macos-intel",
+            ),
+            (
+                zed::Os::Linux,
+                zed::Architecture::Aarch64,
+                "cambridge-lsp-linux-arm64",
+            ),
+            (
+                zed::Os::Linux,
+                zed::Architecture::X8664,
+                "cambridge-lsp-linux",
+            ),
+            (
+                zed::Os::Windows,
+                zed::Architecture::Aarch64,
+                "cambridge-lsp-windows-arm64.exe",
+            ),
+            (
+                zed::Os::Windows,
+                zed::Architecture::X8664,
+                "cambridge-lsp.exe",
+            ),
+        ];
+        for (platform, arch, expected) in cases {
+            assert_eq!(platform_asset_name(platform, arch), Ok(expected));
+        }
+        assert!(platform_asset_name(zed::Os::Linux, zed::Architecture::X86).is_err());
+    }
+
+    #[test]
+    fn binary_assets_by_packing() {
+        let assets = release(&[
+            "cambridge-lsp-linux",
+            "cambridge-lsp-linux.gz",
+            "cambridge-lsp-linux-arm64",
+            "cambridge-lsp.exe.zip",
+        ]);
+
+        let (asset, file_type) = find_binary_asset(&assets, "cambridge-lsp-linux").unwrap();
+        assert_eq!(asset.name, "cambridge-lsp-linux.gz");
+        assert!(matches!(file_type, zed::DownloadedFileType::Gzip));
+
+        let (asset, file_type) = find_binary_asset(&assets, "cambridge-lsp-linux-arm64").unwrap();
+        assert_eq!(asset.name, "cambridge-lsp-linux-arm64");
+        assert!(matches!(file_type, zed::DownloadedFileType::Uncompressed));
+
+        let (asset, file_type) = find_binary_asset(&assets, "cambridge-lsp.exe").unwrap();
+        assert_eq!(asset.name, "cambridge-lsp.exe.zip");
+        assert!(matches!(file_type, zed::DownloadedFileType::Zip));
+
+        assert!(find_binary_asset(&assets, "cambridge-lsp-macos-arm64").is_err());
+    }
+
+    #[test]
+    fn verify_checks_the_listed_checksum() {
+        let binary = temp_file("cambridge-lsp-linux", b"hello");
+        let hello = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+        let listed = temp_file(
+            "listed",
+            format!("{}  cambridge-lsp-linux\n", hello).as_bytes(),
+        );
+        assert_eq!(verify(&binary, &listed, "cambridge-lsp-linux"), Ok(()));
+
+        let binary_mode = temp_file(
+            "binary-mode",
+            format!("{} *cambridge-lsp-linux\n", hello.to_uppercase()).as_bytes(),
+        );
+        assert_eq!(verify(&binary, &binary_mode, "cambridge-lsp-linux"), Ok(()));
+
+        let mismatched = temp_file(
+            "mismatched",
+            format!("{}  cambridge-lsp-linux\n", "0".repeat(64)).as_bytes(),
+        );
+        let err = verify(&binary, &mismatched, "cambridge-lsp-linux").unwrap_err();
+        assert!(err.starts_with("Refusing to run"), "{}", err);
+
+        let err = verify(&binary, &listed, "cambridge-lsp-linux-arm64").unwrap_err();
+        assert!(err.contains("lists no checksum"), "{}", err);
+
+        let missing = std::env::temp_dir().join("cambridge-zed-no-such-manifest");
+        assert!(verify(&binary, &missing.to_string_lossy(), "cambridge-lsp-linux").is_err());
+    }
+}
@@ -0,0 +1,104 @@
+package ast
+
+// blockSize is the number of nodes of one type allocated together
+const blockSize = 128
+
+// slab hands out nodes of one type from contiguous blocks
+type slab[T any] struct {
+	blocks  [][]T
+	current []T
+	next    int // index of the block after current
+	used    int // nodes taken from current
+}
+
+func (s *slab[T]) alloc(node T) *T {
+	if s.used == len(s.current) {
+		if s.next < len(s.blocks) {
+			s.current = s.blocks[s.next]
+		} else {
+			s.current = make([]T, blockSize)
+			s.blocks = append(s.blocks, s.current)
+		}
+		s.next++
+		s.used = 0
+	}
+	n := &s.current[s.used]
+	*n = node
+	s.used++
+	return n
+}
+
+func (s *slab[T]) reset() {
+	for _, block := range s.blocks {
+		clear(block)
+	}
+	s.current = nil
+	s.next = 0
+	s.used = 0
+}
+
+// Arena allocates the most common AST nodes in blocks rather than one at a
+// time, which cuts the parser's allocations several-fold and keeps nodes
+// that were parsed together close in memory.
+// An arena can be Reset and reused for the next parse once nothing refers
+// to the previous program any more, as when the language server re-parses
+// a document only to report its errors.
+type Arena struct {
+	identifiers slab[Identifier]
+	integers    slab[IntegerLiteral]
+	reals       slab[RealLiteral]
+	strs        slab[StringLiteral]
+	chars       slab[CharLiteral]
+	booleans    slab[BooleanLiteral]
+	prefixes    slab[PrefixExpression]
+	infixes     slab[InfixExpression]
+	accesses    slab[ArrayAccess]
+	calls       slab[CallExpression]
+	assignments slab[AssignmentStatement]
+	expressions slab[ExpressionStatement]
+	outputs     slab[OutputStatement]
+}
+
+// NewArena creates an empty arena
+func NewArena() *Arena {
+	return &Arena{}
+}
+
+// Reset makes the arena's memory available for a new program
+// Every node previously allocated from it is cleared.
+func (a *Arena) Reset() {
+	a.identifiers.reset()
+	a.integers.reset()
+	a.reals.reset()
+	a.strs.reset()
+	a.chars.reset()
+	a.booleans.reset()
+	a.prefixes.reset()
+	a.infixes.reset()
+	a.accesses.reset()
+	a.calls.reset()
+	a.assignments.reset()
+	a.expressions.reset()
+	a.outputs.reset()
+}
+
+// Each constructor copies n into the arena and returns the arena's copy
+func (a *Arena) Identifier(n Identifier) *Identifier             { return a.identifiers.alloc(n) }
+func (a *Arena) IntegerLiteral(n IntegerLiteral) *IntegerLiteral { return a.integers.alloc(n) }
+func (a *Arena) RealLiteral(n RealLiteral) *RealLiteral          { return a.reals.alloc(n) }
+func (a *Arena) StringLiteral(n StringLiteral) *StringLiteral    { return a.strs.alloc(n) }
+func (a *Arena) CharLiteral(n CharLiteral) *CharLiteral          { return a.chars.alloc(n) }
+func (a *Arena) BooleanLiteral(n BooleanLiteral) *BooleanLiteral { return a.booleans.alloc(n) }
+func (a *Arena) PrefixExpression(n PrefixExpression) *PrefixExpression {
+	return a.prefixes.alloc(n)
+}
+func (a *Arena) InfixExpression(n InfixExpression) *InfixExpression { return a.infixes.alloc(n) }
+func (a *Arena) ArrayAccess(n ArrayAccess) *ArrayAccess             { return a.accesses.alloc(n) }
+func (a *Arena) CallExpression(n CallExpression) *CallExpression    { return a.calls.alloc(n) }
+func (a *Arena) AssignmentStatement(n AssignmentStatement) *AssignmentStatement {
+	return a.assignments.alloc(n)
+}
+func (a *Arena) ExpressionStatement(n ExpressionStatement) *ExpressionStatement {
+	return a.expressions.alloc(n)
+}
+func (a *Arena) OutputStatement(n OutputStatement) *OutputStatement { return a.outputs.alloc(n) }
@@ -4,6 +4,7 @@ package ast
 import (
 	"bytes"
 	"fmt"
+	"math/big"
 	"strings"
 
 	"github.com/andrinoff/cambridge-lang/pkg/token"
@@ -54,6 +55,12 @@ func (p *Program) String() string {
 type Identifier struct {
 	Token token.Token
 	Value string
+
+	// Set by the interpreter's resolver when the name always refers to a
+	// routine parameter or FOR variable, held in Slot of the frame Depth
+	// frames out from where the identifier is used
+	Resolved    bool
+	Depth, Slot int
 }
 
 func (i *Identifier) expressionNode()      {}
@@ -61,9 +68,12 @@ func (i *Identifier) TokenLiteral() string { return i.Token.Literal }
 func (i *Identifier) String() string       { return i.Value }
 
 // IntegerLiteral represents an integer value
+// One too large for 64 bits is held whole in Big, with Value clamped to
+// the largest int64, so that it can run under --bigint.
 type IntegerLiteral struct {
 	Token token.Token
 	Value int64
+	Big   *big.Int
 }
 
 func (il *IntegerLiteral) expressionNode()      {}
@@ -172,6 +182,7 @@ type CallExpression struct {
 	Token     token.Token
 	Function  Expression // Identifier or MemberAccess
 	Arguments []Expression
+	Tail      bool // set by Resolve for a routine's last call to itself
 }
 
 func (ce *CallExpression) expressionNode()      {}
@@ -469,6 +480,7 @@ type CallStatement struct {
 	Token     token.Token
 	Name      Expression // can be Identifier or MemberAccess
 	Arguments []Expression
+	Tail      bool // set by Resolve for a routine's last call to itself
 }
 
 func (cs *CallStatement) statementNode()       {}
@@ -575,6 +587,19 @@ func (wf *WriteFileStatement) String() string {
 	return "WRITEFILE " + wf.Filename.String() + ", " + wf.Data.String()
 }
 
+// IncludeStatement represents: INCLUDE "library.cam"
+// Loading the program replaces it with the statements of the file it names.
+type IncludeStatement struct {
+	Token token.Token
+	Path  string
+}
+
+func (is *IncludeStatement) statementNode()       {}
+func (is *IncludeStatement) TokenLiteral() string { return is.Token.Literal }
+func (is *IncludeStatement) String() string {
+	return "INCLUDE \"" + is.Path + "\""
+}
+
 // TypeStatement represents: TYPE name...ENDTYPE (for records, enums, etc.)
 type TypeStatement struct {
 	Token      token.Token
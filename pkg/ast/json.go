@@ -0,0 +1,201 @@
+package ast
+
+import (
+	"bytes"
+	"encoding/json"
+	"fmt"
+	"reflect"
+	"unicode"
+	"unicode/utf8"
+)
+
+// kinds holds one of every node that can stand behind a Statement,
+// Expression or DataType field
+var kinds = []any{
+	&Identifier{}, &IntegerLiteral{}, &RealLiteral{}, &StringLiteral{},
+	&CharLiteral{}, &BooleanLiteral{}, &PrefixExpression{},
+	&InfixExpression{}, &ArrayAccess{}, &MemberAccess{}, &CallExpression{},
+	&NewExpression{}, &SuperExpression{}, &RangeExpression{},
+	&DeclareStatement{}, &ConstantStatement{}, &AssignmentStatement{},
+	&IfStatement{}, &CaseStatement{}, &ForStatement{}, &WhileStatement{},
+	&RepeatStatement{}, &ProcedureStatement{}, &FunctionStatement{},
+	&CallStatement{}, &ReturnStatement{}, &InputStatement{},
+	&OutputStatement{}, &OpenFileStatement{}, &CloseFileStatement{},
+	&ReadFileStatement{}, &WriteFileStatement{}, &IncludeStatement{},
+	&TypeStatement{}, &ClassStatement{}, &ExpressionStatement{},
+	&PrimitiveType{}, &ArrayType{}, &RecordType{}, &EnumType{},
+	&PointerType{}, &CustomType{},
+}
+
+// kindNames maps each node type in kinds to the name it is written with,
+// and kindTypes maps it back
+var kindNames, kindTypes = func() (map[reflect.Type]string, map[string]reflect.Type) {
+	names := make(map[reflect.Type]string, len(kinds))
+	types := make(map[string]reflect.Type, len(kinds))
+	for _, k := range kinds {
+		t := reflect.TypeOf(k)
+		names[t] = t.Elem().Name()
+		types[t.Elem().Name()] = t
+	}
+	return names, types
+}()
+
+// Kinds returns a new, empty node of every concrete type, for encoders that
+// must be told each type an interface field may hold
+func Kinds() []any {
+	nodes := make([]any, len(kinds))
+	for idx, k := range kinds {
+		nodes[idx] = reflect.New(reflect.TypeOf(k).Elem()).Interface()
+	}
+	return nodes
+}
+
+// MarshalJSON writes the program as JSON for tools outside Go
+// Every node is an object whose "kind" names its type, such as
+// "IfStatement", followed by its fields in lower camel case; a Token field
+// gives the node's position.
+func (p *Program) MarshalJSON() ([]byte, error) {
+	var buf bytes.Buffer
+	if err := encodeJSON(&buf, reflect.ValueOf(*p)); err != nil {
+		return nil, err
+	}
+	return buf.Bytes(), nil
+}
+
+// UnmarshalJSON reads a program written by MarshalJSON
+func (p *Program) UnmarshalJSON(data []byte) error {
+	var program Program
+	if err := decodeJSON(data, reflect.ValueOf(&program).Elem()); err != nil {
+		return err
+	}
+	*p = program
+	return nil
+}
+
+func encodeJSON(buf *bytes.Buffer, v reflect.Value) error {
+	switch v.Kind() {
+	case reflect.Interface, reflect.Pointer:
+		if v.IsNil() {
+			buf.WriteString("null")
+			return nil
+		}
+		if v.Kind() == reflect.Pointer {
+			return encodeStruct(buf, v.Elem(), kindNames[v.Type()])
+		}
+		return encodeJSON(buf, v.Elem())
+	case reflect.Struct:
+		return encodeStruct(buf, v, "")
+	case reflect.Slice:
+		if v.IsNil() {
+			buf.WriteString("null")
+			return nil
+		}
+		buf.WriteByte('[')
+		for idx := 0; idx < v.Len(); idx++ {
+			if idx > 0 {
+				buf.WriteByte(',')
+			}
+			if err := encodeJSON(buf, v.Index(idx)); err != nil {
+				return err
+			}
+		}
+		buf.WriteByte(']')
+		return nil
+	}
+	data, err := json.Marshal(v.Interface())
+	buf.Write(data)
+	return err
+}
+
+// encodeStruct writes the exported fields of v, preceded by its kind if
+// it is a node
+func encodeStruct(buf *bytes.Buffer, v reflect.Value, kind string) error {
+	buf.WriteByte('{')
+	first := true
+	if kind != "" {
+		fmt.Fprintf(buf, `"kind":%q`, kind)
+		first = false
+	}
+	for idx := 0; idx < v.NumField(); idx++ {
+		field := v.Type().Field(idx)
+		if !field.IsExported() {
+			continue
+		}
+		if !first {
+			buf.WriteByte(',')
+		}
+		first = false
+		fmt.Fprintf(buf, "%q:", jsonName(field.Name))
+		if err := encodeJSON(buf, v.Field(idx)); err != nil {
+			return err
+		}
+	}
+	buf.WriteByte('}')
+	return nil
+}
+
+func decodeJSON(data []byte, v reflect.Value) error {
+	if string(bytes.TrimSpace(data)) == "null" {
+		return nil
+	}
+	switch v.Kind() {
+	case reflect.Interface:
+		var head struct {
+			Kind string `json:"kind"`
+		}
+		if err := json.Unmarshal(data, &head); err != nil {
+			return err
+		}
+		t, ok := kindTypes[head.Kind]
+		if !ok || !t.Implements(v.Type()) {
+			return fmt.Errorf("ast: %q is not a kind of %s", head.Kind, v.Type().Name())
+		}
+		node := reflect.New(t.Elem())
+		if err := decodeJSON(data, node.Elem()); err != nil {
+			return err
+		}
+		v.Set(node)
+		return nil
+	case reflect.Pointer:
+		node := reflect.New(v.Type().Elem())
+		if err := decodeJSON(data, node.Elem()); err != nil {
+			return err
+		}
+		v.Set(node)
+		return nil
+	case reflect.Struct:
+		var fields map[string]json.RawMessage
+		if err := json.Unmarshal(data, &fields); err != nil {
+			return err
+		}
+		for idx := 0; idx < v.NumField(); idx++ {
+			field := v.Type().Field(idx)
+			if raw, ok := fields[jsonName(field.Name)]; ok && field.IsExported() {
+				if err := decodeJSON(raw, v.Field(idx)); err != nil {
+					return fmt.Errorf("%s: %w", jsonName(field.Name), err)
+				}
+			}
+		}
+		return nil
+	case reflect.Slice:
+		var items []json.RawMessage
+		if err := json.Unmarshal(data, &items); err != nil {
+			return err
+		}
+		slice := reflect.MakeSlice(v.Type(), len(items), len(items))
+		for idx, item := range items {
+			if err := decodeJSON(item, slice.Index(idx)); err != nil {
+				return err
+			}
+		}
+		v.Set(slice)
+		return nil
+	}
+	return json.Unmarshal(data, v.Addr().Interface())
+}
+
+// jsonName returns a field's name in lower camel case
+func jsonName(field string) string {
+	r, size := utf8.DecodeRuneInString(field)
+	return string(unicode.ToLower(r)) + field[size:]
+}
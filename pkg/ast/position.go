@@ -0,0 +1,49 @@
+package ast
+
+import (
+	"reflect"
+
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// Position returns the line and column where node starts in the source
+// Both are zero for nodes that carry no token, such as data types.
+func Position(node Node) (line, column int) {
+	if IsNil(node) {
+		return 0, 0
+	}
+
+	// These nodes keep an operator or trailing token rather than the one
+	// they start with
+	switch n := node.(type) {
+	case *AssignmentStatement:
+		return Position(n.Name)
+	case *ExpressionStatement:
+		return Position(n.Expression)
+	case *InfixExpression:
+		return Position(n.Left)
+	case *ArrayAccess:
+		return Position(n.Array)
+	case *MemberAccess:
+		return Position(n.Object)
+	case *CallExpression:
+		return Position(n.Function)
+	}
+
+	v := reflect.ValueOf(node)
+	if v.Kind() == reflect.Ptr {
+		v = v.Elem()
+	}
+	if v.Kind() != reflect.Struct {
+		return 0, 0
+	}
+
+	field := v.FieldByName("Token")
+	if !field.IsValid() {
+		return 0, 0
+	}
+	if tok, ok := field.Interface().(token.Token); ok {
+		return tok.Line, tok.Column
+	}
+	return 0, 0
+}
@@ -0,0 +1,118 @@
+package ast
+
+import "reflect"
+
+// Inspect traverses the AST in depth-first order, calling f for each node
+// If f returns false, the children of that node are skipped
+func Inspect(node Node, f func(Node) bool) {
+	if IsNil(node) || !f(node) {
+		return
+	}
+
+	switch n := node.(type) {
+	case *Program:
+		inspectStatements(n.Statements, f)
+
+	// Expressions
+	case *PrefixExpression:
+		Inspect(n.Right, f)
+	case *InfixExpression:
+		Inspect(n.Left, f)
+		Inspect(n.Right, f)
+	case *ArrayAccess:
+		Inspect(n.Array, f)
+		inspectExpressions(n.Indices, f)
+	case *MemberAccess:
+		Inspect(n.Object, f)
+	case *CallExpression:
+		Inspect(n.Function, f)
+		inspectExpressions(n.Arguments, f)
+	case *NewExpression:
+		inspectExpressions(n.Arguments, f)
+	case *RangeExpression:
+		Inspect(n.Start, f)
+		Inspect(n.End, f)
+
+	// Statements
+	case *DeclareStatement:
+		Inspect(n.Name, f)
+	case *ConstantStatement:
+		Inspect(n.Name, f)
+		Inspect(n.Value, f)
+	case *AssignmentStatement:
+		Inspect(n.Name, f)
+		Inspect(n.Value, f)
+	case *IfStatement:
+		Inspect(n.Condition, f)
+		inspectStatements(n.Consequence, f)
+		inspectStatements(n.Alternative, f)
+	case *CaseStatement:
+		Inspect(n.Expr, f)
+		for _, c := range n.Cases {
+			inspectExpressions(c.Values, f)
+			inspectStatements(c.Body, f)
+		}
+		inspectStatements(n.Otherwise, f)
+	case *ForStatement:
+		Inspect(n.Variable, f)
+		Inspect(n.Start, f)
+		Inspect(n.End, f)
+		Inspect(n.Step, f)
+		inspectStatements(n.Body, f)
+	case *WhileStatement:
+		Inspect(n.Condition, f)
+		inspectStatements(n.Body, f)
+	case *RepeatStatement:
+		inspectStatements(n.Body, f)
+		Inspect(n.Condition, f)
+	case *ProcedureStatement:
+		inspectStatements(n.Body, f)
+	case *FunctionStatement:
+		inspectStatements(n.Body, f)
+	case *CallStatement:
+		Inspect(n.Name, f)
+		inspectExpressions(n.Arguments, f)
+	case *ReturnStatement:
+		Inspect(n.Value, f)
+	case *InputStatement:
+		Inspect(n.Variable, f)
+	case *OutputStatement:
+		inspectExpressions(n.Values, f)
+	case *OpenFileStatement:
+		Inspect(n.Filename, f)
+	case *CloseFileStatement:
+		Inspect(n.Filename, f)
+	case *ReadFileStatement:
+		Inspect(n.Filename, f)
+		Inspect(n.Variable, f)
+	case *WriteFileStatement:
+		Inspect(n.Filename, f)
+		Inspect(n.Data, f)
+	case *ClassStatement:
+		inspectStatements(n.Members, f)
+	case *ExpressionStatement:
+		Inspect(n.Expression, f)
+	}
+}
+
+func inspectStatements(stmts []Statement, f func(Node) bool) {
+	for _, s := range stmts {
+		Inspect(s, f)
+	}
+}
+
+func inspectExpressions(exprs []Expression, f func(Node) bool) {
+	for _, e := range exprs {
+		Inspect(e, f)
+	}
+}
+
+// IsNil reports whether node is nil or a typed nil pointer, which the
+// parser can leave behind after a syntax error
+func IsNil(node Node) bool {
+	if node == nil {
+		return true
+	}
+	v := reflect.ValueOf(node)
+	return v.Kind() == reflect.Ptr && v.IsNil()
+}
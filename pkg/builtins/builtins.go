@@ -5,6 +5,7 @@ package builtins
 import (
 	"fmt"
 	"math"
+	"math/big"
 	"math/rand"
 	"strconv"
 	"strings"
@@ -37,8 +38,8 @@ func GetBuiltins() map[string]*interpreter.Builtin {
 
 		// Numeric functions
 		"INT":    {Name: "INT", Fn: intFunc},
-		"RAND":   {Name: "RAND", Fn: randFunc},
-		"RANDOM": {Name: "RANDOM", Fn: random},
+		"RAND":   {Name: "RAND", Fn: randFunc, Needs: interpreter.Randomness},
+		"RANDOM": {Name: "RANDOM", Fn: random, Needs: interpreter.Randomness},
 		"ROUND":  {Name: "ROUND", Fn: round},
 
 		// Conversion functions
@@ -46,7 +47,7 @@ func GetBuiltins() map[string]*interpreter.Builtin {
 		"STR_TO_NUM": {Name: "STR_TO_NUM", Fn: strToNum},
 
 		// File function
-		"EOF": {Name: "EOF", Fn: eof},
+		"EOF": {Name: "EOF", Fn: eof, Needs: interpreter.FileHandling},
 
 		// Math functions (additional)
 		"ABS":  {Name: "ABS", Fn: abs},
@@ -63,6 +64,33 @@ func GetBuiltins() map[string]*interpreter.Builtin {
 	}
 }
 
+// FrozenDate is the date TODAY returns in deterministic mode
+var FrozenDate = interpreter.Date{Day: 1, Month: 1, Year: 2000}
+
+// Deterministic returns the built-in functions with RAND and RANDOM drawing
+// from a generator seeded with seed and TODAY returning FrozenDate, so that
+// a program gives the same output every time it runs with the same input
+// Each call starts a new sequence, and the functions it returns must not
+// be shared between programs running at the same time.
+func Deterministic(seed int64) map[string]*interpreter.Builtin {
+	rng := rand.New(rand.NewSource(seed))
+	fns := GetBuiltins()
+	fns["RAND"].Fn = func(args ...interpreter.Object) interpreter.Object {
+		return randFrom(rng.Float64, args)
+	}
+	fns["RANDOM"].Fn = func(args ...interpreter.Object) interpreter.Object {
+		return randomFrom(rng.Float64, args)
+	}
+	fns["TODAY"].Fn = func(args ...interpreter.Object) interpreter.Object {
+		return todayFrom(frozenClock, args)
+	}
+	return fns
+}
+
+func frozenClock() time.Time {
+	return time.Date(FrozenDate.Year, time.Month(FrozenDate.Month), FrozenDate.Day, 0, 0, 0, 0, time.UTC)
+}
+
 // LENGTH(s) - returns the length of a string
 func length(args ...interpreter.Object) interpreter.Object {
 	if len(args) != 1 {
@@ -154,20 +182,22 @@ func mid(args ...interpreter.Object) interpreter.Object {
 		return newError("MID requires INTEGER as third argument")
 	}
 
-	// Convert to 0-based indexing
-	startIdx := int(start.Value) - 1
-	if startIdx < 0 {
-		startIdx = 0
+	if length.Value < 0 {
+		return newError("MID: length cannot be negative")
 	}
 
-	strLen := len(str.Value)
+	// Convert to 0-based indexing; a start or length beyond 64 bits is
+	// clamped, so is past the end of any string
+	startIdx := max(start.Value, 1) - 1
+
+	strLen := int64(len(str.Value))
 	if startIdx >= strLen {
 		return &interpreter.String{Value: ""}
 	}
 
-	endIdx := startIdx + int(length.Value)
-	if endIdx > strLen {
-		endIdx = strLen
+	endIdx := strLen
+	if length.Value < strLen-startIdx {
+		endIdx = startIdx + length.Value
 	}
 
 	return &interpreter.String{Value: str.Value[startIdx:endIdx]}
@@ -263,6 +293,10 @@ func chr(args ...interpreter.Object) interpreter.Object {
 		return newError("CHR requires INTEGER argument")
 	}
 
+	if n.Value < 0 || n.Value > unicode.MaxRune {
+		return newError("CHR: %s is not a character code", n.Inspect())
+	}
+
 	return &interpreter.Char{Value: rune(n.Value)}
 }
 
@@ -274,7 +308,17 @@ func intFunc(args ...interpreter.Object) interpreter.Object {
 
 	switch arg := args[0].(type) {
 	case *interpreter.Real:
-		return &interpreter.Integer{Value: int64(arg.Value)}
+		if arg.Dec != nil {
+			return interpreter.NewBigInteger(new(big.Int).Quo(arg.Dec.Num(), arg.Dec.Denom()))
+		}
+		if math.IsNaN(arg.Value) || math.IsInf(arg.Value, 0) {
+			return newError("INT: %s has no integer part", arg.Inspect())
+		}
+		if math.Abs(arg.Value) < math.MaxInt64 {
+			return interpreter.NewInteger(int64(arg.Value))
+		}
+		n, _ := big.NewFloat(arg.Value).Int(nil)
+		return interpreter.NewBigInteger(n)
 	case *interpreter.Integer:
 		return arg
 	default:
@@ -284,6 +328,10 @@ func intFunc(args ...interpreter.Object) interpreter.Object {
 
 // RAND(n) - returns random real number from 0 to n (exclusive)
 func randFunc(args ...interpreter.Object) interpreter.Object {
+	return randFrom(rand.Float64, args)
+}
+
+func randFrom(next func() float64, args []interpreter.Object) interpreter.Object {
 	if len(args) != 1 {
 		return newError("RAND requires 1 argument, got %d", len(args))
 	}
@@ -291,22 +339,26 @@ func randFunc(args ...interpreter.Object) interpreter.Object {
 	var max float64
 	switch arg := args[0].(type) {
 	case *interpreter.Integer:
-		max = float64(arg.Value)
+		max = toFloat(arg)
 	case *interpreter.Real:
 		max = arg.Value
 	default:
 		return newError("RAND requires numeric argument")
 	}
 
-	return &interpreter.Real{Value: rand.Float64() * max}
+	return &interpreter.Real{Value: next() * max}
 }
 
 // RANDOM() - returns random real number from 0 to 1 (inclusive)
 func random(args ...interpreter.Object) interpreter.Object {
+	return randomFrom(rand.Float64, args)
+}
+
+func randomFrom(next func() float64, args []interpreter.Object) interpreter.Object {
 	if len(args) != 0 {
 		return newError("RANDOM requires 0 arguments, got %d", len(args))
 	}
-	return &interpreter.Real{Value: rand.Float64()}
+	return &interpreter.Real{Value: next()}
 }
 
 // ROUND(x, places) - rounds to specified decimal places
@@ -320,7 +372,7 @@ func round(args ...interpreter.Object) interpreter.Object {
 	case *interpreter.Real:
 		value = arg.Value
 	case *interpreter.Integer:
-		value = float64(arg.Value)
+		value = toFloat(arg)
 	default:
 		return newError("ROUND requires numeric first argument")
 	}
@@ -344,7 +396,7 @@ func numToStr(args ...interpreter.Object) interpreter.Object {
 
 	switch arg := args[0].(type) {
 	case *interpreter.Integer:
-		return &interpreter.String{Value: strconv.FormatInt(arg.Value, 10)}
+		return &interpreter.String{Value: arg.Inspect()}
 	case *interpreter.Real:
 		return &interpreter.String{Value: strconv.FormatFloat(arg.Value, 'f', -1, 64)}
 	default:
@@ -363,9 +415,12 @@ func strToNum(args ...interpreter.Object) interpreter.Object {
 		return newError("STR_TO_NUM requires STRING argument")
 	}
 
-	// Try to parse as integer first
+	// Try to parse as integer first, keeping every digit of one too large
+	// for 64 bits, which only --bigint lets the program use
 	if i, err := strconv.ParseInt(str.Value, 10, 64); err == nil {
 		return &interpreter.Integer{Value: i}
+	} else if n, ok := new(big.Int).SetString(str.Value, 10); ok {
+		return interpreter.NewBigInteger(n)
 	}
 
 	// Try to parse as float
@@ -394,6 +449,9 @@ func abs(args ...interpreter.Object) interpreter.Object {
 
 	switch arg := args[0].(type) {
 	case *interpreter.Integer:
+		if arg.Big != nil || arg.Value == math.MinInt64 {
+			return interpreter.NewBigInteger(new(big.Int).Abs(toBig(arg)))
+		}
 		if arg.Value < 0 {
 			return &interpreter.Integer{Value: -arg.Value}
 		}
@@ -414,7 +472,7 @@ func sqrt(args ...interpreter.Object) interpreter.Object {
 	var value float64
 	switch arg := args[0].(type) {
 	case *interpreter.Integer:
-		value = float64(arg.Value)
+		value = toFloat(arg)
 	case *interpreter.Real:
 		value = arg.Value
 	default:
@@ -438,7 +496,7 @@ func pow(args ...interpreter.Object) interpreter.Object {
 
 	switch arg := args[0].(type) {
 	case *interpreter.Integer:
-		base = float64(arg.Value)
+		base = toFloat(arg)
 	case *interpreter.Real:
 		base = arg.Value
 	default:
@@ -447,7 +505,7 @@ func pow(args ...interpreter.Object) interpreter.Object {
 
 	switch arg := args[1].(type) {
 	case *interpreter.Integer:
-		exp = float64(arg.Value)
+		exp = toFloat(arg)
 	case *interpreter.Real:
 		exp = arg.Value
 	default:
@@ -541,6 +599,12 @@ func setDate(args ...interpreter.Object) interpreter.Object {
 		return newError("SETDATE requires INTEGER as third argument (Year)")
 	}
 
+	for _, arg := range []*interpreter.Integer{dayArg, monthArg, yearArg} {
+		if arg.Big != nil || arg.Value < math.MinInt32 || arg.Value > math.MaxInt32 {
+			return newError("SETDATE: %s is too large for a date", arg.Inspect())
+		}
+	}
+
 	return &interpreter.Date{
 		Day:   int(dayArg.Value),
 		Month: int(monthArg.Value),
@@ -550,16 +614,37 @@ func setDate(args ...interpreter.Object) interpreter.Object {
 
 // TODAY() - returns a DATE corresponding to the current date
 func today(args ...interpreter.Object) interpreter.Object {
+	return todayFrom(time.Now, args)
+}
+
+func todayFrom(now func() time.Time, args []interpreter.Object) interpreter.Object {
 	if len(args) != 0 {
 		return newError("TODAY requires 0 arguments, got %d", len(args))
 	}
 
-	now := time.Now()
+	date := now()
 	return &interpreter.Date{
-		Day:   now.Day(),
-		Month: int(now.Month()),
-		Year:  now.Year(),
+		Day:   date.Day(),
+		Month: int(date.Month()),
+		Year:  date.Year(),
+	}
+}
+
+// toBig returns n in arbitrary precision, whether or not it fits in 64 bits
+func toBig(n *interpreter.Integer) *big.Int {
+	if n.Big != nil {
+		return n.Big
+	}
+	return big.NewInt(n.Value)
+}
+
+// toFloat returns the nearest float64 to n
+func toFloat(n *interpreter.Integer) float64 {
+	if n.Big != nil {
+		f, _ := new(big.Float).SetInt(n.Big).Float64()
+		return f
 	}
+	return float64(n.Value)
 }
 
 func newError(format string, a ...interface{}) *interpreter.Error {
@@ -1,6 +1,9 @@
 package builtins
 
 import (
+	"math"
+	"math/big"
+	"strings"
 	"testing"
 
 	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
@@ -417,6 +420,30 @@ func TestRandom(t *testing.T) {
 	}
 }
 
+func TestDeterministic(t *testing.T) {
+	draw := func() []float64 {
+		fns := Deterministic(42)
+		var values []float64
+		for i := 0; i < 5; i++ {
+			values = append(values, fns["RAND"].Fn(&interpreter.Integer{Value: 10}).(*interpreter.Real).Value)
+			values = append(values, fns["RANDOM"].Fn().(*interpreter.Real).Value)
+		}
+		return values
+	}
+
+	first, second := draw(), draw()
+	for i := range first {
+		if first[i] != second[i] {
+			t.Fatalf("expected the same sequence for the same seed, got %v and %v", first, second)
+		}
+	}
+
+	today, ok := Deterministic(42)["TODAY"].Fn().(*interpreter.Date)
+	if !ok || *today != FrozenDate {
+		t.Errorf("expected TODAY to return %v, got %v", FrozenDate, today)
+	}
+}
+
 func TestRound(t *testing.T) {
 	tests := []struct {
 		value    float64
@@ -993,3 +1020,80 @@ func TestDateBuiltinsRegistered(t *testing.T) {
 		}
 	}
 }
+
+func TestSignatures(t *testing.T) {
+	fns := GetBuiltins()
+	for name := range fns {
+		if _, ok := Signatures[name]; !ok {
+			t.Errorf("%s has no signature", name)
+		}
+	}
+	for name, sig := range Signatures {
+		if _, ok := fns[name]; !ok || sig.Name != name {
+			t.Errorf("signature %s does not describe a builtin", name)
+		}
+		if sig.Doc == "" || !strings.HasPrefix(sig.Example, name+"(") {
+			t.Errorf("signature %s needs a description and an example calling it", name)
+		}
+		for _, p := range sig.Params {
+			if p.Doc == "" {
+				t.Errorf("parameter %s of %s is not described", p.Name, name)
+			}
+		}
+	}
+
+	expected := "LEFT(ThisString : STRING, x : INTEGER) RETURNS STRING"
+	if got := Signatures["LEFT"].String(); got != expected {
+		t.Errorf("expected %q, got %q", expected, got)
+	}
+}
+
+func TestBigIntegers(t *testing.T) {
+	huge, _ := new(big.Int).SetString("15511210043330985984000000", 10)
+	large := interpreter.NewBigInteger(huge)
+	negative := interpreter.NewBigInteger(new(big.Int).Neg(huge))
+
+	tests := []struct {
+		name     string
+		args     []interpreter.Object
+		expected string // the result's Inspect, or "error"
+	}{
+		{"NUM_TO_STR", []interpreter.Object{large}, "15511210043330985984000000"},
+		{"STR_TO_NUM", []interpreter.Object{&interpreter.String{Value: "15511210043330985984000000"}}, "15511210043330985984000000"},
+		{"ABS", []interpreter.Object{negative}, "15511210043330985984000000"},
+		{"ABS", []interpreter.Object{&interpreter.Integer{Value: math.MinInt64}}, "9223372036854775808"},
+		{"INT", []interpreter.Object{large}, "15511210043330985984000000"},
+		{"INT", []interpreter.Object{&interpreter.Real{Value: 1e20}}, "100000000000000000000"},
+		{"INT", []interpreter.Object{&interpreter.Real{Value: math.Inf(1)}}, "error"},
+		{"ROUND", []interpreter.Object{large, &interpreter.Integer{Value: 0}}, "1.5511210043330986e+25"},
+		{"SQRT", []interpreter.Object{interpreter.NewBigInteger(new(big.Int).Lsh(big.NewInt(1), 100))}, "1.125899906842624e+15"},
+		{"POW", []interpreter.Object{large, &interpreter.Integer{Value: 1}}, "1.5511210043330986e+25"},
+		{"CHR", []interpreter.Object{large}, "error"},
+		{"CHR", []interpreter.Object{&interpreter.Integer{Value: -1}}, "error"},
+		{"LEFT", []interpreter.Object{&interpreter.String{Value: "Hello"}, large}, "Hello"},
+		{"RIGHT", []interpreter.Object{&interpreter.String{Value: "Hello"}, negative}, "error"},
+		{"MID", []interpreter.Object{&interpreter.String{Value: "Hello"}, &interpreter.Integer{Value: 2}, large}, "ello"},
+		{"MID", []interpreter.Object{&interpreter.String{Value: "Hello"}, large, &interpreter.Integer{Value: 2}}, ""},
+		{"MID", []interpreter.Object{&interpreter.String{Value: "Hello"}, &interpreter.Integer{Value: 2}, &interpreter.Integer{Value: -1}}, "error"},
+		{"SETDATE", []interpreter.Object{&interpreter.Integer{Value: 1}, &interpreter.Integer{Value: 1}, large}, "error"},
+	}
+
+	builtins := GetBuiltins()
+	for _, tt := range tests {
+		result := builtins[tt.name].Fn(tt.args...)
+		got := result.Inspect()
+		if _, ok := result.(*interpreter.Error); ok {
+			got = "error"
+		}
+		if got != tt.expected {
+			t.Errorf("%s(%v): expected %s, got %s", tt.name, tt.args, tt.expected, got)
+		}
+	}
+
+	for n := 0; n < 100; n++ {
+		r, ok := builtins["RAND"].Fn(large).(*interpreter.Real)
+		if !ok || r.Value < 0 || r.Value >= 1.5511210043330986e25 {
+			t.Fatalf("RAND(%s): expected a REAL below it, got %v", large.Inspect(), r)
+		}
+	}
+}
@@ -0,0 +1,20 @@
+package builtins
+
+// FirstInsert is the year of the first 9618 insert, which lists every
+// builtin that Introduced does not
+const FirstInsert = 2021
+
+// Introduced is the year of the insert each builtin added after the first
+// was introduced in, so that a program can be checked against the insert
+// its students are given in the exam
+var Introduced = map[string]int{
+	"DAYINDEX": 2023,
+	"SETDATE":  2023,
+	"TODAY":    2023,
+}
+
+// InInsert reports whether the builtin name is listed in the insert for
+// the exams of year
+func InInsert(name string, year int) bool {
+	return Introduced[name] <= year
+}
@@ -0,0 +1,98 @@
+package builtins
+
+import "strings"
+
+// Param is a parameter of a builtin, named as in the syllabus insert
+type Param struct {
+	Name string
+	Type string
+	Doc  string
+}
+
+// Signature describes a builtin for editors: how it is called, what it
+// returns, what it does and an example of its use, as the insert gives them
+type Signature struct {
+	Name    string
+	Params  []Param
+	Returns string
+	Doc     string
+	Example string
+}
+
+// String writes the signature as pseudocode, such as
+// LEFT(ThisString : STRING, x : INTEGER) RETURNS STRING
+func (s Signature) String() string {
+	var b strings.Builder
+	b.WriteString(s.Name)
+	b.WriteString("(")
+	for idx, p := range s.Params {
+		if idx > 0 {
+			b.WriteString(", ")
+		}
+		b.WriteString(p.Name + " : " + p.Type)
+	}
+	b.WriteString(") RETURNS " + s.Returns)
+	return b.String()
+}
+
+// Signatures describes every function GetBuiltins returns, by name
+var Signatures = map[string]Signature{
+	"LENGTH": {"LENGTH", []Param{{"ThisString", "STRING", "the string to measure"}}, "INTEGER",
+		"Returns the number of characters in ThisString.", `LENGTH("Happy Days") returns 10`},
+	"LEFT": {"LEFT", []Param{{"ThisString", "STRING", "the string to take characters from"}, {"x", "INTEGER", "how many characters to take"}}, "STRING",
+		"Returns the leftmost x characters of ThisString.", `LEFT("ABCDEFGH", 3) returns "ABC"`},
+	"RIGHT": {"RIGHT", []Param{{"ThisString", "STRING", "the string to take characters from"}, {"x", "INTEGER", "how many characters to take"}}, "STRING",
+		"Returns the rightmost x characters of ThisString.", `RIGHT("ABCDEFGH", 3) returns "FGH"`},
+	"MID": {"MID", []Param{{"ThisString", "STRING", "the string to take characters from"}, {"x", "INTEGER", "the position of the first character, counting from 1"}, {"y", "INTEGER", "how many characters to take"}}, "STRING",
+		"Returns y characters of ThisString, starting at position x.", `MID("ABCDEFGH", 2, 3) returns "BCD"`},
+	"LCASE": {"LCASE", []Param{{"ThisChar", "CHAR", "the character to convert"}}, "CHAR",
+		"Returns ThisChar in lower case.", `LCASE('W') returns 'w'`},
+	"UCASE": {"UCASE", []Param{{"ThisChar", "CHAR", "the character to convert"}}, "CHAR",
+		"Returns ThisChar in upper case.", `UCASE('a') returns 'A'`},
+	"TO_UPPER": {"TO_UPPER", []Param{{"x", "STRING", "the string to convert"}}, "STRING",
+		"Returns x with every letter in upper case.", `TO_UPPER("Error 803") returns "ERROR 803"`},
+	"TO_LOWER": {"TO_LOWER", []Param{{"x", "STRING", "the string to convert"}}, "STRING",
+		"Returns x with every letter in lower case.", `TO_LOWER("JIM 803") returns "jim 803"`},
+
+	"ASC": {"ASC", []Param{{"ThisChar", "CHAR", "the character to look up"}}, "INTEGER",
+		"Returns the ASCII value of ThisChar.", `ASC('A') returns 65`},
+	"CHR": {"CHR", []Param{{"x", "INTEGER", "an ASCII value"}}, "CHAR",
+		"Returns the character whose ASCII value is x.", `CHR(87) returns 'W'`},
+
+	"INT": {"INT", []Param{{"x", "REAL", "the number to truncate"}}, "INTEGER",
+		"Returns the integer part of x.", `INT(27.5415) returns 27`},
+	"RAND": {"RAND", []Param{{"x", "INTEGER", "the upper limit, which is never returned"}}, "REAL",
+		"Returns a random number from 0 up to, but not including, x.", `RAND(87) could return 35.43`},
+	"RANDOM": {"RANDOM", nil, "REAL",
+		"Returns a random number from 0 up to, but not including, 1.", `RANDOM() could return 0.718`},
+	"ROUND": {"ROUND", []Param{{"x", "REAL", "the number to round"}, {"Places", "INTEGER", "how many decimal places to keep"}}, "REAL",
+		"Returns x rounded to Places decimal places.", `ROUND(3.14159, 2) returns 3.14`},
+
+	"NUM_TO_STR": {"NUM_TO_STR", []Param{{"x", "REAL", "the number to convert"}}, "STRING",
+		"Returns the number x as a string.", `NUM_TO_STR(87.5) returns "87.5"`},
+	"STR_TO_NUM": {"STR_TO_NUM", []Param{{"x", "STRING", "a string holding a number"}}, "REAL",
+		"Returns the number written in the string x.", `STR_TO_NUM("23.45") returns 23.45`},
+
+	"EOF": {"EOF", []Param{{"FileName", "STRING", "the name of a file open for READ"}}, "BOOLEAN",
+		"Returns TRUE if there are no more lines to read from FileName.", `EOF("Test.txt") returns TRUE after the last line has been read`},
+
+	"ABS": {"ABS", []Param{{"x", "REAL", "any number"}}, "REAL",
+		"Returns the absolute value of x.", `ABS(-4.5) returns 4.5`},
+	"SQRT": {"SQRT", []Param{{"x", "REAL", "a number that is not negative"}}, "REAL",
+		"Returns the square root of x.", `SQRT(16) returns 4`},
+	"POW": {"POW", []Param{{"Base", "REAL", "the number to raise"}, {"Exponent", "REAL", "the power to raise it to"}}, "REAL",
+		"Returns Base raised to the power Exponent.", `POW(2, 10) returns 1024`},
+
+	"DAY": {"DAY", []Param{{"ThisDate", "DATE", "the date to look at"}}, "INTEGER",
+		"Returns the day of the month of ThisDate.", `DAY(4/10/2003) returns 4`},
+	"MONTH": {"MONTH", []Param{{"ThisDate", "DATE", "the date to look at"}}, "INTEGER",
+		"Returns the month of ThisDate.", `MONTH(4/10/2003) returns 10`},
+	"YEAR": {"YEAR", []Param{{"ThisDate", "DATE", "the date to look at"}}, "INTEGER",
+		"Returns the year of ThisDate.", `YEAR(4/10/2003) returns 2003`},
+	"DAYINDEX": {"DAYINDEX", []Param{{"ThisDate", "DATE", "the date to look at"}}, "INTEGER",
+		"Returns the day of the week of ThisDate, where Sunday is 1 and Saturday is 7.", `DAYINDEX(9/5/2023) returns 3`},
+	"SETDATE": {"SETDATE", []Param{{"Day", "INTEGER", "the day of the month"}, {"Month", "INTEGER", "the month, from 1 to 12"}, {"Year", "INTEGER", "the year"}}, "DATE",
+		"Returns the date Day/Month/Year.", `SETDATE(26, 10, 2003) returns the date 26/10/2003`},
+	"TODAY": {"TODAY", nil, "DATE",
+		"Returns today's date.", `TODAY() returns the date the program runs on`},
+}
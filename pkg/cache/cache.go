@@ -0,0 +1,254 @@
+// Package cache keeps parsed programs keyed by a hash of their source, so
+// that a source seen before skips the lexer and parser
+// The CLI keeps programs on disk between runs; the language server keeps
+// the results of its analyses in memory.
+package cache
+
+import (
+	"bytes"
+	"crypto/sha256"
+	"encoding/gob"
+	"encoding/hex"
+	"fmt"
+	"io/fs"
+	"os"
+	"path/filepath"
+	"reflect"
+	"runtime/debug"
+	"sort"
+	"strings"
+	"sync"
+	"time"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+)
+
+// formatVersion is part of every key, so that programs cached by another
+// build, or one with a differently shaped syntax tree, are never read back
+var formatVersion = format()
+
+// format returns the version of the build with a hash of every type gob
+// may write for a program, field by field
+func format() string {
+	var shape strings.Builder
+	seen := map[reflect.Type]bool{}
+	var describe func(t reflect.Type)
+	describe = func(t reflect.Type) {
+		for t.Kind() == reflect.Pointer || t.Kind() == reflect.Slice || t.Kind() == reflect.Array || t.Kind() == reflect.Map {
+			if t.Kind() == reflect.Map {
+				describe(t.Key())
+			}
+			t = t.Elem()
+		}
+		if t.Kind() != reflect.Struct || seen[t] {
+			return
+		}
+		seen[t] = true
+		fmt.Fprintf(&shape, "%s{", t)
+		for n := 0; n < t.NumField(); n++ {
+			if f := t.Field(n); f.IsExported() {
+				fmt.Fprintf(&shape, "%s %s;", f.Name, f.Type)
+			}
+		}
+		shape.WriteString("}")
+		for n := 0; n < t.NumField(); n++ {
+			if f := t.Field(n); f.IsExported() {
+				describe(f.Type)
+			}
+		}
+	}
+	describe(reflect.TypeOf(ast.Program{}))
+	for _, node := range ast.Kinds() {
+		describe(reflect.TypeOf(node))
+	}
+
+	version := "unknown"
+	if info, ok := debug.ReadBuildInfo(); ok {
+		version = info.Main.Version
+	}
+	sum := sha256.Sum256([]byte(shape.String()))
+	return "ast-" + version + "-" + hex.EncodeToString(sum[:8])
+}
+
+// Key returns the hash identifying source
+func Key(source string) string {
+	sum := sha256.Sum256([]byte(formatVersion + "\x00" + source))
+	return hex.EncodeToString(sum[:])
+}
+
+// Memory holds up to a fixed number of values, forgetting the oldest first
+// It is safe for concurrent use.
+type Memory[V any] struct {
+	mu       sync.Mutex
+	capacity int
+	values   map[string]V
+	order    []string // keys from oldest to newest
+}
+
+// NewMemory creates a cache holding at most capacity values
+func NewMemory[V any](capacity int) *Memory[V] {
+	return &Memory[V]{capacity: capacity, values: make(map[string]V)}
+}
+
+// Get returns the value stored under key
+func (m *Memory[V]) Get(key string) (V, bool) {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	v, ok := m.values[key]
+	return v, ok
+}
+
+// Put stores value under key
+func (m *Memory[V]) Put(key string, value V) {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	if _, ok := m.values[key]; !ok {
+		if len(m.order) == m.capacity {
+			delete(m.values, m.order[0])
+			m.order = m.order[1:]
+		}
+		m.order = append(m.order, key)
+	}
+	m.values[key] = value
+}
+
+// diskLimit is how many bytes of programs a Disk keeps
+const diskLimit = 64 << 20
+
+// Disk keeps parsed programs as files in a folder, forgetting those used
+// least recently once they take up more than diskLimit
+// A nil Disk caches nothing, so callers need not check whether a cache
+// folder was available.
+type Disk struct {
+	dir   string
+	limit int64
+}
+
+// DefaultDir returns the folder the CLI caches programs in: the value of
+// CAMBRIDGE_CACHE_DIR if set, otherwise the user's cache folder; it is empty
+// if caching is turned off with CAMBRIDGE_CACHE_DIR=off or there is nowhere
+// to cache
+func DefaultDir() string {
+	if dir := os.Getenv("CAMBRIDGE_CACHE_DIR"); dir != "" {
+		if dir == "off" {
+			return ""
+		}
+		return dir
+	}
+	dir, err := os.UserCacheDir()
+	if err != nil {
+		return ""
+	}
+	return filepath.Join(dir, "cambridge", "programs")
+}
+
+// NewDisk returns a cache in dir, or nil if dir is empty
+func NewDisk(dir string) *Disk {
+	if dir == "" {
+		return nil
+	}
+	return &Disk{dir: dir, limit: diskLimit}
+}
+
+func (d *Disk) path(key string) string {
+	return filepath.Join(d.dir, key[:2], key+".gob")
+}
+
+// Load returns the program cached under key
+// A missing or unreadable entry is a miss. A hit marks the entry as used
+// now, so that pruning keeps it over those not used since.
+func (d *Disk) Load(key string) (*ast.Program, bool) {
+	if d == nil {
+		return nil, false
+	}
+	path := d.path(key)
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return nil, false
+	}
+	var program ast.Program
+	if err := gob.NewDecoder(bytes.NewReader(data)).Decode(&program); err != nil {
+		return nil, false
+	}
+	now := time.Now()
+	os.Chtimes(path, now, now)
+	return &program, true
+}
+
+// Store caches program under key
+// The program must not have been optimized yet, since it is read back in
+// place of what the parser produced.
+func (d *Disk) Store(key string, program *ast.Program) error {
+	if d == nil {
+		return nil
+	}
+	var buf bytes.Buffer
+	if err := gob.NewEncoder(&buf).Encode(program); err != nil {
+		return err
+	}
+
+	path := d.path(key)
+	if err := os.MkdirAll(filepath.Dir(path), 0755); err != nil {
+		return err
+	}
+	// Write to a temporary file first so a concurrent Load never sees a
+	// partial entry
+	tmp, err := os.CreateTemp(filepath.Dir(path), key+".*")
+	if err != nil {
+		return err
+	}
+	if _, err := tmp.Write(buf.Bytes()); err != nil {
+		tmp.Close()
+		os.Remove(tmp.Name())
+		return err
+	}
+	if err := tmp.Close(); err != nil {
+		os.Remove(tmp.Name())
+		return err
+	}
+	if err := os.Rename(tmp.Name(), path); err != nil {
+		return err
+	}
+	d.prune()
+	return nil
+}
+
+// prune removes the entries used least recently until the rest fit in the
+// limit
+// An entry that cannot be removed is left for a later prune.
+func (d *Disk) prune() {
+	type entry struct {
+		path string
+		size int64
+		used time.Time
+	}
+	var entries []entry
+	var total int64
+	_ = filepath.WalkDir(d.dir, func(path string, e fs.DirEntry, err error) error {
+		if err != nil || e.IsDir() || filepath.Ext(path) != ".gob" {
+			return nil
+		}
+		if info, err := e.Info(); err == nil {
+			entries = append(entries, entry{path: path, size: info.Size(), used: info.ModTime()})
+			total += info.Size()
+		}
+		return nil
+	})
+	sort.Slice(entries, func(i, j int) bool { return entries[i].used.Before(entries[j].used) })
+	for _, e := range entries {
+		if total <= d.limit {
+			break
+		}
+		if os.Remove(e.path) == nil {
+			total -= e.size
+		}
+	}
+}
+
+func init() {
+	// Every node that can appear behind a Statement, Expression or
+	// DataType field
+	for _, node := range ast.Kinds() {
+		gob.Register(node)
+	}
+}
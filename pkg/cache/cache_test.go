@@ -0,0 +1,151 @@
+package cache
+
+import (
+	"os"
+	"path/filepath"
+	"testing"
+	"time"
+
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+const program = `TYPE Point
+    DECLARE X : INTEGER
+ENDTYPE
+DECLARE Scores : ARRAY[1:3] OF REAL
+CONSTANT Limit = 10
+
+FUNCTION Twice(n : INTEGER) RETURNS INTEGER
+    RETURN n * 2
+ENDFUNCTION
+
+FOR i <- 1 TO 3
+    Scores[i] <- Twice(i) / 4
+NEXT i
+IF Scores[1] > 0.5 AND NOT FALSE THEN
+    OUTPUT "big", 'c'
+ELSE
+    CASE OF Limit
+        1 TO 5 : OUTPUT "small"
+        OTHERWISE : OUTPUT "other"
+    ENDCASE
+ENDIF`
+
+func TestKey(t *testing.T) {
+	if Key("OUTPUT 1") != Key("OUTPUT 1") || Key("OUTPUT 1") == Key("OUTPUT 2") {
+		t.Error("expected keys to depend only on the source")
+	}
+
+	if format() != formatVersion {
+		t.Errorf("expected the same format each time, got %q and %q", format(), formatVersion)
+	}
+	key := Key("OUTPUT 1")
+	defer func(saved string) { formatVersion = saved }(formatVersion)
+	formatVersion = "ast-another-build"
+	if Key("OUTPUT 1") == key {
+		t.Error("expected another build's key for the same source to differ")
+	}
+}
+
+func TestMemory(t *testing.T) {
+	m := NewMemory[int](2)
+	m.Put("a", 1)
+	m.Put("b", 2)
+	m.Put("a", 3) // replacing a value keeps its place
+	m.Put("c", 4)
+
+	if _, ok := m.Get("a"); ok {
+		t.Error("expected the oldest value to be forgotten")
+	}
+	if v, ok := m.Get("b"); !ok || v != 2 {
+		t.Errorf("expected b = 2, got %d, %t", v, ok)
+	}
+	if v, ok := m.Get("c"); !ok || v != 4 {
+		t.Errorf("expected c = 4, got %d, %t", v, ok)
+	}
+}
+
+func TestDisk(t *testing.T) {
+	p := parser.New(lexer.New(program))
+	tree := p.ParseProgram()
+	if len(p.Errors()) > 0 {
+		t.Fatalf("parser errors: %v", p.Errors())
+	}
+
+	d := NewDisk(t.TempDir())
+	key := Key(program)
+	if _, ok := d.Load(key); ok {
+		t.Fatal("expected a miss before anything is stored")
+	}
+	if err := d.Store(key, tree); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	loaded, ok := d.Load(key)
+	if !ok {
+		t.Fatal("expected the stored program")
+	}
+	if loaded.String() != tree.String() {
+		t.Errorf("expected the same program back\nstored:\n%s\nloaded:\n%s", tree.String(), loaded.String())
+	}
+
+	// A damaged entry is treated as missing
+	if err := os.WriteFile(d.path(key), []byte("not a program"), 0644); err != nil {
+		t.Fatal(err)
+	}
+	if _, ok := d.Load(key); ok {
+		t.Error("expected a damaged entry to be a miss")
+	}
+}
+
+func TestDiskLimit(t *testing.T) {
+	d := NewDisk(t.TempDir())
+	sources := []string{"OUTPUT 1", "OUTPUT 2", "OUTPUT 3"}
+	store := func(source string) {
+		if err := d.Store(Key(source), parser.New(lexer.New(source)).ParseProgram()); err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+	}
+	store(sources[0])
+	store(sources[1])
+	for n, source := range sources[:2] {
+		then := time.Now().Add(time.Duration(n-2) * time.Hour)
+		if err := os.Chtimes(d.path(Key(source)), then, then); err != nil {
+			t.Fatal(err)
+		}
+	}
+	info, err := os.Stat(d.path(Key(sources[0])))
+	if err != nil {
+		t.Fatal(err)
+	}
+	d.limit = info.Size()*2 + info.Size()/2 // room for two entries
+
+	// Loading the oldest makes the other the one used least recently
+	if _, ok := d.Load(Key(sources[0])); !ok {
+		t.Fatal("expected the first program")
+	}
+	store(sources[2])
+	for n, kept := range []bool{true, false, true} {
+		if _, ok := d.Load(Key(sources[n])); ok != kept {
+			t.Errorf("%s: expected kept to be %t", sources[n], kept)
+		}
+	}
+}
+
+func TestDiskOff(t *testing.T) {
+	t.Setenv("CAMBRIDGE_CACHE_DIR", "off")
+	d := NewDisk(DefaultDir())
+	if d != nil {
+		t.Fatalf("expected no cache, got one in %s", d.dir)
+	}
+	if err := d.Store(Key(program), nil); err != nil {
+		t.Errorf("expected a nil cache to ignore stores, got %v", err)
+	}
+
+	dir := filepath.Join(t.TempDir(), "programs")
+	t.Setenv("CAMBRIDGE_CACHE_DIR", dir)
+	if got := DefaultDir(); got != dir {
+		t.Errorf("expected %s, got %s", dir, got)
+	}
+}
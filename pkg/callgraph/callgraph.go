@@ -0,0 +1,225 @@
+// Package callgraph extracts which procedures and functions call which
+package callgraph
+
+import (
+	"fmt"
+	"sort"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+)
+
+// Main is the name of the node representing top-level program statements
+const Main = "(main)"
+
+// Routine is a node in the call graph
+type Routine struct {
+	Name      string `json:"name"`
+	Kind      string `json:"kind"` // "PROGRAM", "PROCEDURE" or "FUNCTION"
+	Line      int    `json:"line"`
+	Recursive bool   `json:"recursive"`
+	Reachable bool   `json:"reachable"`
+}
+
+// Call is a single call site from one routine to another
+type Call struct {
+	Caller string `json:"caller"`
+	Callee string `json:"callee"`
+	Line   int    `json:"line"`
+}
+
+// Graph is the call graph of a program
+type Graph struct {
+	Routines []Routine `json:"routines"`
+	Calls    []Call    `json:"calls"`
+}
+
+// routineDef is a routine body waiting to be scanned for calls
+type routineDef struct {
+	name  string
+	class string // enclosing class, empty for top-level routines
+	body  []ast.Statement
+}
+
+// Build computes the call graph of program
+// Calls to built-in functions are not included
+func Build(program *ast.Program) *Graph {
+	g := &Graph{Routines: []Routine{{Name: Main, Kind: "PROGRAM", Line: 1}}}
+	index := map[string]int{Main: 0}
+	methods := map[string][]string{} // method name -> qualified names
+
+	var defs []routineDef
+	var topLevel []ast.Statement
+
+	addRoutine := func(name, kind string, line int) {
+		index[name] = len(g.Routines)
+		g.Routines = append(g.Routines, Routine{Name: name, Kind: kind, Line: line})
+	}
+
+	for _, stmt := range program.Statements {
+		switch s := stmt.(type) {
+		case *ast.ProcedureStatement:
+			addRoutine(s.Name, "PROCEDURE", s.Token.Line)
+			defs = append(defs, routineDef{name: s.Name, body: s.Body})
+		case *ast.FunctionStatement:
+			addRoutine(s.Name, "FUNCTION", s.Token.Line)
+			defs = append(defs, routineDef{name: s.Name, body: s.Body})
+		case *ast.ClassStatement:
+			for _, member := range s.Members {
+				switch m := member.(type) {
+				case *ast.ProcedureStatement:
+					name := s.Name + "." + m.Name
+					addRoutine(name, "PROCEDURE", m.Token.Line)
+					methods[m.Name] = append(methods[m.Name], name)
+					defs = append(defs, routineDef{name: name, class: s.Name, body: m.Body})
+				case *ast.FunctionStatement:
+					name := s.Name + "." + m.Name
+					addRoutine(name, "FUNCTION", m.Token.Line)
+					methods[m.Name] = append(methods[m.Name], name)
+					defs = append(defs, routineDef{name: name, class: s.Name, body: m.Body})
+				}
+			}
+		default:
+			topLevel = append(topLevel, stmt)
+		}
+	}
+
+	// resolve maps a called name to the routines it may refer to
+	resolve := func(callee ast.Expression, class string) []string {
+		switch c := callee.(type) {
+		case *ast.Identifier:
+			if class != "" {
+				if _, ok := index[class+"."+c.Value]; ok {
+					return []string{class + "." + c.Value}
+				}
+			}
+			if _, ok := index[c.Value]; ok {
+				return []string{c.Value}
+			}
+		case *ast.MemberAccess:
+			return methods[c.Member]
+		}
+		return nil
+	}
+
+	scan := func(caller, class string, body []ast.Statement) {
+		for _, stmt := range body {
+			ast.Inspect(stmt, func(n ast.Node) bool {
+				var targets []string
+				line := 0
+				switch n := n.(type) {
+				case *ast.CallStatement:
+					targets, line = resolve(n.Name, class), n.Token.Line
+				case *ast.CallExpression:
+					targets, line = resolve(n.Function, class), n.Token.Line
+				case *ast.NewExpression:
+					if _, ok := index[n.ClassName+".NEW"]; ok {
+						targets, line = []string{n.ClassName + ".NEW"}, n.Token.Line
+					}
+				}
+				for _, target := range targets {
+					g.Calls = append(g.Calls, Call{Caller: caller, Callee: target, Line: line})
+				}
+				return true
+			})
+		}
+	}
+
+	scan(Main, "", topLevel)
+	for _, def := range defs {
+		scan(def.name, def.class, def.body)
+	}
+
+	g.markReachable(index)
+	g.markRecursive()
+	return g
+}
+
+// Callees returns the distinct routines called by name, in sorted order
+func (g *Graph) Callees(name string) []string {
+	seen := map[string]bool{}
+	var out []string
+	for _, c := range g.Calls {
+		if c.Caller == name && !seen[c.Callee] {
+			seen[c.Callee] = true
+			out = append(out, c.Callee)
+		}
+	}
+	sort.Strings(out)
+	return out
+}
+
+// markReachable flags every routine that can be reached from the main program
+func (g *Graph) markReachable(index map[string]int) {
+	stack := []string{Main}
+	for len(stack) > 0 {
+		name := stack[len(stack)-1]
+		stack = stack[:len(stack)-1]
+
+		r := &g.Routines[index[name]]
+		if r.Reachable {
+			continue
+		}
+		r.Reachable = true
+		stack = append(stack, g.Callees(name)...)
+	}
+}
+
+// markRecursive flags routines that can end up calling themselves, either
+// directly or through a cycle of other routines
+func (g *Graph) markRecursive() {
+	for i := range g.Routines {
+		start := g.Routines[i].Name
+		visited := map[string]bool{}
+		stack := g.Callees(start)
+
+		for len(stack) > 0 {
+			name := stack[len(stack)-1]
+			stack = stack[:len(stack)-1]
+
+			if name == start {
+				g.Routines[i].Recursive = true
+				break
+			}
+			if visited[name] {
+				continue
+			}
+			visited[name] = true
+			stack = append(stack, g.Callees(name)...)
+		}
+	}
+}
+
+// DOT renders the graph in Graphviz format
+// Recursive routines are drawn in red and unreachable ones dashed
+func (g *Graph) DOT() string {
+	var out strings.Builder
+	out.WriteString("digraph calls {\n")
+
+	for _, r := range g.Routines {
+		var attrs []string
+		if r.Name == Main {
+			attrs = append(attrs, "shape=box")
+		}
+		if r.Recursive {
+			attrs = append(attrs, "color=red")
+		}
+		if !r.Reachable {
+			attrs = append(attrs, "style=dashed")
+		}
+		if len(attrs) > 0 {
+			fmt.Fprintf(&out, "  %q [%s];\n", r.Name, strings.Join(attrs, ", "))
+		} else {
+			fmt.Fprintf(&out, "  %q;\n", r.Name)
+		}
+	}
+
+	for _, r := range g.Routines {
+		for _, callee := range g.Callees(r.Name) {
+			fmt.Fprintf(&out, "  %q -> %q;\n", r.Name, callee)
+		}
+	}
+
+	out.WriteString("}\n")
+	return out.String()
+}
@@ -0,0 +1,144 @@
+package callgraph
+
+import (
+	"strings"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+const program = `FUNCTION Fact(N : INTEGER) RETURNS INTEGER
+    IF N <= 1 THEN
+        RETURN 1
+    ENDIF
+    RETURN N * Fact(N - 1)
+ENDFUNCTION
+
+FUNCTION IsEven(N : INTEGER) RETURNS BOOLEAN
+    IF N = 0 THEN
+        RETURN TRUE
+    ENDIF
+    RETURN IsOdd(N - 1)
+ENDFUNCTION
+
+FUNCTION IsOdd(N : INTEGER) RETURNS BOOLEAN
+    IF N = 0 THEN
+        RETURN FALSE
+    ENDIF
+    RETURN IsEven(N - 1)
+ENDFUNCTION
+
+PROCEDURE Report(N : INTEGER)
+    OUTPUT Fact(N), " ", LENGTH("abc")
+ENDPROCEDURE
+
+PROCEDURE Unused()
+    CALL Report(1)
+ENDPROCEDURE
+
+CALL Report(5)
+OUTPUT IsEven(4)`
+
+func build(t *testing.T, input string) *Graph {
+	t.Helper()
+	p := parser.New(lexer.New(input))
+	prog := p.ParseProgram()
+	if len(p.Errors()) > 0 {
+		t.Fatalf("parser errors: %v", p.Errors())
+	}
+	return Build(prog)
+}
+
+func routine(t *testing.T, g *Graph, name string) Routine {
+	t.Helper()
+	for _, r := range g.Routines {
+		if r.Name == name {
+			return r
+		}
+	}
+	t.Fatalf("routine %s not found", name)
+	return Routine{}
+}
+
+func TestCallees(t *testing.T) {
+	g := build(t, program)
+
+	callees := g.Callees(Main)
+	if strings.Join(callees, ",") != "IsEven,Report" {
+		t.Errorf("unexpected main callees: %v", callees)
+	}
+
+	// Built-in LENGTH is not part of the graph
+	callees = g.Callees("Report")
+	if strings.Join(callees, ",") != "Fact" {
+		t.Errorf("unexpected Report callees: %v", callees)
+	}
+}
+
+func TestRecursionDetection(t *testing.T) {
+	g := build(t, program)
+
+	tests := map[string]bool{
+		"Fact":   true,
+		"IsEven": true,
+		"IsOdd":  true,
+		"Report": false,
+		"Unused": false,
+	}
+
+	for name, expected := range tests {
+		if r := routine(t, g, name); r.Recursive != expected {
+			t.Errorf("%s: expected recursive=%t, got %t", name, expected, r.Recursive)
+		}
+	}
+}
+
+func TestReachability(t *testing.T) {
+	g := build(t, program)
+
+	if !routine(t, g, "Fact").Reachable {
+		t.Errorf("expected Fact to be reachable")
+	}
+	if routine(t, g, "Unused").Reachable {
+		t.Errorf("expected Unused to be unreachable")
+	}
+}
+
+func TestMethodCalls(t *testing.T) {
+	g := build(t, `CLASS Pet
+    PUBLIC PROCEDURE NEW()
+        CALL Speak()
+    ENDPROCEDURE
+    PUBLIC PROCEDURE Speak()
+        OUTPUT "..."
+    ENDPROCEDURE
+ENDCLASS
+
+DECLARE P : Pet
+P <- NEW Pet()
+CALL P.Speak()`)
+
+	if callees := g.Callees(Main); strings.Join(callees, ",") != "Pet.NEW,Pet.Speak" {
+		t.Errorf("unexpected main callees: %v", callees)
+	}
+	if callees := g.Callees("Pet.NEW"); strings.Join(callees, ",") != "Pet.Speak" {
+		t.Errorf("unexpected constructor callees: %v", callees)
+	}
+}
+
+func TestDOT(t *testing.T) {
+	dot := build(t, program).DOT()
+
+	for _, want := range []string{
+		`digraph calls {`,
+		`"Fact" [color=red];`,
+		`"Unused" [style=dashed];`,
+		`"(main)" -> "Report";`,
+		`"IsEven" -> "IsOdd";`,
+	} {
+		if !strings.Contains(dot, want) {
+			t.Errorf("DOT output missing %q:\n%s", want, dot)
+		}
+	}
+}
@@ -0,0 +1,358 @@
+// Package cambridge embeds Cambridge Pseudocode in Go programs, such as web
+// services, editors and autograders, without running the CLI
+//
+// A host parses source once and may then run it any number of times, from
+// several goroutines at once:
+//
+//	prog := cambridge.Parse("main.cam", source)
+//	if !prog.OK() {
+//		return prog.Diagnostics()
+//	}
+//	interp := cambridge.New()
+//	interp.SetInput(strings.NewReader("Ada\n"))
+//	interp.SetOutput(&out)
+//	if err := interp.Run(prog); err != nil {
+//		return err
+//	}
+//	total, _ := interp.Variable("Total")
+package cambridge
+
+import (
+	"fmt"
+	"io"
+	"iter"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+// Program is source that has been parsed and analysed, ready to run
+type Program struct {
+	Name   string
+	Source string
+
+	tree        *ast.Program
+	diagnostics []diagnostic.Diagnostic
+}
+
+// Parse parses and analyses source, which is reported under name
+// Problems are listed by Diagnostics rather than returned, so a host can
+// show all of them.
+func Parse(name, source string) *Program {
+	p := parser.New(lexer.New(source))
+	tree := p.ParseProgram()
+	interpreter.Resolve(tree)
+	return &Program{Name: name, Source: source, tree: tree, diagnostics: p.Diagnostics()}
+}
+
+// OK reports whether the program parsed without errors and can be run
+func (p *Program) OK() bool {
+	_, failed := p.firstError()
+	return !failed
+}
+
+// firstError returns the first diagnostic that stops p from running
+func (p *Program) firstError() (diagnostic.Diagnostic, bool) {
+	for _, d := range p.diagnostics {
+		if d.Severity == diagnostic.Error {
+			return d, true
+		}
+	}
+	return diagnostic.Diagnostic{}, false
+}
+
+// Diagnostics returns the problems found while parsing, in source order
+func (p *Program) Diagnostics() []diagnostic.Diagnostic {
+	return p.diagnostics
+}
+
+// AST returns the syntax tree, for hosts that analyse programs further
+// It must not be changed while the program may be running.
+func (p *Program) AST() *ast.Program {
+	return p.tree
+}
+
+// Error is a problem that stopped a program from running to the end
+type Error struct {
+	Program    string
+	Diagnostic diagnostic.Diagnostic
+	Calls      []interpreter.Call // for runtime errors, innermost first
+}
+
+func (e *Error) Error() string {
+	if e.Diagnostic.Line > 0 {
+		return fmt.Sprintf("%s:%d:%d: %s", e.Program, e.Diagnostic.Line, e.Diagnostic.Column, e.Diagnostic.Message)
+	}
+	return fmt.Sprintf("%s: %s", e.Program, e.Diagnostic.Message)
+}
+
+// Interpreter runs programs for a host
+// Unlike the CLI it reads no input and discards output unless given a
+// reader and writer. One Interpreter runs one program at a time; use
+// several to run programs in parallel.
+type Interpreter struct {
+	input         io.Reader
+	output        io.Writer
+	files         interpreter.FileSystem
+	arith         interpreter.Arithmetic
+	maxDepth      int
+	maxSize       int
+	tailCalls     bool
+	deterministic bool
+	seed          int64
+	defined       []*interpreter.Builtin
+	disabled      []interpreter.Capability
+	debugger      interpreter.Debugger
+
+	last *interpreter.Interpreter // the most recent run
+}
+
+// New creates an interpreter with the same defaults as cambridge run
+func New() *Interpreter {
+	return &Interpreter{
+		input:    strings.NewReader(""),
+		output:   io.Discard,
+		files:    interpreter.OSFiles{},
+		maxDepth: interpreter.DefaultMaxDepth,
+	}
+}
+
+// SetInput sets where INPUT reads from
+func (i *Interpreter) SetInput(r io.Reader) {
+	i.input = r
+}
+
+// SetOutput sets where OUTPUT writes to
+func (i *Interpreter) SetOutput(w io.Writer) {
+	i.output = w
+}
+
+// SetFiles sets where OPENFILE finds files, which is the disk unless
+// changed; interpreter.NewMemoryFiles keeps them in memory instead
+func (i *Interpreter) SetFiles(fs interpreter.FileSystem) {
+	i.files = fs
+}
+
+// SetArithmetic selects arbitrary-precision INTEGER or decimal REAL
+// arithmetic, as run --bigint and language.real do
+func (i *Interpreter) SetArithmetic(a interpreter.Arithmetic) {
+	i.arith = a
+}
+
+// SetMaxDepth sets how deeply routine calls may nest
+// A host allowing deeper recursion than interpreter.DefaultMaxDepth should
+// let the Go stack grow to match, as interpreter.HostStack describes.
+func (i *Interpreter) SetMaxDepth(depth int) {
+	i.maxDepth = depth
+}
+
+// SetMaxSize limits how many characters a STRING, or elements an ARRAY,
+// may hold, for a host that must not run out of memory; there is no limit
+// unless one is set
+func (i *Interpreter) SetMaxSize(size int) {
+	i.maxSize = size
+}
+
+// SetTailCalls lets a routine that ends by calling itself reuse its frame
+func (i *Interpreter) SetTailCalls(enabled bool) {
+	i.tailCalls = enabled
+}
+
+// SetDeterministic seeds RAND and RANDOM with seed and freezes TODAY, so
+// that every run gives the same output for the same input
+func (i *Interpreter) SetDeterministic(seed int64) {
+	i.deterministic = true
+	i.seed = seed
+}
+
+// Disable turns off groups of statements and builtins, such as file
+// handling in a playground with no disk; a program using one stops with an
+// error saying it is disabled in this environment
+func (i *Interpreter) Disable(caps ...interpreter.Capability) {
+	i.disabled = append(i.disabled, caps...)
+}
+
+// SetDebugger has d told of each statement before it runs, so that it can
+// pause the program there, as the debug adapter does
+func (i *Interpreter) SetDebugger(d interpreter.Debugger) {
+	i.debugger = d
+}
+
+// Define adds a routine written in Go that programs can call by name, with
+// CALL as a procedure or in an expression as a function, replacing any
+// standard builtin of the same name
+// Each argument is checked against params before fn is called, and an
+// INTEGER is converted for a REAL parameter; nil params accept anything.
+// fn returns the result, or an *interpreter.Error to stop the program.
+func (i *Interpreter) Define(name string, params []interpreter.ObjectType, fn interpreter.BuiltinFunction) {
+	i.defined = append(i.defined, &interpreter.Builtin{Name: name, Fn: fn, Params: params})
+}
+
+// Run runs p from the start with fresh variables
+// It returns an *Error if p did not parse or stopped with a runtime error.
+func (i *Interpreter) Run(p *Program) error {
+	if d, ok := p.firstError(); ok {
+		return &Error{Program: p.Name, Diagnostic: d}
+	}
+
+	interp := i.prepare()
+	if err, ok := interp.Eval(p.tree).(*interpreter.Error); ok {
+		return &Error{Program: p.Name, Diagnostic: err.Diagnostic(), Calls: err.Calls}
+	}
+	return nil
+}
+
+// Execution is a program running in instalments of fuel, as described by
+// interpreter.Execution
+type Execution struct {
+	program *Program
+	run     *interpreter.Execution
+	err     error // for a program that did not parse
+}
+
+// Start prepares p to run from the start with fresh variables, a few
+// statements at a time; nothing runs until Continue
+// A service can share one goroutine between many programs this way, and
+// stop any that run for too long.
+func (i *Interpreter) Start(p *Program) *Execution {
+	if d, ok := p.firstError(); ok {
+		return &Execution{program: p, err: &Error{Program: p.Name, Diagnostic: d}}
+	}
+	return &Execution{program: p, run: i.prepare().Start(p.tree)}
+}
+
+// Continue runs the program until it has used fuel more units or has
+// finished, and reports whether it has finished and any error it stopped
+// with
+func (e *Execution) Continue(fuel int) (bool, error) {
+	if e.run == nil {
+		return true, e.err
+	}
+	if !e.run.Continue(fuel) {
+		return false, nil
+	}
+	if err, ok := e.run.Result().(*interpreter.Error); ok {
+		return true, &Error{Program: e.program.Name, Diagnostic: err.Diagnostic(), Calls: err.Calls}
+	}
+	return true, nil
+}
+
+// Stop abandons a program that has not finished
+func (e *Execution) Stop() {
+	if e.run != nil {
+		e.run.Stop()
+	}
+}
+
+// Steps runs p from the start one statement at a time, as described by
+// interpreter.Interpreter.Steps
+// A program that did not parse gives a single step holding its first
+// error.
+func (i *Interpreter) Steps(p *Program) iter.Seq[interpreter.Step] {
+	if d, ok := p.firstError(); ok {
+		return func(yield func(interpreter.Step) bool) {
+			yield(interpreter.Step{
+				Line:   d.Line,
+				Column: d.Column,
+				Err:    &interpreter.Error{Message: d.Message, Code: d.Code, Args: d.Args, Line: d.Line, Column: d.Column},
+			})
+		}
+	}
+	return i.prepare().Steps(p.tree)
+}
+
+// Snapshot captures the state of the last run, as described by
+// interpreter.Interpreter.Snapshot
+// It is usually taken from a step, to come back to that point later.
+func (i *Interpreter) Snapshot() (*interpreter.Snapshot, error) {
+	if i.last == nil {
+		return nil, fmt.Errorf("no program has run")
+	}
+	return i.last.Snapshot()
+}
+
+// Resume runs p from where s was taken, with this interpreter's settings
+// s must come from a run of p, here or in another process.
+func (i *Interpreter) Resume(p *Program, s *interpreter.Snapshot) error {
+	interp := i.prepare()
+	if err := interp.Restore(p.tree, s); err != nil {
+		return err
+	}
+	if err, ok := interp.Resume(p.tree).(*interpreter.Error); ok {
+		return &Error{Program: p.Name, Diagnostic: err.Diagnostic(), Calls: err.Calls}
+	}
+	return nil
+}
+
+// Session runs programs one after another in the same variables, as the
+// cells of a notebook or the lines of a REPL are
+type Session struct {
+	host   *Interpreter
+	interp *interpreter.Interpreter
+}
+
+// NewSession starts a session with this interpreter's settings and no
+// variables
+func (i *Interpreter) NewSession() *Session {
+	return &Session{host: i, interp: i.prepare()}
+}
+
+// Run runs p with the variables, routines and types left by the programs
+// run before it in the session, even those that stopped with an error
+// Input and output are taken from the interpreter again on every run, so
+// a host can give each program its own.
+func (s *Session) Run(p *Program) error {
+	if d, ok := p.firstError(); ok {
+		return &Error{Program: p.Name, Diagnostic: d}
+	}
+
+	s.interp.SetInput(s.host.input)
+	s.interp.SetOutput(s.host.output)
+	if err, ok := s.interp.Eval(p.tree).(*interpreter.Error); ok {
+		return &Error{Program: p.Name, Diagnostic: err.Diagnostic(), Calls: err.Calls}
+	}
+	return nil
+}
+
+// Variable returns the value a top-level variable or constant holds
+func (s *Session) Variable(name string) (interpreter.Object, bool) {
+	return s.interp.Global(name)
+}
+
+// prepare creates the interpreter for one run with the host's settings
+func (i *Interpreter) prepare() *interpreter.Interpreter {
+	interp := interpreter.New()
+	fns := builtins.GetBuiltins()
+	if i.deterministic {
+		fns = builtins.Deterministic(i.seed)
+	}
+	for _, b := range i.defined {
+		fns[b.Name] = b
+	}
+	interp.SetBuiltins(fns)
+	interp.SetInput(i.input)
+	interp.SetOutput(i.output)
+	interp.SetFiles(i.files)
+	interp.SetArithmetic(i.arith)
+	interp.SetMaxDepth(i.maxDepth)
+	interp.SetMaxSize(i.maxSize)
+	interp.SetTailCalls(i.tailCalls)
+	interp.Disable(i.disabled...)
+	interp.SetDebugger(i.debugger)
+	i.last = interp
+	return interp
+}
+
+// Variable returns the value a top-level variable or constant held when
+// the last run finished, or stopped with an error
+func (i *Interpreter) Variable(name string) (interpreter.Object, bool) {
+	if i.last == nil {
+		return nil, false
+	}
+	return i.last.Global(name)
+}
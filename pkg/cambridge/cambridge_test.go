@@ -0,0 +1,320 @@
+package cambridge
+
+import (
+	"bytes"
+	"errors"
+	"fmt"
+	"strings"
+	"sync"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+)
+
+const greeter = `DECLARE Name : STRING
+DECLARE Total : INTEGER
+INPUT Name
+Total <- 0
+FOR i <- 1 TO 4
+    Total <- Total + i
+NEXT i
+OUTPUT "Hello, ", Name, " ", Total`
+
+func TestParseDiagnostics(t *testing.T) {
+	tests := []struct {
+		source string
+		ok     bool
+	}{
+		{greeter, true},
+		{"IF TRUE\n    OUTPUT 1\nENDIF", false},
+	}
+
+	for _, tt := range tests {
+		prog := Parse("main.cam", tt.source)
+		if prog.OK() != tt.ok {
+			t.Errorf("%q: expected OK() = %t, diagnostics %v", tt.source, tt.ok, prog.Diagnostics())
+		}
+		if !tt.ok {
+			err := New().Run(prog)
+			var cerr *Error
+			if !errors.As(err, &cerr) || !strings.HasPrefix(err.Error(), "main.cam:") {
+				t.Errorf("%q: expected a parse error from Run, got %v", tt.source, err)
+			}
+		}
+	}
+}
+
+func TestRun(t *testing.T) {
+	prog := Parse("main.cam", greeter)
+	var out bytes.Buffer
+	interp := New()
+	interp.SetInput(strings.NewReader("Ada\n"))
+	interp.SetOutput(&out)
+
+	if err := interp.Run(prog); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if got := out.String(); got != "Hello, Ada 10\n" {
+		t.Errorf("expected %q, got %q", "Hello, Ada 10\n", got)
+	}
+
+	total, ok := interp.Variable("Total")
+	if !ok || total.Inspect() != "10" {
+		t.Errorf("expected Total = 10, got %v, %t", total, ok)
+	}
+	if _, ok := interp.Variable("Missing"); ok {
+		t.Error("expected no variable called Missing")
+	}
+}
+
+func TestRuntimeError(t *testing.T) {
+	prog := Parse("main.cam", "DECLARE x : INTEGER\nx <- 1\nx <- x DIV 0")
+	interp := New()
+
+	err := interp.Run(prog)
+	var cerr *Error
+	if !errors.As(err, &cerr) {
+		t.Fatalf("expected an *Error, got %v", err)
+	}
+	if cerr.Diagnostic.Line != 3 || !strings.HasPrefix(err.Error(), "main.cam:3:") {
+		t.Errorf("expected an error on line 3, got %v", err)
+	}
+	// Variables keep the values they had when the program stopped
+	if x, ok := interp.Variable("x"); !ok || x.Inspect() != "1" {
+		t.Errorf("expected x = 1, got %v, %t", x, ok)
+	}
+}
+
+func TestDeterministic(t *testing.T) {
+	prog := Parse("main.cam", "OUTPUT RAND(1000), TODAY()")
+	run := func() string {
+		var out bytes.Buffer
+		interp := New()
+		interp.SetOutput(&out)
+		interp.SetDeterministic(7)
+		if err := interp.Run(prog); err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+		return out.String()
+	}
+	if first, second := run(), run(); first != second {
+		t.Errorf("expected the same output twice, got %q and %q", first, second)
+	}
+}
+
+func TestBigIntegerBuiltins(t *testing.T) {
+	prog := Parse("main.cam", `FUNCTION Factorial(n : INTEGER) RETURNS INTEGER
+    IF n <= 1 THEN
+        RETURN 1
+    ENDIF
+    RETURN n * Factorial(n - 1)
+ENDFUNCTION
+
+OUTPUT NUM_TO_STR(Factorial(25))
+OUTPUT ABS(-Factorial(21)), " ", INT(Factorial(21) / 2), " ", STR_TO_NUM("99999999999999999999") + 1
+OUTPUT ROUND(Factorial(21), 0) = Factorial(21) / 1, " ", RAND(Factorial(30)) < Factorial(30)`)
+
+	var out bytes.Buffer
+	interp := New()
+	interp.SetOutput(&out)
+	interp.SetArithmetic(interpreter.Arithmetic{BigIntegers: true})
+	if err := interp.Run(prog); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	expected := "15511210043330985984000000\n51090942171709440000 25545471085854720000 100000000000000000000\nTRUE TRUE\n"
+	if out.String() != expected {
+		t.Errorf("expected %q, got %q", expected, out.String())
+	}
+
+	var cerr *Error
+	err := New().Run(Parse("main.cam", `OUTPUT STR_TO_NUM("99999999999999999999")`))
+	if !errors.As(err, &cerr) || cerr.Diagnostic.Code != "E0228" {
+		t.Errorf("expected an overflow without big integers, got %v", err)
+	}
+}
+
+func TestConcurrentRuns(t *testing.T) {
+	prog := Parse("main.cam", greeter)
+	var wg sync.WaitGroup
+	outputs := make([]string, 8)
+	for idx := range outputs {
+		wg.Add(1)
+		go func() {
+			defer wg.Done()
+			var out bytes.Buffer
+			interp := New()
+			interp.SetInput(strings.NewReader(fmt.Sprintf("Run%d\n", idx)))
+			interp.SetOutput(&out)
+			if err := interp.Run(prog); err != nil {
+				t.Errorf("run %d: unexpected error: %v", idx, err)
+			}
+			outputs[idx] = out.String()
+		}()
+	}
+	wg.Wait()
+
+	for idx, got := range outputs {
+		if want := fmt.Sprintf("Hello, Run%d 10\n", idx); got != want {
+			t.Errorf("run %d: expected %q, got %q", idx, want, got)
+		}
+	}
+}
+
+func TestFiles(t *testing.T) {
+	prog := Parse("main.cam", `OPENFILE "log.txt" FOR APPEND
+WRITEFILE "log.txt", "ran"
+CLOSEFILE "log.txt"`)
+	files := interpreter.NewMemoryFiles(nil)
+	interp := New()
+	interp.SetFiles(files)
+
+	for range 2 {
+		if err := interp.Run(prog); err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+	}
+	if got, _ := files.Contents("log.txt"); got != "ran\nran\n" {
+		t.Errorf("expected %q, got %q", "ran\nran\n", got)
+	}
+}
+
+func TestDefine(t *testing.T) {
+	var moves []string
+	interp := New()
+	interp.Define("FORWARD", []interpreter.ObjectType{interpreter.REAL_OBJ}, func(args ...interpreter.Object) interpreter.Object {
+		moves = append(moves, args[0].Inspect())
+		return &interpreter.Null{}
+	})
+	interp.Define("TWICE", []interpreter.ObjectType{interpreter.INTEGER_OBJ}, func(args ...interpreter.Object) interpreter.Object {
+		return &interpreter.Integer{Value: 2 * args[0].(*interpreter.Integer).Value}
+	})
+
+	if err := interp.Run(Parse("main.cam", "CALL FORWARD(TWICE(5))\nCALL FORWARD(2.5)")); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if got := strings.Join(moves, " "); got != "10 2.5" {
+		t.Errorf("expected moves %q, got %q", "10 2.5", got)
+	}
+
+	tests := []struct {
+		source  string
+		message string
+	}{
+		{"CALL FORWARD()", "FORWARD expects 1 arguments, got 0"},
+		{`CALL FORWARD("far")`, "argument 1 of FORWARD must be REAL, got STRING"},
+	}
+	for _, tt := range tests {
+		err := interp.Run(Parse("main.cam", tt.source))
+		var cerr *Error
+		if !errors.As(err, &cerr) || cerr.Diagnostic.Message != tt.message {
+			t.Errorf("%q: expected %q, got %v", tt.source, tt.message, err)
+		}
+	}
+}
+
+func TestSteps(t *testing.T) {
+	var lines []int
+	for step := range New().Steps(Parse("main.cam", "DECLARE x : INTEGER\nx <- 2\nOUTPUT x")) {
+		lines = append(lines, step.Line)
+	}
+	if fmt.Sprint(lines) != "[1 2 3]" {
+		t.Errorf("expected steps on lines [1 2 3], got %v", lines)
+	}
+
+	var steps []interpreter.Step
+	for step := range New().Steps(Parse("main.cam", "IF TRUE\n    OUTPUT 1\nENDIF")) {
+		steps = append(steps, step)
+	}
+	if len(steps) != 1 || steps[0].Err == nil {
+		t.Errorf("expected one step with the parse error, got %v", steps)
+	}
+}
+
+func TestResume(t *testing.T) {
+	prog := Parse("main.cam", greeter)
+	interp := New()
+	interp.SetInput(strings.NewReader("Ada\n"))
+	var snap *interpreter.Snapshot
+	for step := range interp.Steps(prog) {
+		if step.Line == 4 {
+			var err error
+			if snap, err = interp.Snapshot(); err != nil {
+				t.Fatalf("unexpected error: %v", err)
+			}
+			break
+		}
+	}
+
+	// The rest of the program runs without reading INPUT again
+	var out bytes.Buffer
+	interp.SetOutput(&out)
+	if err := interp.Resume(prog, snap); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if got := out.String(); got != "Hello, Ada 10\n" {
+		t.Errorf("expected %q, got %q", "Hello, Ada 10\n", got)
+	}
+}
+
+func TestSession(t *testing.T) {
+	var out bytes.Buffer
+	interp := New()
+	interp.SetOutput(&out)
+	session := interp.NewSession()
+
+	cells := []struct {
+		source string
+		output string
+		fails  bool
+	}{
+		{"DECLARE Count : INTEGER\nCount <- 1", "", false},
+		{"FUNCTION Twice(N : INTEGER) RETURNS INTEGER\n    RETURN N * 2\nENDFUNCTION", "", false},
+		{"Count <- Twice(Count) + 1\nOUTPUT Count", "3\n", false},
+		{"Count <- Count + 1\nCount <- Count DIV 0", "", true},
+		{"OUTPUT Count", "4\n", false},
+		{"OUTPUT Count +", "", true},
+	}
+
+	for idx, cell := range cells {
+		out.Reset()
+		err := session.Run(Parse(fmt.Sprintf("cell%d", idx+1), cell.source))
+		if (err != nil) != cell.fails {
+			t.Errorf("cell %d: expected failure %t, got %v", idx+1, cell.fails, err)
+		}
+		if out.String() != cell.output {
+			t.Errorf("cell %d: expected output %q, got %q", idx+1, cell.output, out.String())
+		}
+	}
+
+	if count, ok := session.Variable("Count"); !ok || count.Inspect() != "4" {
+		t.Errorf("expected Count = 4, got %v, %t", count, ok)
+	}
+}
+
+func TestStart(t *testing.T) {
+	var out bytes.Buffer
+	interp := New()
+	interp.SetOutput(&out)
+
+	endless := interp.Start(Parse("main.cam", "DECLARE n : INTEGER\nn <- 0\nREPEAT\n    n <- n + 1\nUNTIL FALSE"))
+	for instalment := 0; instalment < 3; instalment++ {
+		if done, err := endless.Continue(1000); done || err != nil {
+			t.Fatalf("expected an endless loop to keep running, got %t, %v", done, err)
+		}
+	}
+	if n, _ := interp.Variable("n"); n.Inspect() == "0" {
+		t.Error("expected the loop to have made progress")
+	}
+	endless.Stop()
+
+	finite := interp.Start(Parse("main.cam", "FOR i <- 1 TO 3\n    OUTPUT i\nNEXT i"))
+	done, err := finite.Continue(1000)
+	if !done || err != nil || out.String() != "1\n2\n3\n" {
+		t.Errorf("expected the program to finish, got %t, %v and output %q", done, err, out.String())
+	}
+
+	broken := interp.Start(Parse("main.cam", "OUTPUT 1 +"))
+	if done, err := broken.Continue(1000); !done || err == nil {
+		t.Errorf("expected a parse error at once, got %t, %v", done, err)
+	}
+}
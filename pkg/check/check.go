@@ -0,0 +1,417 @@
+// Package check finds problems in a program before it runs: values of the
+// wrong type, names that are not declared or look misspelt, variables
+// never used or never given a value, comparisons written where an
+// assignment was meant, and functions that can finish without returning
+// a value
+package check
+
+import (
+	"sort"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// Program checks program, parsed from source, returning what it finds in
+// source order
+// Types are judged from declarations alone, so an expression whose type
+// cannot be told that way is never reported.
+func Program(program *ast.Program, source string) []diagnostic.Diagnostic {
+	c := &checker{
+		table:    symbols.Build(program, source),
+		refs:     map[symbols.Position]*symbols.Symbol{},
+		byRef:    map[symbols.Position]bool{},
+		loops:    map[string]bool{},
+		starts:   map[symbols.Position]*symbols.Symbol{},
+		declared: map[symbols.Position]bool{},
+	}
+	for _, stmt := range program.Statements {
+		if _, ok := stmt.(*ast.IncludeStatement); ok {
+			c.includes = true
+		}
+	}
+	for _, ref := range c.table.References {
+		c.refs[ref.Pos] = ref.Symbol
+	}
+	for _, sym := range c.table.Symbols() {
+		c.starts[sym.Start] = sym
+		c.declared[sym.Pos] = true
+		for _, m := range sym.Members {
+			c.starts[m.Start] = m
+			c.declared[m.Pos] = true
+		}
+	}
+
+	ast.Inspect(program, func(node ast.Node) bool {
+		switch n := node.(type) {
+		case *ast.Identifier:
+			c.identifier(n)
+		case *ast.ExpressionStatement:
+			c.comparison(n)
+		case *ast.AssignmentStatement:
+			c.assignment(n)
+		case *ast.FunctionStatement:
+			c.function(n)
+		case *ast.ForStatement:
+			if !ast.IsNil(n.Variable) {
+				c.loops[n.Variable.Value] = true
+			}
+		case *ast.CallStatement:
+			c.arguments(n.Name, n.Arguments)
+		case *ast.CallExpression:
+			c.arguments(n.Function, n.Arguments)
+		}
+		return true
+	})
+	c.variables()
+
+	sort.SliceStable(c.diagnostics, func(i, j int) bool {
+		a, b := c.diagnostics[i], c.diagnostics[j]
+		return a.Line < b.Line || a.Line == b.Line && a.Column < b.Column
+	})
+	return c.diagnostics
+}
+
+type checker struct {
+	table       *symbols.Table
+	refs        map[symbols.Position]*symbols.Symbol // what the name at each position refers to
+	byRef       map[symbols.Position]bool            // arguments a BYREF parameter may give a value
+	loops       map[string]bool                      // the names of FOR loop variables
+	starts      map[symbols.Position]*symbols.Symbol // declarations, by where they start
+	declared    map[symbols.Position]bool            // where each name is declared
+	includes    bool                                 // names may be declared in a file the program includes
+	diagnostics []diagnostic.Diagnostic
+}
+
+func (c *checker) report(severity diagnostic.Severity, pos symbols.Position, code diagnostic.Code, args ...interface{}) {
+	d := diagnostic.New(severity, code, args...)
+	d.Line, d.Column = pos.Line, pos.Column
+	c.diagnostics = append(c.diagnostics, d)
+}
+
+// identifier reports a name that is neither declared nor a builtin,
+// suggesting a name in scope it may be a misspelling of
+func (c *checker) identifier(n *ast.Identifier) {
+	if n == nil {
+		return
+	}
+	pos := position(n.Token)
+	if c.refs[pos] != nil || c.declared[pos] {
+		return
+	}
+	if _, ok := builtins.Signatures[n.Value]; ok {
+		return
+	}
+	scope := c.table.At(pos)
+	if inherited(scope, n.Value) || c.includes {
+		return
+	}
+	if suggestion, ok := suggest(n.Value, candidates(scope)); ok {
+		c.report(diagnostic.Warning, pos, diagnostic.PossibleTypo, n.Value, suggestion)
+	} else {
+		c.report(diagnostic.Warning, pos, diagnostic.UndeclaredVariable, n.Value)
+	}
+}
+
+// inherited reports whether name is a member a method's class inherits,
+// which is in scope without being declared in the class itself
+func inherited(scope *symbols.Scope, name string) bool {
+	for s := scope; s != nil; s = s.Parent {
+		if s.Owner != nil && s.Owner.Kind == symbols.Class {
+			return s.Member(s.Owner.Name, name) != nil
+		}
+	}
+	return false
+}
+
+// comparison reports a statement comparing a variable with a value, which
+// is an assignment written with = in place of ←
+func (c *checker) comparison(n *ast.ExpressionStatement) {
+	infix, ok := n.Expression.(*ast.InfixExpression)
+	if !ok || infix == nil || infix.Operator != "=" {
+		return
+	}
+	switch infix.Left.(type) {
+	case *ast.Identifier, *ast.ArrayAccess, *ast.MemberAccess:
+		line, column := ast.Position(infix.Left)
+		c.report(diagnostic.Warning, symbols.Position{Line: line, Column: column}, diagnostic.ComparisonStatement, infix.Left.String())
+	}
+}
+
+// assignment reports a value whose type cannot be stored in the variable,
+// element or field it is assigned to
+func (c *checker) assignment(n *ast.AssignmentStatement) {
+	if ast.IsNil(n.Name) || ast.IsNil(n.Value) {
+		return
+	}
+	scope := c.table.At(position(n.Token))
+	declared, ok := scope.TypeOf(n.Name).(*ast.PrimitiveType)
+	if !ok {
+		return
+	}
+	if value := TypeOf(scope, n.Value); value != "" && !assignable(declared.Name, value) {
+		line, column := ast.Position(n.Value)
+		c.report(diagnostic.Error, symbols.Position{Line: line, Column: column}, diagnostic.AssignedTypeMismatch, value, n.Name.String(), declared.Name)
+	}
+}
+
+// function reports RETURN values of the wrong type, and a function that
+// can reach its end without returning
+func (c *checker) function(n *ast.FunctionStatement) {
+	declared, _ := n.ReturnType.(*ast.PrimitiveType)
+	for _, stmt := range n.Body {
+		ast.Inspect(stmt, func(node ast.Node) bool {
+			ret, ok := node.(*ast.ReturnStatement)
+			if !ok || declared == nil || ast.IsNil(ret.Value) {
+				return true
+			}
+			if value := TypeOf(c.table.At(position(ret.Token)), ret.Value); value != "" && !assignable(declared.Name, value) {
+				line, column := ast.Position(ret.Value)
+				c.report(diagnostic.Error, symbols.Position{Line: line, Column: column}, diagnostic.ReturnedTypeMismatch, n.Name, declared.Name, value)
+			}
+			return true
+		})
+	}
+
+	if !returns(n.Body) {
+		pos := position(n.Token)
+		if sym, ok := c.starts[pos]; ok {
+			pos = sym.Pos
+		}
+		c.report(diagnostic.Warning, pos, diagnostic.MissingReturn, n.Name)
+	}
+}
+
+// arguments marks the variables passed to callee that it may give values
+// to: those passed BYREF, and any passed to a routine that cannot be told
+func (c *checker) arguments(callee ast.Expression, args []ast.Expression) {
+	var sym *symbols.Symbol
+	if f, ok := callee.(*ast.Identifier); ok && f != nil {
+		sym = c.refs[position(f.Token)]
+		if _, ok := builtins.Signatures[f.Value]; ok && sym == nil {
+			return // builtins only read their arguments
+		}
+	}
+	for idx, arg := range args {
+		name := variable(arg)
+		if name == nil {
+			continue
+		}
+		if sym == nil || idx >= len(sym.Params) || strings.HasPrefix(sym.Params[idx].Detail, "BYREF ") {
+			c.byRef[position(name.Token)] = true
+		}
+	}
+}
+
+// variables reports variables that are never used, and those that are
+// used but never given a value anywhere
+// A FOR loop has a variable of its own, which gives it a value, yet the
+// syllabus still has its name declared; such declarations are left alone.
+// Variables holding records or objects are given values field by field,
+// so only the use of the whole is checked for them.
+func (c *checker) variables() {
+	for _, sym := range c.table.Symbols() {
+		if sym.Kind != symbols.Variable || c.loops[sym.Name] {
+			continue
+		}
+		uses := c.table.Uses(sym)
+		if len(uses) == 0 {
+			c.report(diagnostic.Note, sym.Pos, diagnostic.UnusedVariable, sym.Name)
+			continue
+		}
+		if !primitive(sym.DataType) {
+			continue
+		}
+		written := false
+		for _, use := range uses {
+			written = written || use.Write || c.byRef[use.Pos]
+		}
+		if !written {
+			c.report(diagnostic.Warning, uses[0].Pos, diagnostic.UnassignedVariable, sym.Name)
+		}
+	}
+}
+
+// primitive reports whether t is a primitive type or an array of them, so
+// that giving it a value is always an assignment to its name
+func primitive(t ast.DataType) bool {
+	switch t := t.(type) {
+	case *ast.PrimitiveType:
+		return true
+	case *ast.ArrayType:
+		return primitive(t.ElementType)
+	}
+	return false
+}
+
+// variable returns the variable an argument is, or whose element it is,
+// or nil if it is neither
+func variable(arg ast.Expression) *ast.Identifier {
+	if ast.IsNil(arg) {
+		return nil
+	}
+	switch a := arg.(type) {
+	case *ast.Identifier:
+		return a
+	case *ast.ArrayAccess:
+		return variable(a.Array)
+	}
+	return nil
+}
+
+// returns reports whether running stmts always ends in a RETURN
+func returns(stmts []ast.Statement) bool {
+	for _, stmt := range stmts {
+		if ast.IsNil(stmt) {
+			continue
+		}
+		switch s := stmt.(type) {
+		case *ast.ReturnStatement:
+			return true
+		case *ast.IfStatement:
+			if s.Alternative != nil && returns(s.Consequence) && returns(s.Alternative) {
+				return true
+			}
+		case *ast.CaseStatement:
+			all := s.Otherwise != nil && returns(s.Otherwise)
+			for _, clause := range s.Cases {
+				all = all && returns(clause.Body)
+			}
+			if all {
+				return true
+			}
+		case *ast.RepeatStatement:
+			if returns(s.Body) { // the body always runs at least once
+				return true
+			}
+		}
+	}
+	return false
+}
+
+// TypeOf returns the name of the primitive type expr has in scope, or ""
+// if that cannot be told
+func TypeOf(scope *symbols.Scope, expr ast.Expression) string {
+	switch e := expr.(type) {
+	case *ast.IntegerLiteral:
+		return "INTEGER"
+	case *ast.RealLiteral:
+		return "REAL"
+	case *ast.StringLiteral:
+		return "STRING"
+	case *ast.CharLiteral:
+		return "CHAR"
+	case *ast.BooleanLiteral:
+		return "BOOLEAN"
+	case *ast.PrefixExpression:
+		if e.Operator == "NOT" {
+			return "BOOLEAN"
+		}
+		if right := TypeOf(scope, e.Right); numeric(right) {
+			return right
+		}
+		return ""
+	case *ast.InfixExpression:
+		left, right := TypeOf(scope, e.Left), TypeOf(scope, e.Right)
+		switch e.Operator {
+		case "&":
+			return "STRING"
+		case "=", "<>", "<", ">", "<=", ">=", "AND", "OR":
+			return "BOOLEAN"
+		case "/":
+			return "REAL"
+		case "DIV", "MOD":
+			return "INTEGER"
+		case "+", "-", "*":
+			if left == "INTEGER" && right == "INTEGER" {
+				return "INTEGER"
+			} else if numeric(left) && numeric(right) {
+				return "REAL"
+			}
+		}
+		return ""
+	case *ast.CallExpression:
+		if id, ok := e.Function.(*ast.Identifier); ok && scope.Lookup(id.Value) == nil {
+			return builtins.Signatures[id.Value].Returns
+		}
+	}
+	if t, ok := scope.TypeOf(expr).(*ast.PrimitiveType); ok {
+		return t.Name
+	}
+	return ""
+}
+
+// candidates are the names a misspelt name in scope may have been meant
+// to be: those visible there, then the builtins
+func candidates(scope *symbols.Scope) []string {
+	var names []string
+	for _, sym := range scope.Visible() {
+		names = append(names, sym.Name)
+	}
+	var builtinNames []string
+	for name := range builtins.Signatures {
+		builtinNames = append(builtinNames, name)
+	}
+	sort.Strings(builtinNames)
+	return append(names, builtinNames...)
+}
+
+// suggest returns the candidate name is most likely a misspelling of: one
+// differing only in case, or else by a single edit, or two for names of
+// more than six letters
+func suggest(name string, candidates []string) (string, bool) {
+	best, bestDistance := "", 2
+	if len(name) > 6 {
+		bestDistance = 3
+	}
+	for _, candidate := range candidates {
+		if d := distance(strings.ToUpper(name), strings.ToUpper(candidate)); d < bestDistance {
+			best, bestDistance = candidate, d
+		}
+	}
+	return best, best != ""
+}
+
+// distance is the number of characters that must be inserted, deleted,
+// changed or swapped with the next to turn a into b
+func distance(a, b string) int {
+	var before []int
+	prev := make([]int, len(b)+1)
+	for j := range prev {
+		prev[j] = j
+	}
+	for i := 1; i <= len(a); i++ {
+		cur := make([]int, len(b)+1)
+		cur[0] = i
+		for j := 1; j <= len(b); j++ {
+			cost := 1
+			if a[i-1] == b[j-1] {
+				cost = 0
+			}
+			cur[j] = min(prev[j]+1, cur[j-1]+1, prev[j-1]+cost)
+			if i > 1 && j > 1 && a[i-1] == b[j-2] && a[i-2] == b[j-1] {
+				cur[j] = min(cur[j], before[j-2]+1)
+			}
+		}
+		before, prev = prev, cur
+	}
+	return prev[len(b)]
+}
+
+func numeric(t string) bool {
+	return t == "INTEGER" || t == "REAL"
+}
+
+// assignable reports whether a value of type value can be stored where
+// declared is expected: the same type, or one the value widens to
+func assignable(declared, value string) bool {
+	return declared == value || declared == "REAL" && value == "INTEGER" || declared == "STRING" && value == "CHAR"
+}
+
+func position(tok token.Token) symbols.Position {
+	return symbols.Position{Line: tok.Line, Column: tok.Column}
+}
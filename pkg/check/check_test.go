@@ -0,0 +1,218 @@
+package check
+
+import (
+	"fmt"
+	"strings"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/format"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+func TestProgram(t *testing.T) {
+	tests := []struct {
+		name     string
+		src      string
+		expected []string // line:column code
+	}{
+		{
+			"clean",
+			"DECLARE Total : REAL\nTotal <- 1\nTotal <- Total / 2\nOUTPUT Total\n",
+			nil,
+		},
+		{
+			"assigned type mismatch",
+			"DECLARE Count : INTEGER\nCount <- TRUE\nCount <- 7 / 2\nOUTPUT Count\n",
+			[]string{"2:10 E0301", "3:10 E0301"},
+		},
+		{
+			"element and builtin types",
+			"DECLARE Names : ARRAY[1:3] OF STRING\nNames[1] <- LENGTH(\"Ann\")\nNames[2] <- LEFT(\"Bob\", 1)\nOUTPUT Names[1]\n",
+			[]string{"2:13 E0301"},
+		},
+		{
+			"returned type mismatch",
+			"FUNCTION Half(N : INTEGER) RETURNS INTEGER\n    RETURN N / 2\nENDFUNCTION\nOUTPUT Half(4)\n",
+			[]string{"2:12 E0302"},
+		},
+		{
+			"unused and unassigned",
+			"DECLARE Spare : INTEGER\nDECLARE Score : INTEGER\nOUTPUT Score\n",
+			[]string{"1:9 W0301", "3:8 W0302"},
+		},
+		{
+			"given values by INPUT, BYREF and loops",
+			"PROCEDURE Reset(BYREF X : INTEGER)\n    X <- 0\nENDPROCEDURE\nDECLARE A : INTEGER\nDECLARE B : INTEGER\nDECLARE I : INTEGER\nINPUT A\nCALL Reset(B)\nFOR I <- 1 TO 3\n    OUTPUT A + B + I\nNEXT I\n",
+			nil,
+		},
+		{
+			"declared in an included file",
+			"INCLUDE \"sort.cam\"\nDECLARE Count : INTEGER\nCount <- 3\nCALL BubbleSort(Names, Count)\n",
+			nil,
+		},
+		{
+			"passed by value",
+			"PROCEDURE Show(X : INTEGER)\n    OUTPUT X\nENDPROCEDURE\nDECLARE A : INTEGER\nCALL Show(A)\n",
+			[]string{"5:11 W0302"},
+		},
+		{
+			"missing return",
+			"FUNCTION Sign(N : INTEGER) RETURNS INTEGER\n    IF N > 0 THEN\n        RETURN 1\n    ELSE\n        IF N < 0 THEN\n            RETURN -1\n        ENDIF\n    ENDIF\nENDFUNCTION\nOUTPUT Sign(2)\n",
+			[]string{"1:10 W0303"},
+		},
+		{
+			"every branch returns",
+			"FUNCTION Sign(N : INTEGER) RETURNS INTEGER\n    CASE OF N\n        0 : RETURN 0\n        OTHERWISE : RETURN 1\n    ENDCASE\nENDFUNCTION\nOUTPUT Sign(2)\n",
+			nil,
+		},
+		{
+			"undeclared and misspelt names",
+			"DECLARE Total : INTEGER\nTotal <- 0\ntotal <- Total + 1\nOUTPUT Totl, Amount, LENGHT(\"abc\")\n",
+			[]string{"3:1 W0305", "4:8 W0305", "4:14 W0304", "4:22 W0305"},
+		},
+		{
+			"comparison written for an assignment",
+			"DECLARE X : INTEGER\nX = 5\nOUTPUT X\n",
+			[]string{"2:1 W0306", "2:1 W0302"},
+		},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			p := parser.New(lexer.New(tt.src))
+			prog := p.ParseProgram()
+			if len(p.Errors()) > 0 {
+				t.Fatalf("parser errors: %v", p.Errors())
+			}
+			var got []string
+			for _, d := range Program(prog, tt.src) {
+				got = append(got, fmt.Sprintf("%d:%d %s", d.Line, d.Column, d.Code))
+			}
+			if strings.Join(got, ", ") != strings.Join(tt.expected, ", ") {
+				t.Errorf("expected %v, got %v", tt.expected, got)
+			}
+		})
+	}
+}
+
+func TestInsert(t *testing.T) {
+	src := "DECLARE D : DATE\nD <- SETDATE(1, 2, 2024)\nOUTPUT DAYINDEX(D), LENGTH(\"abc\")\n"
+	tests := []struct {
+		name     string
+		src      string
+		year     int
+		expected []string // line:column code
+	}{
+		{"latest", src, 0, nil},
+		{"before", src, 2021, []string{"2:6 W0308", "3:8 W0308"}},
+		{"since", src, 2023, nil},
+		{"own routine", "FUNCTION TODAY() RETURNS INTEGER\n    RETURN 1\nENDFUNCTION\nOUTPUT TODAY()\n", 2021, nil},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			p := parser.New(lexer.New(tt.src))
+			prog := p.ParseProgram()
+			if len(p.Errors()) > 0 {
+				t.Fatalf("parser errors: %v", p.Errors())
+			}
+			var got []string
+			for _, d := range Insert(prog, tt.year) {
+				got = append(got, fmt.Sprintf("%d:%d %s", d.Line, d.Column, d.Code))
+			}
+			if strings.Join(got, ", ") != strings.Join(tt.expected, ", ") {
+				t.Errorf("expected %v, got %v", tt.expected, got)
+			}
+		})
+	}
+}
+
+func TestDialect(t *testing.T) {
+	src := "TYPE Point\n    DECLARE X : INTEGER\nENDTYPE\nCLASS Pet\n    PRIVATE DECLARE Name : STRING\nENDCLASS\nOUTPUT 1\n"
+	tests := []struct {
+		dialect  string
+		expected []string // line:column code
+	}{
+		{"9618", nil},
+		{"0478", []string{"1:1 W0307", "4:1 W0307"}},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.dialect, func(t *testing.T) {
+			p := parser.New(lexer.New(src))
+			prog := p.ParseProgram()
+			if len(p.Errors()) > 0 {
+				t.Fatalf("parser errors: %v", p.Errors())
+			}
+			var got []string
+			for _, d := range Dialect(prog, tt.dialect) {
+				got = append(got, fmt.Sprintf("%d:%d %s", d.Line, d.Column, d.Code))
+			}
+			if strings.Join(got, ", ") != strings.Join(tt.expected, ", ") {
+				t.Errorf("expected %v, got %v", tt.expected, got)
+			}
+		})
+	}
+}
+
+func TestStyle(t *testing.T) {
+	tests := []struct {
+		name     string
+		src      string
+		expected []string // line:column code
+	}{
+		{
+			"as the guide sets it out",
+			"DECLARE Total : INTEGER\nFOR I <- 1 TO 3\n    Total <- Total + I\nNEXT I\nOUTPUT Total\n",
+			nil,
+		},
+		{
+			"keyword case",
+			"Declare Total : integer\nTotal <- 1\nOutput Total\n",
+			[]string{"1:1 W0309", "1:17 W0309", "3:1 W0309"},
+		},
+		{
+			"identifier case",
+			"DECLARE total_score : INTEGER\nDECLARE MAXIMUM : INTEGER\nDECLARE numberOfPlayers : INTEGER\nDECLARE X : INTEGER\n",
+			[]string{"1:9 W0310", "2:9 W0310"},
+		},
+		{
+			"indentation",
+			"IF TRUE THEN\n  OUTPUT 1\nOUTPUT 2\nENDIF\n",
+			[]string{"2:3 W0311", "3:1 W0311"},
+		},
+		{
+			"statements on one line",
+			"DECLARE A : INTEGER\nA <- 1 OUTPUT A\n",
+			[]string{"2:8 W0312"},
+		},
+		{
+			"declaration placement",
+			"DECLARE A : INTEGER\nA <- 1\nDECLARE B : INTEGER\nWHILE A < 3\n    DECLARE C : INTEGER\n    A <- A + 1\nENDWHILE\n",
+			[]string{"3:1 W0313", "5:5 W0313"},
+		},
+		{
+			"routine declarations",
+			"PROCEDURE Greet()\n    OUTPUT \"Hi\"\n    DECLARE Name : STRING\nENDPROCEDURE\nDECLARE Count : INTEGER\nCALL Greet()\n",
+			[]string{"3:5 W0313"},
+		},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			p := parser.New(lexer.New(tt.src))
+			prog := p.ParseProgram()
+			if len(p.Errors()) > 0 {
+				t.Fatalf("parser errors: %v", p.Errors())
+			}
+			var got []string
+			for _, d := range Style(prog, tt.src, format.House) {
+				got = append(got, fmt.Sprintf("%d:%d %s", d.Line, d.Column, d.Code))
+			}
+			if strings.Join(got, ", ") != strings.Join(tt.expected, ", ") {
+				t.Errorf("expected %v, got %v", tt.expected, got)
+			}
+		})
+	}
+}
@@ -0,0 +1,74 @@
+package check
+
+import (
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/config"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// Dialect reports the statements in program that the syllabus dialect, as
+// named in cambridge.toml, does not teach
+// Only IGCSE leaves anything out: it has no user-defined types or classes,
+// which the interpreter still runs, so these are warnings.
+func Dialect(program *ast.Program, dialect string) []diagnostic.Diagnostic {
+	if dialect != config.IGCSE {
+		return nil
+	}
+	var found []diagnostic.Diagnostic
+	report := func(tok token.Token) {
+		d := diagnostic.New(diagnostic.Warning, diagnostic.NotInDialect, string(tok.Type), dialect)
+		d.Line, d.Column = tok.Line, tok.Column
+		found = append(found, d)
+	}
+	ast.Inspect(program, func(node ast.Node) bool {
+		switch n := node.(type) {
+		case *ast.TypeStatement:
+			report(n.Token)
+		case *ast.ClassStatement:
+			report(n.Token)
+			return false // its members are part of the one problem
+		}
+		return true
+	})
+	return found
+}
+
+// Insert reports the calls in program to builtins that the insert for the
+// exams of year, as set in cambridge.toml, does not list, so that students
+// do not rely on functions they cannot cite
+// A year of 0 means the latest insert, which lists them all. A routine the
+// program declares with a builtin's name is its own, and is not reported.
+func Insert(program *ast.Program, year int) []diagnostic.Diagnostic {
+	if year == 0 {
+		return nil
+	}
+	declared := map[string]bool{}
+	for _, stmt := range program.Statements {
+		switch s := stmt.(type) {
+		case *ast.FunctionStatement:
+			declared[s.Name] = true
+		case *ast.ProcedureStatement:
+			declared[s.Name] = true
+		}
+	}
+	var found []diagnostic.Diagnostic
+	ast.Inspect(program, func(node ast.Node) bool {
+		call, ok := node.(*ast.CallExpression)
+		if !ok {
+			return true
+		}
+		fn, ok := call.Function.(*ast.Identifier)
+		if !ok || fn == nil || declared[fn.Value] || builtins.InInsert(fn.Value, year) {
+			return true
+		}
+		if _, ok := builtins.Signatures[fn.Value]; ok {
+			d := diagnostic.New(diagnostic.Warning, diagnostic.NotInInsert, fn.Value, year, builtins.Introduced[fn.Value])
+			d.Line, d.Column = fn.Token.Line, fn.Token.Column
+			found = append(found, d)
+		}
+		return true
+	})
+	return found
+}
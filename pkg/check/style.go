@@ -0,0 +1,195 @@
+package check
+
+import (
+	"sort"
+	"strings"
+	"unicode"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/format"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/symbols"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// Style reports where program, parsed from source, is not set out as the
+// Cambridge pseudocode guide sets out its examples, which is what written
+// answers are marked against: keywords in capitals, names in mixed case,
+// each block indented a level as layout indents them, a statement to a
+// line, and variables declared before the statements using them
+// These are notes, as the program runs the same either way. Names of a
+// single letter, such as loop counters, are left as they are.
+func Style(program *ast.Program, source string, layout format.Style) []diagnostic.Diagnostic {
+	s := &styler{}
+	s.keywords(source)
+	s.names(symbols.Build(program, source))
+	s.indentation(source, layout)
+	s.statements(program)
+
+	sort.SliceStable(s.diagnostics, func(i, j int) bool {
+		a, b := s.diagnostics[i], s.diagnostics[j]
+		return a.Line < b.Line || a.Line == b.Line && a.Column < b.Column
+	})
+	return s.diagnostics
+}
+
+type styler struct {
+	diagnostics []diagnostic.Diagnostic
+}
+
+func (s *styler) report(line, column int, code diagnostic.Code, args ...interface{}) {
+	d := diagnostic.New(diagnostic.Note, code, args...)
+	d.Line, d.Column = line, column
+	s.diagnostics = append(s.diagnostics, d)
+}
+
+// keywords reports each keyword in source not written in capitals
+func (s *styler) keywords(source string) {
+	l := lexer.New(source)
+	for tok := l.NextToken(); tok.Type != token.EOF; tok = l.NextToken() {
+		upper := strings.ToUpper(tok.Literal)
+		if upper != tok.Literal && token.Keywords[upper] == tok.Type {
+			s.report(tok.Line, tok.Column, diagnostic.KeywordCase, tok.Literal, upper)
+		}
+	}
+}
+
+// names reports each name declared in table that is not in mixed case,
+// suggesting how it would be written in the guide's style
+func (s *styler) names(table *symbols.Table) {
+	for _, sym := range table.Symbols() {
+		if sym.Name == "NEW" {
+			continue // a constructor's name is the keyword
+		}
+		if suggestion := mixedCase(sym.Name); suggestion != sym.Name {
+			s.report(sym.Pos.Line, sym.Pos.Column, diagnostic.IdentifierCase, sym.Name, suggestion)
+		}
+	}
+}
+
+// mixedCase returns name as the guide would write it: each word starting
+// with a capital and no underscores between them, as in NumberOfPlayers
+// A name already in mixed case, or with a single letter, is returned as
+// it is.
+func mixedCase(name string) string {
+	letters, upper, lower := 0, false, false
+	for _, r := range name {
+		if unicode.IsLetter(r) {
+			letters++
+			upper = upper || unicode.IsUpper(r)
+			lower = lower || unicode.IsLower(r)
+		}
+	}
+	if letters < 2 || upper && lower && !strings.Contains(name, "_") {
+		return name
+	}
+
+	var out strings.Builder
+	for _, word := range strings.Split(name, "_") {
+		if word == "" {
+			continue
+		}
+		if strings.ToUpper(word) == word {
+			word = strings.ToLower(word)
+		}
+		out.WriteString(strings.ToUpper(word[:1]) + word[1:])
+	}
+	if out.Len() == 0 {
+		return name
+	}
+	return out.String()
+}
+
+// indentation reports each line of source not indented as layout indents
+// it, giving the level it should be at
+func (s *styler) indentation(source string, layout format.Style) {
+	laid, err := layout.Lines(source)
+	if err != nil {
+		return
+	}
+	for idx, text := range strings.Split(source, "\n") {
+		if idx >= len(laid) || strings.TrimSpace(text) == "" {
+			continue
+		}
+		have, want := leading(text), leading(laid[idx])
+		if have != want {
+			s.report(idx+1, len(have)+1, diagnostic.Misindented, len(want)/max(len(layout.Indent), 1))
+		}
+	}
+}
+
+// leading returns the whitespace text starts with
+func leading(text string) string {
+	return text[:len(text)-len(strings.TrimLeft(text, " \t"))]
+}
+
+// statements reports a statement starting on the line of the one before,
+// and a DECLARE that is not among the declarations opening its program or
+// routine, giving the line it belongs before
+func (s *styler) statements(program *ast.Program) {
+	var visit func(stmts []ast.Statement, before int)
+	visit = func(stmts []ast.Statement, before int) {
+		prev := 0
+		for _, stmt := range stmts {
+			line, column := ast.Position(stmt)
+			if line != 0 && line == prev {
+				s.report(line, column, diagnostic.SeveralStatements)
+			}
+			prev = line
+
+			if d, ok := stmt.(*ast.DeclareStatement); ok && before != 0 && line > before && !ast.IsNil(d.Name) {
+				s.report(line, column, diagnostic.DeclarationPlacement, d.Name.Value, before)
+			}
+			for _, body := range bodies(stmt) {
+				visit(body, before)
+			}
+			switch r := stmt.(type) {
+			case *ast.ProcedureStatement:
+				visit(r.Body, firstStatement(r.Body))
+			case *ast.FunctionStatement:
+				visit(r.Body, firstStatement(r.Body))
+			case *ast.ClassStatement:
+				visit(r.Members, 0) // properties may be declared among methods
+			}
+		}
+	}
+	visit(program.Statements, firstStatement(program.Statements))
+}
+
+// firstStatement returns the line of the first statement in stmts that is
+// not a declaration, before which every DECLARE belongs, or 0 if there is
+// none
+func firstStatement(stmts []ast.Statement) int {
+	for _, stmt := range stmts {
+		switch stmt.(type) {
+		case *ast.DeclareStatement, *ast.ConstantStatement, *ast.TypeStatement,
+			*ast.ProcedureStatement, *ast.FunctionStatement, *ast.ClassStatement:
+			continue
+		}
+		line, _ := ast.Position(stmt)
+		return line
+	}
+	return 0
+}
+
+// bodies returns the statements nested in a control structure
+func bodies(stmt ast.Statement) [][]ast.Statement {
+	switch n := stmt.(type) {
+	case *ast.IfStatement:
+		return [][]ast.Statement{n.Consequence, n.Alternative}
+	case *ast.CaseStatement:
+		var out [][]ast.Statement
+		for _, c := range n.Cases {
+			out = append(out, c.Body)
+		}
+		return append(out, n.Otherwise)
+	case *ast.ForStatement:
+		return [][]ast.Statement{n.Body}
+	case *ast.WhileStatement:
+		return [][]ast.Statement{n.Body}
+	case *ast.RepeatStatement:
+		return [][]ast.Statement{n.Body}
+	}
+	return nil
+}
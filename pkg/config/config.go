@@ -0,0 +1,210 @@
+// Package config reads cambridge.toml project settings
+package config
+
+import (
+	"fmt"
+	"os"
+	"path/filepath"
+	"strconv"
+	"strings"
+)
+
+// FileName is the name of the project settings file
+const FileName = "cambridge.toml"
+
+// Config holds the settings from cambridge.toml
+type Config struct {
+	Project      Project
+	Language     Language
+	Diagnostics  Diagnostics
+	Capabilities Capabilities
+
+	// Path is the file the settings were read from, empty for defaults
+	Path string
+}
+
+// Project describes the program being developed
+type Project struct {
+	Name  string
+	Entry string
+}
+
+// Language selects the pseudocode dialect
+type Language struct {
+	Dialect string // ALevel or IGCSE
+	Real    string // FloatReals or DecimalReals
+	Insert  int    // the year of the exams whose insert lists the builtins, 0 for the latest
+}
+
+// The values of dialect in the [language] table: the syllabus codes of
+// A Level and IGCSE Computer Science
+const (
+	ALevel = "9618"
+	IGCSE  = "0478"
+)
+
+// The values of real in the [language] table
+const (
+	FloatReals   = "float"
+	DecimalReals = "decimal"
+)
+
+// Diagnostics controls how errors are reported
+type Diagnostics struct {
+	Locale string
+}
+
+// Capabilities turns off groups of statements and builtins, with a key
+// such as files = false in the [capabilities] table
+type Capabilities struct {
+	Disabled []string // in the order given
+}
+
+// Default returns the settings used when no cambridge.toml exists
+func Default() *Config {
+	return &Config{Language: Language{Dialect: ALevel, Real: FloatReals}}
+}
+
+// Find looks for cambridge.toml in dir and each of its parents, returning
+// the default settings if there is none
+func Find(dir string) (*Config, error) {
+	dir, err := filepath.Abs(dir)
+	if err != nil {
+		return nil, err
+	}
+
+	for {
+		path := filepath.Join(dir, FileName)
+		if _, err := os.Stat(path); err == nil {
+			return Load(path)
+		}
+
+		parent := filepath.Dir(dir)
+		if parent == dir {
+			return Default(), nil
+		}
+		dir = parent
+	}
+}
+
+// Load reads the settings in the file at path
+func Load(path string) (*Config, error) {
+	content, err := os.ReadFile(path)
+	if err != nil {
+		return nil, err
+	}
+
+	cfg, err := Parse(string(content))
+	if err != nil {
+		return nil, fmt.Errorf("%s:%w", path, err)
+	}
+	cfg.Path = path
+	return cfg, nil
+}
+
+// Parse reads settings from the text of a cambridge.toml file
+// Only the subset of TOML the file uses is supported: [tables], and keys
+// with string, integer or boolean values. Unknown keys are ignored.
+func Parse(text string) (*Config, error) {
+	cfg := Default()
+	table := ""
+
+	for n, line := range strings.Split(text, "\n") {
+		line = strings.TrimSpace(stripComment(line))
+		if line == "" {
+			continue
+		}
+
+		if strings.HasPrefix(line, "[") {
+			if !strings.HasSuffix(line, "]") {
+				return nil, fmt.Errorf("%d: unterminated table header", n+1)
+			}
+			table = strings.TrimSpace(line[1 : len(line)-1])
+			continue
+		}
+
+		key, raw, ok := strings.Cut(line, "=")
+		if !ok {
+			return nil, fmt.Errorf("%d: expected key = value", n+1)
+		}
+		value, err := parseValue(strings.TrimSpace(raw))
+		if err != nil {
+			return nil, fmt.Errorf("%d: %v", n+1, err)
+		}
+
+		if err := cfg.set(table+"."+strings.TrimSpace(key), value); err != nil {
+			return nil, fmt.Errorf("%d: %v", n+1, err)
+		}
+	}
+
+	return cfg, nil
+}
+
+func (c *Config) set(key, value string) error {
+	switch key {
+	case "project.name":
+		c.Project.Name = value
+	case "project.entry":
+		c.Project.Entry = value
+	case "language.dialect":
+		c.Language.Dialect = value
+	case "language.real":
+		c.Language.Real = value
+	case "language.insert":
+		year, err := strconv.Atoi(value)
+		if err != nil || year < 0 {
+			return fmt.Errorf("insert must be the year of an exam series, not %q", value)
+		}
+		c.Language.Insert = year
+	case "diagnostics.locale":
+		c.Diagnostics.Locale = value
+	default:
+		if name, ok := strings.CutPrefix(key, "capabilities."); ok && value == "false" {
+			c.Capabilities.Disabled = append(c.Capabilities.Disabled, name)
+		}
+	}
+	return nil
+}
+
+// parseValue decodes a TOML string, integer or boolean as text
+func parseValue(raw string) (string, error) {
+	switch {
+	case strings.HasPrefix(raw, `"`):
+		return strconv.Unquote(raw)
+	case strings.HasPrefix(raw, "'"):
+		// Literal strings have no escapes
+		if len(raw) < 2 || !strings.HasSuffix(raw, "'") {
+			return "", fmt.Errorf("unterminated string %s", raw)
+		}
+		return raw[1 : len(raw)-1], nil
+	case raw == "true" || raw == "false":
+		return raw, nil
+	}
+	if _, err := strconv.ParseInt(raw, 10, 64); err == nil {
+		return raw, nil
+	}
+	return "", fmt.Errorf("unsupported value %s", raw)
+}
+
+// stripComment removes a # comment that is not inside a string
+func stripComment(line string) string {
+	var quote rune
+	escaped := false
+	for i, ch := range line {
+		switch {
+		case escaped:
+			escaped = false
+		case quote == '"' && ch == '\\':
+			escaped = true
+		case quote != 0:
+			if ch == quote {
+				quote = 0
+			}
+		case ch == '"' || ch == '\'':
+			quote = ch
+		case ch == '#':
+			return line[:i]
+		}
+	}
+	return line
+}
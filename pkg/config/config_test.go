@@ -0,0 +1,99 @@
+package config
+
+import (
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+)
+
+func TestParse(t *testing.T) {
+	cfg, err := Parse(`# Project settings
+[project]
+name = "coursework" # trailing comment
+entry = 'main.cam'
+
+[language]
+dialect = "0478"
+real = "decimal"
+insert = 2023
+
+[diagnostics]
+locale = "es"
+
+[capabilities]
+files = false
+random = true
+input = false
+
+[unknown]
+answer = 42
+`)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if cfg.Project.Name != "coursework" || cfg.Project.Entry != "main.cam" {
+		t.Errorf("unexpected project settings: %+v", cfg.Project)
+	}
+	if cfg.Language.Dialect != "0478" {
+		t.Errorf("expected dialect 0478, got %q", cfg.Language.Dialect)
+	}
+	if cfg.Language.Real != DecimalReals {
+		t.Errorf("expected decimal reals, got %q", cfg.Language.Real)
+	}
+	if cfg.Language.Insert != 2023 {
+		t.Errorf("expected the 2023 insert, got %d", cfg.Language.Insert)
+	}
+	if cfg.Diagnostics.Locale != "es" {
+		t.Errorf("expected locale es, got %q", cfg.Diagnostics.Locale)
+	}
+	if got := strings.Join(cfg.Capabilities.Disabled, ","); got != "files,input" {
+		t.Errorf("expected files and input to be disabled, got %q", got)
+	}
+}
+
+func TestParseErrors(t *testing.T) {
+	tests := []string{
+		"[project",
+		"name",
+		`name = "unterminated`,
+		"name = value",
+		"[language]\ninsert = \"latest\"",
+	}
+
+	for _, input := range tests {
+		if _, err := Parse(input); err == nil {
+			t.Errorf("expected an error for %q", input)
+		}
+	}
+}
+
+func TestFindSearchesParents(t *testing.T) {
+	root := t.TempDir()
+	nested := filepath.Join(root, "src", "week1")
+	if err := os.MkdirAll(nested, 0755); err != nil {
+		t.Fatal(err)
+	}
+	if err := os.WriteFile(filepath.Join(root, FileName), []byte("[project]\nname = \"root\"\n"), 0644); err != nil {
+		t.Fatal(err)
+	}
+
+	cfg, err := Find(nested)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if cfg.Project.Name != "root" {
+		t.Errorf("expected settings from the project root, got %+v", cfg)
+	}
+}
+
+func TestFindDefaults(t *testing.T) {
+	cfg, err := Find(t.TempDir())
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if cfg.Path != "" || cfg.Language.Dialect != "9618" {
+		t.Errorf("expected default settings, got %+v", cfg)
+	}
+}
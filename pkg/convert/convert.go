@@ -0,0 +1,150 @@
+// Package convert rewrites operator symbols between their Unicode glyphs
+// and ASCII spellings
+package convert
+
+import (
+	"fmt"
+	"strings"
+)
+
+// Direction selects which spelling of the operators Convert produces
+type Direction int
+
+const (
+	// ToASCII rewrites glyphs such as ← and ≠ as <- and <>
+	ToASCII Direction = iota
+	// ToUnicode rewrites <-, <>, <= and >= as ←, ≠, ≤ and ≥
+	ToUnicode
+)
+
+// ParseDirection maps a --to flag value onto a Direction
+func ParseDirection(s string) (Direction, error) {
+	switch strings.ToLower(s) {
+	case "ascii":
+		return ToASCII, nil
+	case "unicode":
+		return ToUnicode, nil
+	}
+	return ToASCII, fmt.Errorf("unknown target %q (expected ascii or unicode)", s)
+}
+
+// operators lists the symbol pairs understood in both directions
+var operators = []struct {
+	glyph string
+	ascii string
+}{
+	{"←", "<-"},
+	{"≠", "<>"},
+	{"≤", "<="},
+	{"≥", ">="},
+}
+
+// asciiOnly lists glyphs common in copied material that are only ever
+// converted to ASCII
+var asciiOnly = []struct {
+	glyph string
+	ascii string
+}{
+	{"×", "*"},
+	{"÷", "/"},
+	{"−", "-"}, // U+2212 MINUS SIGN
+}
+
+// Convert rewrites the operators in source in the given direction
+// String and character literals and comments are copied unchanged, except
+// that typographic quotes delimiting them become plain quotes under ToASCII.
+func Convert(source string, dir Direction) string {
+	var out strings.Builder
+	out.Grow(len(source))
+
+	for i := 0; i < len(source); {
+		rest := source[i:]
+
+		// Comments run to the end of the line
+		if strings.HasPrefix(rest, "//") {
+			end := strings.IndexByte(rest, '\n')
+			if end < 0 {
+				end = len(rest)
+			}
+			out.WriteString(rest[:end])
+			i += end
+			continue
+		}
+
+		if quote, width, ok := openingQuote(rest, dir); ok {
+			out.WriteByte(quote)
+			i += width
+			i += copyLiteral(&out, source[i:], quote, dir)
+			continue
+		}
+
+		if replacement, width, ok := operator(rest, dir); ok {
+			out.WriteString(replacement)
+			i += width
+			continue
+		}
+
+		out.WriteByte(source[i])
+		i++
+	}
+
+	return out.String()
+}
+
+// operator reports the replacement for an operator at the start of s
+func operator(s string, dir Direction) (string, int, bool) {
+	for _, op := range operators {
+		if dir == ToASCII && strings.HasPrefix(s, op.glyph) {
+			return op.ascii, len(op.glyph), true
+		}
+		if dir == ToUnicode && strings.HasPrefix(s, op.ascii) {
+			return op.glyph, len(op.ascii), true
+		}
+	}
+	if dir == ToASCII {
+		for _, op := range asciiOnly {
+			if strings.HasPrefix(s, op.glyph) {
+				return op.ascii, len(op.glyph), true
+			}
+		}
+	}
+	return "", 0, false
+}
+
+// openingQuote reports whether s starts a string or character literal,
+// returning the plain quote character to write and the width consumed
+func openingQuote(s string, dir Direction) (byte, int, bool) {
+	switch {
+	case s[0] == '"' || s[0] == '\'':
+		return s[0], 1, true
+	case dir == ToASCII && (strings.HasPrefix(s, "“") || strings.HasPrefix(s, "”")):
+		return '"', len("“"), true
+	case dir == ToASCII && (strings.HasPrefix(s, "‘") || strings.HasPrefix(s, "’")):
+		return '\'', len("‘"), true
+	}
+	return 0, 0, false
+}
+
+// copyLiteral copies the body of a literal opened with quote, including its
+// closing quote, and returns the number of bytes consumed
+// Like the lexer, a literal ends at a newline if it is never closed.
+func copyLiteral(out *strings.Builder, s string, quote byte, dir Direction) int {
+	for i := 0; i < len(s); {
+		switch {
+		case s[i] == '\n':
+			return i
+		case s[i] == quote:
+			out.WriteByte(quote)
+			return i + 1
+		case dir == ToASCII && quote == '"' && (strings.HasPrefix(s[i:], "“") || strings.HasPrefix(s[i:], "”")):
+			out.WriteByte(quote)
+			return i + len("”")
+		case dir == ToASCII && quote == '\'' && (strings.HasPrefix(s[i:], "‘") || strings.HasPrefix(s[i:], "’")):
+			out.WriteByte(quote)
+			return i + len("’")
+		}
+		out.WriteByte(s[i])
+		i++
+	}
+	return len(s)
+}
@@ -0,0 +1,55 @@
+package convert
+
+import "testing"
+
+func TestConvert(t *testing.T) {
+	tests := []struct {
+		name     string
+		input    string
+		dir      Direction
+		expected string
+	}{
+		{"arrow to ascii", "x ← 5", ToASCII, "x <- 5"},
+		{"comparisons to ascii", "IF a ≠ b AND c ≤ d OR e ≥ f THEN", ToASCII, "IF a <> b AND c <= d OR e >= f THEN"},
+		{"arithmetic to ascii", "x ← a × b ÷ c − 1", ToASCII, "x <- a * b / c - 1"},
+		{"arrow to unicode", "x <- 5", ToUnicode, "x ← 5"},
+		{"comparisons to unicode", "WHILE a <> b AND c <= d AND e >= f", ToUnicode, "WHILE a ≠ b AND c ≤ d AND e ≥ f"},
+		{"less than left alone", "IF a < b THEN", ToUnicode, "IF a < b THEN"},
+		{"strings untouched", `OUTPUT "a <- b ≠ c"`, ToUnicode, `OUTPUT "a <- b ≠ c"`},
+		{"strings untouched to ascii", `OUTPUT "x ← 1" & x ← 2`, ToASCII, `OUTPUT "x ← 1" & x <- 2`},
+		{"char literal untouched", `c <- '<'`, ToUnicode, `c ← '<'`},
+		{"comments untouched", "x <- 1 // a <- b\ny <- 2", ToUnicode, "x ← 1 // a <- b\ny ← 2"},
+		{"smart quotes", "OUTPUT “Hello ← World” & ‘!’", ToASCII, `OUTPUT "Hello ← World" & '!'`},
+		{"unterminated string", "OUTPUT \"a <- b\nx <- 1", ToUnicode, "OUTPUT \"a <- b\nx ← 1"},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			if got := Convert(tt.input, tt.dir); got != tt.expected {
+				t.Errorf("expected %q, got %q", tt.expected, got)
+			}
+		})
+	}
+}
+
+func TestRoundTrip(t *testing.T) {
+	input := `DECLARE i : INTEGER
+FOR i <- 1 TO 10
+    IF i <> 5 AND i <= 8 THEN
+        OUTPUT "i <- ", i
+    ENDIF
+NEXT i`
+
+	if got := Convert(Convert(input, ToUnicode), ToASCII); got != input {
+		t.Errorf("round trip changed the program:\n%s", got)
+	}
+}
+
+func TestParseDirection(t *testing.T) {
+	if d, err := ParseDirection("Unicode"); err != nil || d != ToUnicode {
+		t.Errorf("expected ToUnicode, got %v (%v)", d, err)
+	}
+	if _, err := ParseDirection("latin1"); err == nil {
+		t.Errorf("expected an error for an unknown target")
+	}
+}
@@ -0,0 +1,592 @@
+// Package dap is a debug adapter, speaking the Debug Adapter Protocol, so
+// that an editor can run a pseudocode program under its debugger
+// The editor launches a program by its path. What the program outputs is
+// sent to the editor as it is written, and how the program ended as an
+// exit code: 0 if it ran to the end and 1 if it stopped with an error.
+// Unless launched with noDebug, the program pauses at the breakpoints set
+// in it and can be stepped through a line at a time. While it is paused,
+// the editor can see the routine calls in progress and the variables each
+// can see, look inside arrays, records and objects, and work out
+// expressions or make assignments in the debug console.
+package dap
+
+import (
+	"bufio"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"net/textproto"
+	"os"
+	"path/filepath"
+	"strconv"
+	"strings"
+	"sync"
+	"sync/atomic"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/cambridge"
+	"github.com/andrinoff/cambridge-lang/pkg/config"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/logging"
+)
+
+var logger = logging.For("dap")
+
+// fuel is how much a program runs between checks that the editor has not
+// asked for it to stop
+const fuel = 10000
+
+// threadID is the one thread a program runs on
+const threadID = 1
+
+// request is a message from the editor asking for something to be done
+type request struct {
+	Seq       int             `json:"seq"`
+	Command   string          `json:"command"`
+	Arguments json.RawMessage `json:"arguments"`
+}
+
+type response struct {
+	Seq        int         `json:"seq"`
+	Type       string      `json:"type"`
+	RequestSeq int         `json:"request_seq"`
+	Success    bool        `json:"success"`
+	Command    string      `json:"command"`
+	Message    string      `json:"message,omitempty"`
+	Body       interface{} `json:"body,omitempty"`
+}
+
+type event struct {
+	Seq   int         `json:"seq"`
+	Type  string      `json:"type"`
+	Event string      `json:"event"`
+	Body  interface{} `json:"body,omitempty"`
+}
+
+// launchArguments are what launch and attach are given
+// Attaching starts the program as launching does, for editors that connect
+// to an adapter already listening rather than starting one themselves.
+type launchArguments struct {
+	Program     string `json:"program"`     // the path of the file to run
+	Input       string `json:"input"`       // the lines INPUT reads, none if empty
+	StopOnEntry bool   `json:"stopOnEntry"` // pause before the first statement
+	NoDebug     bool   `json:"noDebug"`     // run without pausing at breakpoints
+}
+
+// source is a file in the editor
+type source struct {
+	Path string `json:"path"`
+}
+
+type setBreakpointsArguments struct {
+	Source      source `json:"source"`
+	Breakpoints []struct {
+		Line         int    `json:"line"`
+		Condition    string `json:"condition"`    // pause only when it is TRUE
+		HitCondition string `json:"hitCondition"` // as parseHitCondition reads it
+	} `json:"breakpoints"`
+}
+
+type evaluateArguments struct {
+	Expression string `json:"expression"`
+	FrameID    int    `json:"frameId"`
+	Context    string `json:"context"` // watch, hover or repl
+}
+
+// session is one editor's conversation with the adapter
+type session struct {
+	reader *bufio.Reader
+
+	mu     sync.Mutex // guards writer and seq, as programs send events as they run
+	writer io.Writer
+	seq    int
+
+	program *cambridge.Program // launched, waiting for configurationDone
+	input   string
+	running *run
+
+	debugger    *debugger                      // nil if launched with noDebug
+	breakpoints map[string]map[int]*breakpoint // by path and line
+	containers  []container                    // by variablesReference, while paused
+}
+
+// run is a program being debugged
+type run struct {
+	stopped atomic.Bool // the editor asked for it to end
+	done    chan struct{}
+}
+
+// Serve debugs the programs an editor launches, reading its requests from
+// r and writing to w, until it disconnects
+func Serve(r io.Reader, w io.Writer) error {
+	s := &session{reader: bufio.NewReader(r), writer: w, breakpoints: map[string]map[int]*breakpoint{}}
+	defer s.stop()
+	for {
+		req, err := s.read()
+		if err != nil {
+			if errors.Is(err, io.EOF) {
+				return nil
+			}
+			return err
+		}
+		logger.Debug("request", "command", req.Command, "seq", req.Seq)
+		if !s.handle(req) {
+			return nil
+		}
+	}
+}
+
+// handle answers req, reporting false once the editor has disconnected
+func (s *session) handle(req request) bool {
+	switch req.Command {
+	case "initialize":
+		s.respond(req, map[string]interface{}{
+			"supportsConfigurationDoneRequest":  true,
+			"supportsTerminateRequest":          true,
+			"supportsConditionalBreakpoints":    true,
+			"supportsHitConditionalBreakpoints": true,
+			"supportsEvaluateForHovers":         true,
+		})
+		s.send("initialized", nil)
+	case "launch", "attach":
+		var args launchArguments
+		if err := json.Unmarshal(req.Arguments, &args); err != nil {
+			s.fail(req, "invalid arguments: %v", err)
+			return true
+		}
+		if err := s.load(args); err != nil {
+			s.fail(req, "%v", err)
+			return true
+		}
+		s.respond(req, nil)
+	case "setBreakpoints":
+		var args setBreakpointsArguments
+		if err := json.Unmarshal(req.Arguments, &args); err != nil {
+			s.fail(req, "invalid arguments: %v", err)
+			return true
+		}
+		s.respond(req, map[string]interface{}{"breakpoints": s.setBreakpoints(args)})
+	case "configurationDone":
+		s.respond(req, nil)
+		s.start()
+	case "continue", "next", "stepIn", "stepOut":
+		if s.debugger == nil || !s.debugger.resume(steps[req.Command]) {
+			s.fail(req, "the program is not paused")
+			return true
+		}
+		s.containers = nil
+		s.respond(req, map[string]interface{}{"allThreadsContinued": true})
+	case "pause":
+		if s.debugger == nil {
+			s.fail(req, "the program is not being debugged")
+			return true
+		}
+		s.debugger.interrupt()
+		s.respond(req, nil)
+	case "stackTrace":
+		s.respond(req, s.stackTrace())
+	case "scopes":
+		var args struct {
+			FrameID int `json:"frameId"`
+		}
+		json.Unmarshal(req.Arguments, &args)
+		scopes, err := s.scopes(args.FrameID)
+		if err != nil {
+			s.fail(req, "%v", err)
+			return true
+		}
+		s.respond(req, map[string]interface{}{"scopes": scopes})
+	case "variables":
+		var args struct {
+			Reference int `json:"variablesReference"`
+			Start     int `json:"start"`
+			Count     int `json:"count"`
+		}
+		json.Unmarshal(req.Arguments, &args)
+		if s.pausedAt() == nil || args.Reference < 1 || args.Reference > len(s.containers) {
+			s.fail(req, "no variables to show: the program is not paused there")
+			return true
+		}
+		vars := s.children(s.containers[args.Reference-1], args.Start, args.Count)
+		s.respond(req, map[string]interface{}{"variables": vars})
+	case "evaluate":
+		var args evaluateArguments
+		if err := json.Unmarshal(req.Arguments, &args); err != nil {
+			s.fail(req, "invalid arguments: %v", err)
+			return true
+		}
+		result, err := s.evaluate(args)
+		if err != nil {
+			s.fail(req, "%v", err)
+			return true
+		}
+		s.respond(req, result)
+	case "threads":
+		s.respond(req, map[string]interface{}{
+			"threads": []map[string]interface{}{{"id": threadID, "name": "main"}},
+		})
+	case "terminate":
+		s.respond(req, nil)
+		s.stop()
+	case "disconnect":
+		s.stop()
+		s.respond(req, nil)
+		return false
+	default:
+		s.fail(req, "%s is not supported", req.Command)
+	}
+	return true
+}
+
+// load reads and parses the program args name, ready to start
+func (s *session) load(args launchArguments) error {
+	if args.Program == "" {
+		return fmt.Errorf("no program to run: set program to the path of a file")
+	}
+	source, err := os.ReadFile(args.Program)
+	if err != nil {
+		return err
+	}
+	prog := cambridge.Parse(args.Program, string(source))
+	for _, d := range prog.Diagnostics() {
+		if d.Severity == diagnostic.Error {
+			return &cambridge.Error{Program: args.Program, Diagnostic: d}
+		}
+	}
+	s.program, s.input = prog, args.Input
+	s.debugger = nil
+	if !args.NoDebug {
+		s.debugger = &debugger{s: s}
+		if args.StopOnEntry {
+			s.debugger.step = entering
+		}
+	}
+	return nil
+}
+
+// steps are what each request to go on has the program do before it
+// next pauses
+var steps = map[string]step{
+	"continue": running,
+	"next":     stepOver,
+	"stepIn":   stepIn,
+	"stepOut":  stepOut,
+}
+
+// setBreakpoints replaces the breakpoints in the file args names, moving
+// each to the first line at or after it a program can be paused at
+func (s *session) setBreakpoints(args setBreakpointsArguments) []map[string]interface{} {
+	path := filepath.Clean(args.Source.Path)
+	var lines []int
+	if text, err := os.ReadFile(path); err == nil {
+		lines = statementLines(cambridge.Parse(path, string(text)).AST())
+	}
+	set := map[int]*breakpoint{}
+	verified := make([]map[string]interface{}, 0, len(args.Breakpoints))
+	for _, bp := range args.Breakpoints {
+		line, ok := breakpointLine(lines, bp.Line)
+		if !ok {
+			verified = append(verified, map[string]interface{}{"verified": false, "line": bp.Line, "message": "there is no statement here"})
+			continue
+		}
+		b, err := newBreakpoint(bp.Condition, bp.HitCondition)
+		if err != nil {
+			verified = append(verified, map[string]interface{}{"verified": false, "line": line, "message": err.Error()})
+			continue
+		}
+		set[line] = b
+		verified = append(verified, map[string]interface{}{"verified": true, "line": line})
+	}
+	s.breakpoints[path] = set
+	if s.debugger != nil && s.program != nil && filepath.Clean(s.program.Name) == path {
+		s.debugger.setBreakpoints(set)
+	}
+	return verified
+}
+
+// newBreakpoint makes a breakpoint pausing when condition, if any, holds
+// and it has been reached as often as hitCondition asks
+func newBreakpoint(condition, hitCondition string) (*breakpoint, error) {
+	bp := &breakpoint{}
+	if strings.TrimSpace(condition) != "" {
+		expr, err := parseExpression(condition)
+		if err != nil {
+			return nil, err
+		}
+		bp.condition = expr
+	}
+	hits, err := parseHitCondition(hitCondition)
+	if err != nil {
+		return nil, err
+	}
+	bp.hits = hits
+	return bp, nil
+}
+
+// evaluate works out the expression args gives in the frame it names,
+// while the program is paused, or in the debug console makes the
+// assignment it gives
+func (s *session) evaluate(args evaluateArguments) (map[string]interface{}, error) {
+	p := s.pausedAt()
+	if p == nil || args.FrameID < 0 || args.FrameID >= len(p.frames) {
+		return nil, fmt.Errorf("cannot evaluate %s: the program is not paused", args.Expression)
+	}
+	stmt, err := parseStatement(args.Expression)
+	if err != nil {
+		return nil, err
+	}
+	env := p.frames[p.depth()-args.FrameID].env
+	var run func() (interpreter.Object, *interpreter.Error)
+	switch stmt := stmt.(type) {
+	case *ast.ExpressionStatement:
+		run = func() (interpreter.Object, *interpreter.Error) { return p.interp.Evaluate(stmt.Expression, env) }
+	case *ast.AssignmentStatement:
+		if args.Context != "repl" {
+			return nil, fmt.Errorf("cannot evaluate %s: assignments can only be made in the debug console", args.Expression)
+		}
+		run = func() (interpreter.Object, *interpreter.Error) { return p.interp.Assign(stmt, env) }
+	}
+	var value interpreter.Object
+	var failed *interpreter.Error
+	if !s.debugger.within(p, func() { value, failed = run() }) {
+		return nil, fmt.Errorf("cannot evaluate %s: the program is not paused", args.Expression)
+	}
+	if failed != nil {
+		return nil, errors.New(failed.Message)
+	}
+	v := s.variable(args.Expression, value, nil)
+	v["result"] = v["value"]
+	delete(v, "value")
+	delete(v, "name")
+	return v, nil
+}
+
+// parseExpression parses text as a single pseudocode expression
+func parseExpression(text string) (ast.Expression, error) {
+	stmt, err := parseStatement(text)
+	if err != nil {
+		return nil, err
+	}
+	expr, ok := stmt.(*ast.ExpressionStatement)
+	if !ok {
+		return nil, fmt.Errorf("cannot parse %s: expected an expression", strings.TrimSpace(text))
+	}
+	return expr.Expression, nil
+}
+
+// parseStatement parses text as a single expression or assignment, the
+// statements the debug console runs
+func parseStatement(text string) (ast.Statement, error) {
+	prog := cambridge.Parse("expression", text)
+	for _, d := range prog.Diagnostics() {
+		if d.Severity == diagnostic.Error {
+			return nil, fmt.Errorf("cannot parse %s: %s", strings.TrimSpace(text), d.Message)
+		}
+	}
+	stmts := prog.AST().Statements
+	if len(stmts) == 1 {
+		switch stmt := stmts[0].(type) {
+		case *ast.ExpressionStatement, *ast.AssignmentStatement:
+			return stmt, nil
+		}
+	}
+	return nil, fmt.Errorf("cannot parse %s: expected an expression or assignment", strings.TrimSpace(text))
+}
+
+// stackTrace returns the routine calls in progress where the program is
+// paused, innermost first, each numbered by how far out it is
+func (s *session) stackTrace() map[string]interface{} {
+	frames := []map[string]interface{}{}
+	if p := s.pausedAt(); p != nil {
+		calls := p.interp.CallStack()
+		for id := 0; id < len(p.frames); id++ {
+			depth := p.depth() - id
+			name := "main program"
+			if depth > 0 && depth <= len(calls) {
+				name = calls[depth-1].Name
+			}
+			line, column := ast.Position(p.frames[depth].stmt)
+			frames = append(frames, map[string]interface{}{
+				"id": id, "name": name, "line": line, "column": column,
+				"source": source{Path: s.program.Name},
+			})
+		}
+	}
+	return map[string]interface{}{"stackFrames": frames, "totalFrames": len(frames)}
+}
+
+// scopes returns the parameters, local and global variables that can be
+// seen from the frame numbered id
+func (s *session) scopes(id int) ([]map[string]interface{}, error) {
+	p := s.pausedAt()
+	if p == nil || id < 0 || id >= len(p.frames) {
+		return nil, fmt.Errorf("no frame %d: the program is not paused there", id)
+	}
+	depth := p.depth() - id
+	scope := p.interp.Scope(p.frames[depth].env, depth)
+	var scopes []map[string]interface{}
+	add := func(name, hint string, part func(interpreter.Scope) []interpreter.Variable) {
+		vars := func() []interpreter.Variable { return part(p.interp.Scope(p.frames[depth].env, depth)) }
+		scopes = append(scopes, map[string]interface{}{
+			"name": name, "presentationHint": hint, "expensive": false,
+			"variablesReference": s.reference(container{vars: vars}), "namedVariables": len(part(scope)),
+		})
+	}
+	if depth > 0 {
+		add("Parameters", "arguments", func(sc interpreter.Scope) []interpreter.Variable { return sc.Parameters })
+	}
+	if depth > 0 || len(scope.Locals) > 0 {
+		add("Locals", "locals", func(sc interpreter.Scope) []interpreter.Variable { return sc.Locals })
+	}
+	add("Globals", "globals", func(sc interpreter.Scope) []interpreter.Variable { return sc.Globals })
+	return scopes, nil
+}
+
+// pausedAt returns where the program is paused, or nil if it is not
+func (s *session) pausedAt() *pause {
+	if s.debugger == nil {
+		return nil
+	}
+	return s.debugger.where()
+}
+
+// start runs the launched program, as cambridge run would in its
+// directory, sending its output and how it ended to the editor
+func (s *session) start() {
+	if s.program == nil || s.running != nil {
+		return
+	}
+	interp := cambridge.New()
+	if err := configure(interp, filepath.Dir(s.program.Name)); err != nil {
+		s.output("stderr", err.Error()+"\n")
+	}
+	interp.SetInput(strings.NewReader(s.input))
+	interp.SetOutput(writer{s, "stdout"})
+	if s.debugger != nil {
+		s.debugger.setBreakpoints(s.breakpoints[filepath.Clean(s.program.Name)])
+		interp.SetDebugger(s.debugger)
+	}
+
+	r := &run{done: make(chan struct{})}
+	s.running = r
+	exec := interp.Start(s.program)
+	go func() {
+		defer close(r.done)
+		for {
+			done, err := exec.Continue(fuel)
+			if done && !r.stopped.Load() {
+				code := 0
+				if err != nil {
+					s.output("stderr", err.Error()+"\n")
+					code = 1
+				}
+				s.send("exited", map[string]interface{}{"exitCode": code})
+				s.send("terminated", nil)
+				return
+			}
+			if r.stopped.Load() {
+				exec.Stop()
+				s.send("terminated", nil)
+				return
+			}
+		}
+	}()
+}
+
+// stop ends the program running, if there is one, once it has finished
+// its current instalment or, if paused, before its next statement
+func (s *session) stop() {
+	if s.running != nil {
+		s.running.stopped.Store(true)
+		if s.debugger != nil {
+			s.debugger.end()
+		}
+		<-s.running.done
+	}
+}
+
+// configure applies the cambridge.toml found from dir to interp
+func configure(interp *cambridge.Interpreter, dir string) error {
+	settings, err := config.Find(dir)
+	if err != nil {
+		return err
+	}
+	interp.SetArithmetic(interpreter.Arithmetic{DecimalReals: settings.Language.Real == config.DecimalReals})
+	for _, name := range settings.Capabilities.Disabled {
+		capability, err := interpreter.ParseCapability(name)
+		if err != nil {
+			return err
+		}
+		interp.Disable(capability)
+	}
+	return nil
+}
+
+// writer sends what a program writes to the editor as output events
+type writer struct {
+	s        *session
+	category string
+}
+
+func (w writer) Write(b []byte) (int, error) {
+	w.s.output(w.category, string(b))
+	return len(b), nil
+}
+
+func (s *session) output(category, text string) {
+	s.send("output", map[string]interface{}{"category": category, "output": text})
+}
+
+// read returns the next request, each preceded by a header giving its
+// Content-Length as in the language server protocol
+func (s *session) read() (request, error) {
+	header, err := textproto.NewReader(s.reader).ReadMIMEHeader()
+	if err != nil {
+		return request{}, err
+	}
+	length, err := strconv.Atoi(header.Get("Content-Length"))
+	if err != nil {
+		return request{}, fmt.Errorf("invalid Content-Length: %v", err)
+	}
+	body := make([]byte, length)
+	if _, err := io.ReadFull(s.reader, body); err != nil {
+		return request{}, err
+	}
+	var req request
+	if err := json.Unmarshal(body, &req); err != nil {
+		return request{}, fmt.Errorf("invalid request: %v", err)
+	}
+	return req, nil
+}
+
+func (s *session) respond(req request, body interface{}) {
+	s.write(func(seq int) interface{} {
+		return response{Seq: seq, Type: "response", RequestSeq: req.Seq, Success: true, Command: req.Command, Body: body}
+	})
+}
+
+func (s *session) fail(req request, format string, args ...interface{}) {
+	message := fmt.Sprintf(format, args...)
+	s.write(func(seq int) interface{} {
+		return response{Seq: seq, Type: "response", RequestSeq: req.Seq, Command: req.Command, Message: message}
+	})
+}
+
+func (s *session) send(name string, body interface{}) {
+	s.write(func(seq int) interface{} {
+		return event{Seq: seq, Type: "event", Event: name, Body: body}
+	})
+}
+
+// write numbers and sends the message msg makes
+func (s *session) write(msg func(seq int) interface{}) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+	s.seq++
+	content, _ := json.Marshal(msg(s.seq))
+	if _, err := fmt.Fprintf(s.writer, "Content-Length: %d\r\n\r\n%s", len(content), content); err != nil {
+		logger.Warn("cannot send message", "error", err)
+	}
+}
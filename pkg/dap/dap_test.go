@@ -0,0 +1,482 @@
+package dap
+
+import (
+	"bufio"
+	"encoding/json"
+	"fmt"
+	"io"
+	"net/textproto"
+	"os"
+	"path/filepath"
+	"strconv"
+	"strings"
+	"testing"
+)
+
+// client drives a session the way an editor would
+type client struct {
+	t      *testing.T
+	writer io.Writer
+	reader *bufio.Reader
+	seq    int
+}
+
+func newClient(t *testing.T) *client {
+	t.Helper()
+	toAdapter, fromClient := io.Pipe()
+	fromAdapter, toClient := io.Pipe()
+	done := make(chan error, 1)
+	go func() {
+		done <- Serve(toAdapter, toClient)
+		toClient.Close()
+	}()
+	t.Cleanup(func() {
+		fromClient.Close()
+		fromAdapter.Close()
+		if err := <-done; err != nil {
+			t.Errorf("serve: %v", err)
+		}
+	})
+	return &client{t: t, writer: fromClient, reader: bufio.NewReader(fromAdapter)}
+}
+
+func (c *client) send(command string, args interface{}) {
+	c.t.Helper()
+	c.seq++
+	body, _ := json.Marshal(map[string]interface{}{"seq": c.seq, "type": "request", "command": command, "arguments": args})
+	if _, err := fmt.Fprintf(c.writer, "Content-Length: %d\r\n\r\n%s", len(body), body); err != nil {
+		c.t.Fatalf("cannot send %s: %v", command, err)
+	}
+}
+
+// next returns the next message, decoded
+func (c *client) next() map[string]interface{} {
+	c.t.Helper()
+	header, err := textproto.NewReader(c.reader).ReadMIMEHeader()
+	if err != nil {
+		c.t.Fatalf("cannot read header: %v", err)
+	}
+	length, _ := strconv.Atoi(header.Get("Content-Length"))
+	body := make([]byte, length)
+	if _, err := io.ReadFull(c.reader, body); err != nil {
+		c.t.Fatalf("cannot read body: %v", err)
+	}
+	var msg map[string]interface{}
+	if err := json.Unmarshal(body, &msg); err != nil {
+		c.t.Fatalf("invalid message %s: %v", body, err)
+	}
+	return msg
+}
+
+// until reads messages up to the event or response named, returning it
+// and the output events before it
+func (c *client) until(name string) (map[string]interface{}, string) {
+	c.t.Helper()
+	var output strings.Builder
+	for {
+		msg := c.next()
+		if msg["event"] == name || msg["type"] == "response" && msg["command"] == name {
+			return msg, output.String()
+		}
+		if msg["event"] == "output" {
+			body := msg["body"].(map[string]interface{})
+			output.WriteString(body["category"].(string) + ": " + body["output"].(string))
+		}
+	}
+}
+
+// launch starts the program src in a new session with args and
+// breakpoints on the lines given, returning the client
+func launch(t *testing.T, src string, args map[string]interface{}, lines ...int) *client {
+	t.Helper()
+	var breakpoints []map[string]interface{}
+	for _, line := range lines {
+		breakpoints = append(breakpoints, map[string]interface{}{"line": line})
+	}
+	c, path := load(t, src, args)
+	if len(breakpoints) > 0 {
+		c.setBreakpoints(path, breakpoints...)
+	}
+	c.send("configurationDone", nil)
+	return c
+}
+
+// load launches the program src in a new session with args, returning the
+// client and the program's path, ready for configurationDone
+func load(t *testing.T, src string, args map[string]interface{}) (*client, string) {
+	t.Helper()
+	path := filepath.Join(t.TempDir(), "main.cam")
+	if err := os.WriteFile(path, []byte(src), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	c := newClient(t)
+	c.send("initialize", map[string]interface{}{"adapterID": "cambridge"})
+	c.until("initialized")
+	args["program"] = path
+	c.send("launch", args)
+	if resp, _ := c.until("launch"); resp["success"] != true {
+		t.Fatalf("launch failed: %v", resp["message"])
+	}
+	return c, path
+}
+
+// setBreakpoints sets breakpoints in path, returning the lines they were
+// put on, or 0 for those not verified
+func (c *client) setBreakpoints(path string, breakpoints ...map[string]interface{}) []int {
+	c.t.Helper()
+	c.send("setBreakpoints", map[string]interface{}{"source": map[string]interface{}{"path": path}, "breakpoints": breakpoints})
+	resp, _ := c.until("setBreakpoints")
+	var set []int
+	for _, bp := range resp["body"].(map[string]interface{})["breakpoints"].([]interface{}) {
+		bp := bp.(map[string]interface{})
+		if bp["verified"] == true {
+			set = append(set, int(bp["line"].(float64)))
+		} else {
+			set = append(set, 0)
+		}
+	}
+	return set
+}
+
+// stoppedAt waits for the program to pause, returning the line it paused
+// at
+func (c *client) stoppedAt() int {
+	c.t.Helper()
+	c.until("stopped")
+	c.send("stackTrace", map[string]interface{}{"threadId": threadID})
+	resp, _ := c.until("stackTrace")
+	frames := resp["body"].(map[string]interface{})["stackFrames"].([]interface{})
+	if len(frames) == 0 {
+		c.t.Fatalf("no stack frames while paused")
+	}
+	return int(frames[0].(map[string]interface{})["line"].(float64))
+}
+
+func TestRun(t *testing.T) {
+	tests := []struct {
+		name     string
+		src      string
+		input    string
+		output   string
+		exitCode float64
+	}{
+		{"finishes", "OUTPUT \"Hello\"\nOUTPUT 1 + 2\n", "", "stdout: Hello\nstdout: 3\n", 0},
+		{"reads input", "DECLARE Name : STRING\nINPUT Name\nOUTPUT \"Hi \", Name\n", "Ada\n", "stdout: Hi Ada\n", 0},
+		{"stops with an error", "OUTPUT 1\nOUTPUT 1 DIV 0\n", "", "stdout: 1\nstderr: ", 1},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			c := launch(t, tt.src, map[string]interface{}{"input": tt.input})
+			exited, output := c.until("exited")
+			if !strings.HasPrefix(output, tt.output) {
+				t.Errorf("expected output %q, got %q", tt.output, output)
+			}
+			if code := exited["body"].(map[string]interface{})["exitCode"]; code != tt.exitCode {
+				t.Errorf("expected exit code %v, got %v", tt.exitCode, code)
+			}
+			c.until("terminated")
+			c.send("disconnect", nil)
+			c.until("disconnect")
+		})
+	}
+}
+
+func TestLaunchErrors(t *testing.T) {
+	c := newClient(t)
+	c.send("initialize", nil)
+	c.until("initialized")
+
+	path := filepath.Join(t.TempDir(), "broken.cam")
+	os.WriteFile(path, []byte("IF TRUE THEN\nOUTPUT 1\n"), 0o644)
+	for _, program := range []string{"", filepath.Join(t.TempDir(), "missing.cam"), path} {
+		c.send("launch", map[string]interface{}{"program": program})
+		if resp, _ := c.until("launch"); resp["success"] != false || resp["message"] == "" {
+			t.Errorf("launching %q: expected a failure with a message, got %v", program, resp)
+		}
+	}
+	c.send("disconnect", nil)
+	c.until("disconnect")
+}
+
+const greet = `PROCEDURE Greet(N : INTEGER)
+    OUTPUT "Hi ", N
+ENDPROCEDURE
+DECLARE I : INTEGER
+FOR I <- 1 TO 2
+    CALL Greet(I)
+NEXT I
+OUTPUT "done"
+`
+
+func TestStepping(t *testing.T) {
+	tests := []struct {
+		name        string
+		stopOnEntry bool
+		breakpoints []int
+		command     string // sent each time the program pauses
+		lines       []int  // where it pauses
+	}{
+		{"continues to breakpoints", false, []int{2}, "continue", []int{2, 2}},
+		{"steps over calls", true, nil, "next", []int{4, 5, 6, 6, 8}},
+		{"steps into calls", true, nil, "stepIn", []int{4, 5, 6, 2, 6, 2, 8}},
+		{"steps out of calls", false, []int{2}, "stepOut", []int{2, 6, 2, 8}},
+		{"runs without pausing", false, nil, "continue", nil},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			c := launch(t, greet, map[string]interface{}{"stopOnEntry": tt.stopOnEntry}, tt.breakpoints...)
+			var lines []int
+			for range tt.lines {
+				lines = append(lines, c.stoppedAt())
+				c.send(tt.command, map[string]interface{}{"threadId": threadID})
+			}
+			if fmt.Sprint(lines) != fmt.Sprint(tt.lines) {
+				t.Errorf("expected to pause at %v, got %v", tt.lines, lines)
+			}
+			if _, output := c.until("exited"); !strings.HasSuffix(output, "stdout: done\n") {
+				t.Errorf("expected the program to finish, got %q", output)
+			}
+			c.send("disconnect", nil)
+			c.until("disconnect")
+		})
+	}
+}
+
+func TestSetBreakpoints(t *testing.T) {
+	path := filepath.Join(t.TempDir(), "main.cam")
+	os.WriteFile(path, []byte(greet), 0o644)
+	c := newClient(t)
+	c.send("initialize", nil)
+	c.until("initialized")
+	got := c.setBreakpoints(path,
+		map[string]interface{}{"line": 1},
+		map[string]interface{}{"line": 3},
+		map[string]interface{}{"line": 6, "condition": "I > 1"},
+		map[string]interface{}{"line": 9},
+		map[string]interface{}{"line": 6, "condition": "I >"},
+		map[string]interface{}{"line": 6, "hitCondition": ">x"},
+	)
+	if fmt.Sprint(got) != "[2 4 6 0 0 0]" {
+		t.Errorf("expected breakpoints on [2 4 6 0 0 0], got %v", got)
+	}
+	c.send("disconnect", nil)
+	c.until("disconnect")
+}
+
+func TestDisconnectWhilePaused(t *testing.T) {
+	c := launch(t, greet, map[string]interface{}{}, 2)
+	if line := c.stoppedAt(); line != 2 {
+		t.Fatalf("expected to pause at 2, got %d", line)
+	}
+	c.send("disconnect", nil)
+	if _, output := c.until("disconnect"); strings.Contains(output, "done") {
+		t.Errorf("expected the program to stop, got %q", output)
+	}
+}
+
+// request sends command and returns the body of its response
+func (c *client) request(command string, args interface{}) map[string]interface{} {
+	c.t.Helper()
+	c.send(command, args)
+	resp, _ := c.until(command)
+	if resp["success"] != true {
+		c.t.Fatalf("%s failed: %v", command, resp["message"])
+	}
+	body, _ := resp["body"].(map[string]interface{})
+	return body
+}
+
+// variables returns the values of the variables reference holds, by name,
+// and the references to expand each by
+func (c *client) variables(reference interface{}) (map[string]string, map[string]interface{}) {
+	c.t.Helper()
+	values, references := map[string]string{}, map[string]interface{}{}
+	body := c.request("variables", map[string]interface{}{"variablesReference": reference})
+	for _, v := range body["variables"].([]interface{}) {
+		v := v.(map[string]interface{})
+		values[v["name"].(string)] = v["value"].(string)
+		references[v["name"].(string)] = v["variablesReference"]
+	}
+	return values, references
+}
+
+func TestInspection(t *testing.T) {
+	src := `TYPE Point
+    DECLARE X : INTEGER
+    DECLARE Y : INTEGER
+ENDTYPE
+DECLARE Scores : ARRAY[1:3] OF INTEGER
+DECLARE P : Point
+PROCEDURE Fill(N : INTEGER)
+    DECLARE Total : INTEGER
+    Total <- N * 2
+    Scores[N] <- Total
+ENDPROCEDURE
+P.X <- 4
+Scores[1] <- 7
+CALL Fill(2)
+`
+	c := launch(t, src, map[string]interface{}{}, 10)
+	c.until("stopped")
+
+	var stack []string
+	for _, f := range c.request("stackTrace", map[string]interface{}{"threadId": threadID})["stackFrames"].([]interface{}) {
+		f := f.(map[string]interface{})
+		stack = append(stack, fmt.Sprintf("%s:%v", f["name"], f["line"]))
+	}
+	if fmt.Sprint(stack) != "[Fill:10 main program:14]" {
+		t.Errorf("unexpected stack %v", stack)
+	}
+
+	scopes := map[string]interface{}{}
+	for _, s := range c.request("scopes", map[string]interface{}{"frameId": 0})["scopes"].([]interface{}) {
+		s := s.(map[string]interface{})
+		scopes[s["name"].(string)] = s["variablesReference"]
+	}
+	if params, _ := c.variables(scopes["Parameters"]); fmt.Sprint(params) != "map[N:2]" {
+		t.Errorf("unexpected parameters %v", params)
+	}
+	if locals, _ := c.variables(scopes["Locals"]); fmt.Sprint(locals) != "map[Total:4]" {
+		t.Errorf("unexpected locals %v", locals)
+	}
+	globals, references := c.variables(scopes["Globals"])
+	if globals["Scores"] != "ARRAY[1:3]" || globals["P"] != "Point" || len(globals) != 2 {
+		t.Errorf("unexpected globals %v", globals)
+	}
+	if scores, _ := c.variables(references["Scores"]); scores["[1]"] != "7" || len(scores) != 3 {
+		t.Errorf("unexpected elements %v", scores)
+	}
+	if point, _ := c.variables(references["P"]); point["X"] != "4" || len(point) != 2 {
+		t.Errorf("unexpected fields %v", point)
+	}
+
+	c.send("continue", map[string]interface{}{"threadId": threadID})
+	c.until("exited")
+	c.send("disconnect", nil)
+	c.until("disconnect")
+}
+
+const sum = `DECLARE I : INTEGER
+DECLARE Total : INTEGER
+Total <- 0
+FOR I <- 1 TO 10
+    Total <- Total + I
+NEXT I
+`
+
+func TestConditionalBreakpoints(t *testing.T) {
+	tests := []struct {
+		name         string
+		condition    string
+		hitCondition string
+		paused       []string // the values of I where the program pauses
+	}{
+		{"condition", "I = 7", "", []string{"7"}},
+		{"hit count", "", "3", []string{"3"}},
+		{"hit count at least", "", ">=9", []string{"9", "10"}},
+		{"every nth hit", "", "%4", []string{"4", "8"}},
+		{"condition and hit count", "I MOD 2 = 0", "%2", []string{"4", "8"}},
+		{"condition that cannot be checked", "Missing > 1", "", strings.Fields("1 2 3 4 5 6 7 8 9 10")},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			c, path := load(t, sum, map[string]interface{}{})
+			c.setBreakpoints(path, map[string]interface{}{"line": 5, "condition": tt.condition, "hitCondition": tt.hitCondition})
+			c.send("configurationDone", nil)
+			var paused []string
+			for range tt.paused {
+				c.until("stopped")
+				paused = append(paused, c.request("evaluate", map[string]interface{}{"expression": "I", "frameId": 0, "context": "watch"})["result"].(string))
+				c.send("continue", map[string]interface{}{"threadId": threadID})
+			}
+			if fmt.Sprint(paused) != fmt.Sprint(tt.paused) {
+				t.Errorf("expected to pause where I is %v, got %v", tt.paused, paused)
+			}
+			c.until("exited")
+			c.send("disconnect", nil)
+			c.until("disconnect")
+		})
+	}
+}
+
+func TestWatch(t *testing.T) {
+	c, path := load(t, sum, map[string]interface{}{})
+	c.setBreakpoints(path, map[string]interface{}{"line": 5, "condition": "I = 7"})
+	c.send("configurationDone", nil)
+	c.until("stopped")
+
+	tests := []struct {
+		expression string
+		result     string
+		ok         bool
+	}{
+		{"Total * 2", "42", true},
+		{"I = 7 AND Total > 20", "TRUE", true},
+		{"Missing + 1", "", false},
+		{"Total <-", "", false},
+	}
+	for _, tt := range tests {
+		c.send("evaluate", map[string]interface{}{"expression": tt.expression, "frameId": 0, "context": "watch"})
+		resp, _ := c.until("evaluate")
+		if ok := resp["success"] == true; ok != tt.ok {
+			t.Errorf("evaluating %q: expected success %v, got %v", tt.expression, tt.ok, resp)
+			continue
+		}
+		if tt.ok {
+			if result := resp["body"].(map[string]interface{})["result"]; result != tt.result {
+				t.Errorf("evaluating %q: expected %s, got %v", tt.expression, tt.result, result)
+			}
+		}
+	}
+
+	c.send("continue", map[string]interface{}{"threadId": threadID})
+	c.until("exited")
+	c.send("disconnect", nil)
+	c.until("disconnect")
+}
+
+func TestConsole(t *testing.T) {
+	c, path := load(t, sum, map[string]interface{}{})
+	c.setBreakpoints(path, map[string]interface{}{"line": 5, "condition": "I = 10"})
+	c.send("configurationDone", nil)
+	c.until("stopped")
+
+	tests := []struct {
+		expression string
+		context    string
+		result     string // empty if it should fail
+	}{
+		{"Total", "repl", "45"},
+		{"Total <- 100", "repl", "100"},
+		{"Total", "repl", "100"},
+		{"Total <- 0", "watch", ""},
+		{"Missing <- 1", "repl", ""},
+		{"OUTPUT Total", "repl", ""},
+	}
+	for _, tt := range tests {
+		c.send("evaluate", map[string]interface{}{"expression": tt.expression, "frameId": 0, "context": tt.context})
+		resp, _ := c.until("evaluate")
+		if tt.result == "" {
+			if resp["success"] != false {
+				t.Errorf("evaluating %q: expected a failure, got %v", tt.expression, resp)
+			}
+			continue
+		}
+		body, _ := resp["body"].(map[string]interface{})
+		if body["result"] != tt.result {
+			t.Errorf("evaluating %q: expected %s, got %v", tt.expression, tt.result, resp)
+		}
+	}
+
+	c.send("continue", map[string]interface{}{"threadId": threadID})
+	c.until("exited")
+	c.send("evaluate", map[string]interface{}{"expression": "Total", "context": "repl"})
+	if resp, _ := c.until("evaluate"); resp["success"] != false {
+		t.Errorf("expected evaluating after the program finished to fail, got %v", resp)
+	}
+	c.send("disconnect", nil)
+	c.until("disconnect")
+}
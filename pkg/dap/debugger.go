@@ -0,0 +1,305 @@
+package dap
+
+import (
+	"fmt"
+	"slices"
+	"sort"
+	"strconv"
+	"strings"
+	"sync"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+)
+
+// step is what a program does until it next pauses
+type step int
+
+const (
+	running  step = iota // until a breakpoint
+	entering             // to the first statement, when told to stop on entry
+	stepIn               // to the next statement, in a routine it calls or not
+	stepOver             // to the next statement outside the routines it calls
+	stepOut              // to the next statement in the routine's caller
+	pausing              // to the next statement, as the editor asked to pause
+)
+
+// pause is where a program is paused
+type pause struct {
+	interp *interpreter.Interpreter
+	frames []frame       // outermost first, one more than the calls in progress
+	wake   chan struct{} // closed to let the program go on
+	work   chan func()   // run by the program while it waits
+}
+
+// frame is the statement running in the main program or a routine call
+type frame struct {
+	stmt ast.Statement
+	env  *interpreter.Environment
+}
+
+// depth returns the routine calls in progress
+func (p *pause) depth() int {
+	return len(p.frames) - 1
+}
+
+// debugger pauses a program at its breakpoints and between steps, while
+// the session answers the editor's requests about where it is
+type debugger struct {
+	s *session
+
+	mu          sync.Mutex
+	breakpoints map[int]*breakpoint // by the line of the statement to pause before
+	step        step
+	depth       int // where the step began
+	paused      *pause
+	ended       bool    // the editor asked for the program to stop
+	frames      []frame // as of the statement before
+
+	// Where the statement before ran and the first statement run on its
+	// line, so that a line holding several statements is paused once
+	// each time it runs
+	lastLine, lastDepth int
+	first               ast.Statement
+}
+
+func (d *debugger) Before(i *interpreter.Interpreter, stmt ast.Statement, env *interpreter.Environment) bool {
+	if definition(stmt) {
+		d.mu.Lock()
+		defer d.mu.Unlock()
+		return !d.ended
+	}
+	line, _ := ast.Position(stmt)
+	depth := i.CallDepth()
+
+	d.mu.Lock()
+	fresh := line != d.lastLine || depth != d.lastDepth || stmt == d.first
+	if fresh {
+		d.first = stmt
+	}
+	d.lastLine, d.lastDepth = line, depth
+	for len(d.frames) < depth {
+		d.frames = append(d.frames, frame{stmt, env})
+	}
+	d.frames = append(d.frames[:depth], frame{stmt, env})
+	reason := d.reason(i, env, line, depth, fresh)
+	if d.ended || reason == "" {
+		defer d.mu.Unlock()
+		return !d.ended
+	}
+	p := &pause{interp: i, frames: slices.Clone(d.frames), wake: make(chan struct{}), work: make(chan func())}
+	d.paused, d.step = p, running
+	d.mu.Unlock()
+
+	d.s.send("stopped", map[string]interface{}{"reason": reason, "threadId": threadID, "allThreadsStopped": true})
+	for waiting := true; waiting; {
+		select {
+		case <-p.wake:
+			waiting = false
+		case f := <-p.work:
+			f()
+		}
+	}
+
+	d.mu.Lock()
+	defer d.mu.Unlock()
+	return !d.ended
+}
+
+// reason returns why the program should pause before a statement on line
+// with depth calls in progress, about to run in env, or "" if it should
+// not. Steps and breakpoints only pause at the first statement run on a
+// line.
+func (d *debugger) reason(i *interpreter.Interpreter, env *interpreter.Environment, line, depth int, fresh bool) string {
+	switch {
+	case d.step == entering:
+		return "entry"
+	case d.step == pausing:
+		return "pause"
+	case !fresh:
+		return ""
+	case d.step == stepIn, d.step == stepOver && depth <= d.depth, d.step == stepOut && depth < d.depth:
+		return "step"
+	case d.breakpoints[line] != nil && d.hit(i, env, d.breakpoints[line]):
+		return "breakpoint"
+	}
+	return ""
+}
+
+// hit reports whether the program should pause at bp, counting it as
+// reached if its condition holds in env
+// A condition that cannot be worked out pauses the program, so that the
+// editor can see why.
+func (d *debugger) hit(i *interpreter.Interpreter, env *interpreter.Environment, bp *breakpoint) bool {
+	if bp.condition != nil {
+		value, err := i.Evaluate(bp.condition, env)
+		if err != nil {
+			d.s.output("console", fmt.Sprintf("cannot check the breakpoint condition %s: %s\n", bp.condition, err.Message))
+			return true
+		}
+		holds, ok := value.(*interpreter.Boolean)
+		if !ok {
+			d.s.output("console", fmt.Sprintf("the breakpoint condition %s is %s, not BOOLEAN\n", bp.condition, value.Type()))
+			return true
+		}
+		if !holds.Value {
+			return false
+		}
+	}
+	bp.count++
+	return bp.hits.met(bp.count)
+}
+
+// within runs f on the goroutine running the program paused at p, while it
+// waits, reporting false if it went on before f could run
+func (d *debugger) within(p *pause, f func()) bool {
+	done := make(chan struct{})
+	select {
+	case p.work <- func() { defer close(done); f() }:
+		<-done
+		return true
+	case <-p.wake:
+		return false
+	}
+}
+
+// resume lets a paused program go on, until it has taken the step given,
+// reporting false if it was not paused
+func (d *debugger) resume(s step) bool {
+	d.mu.Lock()
+	defer d.mu.Unlock()
+	if d.paused == nil {
+		return false
+	}
+	d.step, d.depth = s, d.paused.depth()
+	close(d.paused.wake)
+	d.paused = nil
+	return true
+}
+
+// interrupt pauses a running program before its next statement
+func (d *debugger) interrupt() {
+	d.mu.Lock()
+	defer d.mu.Unlock()
+	if d.paused == nil {
+		d.step = pausing
+	}
+}
+
+// end stops the program before its next statement, waking it if paused
+func (d *debugger) end() {
+	d.mu.Lock()
+	defer d.mu.Unlock()
+	d.ended = true
+	if d.paused != nil {
+		close(d.paused.wake)
+		d.paused = nil
+	}
+}
+
+// where returns where the program is paused, or nil while it runs
+func (d *debugger) where() *pause {
+	d.mu.Lock()
+	defer d.mu.Unlock()
+	return d.paused
+}
+
+func (d *debugger) setBreakpoints(lines map[int]*breakpoint) {
+	d.mu.Lock()
+	defer d.mu.Unlock()
+	d.breakpoints = lines
+}
+
+// definition reports whether stmt only declares a routine, type or class,
+// which is not somewhere a program can be paused as nothing runs there
+func definition(stmt ast.Statement) bool {
+	switch stmt.(type) {
+	case *ast.ProcedureStatement, *ast.FunctionStatement, *ast.TypeStatement, *ast.ClassStatement:
+		return true
+	}
+	return false
+}
+
+// statementLines returns the lines a program can be paused at, in order
+func statementLines(prog *ast.Program) []int {
+	seen := map[int]bool{}
+	ast.Inspect(prog, func(node ast.Node) bool {
+		if stmt, ok := node.(ast.Statement); ok && !definition(stmt) {
+			line, _ := ast.Position(stmt)
+			seen[line] = true
+		}
+		return true
+	})
+	var lines []int
+	for line := range seen {
+		lines = append(lines, line)
+	}
+	sort.Ints(lines)
+	return lines
+}
+
+// breakpointLine returns the line a breakpoint asked for on line is put
+// on: the first at or after it where the program can be paused
+func breakpointLine(lines []int, line int) (int, bool) {
+	idx := sort.SearchInts(lines, line)
+	if idx == len(lines) {
+		return 0, false
+	}
+	return lines[idx], true
+}
+
+// breakpoint is where a program pauses, if its condition holds and it has
+// been reached as many times as its hit condition asks
+type breakpoint struct {
+	condition ast.Expression // nil to pause whenever it is reached
+	hits      hitCondition
+	count     int // times reached with the condition holding
+}
+
+// hitCondition is how many times a breakpoint must be reached before the
+// program pauses there, written as the editor gives it: "5" or "=5" for
+// the fifth time only, ">5", ">=5", "<5" or "<=5" for those times, and
+// "%5" for every fifth time
+// The zero hitCondition pauses every time.
+type hitCondition struct {
+	op string
+	n  int
+}
+
+func parseHitCondition(text string) (hitCondition, error) {
+	text = strings.TrimSpace(text)
+	if text == "" {
+		return hitCondition{}, nil
+	}
+	op, count := "=", text
+	for _, prefix := range []string{">=", "<=", "=", ">", "<", "%"} {
+		if strings.HasPrefix(text, prefix) {
+			op, count = prefix, strings.TrimSpace(text[len(prefix):])
+			break
+		}
+	}
+	n, err := strconv.Atoi(count)
+	if err != nil || n < 0 || op == "%" && n == 0 {
+		return hitCondition{}, fmt.Errorf("invalid hit count %q: expected a number such as 5, >5 or %%5", text)
+	}
+	return hitCondition{op, n}, nil
+}
+
+// met reports whether a breakpoint reached count times should pause
+func (h hitCondition) met(count int) bool {
+	switch h.op {
+	case "=":
+		return count == h.n
+	case ">":
+		return count > h.n
+	case ">=":
+		return count >= h.n
+	case "<":
+		return count < h.n
+	case "<=":
+		return count <= h.n
+	case "%":
+		return count%h.n == 0
+	}
+	return true
+}
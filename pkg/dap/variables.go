@@ -0,0 +1,125 @@
+package dap
+
+import (
+	"fmt"
+	"sort"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+)
+
+// maxChildren is how many elements of an array are listed at once when
+// the editor does not ask for a range of them
+const maxChildren = 1000
+
+// container is something the editor can expand to see the variables in
+// it: a scope, or an array, record or object
+type container struct {
+	vars   func() []interpreter.Variable // a scope's, as they are now
+	value  interpreter.Object
+	prefix []int64 // the indices of the elements of an array listed
+}
+
+// variable describes one variable to the editor, giving it a reference to
+// expand it by if it holds others
+func (s *session) variable(name string, value interpreter.Object, prefix []int64) map[string]interface{} {
+	v := map[string]interface{}{"name": name, "value": display(value, prefix), "type": typeName(value)}
+	switch value := value.(type) {
+	case *interpreter.Array:
+		if len(prefix) < len(value.Dimensions) {
+			v["variablesReference"] = s.reference(container{value: value, prefix: prefix})
+			d := value.Dimensions[len(prefix)]
+			v["indexedVariables"] = max(d.Upper-d.Lower+1, 0)
+			return v
+		}
+	case *interpreter.Record, *interpreter.Instance:
+		v["variablesReference"] = s.reference(container{value: value})
+		return v
+	}
+	v["variablesReference"] = 0
+	return v
+}
+
+// reference returns the number the editor expands c by, valid until the
+// program goes on
+func (s *session) reference(c container) int {
+	s.containers = append(s.containers, c)
+	return len(s.containers)
+}
+
+// children returns the variables in c, or from start up to count of the
+// elements of an array
+func (s *session) children(c container, start, count int) []map[string]interface{} {
+	vars := []map[string]interface{}{}
+	switch value := c.value.(type) {
+	case nil:
+		for _, v := range c.vars() {
+			vars = append(vars, s.variable(v.Name, v.Value, nil))
+		}
+	case *interpreter.Array:
+		if count <= 0 {
+			count = maxChildren
+		}
+		d := value.Dimensions[len(c.prefix)]
+		for idx := d.Lower + start; idx <= d.Upper && idx < d.Lower+start+count; idx++ {
+			indices := append(c.prefix[:len(c.prefix):len(c.prefix)], int64(idx))
+			name := fmt.Sprintf("[%d]", idx)
+			if len(indices) < len(value.Dimensions) {
+				vars = append(vars, s.variable(name, value, indices))
+			} else {
+				vars = append(vars, s.variable(name, value.Get(indices...), nil))
+			}
+		}
+	case *interpreter.Record:
+		vars = s.fields(value.Fields)
+	case *interpreter.Instance:
+		vars = s.fields(value.Fields)
+	}
+	return vars
+}
+
+// fields returns the fields of a record or object, in order of name
+func (s *session) fields(fields map[string]interpreter.Object) []map[string]interface{} {
+	names := make([]string, 0, len(fields))
+	for name := range fields {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+	vars := make([]map[string]interface{}, 0, len(names))
+	for _, name := range names {
+		vars = append(vars, s.variable(name, fields[name], nil))
+	}
+	return vars
+}
+
+// display returns value as it would be written in a program, or for an
+// array, record or object, what kind it is; prefix selects the elements
+// of an array shown
+func display(value interpreter.Object, prefix []int64) string {
+	switch value := value.(type) {
+	case nil:
+		return "NULL"
+	case *interpreter.String:
+		return `"` + value.Value + `"`
+	case *interpreter.Char:
+		return "'" + string(value.Value) + "'"
+	case *interpreter.Array:
+		var bounds []string
+		for _, d := range value.Dimensions[min(len(prefix), len(value.Dimensions)):] {
+			bounds = append(bounds, fmt.Sprintf("%d:%d", d.Lower, d.Upper))
+		}
+		return "ARRAY[" + strings.Join(bounds, ", ") + "]"
+	case *interpreter.Record:
+		return value.TypeName
+	case *interpreter.Instance:
+		return value.Class.Name
+	}
+	return value.Inspect()
+}
+
+func typeName(value interpreter.Object) string {
+	if value == nil {
+		return string(interpreter.NULL_OBJ)
+	}
+	return string(value.Type())
+}
@@ -0,0 +1,282 @@
+package diagnostic
+
+import (
+	"fmt"
+	"os"
+	"sort"
+	"strings"
+)
+
+// English is the language used when no translation is available
+const English = "en"
+
+// catalogue holds the message format for every code in each language
+// English must have an entry for every code; other languages fall back to
+// it for anything they are missing.
+var catalogue = map[string]map[Code]string{
+	"en": {
+		ExpectedToken:               "expected next token to be %s, got %s instead",
+		ExpectedParameterName:       "expected parameter name",
+		ExpectedFileMode:            "expected READ, WRITE, or APPEND after FOR",
+		ExpectedFieldName:           "expected field name",
+		ExpectedMemberAfterModifier: "expected PROCEDURE, FUNCTION, DECLARE, or property name after access modifier",
+		UnexpectedDataType:          "unexpected data type: %s",
+		ExpectedLowerBound:          "expected integer for array lower bound",
+		ExpectedUpperBound:          "expected integer for array upper bound",
+		UnexpectedToken:             "no prefix parse function for %s",
+		InvalidInteger:              "could not parse %q as integer",
+		InvalidReal:                 "could not parse %q as real",
+		MissingBlockEnd:             "%s is never closed with %s",
+
+		ConstantModified:        "cannot modify constant: %s",
+		InvalidAssignmentTarget: "invalid assignment target",
+		NotAnArray:              "not an array",
+		ArrayIndexNotInteger:    "array index must be an integer",
+		NoMembers:               "cannot access member of non-record/instance",
+		ForStartNotInteger:      "FOR loop start must be an integer",
+		ForEndNotInteger:        "FOR loop end must be an integer",
+		InputFailed:             "input error: %v",
+		FilenameNotString:       "filename must be a string",
+		FileOpenFailed:          "cannot open file: %v",
+		FileNotOpen:             "file not open",
+		FileNotReadable:         "file not open for reading",
+		FileNotWritable:         "file not open for writing",
+		WriteFailed:             "write error: %v",
+		UndefinedIdentifier:     "identifier not found: %s",
+		UnknownOperator:         "unknown operator: %s",
+		TypeMismatch:            "type mismatch: %s %s %s",
+		DivisionByZero:          "division by zero",
+		FieldNotFound:           "field not found: %s",
+		MemberNotFound:          "member not found: %s",
+		NoParentClass:           "no parent class",
+		ParentMethodNotFound:    "method not found in parent class: %s",
+		NotAFunction:            "not a function: %s",
+		ClassNotFound:           "class not found: %s",
+		NotAClass:               "%s is not a class",
+		SuperOutsideMethod:      "SUPER can only be used within a class method",
+		RecursionLimit:          "maximum recursion depth (%d) exceeded in %s %s called from line %d",
+		IntegerOverflow:         "INTEGER overflow: %s does not fit in 64 bits (run with --bigint for arbitrary precision)",
+		BuiltinArgumentCount:    "%s expects %d arguments, got %d",
+		BuiltinArgumentType:     "argument %d of %s must be %s, got %s",
+		FileHandlingDisabled:    "file handling is disabled in this environment",
+		RandomnessDisabled:      "%s is disabled in this environment",
+		InputDisabled:           "INPUT is disabled in this environment",
+		IncludeNotLoaded:        "INCLUDE %s cannot run here, as the program was not loaded with the files it includes",
+		StringTooLong:           "a STRING of %d characters is longer than the %d allowed in this environment",
+		ArrayTooLarge:           "an ARRAY of %d elements is larger than the %d allowed in this environment",
+
+		AssignedTypeMismatch: "cannot assign %s to %s, which is declared as %s",
+		ReturnedTypeMismatch: "%s must return %s, not %s",
+		UnusedVariable:       "%s is declared but never used",
+		UnassignedVariable:   "%s is used but never given a value",
+		MissingReturn:        "FUNCTION %s can reach ENDFUNCTION without returning a value",
+		UndeclaredVariable:   "%s is not declared",
+		PossibleTypo:         "%s is not declared; did you mean %s?",
+		ComparisonStatement:  "this compares %s with a value and discards the result; use ← to assign",
+		NotInDialect:         "%s is not part of the %s syllabus",
+		NotInInsert:          "%s is not available in the %d insert; it was added in %d",
+		KeywordCase:          "write %s as %s: keywords are written in capitals",
+		IdentifierCase:       "write %s in mixed case, as %s",
+		Misindented:          "this line should be indented to level %d",
+		SeveralStatements:    "put each statement on a line of its own",
+		DeclarationPlacement: "declare %s with the other declarations, before line %d",
+
+		IncludeUnreadable:  "cannot read %s, included here: %s",
+		IncludeCycle:       "%s includes itself, through %s",
+		IncludeNotTopLevel: "INCLUDE must be at the top level of a file, outside any routine or block",
+	},
+
+	"es": {
+		ExpectedToken:               "se esperaba %s como siguiente símbolo, pero se encontró %s",
+		ExpectedParameterName:       "se esperaba el nombre de un parámetro",
+		ExpectedFileMode:            "se esperaba READ, WRITE o APPEND después de FOR",
+		ExpectedFieldName:           "se esperaba el nombre de un campo",
+		ExpectedMemberAfterModifier: "se esperaba PROCEDURE, FUNCTION, DECLARE o el nombre de una propiedad después del modificador de acceso",
+		UnexpectedDataType:          "tipo de dato inesperado: %s",
+		ExpectedLowerBound:          "se esperaba un entero como límite inferior del arreglo",
+		ExpectedUpperBound:          "se esperaba un entero como límite superior del arreglo",
+		UnexpectedToken:             "no se esperaba %s aquí",
+		InvalidInteger:              "no se pudo interpretar %q como entero",
+		InvalidReal:                 "no se pudo interpretar %q como real",
+		MissingBlockEnd:             "%s nunca se cierra con %s",
+
+		ConstantModified:        "no se puede modificar la constante: %s",
+		InvalidAssignmentTarget: "destino de asignación no válido",
+		NotAnArray:              "no es un arreglo",
+		ArrayIndexNotInteger:    "el índice del arreglo debe ser un entero",
+		NoMembers:               "no se puede acceder a un miembro de algo que no es un registro ni un objeto",
+		ForStartNotInteger:      "el inicio del bucle FOR debe ser un entero",
+		ForEndNotInteger:        "el final del bucle FOR debe ser un entero",
+		InputFailed:             "error de entrada: %v",
+		FilenameNotString:       "el nombre del archivo debe ser una cadena",
+		FileOpenFailed:          "no se puede abrir el archivo: %v",
+		FileNotOpen:             "el archivo no está abierto",
+		FileNotReadable:         "el archivo no está abierto para lectura",
+		FileNotWritable:         "el archivo no está abierto para escritura",
+		WriteFailed:             "error de escritura: %v",
+		UndefinedIdentifier:     "identificador no encontrado: %s",
+		UnknownOperator:         "operador desconocido: %s",
+		TypeMismatch:            "tipos incompatibles: %s %s %s",
+		DivisionByZero:          "división entre cero",
+		FieldNotFound:           "campo no encontrado: %s",
+		MemberNotFound:          "miembro no encontrado: %s",
+		NoParentClass:           "no hay clase padre",
+		ParentMethodNotFound:    "método no encontrado en la clase padre: %s",
+		NotAFunction:            "no es una función: %s",
+		ClassNotFound:           "clase no encontrada: %s",
+		NotAClass:               "%s no es una clase",
+		SuperOutsideMethod:      "SUPER solo se puede usar dentro de un método de clase",
+		RecursionLimit:          "se superó la profundidad máxima de recursión (%d) en %s %s, llamado desde la línea %d",
+		IntegerOverflow:         "desbordamiento de INTEGER: %s no cabe en 64 bits (ejecute con --bigint para precisión arbitraria)",
+		BuiltinArgumentCount:    "%s espera %d argumentos, recibió %d",
+		BuiltinArgumentType:     "el argumento %d de %s debe ser %s, se recibió %s",
+		FileHandlingDisabled:    "el manejo de archivos está desactivado en este entorno",
+		RandomnessDisabled:      "%s está desactivado en este entorno",
+		InputDisabled:           "INPUT está desactivado en este entorno",
+		IncludeNotLoaded:        "INCLUDE %s no se puede ejecutar aquí, ya que el programa no se cargó con los archivos que incluye",
+		StringTooLong:           "un STRING de %d caracteres supera los %d permitidos en este entorno",
+		ArrayTooLarge:           "un ARRAY de %d elementos supera los %d permitidos en este entorno",
+
+		AssignedTypeMismatch: "no se puede asignar %s a %s, que está declarado como %s",
+		ReturnedTypeMismatch: "%s debe devolver %s, no %s",
+		UnusedVariable:       "%s está declarado pero nunca se usa",
+		UnassignedVariable:   "%s se usa pero nunca recibe un valor",
+		MissingReturn:        "la función %s puede llegar a ENDFUNCTION sin devolver un valor",
+		UndeclaredVariable:   "%s no está declarado",
+		PossibleTypo:         "%s no está declarado; ¿quería decir %s?",
+		ComparisonStatement:  "esto compara %s con un valor y descarta el resultado; use ← para asignar",
+		NotInDialect:         "%s no forma parte del programa %s",
+		NotInInsert:          "%s no está disponible en el inserto de %d; se añadió en %d",
+		KeywordCase:          "escriba %s como %s: las palabras clave se escriben en mayúsculas",
+		IdentifierCase:       "escriba %s con mayúsculas y minúsculas, como %s",
+		Misindented:          "esta línea debería estar sangrada al nivel %d",
+		SeveralStatements:    "escriba cada instrucción en su propia línea",
+		DeclarationPlacement: "declare %s junto a las demás declaraciones, antes de la línea %d",
+
+		IncludeUnreadable:  "no se puede leer %s, incluido aquí: %s",
+		IncludeCycle:       "%s se incluye a sí mismo, a través de %s",
+		IncludeNotTopLevel: "INCLUDE debe estar en el nivel superior del archivo, fuera de cualquier rutina o bloque",
+	},
+
+	"fr": {
+		ExpectedToken:               "%s attendu comme symbole suivant, mais %s trouvé",
+		ExpectedParameterName:       "nom de paramètre attendu",
+		ExpectedFileMode:            "READ, WRITE ou APPEND attendu après FOR",
+		ExpectedFieldName:           "nom de champ attendu",
+		ExpectedMemberAfterModifier: "PROCEDURE, FUNCTION, DECLARE ou nom de propriété attendu après le modificateur d'accès",
+		UnexpectedDataType:          "type de données inattendu : %s",
+		ExpectedLowerBound:          "entier attendu pour la borne inférieure du tableau",
+		ExpectedUpperBound:          "entier attendu pour la borne supérieure du tableau",
+		UnexpectedToken:             "%s inattendu ici",
+		InvalidInteger:              "impossible d'interpréter %q comme un entier",
+		InvalidReal:                 "impossible d'interpréter %q comme un réel",
+		MissingBlockEnd:             "%s n'est jamais fermé par %s",
+
+		ConstantModified:        "impossible de modifier la constante : %s",
+		InvalidAssignmentTarget: "cible d'affectation invalide",
+		NotAnArray:              "ce n'est pas un tableau",
+		ArrayIndexNotInteger:    "l'indice du tableau doit être un entier",
+		NoMembers:               "impossible d'accéder au membre de quelque chose qui n'est ni un enregistrement ni un objet",
+		ForStartNotInteger:      "le début de la boucle FOR doit être un entier",
+		ForEndNotInteger:        "la fin de la boucle FOR doit être un entier",
+		InputFailed:             "erreur de saisie : %v",
+		FilenameNotString:       "le nom de fichier doit être une chaîne",
+		FileOpenFailed:          "impossible d'ouvrir le fichier : %v",
+		FileNotOpen:             "fichier non ouvert",
+		FileNotReadable:         "fichier non ouvert en lecture",
+		FileNotWritable:         "fichier non ouvert en écriture",
+		WriteFailed:             "erreur d'écriture : %v",
+		UndefinedIdentifier:     "identificateur introuvable : %s",
+		UnknownOperator:         "opérateur inconnu : %s",
+		TypeMismatch:            "types incompatibles : %s %s %s",
+		DivisionByZero:          "division par zéro",
+		FieldNotFound:           "champ introuvable : %s",
+		MemberNotFound:          "membre introuvable : %s",
+		NoParentClass:           "aucune classe parente",
+		ParentMethodNotFound:    "méthode introuvable dans la classe parente : %s",
+		NotAFunction:            "ce n'est pas une fonction : %s",
+		ClassNotFound:           "classe introuvable : %s",
+		NotAClass:               "%s n'est pas une classe",
+		SuperOutsideMethod:      "SUPER ne peut être utilisé que dans une méthode de classe",
+		RecursionLimit:          "profondeur de récursion maximale (%d) dépassée dans %s %s, appelé depuis la ligne %d",
+		IntegerOverflow:         "dépassement INTEGER : %s ne tient pas sur 64 bits (lancez avec --bigint pour une précision arbitraire)",
+		BuiltinArgumentCount:    "%s attend %d arguments, en a reçu %d",
+		BuiltinArgumentType:     "l'argument %d de %s doit être %s, reçu %s",
+		FileHandlingDisabled:    "la gestion des fichiers est désactivée dans cet environnement",
+		RandomnessDisabled:      "%s est désactivé dans cet environnement",
+		InputDisabled:           "INPUT est désactivé dans cet environnement",
+		IncludeNotLoaded:        "INCLUDE %s ne peut pas s'exécuter ici, car le programme n'a pas été chargé avec les fichiers qu'il inclut",
+		StringTooLong:           "une STRING de %d caractères dépasse les %d permis dans cet environnement",
+		ArrayTooLarge:           "un ARRAY de %d éléments dépasse les %d permis dans cet environnement",
+
+		AssignedTypeMismatch: "impossible d'affecter %s à %s, déclaré comme %s",
+		ReturnedTypeMismatch: "%s doit renvoyer %s, pas %s",
+		UnusedVariable:       "%s est déclaré mais jamais utilisé",
+		UnassignedVariable:   "%s est utilisé mais ne reçoit jamais de valeur",
+		MissingReturn:        "la fonction %s peut atteindre ENDFUNCTION sans renvoyer de valeur",
+		UndeclaredVariable:   "%s n'est pas déclaré",
+		PossibleTypo:         "%s n'est pas déclaré ; vouliez-vous dire %s ?",
+		ComparisonStatement:  "ceci compare %s à une valeur et ignore le résultat ; utilisez ← pour affecter",
+		NotInDialect:         "%s ne fait pas partie du programme %s",
+		NotInInsert:          "%s n'est pas disponible dans l'insert de %d ; elle a été ajoutée en %d",
+		KeywordCase:          "écrivez %s sous la forme %s : les mots-clés s'écrivent en majuscules",
+		IdentifierCase:       "écrivez %s en casse mixte, comme %s",
+		Misindented:          "cette ligne devrait être indentée au niveau %d",
+		SeveralStatements:    "écrivez chaque instruction sur sa propre ligne",
+		DeclarationPlacement: "déclarez %s avec les autres déclarations, avant la ligne %d",
+
+		IncludeUnreadable:  "impossible de lire %s, inclus ici : %s",
+		IncludeCycle:       "%s s'inclut lui-même, par %s",
+		IncludeNotTopLevel: "INCLUDE doit être au niveau supérieur du fichier, hors de toute routine et de tout bloc",
+	},
+}
+
+// Format returns the message for code in lang, falling back to English
+func Format(lang string, code Code, args ...interface{}) string {
+	format, ok := catalogue[lang][code]
+	if !ok {
+		format, ok = catalogue[English][code]
+	}
+	if !ok {
+		return string(code)
+	}
+	return fmt.Sprintf(format, args...)
+}
+
+// Languages returns the languages that have a message catalogue
+func Languages() []string {
+	var langs []string
+	for lang := range catalogue {
+		langs = append(langs, lang)
+	}
+	sort.Strings(langs)
+	return langs
+}
+
+// Language picks the message language from the first non-empty setting,
+// such as a command-line flag followed by a config value, then from the
+// CAMBRIDGE_LANG, LC_ALL, LC_MESSAGES and LANG environment variables
+// Unsupported languages fall through to the next setting, ending at English.
+func Language(settings ...string) string {
+	candidates := append(settings,
+		os.Getenv("CAMBRIDGE_LANG"),
+		os.Getenv("LC_ALL"),
+		os.Getenv("LC_MESSAGES"),
+		os.Getenv("LANG"))
+
+	for _, setting := range candidates {
+		lang := localeLanguage(setting)
+		if _, ok := catalogue[lang]; ok {
+			return lang
+		}
+	}
+	return English
+}
+
+// localeLanguage reduces a locale such as "es_ES.UTF-8" to its language
+func localeLanguage(locale string) string {
+	if i := strings.IndexAny(locale, "_.@-"); i >= 0 {
+		locale = locale[:i]
+	}
+	return strings.ToLower(locale)
+}
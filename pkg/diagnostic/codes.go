@@ -0,0 +1,91 @@
+package diagnostic
+
+// Code identifies a kind of diagnostic and is its key in the message
+// catalogue. Syntax errors are E01xx and runtime errors E02xx; problems
+// found by checking a program before it runs are E03xx if it cannot be
+// right and W03xx if it is merely suspicious or set out other than as the
+// pseudocode guide sets it out. Problems including one file in another
+// are E04xx.
+type Code string
+
+// Syntax errors reported by the parser
+const (
+	ExpectedToken               Code = "E0101"
+	ExpectedParameterName       Code = "E0102"
+	ExpectedFileMode            Code = "E0103"
+	ExpectedFieldName           Code = "E0104"
+	ExpectedMemberAfterModifier Code = "E0105"
+	UnexpectedDataType          Code = "E0106"
+	ExpectedLowerBound          Code = "E0107"
+	ExpectedUpperBound          Code = "E0108"
+	UnexpectedToken             Code = "E0109"
+	InvalidInteger              Code = "E0110"
+	InvalidReal                 Code = "E0111"
+	MissingBlockEnd             Code = "E0112"
+)
+
+// Runtime errors reported by the interpreter
+const (
+	ConstantModified        Code = "E0201"
+	InvalidAssignmentTarget Code = "E0202"
+	NotAnArray              Code = "E0203"
+	ArrayIndexNotInteger    Code = "E0204"
+	NoMembers               Code = "E0205"
+	ForStartNotInteger      Code = "E0206"
+	ForEndNotInteger        Code = "E0207"
+	InputFailed             Code = "E0208"
+	FilenameNotString       Code = "E0209"
+	FileOpenFailed          Code = "E0210"
+	FileNotOpen             Code = "E0211"
+	FileNotReadable         Code = "E0212"
+	FileNotWritable         Code = "E0213"
+	WriteFailed             Code = "E0214"
+	UndefinedIdentifier     Code = "E0215"
+	UnknownOperator         Code = "E0216"
+	TypeMismatch            Code = "E0217"
+	DivisionByZero          Code = "E0218"
+	FieldNotFound           Code = "E0219"
+	MemberNotFound          Code = "E0220"
+	NoParentClass           Code = "E0221"
+	ParentMethodNotFound    Code = "E0222"
+	NotAFunction            Code = "E0223"
+	ClassNotFound           Code = "E0224"
+	NotAClass               Code = "E0225"
+	SuperOutsideMethod      Code = "E0226"
+	RecursionLimit          Code = "E0227"
+	IntegerOverflow         Code = "E0228"
+	BuiltinArgumentCount    Code = "E0229"
+	BuiltinArgumentType     Code = "E0230"
+	FileHandlingDisabled    Code = "E0231"
+	RandomnessDisabled      Code = "E0232"
+	InputDisabled           Code = "E0233"
+	IncludeNotLoaded        Code = "E0234"
+	StringTooLong           Code = "E0235"
+	ArrayTooLarge           Code = "E0236"
+)
+
+// Problems found by checking a program before it runs
+const (
+	AssignedTypeMismatch Code = "E0301"
+	ReturnedTypeMismatch Code = "E0302"
+	UnusedVariable       Code = "W0301"
+	UnassignedVariable   Code = "W0302"
+	MissingReturn        Code = "W0303"
+	UndeclaredVariable   Code = "W0304"
+	PossibleTypo         Code = "W0305"
+	ComparisonStatement  Code = "W0306"
+	NotInDialect         Code = "W0307"
+	NotInInsert          Code = "W0308"
+	KeywordCase          Code = "W0309"
+	IdentifierCase       Code = "W0310"
+	Misindented          Code = "W0311"
+	SeveralStatements    Code = "W0312"
+	DeclarationPlacement Code = "W0313"
+)
+
+// Problems found including the files a program names with INCLUDE
+const (
+	IncludeUnreadable  Code = "E0401"
+	IncludeCycle       Code = "E0402"
+	IncludeNotTopLevel Code = "E0403"
+)
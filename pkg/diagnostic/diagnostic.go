@@ -0,0 +1,222 @@
+// Package diagnostic renders errors and warnings for the terminal
+package diagnostic
+
+import (
+	"fmt"
+	"io"
+	"os"
+	"strings"
+)
+
+// Severity is how serious a diagnostic is
+type Severity int
+
+const (
+	Error Severity = iota
+	Warning
+	Note
+)
+
+func (s Severity) String() string {
+	switch s {
+	case Warning:
+		return "warning"
+	case Note:
+		return "note"
+	default:
+		return "error"
+	}
+}
+
+// MarshalText writes the severity by name, as in JSON
+func (s Severity) MarshalText() ([]byte, error) {
+	return []byte(s.String()), nil
+}
+
+// UnmarshalText reads a severity written by MarshalText
+func (s *Severity) UnmarshalText(text []byte) error {
+	for _, severity := range []Severity{Error, Warning, Note} {
+		if severity.String() == string(text) {
+			*s = severity
+			return nil
+		}
+	}
+	return fmt.Errorf("unknown severity %q", text)
+}
+
+// Diagnostic is a single message about a program
+// Message is always in English; Code and Args let the renderer translate it.
+type Diagnostic struct {
+	Severity Severity      `json:"severity"`
+	Code     Code          `json:"code,omitempty"`
+	Args     []interface{} `json:"args,omitempty"`
+	Message  string        `json:"message"`
+	Line     int           `json:"line"`   // 1-based, 0 when the location is unknown
+	Column   int           `json:"column"` // 1-based
+}
+
+// New creates a diagnostic from the message catalogue
+func New(severity Severity, code Code, args ...interface{}) Diagnostic {
+	return Diagnostic{
+		Severity: severity,
+		Code:     code,
+		Args:     args,
+		Message:  Format(English, code, args...),
+	}
+}
+
+// Localize returns the message of d in lang
+func (d Diagnostic) Localize(lang string) string {
+	if d.Code == "" || lang == English {
+		return d.Message
+	}
+	return Format(lang, d.Code, d.Args...)
+}
+
+// Source is the file a diagnostic refers to, used to show the offending line
+type Source struct {
+	Name string
+	Text string
+}
+
+// ANSI escape sequences
+const (
+	reset  = "\x1b[0m"
+	bold   = "\x1b[1m"
+	red    = "\x1b[31m"
+	yellow = "\x1b[33m"
+	blue   = "\x1b[34m"
+	cyan   = "\x1b[36m"
+)
+
+// Renderer writes diagnostics to a stream, optionally in colour
+type Renderer struct {
+	w     io.Writer
+	color bool
+	lang  string
+}
+
+// NewRenderer creates a renderer writing English messages to w
+func NewRenderer(w io.Writer, color bool) *Renderer {
+	return &Renderer{w: w, color: color, lang: English}
+}
+
+// SetLanguage selects the catalogue language used for messages
+func (r *Renderer) SetLanguage(lang string) {
+	r.lang = lang
+}
+
+// ColorEnabled reports whether output to f should be coloured
+// Colour is used only for terminals, and never when noColor is set, the
+// NO_COLOR environment variable is present or TERM is "dumb".
+func ColorEnabled(f *os.File, noColor bool) bool {
+	if noColor {
+		return false
+	}
+	if _, ok := os.LookupEnv("NO_COLOR"); ok {
+		return false
+	}
+	if os.Getenv("TERM") == "dumb" {
+		return false
+	}
+	info, err := f.Stat()
+	if err != nil {
+		return false
+	}
+	return info.Mode()&os.ModeCharDevice != 0
+}
+
+// Render writes d, followed by the offending line of src when src is
+// non-nil and d has a location
+//
+//	error[E0101]: expected next token to be THEN, got NEWLINE instead
+//	  --> main.cam:3:9
+//	   |
+//	 3 | IF x > 1
+//	   |         ^
+func (r *Renderer) Render(d Diagnostic, src *Source) {
+	badgeColor := r.severityColor(d.Severity)
+
+	badge := d.Severity.String()
+	if d.Code != "" {
+		badge += "[" + string(d.Code) + "]"
+	}
+	fmt.Fprintf(r.w, "%s: %s\n",
+		r.paint(bold+badgeColor, badge),
+		r.paint(bold, d.Localize(r.lang)))
+
+	if d.Line <= 0 {
+		return
+	}
+
+	name := "<input>"
+	if src != nil && src.Name != "" {
+		name = src.Name
+	}
+	gutter := strings.Repeat(" ", len(fmt.Sprint(d.Line)))
+	fmt.Fprintf(r.w, "%s%s %s:%d:%d\n", gutter, r.paint(bold+blue, "-->"), name, d.Line, d.Column)
+
+	line, ok := sourceLine(src, d.Line)
+	if !ok {
+		return
+	}
+
+	bar := r.paint(bold+blue, "|")
+	fmt.Fprintf(r.w, "%s %s\n", gutter, bar)
+	fmt.Fprintf(r.w, "%s %s %s\n", r.paint(bold+blue, fmt.Sprint(d.Line)), bar, line)
+	fmt.Fprintf(r.w, "%s %s %s%s\n", gutter, bar, caretPadding(line, d.Column), r.paint(bold+badgeColor, "^"))
+}
+
+// Errorf renders an error without a location
+func (r *Renderer) Errorf(format string, args ...interface{}) {
+	r.Render(Diagnostic{Severity: Error, Message: fmt.Sprintf(format, args...)}, nil)
+}
+
+func (r *Renderer) severityColor(s Severity) string {
+	switch s {
+	case Warning:
+		return yellow
+	case Note:
+		return cyan
+	default:
+		return red
+	}
+}
+
+func (r *Renderer) paint(style, text string) string {
+	if !r.color {
+		return text
+	}
+	return style + text + reset
+}
+
+// sourceLine returns the given 1-based line of src without its line ending
+func sourceLine(src *Source, line int) (string, bool) {
+	if src == nil {
+		return "", false
+	}
+	lines := strings.Split(src.Text, "\n")
+	if line > len(lines) {
+		return "", false
+	}
+	return strings.TrimRight(lines[line-1], "\r"), true
+}
+
+// caretPadding returns the whitespace that lines a caret up under the given
+// 1-based byte column of line, keeping tabs so the terminal aligns them the
+// same way and counting multi-byte characters once
+func caretPadding(line string, column int) string {
+	if column-1 < len(line) {
+		line = line[:max(column-1, 0)]
+	}
+
+	var pad strings.Builder
+	for _, ch := range line {
+		if ch == '\t' {
+			pad.WriteByte('\t')
+		} else {
+			pad.WriteByte(' ')
+		}
+	}
+	return pad.String()
+}
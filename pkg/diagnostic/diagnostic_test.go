@@ -0,0 +1,154 @@
+package diagnostic
+
+import (
+	"bytes"
+	"encoding/json"
+	"strings"
+	"testing"
+)
+
+func TestRenderPlain(t *testing.T) {
+	var out bytes.Buffer
+	r := NewRenderer(&out, false)
+
+	r.Render(Diagnostic{
+		Severity: Error,
+		Message:  "expected THEN",
+		Line:     2,
+		Column:   9,
+	}, &Source{Name: "main.cam", Text: "DECLARE x : INTEGER\nIF x > 1\nENDIF\n"})
+
+	expected := "error: expected THEN\n" +
+		" --> main.cam:2:9\n" +
+		"  |\n" +
+		"2 | IF x > 1\n" +
+		"  |         ^\n"
+	if out.String() != expected {
+		t.Errorf("expected:\n%s\ngot:\n%s", expected, out.String())
+	}
+}
+
+func TestDiagnosticJSON(t *testing.T) {
+	d := New(Warning, UndefinedIdentifier, "Total")
+	d.Line, d.Column = 3, 7
+
+	data, err := json.Marshal(d)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	expected := `{"severity":"warning","code":"E0215","args":["Total"],"message":"identifier not found: Total","line":3,"column":7}`
+	if string(data) != expected {
+		t.Errorf("expected %s, got %s", expected, data)
+	}
+
+	var decoded Diagnostic
+	if err := json.Unmarshal(data, &decoded); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if decoded.Severity != Warning || decoded.Localize("es") != "identificador no encontrado: Total" {
+		t.Errorf("unexpected diagnostic %+v", decoded)
+	}
+}
+
+func TestRenderWithoutLocation(t *testing.T) {
+	var out bytes.Buffer
+	NewRenderer(&out, false).Errorf("cannot read %s", "main.cam")
+
+	if out.String() != "error: cannot read main.cam\n" {
+		t.Errorf("unexpected output %q", out.String())
+	}
+}
+
+func TestRenderColor(t *testing.T) {
+	var out bytes.Buffer
+	NewRenderer(&out, true).Render(Diagnostic{Severity: Warning, Message: "unused"}, nil)
+
+	if !strings.Contains(out.String(), "\x1b[1m\x1b[33mwarning\x1b[0m") {
+		t.Errorf("expected a bold yellow badge, got %q", out.String())
+	}
+}
+
+func TestCaretPadding(t *testing.T) {
+	tests := []struct {
+		line     string
+		column   int
+		expected string
+	}{
+		{"x <- 1", 3, "  "},
+		{"\tx <- 1", 2, "\t"},
+		{"x ← y", 7, "    "}, // ← is three bytes wide
+		{"IF", 3, "  "},
+	}
+
+	for _, tt := range tests {
+		if got := caretPadding(tt.line, tt.column); got != tt.expected {
+			t.Errorf("caretPadding(%q, %d) = %q, want %q", tt.line, tt.column, got, tt.expected)
+		}
+	}
+}
+
+func TestColorEnabledRespectsNoColor(t *testing.T) {
+	t.Setenv("NO_COLOR", "1")
+	if ColorEnabled(nil, false) {
+		t.Errorf("expected colour to be disabled by NO_COLOR")
+	}
+}
+
+func TestCatalogueComplete(t *testing.T) {
+	for _, lang := range Languages() {
+		for code, english := range catalogue[English] {
+			msg, ok := catalogue[lang][code]
+			if !ok {
+				t.Errorf("%s: missing message for %s", lang, code)
+				continue
+			}
+			if strings.Count(msg, "%") != strings.Count(english, "%") {
+				t.Errorf("%s: message for %s takes different arguments: %q", lang, code, msg)
+			}
+		}
+	}
+}
+
+func TestRenderLocalized(t *testing.T) {
+	var out bytes.Buffer
+	r := NewRenderer(&out, false)
+	r.SetLanguage("es")
+
+	d := New(Error, UndefinedIdentifier, "Total")
+	if d.Message != "identifier not found: Total" {
+		t.Errorf("expected English message, got %q", d.Message)
+	}
+
+	r.Render(d, nil)
+	if out.String() != "error[E0215]: identificador no encontrado: Total\n" {
+		t.Errorf("unexpected output %q", out.String())
+	}
+}
+
+func TestLanguage(t *testing.T) {
+	t.Setenv("CAMBRIDGE_LANG", "")
+	t.Setenv("LC_ALL", "")
+	t.Setenv("LC_MESSAGES", "")
+	t.Setenv("LANG", "fr_FR.UTF-8")
+
+	tests := []struct {
+		settings []string
+		expected string
+	}{
+		{nil, "fr"},
+		{[]string{"es"}, "es"},
+		{[]string{"", "es_MX"}, "es"},
+		{[]string{"de"}, "fr"}, // no German catalogue, so use the locale
+	}
+
+	for _, tt := range tests {
+		if got := Language(tt.settings...); got != tt.expected {
+			t.Errorf("Language(%q) = %q, want %q", tt.settings, got, tt.expected)
+		}
+	}
+
+	t.Setenv("LANG", "C")
+	if got := Language(); got != English {
+		t.Errorf("expected English for the C locale, got %q", got)
+	}
+}
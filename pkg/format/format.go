@@ -0,0 +1,208 @@
+// Package format lays out pseudocode in one consistent style: the body of
+// each block indented four spaces, or as a Style says, no trailing spaces,
+// at most one blank line in a row and a newline at the end
+// Only whitespace at the start and end of lines changes, so comments and
+// the spacing within statements are kept as written.
+package format
+
+import (
+	"fmt"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// Indent is one level of indentation
+const Indent = "    "
+
+// Style is what may differ between layouts: the text indenting each level
+type Style struct {
+	Indent string
+}
+
+// House is the style cambridge fmt lays programs out in
+var House = Style{Indent: Indent}
+
+// closers maps each keyword that ends a block to the keyword opening it
+var closers = map[token.Type]token.Type{
+	token.ENDIF:        token.IF,
+	token.ENDCASE:      token.CASE,
+	token.NEXT:         token.FOR,
+	token.ENDWHILE:     token.WHILE,
+	token.UNTIL:        token.REPEAT,
+	token.ENDPROCEDURE: token.PROCEDURE,
+	token.ENDFUNCTION:  token.FUNCTION,
+	token.ENDCLASS:     token.CLASS,
+	token.ENDTYPE:      token.TYPE,
+}
+
+// line is what the layout of one source line depends on
+type line struct {
+	first, second token.Type
+	hasEq         bool // a TYPE with = is complete on one line
+}
+
+// block is an open block and the level its first line is indented to
+type block struct {
+	kind  token.Type
+	level int
+}
+
+// Source returns src laid out in the house style
+// It fails if src does not parse, rather than guess at the layout of a
+// broken program.
+func Source(src string) (string, error) {
+	return House.Source(src)
+}
+
+// Source returns src laid out in style s
+func (s Style) Source(src string) (string, error) {
+	laid, err := layout(src, s.Indent)
+	if err != nil {
+		return "", err
+	}
+	var out strings.Builder
+	blank := true // so that blank lines at the start are dropped
+	for _, text := range laid {
+		if text == "" {
+			if !blank {
+				out.WriteString("\n")
+				blank = true
+			}
+			continue
+		}
+		blank = false
+		out.WriteString(text)
+		out.WriteString("\n")
+	}
+
+	formatted := strings.TrimRight(out.String(), "\n")
+	if formatted == "" {
+		return "", nil
+	}
+	return formatted + "\n", nil
+}
+
+// Range returns lines first to last of src, counting from 1, laid out as
+// Source lays them out in the whole program, without the line break ending
+// the last of them
+// The lines around them are left alone, so a pasted snippet can be tidied
+// in a file kept as written elsewhere.
+func Range(src string, first, last int) (string, error) {
+	return House.Range(src, first, last)
+}
+
+// Range returns lines first to last of src laid out in style s
+func (s Style) Range(src string, first, last int) (string, error) {
+	laid, err := layout(src, s.Indent)
+	if err != nil {
+		return "", err
+	}
+	first, last = max(first, 1), min(last, len(laid))
+	var out []string
+	for idx := first - 1; idx < last; idx++ {
+		if laid[idx] == "" && len(out) > 0 && out[len(out)-1] == "" {
+			continue
+		}
+		out = append(out, laid[idx])
+	}
+	return strings.Join(out, "\n"), nil
+}
+
+// Lines returns each line of src laid out in style s, trimmed of the spaces
+// around it and indented to its level, leaving blank lines empty
+func (s Style) Lines(src string) ([]string, error) {
+	return layout(src, s.Indent)
+}
+
+// layout indents each line of src to its level, trimmed of the spaces
+// around it, leaving blank lines empty
+func layout(src, indent string) ([]string, error) {
+	p := parser.New(lexer.New(src))
+	p.ParseProgram()
+	if errs := p.Errors(); len(errs) > 0 {
+		return nil, fmt.Errorf("cannot format a program with errors: %s", errs[0])
+	}
+
+	lines := scan(src)
+	var laid []string
+	var stack []block
+	for idx, text := range strings.Split(src, "\n") {
+		text = strings.TrimSpace(text)
+		if text == "" {
+			laid = append(laid, "")
+			continue
+		}
+
+		info := lines[idx+1]
+		level := 0
+		if len(stack) > 0 {
+			level = stack[len(stack)-1].level + 1
+		}
+		switch info.first {
+		case token.ELSE:
+			level--
+		case token.THEN:
+			// THEN on its own line is a level inside its IF, with the
+			// branches a level further in
+			stack = append(stack, block{kind: token.THEN, level: level})
+		default:
+			if opener, ok := closers[info.first]; ok {
+				if opener == token.IF && len(stack) > 0 && stack[len(stack)-1].kind == token.THEN {
+					stack = stack[:len(stack)-1]
+				}
+				if len(stack) > 0 && stack[len(stack)-1].kind == opener {
+					level = stack[len(stack)-1].level
+					stack = stack[:len(stack)-1]
+				}
+			}
+		}
+		laid = append(laid, strings.Repeat(indent, max(level, 0))+text)
+
+		if kind, ok := opens(info); ok {
+			stack = append(stack, block{kind: kind, level: level})
+		}
+	}
+	return laid, nil
+}
+
+// opens reports the block a line starts, if any
+func opens(l line) (token.Type, bool) {
+	switch l.first {
+	case token.IF, token.CASE, token.FOR, token.WHILE, token.REPEAT,
+		token.PROCEDURE, token.FUNCTION, token.CLASS:
+		return l.first, true
+	case token.TYPE:
+		return token.TYPE, !l.hasEq
+	case token.PUBLIC, token.PRIVATE:
+		if l.second == token.PROCEDURE || l.second == token.FUNCTION {
+			return l.second, true
+		}
+	}
+	return "", false
+}
+
+// scan finds the tokens that decide the layout of each line, by line number
+func scan(src string) map[int]line {
+	lines := map[int]line{}
+	l := lexer.New(src)
+	for tok := l.NextToken(); tok.Type != token.EOF; tok = l.NextToken() {
+		if tok.Type == token.NEWLINE {
+			continue
+		}
+		info, seen := lines[tok.Line]
+		switch {
+		case !seen:
+			info.first = tok.Type
+		case info.second == "":
+			info.second = tok.Type
+		}
+		if tok.Type == token.EQ {
+			info.hasEq = true
+		}
+		lines[tok.Line] = info
+	}
+	return lines
+}
@@ -0,0 +1,108 @@
+package format
+
+import "testing"
+
+func TestSource(t *testing.T) {
+	tests := []struct {
+		name     string
+		input    string
+		expected string
+	}{
+		{
+			"indents blocks",
+			"DECLARE I : INTEGER\nFOR I <- 1 TO 3\nIF I > 1 THEN\nOUTPUT I\nELSE\nOUTPUT \"one\"\nENDIF\nNEXT I\n",
+			"DECLARE I : INTEGER\nFOR I <- 1 TO 3\n    IF I > 1 THEN\n        OUTPUT I\n    ELSE\n        OUTPUT \"one\"\n    ENDIF\nNEXT I\n",
+		},
+		{
+			"THEN on its own line",
+			"IF TRUE\nTHEN\nOUTPUT 1\nELSE\nOUTPUT 2\nENDIF",
+			"IF TRUE\n    THEN\n        OUTPUT 1\n    ELSE\n        OUTPUT 2\nENDIF\n",
+		},
+		{
+			"case clauses",
+			"DECLARE X : INTEGER\nX <- 2\nCASE OF X\n1 : OUTPUT \"one\"\nOTHERWISE : OUTPUT \"many\"\nENDCASE",
+			"DECLARE X : INTEGER\nX <- 2\nCASE OF X\n    1 : OUTPUT \"one\"\n    OTHERWISE : OUTPUT \"many\"\nENDCASE\n",
+		},
+		{
+			"routines and loops",
+			"FUNCTION Twice(N : INTEGER) RETURNS INTEGER\n  // double it\n        RETURN N * 2\nENDFUNCTION\nPROCEDURE Count()\nDECLARE N : INTEGER\nN <- 0\nWHILE N < 3\nN <- N + 1\nENDWHILE\nREPEAT\nN <- N - 1\nUNTIL N = 0\nENDPROCEDURE",
+			"FUNCTION Twice(N : INTEGER) RETURNS INTEGER\n    // double it\n    RETURN N * 2\nENDFUNCTION\nPROCEDURE Count()\n    DECLARE N : INTEGER\n    N <- 0\n    WHILE N < 3\n        N <- N + 1\n    ENDWHILE\n    REPEAT\n        N <- N - 1\n    UNTIL N = 0\nENDPROCEDURE\n",
+		},
+		{
+			"types and classes",
+			"TYPE Season = (Spring, Summer)\nTYPE Point\nDECLARE X : INTEGER\nENDTYPE\nCLASS Pet\nPRIVATE DECLARE Name : STRING\nPUBLIC PROCEDURE NEW(N : STRING)\nName <- N\nENDPROCEDURE\nENDCLASS",
+			"TYPE Season = (Spring, Summer)\nTYPE Point\n    DECLARE X : INTEGER\nENDTYPE\nCLASS Pet\n    PRIVATE DECLARE Name : STRING\n    PUBLIC PROCEDURE NEW(N : STRING)\n        Name <- N\n    ENDPROCEDURE\nENDCLASS\n",
+		},
+		{
+			"blank lines and trailing spaces",
+			"\n\nOUTPUT 1   \n\n\n\nOUTPUT 2\t\n\n",
+			"OUTPUT 1\n\nOUTPUT 2\n",
+		},
+		{"empty", "\n\n", ""},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			got, err := Source(tt.input)
+			if err != nil {
+				t.Fatalf("unexpected error: %v", err)
+			}
+			if got != tt.expected {
+				t.Errorf("expected:\n%s\ngot:\n%s", tt.expected, got)
+			}
+			again, _ := Source(got)
+			if again != got {
+				t.Errorf("formatting is not stable:\n%s", again)
+			}
+		})
+	}
+}
+
+func TestSourceWithErrors(t *testing.T) {
+	if _, err := Source("IF TRUE THEN\nOUTPUT 1\n"); err == nil {
+		t.Error("expected an error for a program that does not parse")
+	}
+}
+
+func TestRange(t *testing.T) {
+	src := "OUTPUT  1\nIF TRUE THEN\nOUTPUT 2\n\n\n   OUTPUT 3   \nENDIF\n"
+	tests := []struct {
+		first, last int
+		expected    string
+	}{
+		{1, 1, "OUTPUT  1"},
+		{3, 6, "    OUTPUT 2\n\n    OUTPUT 3"},
+		{6, 9, "    OUTPUT 3\nENDIF\n"},
+	}
+
+	for _, tt := range tests {
+		got, err := Range(src, tt.first, tt.last)
+		if err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+		if got != tt.expected {
+			t.Errorf("lines %d to %d: expected %q, got %q", tt.first, tt.last, tt.expected, got)
+		}
+	}
+}
+
+func TestStyle(t *testing.T) {
+	src := "WHILE TRUE\nIF TRUE THEN\nOUTPUT 1\nENDIF\nENDWHILE\n"
+	tests := []struct {
+		indent   string
+		expected string
+	}{
+		{"  ", "WHILE TRUE\n  IF TRUE THEN\n    OUTPUT 1\n  ENDIF\nENDWHILE\n"},
+		{"\t", "WHILE TRUE\n\tIF TRUE THEN\n\t\tOUTPUT 1\n\tENDIF\nENDWHILE\n"},
+	}
+
+	for _, tt := range tests {
+		got, err := Style{Indent: tt.indent}.Source(src)
+		if err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+		if got != tt.expected {
+			t.Errorf("indenting with %q: expected %q, got %q", tt.indent, tt.expected, got)
+		}
+	}
+}
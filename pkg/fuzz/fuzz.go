@@ -0,0 +1,452 @@
+// Package fuzz runs a program on many sets of random input, for `cambridge
+// fuzz`, and reports the runs that go wrong: those stopping with an error,
+// those still running after their steps or time are up, and those leaving
+// behind results an assertion says are wrong
+package fuzz
+
+import (
+	"bytes"
+	"fmt"
+	"math"
+	"math/rand"
+	"runtime"
+	"sort"
+	"strconv"
+	"strings"
+	"sync"
+	"time"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+// OutputName is the name an assertion reads everything the program wrote
+// by, as a STRING
+const OutputName = "OutputText"
+
+// Kind is the sort of values a Range generates
+type Kind int
+
+const (
+	Integer Kind = iota // whole numbers from Low to High
+	Real                // numbers from Low to High, to two decimal places
+	Text                // letters and digits, Low to High characters long
+	Choice              // one of Choices
+)
+
+// Range is the values generated for the INPUTs into one variable
+// Numbers land on one of the bounds, or on zero between them, a fifth of
+// the time, as that is where programs most often go wrong.
+type Range struct {
+	Kind      Kind
+	Low, High float64
+	Choices   []string
+}
+
+// ParseRange reads a range written as LOW..HIGH, for integers or reals by
+// how the bounds are written, string:LOW..HIGH for text of that length,
+// or A|B|C for one of the choices
+func ParseRange(text string) (Range, error) {
+	if length, ok := strings.CutPrefix(text, "string:"); ok {
+		low, high, err := bounds(length, true)
+		if err != nil {
+			return Range{}, fmt.Errorf("invalid range %q: %v", text, err)
+		}
+		if low < 0 {
+			return Range{}, fmt.Errorf("invalid range %q: a string cannot be shorter than nothing", text)
+		}
+		return Range{Kind: Text, Low: low, High: high}, nil
+	}
+	if strings.Contains(text, "..") {
+		whole := !strings.ContainsAny(strings.Replace(text, "..", " ", 1), ".eE")
+		low, high, err := bounds(text, whole)
+		if err != nil {
+			return Range{}, fmt.Errorf("invalid range %q: %v", text, err)
+		}
+		if whole {
+			return Range{Kind: Integer, Low: low, High: high}, nil
+		}
+		return Range{Kind: Real, Low: low, High: high}, nil
+	}
+	if text == "" {
+		return Range{}, fmt.Errorf("invalid range %q: give LOW..HIGH, string:LOW..HIGH or choices separated by |", text)
+	}
+	return Range{Kind: Choice, Choices: strings.Split(text, "|")}, nil
+}
+
+// bounds reads LOW..HIGH, as whole numbers if whole is set
+func bounds(text string, whole bool) (float64, float64, error) {
+	lowText, highText, ok := strings.Cut(text, "..")
+	if !ok {
+		return 0, 0, fmt.Errorf("expected LOW..HIGH")
+	}
+	parse := func(s string) (float64, error) {
+		if whole {
+			n, err := strconv.ParseInt(strings.TrimSpace(s), 10, 64)
+			return float64(n), err
+		}
+		return strconv.ParseFloat(strings.TrimSpace(s), 64)
+	}
+	low, err := parse(lowText)
+	if err != nil {
+		return 0, 0, fmt.Errorf("%q is not a number", lowText)
+	}
+	high, err := parse(highText)
+	if err != nil {
+		return 0, 0, fmt.Errorf("%q is not a number", highText)
+	}
+	if low > high {
+		return 0, 0, fmt.Errorf("%s is more than %s", lowText, highText)
+	}
+	return low, high, nil
+}
+
+// characters are what generated text is made of
+const characters = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 "
+
+// Generate returns one line of input from r
+func (r Range) Generate(rng *rand.Rand) string {
+	edge := rng.Intn(5) == 0
+	switch r.Kind {
+	case Integer:
+		n := r.Low + float64(rng.Int63n(int64(r.High-r.Low)+1))
+		if edge {
+			n = r.edge(rng)
+		}
+		return strconv.FormatInt(int64(n), 10)
+	case Real:
+		x := math.Round((r.Low+rng.Float64()*(r.High-r.Low))*100) / 100
+		if edge {
+			x = r.edge(rng)
+		}
+		return strconv.FormatFloat(x, 'f', -1, 64)
+	case Text:
+		length := int(r.Low) + rng.Intn(int(r.High-r.Low)+1)
+		if edge {
+			length = int(r.edge(rng))
+		}
+		out := make([]byte, length)
+		for n := range out {
+			out[n] = characters[rng.Intn(len(characters))]
+		}
+		return string(out)
+	}
+	return r.Choices[rng.Intn(len(r.Choices))]
+}
+
+// edge returns one of the bounds of r, or zero if it lies between them
+func (r Range) edge(rng *rand.Rand) float64 {
+	edges := []float64{r.Low, r.High}
+	if r.Low < 0 && r.High > 0 {
+		edges = append(edges, 0)
+	}
+	return edges[rng.Intn(len(edges))]
+}
+
+// defaults are the ranges of the INPUTs into variables declared with each
+// type that no range is given for
+var defaults = map[string]Range{
+	"INTEGER": {Kind: Integer, Low: -1000, High: 1000},
+	"REAL":    {Kind: Real, Low: -1000, High: 1000},
+	"STRING":  {Kind: Text, Low: 0, High: 10},
+	"CHAR":    {Kind: Text, Low: 1, High: 1},
+	"BOOLEAN": {Kind: Choice, Choices: []string{"TRUE", "FALSE"}},
+}
+
+// ParseAssertion parses text as the expression a run's results must make
+// TRUE, which may name the program's top-level variables and OutputName
+func ParseAssertion(text string) (ast.Expression, error) {
+	p := parser.New(lexer.New(text))
+	prog := p.ParseProgram()
+	if errs := p.Errors(); len(errs) > 0 {
+		return nil, fmt.Errorf("cannot parse assertion %s: %s", text, errs[0])
+	}
+	if len(prog.Statements) == 1 {
+		if stmt, ok := prog.Statements[0].(*ast.ExpressionStatement); ok {
+			return stmt.Expression, nil
+		}
+	}
+	return nil, fmt.Errorf("cannot parse assertion %s: expected an expression", text)
+}
+
+// Options say how a program is fuzzed
+type Options struct {
+	Runs    int              // sets of input to try
+	Seed    int64            // the same seed tries the same input again
+	Steps   int              // statements a run may take, 0 for no limit
+	Timeout time.Duration    // how long a run may take, 0 for no limit
+	Inputs  map[string]Range // the values read into each variable, by name
+	Assert  ast.Expression   // must be TRUE once a run finishes, nil for none
+	Workers int              // runs at once, one per CPU if below one
+
+	Arithmetic interpreter.Arithmetic
+	Files      interpreter.FileSystem // where OPENFILE looks, the disk if nil
+	Disabled   []interpreter.Capability
+
+	// Builtins returns the builtin functions for one run, the standard set
+	// if nil; it is called afresh for each run
+	Builtins func() map[string]*interpreter.Builtin
+}
+
+// Outcome is how a run went wrong
+type Outcome string
+
+const (
+	Crashed  Outcome = "crash"     // stopped with a runtime error
+	TimedOut Outcome = "timeout"   // still running when its steps or time ran out
+	Violated Outcome = "assertion" // finished, but not as the assertion says it should
+)
+
+// Failure is a way the program went wrong, with the first input found to
+// make it do so
+// Runs failing with the same outcome, message and line count as one, as do
+// all those running out of steps or time.
+type Failure struct {
+	Outcome Outcome  `json:"outcome"`
+	Message string   `json:"message"`
+	Line    int      `json:"line,omitempty"`
+	Run     int      `json:"run"`   // the first run to fail this way, counting from 1
+	Input   []string `json:"input"` // the lines INPUT read in that run
+	Output  string   `json:"output"`
+	Count   int      `json:"count"`
+}
+
+// Report is the outcome of fuzzing a program
+type Report struct {
+	Runs     int       `json:"runs"`
+	Seed     int64     `json:"seed"`
+	Failures []Failure `json:"failures"` // in order of the run first failing each way
+}
+
+// Run tries program, already resolved, on opts.Runs sets of input
+// It fails before running anything if an INPUT reads into a variable that
+// has no range and is not declared with a type one can be chosen for.
+func Run(program *ast.Program, opts Options) (Report, error) {
+	ranges, err := InputRanges(program, opts.Inputs)
+	if err != nil {
+		return Report{}, err
+	}
+
+	failures := make([]*Failure, opts.Runs)
+	workers := opts.Workers
+	if workers < 1 {
+		workers = runtime.NumCPU()
+	}
+	jobs := make(chan int)
+	var wg sync.WaitGroup
+	for w := 0; w < min(workers, opts.Runs); w++ {
+		wg.Add(1)
+		go func() {
+			defer wg.Done()
+			for n := range jobs {
+				failures[n] = runOnce(program, opts, ranges, n)
+			}
+		}()
+	}
+	for n := 0; n < opts.Runs; n++ {
+		jobs <- n
+	}
+	close(jobs)
+	wg.Wait()
+
+	report := Report{Runs: opts.Runs, Seed: opts.Seed}
+	seen := map[string]int{}
+	for _, f := range failures {
+		if f == nil {
+			continue
+		}
+		key := fmt.Sprintf("%s %s", f.Outcome, f.Message)
+		if f.Outcome != TimedOut {
+			key += fmt.Sprintf(" %d", f.Line) // wherever a run is stopped, it is the one failure
+		}
+		if idx, ok := seen[key]; ok {
+			report.Failures[idx].Count++
+			continue
+		}
+		seen[key] = len(report.Failures)
+		f.Count = 1
+		report.Failures = append(report.Failures, *f)
+	}
+	return report, nil
+}
+
+// InputRanges returns the range of every variable an INPUT in program
+// reads into, from given or else from the type it is declared with
+func InputRanges(program *ast.Program, given map[string]Range) (map[string]Range, error) {
+	types := map[string]string{}
+	ranges := map[string]Range{}
+	var missing []string
+	ast.Inspect(program, func(node ast.Node) bool {
+		if d, ok := node.(*ast.DeclareStatement); ok && !ast.IsNil(d.Name) {
+			if _, seen := types[d.Name.Value]; !seen {
+				types[d.Name.Value] = elementType(d.DataType)
+			}
+		}
+		return true
+	})
+	ast.Inspect(program, func(node ast.Node) bool {
+		input, ok := node.(*ast.InputStatement)
+		if !ok {
+			return true
+		}
+		name := target(input.Variable)
+		if r, ok := given[name]; ok {
+			ranges[name] = r
+		} else if r, ok := defaults[types[name]]; ok {
+			ranges[name] = r
+		} else if _, ok := ranges[name]; !ok {
+			missing = append(missing, name)
+			ranges[name] = Range{}
+		}
+		return true
+	})
+	if len(missing) > 0 {
+		sort.Strings(missing)
+		return nil, fmt.Errorf("give a range with --input for %s, as INPUT reads into it and its type has no default range", strings.Join(missing, ", "))
+	}
+	return ranges, nil
+}
+
+// elementType names the type of t, or of its elements if it is an array
+func elementType(t ast.DataType) string {
+	if array, ok := t.(*ast.ArrayType); ok {
+		return elementType(array.ElementType)
+	}
+	if t == nil {
+		return ""
+	}
+	return t.String()
+}
+
+// target returns the name of the variable an INPUT reads into: an array's
+// for one of its elements, and as written for anything else
+func target(expr ast.Expression) string {
+	switch e := expr.(type) {
+	case *ast.Identifier:
+		return e.Value
+	case *ast.ArrayAccess:
+		return target(e.Array)
+	}
+	if ast.IsNil(expr) {
+		return ""
+	}
+	return expr.String()
+}
+
+// Feeder generates each line of input as an INPUT is about to read it,
+// from the range of the variable it reads into, and stops a run that has
+// gone on too long
+// It is both the debugger and the input of the interpreter it feeds.
+type Feeder struct {
+	Limit    int       // statements the run may take, 0 for no limit
+	Deadline time.Time // when the run must stop by, zero for never
+
+	rng     *rand.Rand
+	ranges  map[string]Range
+	pending bytes.Buffer // generated but not yet read
+	read    []string
+	steps   int
+	expired bool
+}
+
+// NewFeeder returns a Feeder drawing on ranges, as InputRanges gives them,
+// that generates the same input for the same seed
+func NewFeeder(ranges map[string]Range, seed int64) *Feeder {
+	return &Feeder{rng: rand.New(rand.NewSource(seed)), ranges: ranges}
+}
+
+func (f *Feeder) Before(_ *interpreter.Interpreter, stmt ast.Statement, _ *interpreter.Environment) bool {
+	f.steps++
+	if f.Limit > 0 && f.steps > f.Limit || !f.Deadline.IsZero() && f.steps%1024 == 0 && time.Now().After(f.Deadline) {
+		f.expired = true
+		return false
+	}
+	if input, ok := stmt.(*ast.InputStatement); ok {
+		line := f.ranges[target(input.Variable)].Generate(f.rng)
+		f.read = append(f.read, line)
+		f.pending.WriteString(line + "\n")
+	}
+	return true
+}
+
+// Read gives INPUT the lines generated for it
+func (f *Feeder) Read(p []byte) (int, error) {
+	return f.pending.Read(p)
+}
+
+// Lines returns the lines of input generated so far
+func (f *Feeder) Lines() []string {
+	return f.read
+}
+
+// Expired reports whether the run was stopped for going on too long
+func (f *Feeder) Expired() bool {
+	return f.expired
+}
+
+// runOnce makes run n of program, returning how it went wrong, or nil
+func runOnce(program *ast.Program, opts Options, ranges map[string]Range, n int) (failure *Failure) {
+	f := NewFeeder(ranges, opts.Seed+int64(n))
+	f.Limit = opts.Steps
+	if opts.Timeout > 0 {
+		f.Deadline = time.Now().Add(opts.Timeout)
+	}
+	var out bytes.Buffer
+	fail := func(outcome Outcome, message string, line int) *Failure {
+		return &Failure{Outcome: outcome, Message: message, Line: line, Run: n + 1, Input: f.read, Output: out.String()}
+	}
+	defer func() {
+		if r := recover(); r != nil {
+			failure = fail(Crashed, fmt.Sprintf("interpreter panic: %v", r), 0)
+		}
+	}()
+
+	interp := interpreter.New()
+	if opts.Builtins != nil {
+		interp.SetBuiltins(opts.Builtins())
+	} else {
+		interp.SetBuiltins(builtins.GetBuiltins())
+	}
+	interp.SetInput(f)
+	interp.SetOutput(&out)
+	interp.SetArithmetic(opts.Arithmetic)
+	if opts.Files != nil {
+		interp.SetFiles(opts.Files)
+	}
+	interp.Disable(opts.Disabled...)
+	interp.SetDebugger(f)
+
+	if err, ok := interp.Eval(program).(*interpreter.Error); ok {
+		if f.Expired() {
+			return fail(TimedOut, timeout(opts, f), err.Line)
+		}
+		return fail(Crashed, err.Message, err.Line)
+	}
+	if opts.Assert == nil {
+		return nil
+	}
+
+	env := interpreter.NewEnclosedEnvironment(interp.Globals())
+	env.Declare(OutputName, &interpreter.String{Value: out.String()})
+	value, err := interp.Evaluate(opts.Assert, env)
+	switch result, _ := value.(*interpreter.Boolean); {
+	case err != nil:
+		return fail(Violated, fmt.Sprintf("%s could not be worked out: %s", opts.Assert, err.Message), 0)
+	case result == nil:
+		return fail(Violated, fmt.Sprintf("%s is %s, not TRUE or FALSE", opts.Assert, value.Inspect()), 0)
+	case !result.Value:
+		return fail(Violated, fmt.Sprintf("%s is FALSE", opts.Assert), 0)
+	}
+	return nil
+}
+
+// timeout says what a run that went on too long ran out of
+func timeout(opts Options, f *Feeder) string {
+	if opts.Steps > 0 && f.steps > opts.Steps {
+		return fmt.Sprintf("still running after %d steps", opts.Steps)
+	}
+	return fmt.Sprintf("still running after %s", opts.Timeout)
+}
@@ -0,0 +1,136 @@
+package fuzz
+
+import (
+	"fmt"
+	"strings"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+func TestParseRange(t *testing.T) {
+	tests := []struct {
+		text     string
+		expected Range
+		err      bool
+	}{
+		{"1..10", Range{Kind: Integer, Low: 1, High: 10}, false},
+		{"-5..5", Range{Kind: Integer, Low: -5, High: 5}, false},
+		{"0.5..2.5", Range{Kind: Real, Low: 0.5, High: 2.5}, false},
+		{"string:0..8", Range{Kind: Text, Low: 0, High: 8}, false},
+		{"yes|no", Range{Kind: Choice, Choices: []string{"yes", "no"}}, false},
+		{"10..1", Range{}, true},
+		{"a..b", Range{}, true},
+		{"string:-1..2", Range{}, true},
+		{"", Range{}, true},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.text, func(t *testing.T) {
+			got, err := ParseRange(tt.text)
+			if (err != nil) != tt.err {
+				t.Fatalf("expected error %t, got %v", tt.err, err)
+			}
+			if fmt.Sprint(got) != fmt.Sprint(tt.expected) {
+				t.Errorf("expected %+v, got %+v", tt.expected, got)
+			}
+		})
+	}
+}
+
+func TestRun(t *testing.T) {
+	tests := []struct {
+		name     string
+		src      string
+		inputs   map[string]string
+		assert   string
+		expected []string // outcome:line
+	}{
+		{
+			"clean",
+			"DECLARE Name : STRING\nINPUT Name\nOUTPUT \"Hello \" & Name\n",
+			nil,
+			"LENGTH(OutputText) >= 6",
+			nil,
+		},
+		{
+			"crash on an edge",
+			"DECLARE N : STRING\nINPUT N\nOUTPUT 10 / STR_TO_NUM(N)\n",
+			map[string]string{"N": "0..3"},
+			"",
+			[]string{"crash:3"},
+		},
+		{
+			"timeout",
+			"DECLARE N : STRING\nINPUT N\nWHILE N <> \"stop\"\n    N <- N\nENDWHILE\n",
+			map[string]string{"N": "go|stop"},
+			"",
+			[]string{"timeout:0"},
+		},
+		{
+			"assertion",
+			"DECLARE N : STRING\nDECLARE Twice : INTEGER\nINPUT N\nTwice <- STR_TO_NUM(N) + 2\n",
+			map[string]string{"N": "0..5"},
+			"Twice = STR_TO_NUM(N) * 2",
+			[]string{"assertion:0"},
+		},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			p := parser.New(lexer.New(tt.src))
+			prog := p.ParseProgram()
+			if len(p.Errors()) > 0 {
+				t.Fatalf("parser errors: %v", p.Errors())
+			}
+			interpreter.Resolve(prog)
+			opts := Options{Runs: 50, Seed: 1, Steps: 1000, Inputs: map[string]Range{}}
+			for name, text := range tt.inputs {
+				r, err := ParseRange(text)
+				if err != nil {
+					t.Fatal(err)
+				}
+				opts.Inputs[name] = r
+			}
+			if tt.assert != "" {
+				expr, err := ParseAssertion(tt.assert)
+				if err != nil {
+					t.Fatal(err)
+				}
+				opts.Assert = expr
+			}
+
+			report, err := Run(prog, opts)
+			if err != nil {
+				t.Fatalf("unexpected error: %v", err)
+			}
+			var got []string
+			for _, f := range report.Failures {
+				got = append(got, fmt.Sprintf("%s:%d", f.Outcome, lineOf(f)))
+			}
+			if strings.Join(got, ", ") != strings.Join(tt.expected, ", ") {
+				t.Errorf("expected %v, got %v", tt.expected, got)
+			}
+		})
+	}
+}
+
+// lineOf is where f happened, or 0 for an outcome that has no one place
+func lineOf(f Failure) int {
+	if f.Outcome == TimedOut {
+		return 0
+	}
+	return f.Line
+}
+
+func TestRunNeedsRanges(t *testing.T) {
+	p := parser.New(lexer.New("DECLARE Birthday : DATE\nINPUT Birthday\nINPUT Other\n"))
+	prog := p.ParseProgram()
+	interpreter.Resolve(prog)
+	_, err := Run(prog, Options{Runs: 1})
+	if err == nil || !strings.Contains(err.Error(), "Birthday, Other") {
+		t.Errorf("expected an error naming Birthday and Other, got %v", err)
+	}
+}
@@ -0,0 +1,217 @@
+// Package include loads a program split across files with INCLUDE, so that
+// a library of routines, such as sorting or string helpers, can be shared
+// by several programs
+// Each INCLUDE at the top level of a file is replaced by the statements of
+// the file it names, found relative to the including file, so the program
+// runs like any other. Lines are numbered as if the files followed one
+// another, the main file first: Locate turns those numbers back into a
+// file and a line within it, so that problems are reported where they are.
+package include
+
+import (
+	"errors"
+	"fmt"
+	"io"
+	"io/fs"
+	"os"
+	"path/filepath"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+// File is one of the files a program was loaded from
+type File struct {
+	diagnostic.Source
+	First int // the program's number for the file's first line
+}
+
+// Program is a program loaded from a main file and the files it includes
+type Program struct {
+	AST         *ast.Program
+	Files       []*File                 // the main file first, then each file included in the order reached
+	Diagnostics []diagnostic.Diagnostic // numbered as the program's lines are, for Attribute
+}
+
+// Load parses the main file name, whose text is text, and every file it
+// includes, which read is given the names of
+// A file included more than once is only loaded the first time, and one
+// that includes itself, directly or through others, is reported.
+func Load(name, text string, read func(name string) (string, error)) *Program {
+	l := &loader{read: read, prog: &Program{}, next: 1, loaded: map[string]bool{filepath.Clean(name): true}}
+	l.prog.AST = &ast.Program{Statements: l.load(name, text)}
+	return l.prog
+}
+
+// ReadFile reads an included file from the disk, for Load
+func ReadFile(name string) (string, error) {
+	data, err := os.ReadFile(name)
+	return string(data), err
+}
+
+// ReadSandboxed returns a read for Load that, as interpreter.SandboxFiles
+// does for OPENFILE, only reads files inside root
+// An absolute INCLUDE, or one whose .. leaves root, is refused with
+// interpreter.ErrOutsideRoot, so its text is never read or shown.
+func ReadSandboxed(root string) func(name string) (string, error) {
+	files := interpreter.SandboxFiles{Root: root}
+	return func(name string) (string, error) {
+		path := name
+		if filepath.IsAbs(root) && !filepath.IsAbs(path) {
+			path, _ = filepath.Abs(path)
+		}
+		rel, err := filepath.Rel(root, path)
+		if err != nil || filepath.IsAbs(path) != filepath.IsAbs(root) {
+			return "", &fs.PathError{Op: "open", Path: name, Err: interpreter.ErrOutsideRoot}
+		}
+		f, err := files.Open(rel)
+		if err != nil {
+			return "", err
+		}
+		defer f.Close()
+		data, err := io.ReadAll(f)
+		return string(data), err
+	}
+}
+
+// Single wraps a program parsed from one file, so it can be reported on
+// like one loaded
+func Single(name, text string, tree *ast.Program) *Program {
+	return &Program{AST: tree, Files: []*File{{Source: diagnostic.Source{Name: name, Text: text}, First: 1}}}
+}
+
+// OK reports whether every file parsed and could be included
+func (p *Program) OK() bool {
+	return len(p.Diagnostics) == 0
+}
+
+// Main returns the main file
+func (p *Program) Main() *diagnostic.Source {
+	return &p.Files[0].Source
+}
+
+// Text returns the text of the files one after another, in which the
+// program's line numbers are those of its lines
+func (p *Program) Text() string {
+	texts := make([]string, len(p.Files))
+	for i, f := range p.Files {
+		texts[i] = f.Text
+	}
+	return strings.Join(texts, "\n")
+}
+
+// Locate returns the file holding line of the program and the line within
+// it; lines it knows nothing of are put in the main file
+func (p *Program) Locate(line int) (*File, int) {
+	for i := len(p.Files) - 1; i > 0; i-- {
+		if f := p.Files[i]; line >= f.First {
+			return f, line - f.First + 1
+		}
+	}
+	return p.Files[0], line
+}
+
+// Attribute returns d numbered within the file it is about, with that file
+// to render it against
+func (p *Program) Attribute(d diagnostic.Diagnostic) (diagnostic.Diagnostic, *diagnostic.Source) {
+	if d.Line <= 0 {
+		return d, p.Main()
+	}
+	file, line := p.Locate(d.Line)
+	d.Line = line
+	return d, &file.Source
+}
+
+// Errors returns the problems loading the program as parser.Errors words
+// them, naming the file for those not in the main one
+func (p *Program) Errors() []string {
+	var errs []string
+	for _, d := range p.Diagnostics {
+		d, src := p.Attribute(d)
+		msg := fmt.Sprintf("line %d, column %d: %s", d.Line, d.Column, d.Message)
+		if src != p.Main() {
+			msg = src.Name + ", " + msg
+		}
+		errs = append(errs, msg)
+	}
+	return errs
+}
+
+type loader struct {
+	read   func(name string) (string, error)
+	prog   *Program
+	next   int             // the number of the next file's first line
+	loaded map[string]bool // files already loaded, by cleaned name
+	open   []string        // files being loaded, the main file first
+}
+
+// load parses a file and the files it includes, returning its statements
+// with those of the files in place of each INCLUDE
+func (l *loader) load(name, text string) []ast.Statement {
+	file := &File{Source: diagnostic.Source{Name: name, Text: text}, First: l.next}
+	l.prog.Files = append(l.prog.Files, file)
+	l.next += strings.Count(text, "\n") + 1
+
+	p := parser.New(lexer.NewAt(text, file.First))
+	tree := p.ParseProgram()
+	l.prog.Diagnostics = append(l.prog.Diagnostics, p.Diagnostics()...)
+
+	l.open = append(l.open, filepath.Clean(name))
+	defer func() { l.open = l.open[:len(l.open)-1] }()
+
+	stmts := []ast.Statement{}
+	for _, stmt := range tree.Statements {
+		if inc, ok := stmt.(*ast.IncludeStatement); ok && inc != nil {
+			stmts = append(stmts, l.include(name, inc)...)
+			continue
+		}
+		ast.Inspect(stmt, func(node ast.Node) bool {
+			if inc, ok := node.(*ast.IncludeStatement); ok {
+				l.report(inc, diagnostic.IncludeNotTopLevel)
+			}
+			return true
+		})
+		stmts = append(stmts, stmt)
+	}
+	return stmts
+}
+
+// include returns the statements of the file stmt names, in from
+func (l *loader) include(from string, stmt *ast.IncludeStatement) []ast.Statement {
+	name := filepath.FromSlash(stmt.Path)
+	if !filepath.IsAbs(name) {
+		name = filepath.Join(filepath.Dir(from), name)
+	}
+	name = filepath.Clean(name)
+	for i, open := range l.open {
+		if open == name {
+			chain := append(append([]string{}, l.open[i:]...), name)
+			l.report(stmt, diagnostic.IncludeCycle, name, strings.Join(chain, " → "))
+			return nil
+		}
+	}
+	if l.loaded[name] {
+		return nil
+	}
+	text, err := l.read(name)
+	if err != nil {
+		var pathErr *fs.PathError
+		if errors.As(err, &pathErr) {
+			err = pathErr.Err
+		}
+		l.report(stmt, diagnostic.IncludeUnreadable, name, err.Error())
+		return nil
+	}
+	l.loaded[name] = true
+	return l.load(name, text)
+}
+
+func (l *loader) report(stmt *ast.IncludeStatement, code diagnostic.Code, args ...interface{}) {
+	d := diagnostic.New(diagnostic.Error, code, args...)
+	d.Line, d.Column = stmt.Token.Line, stmt.Token.Column
+	l.prog.Diagnostics = append(l.prog.Diagnostics, d)
+}
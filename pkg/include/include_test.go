@@ -0,0 +1,184 @@
+package include
+
+import (
+	"bytes"
+	"io/fs"
+	"os"
+	"path/filepath"
+	"reflect"
+	"strings"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+)
+
+// files reads from a map, as the disk would
+func files(m map[string]string) func(string) (string, error) {
+	return func(name string) (string, error) {
+		if text, ok := m[name]; ok {
+			return text, nil
+		}
+		return "", &fs.PathError{Op: "open", Path: name, Err: fs.ErrNotExist}
+	}
+}
+
+func TestLoad(t *testing.T) {
+	disk := map[string]string{
+		"lib/strings.cam": "FUNCTION Shout(S : STRING) RETURNS STRING\n    RETURN S & \"!\"\nENDFUNCTION\n",
+		"lib/maths.cam":   "INCLUDE \"strings.cam\"\nFUNCTION Half(N : INTEGER) RETURNS INTEGER\n    RETURN N DIV 0\nENDFUNCTION",
+	}
+	main := "INCLUDE \"lib/strings.cam\"\nINCLUDE \"lib/maths.cam\"\nOUTPUT Shout(\"hello\")\nOUTPUT Half(4)"
+	prog := Load("main.cam", main, files(disk))
+	if !prog.OK() {
+		t.Fatalf("unexpected errors: %v", prog.Errors())
+	}
+
+	var names []string
+	for _, f := range prog.Files {
+		names = append(names, f.Name)
+	}
+	if expected := []string{"main.cam", "lib/strings.cam", "lib/maths.cam"}; !reflect.DeepEqual(names, expected) {
+		t.Errorf("expected files %v, got %v", expected, names)
+	}
+	if len(prog.AST.Statements) != 4 {
+		t.Fatalf("expected the two functions and two OUTPUTs, got %s", prog.AST)
+	}
+
+	interpreter.Resolve(prog.AST)
+	interp := interpreter.New()
+	var out bytes.Buffer
+	interp.SetOutput(&out)
+	result := interp.Eval(prog.AST)
+	if out.String() != "hello!\n" {
+		t.Errorf("expected hello!, got %q", out.String())
+	}
+	err, ok := result.(*interpreter.Error)
+	if !ok {
+		t.Fatalf("expected division by zero, got %v", result)
+	}
+	d, src := prog.Attribute(err.Diagnostic())
+	if src.Name != "lib/maths.cam" || d.Line != 3 || d.Code != diagnostic.DivisionByZero {
+		t.Errorf("expected the error at lib/maths.cam:3, got %s:%d %s", src.Name, d.Line, d.Code)
+	}
+	if file, line := prog.Locate(4); file.Name != "main.cam" || line != 4 {
+		t.Errorf("expected main.cam:4, got %s:%d", file.Name, line)
+	}
+	if lines := strings.Split(prog.Text(), "\n"); lines[10] != "    RETURN N DIV 0" {
+		t.Errorf("expected line 11 of the text to be the RETURN, got %q", lines[10])
+	}
+}
+
+func TestLoadErrors(t *testing.T) {
+	tests := []struct {
+		name     string
+		main     string
+		disk     map[string]string
+		code     diagnostic.Code
+		file     string
+		line     int
+		expected string
+	}{
+		{
+			"missing file",
+			"OUTPUT 1\nINCLUDE \"sort.cam\"",
+			nil,
+			diagnostic.IncludeUnreadable,
+			"main.cam", 2,
+			"cannot read sort.cam, included here: file does not exist",
+		},
+		{
+			"cycle",
+			"INCLUDE \"a.cam\"",
+			map[string]string{"a.cam": "INCLUDE \"b.cam\"", "b.cam": "\nINCLUDE \"a.cam\""},
+			diagnostic.IncludeCycle,
+			"b.cam", 2,
+			"a.cam includes itself, through a.cam → b.cam → a.cam",
+		},
+		{
+			"includes itself",
+			"INCLUDE \"main.cam\"",
+			nil,
+			diagnostic.IncludeCycle,
+			"main.cam", 1,
+			"main.cam includes itself, through main.cam → main.cam",
+		},
+		{
+			"inside a block",
+			"IF TRUE THEN\n    INCLUDE \"sort.cam\"\nENDIF",
+			nil,
+			diagnostic.IncludeNotTopLevel,
+			"main.cam", 2,
+			"INCLUDE must be at the top level of a file, outside any routine or block",
+		},
+		{
+			"syntax error in an included file",
+			"DECLARE X : INTEGER\nINCLUDE \"sort.cam\"\nX <- 1",
+			map[string]string{"sort.cam": "PROCEDURE Sort()\n    OUTPUT (1\nENDPROCEDURE"},
+			diagnostic.ExpectedToken,
+			"sort.cam", 2,
+			"",
+		},
+	}
+
+	for _, tt := range tests {
+		prog := Load("main.cam", tt.main, files(tt.disk))
+		if prog.OK() {
+			t.Errorf("%s: expected an error", tt.name)
+			continue
+		}
+		d, src := prog.Attribute(prog.Diagnostics[0])
+		if d.Code != tt.code || src.Name != tt.file || d.Line != tt.line {
+			t.Errorf("%s: expected %s at %s:%d, got %s at %s:%d", tt.name, tt.code, tt.file, tt.line, d.Code, src.Name, d.Line)
+		}
+		if tt.expected != "" && d.Message != tt.expected {
+			t.Errorf("%s: expected %q, got %q", tt.name, tt.expected, d.Message)
+		}
+	}
+}
+
+func TestErrors(t *testing.T) {
+	prog := Load("main.cam", "INCLUDE \"lib.cam\"\nOUTPUT (", files(map[string]string{"lib.cam": "OUTPUT (1"}))
+	errs := prog.Errors()
+	if len(errs) < 2 || !strings.HasPrefix(errs[0], "line 2, ") || !strings.HasPrefix(errs[len(errs)-1], "lib.cam, line 1, ") {
+		t.Errorf("expected the main file's error then the library's, got %q", errs)
+	}
+}
+
+func TestReadSandboxed(t *testing.T) {
+	dir := t.TempDir()
+	root := filepath.Join(dir, "project")
+	secret := filepath.Join(dir, "secret.cam")
+	if err := os.Mkdir(root, 0755); err != nil {
+		t.Fatal(err)
+	}
+	os.WriteFile(filepath.Join(root, "lib.cam"), []byte("DECLARE X : INTEGER"), 0644)
+	os.WriteFile(secret, []byte("the password"), 0644)
+
+	tests := []struct {
+		include string
+		allowed bool
+	}{
+		{"lib.cam", true},
+		{"../secret.cam", false},
+		{filepath.ToSlash(secret), false},
+	}
+
+	read := ReadSandboxed(root)
+	for _, tt := range tests {
+		prog := Load(filepath.Join(root, "main.cam"), "INCLUDE \""+tt.include+"\"", read)
+		if prog.OK() != tt.allowed {
+			t.Errorf("%s: expected allowed %v, got %v", tt.include, tt.allowed, prog.Errors())
+			continue
+		}
+		if tt.allowed {
+			continue
+		}
+		if d := prog.Diagnostics[0]; d.Code != diagnostic.IncludeUnreadable || !strings.HasSuffix(d.Message, interpreter.ErrOutsideRoot.Error()) {
+			t.Errorf("%s: expected it refused as outside the folder, got %s %q", tt.include, d.Code, d.Message)
+		}
+		if strings.Contains(prog.Text(), "password") {
+			t.Errorf("%s: the refused file was read", tt.include)
+		}
+	}
+}
@@ -0,0 +1,68 @@
+package interpreter
+
+import (
+	"fmt"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+)
+
+// Capability is a group of statements and builtins that a host can turn
+// off, such as a browser playground with no disk or an exam-practice mode
+type Capability string
+
+const (
+	FileHandling Capability = "files"  // OPENFILE, READFILE, WRITEFILE, CLOSEFILE and EOF
+	Randomness   Capability = "random" // RAND and RANDOM
+	Interaction  Capability = "input"  // INPUT
+)
+
+// Capabilities lists every capability
+var Capabilities = []Capability{FileHandling, Randomness, Interaction}
+
+// ParseCapability returns the capability called name
+func ParseCapability(name string) (Capability, error) {
+	for _, c := range Capabilities {
+		if string(c) == name {
+			return c, nil
+		}
+	}
+	return "", fmt.Errorf("unknown capability %q (expected files, random or input)", name)
+}
+
+// Disable turns off caps, so that a program using one stops with an error
+// saying it is disabled in this environment
+func (i *Interpreter) Disable(caps ...Capability) {
+	if i.off == nil && len(caps) > 0 {
+		i.off = make(map[Capability]bool)
+	}
+	for _, c := range caps {
+		i.off[c] = true
+	}
+}
+
+// disabled returns the error for using c through name, or nil if c is on
+func (i *Interpreter) disabled(c Capability, name string) *Error {
+	if !i.off[c] {
+		return nil
+	}
+	switch c {
+	case FileHandling:
+		return NewError(diagnostic.FileHandlingDisabled)
+	case Randomness:
+		return NewError(diagnostic.RandomnessDisabled, name)
+	default:
+		return NewError(diagnostic.InputDisabled)
+	}
+}
+
+// needs returns the capability stmt uses, if it uses one
+func needs(stmt ast.Statement) Capability {
+	switch stmt.(type) {
+	case *ast.OpenFileStatement, *ast.ReadFileStatement, *ast.WriteFileStatement, *ast.CloseFileStatement:
+		return FileHandling
+	case *ast.InputStatement:
+		return Interaction
+	}
+	return ""
+}
@@ -0,0 +1,169 @@
+package interpreter
+
+import (
+	"sort"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+)
+
+// Debugger is told of each statement just before it runs, including those
+// that only run the statements inside them, such as IF and loops
+// Before is called on the goroutine running the program, which waits for
+// it to return, so a debugger pauses the program by not returning until
+// it should go on. Returning false stops the program with an error.
+type Debugger interface {
+	Before(i *Interpreter, stmt ast.Statement, env *Environment) bool
+}
+
+// SetDebugger has d told of each statement before it runs, or no one if d
+// is nil
+func (i *Interpreter) SetDebugger(d Debugger) {
+	i.debugger = d
+}
+
+// CallDepth returns how many routine calls are in progress, which is the
+// length of CallStack without building it
+func (i *Interpreter) CallDepth() int {
+	return len(i.calls)
+}
+
+// debug tells the debugger stmt is about to run in env, returning the
+// error stopping the program if the debugger says it should go no further
+func (i *Interpreter) debug(stmt ast.Statement, env *Environment) *Error {
+	if i.debugger.Before(i, stmt, env) {
+		return nil
+	}
+	line, column := ast.Position(stmt)
+	return &Error{Message: "stopped by the debugger", Line: line, Column: column}
+}
+
+// Evaluate returns the value of expr as a statement about to run in env
+// would see it, so that a debugger can look at a paused program
+// It is called by the debugger's Before, on the goroutine running the
+// program. The debugger is not told of the statements run by any routine
+// expr calls.
+func (i *Interpreter) Evaluate(expr ast.Expression, env *Environment) (Object, *Error) {
+	debugger := i.debugger
+	i.debugger = nil
+	defer func() { i.debugger = debugger }()
+	value := i.evalExpression(expr, env)
+	if err, ok := value.(*Error); ok {
+		return nil, err
+	}
+	return value, nil
+}
+
+// Assign runs stmt as if it were about to run in env, so that a debugger
+// can change the variables of a paused program, and returns the value
+// assigned
+// Unlike in a program, a variable must exist to be assigned to.
+func (i *Interpreter) Assign(stmt *ast.AssignmentStatement, env *Environment) (Object, *Error) {
+	if target, ok := stmt.Name.(*ast.Identifier); ok {
+		if _, found := env.Get(target.Value); !found {
+			return nil, NewError(diagnostic.UndefinedIdentifier, target.Value)
+		}
+	}
+	debugger := i.debugger
+	i.debugger = nil
+	defer func() { i.debugger = debugger }()
+	value := i.evalAssignmentStatement(stmt, env)
+	if err, ok := value.(*Error); ok {
+		return nil, err
+	}
+	return value, nil
+}
+
+// Variable is a variable a paused program can see
+type Variable struct {
+	Name  string
+	Value Object
+}
+
+// Scope is the variables a statement about to run can see, in order of
+// name
+type Scope struct {
+	Parameters []Variable // of the routine the statement is in
+	Locals     []Variable // declared in the routine, and FOR loop variables
+	Globals    []Variable
+}
+
+// Scope returns the variables a statement about to run in env, depth calls
+// deep, can see, leaving out routines and classes
+// A variable hidden by one of the same name further in is left out too.
+func (i *Interpreter) Scope(env *Environment, depth int) Scope {
+	var params map[string]bool
+	if depth > 0 && depth <= len(i.calls) {
+		params = map[string]bool{}
+		for _, p := range parameters(i.calls[depth-1].fn) {
+			params[p.Name] = true
+		}
+	}
+	var scope Scope
+	seen := map[string]bool{}
+	for e := env; e != nil && e != i.env; e = e.outer {
+		for _, v := range e.variables() {
+			if seen[v.Name] {
+				continue
+			}
+			seen[v.Name] = true
+			if params[v.Name] {
+				scope.Parameters = append(scope.Parameters, v)
+			} else {
+				scope.Locals = append(scope.Locals, v)
+			}
+		}
+		if e.call {
+			break
+		}
+	}
+	for _, v := range i.env.variables() {
+		if !seen[v.Name] {
+			scope.Globals = append(scope.Globals, v)
+		}
+	}
+	for _, vars := range [][]Variable{scope.Parameters, scope.Locals} {
+		sort.Slice(vars, func(a, b int) bool { return vars[a].Name < vars[b].Name })
+	}
+	return scope
+}
+
+// variables returns the variables bound in e itself, in order of name
+func (e *Environment) variables() []Variable {
+	var vars []Variable
+	for idx, name := range e.names {
+		if e.slots[idx] != nil {
+			vars = append(vars, Variable{name, e.slots[idx]})
+		}
+	}
+	for name, value := range e.store {
+		if e.slotIndex(name) < 0 && inspectable(value) {
+			vars = append(vars, Variable{name, value})
+		}
+	}
+	sort.Slice(vars, func(a, b int) bool { return vars[a].Name < vars[b].Name })
+	return vars
+}
+
+// inspectable reports whether value is data, rather than a routine or class
+// a program defined
+func inspectable(value Object) bool {
+	switch value.(type) {
+	case *Procedure, *Function, *Builtin, *BoundMethod, *Class, *Super:
+		return false
+	}
+	return true
+}
+
+// parameters returns the parameters of a routine on the call stack
+func parameters(fn Object) []ast.Parameter {
+	switch fn := fn.(type) {
+	case *Function:
+		return fn.Parameters
+	case *Procedure:
+		return fn.Parameters
+	case *BoundMethod:
+		return parameters(fn.Method)
+	}
+	return nil
+}
@@ -1,20 +1,27 @@
 package interpreter
 
+import "github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+
 // Environment stores variable bindings
+// The maps are only allocated once something is stored in them, since most
+// environments are routine calls or loops that only hold their slots.
 type Environment struct {
 	store     map[string]Object
 	constants map[string]bool
 	outer     *Environment
 	types     map[string]Object // For TYPE declarations
 	instance  *Instance         // For method execution context
+	call      bool              // For the frame of a routine call
+
+	// The parameters of a routine call or the variable of a FOR loop, in
+	// the slots the resolver assigns to them; a nil slot is not yet bound
+	names []string
+	slots []Object
 }
 
 // NewEnvironment creates a new environment
 func NewEnvironment() *Environment {
-	s := make(map[string]Object)
-	c := make(map[string]bool)
-	t := make(map[string]Object)
-	return &Environment{store: s, constants: c, outer: nil, types: t}
+	return &Environment{}
 }
 
 // NewEnclosedEnvironment creates a new environment with an outer scope
@@ -24,9 +31,73 @@ func NewEnclosedEnvironment(outer *Environment) *Environment {
 	return env
 }
 
+// newFrame creates an environment for a routine call or FOR loop whose
+// names are kept in slots, in the order given
+func newFrame(outer *Environment, names []string) *Environment {
+	return &Environment{outer: outer, names: names, slots: make([]Object, len(names))}
+}
+
+// slotIndex returns the slot holding name, or -1 if it is not a slot name
+func (e *Environment) slotIndex(name string) int {
+	for idx, n := range e.names {
+		if n == name {
+			return idx
+		}
+	}
+	return -1
+}
+
+// local retrieves a variable from this environment only
+func (e *Environment) local(name string) (Object, bool) {
+	if idx := e.slotIndex(name); idx >= 0 && e.slots[idx] != nil {
+		return e.slots[idx], true
+	}
+	obj, ok := e.store[name]
+	return obj, ok
+}
+
+// put stores a variable in this environment
+func (e *Environment) put(name string, val Object) {
+	if idx := e.slotIndex(name); idx >= 0 {
+		e.slots[idx] = val
+		return
+	}
+	if e.store == nil {
+		e.store = make(map[string]Object)
+	}
+	e.store[name] = val
+}
+
+// frame returns the environment depth frames out from e
+func (e *Environment) frame(depth int) *Environment {
+	for ; depth > 0 && e != nil; depth-- {
+		e = e.outer
+	}
+	return e
+}
+
+// slot retrieves a resolved variable, or nil if the slot is not bound
+func (e *Environment) slot(depth, index int) Object {
+	if f := e.frame(depth); f != nil && index < len(f.slots) {
+		return f.slots[index]
+	}
+	return nil
+}
+
+// setSlot updates a resolved variable, reporting false if the slot is not
+// bound and the name has to be looked up instead
+func (e *Environment) setSlot(depth, index int, val Object) bool {
+	f := e.frame(depth)
+	if f == nil || index >= len(f.slots) || f.slots[index] == nil {
+		return false
+	}
+	f.slots[index] = val
+	return true
+}
+
 // Get retrieves a variable from the environment
 func (e *Environment) Get(name string) (Object, bool) {
-	obj, ok := e.store[name]
+	obj, ok := e.local(name)
 	if !ok && e.instance != nil {
 		// Check instance fields
 		if val, found := e.instance.Fields[name]; found {
@@ -43,21 +114,24 @@ func (e *Environment) Get(name string) (Object, bool) {
 func (e *Environment) Set(name string, val Object) Object {
 	// Check if it's a constant
 	if e.isConstant(name) {
-		return &Error{Message: "cannot modify constant: " + name}
+		return NewError(diagnostic.ConstantModified, name)
 	}
-	e.store[name] = val
+	e.put(name, val)
 	return val
 }
 
 // Declare declares a new variable in the current scope
 func (e *Environment) Declare(name string, val Object) Object {
-	e.store[name] = val
+	e.put(name, val)
 	return val
 }
 
 // DeclareConstant declares a constant
 func (e *Environment) DeclareConstant(name string, val Object) Object {
-	e.store[name] = val
+	e.put(name, val)
+	if e.constants == nil {
+		e.constants = make(map[string]bool)
+	}
 	e.constants[name] = true
 	return val
 }
@@ -75,11 +149,11 @@ func (e *Environment) isConstant(name string) bool {
 
 // SetInPlace updates a variable in its original scope
 func (e *Environment) SetInPlace(name string, val Object) Object {
-	if _, ok := e.store[name]; ok {
+	if _, ok := e.local(name); ok {
 		if e.constants[name] {
-			return &Error{Message: "cannot modify constant: " + name}
+			return NewError(diagnostic.ConstantModified, name)
 		}
-		e.store[name] = val
+		e.put(name, val)
 		return val
 	}
 	// Check if it's an instance field
@@ -93,12 +167,15 @@ func (e *Environment) SetInPlace(name string, val Object) Object {
 		return e.outer.SetInPlace(name, val)
 	}
 	// Variable not found, create it in current scope
-	e.store[name] = val
+	e.put(name, val)
 	return val
 }
 
 // DefineType defines a type
 func (e *Environment) DefineType(name string, typ Object) {
+	if e.types == nil {
+		e.types = make(map[string]Object)
+	}
 	e.types[name] = typ
 }
 
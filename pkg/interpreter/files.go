@@ -0,0 +1,183 @@
+package interpreter
+
+import (
+	"errors"
+	"io"
+	"os"
+	"path/filepath"
+	"strings"
+	"sync"
+)
+
+// FileSystem opens the files a program names in OPENFILE
+// A host that must not let programs touch the disk, such as a playground or
+// a test, supplies its own in place of the default OSFiles.
+type FileSystem interface {
+	// Open opens name FOR READ
+	Open(name string) (io.ReadCloser, error)
+	// Create opens name FOR WRITE, emptying it first
+	Create(name string) (io.WriteCloser, error)
+	// Append opens name FOR APPEND, creating it if needed
+	Append(name string) (io.WriteCloser, error)
+}
+
+// OSFiles opens files on disk, relative to the working directory
+type OSFiles struct{}
+
+func (OSFiles) Open(name string) (io.ReadCloser, error) {
+	return os.Open(name)
+}
+
+func (OSFiles) Create(name string) (io.WriteCloser, error) {
+	return os.Create(name)
+}
+
+func (OSFiles) Append(name string) (io.WriteCloser, error) {
+	return os.OpenFile(name, os.O_APPEND|os.O_CREATE|os.O_WRONLY, 0644)
+}
+
+// ErrOutsideRoot is the error a SandboxFiles gives for a name it refuses
+var ErrOutsideRoot = errors.New("outside the folder programs may use")
+
+// SandboxFiles opens files on disk inside Root, for running programs that
+// must not reach the rest of the machine, such as untrusted submissions
+// Names are relative to Root. An absolute name, one whose .. would leave
+// Root, or one passing through a symbolic link to outside it is refused.
+type SandboxFiles struct {
+	Root string
+}
+
+func (s SandboxFiles) Open(name string) (io.ReadCloser, error) {
+	path, err := s.path("open", name)
+	if err != nil {
+		return nil, err
+	}
+	return os.Open(path)
+}
+
+func (s SandboxFiles) Create(name string) (io.WriteCloser, error) {
+	path, err := s.path("create", name)
+	if err != nil {
+		return nil, err
+	}
+	return os.Create(path)
+}
+
+func (s SandboxFiles) Append(name string) (io.WriteCloser, error) {
+	path, err := s.path("append", name)
+	if err != nil {
+		return nil, err
+	}
+	return os.OpenFile(path, os.O_APPEND|os.O_CREATE|os.O_WRONLY, 0644)
+}
+
+// path returns where name is on disk, or an error if it is outside Root
+func (s SandboxFiles) path(op, name string) (string, error) {
+	refused := &os.PathError{Op: op, Path: name, Err: ErrOutsideRoot}
+	if filepath.IsAbs(name) || !filepath.IsLocal(name) {
+		return "", refused
+	}
+	path := filepath.Join(s.Root, name)
+
+	// Links are followed as far as the file or, for one not yet created,
+	// its folder exists; a link to nothing is refused, as creating the
+	// file would follow it wherever it points
+	root, err := filepath.EvalSymlinks(s.Root)
+	if err != nil {
+		return "", err
+	}
+	real, err := filepath.EvalSymlinks(path)
+	if err != nil {
+		if info, err := os.Lstat(path); err == nil && info.Mode()&os.ModeSymlink != 0 {
+			return "", refused
+		}
+		if real, err = filepath.EvalSymlinks(filepath.Dir(path)); err != nil {
+			return path, nil // opening it will fail
+		}
+	}
+	if rel, err := filepath.Rel(root, real); err != nil || !filepath.IsLocal(rel) && rel != "." {
+		return "", refused
+	}
+	return path, nil
+}
+
+// MemoryFiles keeps files as strings in memory
+// It is safe for concurrent use, though programs sharing one see each
+// other's writes.
+type MemoryFiles struct {
+	mu    sync.Mutex
+	files map[string]*strings.Builder
+}
+
+// NewMemoryFiles creates a file system holding files, keyed by name
+func NewMemoryFiles(files map[string]string) *MemoryFiles {
+	m := &MemoryFiles{files: make(map[string]*strings.Builder)}
+	for name, contents := range files {
+		m.files[name] = &strings.Builder{}
+		m.files[name].WriteString(contents)
+	}
+	return m
+}
+
+// Contents returns what name holds now
+func (m *MemoryFiles) Contents(name string) (string, bool) {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	file, ok := m.files[name]
+	if !ok {
+		return "", false
+	}
+	return file.String(), true
+}
+
+// Files returns every file and what it holds now, keyed by name
+func (m *MemoryFiles) Files() map[string]string {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	files := make(map[string]string, len(m.files))
+	for name, file := range m.files {
+		files[name] = file.String()
+	}
+	return files
+}
+
+// Open reads what name held when it was opened
+func (m *MemoryFiles) Open(name string) (io.ReadCloser, error) {
+	contents, ok := m.Contents(name)
+	if !ok {
+		return nil, &os.PathError{Op: "open", Path: name, Err: os.ErrNotExist}
+	}
+	return io.NopCloser(strings.NewReader(contents)), nil
+}
+
+func (m *MemoryFiles) Create(name string) (io.WriteCloser, error) {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	m.files[name] = &strings.Builder{}
+	return memoryWriter{m, name}, nil
+}
+
+func (m *MemoryFiles) Append(name string) (io.WriteCloser, error) {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	if _, ok := m.files[name]; !ok {
+		m.files[name] = &strings.Builder{}
+	}
+	return memoryWriter{m, name}, nil
+}
+
+// memoryWriter adds to a file in a MemoryFiles as it is written
+type memoryWriter struct {
+	files *MemoryFiles
+	name  string
+}
+
+func (w memoryWriter) Write(p []byte) (int, error) {
+	w.files.mu.Lock()
+	defer w.files.mu.Unlock()
+	return w.files.files[w.name].Write(p)
+}
+
+func (w memoryWriter) Close() error {
+	return nil
+}
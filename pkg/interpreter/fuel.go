@@ -0,0 +1,87 @@
+package interpreter
+
+import (
+	"iter"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+)
+
+// Execution is a program running in instalments of fuel, for hosts such as
+// a web service or the language server that must get control back from
+// programs that run for a long time, or forever
+// Each statement costs one unit, as does each run of a block of statements,
+// such as one iteration of a loop, so that even an empty loop runs out.
+// Between instalments the program is suspended where it was, on the
+// host's own goroutine rather than one of its own.
+type Execution struct {
+	next   func() (struct{}, bool)
+	stop   func()
+	meter  *meter
+	result Object
+	done   bool
+}
+
+// meter holds the fuel an execution has left
+type meter struct {
+	fuel    int
+	pause   func() bool // waits for more fuel, false if the run was stopped
+	stopped bool
+}
+
+// Start prepares program to run in instalments, from the start with the
+// interpreter's current variables; nothing runs until Continue
+func (i *Interpreter) Start(program *ast.Program) *Execution {
+	e := &Execution{meter: &meter{}}
+	e.next, e.stop = iter.Pull(func(yield func(struct{}) bool) {
+		e.meter.pause = func() bool { return yield(struct{}{}) }
+		i.meter = e.meter
+		e.result = i.Eval(program)
+		i.meter = nil
+		e.done = true
+	})
+	return e
+}
+
+// Continue runs the program until it has used fuel more units or has
+// finished, and reports whether it has finished
+// A program paused between top-level statements can be snapshotted.
+func (e *Execution) Continue(fuel int) bool {
+	if e.done {
+		return true
+	}
+	e.meter.fuel = fuel
+	e.next()
+	return e.done
+}
+
+// Done reports whether the program has finished
+func (e *Execution) Done() bool {
+	return e.done
+}
+
+// Result returns what Eval would have, once the program has finished
+func (e *Execution) Result() Object {
+	return e.result
+}
+
+// Stop abandons a program that has not finished, so it holds on to nothing
+// Afterwards it counts as finished, with no result.
+func (e *Execution) Stop() {
+	e.stop()
+	e.result = nil
+	e.done = true
+}
+
+// burn takes one unit of fuel, waiting for more if there is none, and
+// reports whether the program should go on
+func (i *Interpreter) burn() bool {
+	m := i.meter
+	for m.fuel <= 0 {
+		if m.stopped || !m.pause() {
+			m.stopped = true
+			return false
+		}
+	}
+	m.fuel--
+	return true
+}
@@ -4,25 +4,85 @@ import (
 	"bufio"
 	"fmt"
 	"io"
+	"math/big"
 	"os"
 	"strings"
+	"time"
 
 	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/logging"
 )
 
+var logger = logging.For("interpreter")
+
+// DefaultMaxDepth is how deeply routine calls may nest before the program
+// is stopped with an error
+// The interpreter recurses on the Go stack, so the default is kept to what
+// fits in the 1GB a goroutine may grow to unless the host raises it: a host
+// that never calls debug.SetMaxStack still ends a runaway recursion with
+// the error rather than Go's fatal stack overflow.
+const DefaultMaxDepth = 50000
+
+// maxReportedCalls is how many of the innermost calls a recursion error
+// lists
+const maxReportedCalls = 5
+
+// hostStackPerCall is a generous estimate of the Go stack one routine call
+// uses, counting the evaluation of the expressions around it
+const hostStackPerCall = 16 << 10
+
 // Interpreter evaluates the AST
 type Interpreter struct {
 	env      *Environment
 	builtins map[string]*Builtin
 	files    map[string]*fileState
-	input    io.Reader
+	fs       FileSystem
+	input    *bufio.Reader
 	output   io.Writer
+
+	calls     []activeCall
+	maxDepth  int
+	maxSize   int // characters in a STRING or elements in an ARRAY, no limit if 0
+	tailCalls bool
+	arith     Arithmetic
+	stepper   *stepper // while running under Steps
+	meter     *meter   // while running under Start
+	debugger  Debugger // told of each statement before it runs
+	log       Transcript
+	off       map[Capability]bool
+
+	next  int // the top-level statement to run next
+	depth int // statements running, counting those they are inside
+}
+
+// Call is an active routine call
+type Call struct {
+	Kind   string // PROCEDURE or FUNCTION
+	Name   string
+	Line   int // where the call was made
+	Column int
+}
+
+// activeCall is an entry on the call stack; its position is only worked out
+// when it is reported
+type activeCall struct {
+	kind, name string
+	site       ast.Node
+	fn         Object
+}
+
+func (c activeCall) call() Call {
+	line, column := ast.Position(c.site)
+	return Call{Kind: c.kind, Name: c.name, Line: line, Column: column}
 }
 
 type fileState struct {
-	file    *os.File
+	file    io.Closer
+	writer  io.Writer // for WRITE and APPEND
 	mode    string
 	scanner *bufio.Scanner
+	lines   int // read so far
 	atEOF   bool
 }
 
@@ -32,8 +92,10 @@ func New() *Interpreter {
 		env:      NewEnvironment(),
 		builtins: make(map[string]*Builtin),
 		files:    make(map[string]*fileState),
-		input:    os.Stdin,
+		fs:       OSFiles{},
+		input:    bufio.NewReader(os.Stdin),
 		output:   os.Stdout,
+		maxDepth: DefaultMaxDepth,
 	}
 }
 
@@ -43,8 +105,10 @@ func (i *Interpreter) SetBuiltins(builtins map[string]*Builtin) {
 }
 
 // SetInput sets the input reader
+// It is buffered once here so that successive INPUT statements share the
+// same buffer rather than each discarding what the last one read ahead.
 func (i *Interpreter) SetInput(r io.Reader) {
-	i.input = r
+	i.input = bufio.NewReader(r)
 }
 
 // SetOutput sets the output writer
@@ -52,25 +116,188 @@ func (i *Interpreter) SetOutput(w io.Writer) {
 	i.output = w
 }
 
+// Transcript is told of each line a program reads with INPUT and writes
+// with OUTPUT, in the order it does so
+type Transcript interface {
+	Input(line string)
+	Output(line string)
+}
+
+// SetTranscript has t told of the program's input and output, or no one if
+// t is nil
+func (i *Interpreter) SetTranscript(t Transcript) {
+	i.log = t
+}
+
+// SetFiles sets where OPENFILE finds files, OSFiles unless changed
+func (i *Interpreter) SetFiles(fs FileSystem) {
+	i.fs = fs
+}
+
+// SetMaxDepth sets how deeply routine calls may nest, DefaultMaxDepth
+// unless changed; see HostStack for the Go stack that needs
+func (i *Interpreter) SetMaxDepth(depth int) {
+	i.maxDepth = depth
+}
+
+// SetMaxSize limits how many characters a STRING, or elements an ARRAY,
+// may hold, so a program cannot use up a host's memory; a program making
+// a larger one stops with an error
+// There is no limit unless one is set.
+func (i *Interpreter) SetMaxSize(size int) {
+	i.maxSize = size
+}
+
+// SetTailCalls makes a routine whose last action is to call itself reuse
+// its frame rather than nest a new call, so the recursion never reaches
+// the depth limit
+// It is off by default because the reused calls no longer appear on the
+// call stack, which a trace of the program would otherwise show.
+// Only calls that Resolve marked are affected.
+func (i *Interpreter) SetTailCalls(enabled bool) {
+	i.tailCalls = enabled
+}
+
+// SetArithmetic selects arbitrary-precision INTEGER or decimal REAL
+// arithmetic in place of the 64-bit and floating-point defaults
+// Decimal results are rounded to 30 places after the point, and those
+// that needed rounding, such as 1 / 3, are shown to the precision of a
+// float64.
+func (i *Interpreter) SetArithmetic(a Arithmetic) {
+	i.arith = a
+}
+
 // Eval evaluates a program
 func (i *Interpreter) Eval(program *ast.Program) Object {
+	i.next = 0
+	return i.Resume(program)
+}
+
+// Resume evaluates program from where the interpreter stopped, which after
+// Restore is where the snapshot was taken
+func (i *Interpreter) Resume(program *ast.Program) Object {
 	var result Object
 
-	for _, stmt := range program.Statements {
-		result = i.evalStatement(stmt, i.env)
+	start := time.Now()
+	logger.Debug("run started", "statements", len(program.Statements)-i.next)
+
+	for i.next < len(program.Statements) {
+		result = i.evalStatement(program.Statements[i.next], i.env)
 
 		switch result := result.(type) {
 		case *ReturnValue:
 			return result.Value
 		case *Error:
+			logger.Debug("runtime error", "code", result.Code, "line", result.Line, "column", result.Column, "message", result.Message)
 			return result
 		}
 	}
 
+	logger.Debug("run finished", "duration", time.Since(start))
 	return result
 }
 
+// HostStack returns how many bytes of Go stack depth nested calls may need
+// Evaluation recurses on the goroutine's stack, which grows as needed up to
+// a process-wide limit; exceeding that limit aborts the whole process. A
+// host allowing deeper recursion than DefaultMaxDepth should raise the limit to this with
+// debug.SetMaxStack, so that the depth limit ends a runaway recursion with
+// an ordinary runtime error instead. The interpreter leaves that to the
+// host, as the setting is shared by everything the process runs.
+func HostStack(depth int) int {
+	return depth * hostStackPerCall
+}
+
+// Global returns the value of a top-level variable or constant, for hosts
+// that read a program's results once it has run
+func (i *Interpreter) Global(name string) (Object, bool) {
+	return i.env.local(name)
+}
+
+// Globals returns the environment of the top-level variables, so that a
+// host can Evaluate an expression among them once the program has run
+func (i *Interpreter) Globals() *Environment {
+	return i.env
+}
+
+// CallStack returns the routine calls in progress, outermost first
+func (i *Interpreter) CallStack() []Call {
+	calls := make([]Call, len(i.calls))
+	for idx, c := range i.calls {
+		calls[idx] = c.call()
+	}
+	return calls
+}
+
+// enter records a call to a routine, failing if it would nest too deeply
+func (i *Interpreter) enter(kind, name string, site ast.Node, fn Object) *Error {
+	if len(i.calls) >= i.maxDepth {
+		call := activeCall{kind: kind, name: name, site: site}.call()
+		err := NewError(diagnostic.RecursionLimit, i.maxDepth, call.Kind, call.Name, call.Line)
+		for idx := len(i.calls) - 1; idx >= 0 && len(err.Calls) < maxReportedCalls; idx-- {
+			err.Calls = append(err.Calls, i.calls[idx].call())
+		}
+		return err
+	}
+	i.calls = append(i.calls, activeCall{kind: kind, name: name, site: site, fn: fn})
+	return nil
+}
+
+// leave removes the innermost call
+func (i *Interpreter) leave() {
+	i.calls = i.calls[:len(i.calls)-1]
+}
+
+// routine returns the kind and name of a routine that is called with a new
+// frame, or false for builtins and values that cannot be called
+func routine(fn Object) (string, string, bool) {
+	switch fn := fn.(type) {
+	case *Function:
+		return "FUNCTION", fn.Name, true
+	case *Procedure:
+		return "PROCEDURE", fn.Name, true
+	case *BoundMethod:
+		kind, name, ok := routine(fn.Method)
+		return kind, fn.Instance.Class.Name + "." + name, ok
+	}
+	return "", "", false
+}
+
 func (i *Interpreter) evalStatement(stmt ast.Statement, env *Environment) Object {
+	if i.meter != nil && !i.burn() {
+		line, column := ast.Position(stmt)
+		return &Error{Message: "stopped by the host", Line: line, Column: column}
+	}
+	if i.debugger != nil {
+		if err := i.debug(stmt, env); err != nil {
+			return err
+		}
+	}
+	i.depth++
+	result := i.execStatement(stmt, env)
+	i.depth--
+	if i.depth == 0 {
+		i.next++
+	}
+
+	// Errors are tagged with the innermost statement that raised them
+	if err, ok := result.(*Error); ok && err.Line == 0 {
+		err.Line, err.Column = ast.Position(stmt)
+	}
+	if i.stepper != nil && !i.step(stmt, env, result) && !isError(result) {
+		// Unwind the whole program, which nothing is following any more
+		line, column := ast.Position(stmt)
+		return &Error{Message: "stopped between steps", Line: line, Column: column}
+	}
+	return result
+}
+
+func (i *Interpreter) execStatement(stmt ast.Statement, env *Environment) Object {
+	if i.off != nil {
+		if err := i.disabled(needs(stmt), ""); err != nil {
+			return err
+		}
+	}
 	switch stmt := stmt.(type) {
 	case *ast.DeclareStatement:
 		return i.evalDeclareStatement(stmt, env)
@@ -108,6 +335,10 @@ func (i *Interpreter) evalStatement(stmt ast.Statement, env *Environment) Object
 		return i.evalReadFileStatement(stmt, env)
 	case *ast.WriteFileStatement:
 		return i.evalWriteFileStatement(stmt, env)
+	case *ast.IncludeStatement:
+		// Loading the program with package include puts the file's
+		// statements in its place
+		return NewError(diagnostic.IncludeNotLoaded, stmt.Path)
 	case *ast.TypeStatement:
 		return i.evalTypeStatement(stmt, env)
 	case *ast.ClassStatement:
@@ -124,28 +355,12 @@ func (i *Interpreter) evalDeclareStatement(stmt *ast.DeclareStatement, env *Envi
 
 	switch dt := stmt.DataType.(type) {
 	case *ast.PrimitiveType:
-		switch dt.Name {
-		case "INTEGER":
-			value = &Integer{Value: 0}
-		case "REAL":
-			value = &Real{Value: 0.0}
-		case "STRING":
-			value = &String{Value: ""}
-		case "CHAR":
-			value = &Char{Value: ' '}
-		case "BOOLEAN":
-			value = &Boolean{Value: false}
-		case "DATE":
-			value = &Date{Day: 1, Month: 1, Year: 1970}
-		default:
-			value = &Null{}
-		}
+		value = DefaultValue(dt.Name)
 	case *ast.ArrayType:
-		arr := &Array{
-			Elements:   make(map[string]Object),
-			Dimensions: dt.Dimensions,
+		value = i.limit(NewArray(dt.Dimensions, ElementDefault(dt.ElementType)))
+		if isError(value) {
+			return value
 		}
-		value = arr
 	case *ast.CustomType:
 		// Check if it's a defined type
 		if typ, ok := env.GetType(dt.Name); ok {
@@ -189,16 +404,24 @@ func (i *Interpreter) evalAssignmentStatement(stmt *ast.AssignmentStatement, env
 
 	switch target := stmt.Name.(type) {
 	case *ast.Identifier:
-		return env.SetInPlace(target.Value, value)
+		return i.assign(target, value, env)
 	case *ast.ArrayAccess:
 		return i.evalArrayAssignment(target, value, env)
 	case *ast.MemberAccess:
 		return i.evalMemberAssignment(target, value, env)
 	default:
-		return &Error{Message: "invalid assignment target"}
+		return NewError(diagnostic.InvalidAssignmentTarget)
 	}
 }
 
+// assign stores value in the variable target names
+func (i *Interpreter) assign(target *ast.Identifier, value Object, env *Environment) Object {
+	if target.Resolved && env.setSlot(target.Depth, target.Slot, value) {
+		return value
+	}
+	return env.SetInPlace(target.Value, value)
+}
+
 func (i *Interpreter) evalArrayAssignment(access *ast.ArrayAccess, value Object, env *Environment) Object {
 	arr := i.evalExpression(access.Array, env)
 	if isError(arr) {
@@ -207,7 +430,7 @@ func (i *Interpreter) evalArrayAssignment(access *ast.ArrayAccess, value Object,
 
 	array, ok := arr.(*Array)
 	if !ok {
-		return &Error{Message: "not an array"}
+		return NewError(diagnostic.NotAnArray)
 	}
 
 	indices := []int64{}
@@ -218,13 +441,15 @@ func (i *Interpreter) evalArrayAssignment(access *ast.ArrayAccess, value Object,
 		}
 		intVal, ok := idxVal.(*Integer)
 		if !ok {
-			return &Error{Message: "array index must be an integer"}
+			return NewError(diagnostic.ArrayIndexNotInteger)
 		}
 		indices = append(indices, intVal.Value)
 	}
 
-	key := array.GetIndex(indices...)
-	array.Elements[key] = value
+	array.Set(value, indices...)
+	if err := i.limit(array); isError(err) {
+		return err
+	}
 	return value
 }
 
@@ -242,7 +467,7 @@ func (i *Interpreter) evalMemberAssignment(access *ast.MemberAccess, value Objec
 		o.Fields[access.Member] = value
 		return value
 	default:
-		return &Error{Message: "cannot access member of non-record/instance"}
+		return NewError(diagnostic.NoMembers)
 	}
 }
 
@@ -252,7 +477,7 @@ func (i *Interpreter) evalIfStatement(stmt *ast.IfStatement, env *Environment) O
 		return condition
 	}
 
-	if isTruthy(condition) {
+	if IsTruthy(condition) {
 		return i.evalStatements(stmt.Consequence, env)
 	} else if stmt.Alternative != nil {
 		return i.evalStatements(stmt.Alternative, env)
@@ -287,57 +512,13 @@ func (i *Interpreter) matchesCaseValue(value Object, caseValue ast.Expression, e
 	case *ast.RangeExpression:
 		start := i.evalExpression(cv.Start, env)
 		end := i.evalExpression(cv.End, env)
-		return i.valueInRange(value, start, end)
+		return ValueInRange(value, start, end)
 	default:
 		evalValue := i.evalExpression(caseValue, env)
-		return i.objectsEqual(value, evalValue)
+		return ObjectsEqual(value, evalValue)
 	}
 }
 
-func (i *Interpreter) valueInRange(value, start, end Object) bool {
-	switch v := value.(type) {
-	case *Integer:
-		s, sok := start.(*Integer)
-		e, eok := end.(*Integer)
-		if sok && eok {
-			return v.Value >= s.Value && v.Value <= e.Value
-		}
-	case *Char:
-		s, sok := start.(*Char)
-		e, eok := end.(*Char)
-		if sok && eok {
-			return v.Value >= s.Value && v.Value <= e.Value
-		}
-	}
-	return false
-}
-
-func (i *Interpreter) objectsEqual(a, b Object) bool {
-	switch av := a.(type) {
-	case *Integer:
-		if bv, ok := b.(*Integer); ok {
-			return av.Value == bv.Value
-		}
-	case *Real:
-		if bv, ok := b.(*Real); ok {
-			return av.Value == bv.Value
-		}
-	case *String:
-		if bv, ok := b.(*String); ok {
-			return av.Value == bv.Value
-		}
-	case *Char:
-		if bv, ok := b.(*Char); ok {
-			return av.Value == bv.Value
-		}
-	case *Boolean:
-		if bv, ok := b.(*Boolean); ok {
-			return av.Value == bv.Value
-		}
-	}
-	return false
-}
-
 func (i *Interpreter) evalForStatement(stmt *ast.ForStatement, env *Environment) Object {
 	start := i.evalExpression(stmt.Start, env)
 	if isError(start) {
@@ -362,16 +543,17 @@ func (i *Interpreter) evalForStatement(stmt *ast.ForStatement, env *Environment)
 
 	startInt, ok := start.(*Integer)
 	if !ok {
-		return &Error{Message: "FOR loop start must be an integer"}
+		return NewError(diagnostic.ForStartNotInteger)
 	}
 
 	endInt, ok := end.(*Integer)
 	if !ok {
-		return &Error{Message: "FOR loop end must be an integer"}
+		return NewError(diagnostic.ForEndNotInteger)
 	}
 
-	loopEnv := NewEnclosedEnvironment(env)
-	loopEnv.Declare(stmt.Variable.Value, startInt)
+	// The loop variable is the frame's only slot
+	loopEnv := newFrame(env, []string{stmt.Variable.Value})
+	loopEnv.slots[0] = startInt
 
 	var result Object
 	for current := startInt.Value; ; {
@@ -383,7 +565,7 @@ func (i *Interpreter) evalForStatement(stmt *ast.ForStatement, env *Environment)
 			break
 		}
 
-		loopEnv.SetInPlace(stmt.Variable.Value, &Integer{Value: current})
+		loopEnv.slots[0] = NewInteger(current)
 		result = i.evalStatements(stmt.Body, loopEnv)
 
 		if isError(result) {
@@ -408,7 +590,7 @@ func (i *Interpreter) evalWhileStatement(stmt *ast.WhileStatement, env *Environm
 			return condition
 		}
 
-		if !isTruthy(condition) {
+		if !IsTruthy(condition) {
 			break
 		}
 
@@ -441,7 +623,7 @@ func (i *Interpreter) evalRepeatStatement(stmt *ast.RepeatStatement, env *Enviro
 			return condition
 		}
 
-		if isTruthy(condition) {
+		if IsTruthy(condition) {
 			break
 		}
 	}
@@ -476,6 +658,7 @@ func (i *Interpreter) evalCallStatement(stmt *ast.CallStatement, env *Environmen
 		Token:     stmt.Token,
 		Function:  stmt.Name,
 		Arguments: stmt.Arguments,
+		Tail:      stmt.Tail,
 	}
 	return i.evalCallExpression(call, env)
 }
@@ -494,17 +677,22 @@ func (i *Interpreter) evalReturnStatement(stmt *ast.ReturnStatement, env *Enviro
 }
 
 func (i *Interpreter) evalInputStatement(stmt *ast.InputStatement, env *Environment) Object {
-	reader := bufio.NewReader(i.input)
-	line, err := reader.ReadString('\n')
+	line, err := i.input.ReadString('\n')
 	if err != nil && err != io.EOF {
-		return &Error{Message: fmt.Sprintf("input error: %v", err)}
+		return NewError(diagnostic.InputFailed, err)
 	}
 
 	line = strings.TrimRight(line, "\r\n")
+	if i.stepper != nil {
+		i.stepper.input = append(i.stepper.input, line)
+	}
+	if i.log != nil {
+		i.log.Input(line)
+	}
 
 	switch target := stmt.Variable.(type) {
 	case *ast.Identifier:
-		env.SetInPlace(target.Value, &String{Value: line})
+		i.assign(target, &String{Value: line}, env)
 	case *ast.ArrayAccess:
 		return i.evalArrayAssignment(target, &String{Value: line}, env)
 	}
@@ -523,7 +711,14 @@ func (i *Interpreter) evalOutputStatement(stmt *ast.OutputStatement, env *Enviro
 		parts = append(parts, value.Inspect())
 	}
 
-	fmt.Fprintln(i.output, strings.Join(parts, ""))
+	line := strings.Join(parts, "")
+	fmt.Fprintln(i.output, line)
+	if i.stepper != nil {
+		i.stepper.output.WriteString(line + "\n")
+	}
+	if i.log != nil {
+		i.log.Output(line)
+	}
 	return &Null{}
 }
 
@@ -535,34 +730,37 @@ func (i *Interpreter) evalOpenFileStatement(stmt *ast.OpenFileStatement, env *En
 
 	filenameStr, ok := filename.(*String)
 	if !ok {
-		return &Error{Message: "filename must be a string"}
+		return NewError(diagnostic.FilenameNotString)
 	}
 
-	var file *os.File
+	fs := &fileState{mode: stmt.Mode}
 	var err error
 
 	switch stmt.Mode {
 	case "READ":
-		file, err = os.Open(filenameStr.Value)
-	case "WRITE":
-		file, err = os.Create(filenameStr.Value)
-	case "APPEND":
-		file, err = os.OpenFile(filenameStr.Value, os.O_APPEND|os.O_CREATE|os.O_WRONLY, 0644)
+		var r io.ReadCloser
+		r, err = i.fs.Open(filenameStr.Value)
+		if err == nil {
+			fs.file, fs.scanner = r, bufio.NewScanner(r)
+		}
+	case "WRITE", "APPEND":
+		var w io.WriteCloser
+		if stmt.Mode == "WRITE" {
+			w, err = i.fs.Create(filenameStr.Value)
+		} else {
+			w, err = i.fs.Append(filenameStr.Value)
+		}
+		if err == nil {
+			fs.file, fs.writer = w, w
+		}
 	}
 
 	if err != nil {
-		return &Error{Message: fmt.Sprintf("cannot open file: %v", err)}
-	}
-
-	fs := &fileState{
-		file: file,
-		mode: stmt.Mode,
-	}
-	if stmt.Mode == "READ" {
-		fs.scanner = bufio.NewScanner(file)
+		return NewError(diagnostic.FileOpenFailed, err)
 	}
 
 	i.files[filenameStr.Value] = fs
+	logger.Debug("file opened", "file", filenameStr.Value, "mode", stmt.Mode)
 	return &Null{}
 }
 
@@ -574,12 +772,12 @@ func (i *Interpreter) evalCloseFileStatement(stmt *ast.CloseFileStatement, env *
 
 	filenameStr, ok := filename.(*String)
 	if !ok {
-		return &Error{Message: "filename must be a string"}
+		return NewError(diagnostic.FilenameNotString)
 	}
 
 	fs, ok := i.files[filenameStr.Value]
 	if !ok {
-		return &Error{Message: "file not open"}
+		return NewError(diagnostic.FileNotOpen)
 	}
 
 	fs.file.Close()
@@ -595,23 +793,24 @@ func (i *Interpreter) evalReadFileStatement(stmt *ast.ReadFileStatement, env *En
 
 	filenameStr, ok := filename.(*String)
 	if !ok {
-		return &Error{Message: "filename must be a string"}
+		return NewError(diagnostic.FilenameNotString)
 	}
 
 	fs, ok := i.files[filenameStr.Value]
 	if !ok {
-		return &Error{Message: "file not open"}
+		return NewError(diagnostic.FileNotOpen)
 	}
 
 	if fs.mode != "READ" {
-		return &Error{Message: "file not open for reading"}
+		return NewError(diagnostic.FileNotReadable)
 	}
 
 	if fs.scanner.Scan() {
+		fs.lines++
 		line := fs.scanner.Text()
 		switch target := stmt.Variable.(type) {
 		case *ast.Identifier:
-			env.SetInPlace(target.Value, &String{Value: line})
+			i.assign(target, &String{Value: line}, env)
 		case *ast.ArrayAccess:
 			return i.evalArrayAssignment(target, &String{Value: line}, env)
 		}
@@ -630,16 +829,16 @@ func (i *Interpreter) evalWriteFileStatement(stmt *ast.WriteFileStatement, env *
 
 	filenameStr, ok := filename.(*String)
 	if !ok {
-		return &Error{Message: "filename must be a string"}
+		return NewError(diagnostic.FilenameNotString)
 	}
 
 	fs, ok := i.files[filenameStr.Value]
 	if !ok {
-		return &Error{Message: "file not open"}
+		return NewError(diagnostic.FileNotOpen)
 	}
 
 	if fs.mode != "WRITE" && fs.mode != "APPEND" {
-		return &Error{Message: "file not open for writing"}
+		return NewError(diagnostic.FileNotWritable)
 	}
 
 	data := i.evalExpression(stmt.Data, env)
@@ -647,9 +846,9 @@ func (i *Interpreter) evalWriteFileStatement(stmt *ast.WriteFileStatement, env *
 		return data
 	}
 
-	_, err := fmt.Fprintln(fs.file, data.Inspect())
+	_, err := fmt.Fprintln(fs.writer, data.Inspect())
 	if err != nil {
-		return &Error{Message: fmt.Sprintf("write error: %v", err)}
+		return NewError(diagnostic.WriteFailed, err)
 	}
 
 	return &Null{}
@@ -720,6 +919,9 @@ func (i *Interpreter) evalClassStatement(stmt *ast.ClassStatement, env *Environm
 }
 
 func (i *Interpreter) evalStatements(stmts []ast.Statement, env *Environment) Object {
+	if i.meter != nil && !i.burn() {
+		return &Error{Message: "stopped by the host"}
+	}
 	var result Object
 
 	for _, stmt := range stmts {
@@ -738,8 +940,16 @@ func (i *Interpreter) evalStatements(stmts []ast.Statement, env *Environment) Ob
 func (i *Interpreter) evalExpression(expr ast.Expression, env *Environment) Object {
 	switch expr := expr.(type) {
 	case *ast.IntegerLiteral:
-		return &Integer{Value: expr.Value}
+		if expr.Big != nil {
+			return i.arith.Fit(NewBigInteger(expr.Big))
+		}
+		return NewInteger(expr.Value)
 	case *ast.RealLiteral:
+		if i.arith.DecimalReals {
+			if d, ok := new(big.Rat).SetString(expr.Token.Literal); ok {
+				return NewDecimal(d)
+			}
+		}
 		return &Real{Value: expr.Value}
 	case *ast.StringLiteral:
 		return &String{Value: expr.Value}
@@ -749,7 +959,7 @@ func (i *Interpreter) evalExpression(expr ast.Expression, env *Environment) Obje
 		}
 		return &Char{Value: ' '}
 	case *ast.BooleanLiteral:
-		return &Boolean{Value: expr.Value}
+		return NewBoolean(expr.Value)
 	case *ast.Identifier:
 		return i.evalIdentifier(expr, env)
 	case *ast.PrefixExpression:
@@ -772,6 +982,12 @@ func (i *Interpreter) evalExpression(expr ast.Expression, env *Environment) Obje
 }
 
 func (i *Interpreter) evalIdentifier(node *ast.Identifier, env *Environment) Object {
+	if node.Resolved {
+		if val := env.slot(node.Depth, node.Slot); val != nil {
+			return val
+		}
+	}
+
 	if val, ok := env.Get(node.Value); ok {
 		return val
 	}
@@ -780,7 +996,7 @@ func (i *Interpreter) evalIdentifier(node *ast.Identifier, env *Environment) Obj
 		return builtin
 	}
 
-	return &Error{Message: fmt.Sprintf("identifier not found: %s", node.Value)}
+	return NewError(diagnostic.UndefinedIdentifier, node.Value)
 }
 
 func (i *Interpreter) evalPrefixExpression(expr *ast.PrefixExpression, env *Environment) Object {
@@ -789,34 +1005,7 @@ func (i *Interpreter) evalPrefixExpression(expr *ast.PrefixExpression, env *Envi
 		return right
 	}
 
-	switch expr.Operator {
-	case "-":
-		return i.evalMinusPrefixOperator(right)
-	case "NOT":
-		return i.evalNotOperator(right)
-	default:
-		return &Error{Message: fmt.Sprintf("unknown operator: %s", expr.Operator)}
-	}
-}
-
-func (i *Interpreter) evalMinusPrefixOperator(right Object) Object {
-	switch obj := right.(type) {
-	case *Integer:
-		return &Integer{Value: -obj.Value}
-	case *Real:
-		return &Real{Value: -obj.Value}
-	default:
-		return &Error{Message: fmt.Sprintf("unknown operator: -%s", right.Type())}
-	}
-}
-
-func (i *Interpreter) evalNotOperator(right Object) Object {
-	switch obj := right.(type) {
-	case *Boolean:
-		return &Boolean{Value: !obj.Value}
-	default:
-		return &Error{Message: fmt.Sprintf("unknown operator: NOT %s", right.Type())}
-	}
+	return i.arith.Unary(expr.Operator, right)
 }
 
 func (i *Interpreter) evalInfixExpression(expr *ast.InfixExpression, env *Environment) Object {
@@ -830,182 +1019,25 @@ func (i *Interpreter) evalInfixExpression(expr *ast.InfixExpression, env *Enviro
 		return right
 	}
 
-	switch {
-	case left.Type() == INTEGER_OBJ && right.Type() == INTEGER_OBJ:
-		return i.evalIntegerInfixExpression(expr.Operator, left, right)
-	case left.Type() == REAL_OBJ || right.Type() == REAL_OBJ:
-		return i.evalRealInfixExpression(expr.Operator, left, right)
-	case left.Type() == STRING_OBJ && right.Type() == STRING_OBJ:
-		return i.evalStringInfixExpression(expr.Operator, left, right)
-	case left.Type() == BOOLEAN_OBJ && right.Type() == BOOLEAN_OBJ:
-		return i.evalBooleanInfixExpression(expr.Operator, left, right)
-	case expr.Operator == "&":
-		// String concatenation - convert operands to strings
-		return i.evalConcatenation(left, right)
-	case expr.Operator == "=":
-		return &Boolean{Value: i.objectsEqual(left, right)}
-	case expr.Operator == "<>":
-		return &Boolean{Value: !i.objectsEqual(left, right)}
-	default:
-		return &Error{Message: fmt.Sprintf("type mismatch: %s %s %s", left.Type(), expr.Operator, right.Type())}
-	}
+	return i.limit(i.arith.Binary(expr.Operator, left, right))
 }
 
-func (i *Interpreter) evalIntegerInfixExpression(op string, left, right Object) Object {
-	leftVal := left.(*Integer).Value
-	rightVal := right.(*Integer).Value
-
-	switch op {
-	case "+":
-		return &Integer{Value: leftVal + rightVal}
-	case "-":
-		return &Integer{Value: leftVal - rightVal}
-	case "*":
-		return &Integer{Value: leftVal * rightVal}
-	case "/":
-		if rightVal == 0 {
-			return &Error{Message: "division by zero"}
-		}
-		return &Real{Value: float64(leftVal) / float64(rightVal)}
-	case "DIV":
-		if rightVal == 0 {
-			return &Error{Message: "division by zero"}
-		}
-		return &Integer{Value: leftVal / rightVal}
-	case "MOD":
-		if rightVal == 0 {
-			return &Error{Message: "division by zero"}
-		}
-		return &Integer{Value: leftVal % rightVal}
-	case "<":
-		return &Boolean{Value: leftVal < rightVal}
-	case ">":
-		return &Boolean{Value: leftVal > rightVal}
-	case "<=":
-		return &Boolean{Value: leftVal <= rightVal}
-	case ">=":
-		return &Boolean{Value: leftVal >= rightVal}
-	case "=":
-		return &Boolean{Value: leftVal == rightVal}
-	case "<>":
-		return &Boolean{Value: leftVal != rightVal}
-	default:
-		return &Error{Message: fmt.Sprintf("unknown operator: %s %s %s", left.Type(), op, right.Type())}
-	}
-}
-
-func (i *Interpreter) evalRealInfixExpression(op string, left, right Object) Object {
-	var leftVal, rightVal float64
-
-	switch l := left.(type) {
-	case *Real:
-		leftVal = l.Value
-	case *Integer:
-		leftVal = float64(l.Value)
-	}
-
-	switch r := right.(type) {
-	case *Real:
-		rightVal = r.Value
-	case *Integer:
-		rightVal = float64(r.Value)
-	}
-
-	switch op {
-	case "+":
-		return &Real{Value: leftVal + rightVal}
-	case "-":
-		return &Real{Value: leftVal - rightVal}
-	case "*":
-		return &Real{Value: leftVal * rightVal}
-	case "/":
-		if rightVal == 0 {
-			return &Error{Message: "division by zero"}
-		}
-		return &Real{Value: leftVal / rightVal}
-	case "<":
-		return &Boolean{Value: leftVal < rightVal}
-	case ">":
-		return &Boolean{Value: leftVal > rightVal}
-	case "<=":
-		return &Boolean{Value: leftVal <= rightVal}
-	case ">=":
-		return &Boolean{Value: leftVal >= rightVal}
-	case "=":
-		return &Boolean{Value: leftVal == rightVal}
-	case "<>":
-		return &Boolean{Value: leftVal != rightVal}
-	default:
-		return &Error{Message: fmt.Sprintf("unknown operator: %s %s %s", left.Type(), op, right.Type())}
-	}
-}
-
-func (i *Interpreter) evalStringInfixExpression(op string, left, right Object) Object {
-	leftVal := left.(*String).Value
-	rightVal := right.(*String).Value
-
-	switch op {
-	case "&":
-		return &String{Value: leftVal + rightVal}
-	case "=":
-		return &Boolean{Value: leftVal == rightVal}
-	case "<>":
-		return &Boolean{Value: leftVal != rightVal}
-	case "<":
-		return &Boolean{Value: leftVal < rightVal}
-	case ">":
-		return &Boolean{Value: leftVal > rightVal}
-	case "<=":
-		return &Boolean{Value: leftVal <= rightVal}
-	case ">=":
-		return &Boolean{Value: leftVal >= rightVal}
-	default:
-		return &Error{Message: fmt.Sprintf("unknown operator: %s %s %s", left.Type(), op, right.Type())}
-	}
-}
-
-func (i *Interpreter) evalBooleanInfixExpression(op string, left, right Object) Object {
-	leftVal := left.(*Boolean).Value
-	rightVal := right.(*Boolean).Value
-
-	switch op {
-	case "AND":
-		return &Boolean{Value: leftVal && rightVal}
-	case "OR":
-		return &Boolean{Value: leftVal || rightVal}
-	case "=":
-		return &Boolean{Value: leftVal == rightVal}
-	case "<>":
-		return &Boolean{Value: leftVal != rightVal}
-	default:
-		return &Error{Message: fmt.Sprintf("unknown operator: %s %s %s", left.Type(), op, right.Type())}
+// limit returns obj, or an error if it is larger than SetMaxSize allows
+func (i *Interpreter) limit(obj Object) Object {
+	if i.maxSize <= 0 {
+		return obj
 	}
-}
-
-func (i *Interpreter) evalConcatenation(left, right Object) Object {
-	leftStr := i.objectToString(left)
-	rightStr := i.objectToString(right)
-	return &String{Value: leftStr + rightStr}
-}
-
-func (i *Interpreter) objectToString(obj Object) string {
 	switch o := obj.(type) {
 	case *String:
-		return o.Value
-	case *Char:
-		return string(o.Value)
-	case *Integer:
-		return fmt.Sprintf("%d", o.Value)
-	case *Real:
-		return fmt.Sprintf("%g", o.Value)
-	case *Boolean:
-		if o.Value {
-			return "TRUE"
+		if len(o.Value) > i.maxSize {
+			return NewError(diagnostic.StringTooLong, len(o.Value), i.maxSize)
+		}
+	case *Array:
+		if n := o.capacity(); n > int64(i.maxSize) {
+			return NewError(diagnostic.ArrayTooLarge, n, i.maxSize)
 		}
-		return "FALSE"
-	default:
-		return obj.Inspect()
 	}
+	return obj
 }
 
 func (i *Interpreter) evalArrayAccess(expr *ast.ArrayAccess, env *Environment) Object {
@@ -1016,7 +1048,7 @@ func (i *Interpreter) evalArrayAccess(expr *ast.ArrayAccess, env *Environment) O
 
 	array, ok := arr.(*Array)
 	if !ok {
-		return &Error{Message: "not an array"}
+		return NewError(diagnostic.NotAnArray)
 	}
 
 	indices := []int64{}
@@ -1027,13 +1059,12 @@ func (i *Interpreter) evalArrayAccess(expr *ast.ArrayAccess, env *Environment) O
 		}
 		intVal, ok := idxVal.(*Integer)
 		if !ok {
-			return &Error{Message: "array index must be an integer"}
+			return NewError(diagnostic.ArrayIndexNotInteger)
 		}
 		indices = append(indices, intVal.Value)
 	}
 
-	key := array.GetIndex(indices...)
-	if val, ok := array.Elements[key]; ok {
+	if val := array.Get(indices...); val != nil {
 		return val
 	}
 
@@ -1051,7 +1082,7 @@ func (i *Interpreter) evalMemberAccess(expr *ast.MemberAccess, env *Environment)
 		if val, ok := o.Fields[expr.Member]; ok {
 			return val
 		}
-		return &Error{Message: fmt.Sprintf("field not found: %s", expr.Member)}
+		return NewError(diagnostic.FieldNotFound, expr.Member)
 	case *Instance:
 		if val, ok := o.Fields[expr.Member]; ok {
 			return val
@@ -1060,18 +1091,18 @@ func (i *Interpreter) evalMemberAccess(expr *ast.MemberAccess, env *Environment)
 		if method := i.lookupMethod(o.Class, expr.Member); method != nil {
 			return &BoundMethod{Instance: o, Method: method}
 		}
-		return &Error{Message: fmt.Sprintf("member not found: %s", expr.Member)}
+		return NewError(diagnostic.MemberNotFound, expr.Member)
 	case *Super:
 		// Look up method in parent class
 		if o.Class == nil {
-			return &Error{Message: "no parent class"}
+			return NewError(diagnostic.NoParentClass)
 		}
 		if method := i.lookupMethod(o.Class, expr.Member); method != nil {
 			return &BoundMethod{Instance: o.Instance, Method: method}
 		}
-		return &Error{Message: fmt.Sprintf("method not found in parent class: %s", expr.Member)}
+		return NewError(diagnostic.ParentMethodNotFound, expr.Member)
 	default:
-		return &Error{Message: "cannot access member of non-record/instance"}
+		return NewError(diagnostic.NoMembers)
 	}
 }
 
@@ -1096,7 +1127,19 @@ func (i *Interpreter) evalCallExpression(expr *ast.CallExpression, env *Environm
 		return args[0]
 	}
 
-	return i.applyFunction(fn, args, env)
+	kind, name, ok := routine(fn)
+	if !ok {
+		return i.applyFunction(fn, args, env)
+	}
+	if expr.Tail && i.tailCalls && len(i.calls) > 0 && i.calls[len(i.calls)-1].fn == fn {
+		return &tailCall{args: args}
+	}
+	if err := i.enter(kind, name, expr, fn); err != nil {
+		return err
+	}
+	result := i.applyFunction(fn, args, env)
+	i.leave()
+	return result
 }
 
 func (i *Interpreter) evalExpressions(exprs []ast.Expression, env *Environment) []Object {
@@ -1116,23 +1159,40 @@ func (i *Interpreter) evalExpressions(exprs []ast.Expression, env *Environment)
 func (i *Interpreter) applyFunction(fn Object, args []Object, callerEnv *Environment) Object {
 	switch fn := fn.(type) {
 	case *Function:
-		extendedEnv := i.extendFunctionEnv(fn, args, fn.Parameters, callerEnv)
-		evaluated := i.evalStatements(fn.Body, extendedEnv)
-		return i.unwrapReturnValue(evaluated)
+		logger.Debug("call", "function", fn.Name, "args", len(args))
+		for {
+			extendedEnv := i.extendFunctionEnv(fn, args, fn.Parameters, callerEnv)
+			evaluated := i.unwrapReturnValue(i.evalStatements(fn.Body, extendedEnv))
+			tc, ok := evaluated.(*tailCall)
+			if !ok {
+				return evaluated
+			}
+			args = tc.args
+		}
 
 	case *Procedure:
-		extendedEnv := i.extendFunctionEnv(&Function{Env: fn.Env}, args, fn.Parameters, callerEnv)
-		evaluated := i.evalStatements(fn.Body, extendedEnv)
-		return i.unwrapReturnValue(evaluated)
+		logger.Debug("call", "procedure", fn.Name, "args", len(args))
+		for {
+			extendedEnv := i.extendFunctionEnv(&Function{Env: fn.Env}, args, fn.Parameters, callerEnv)
+			evaluated := i.unwrapReturnValue(i.evalStatements(fn.Body, extendedEnv))
+			tc, ok := evaluated.(*tailCall)
+			if !ok {
+				return evaluated
+			}
+			args = tc.args
+		}
 
 	case *BoundMethod:
 		return i.applyBoundMethod(fn, args, callerEnv)
 
 	case *Builtin:
-		return fn.Fn(args...)
+		if err := i.disabled(fn.Needs, fn.Name); err != nil {
+			return err
+		}
+		return i.limit(i.arith.Fit(fn.Call(args)))
 
 	default:
-		return &Error{Message: fmt.Sprintf("not a function: %s", fn.Type())}
+		return NewError(diagnostic.NotAFunction, fn.Type())
 	}
 }
 
@@ -1173,6 +1233,7 @@ func (i *Interpreter) createMethodEnv(instance *Instance, callerEnv *Environment
 
 	// Set instance reference so field access/assignment goes through the instance
 	env.instance = instance
+	env.call = true
 
 	// Bind "this" to the instance for explicit self-reference
 	env.Declare("this", instance)
@@ -1201,7 +1262,12 @@ func (i *Interpreter) createMethodEnv(instance *Instance, callerEnv *Environment
 }
 
 func (i *Interpreter) extendFunctionEnv(fn *Function, args []Object, params []ast.Parameter, callerEnv *Environment) *Environment {
-	env := NewEnclosedEnvironment(fn.Env)
+	names := make([]string, len(params))
+	for idx, param := range params {
+		names[idx] = param.Name
+	}
+	env := newFrame(fn.Env, names)
+	env.call = true
 
 	for idx, param := range params {
 		if idx < len(args) {
@@ -1228,12 +1294,12 @@ func (i *Interpreter) unwrapReturnValue(obj Object) Object {
 func (i *Interpreter) evalNewExpression(expr *ast.NewExpression, env *Environment) Object {
 	classObj, ok := env.Get(expr.ClassName)
 	if !ok {
-		return &Error{Message: fmt.Sprintf("class not found: %s", expr.ClassName)}
+		return NewError(diagnostic.ClassNotFound, expr.ClassName)
 	}
 
 	class, ok := classObj.(*Class)
 	if !ok {
-		return &Error{Message: fmt.Sprintf("%s is not a class", expr.ClassName)}
+		return NewError(diagnostic.NotAClass, expr.ClassName)
 	}
 
 	instance := &Instance{
@@ -1260,7 +1326,11 @@ func (i *Interpreter) evalNewExpression(expr *ast.NewExpression, env *Environmen
 					ctorEnv.Declare(param.Name, args[idx])
 				}
 			}
+			if err := i.enter("PROCEDURE", class.Name+".NEW", expr, proc); err != nil {
+				return err
+			}
 			i.evalStatements(proc.Body, ctorEnv)
+			i.leave()
 		}
 	}
 
@@ -1284,7 +1354,7 @@ func (i *Interpreter) evalSuperExpression(expr *ast.SuperExpression, env *Enviro
 	if superObj, ok := env.Get("SUPER"); ok {
 		return superObj
 	}
-	return &Error{Message: "SUPER can only be used within a class method"}
+	return NewError(diagnostic.SuperOutsideMethod)
 }
 
 // IsEOF checks if file is at EOF
@@ -1303,13 +1373,3 @@ func isError(obj Object) bool {
 	return false
 }
 
-func isTruthy(obj Object) bool {
-	switch obj := obj.(type) {
-	case *Null:
-		return false
-	case *Boolean:
-		return obj.Value
-	default:
-		return true
-	}
-}
@@ -2,9 +2,15 @@ package interpreter
 
 import (
 	"bytes"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
 	"strings"
 	"testing"
 
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
 	"github.com/andrinoff/cambridge-lang/pkg/lexer"
 	"github.com/andrinoff/cambridge-lang/pkg/parser"
 )
@@ -398,6 +404,230 @@ result <- Factorial(5)`
 	testIntegerObject(t, evaluated, 120)
 }
 
+func TestDeepRecursion(t *testing.T) {
+	input := `FUNCTION Sum(n : INTEGER) RETURNS INTEGER
+    IF n = 0 THEN
+        RETURN 0
+    ENDIF
+    RETURN n + Sum(n - 1)
+ENDFUNCTION
+
+DECLARE result : INTEGER
+result <- Sum(40000)`
+
+	// Within the default depth, on Go's default stack limit
+	evaluated := testEval(input)
+	testIntegerObject(t, evaluated, 40000*40001/2)
+}
+
+func TestRecursionLimit(t *testing.T) {
+	input := `PROCEDURE Forever(n : INTEGER)
+    CALL Forever(n + 1)
+ENDPROCEDURE
+
+CALL Forever(1)`
+
+	i := New()
+	i.SetMaxDepth(100)
+	result := i.Eval(parser.New(lexer.New(input)).ParseProgram())
+	err, ok := result.(*Error)
+	if !ok {
+		t.Fatalf("expected an error, got %v", result)
+	}
+	if err.Code != diagnostic.RecursionLimit || err.Line != 2 {
+		t.Errorf("expected recursion limit on line 2, got %s at line %d", err.Code, err.Line)
+	}
+	expected := "maximum recursion depth (100) exceeded in PROCEDURE Forever called from line 2"
+	if err.Message != expected {
+		t.Errorf("expected message %q, got %q", expected, err.Message)
+	}
+	if len(err.Calls) != maxReportedCalls || err.Calls[0].Name != "Forever" || err.Calls[0].Line != 2 {
+		t.Errorf("expected the innermost calls to be reported, got %+v", err.Calls)
+	}
+	if len(i.CallStack()) != 0 {
+		t.Errorf("expected the call stack to unwind, got %d calls", len(i.CallStack()))
+	}
+}
+
+func TestMaxSize(t *testing.T) {
+	tests := []struct {
+		input string
+		code  diagnostic.Code // "" if it runs
+		line  int
+	}{
+		{"DECLARE S : STRING\nS <- \"ab\"\nWHILE TRUE\n    S <- S & S\nENDWHILE", diagnostic.StringTooLong, 4},
+		{"OUTPUT \"0123456789\" & \"0123456789\"", "", 0},
+		{"DECLARE A : ARRAY[1:1000000000] OF INTEGER", diagnostic.ArrayTooLarge, 1},
+		{"DECLARE A : ARRAY[1:10] OF INTEGER\nA[5] <- 1", "", 0},
+		{"DECLARE A : ARRAY[1:10] OF INTEGER\nDECLARE N : INTEGER\nFOR N <- 1 TO 1000\n    A[N] <- N\nNEXT N", diagnostic.ArrayTooLarge, 4},
+	}
+
+	for _, tt := range tests {
+		i := New()
+		i.SetOutput(&bytes.Buffer{})
+		i.SetMaxSize(100)
+		result := i.Eval(parser.New(lexer.New(tt.input)).ParseProgram())
+		err, failed := result.(*Error)
+		if tt.code == "" {
+			if failed {
+				t.Errorf("%q: unexpected error %s", tt.input, err.Message)
+			}
+			continue
+		}
+		if !failed || err.Code != tt.code || err.Line != tt.line {
+			t.Errorf("%q: expected %s on line %d, got %v", tt.input, tt.code, tt.line, result)
+		}
+	}
+}
+
+func TestTailCalls(t *testing.T) {
+	input := `FUNCTION Count(n : INTEGER, total : INTEGER) RETURNS INTEGER
+    IF n = 0 THEN
+        RETURN total
+    ENDIF
+    RETURN Count(n - 1, total + n)
+ENDFUNCTION
+
+PROCEDURE CountDown(n : INTEGER)
+    IF n > 0 THEN
+        CALL CountDown(n - 1)
+    ELSE
+        OUTPUT "done"
+    ENDIF
+ENDPROCEDURE
+
+CALL CountDown(1000)
+OUTPUT Count(1000, 0)`
+
+	program := parser.New(lexer.New(input)).ParseProgram()
+	Resolve(program)
+
+	var out bytes.Buffer
+	i := New()
+	i.SetOutput(&out)
+	i.SetMaxDepth(100)
+	i.SetTailCalls(true)
+	if err, ok := i.Eval(program).(*Error); ok {
+		t.Fatalf("unexpected error with tail calls: %s", err.Message)
+	}
+	if out.String() != "done\n500500\n" {
+		t.Errorf("expected done and 500500, got %q", out.String())
+	}
+
+	i = New()
+	i.SetOutput(&out)
+	i.SetMaxDepth(100)
+	err, ok := i.Eval(program).(*Error)
+	if !ok || err.Code != diagnostic.RecursionLimit {
+		t.Errorf("expected the recursion limit without tail calls, got %v", err)
+	}
+}
+
+func TestIntegerOverflow(t *testing.T) {
+	tests := []struct {
+		input    string
+		expected string
+	}{
+		{"9223372036854775807 + 1", "9223372036854775807 + 1"},
+		{"-9223372036854775807 - 2", "-9223372036854775807 - 2"},
+		{"4294967296 * 4294967296", "4294967296 * 4294967296"},
+		{"(-9223372036854775807 - 1) DIV -1", "-9223372036854775808 DIV -1"},
+		{"-(-9223372036854775807 - 1)", "--9223372036854775808"},
+		{"99999999999999999999 - 1", "99999999999999999999"},
+	}
+
+	for _, tt := range tests {
+		err, ok := testEval("OUTPUT " + tt.input).(*Error)
+		if !ok || err.Code != diagnostic.IntegerOverflow {
+			t.Errorf("%s: expected an overflow error, got %v", tt.input, err)
+			continue
+		}
+		if !strings.Contains(err.Message, tt.expected+" does not fit") {
+			t.Errorf("%s: expected the operation in the message, got %q", tt.input, err.Message)
+		}
+	}
+}
+
+func TestBigIntegers(t *testing.T) {
+	input := `FUNCTION Factorial(n : INTEGER) RETURNS INTEGER
+    IF n <= 1 THEN
+        RETURN 1
+    ENDIF
+    RETURN n * Factorial(n - 1)
+ENDFUNCTION
+
+DECLARE Big : INTEGER
+Big <- Factorial(25)
+OUTPUT Big
+OUTPUT Big DIV Factorial(23)
+OUTPUT Big > 9223372036854775807, Big = Factorial(25)
+OUTPUT -Big + Big
+OUTPUT 99999999999999999999 + 1, " ", -9223372036854775808`
+
+	var out bytes.Buffer
+	i := New()
+	i.SetOutput(&out)
+	i.SetArithmetic(Arithmetic{BigIntegers: true})
+	if err, ok := i.Eval(parser.New(lexer.New(input)).ParseProgram()).(*Error); ok {
+		t.Fatalf("unexpected error: %s", err.Message)
+	}
+	expected := "15511210043330985984000000\n600\nTRUETRUE\n0\n100000000000000000000 -9223372036854775808\n"
+	if out.String() != expected {
+		t.Errorf("expected %q, got %q", expected, out.String())
+	}
+}
+
+func TestDecimalReals(t *testing.T) {
+	tests := []struct {
+		input    string
+		expected string
+	}{
+		{"OUTPUT 0.1 + 0.2", "0.3"},
+		{"OUTPUT 0.1 + 0.2 = 0.3", "TRUE"},
+		{"OUTPUT 1.10 * 3", "3.3"},
+		{"OUTPUT 7 / 4, \" \", -2.5 - 0.5", "1.75 -3"},
+		{"OUTPUT 1 / 3", "0.3333333333333333"},
+		{"DECLARE Total : REAL\nFOR i <- 1 TO 10\n    Total <- Total + 0.1\nNEXT i\nOUTPUT Total", "1"},
+	}
+
+	for _, tt := range tests {
+		var out bytes.Buffer
+		i := New()
+		i.SetOutput(&out)
+		i.SetArithmetic(Arithmetic{DecimalReals: true})
+		if err, ok := i.Eval(parser.New(lexer.New(tt.input)).ParseProgram()).(*Error); ok {
+			t.Errorf("%q: unexpected error: %s", tt.input, err.Message)
+			continue
+		}
+		if got := strings.TrimSuffix(out.String(), "\n"); got != tt.expected {
+			t.Errorf("%q: expected %s, got %s", tt.input, tt.expected, got)
+		}
+	}
+
+	// Newton's method for the square root of 2 divides by its last guess
+	// each time, so exact quotients would double in size every step
+	input := `DECLARE X : REAL
+X <- 1.0
+FOR n <- 1 TO 50
+    X <- (X + 2 / X) / 2
+NEXT n
+OUTPUT X`
+	var out bytes.Buffer
+	i := New()
+	i.SetOutput(&out)
+	i.SetArithmetic(Arithmetic{DecimalReals: true})
+	if err, ok := i.Eval(parser.New(lexer.New(input)).ParseProgram()).(*Error); ok {
+		t.Fatalf("unexpected error: %s", err.Message)
+	}
+	if out.String() != "1.4142135623730951\n" {
+		t.Errorf("expected the square root of 2, got %q", out.String())
+	}
+	x, _ := i.Global("X")
+	if r, ok := x.(*Real); !ok || r.Dec == nil || r.Dec.Denom().Cmp(decimalScale) > 0 {
+		t.Errorf("expected a decimal with a denominator of at most 10^%d, got %v", decimalPlaces, x)
+	}
+}
+
 func TestArrayOperations(t *testing.T) {
 	input := `DECLARE arr : ARRAY[1:5] OF INTEGER
 arr[1] <- 10
@@ -423,6 +653,76 @@ result <- matrix[1, 1] + matrix[2, 2]`
 	testIntegerObject(t, evaluated, 5)
 }
 
+func TestArrayStorage(t *testing.T) {
+	arr := NewArray([]ast.ArrayDimension{{Lower: 0, Upper: 2}, {Lower: 1, Upper: 3}}, nil)
+	if arr.elements != nil {
+		t.Fatalf("expected no storage before an element is stored")
+	}
+
+	arr.Set(NewInteger(1), 0, 1)
+	if len(arr.elements) != 9 {
+		t.Fatalf("expected 9 contiguous elements, got %d", len(arr.elements))
+	}
+	arr.Set(NewInteger(2), 2, 3)
+	arr.Set(NewInteger(3), 5, 5) // outside the bounds
+	arr.Set(NewInteger(4), 1)    // wrong number of indices
+
+	tests := []struct {
+		indices  []int64
+		expected int64
+	}{
+		{[]int64{0, 1}, 1},
+		{[]int64{2, 3}, 2},
+		{[]int64{5, 5}, 3},
+		{[]int64{1}, 4},
+	}
+	for _, tt := range tests {
+		testIntegerObject(t, arr.Get(tt.indices...), tt.expected)
+	}
+	if arr.Get(1, 2) != nil {
+		t.Errorf("expected unset element to be nil")
+	}
+	if arr.elements[8] == nil || arr.Inspect() != "ARRAY[4 elements]" {
+		t.Errorf("expected [2, 3] stored last and 4 elements, got %s", arr.Inspect())
+	}
+}
+
+func TestSparseArrays(t *testing.T) {
+	i := setupInterpreter(`DECLARE A : ARRAY[1:1000000] OF INTEGER
+A[500000] <- 7
+A[1000000] <- A[500000] + A[1]`)
+
+	obj, _ := i.env.Get("A")
+	arr := obj.(*Array)
+	testIntegerObject(t, arr.Get(1), 0)
+	testIntegerObject(t, arr.Get(1000000), 7)
+	if arr.elements != nil || len(arr.sparse) != 2 {
+		t.Errorf("expected only the 2 stored elements to be kept, got %d contiguous and %d sparse", len(arr.elements), len(arr.sparse))
+	}
+
+	// Once an eighth of a large array is used it becomes contiguous
+	big := NewArray([]ast.ArrayDimension{{Lower: 1, Upper: 8000}}, nil)
+	for n := int64(1); n <= 1001; n++ {
+		big.Set(NewInteger(n), n)
+	}
+	if len(big.elements) != 8000 || big.sparse != nil {
+		t.Fatalf("expected contiguous storage, got %d contiguous and %d sparse", len(big.elements), len(big.sparse))
+	}
+	testIntegerObject(t, big.Get(1000), 1000)
+	if big.Get(8000) != nil {
+		t.Errorf("expected an unset element to be nil")
+	}
+}
+
+func TestSharedValues(t *testing.T) {
+	if NewInteger(7) != NewInteger(7) || NewBoolean(true) != NewBoolean(true) {
+		t.Error("expected small integers and booleans to be shared")
+	}
+	if v := NewInteger(1 << 40); v.Value != 1<<40 || NewInteger(-129).Value != -129 {
+		t.Errorf("unexpected large integer values")
+	}
+}
+
 func TestRecordType(t *testing.T) {
 	input := `TYPE Person
     DECLARE name : STRING
@@ -550,6 +850,174 @@ INPUT name`
 	}
 }
 
+func TestMultipleInputStatements(t *testing.T) {
+	input := `DECLARE first : STRING
+DECLARE second : STRING
+INPUT first
+INPUT second`
+
+	i := New()
+	i.SetInput(strings.NewReader("Ada\nAlan\n"))
+
+	p := parser.New(lexer.New(input))
+	i.Eval(p.ParseProgram())
+
+	for name, expected := range map[string]string{"first": "Ada", "second": "Alan"} {
+		obj, _ := i.env.Get(name)
+		testStringObject(t, obj, expected)
+	}
+}
+
+func TestMemoryFiles(t *testing.T) {
+	input := `DECLARE Line : STRING
+OPENFILE "in.txt" FOR READ
+READFILE "in.txt", Line
+CLOSEFILE "in.txt"
+OPENFILE "out.txt" FOR WRITE
+WRITEFILE "out.txt", Line & "!"
+CLOSEFILE "out.txt"
+OPENFILE "out.txt" FOR APPEND
+WRITEFILE "out.txt", 2
+CLOSEFILE "out.txt"`
+
+	files := NewMemoryFiles(map[string]string{"in.txt": "hello\nworld\n"})
+	i := New()
+	i.SetFiles(files)
+	p := parser.New(lexer.New(input))
+	if result := i.Eval(p.ParseProgram()); isError(result) {
+		t.Fatalf("unexpected error: %s", result.Inspect())
+	}
+
+	if got, _ := files.Contents("out.txt"); got != "hello!\n2\n" {
+		t.Errorf("expected %q, got %q", "hello!\n2\n", got)
+	}
+	if got, _ := files.Contents("in.txt"); got != "hello\nworld\n" {
+		t.Errorf("expected in.txt to be unchanged, got %q", got)
+	}
+
+	p = parser.New(lexer.New(`OPENFILE "nowhere.txt" FOR READ`))
+	if missing := i.Eval(p.ParseProgram()); !isError(missing) {
+		t.Errorf("expected an error opening a missing file, got %v", missing)
+	}
+}
+
+func TestSandboxFiles(t *testing.T) {
+	outside := t.TempDir()
+	root := filepath.Join(t.TempDir(), "submission")
+	for _, dir := range []string{root, filepath.Join(root, "data")} {
+		if err := os.Mkdir(dir, 0755); err != nil {
+			t.Fatal(err)
+		}
+	}
+	if err := os.WriteFile(filepath.Join(root, "data", "in.txt"), []byte("hello\n"), 0644); err != nil {
+		t.Fatal(err)
+	}
+	if err := os.WriteFile(filepath.Join(outside, "secret.txt"), []byte("key\n"), 0644); err != nil {
+		t.Fatal(err)
+	}
+	linked := os.Symlink(outside, filepath.Join(root, "link")) == nil &&
+		os.Symlink(filepath.Join(outside, "bashrc"), filepath.Join(root, "out.txt")) == nil
+
+	tests := []struct {
+		name    string
+		allowed bool
+	}{
+		{"data/in.txt", true},
+		{"data/../data/in.txt", true},
+		{"new.txt", true},
+		{"../secret.txt", false},
+		{"data/../../secret.txt", false},
+		{filepath.Join(outside, "secret.txt"), false},
+		{"link/secret.txt", false},
+		{"out.txt", false},
+	}
+
+	files := SandboxFiles{Root: root}
+	for _, tt := range tests {
+		if (tt.name == "link/secret.txt" || tt.name == "out.txt") && !linked {
+			continue
+		}
+		w, err := files.Append(tt.name)
+		if err == nil {
+			w.Close()
+		}
+		if tt.allowed != (err == nil) {
+			t.Errorf("%s: expected allowed=%t, got %v", tt.name, tt.allowed, err)
+		}
+		if !tt.allowed && !errors.Is(err, ErrOutsideRoot) {
+			t.Errorf("%s: expected ErrOutsideRoot, got %v", tt.name, err)
+		}
+	}
+	if _, err := os.Lstat(filepath.Join(outside, "bashrc")); linked && err == nil {
+		t.Error("expected the dangling link's target not to be created")
+	}
+}
+
+func TestDisable(t *testing.T) {
+	tests := []struct {
+		input    string
+		disabled Capability
+		code     diagnostic.Code
+	}{
+		{`OPENFILE "data.txt" FOR READ`, FileHandling, diagnostic.FileHandlingDisabled},
+		{`OUTPUT EOF("data.txt")`, FileHandling, diagnostic.FileHandlingDisabled},
+		{"DECLARE x : REAL\nx <- RAND(10)", Randomness, diagnostic.RandomnessDisabled},
+		{"DECLARE x : STRING\nINPUT x", Interaction, diagnostic.InputDisabled},
+	}
+
+	for _, tt := range tests {
+		i := New()
+		i.SetBuiltins(map[string]*Builtin{
+			"EOF":  {Name: "EOF", Fn: func(...Object) Object { return &Boolean{Value: true} }, Needs: FileHandling},
+			"RAND": {Name: "RAND", Fn: func(...Object) Object { return &Real{Value: 0.5} }, Needs: Randomness},
+		})
+		i.SetInput(strings.NewReader("Ada\n"))
+		i.SetOutput(&bytes.Buffer{})
+		i.Disable(tt.disabled)
+		p := parser.New(lexer.New(tt.input))
+		err, ok := i.Eval(p.ParseProgram()).(*Error)
+		if !ok || err.Code != tt.code {
+			t.Errorf("%q: expected %s, got %v", tt.input, tt.code, err)
+		}
+	}
+
+	i := New()
+	i.Disable(FileHandling)
+	i.SetOutput(&bytes.Buffer{})
+	p := parser.New(lexer.New("OUTPUT 1"))
+	if result := i.Eval(p.ParseProgram()); isError(result) {
+		t.Errorf("expected a program without files to run, got %s", result.Inspect())
+	}
+}
+
+func TestExecutionFuel(t *testing.T) {
+	p := parser.New(lexer.New("DECLARE n : INTEGER\nn <- 0\nWHILE TRUE\n    n <- n + 1\nENDWHILE"))
+	i := New()
+	run := i.Start(p.ParseProgram())
+	for _, expected := range []string{"48", "98"} {
+		if run.Continue(100) {
+			t.Fatal("expected an endless loop not to finish")
+		}
+		if n, _ := i.Global("n"); n.Inspect() != expected {
+			t.Errorf("expected n = %s after another 100 units, got %s", expected, n.Inspect())
+		}
+	}
+	run.Stop()
+	if !run.Done() || run.Result() != nil {
+		t.Errorf("expected a stopped run to be done with no result, got %v", run.Result())
+	}
+
+	p = parser.New(lexer.New("DECLARE x : INTEGER\nx <- 1\nFOR k <- 1 TO 10\nNEXT k"))
+	run = New().Start(p.ParseProgram())
+	instalments := 1
+	for !run.Continue(3) {
+		instalments++
+	}
+	if instalments != 5 || isError(run.Result()) {
+		t.Errorf("expected 13 units to take 5 instalments of 3, got %d and %v", instalments, run.Result())
+	}
+}
+
 func TestDivisionByZero(t *testing.T) {
 	tests := []string{
 		"DECLARE x : INTEGER\nx <- 5 DIV 0",
@@ -565,6 +1033,23 @@ func TestDivisionByZero(t *testing.T) {
 	}
 }
 
+func TestErrorLocation(t *testing.T) {
+	input := `DECLARE x : INTEGER
+FOR i <- 1 TO 3
+    x <- i DIV 0
+NEXT i`
+
+	evaluated := testEval(input)
+	err, ok := evaluated.(*Error)
+	if !ok {
+		t.Fatalf("expected error, got %T", evaluated)
+	}
+	// Reported at the innermost statement, not the enclosing loop
+	if err.Line != 3 || err.Column != 5 {
+		t.Errorf("expected error at 3:5, got %d:%d", err.Line, err.Column)
+	}
+}
+
 func TestUndefinedVariable(t *testing.T) {
 	input := `x <- 5`
 
@@ -657,6 +1142,31 @@ result <- "Value: " & 42`
 	testStringObject(t, evaluated, "Value: 42")
 }
 
+func TestStringBuiltInLoop(t *testing.T) {
+	input := `DECLARE s : STRING
+DECLARE a : STRING
+DECLARE b : STRING
+s <- ""
+FOR i <- 1 TO 500
+    s <- s & 'x'
+NEXT i
+a <- s & "a"
+b <- s & "b"
+s <- s & "c"`
+
+	interp := setupInterpreter(input)
+	prefix := strings.Repeat("x", 500)
+
+	// Strings extended from the same value must not see each other's text
+	for name, suffix := range map[string]string{"a": "a", "b": "b", "s": "c"} {
+		val, ok := interp.env.Get(name)
+		if !ok {
+			t.Fatalf("%s not defined", name)
+		}
+		testStringObject(t, val, prefix+suffix)
+	}
+}
+
 func TestCharLiteral(t *testing.T) {
 	input := `DECLARE c : CHAR
 c <- 'A'`
@@ -707,6 +1217,201 @@ result <- FindFirst()`
 	testIntegerObject(t, evaluated, 5)
 }
 
+func TestSteps(t *testing.T) {
+	input := `DECLARE Total : INTEGER
+Total <- 0
+PROCEDURE Add(n : INTEGER)
+    Total <- Total + n
+ENDPROCEDURE
+FOR i <- 1 TO 2
+    CALL Add(i)
+NEXT i
+Total <- Total DIV 0
+OUTPUT "unreachable"`
+
+	type step struct {
+		line    int
+		changed string
+		calls   int
+	}
+	expected := []step{
+		{1, "Total=0", 0},
+		{2, "", 0},
+		{3, "", 0},
+		{4, "Total=1 n=1", 1}, // the procedure cannot see i
+		{7, "i=1", 0},
+		{4, "Total=3 n=2", 1},
+		{7, "i=2", 0},
+		{9, "", 0},
+	}
+
+	p := parser.New(lexer.New(input))
+	program := p.ParseProgram()
+	Resolve(program)
+	i := New()
+	var out bytes.Buffer
+	i.SetOutput(&out)
+
+	var got []step
+	var last Step
+	for s := range i.Steps(program) {
+		var changed []string
+		for _, v := range s.Changed {
+			changed = append(changed, v.Name+"="+v.Value.Inspect())
+		}
+		got = append(got, step{s.Line, strings.Join(changed, " "), len(s.Calls)})
+		last = s
+	}
+
+	if fmt.Sprint(got) != fmt.Sprint(expected) {
+		t.Errorf("expected steps\n%v\ngot\n%v", expected, got)
+	}
+	if last.Err == nil || last.Err.Code != diagnostic.DivisionByZero {
+		t.Errorf("expected the last step to fail with division by zero, got %v", last.Err)
+	}
+	if out.Len() != 0 {
+		t.Errorf("expected the program to stop at the error, got output %q", out.String())
+	}
+
+	// Breaking out of the loop stops the program
+	i = New()
+	i.SetOutput(&out)
+	for s := range i.Steps(program) {
+		if s.Line == 3 {
+			break
+		}
+	}
+	if total, _ := i.Global("Total"); total.Inspect() != "0" {
+		t.Errorf("expected Total to stay 0, got %s", total.Inspect())
+	}
+}
+
+func TestSnapshot(t *testing.T) {
+	input := `DECLARE Scores : ARRAY[1:3] OF INTEGER
+DECLARE Name : STRING
+CONSTANT Bonus = 5
+PROCEDURE Award(i : INTEGER)
+    Scores[i] <- Scores[i] + Bonus
+ENDPROCEDURE
+INPUT Name
+CALL Award(1)
+CALL Award(1)
+OUTPUT Name, " ", Scores[1]`
+
+	p := parser.New(lexer.New(input))
+	program := p.ParseProgram()
+	Resolve(program)
+
+	i := New()
+	i.SetInput(strings.NewReader("Ada\n"))
+	var out bytes.Buffer
+	i.SetOutput(&out)
+	var snap *Snapshot
+	for s := range i.Steps(program) {
+		var err error
+		switch s.Line {
+		case 5:
+			if _, err = i.Snapshot(); err == nil {
+				t.Error("expected no snapshot inside a procedure")
+			}
+		case 8:
+			if snap, err = i.Snapshot(); err != nil {
+				t.Fatalf("unexpected error: %v", err)
+			}
+		}
+	}
+	if out.String() != "Ada 10\n" {
+		t.Fatalf("expected %q, got %q", "Ada 10\n", out.String())
+	}
+
+	var encoded bytes.Buffer
+	if err := snap.Encode(&encoded); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	decoded, err := DecodeSnapshot(&encoded)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	// Each restore carries on from the first award with its own copy
+	for _, s := range []*Snapshot{snap, decoded, snap} {
+		out.Reset()
+		restored := New()
+		restored.SetOutput(&out)
+		if err := restored.Restore(program, s); err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+		if result := restored.Resume(program); isError(result) {
+			t.Fatalf("unexpected error: %s", result.Inspect())
+		}
+		if out.String() != "Ada 10\n" {
+			t.Errorf("expected %q after restoring, got %q", "Ada 10\n", out.String())
+		}
+		if result := restored.env.Set("Bonus", NewInteger(1)); !isError(result) {
+			t.Error("expected Bonus to stay a constant")
+		}
+	}
+}
+
+func TestResolve(t *testing.T) {
+	input := `PROCEDURE Show(N : INTEGER, Label : STRING)
+    FOR i <- 1 TO N
+        OUTPUT Label, i, N
+    NEXT i
+    FOR j <- 1 TO 2
+        DECLARE Label : STRING
+        Label <- "inner"
+        OUTPUT Label
+    NEXT j
+ENDPROCEDURE
+
+FOR k <- 1 TO 2
+    CALL Show(k, "x")
+NEXT k`
+	program := parser.New(lexer.New(input)).ParseProgram()
+	Resolve(program)
+
+	resolved := map[string][]string{}
+	ast.Inspect(program, func(n ast.Node) bool {
+		if id, ok := n.(*ast.Identifier); ok && id.Resolved {
+			resolved[id.Value] = append(resolved[id.Value], fmt.Sprintf("%d:%d", id.Depth, id.Slot))
+		}
+		return true
+	})
+
+	expected := map[string][]string{
+		// The first loop's bound, then N inside that loop; the inner DECLARE
+		// shadows Label in the second loop
+		"N":     {"0:0", "1:0"},
+		"Label": {"1:1"},
+		"i":     {"0:0"},
+		"k":     {"0:0"},
+	}
+	for name, want := range expected {
+		if got := strings.Join(resolved[name], " "); got != strings.Join(want, " ") {
+			t.Errorf("%s: expected slots %v, got %v", name, want, resolved[name])
+		}
+	}
+	if len(resolved["Show"]) != 0 || len(resolved["j"]) != 0 {
+		t.Errorf("expected Show and the unused j to stay unresolved, got %v", resolved)
+	}
+
+	run := func(resolve bool) string {
+		var out bytes.Buffer
+		program := parser.New(lexer.New(input)).ParseProgram()
+		if resolve {
+			Resolve(program)
+		}
+		interp := New()
+		interp.SetOutput(&out)
+		interp.Eval(program)
+		return out.String()
+	}
+	if got, want := run(true), run(false); got != want {
+		t.Errorf("resolved program output %q, expected %q", got, want)
+	}
+}
+
 // Helper functions
 
 func testEval(input string) Object {
@@ -714,6 +1419,7 @@ func testEval(input string) Object {
 	l := lexer.New(input)
 	p := parser.New(l)
 	program := p.ParseProgram()
+	Resolve(program)
 	return i.Eval(program)
 }
 
@@ -722,6 +1428,7 @@ func setupInterpreter(input string) *Interpreter {
 	l := lexer.New(input)
 	p := parser.New(l)
 	program := p.ParseProgram()
+	Resolve(program)
 	i.Eval(program)
 	return i
 }
@@ -777,3 +1484,57 @@ func testStringObject(t *testing.T, obj Object, expected string) bool {
 	}
 	return true
 }
+
+// lineDebugger records where each statement runs, stopping the program at
+// stop
+type lineDebugger struct {
+	stop  int
+	lines []string
+}
+
+func (d *lineDebugger) Before(i *Interpreter, stmt ast.Statement, env *Environment) bool {
+	line, _ := ast.Position(stmt)
+	d.lines = append(d.lines, fmt.Sprintf("%d/%d", line, i.CallDepth()))
+	return line != d.stop
+}
+
+func TestDebugger(t *testing.T) {
+	input := `PROCEDURE Show(n : INTEGER)
+    OUTPUT n
+ENDPROCEDURE
+FOR i <- 1 TO 2
+    CALL Show(i)
+NEXT i
+OUTPUT "done"`
+
+	tests := []struct {
+		stop     int
+		expected string // line/depth of each statement
+		output   string
+	}{
+		{0, "1/0 4/0 5/0 2/1 5/0 2/1 7/0", "1\n2\ndone\n"},
+		{2, "1/0 4/0 5/0 2/1", ""},
+	}
+
+	for _, tt := range tests {
+		p := parser.New(lexer.New(input))
+		program := p.ParseProgram()
+		Resolve(program)
+		i := New()
+		var out bytes.Buffer
+		i.SetOutput(&out)
+		d := &lineDebugger{stop: tt.stop}
+		i.SetDebugger(d)
+		result := i.Eval(program)
+
+		if got := strings.Join(d.lines, " "); got != tt.expected {
+			t.Errorf("stopping at %d: expected statements %s, got %s", tt.stop, tt.expected, got)
+		}
+		if out.String() != tt.output {
+			t.Errorf("stopping at %d: expected output %q, got %q", tt.stop, tt.output, out.String())
+		}
+		if _, failed := result.(*Error); failed != (tt.stop != 0) {
+			t.Errorf("stopping at %d: unexpected result %v", tt.stop, result)
+		}
+	}
+}
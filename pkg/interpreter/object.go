@@ -4,9 +4,14 @@ package interpreter
 import (
 	"bytes"
 	"fmt"
+	"math"
+	"math/big"
+	"strconv"
 	"strings"
+	"unsafe"
 
 	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
 )
 
 // ObjectType represents the type of an object
@@ -32,6 +37,7 @@ const (
 	FILE_OBJ         ObjectType = "FILE"
 	BOUND_METHOD_OBJ ObjectType = "BOUND_METHOD"
 	SUPER_OBJ        ObjectType = "SUPER"
+	TAIL_CALL_OBJ    ObjectType = "TAIL_CALL"
 )
 
 // Object is the interface all values implement
@@ -40,30 +46,166 @@ type Object interface {
 	Inspect() string
 }
 
+// Integers in this range are created once and shared, since loop counters,
+// indices and flags are almost always small
+const (
+	minSharedInteger = -128
+	maxSharedInteger = 1023
+)
+
+var (
+	sharedIntegers = func() []Integer {
+		ints := make([]Integer, maxSharedInteger-minSharedInteger+1)
+		for idx := range ints {
+			ints[idx].Value = int64(idx + minSharedInteger)
+		}
+		return ints
+	}()
+	trueObj  = &Boolean{Value: true}
+	falseObj = &Boolean{Value: false}
+)
+
+// NewInteger returns an INTEGER value, sharing one object per small value
+// Values are never changed once created, so sharing them is safe.
+func NewInteger(v int64) *Integer {
+	if v >= minSharedInteger && v <= maxSharedInteger {
+		return &sharedIntegers[v-minSharedInteger]
+	}
+	return &Integer{Value: v}
+}
+
+// NewBoolean returns one of the two shared BOOLEAN values
+func NewBoolean(v bool) *Boolean {
+	if v {
+		return trueObj
+	}
+	return falseObj
+}
+
 // Integer represents an integer value
+// In --bigint mode a value too large for 64 bits is held in Big, and Value
+// is clamped to the nearest int64 so that indexing and other uses that need
+// a machine integer fail their range checks rather than wrapping.
 type Integer struct {
 	Value int64
+	Big   *big.Int
 }
 
 func (i *Integer) Type() ObjectType { return INTEGER_OBJ }
-func (i *Integer) Inspect() string  { return fmt.Sprintf("%d", i.Value) }
+func (i *Integer) Inspect() string {
+	if i.Big != nil {
+		return i.Big.String()
+	}
+	return fmt.Sprintf("%d", i.Value)
+}
+
+// NewBigInteger returns an INTEGER value for v, which need not fit in 64 bits
+func NewBigInteger(v *big.Int) *Integer {
+	if v.IsInt64() {
+		return NewInteger(v.Int64())
+	}
+	if v.Sign() > 0 {
+		return &Integer{Value: math.MaxInt64, Big: v}
+	}
+	return &Integer{Value: math.MinInt64, Big: v}
+}
+
+func (i *Integer) bigValue() *big.Int {
+	if i.Big != nil {
+		return i.Big
+	}
+	return big.NewInt(i.Value)
+}
+
+func (i *Integer) float() float64 {
+	if i.Big != nil {
+		f, _ := new(big.Float).SetInt(i.Big).Float64()
+		return f
+	}
+	return float64(i.Value)
+}
 
 // Real represents a floating-point value
+// With decimal REAL arithmetic the exact value is held in Dec, and Value is
+// the nearest float64 for builtins that work in floating point.
 type Real struct {
 	Value float64
+	Dec   *big.Rat
 }
 
 func (r *Real) Type() ObjectType { return REAL_OBJ }
-func (r *Real) Inspect() string  { return fmt.Sprintf("%g", r.Value) }
+func (r *Real) Inspect() string {
+	// A decimal using every place kept was rounded, as 1 / 3 is, so is
+	// shown only as precisely as a float64
+	if r.Dec != nil {
+		if digits, exact := r.Dec.FloatPrec(); exact && digits < decimalPlaces {
+			return r.Dec.FloatString(digits)
+		}
+	}
+	return fmt.Sprintf("%g", r.Value)
+}
+
+// NewDecimal returns a REAL value holding v exactly
+func NewDecimal(v *big.Rat) *Real {
+	f, _ := v.Float64()
+	return &Real{Value: f, Dec: v}
+}
+
+// decimal returns the exact value of r, taking a float64 to be the shortest
+// decimal that reads back as it, or nil for infinities and NaN
+func (r *Real) decimal() *big.Rat {
+	if r.Dec != nil {
+		return r.Dec
+	}
+	d, ok := new(big.Rat).SetString(strconv.FormatFloat(r.Value, 'g', -1, 64))
+	if !ok {
+		return nil
+	}
+	return d
+}
 
 // String represents a string value
 type String struct {
 	Value string
+
+	// The buffer Value was built in by concatenation, if any
+	builder *stringBuilder
+}
+
+// stringBuilder is the growing buffer behind a string built one piece at a
+// time, as in s <- s & ch
+// Bytes already in the buffer are never changed, so every string sliced
+// from it stays valid; only the newest of them may append.
+type stringBuilder struct {
+	buf []byte
 }
 
+// minBuilderSize is the length below which concatenation just copies
+const minBuilderSize = 64
+
 func (s *String) Type() ObjectType { return STRING_OBJ }
 func (s *String) Inspect() string  { return s.Value }
 
+// concat returns s followed by suffix
+// Repeatedly extending the latest result appends to a shared buffer, so
+// building a string in a loop takes linear rather than quadratic time.
+func (s *String) concat(suffix string) *String {
+	size := len(s.Value) + len(suffix)
+	if size < minBuilderSize {
+		return &String{Value: s.Value + suffix}
+	}
+	if s.builder == nil || len(s.builder.buf) != len(s.Value) {
+		// Either s was never extended, or a longer string has already been
+		// built from it and owns the end of the buffer
+		buf := make([]byte, len(s.Value), 2*size)
+		copy(buf, s.Value)
+		s.builder = &stringBuilder{buf: buf}
+	}
+	b := s.builder
+	b.buf = append(b.buf, suffix...)
+	return &String{Value: unsafe.String(unsafe.SliceData(b.buf), len(b.buf)), builder: b}
+}
+
 // Char represents a character value
 type Char struct {
 	Value rune
@@ -109,11 +251,28 @@ type ReturnValue struct {
 func (rv *ReturnValue) Type() ObjectType { return RETURN_VALUE_OBJ }
 func (rv *ReturnValue) Inspect() string  { return rv.Value.Inspect() }
 
+// tailCall asks the routine that is running to start again with new
+// arguments instead of calling itself
+type tailCall struct {
+	args []Object
+}
+
+func (tc *tailCall) Type() ObjectType { return TAIL_CALL_OBJ }
+func (tc *tailCall) Inspect() string  { return "tail call" }
+
 // Error represents an error
 type Error struct {
 	Message string
+	Code    diagnostic.Code
+	Args    []interface{}
 	Line    int
 	Column  int
+	Calls   []Call // innermost first, for errors that say how they were reached
+}
+
+// NewError creates an error with its English message from the catalogue
+func NewError(code diagnostic.Code, args ...interface{}) *Error {
+	return &Error{Message: diagnostic.Format(diagnostic.English, code, args...), Code: code, Args: args}
 }
 
 func (e *Error) Type() ObjectType { return ERROR_OBJ }
@@ -124,6 +283,18 @@ func (e *Error) Inspect() string {
 	return fmt.Sprintf("ERROR: %s", e.Message)
 }
 
+// Diagnostic converts the error for rendering
+func (e *Error) Diagnostic() diagnostic.Diagnostic {
+	return diagnostic.Diagnostic{
+		Severity: diagnostic.Error,
+		Code:     e.Code,
+		Args:     e.Args,
+		Message:  e.Message,
+		Line:     e.Line,
+		Column:   e.Column,
+	}
+}
+
 // Function represents a user-defined function
 type Function struct {
 	Name       string
@@ -177,26 +348,169 @@ type BuiltinFunction func(args ...Object) Object
 type Builtin struct {
 	Name string
 	Fn   BuiltinFunction
+	// Params, if set, are the types of the arguments Call accepts, so that
+	// Fn need not check them; an INTEGER is passed as a REAL parameter
+	Params []ObjectType
+	// Needs is the capability the builtin belongs to, if it can be disabled
+	Needs Capability
 }
 
 func (b *Builtin) Type() ObjectType { return BUILTIN_OBJ }
 func (b *Builtin) Inspect() string  { return "builtin function: " + b.Name }
 
+// Call checks args against Params, if set, and calls Fn
+func (b *Builtin) Call(args []Object) Object {
+	if b.Params == nil {
+		return b.Fn(args...)
+	}
+	if len(args) != len(b.Params) {
+		return NewError(diagnostic.BuiltinArgumentCount, b.Name, len(b.Params), len(args))
+	}
+	for idx, want := range b.Params {
+		if n, ok := args[idx].(*Integer); ok && want == REAL_OBJ {
+			args[idx] = &Real{Value: n.float()}
+		} else if args[idx].Type() != want {
+			return NewError(diagnostic.BuiltinArgumentType, idx+1, b.Name, want, args[idx].Type())
+		}
+	}
+	return b.Fn(args...)
+}
+
 // Array represents an array
+// Elements within the declared bounds are stored contiguously in row-major
+// order; any outside them are kept by index so that they still read back.
+// Storage is only allocated once an element is stored. Small arrays then
+// hold every element contiguously; larger ones start by keeping only the
+// elements stored, by position, and become contiguous once enough of them
+// are used for that to take less memory.
 type Array struct {
-	Elements   map[string]Object // key is index as string, e.g., "1" or "1,2"
 	Dimensions []ast.ArrayDimension
+	Default    Object // read from elements never stored; nil for NULL
+
+	size     int64             // number of elements within the bounds
+	elements []Object          // nil where nothing has been stored
+	sparse   map[int64]Object  // stored elements of a large array, by offset
+	outside  map[string]Object // key is index as string, e.g., "1" or "1,2"
+}
+
+const (
+	// maxEagerElements is the largest array made contiguous on first use
+	maxEagerElements = 1 << 12
+	// maxDenseElements is the largest array ever made contiguous
+	maxDenseElements = 1 << 24
+	// denseFraction is the share of a large array's elements that must be
+	// stored before it becomes contiguous, 1/denseFraction
+	denseFraction = 8
+)
+
+// NewArray creates an array with the given bounds and no elements stored
+func NewArray(dimensions []ast.ArrayDimension, def Object) *Array {
+	size := int64(1)
+	for _, d := range dimensions {
+		extent := max(int64(d.Upper)-int64(d.Lower)+1, 0)
+		if extent > 0 && size > math.MaxInt64/extent {
+			// Too many elements to address; every one is kept by index
+			return &Array{Dimensions: dimensions, Default: def}
+		}
+		size *= extent
+	}
+	return &Array{Dimensions: dimensions, Default: def, size: size}
 }
 
 func (a *Array) Type() ObjectType { return ARRAY_OBJ }
 func (a *Array) Inspect() string {
-	return fmt.Sprintf("ARRAY[%d elements]", len(a.Elements))
+	count := len(a.sparse) + len(a.outside)
+	for _, elem := range a.elements {
+		if elem != nil {
+			count++
+		}
+	}
+	return fmt.Sprintf("ARRAY[%d elements]", count)
+}
+
+// offset returns the position of an element within the bounds
+func (a *Array) offset(indices []int64) (int64, bool) {
+	if a.size == 0 || len(indices) != len(a.Dimensions) {
+		return 0, false
+	}
+	offset := int64(0)
+	for i, idx := range indices {
+		d := a.Dimensions[i]
+		if idx < int64(d.Lower) || idx > int64(d.Upper) {
+			return 0, false
+		}
+		offset = offset*int64(d.Upper-d.Lower+1) + idx - int64(d.Lower)
+	}
+	return offset, true
+}
+
+// Get returns the element at indices, or the array's Default if none has
+// been stored
+func (a *Array) Get(indices ...int64) Object {
+	offset, ok := a.offset(indices)
+	if !ok {
+		if value, found := a.outside[a.GetIndex(indices...)]; found {
+			return value
+		}
+		return a.Default
+	}
+	var value Object
+	if a.elements != nil {
+		value = a.elements[offset]
+	} else {
+		value = a.sparse[offset]
+	}
+	if value == nil {
+		return a.Default
+	}
+	return value
+}
+
+// Set stores an element at indices
+func (a *Array) Set(value Object, indices ...int64) {
+	if offset, ok := a.offset(indices); ok {
+		a.setOffset(offset, value)
+		return
+	}
+	if a.outside == nil {
+		a.outside = make(map[string]Object)
+	}
+	a.outside[a.GetIndex(indices...)] = value
+}
+
+// setOffset stores an element within the bounds
+func (a *Array) setOffset(offset int64, value Object) {
+	if a.elements == nil && a.size <= maxDenseElements &&
+		(a.size <= maxEagerElements || int64(len(a.sparse)) >= a.size/denseFraction) {
+		a.elements = make([]Object, a.size)
+		for off, elem := range a.sparse {
+			a.elements[off] = elem
+		}
+		a.sparse = nil
+	}
+	if a.elements != nil {
+		a.elements[offset] = value
+		return
+	}
+	if a.sparse == nil {
+		a.sparse = make(map[int64]Object)
+	}
+	a.sparse[offset] = value
+}
+
+// capacity is how many elements the array takes room for, those within its
+// bounds and any stored outside them
+func (a *Array) capacity() int64 {
+	return a.size + int64(len(a.outside))
 }
 
 func (a *Array) GetIndex(indices ...int64) string {
+	if len(indices) == 1 {
+		return strconv.FormatInt(indices[0], 10)
+	}
 	parts := make([]string, len(indices))
 	for i, idx := range indices {
-		parts[i] = fmt.Sprintf("%d", idx)
+		parts[i] = strconv.FormatInt(idx, 10)
 	}
 	return strings.Join(parts, ",")
 }
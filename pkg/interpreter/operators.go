@@ -0,0 +1,525 @@
+package interpreter
+
+import (
+	"fmt"
+	"math"
+	"math/big"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+)
+
+// Operator semantics live here rather than in the evaluator so that every
+// execution backend agrees on how values combine
+
+// Arithmetic selects how numbers behave
+// The zero value is the default: INTEGER arithmetic is 64-bit, and a result
+// that does not fit is an error rather than wrapping around, while REAL
+// values are binary floating point.
+type Arithmetic struct {
+	// BigIntegers carries INTEGER results on in arbitrary precision
+	BigIntegers bool
+	// DecimalReals computes REAL values as exact decimals, so that
+	// 0.1 + 0.2 is 0.3 rather than 0.30000000000000004
+	DecimalReals bool
+}
+
+// Fit returns result, or an overflow error if it is an INTEGER too large
+// for 64 bits and a does not carry those on, for values such as literals
+// and builtin results that are worked out whole
+func (a Arithmetic) Fit(result Object) Object {
+	if n, ok := result.(*Integer); ok && n.Big != nil && !a.BigIntegers {
+		return NewError(diagnostic.IntegerOverflow, n.Inspect())
+	}
+	return result
+}
+
+// UnaryOp applies a prefix operator ("-" or "NOT") to a value
+func UnaryOp(op string, right Object) Object {
+	return Arithmetic{}.Unary(op, right)
+}
+
+// BinaryOp applies an infix operator to two evaluated operands
+func BinaryOp(op string, left, right Object) Object {
+	return Arithmetic{}.Binary(op, left, right)
+}
+
+// Unary is UnaryOp under these arithmetic settings
+func (a Arithmetic) Unary(op string, right Object) Object {
+	switch op {
+	case "-":
+		return minusPrefix(right, a)
+	case "NOT":
+		return notPrefix(right)
+	default:
+		return NewError(diagnostic.UnknownOperator, op)
+	}
+}
+
+// Binary is BinaryOp under these arithmetic settings
+func (a Arithmetic) Binary(op string, left, right Object) Object {
+	switch {
+	case left.Type() == INTEGER_OBJ && right.Type() == INTEGER_OBJ:
+		return integerInfix(op, left.(*Integer), right.(*Integer), a)
+	case left.Type() == REAL_OBJ || right.Type() == REAL_OBJ:
+		return realInfix(op, left, right, a)
+	case left.Type() == STRING_OBJ && right.Type() == STRING_OBJ:
+		return stringInfix(op, left, right)
+	case left.Type() == BOOLEAN_OBJ && right.Type() == BOOLEAN_OBJ:
+		return booleanInfix(op, left, right)
+	case op == "&":
+		// String concatenation - convert operands to strings
+		return concatenate(left, right)
+	case op == "=":
+		return NewBoolean(ObjectsEqual(left, right))
+	case op == "<>":
+		return NewBoolean(!ObjectsEqual(left, right))
+	default:
+		return NewError(diagnostic.TypeMismatch, left.Type(), op, right.Type())
+	}
+}
+
+func minusPrefix(right Object, a Arithmetic) Object {
+	switch obj := right.(type) {
+	case *Integer:
+		if obj.Big == nil && obj.Value != math.MinInt64 {
+			return NewInteger(-obj.Value)
+		}
+		if !a.BigIntegers {
+			return NewError(diagnostic.IntegerOverflow, "-"+obj.Inspect())
+		}
+		return NewBigInteger(new(big.Int).Neg(obj.bigValue()))
+	case *Real:
+		if obj.Dec != nil {
+			return NewDecimal(new(big.Rat).Neg(obj.Dec))
+		}
+		return &Real{Value: -obj.Value}
+	default:
+		return NewError(diagnostic.UnknownOperator, "-"+string(right.Type()))
+	}
+}
+
+func notPrefix(right Object) Object {
+	switch obj := right.(type) {
+	case *Boolean:
+		return NewBoolean(!obj.Value)
+	default:
+		return NewError(diagnostic.UnknownOperator, "NOT "+string(right.Type()))
+	}
+}
+
+func integerInfix(op string, left, right *Integer, a Arithmetic) Object {
+	if left.Big != nil || right.Big != nil {
+		return bigInfix(op, left, right, a)
+	}
+	leftVal := left.Value
+	rightVal := right.Value
+
+	switch op {
+	case "+":
+		if sum := leftVal + rightVal; (leftVal^sum)&(rightVal^sum) >= 0 {
+			return NewInteger(sum)
+		}
+		return overflow(op, left, right, a)
+	case "-":
+		if diff := leftVal - rightVal; (leftVal^rightVal)&(leftVal^diff) >= 0 {
+			return NewInteger(diff)
+		}
+		return overflow(op, left, right, a)
+	case "*":
+		if product, ok := multiply(leftVal, rightVal); ok {
+			return NewInteger(product)
+		}
+		return overflow(op, left, right, a)
+	case "/":
+		if rightVal == 0 {
+			return NewError(diagnostic.DivisionByZero)
+		}
+		if a.DecimalReals {
+			return roundedDecimal(big.NewRat(leftVal, rightVal))
+		}
+		return &Real{Value: float64(leftVal) / float64(rightVal)}
+	case "DIV":
+		if rightVal == 0 {
+			return NewError(diagnostic.DivisionByZero)
+		}
+		if leftVal == math.MinInt64 && rightVal == -1 {
+			return overflow(op, left, right, a)
+		}
+		return NewInteger(leftVal / rightVal)
+	case "MOD":
+		if rightVal == 0 {
+			return NewError(diagnostic.DivisionByZero)
+		}
+		return NewInteger(leftVal % rightVal)
+	case "<":
+		return NewBoolean(leftVal < rightVal)
+	case ">":
+		return NewBoolean(leftVal > rightVal)
+	case "<=":
+		return NewBoolean(leftVal <= rightVal)
+	case ">=":
+		return NewBoolean(leftVal >= rightVal)
+	case "=":
+		return NewBoolean(leftVal == rightVal)
+	case "<>":
+		return NewBoolean(leftVal != rightVal)
+	default:
+		return NewError(diagnostic.UnknownOperator, fmt.Sprintf("%s %s %s", left.Type(), op, right.Type()))
+	}
+}
+
+// multiply returns a * b, reporting false if it does not fit in 64 bits
+func multiply(a, b int64) (int64, bool) {
+	if a == 0 || b == 0 {
+		return 0, true
+	}
+	product := a * b
+	if product/b != a || (a == -1 && b == math.MinInt64) || (b == -1 && a == math.MinInt64) {
+		return 0, false
+	}
+	return product, true
+}
+
+// overflow reports an INTEGER result too large for 64 bits, or computes it
+// in arbitrary precision if that is allowed
+func overflow(op string, left, right *Integer, a Arithmetic) Object {
+	if !a.BigIntegers {
+		return NewError(diagnostic.IntegerOverflow, fmt.Sprintf("%s %s %s", left.Inspect(), op, right.Inspect()))
+	}
+	return bigInfix(op, left, right, a)
+}
+
+func bigInfix(op string, left, right *Integer, a Arithmetic) Object {
+	leftVal := left.bigValue()
+	rightVal := right.bigValue()
+
+	switch op {
+	case "+":
+		return NewBigInteger(new(big.Int).Add(leftVal, rightVal))
+	case "-":
+		return NewBigInteger(new(big.Int).Sub(leftVal, rightVal))
+	case "*":
+		return NewBigInteger(new(big.Int).Mul(leftVal, rightVal))
+	case "/", "DIV", "MOD":
+		if rightVal.Sign() == 0 {
+			return NewError(diagnostic.DivisionByZero)
+		}
+		switch op {
+		case "/":
+			quotient := new(big.Rat).SetFrac(leftVal, rightVal)
+			if a.DecimalReals {
+				return roundedDecimal(quotient)
+			}
+			f, _ := quotient.Float64()
+			return &Real{Value: f}
+		case "DIV":
+			return NewBigInteger(new(big.Int).Quo(leftVal, rightVal))
+		default:
+			return NewBigInteger(new(big.Int).Rem(leftVal, rightVal))
+		}
+	case "<":
+		return NewBoolean(leftVal.Cmp(rightVal) < 0)
+	case ">":
+		return NewBoolean(leftVal.Cmp(rightVal) > 0)
+	case "<=":
+		return NewBoolean(leftVal.Cmp(rightVal) <= 0)
+	case ">=":
+		return NewBoolean(leftVal.Cmp(rightVal) >= 0)
+	case "=":
+		return NewBoolean(leftVal.Cmp(rightVal) == 0)
+	case "<>":
+		return NewBoolean(leftVal.Cmp(rightVal) != 0)
+	default:
+		return NewError(diagnostic.UnknownOperator, fmt.Sprintf("%s %s %s", left.Type(), op, right.Type()))
+	}
+}
+
+func realInfix(op string, left, right Object, a Arithmetic) Object {
+	if a.DecimalReals {
+		if result := decimalInfix(op, decimalValue(left), decimalValue(right)); result != nil {
+			return result
+		}
+	}
+
+	var leftVal, rightVal float64
+
+	switch l := left.(type) {
+	case *Real:
+		leftVal = l.Value
+	case *Integer:
+		leftVal = l.float()
+	}
+
+	switch r := right.(type) {
+	case *Real:
+		rightVal = r.Value
+	case *Integer:
+		rightVal = r.float()
+	}
+
+	switch op {
+	case "+":
+		return &Real{Value: leftVal + rightVal}
+	case "-":
+		return &Real{Value: leftVal - rightVal}
+	case "*":
+		return &Real{Value: leftVal * rightVal}
+	case "/":
+		if rightVal == 0 {
+			return NewError(diagnostic.DivisionByZero)
+		}
+		return &Real{Value: leftVal / rightVal}
+	case "<":
+		return NewBoolean(leftVal < rightVal)
+	case ">":
+		return NewBoolean(leftVal > rightVal)
+	case "<=":
+		return NewBoolean(leftVal <= rightVal)
+	case ">=":
+		return NewBoolean(leftVal >= rightVal)
+	case "=":
+		return NewBoolean(leftVal == rightVal)
+	case "<>":
+		return NewBoolean(leftVal != rightVal)
+	default:
+		return NewError(diagnostic.UnknownOperator, fmt.Sprintf("%s %s %s", left.Type(), op, right.Type()))
+	}
+}
+
+// decimalInfix applies op to two exact decimals, returning nil if either
+// is missing or op is not a REAL operator, so that realInfix reports it
+func decimalInfix(op string, leftVal, rightVal *big.Rat) Object {
+	if leftVal == nil || rightVal == nil {
+		return nil
+	}
+
+	switch op {
+	case "+":
+		return roundedDecimal(new(big.Rat).Add(leftVal, rightVal))
+	case "-":
+		return roundedDecimal(new(big.Rat).Sub(leftVal, rightVal))
+	case "*":
+		return roundedDecimal(new(big.Rat).Mul(leftVal, rightVal))
+	case "/":
+		if rightVal.Sign() == 0 {
+			return NewError(diagnostic.DivisionByZero)
+		}
+		return roundedDecimal(new(big.Rat).Quo(leftVal, rightVal))
+	case "<":
+		return NewBoolean(leftVal.Cmp(rightVal) < 0)
+	case ">":
+		return NewBoolean(leftVal.Cmp(rightVal) > 0)
+	case "<=":
+		return NewBoolean(leftVal.Cmp(rightVal) <= 0)
+	case ">=":
+		return NewBoolean(leftVal.Cmp(rightVal) >= 0)
+	case "=":
+		return NewBoolean(leftVal.Cmp(rightVal) == 0)
+	case "<>":
+		return NewBoolean(leftVal.Cmp(rightVal) != 0)
+	default:
+		return nil
+	}
+}
+
+// decimalPlaces is how many digits after the point a decimal REAL keeps,
+// so that a loop dividing again and again, as Newton's method does, works
+// with numbers of a bounded size rather than exact quotients that grow
+// with every step
+const decimalPlaces = 30
+
+var decimalScale = new(big.Int).Exp(big.NewInt(10), big.NewInt(decimalPlaces), nil)
+
+// roundedDecimal returns v as a REAL rounded to decimalPlaces digits after
+// the point, halves away from zero
+func roundedDecimal(v *big.Rat) *Real {
+	if v.IsInt() {
+		return NewDecimal(v)
+	}
+	scaled := new(big.Int).Mul(v.Num(), decimalScale)
+	q, r := new(big.Int).QuoRem(scaled, v.Denom(), new(big.Int))
+	if r.Abs(r).Lsh(r, 1).Cmp(v.Denom()) >= 0 {
+		q.Add(q, big.NewInt(int64(v.Sign())))
+	}
+	return NewDecimal(new(big.Rat).SetFrac(q, decimalScale))
+}
+
+// decimalValue returns a number as an exact decimal, or nil if it is not a
+// finite number
+func decimalValue(obj Object) *big.Rat {
+	switch o := obj.(type) {
+	case *Integer:
+		return new(big.Rat).SetInt(o.bigValue())
+	case *Real:
+		return o.decimal()
+	}
+	return nil
+}
+
+func stringInfix(op string, left, right Object) Object {
+	leftVal := left.(*String).Value
+	rightVal := right.(*String).Value
+
+	switch op {
+	case "&":
+		return &String{Value: leftVal + rightVal}
+	case "=":
+		return NewBoolean(leftVal == rightVal)
+	case "<>":
+		return NewBoolean(leftVal != rightVal)
+	case "<":
+		return NewBoolean(leftVal < rightVal)
+	case ">":
+		return NewBoolean(leftVal > rightVal)
+	case "<=":
+		return NewBoolean(leftVal <= rightVal)
+	case ">=":
+		return NewBoolean(leftVal >= rightVal)
+	default:
+		return NewError(diagnostic.UnknownOperator, fmt.Sprintf("%s %s %s", left.Type(), op, right.Type()))
+	}
+}
+
+func booleanInfix(op string, left, right Object) Object {
+	leftVal := left.(*Boolean).Value
+	rightVal := right.(*Boolean).Value
+
+	switch op {
+	case "AND":
+		return NewBoolean(leftVal && rightVal)
+	case "OR":
+		return NewBoolean(leftVal || rightVal)
+	case "=":
+		return NewBoolean(leftVal == rightVal)
+	case "<>":
+		return NewBoolean(leftVal != rightVal)
+	default:
+		return NewError(diagnostic.UnknownOperator, fmt.Sprintf("%s %s %s", left.Type(), op, right.Type()))
+	}
+}
+
+func concatenate(left, right Object) Object {
+	rightStr := objectToString(right)
+	if s, ok := left.(*String); ok {
+		return s.concat(rightStr)
+	}
+	return &String{Value: objectToString(left) + rightStr}
+}
+
+func objectToString(obj Object) string {
+	switch o := obj.(type) {
+	case *String:
+		return o.Value
+	case *Char:
+		return string(o.Value)
+	case *Integer:
+		return o.Inspect()
+	case *Real:
+		return o.Inspect()
+	case *Boolean:
+		if o.Value {
+			return "TRUE"
+		}
+		return "FALSE"
+	default:
+		return obj.Inspect()
+	}
+}
+
+// ValueInRange reports whether value lies in the CASE range start TO end
+func ValueInRange(value, start, end Object) bool {
+	switch v := value.(type) {
+	case *Integer:
+		s, sok := start.(*Integer)
+		e, eok := end.(*Integer)
+		if sok && eok {
+			return v.Value >= s.Value && v.Value <= e.Value
+		}
+	case *Char:
+		s, sok := start.(*Char)
+		e, eok := end.(*Char)
+		if sok && eok {
+			return v.Value >= s.Value && v.Value <= e.Value
+		}
+	}
+	return false
+}
+
+// ObjectsEqual reports whether two values of the same type are equal
+func ObjectsEqual(a, b Object) bool {
+	switch av := a.(type) {
+	case *Integer:
+		if bv, ok := b.(*Integer); ok {
+			if av.Big != nil || bv.Big != nil {
+				return av.bigValue().Cmp(bv.bigValue()) == 0
+			}
+			return av.Value == bv.Value
+		}
+	case *Real:
+		if bv, ok := b.(*Real); ok {
+			if av.Dec != nil || bv.Dec != nil {
+				ad, bd := av.decimal(), bv.decimal()
+				return ad != nil && bd != nil && ad.Cmp(bd) == 0
+			}
+			return av.Value == bv.Value
+		}
+	case *String:
+		if bv, ok := b.(*String); ok {
+			return av.Value == bv.Value
+		}
+	case *Char:
+		if bv, ok := b.(*Char); ok {
+			return av.Value == bv.Value
+		}
+	case *Boolean:
+		if bv, ok := b.(*Boolean); ok {
+			return av.Value == bv.Value
+		}
+	}
+	return false
+}
+
+// IsTruthy reports whether a condition value selects a branch
+func IsTruthy(obj Object) bool {
+	switch obj := obj.(type) {
+	case *Null:
+		return false
+	case *Boolean:
+		return obj.Value
+	default:
+		return true
+	}
+}
+
+// ElementDefault returns the value the elements of an array of elementType
+// hold before anything is stored in them, or nil where that is NULL
+func ElementDefault(elementType ast.DataType) Object {
+	if p, ok := elementType.(*ast.PrimitiveType); ok {
+		if value := DefaultValue(p.Name); value.Type() != NULL_OBJ {
+			return value
+		}
+	}
+	return nil
+}
+
+// DefaultValue returns the value a DECLARE of the named primitive type starts
+// with, or NULL for anything else
+func DefaultValue(typeName string) Object {
+	switch typeName {
+	case "INTEGER":
+		return NewInteger(0)
+	case "REAL":
+		return &Real{Value: 0.0}
+	case "STRING":
+		return &String{Value: ""}
+	case "CHAR":
+		return &Char{Value: ' '}
+	case "BOOLEAN":
+		return NewBoolean(false)
+	case "DATE":
+		return &Date{Day: 1, Month: 1, Year: 1970}
+	default:
+		return &Null{}
+	}
+}
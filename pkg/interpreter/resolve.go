@@ -0,0 +1,180 @@
+package interpreter
+
+import "github.com/andrinoff/cambridge-lang/pkg/ast"
+
+// scope mirrors a frame the interpreter creates at run time: a call to a
+// routine, whose slots are its parameters, or a FOR loop, whose one slot
+// is its variable
+type scope struct {
+	names    []string
+	declared map[string]bool // names a statement in the frame may declare
+	outer    *scope          // nil where the enclosing frames are not known
+}
+
+// Resolve marks every identifier in program that always refers to a
+// parameter or FOR variable with the frame and slot holding it, so the
+// interpreter can read it by index instead of looking its name up
+// Names that a statement could shadow, such as a DECLARE inside the loop,
+// are left to be looked up by name. It also marks the calls a routine makes
+// to itself as its last action, for SetTailCalls. Resolve must be called
+// before the program is evaluated if it is to help, and must not run while
+// another goroutine evaluates the same program.
+func Resolve(program *ast.Program) {
+	for _, stmt := range program.Statements {
+		resolve(stmt, nil)
+	}
+}
+
+func resolve(node ast.Node, s *scope) {
+	ast.Inspect(node, func(n ast.Node) bool {
+		switch n := n.(type) {
+		case *ast.Identifier:
+			resolveIdentifier(n, s)
+		case *ast.DeclareStatement:
+			return false
+		case *ast.ConstantStatement:
+			resolve(n.Value, s)
+			return false
+		case *ast.ForStatement:
+			resolve(n.Start, s)
+			resolve(n.End, s)
+			resolve(n.Step, s)
+			resolveBody(n.Body, &scope{
+				names:    []string{n.Variable.Value},
+				declared: declarations(n.Body),
+				outer:    s,
+			})
+			return false
+		case *ast.ProcedureStatement:
+			resolveBody(n.Body, routineScope(n.Parameters, n.Body))
+			markTailCalls(n.Name, n.Body)
+			return false
+		case *ast.FunctionStatement:
+			resolveBody(n.Body, routineScope(n.Parameters, n.Body))
+			markTailReturns(n.Name, n.Body)
+			return false
+		case *ast.ClassStatement:
+			// Methods run in an environment built around the instance and
+			// the caller, so only their FOR loops can be resolved
+			for _, member := range n.Members {
+				switch m := member.(type) {
+				case *ast.ProcedureStatement:
+					resolveBody(m.Body, nil)
+				case *ast.FunctionStatement:
+					resolveBody(m.Body, nil)
+				}
+			}
+			return false
+		}
+		return true
+	})
+}
+
+func resolveBody(body []ast.Statement, s *scope) {
+	for _, stmt := range body {
+		resolve(stmt, s)
+	}
+}
+
+// routineScope returns the frame of a routine called outside any class; it
+// is enclosed by wherever the routine was defined, which is not tracked
+func routineScope(params []ast.Parameter, body []ast.Statement) *scope {
+	names := make([]string, len(params))
+	for idx, param := range params {
+		names[idx] = param.Name
+	}
+	return &scope{names: names, declared: declarations(body)}
+}
+
+func resolveIdentifier(id *ast.Identifier, s *scope) {
+	id.Resolved = false
+	for depth := 0; s != nil; depth, s = depth+1, s.outer {
+		if s.declared[id.Value] {
+			return
+		}
+		for slot, name := range s.names {
+			if name == id.Value {
+				id.Resolved, id.Depth, id.Slot = true, depth, slot
+				return
+			}
+		}
+	}
+}
+
+// declarations returns the names that body may declare in its own frame,
+// not counting those inside nested FOR loops and routines
+func declarations(body []ast.Statement) map[string]bool {
+	names := map[string]bool{}
+	for _, stmt := range body {
+		ast.Inspect(stmt, func(n ast.Node) bool {
+			switch n := n.(type) {
+			case *ast.DeclareStatement:
+				names[n.Name.Value] = true
+			case *ast.ConstantStatement:
+				names[n.Name.Value] = true
+			case *ast.ProcedureStatement:
+				names[n.Name] = true
+				return false
+			case *ast.FunctionStatement:
+				names[n.Name] = true
+				return false
+			case *ast.ClassStatement:
+				names[n.Name] = true
+				return false
+			case *ast.TypeStatement:
+				if enum, ok := n.Definition.(*ast.EnumType); ok {
+					for _, v := range enum.Values {
+						names[v] = true
+					}
+				}
+			case *ast.ForStatement, ast.Expression:
+				return false
+			}
+			return true
+		})
+	}
+	return names
+}
+
+// markTailCalls marks a CALL of the procedure name that is the last
+// statement to run in body, looking into the branches of a final IF or CASE
+func markTailCalls(name string, body []ast.Statement) {
+	if len(body) == 0 {
+		return
+	}
+	switch s := body[len(body)-1].(type) {
+	case *ast.CallStatement:
+		s.Tail = callsItself(s.Name, name)
+	case *ast.IfStatement:
+		markTailCalls(name, s.Consequence)
+		markTailCalls(name, s.Alternative)
+	case *ast.CaseStatement:
+		for _, c := range s.Cases {
+			markTailCalls(name, c.Body)
+		}
+		markTailCalls(name, s.Otherwise)
+	}
+}
+
+// markTailReturns marks every RETURN of a call to the function name, which
+// leaves nothing to do after the call
+func markTailReturns(name string, body []ast.Statement) {
+	for _, stmt := range body {
+		ast.Inspect(stmt, func(n ast.Node) bool {
+			switch n := n.(type) {
+			case *ast.ReturnStatement:
+				if call, ok := n.Value.(*ast.CallExpression); ok {
+					call.Tail = callsItself(call.Function, name)
+				}
+			case *ast.ProcedureStatement, *ast.FunctionStatement, *ast.ClassStatement, ast.Expression:
+				return false
+			}
+			return true
+		})
+	}
+}
+
+func callsItself(callee ast.Expression, name string) bool {
+	id, ok := callee.(*ast.Identifier)
+	return ok && id.Value == name
+}
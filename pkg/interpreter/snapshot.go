@@ -0,0 +1,359 @@
+package interpreter
+
+import (
+	"bufio"
+	"encoding/gob"
+	"fmt"
+	"io"
+	"math/big"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+)
+
+// Snapshot is the state of a program between two of its top-level
+// statements: its global variables, its open files and the statement it
+// runs next
+// Routines, classes and types are not kept, since Restore defines them
+// again from the program, and neither is INPUT, which carries on from
+// wherever the restoring interpreter's input is.
+type Snapshot struct {
+	data snapshotData
+}
+
+// snapshotData is what Encode writes
+// Values refer to each other by index, so that an array or object shared
+// by two variables is still shared once restored.
+type snapshotData struct {
+	Next      int
+	Globals   map[string]int
+	Constants []string
+	Values    []snapshotValue
+	Files     []snapshotFile
+}
+
+type snapshotValue struct {
+	Kind     ObjectType
+	Int      int64
+	Big      *big.Int
+	Real     float64
+	Dec      *big.Rat
+	Str      string
+	Bool     bool
+	Date     Date
+	Dims     []ast.ArrayDimension
+	Default  int           // index of the array's Default, or -1
+	Elements map[int64]int // array elements within the bounds, by offset
+	Outside  map[string]int
+	TypeName string // of a record, or the class of an instance
+	Fields   map[string]int
+}
+
+// snapshotFile is an open file, reopened through the FileSystem on Restore
+type snapshotFile struct {
+	Name  string
+	Mode  string
+	Lines int // read so far, skipped when a READ file is reopened
+	AtEOF bool
+}
+
+// Snapshot captures the program's state
+// It fails while a statement is running, for example from a step inside a
+// loop or routine, and for values that cannot be kept, such as methods.
+func (i *Interpreter) Snapshot() (*Snapshot, error) {
+	if i.depth > 0 {
+		return nil, fmt.Errorf("a snapshot can only be taken between top-level statements")
+	}
+	w := snapshotWriter{seen: map[Object]int{}}
+	data := snapshotData{Next: i.next, Globals: map[string]int{}}
+	for name, value := range i.env.store {
+		switch value.Type() {
+		case FUNCTION_OBJ, PROCEDURE_OBJ, BUILTIN_OBJ, CLASS_OBJ:
+			continue
+		}
+		idx, err := w.add(value)
+		if err != nil {
+			return nil, fmt.Errorf("cannot keep %s: %w", name, err)
+		}
+		data.Globals[name] = idx
+	}
+	for name := range i.env.constants {
+		data.Constants = append(data.Constants, name)
+	}
+	for name, fs := range i.files {
+		data.Files = append(data.Files, snapshotFile{Name: name, Mode: fs.mode, Lines: fs.lines, AtEOF: fs.atEOF})
+	}
+	data.Values = w.values
+	return &Snapshot{data: data}, nil
+}
+
+// Restore replaces the interpreter's state with s, which must have been
+// taken from a run of program, so that Resume carries on from there
+// The routines, classes and types defined before the snapshot are defined
+// again, and files are reopened, with READ files past the lines already
+// read. A snapshot may be restored any number of times, each restore
+// getting its own copy of the values.
+func (i *Interpreter) Restore(program *ast.Program, s *Snapshot) error {
+	for _, fs := range i.files {
+		fs.file.Close()
+	}
+	i.env = NewEnvironment()
+	i.files = make(map[string]*fileState)
+	i.calls = nil
+
+	next := min(s.data.Next, len(program.Statements))
+	for _, stmt := range program.Statements[:next] {
+		switch stmt.(type) {
+		case *ast.ProcedureStatement, *ast.FunctionStatement, *ast.ClassStatement, *ast.TypeStatement:
+			if err, ok := i.execStatement(stmt, i.env).(*Error); ok {
+				return fmt.Errorf("cannot define again: %s", err.Message)
+			}
+		}
+	}
+
+	r := snapshotReader{data: &s.data, env: i.env, objects: make([]Object, len(s.data.Values))}
+	for name, idx := range s.data.Globals {
+		value, err := r.object(idx)
+		if err != nil {
+			return fmt.Errorf("cannot restore %s: %w", name, err)
+		}
+		i.env.put(name, value)
+	}
+	for _, name := range s.data.Constants {
+		if i.env.constants == nil {
+			i.env.constants = make(map[string]bool)
+		}
+		i.env.constants[name] = true
+	}
+
+	for _, f := range s.data.Files {
+		fs := &fileState{mode: f.Mode, lines: f.Lines, atEOF: f.AtEOF}
+		if f.Mode == "READ" {
+			file, err := i.fs.Open(f.Name)
+			if err != nil {
+				return err
+			}
+			fs.file, fs.scanner = file, bufio.NewScanner(file)
+			for n := 0; n < f.Lines && fs.scanner.Scan(); n++ {
+			}
+		} else {
+			// Reopen for APPEND so what was written before is kept
+			file, err := i.fs.Append(f.Name)
+			if err != nil {
+				return err
+			}
+			fs.file, fs.writer = file, file
+		}
+		i.files[f.Name] = fs
+	}
+
+	i.next = next
+	return nil
+}
+
+// Encode writes s so that DecodeSnapshot can read it back, in this or
+// another process
+func (s *Snapshot) Encode(w io.Writer) error {
+	return gob.NewEncoder(w).Encode(s.data)
+}
+
+// DecodeSnapshot reads a snapshot written by Encode
+func DecodeSnapshot(r io.Reader) (*Snapshot, error) {
+	var s Snapshot
+	if err := gob.NewDecoder(r).Decode(&s.data); err != nil {
+		return nil, err
+	}
+	return &s, nil
+}
+
+// snapshotWriter flattens values into a snapshot
+type snapshotWriter struct {
+	values []snapshotValue
+	seen   map[Object]int // arrays, records and instances already added
+}
+
+// add appends obj and the values inside it, returning its index
+func (w *snapshotWriter) add(obj Object) (int, error) {
+	if obj == nil {
+		return -1, nil
+	}
+	if idx, ok := w.seen[obj]; ok {
+		return idx, nil
+	}
+	idx := len(w.values)
+	w.values = append(w.values, snapshotValue{Kind: obj.Type(), Default: -1})
+
+	v := snapshotValue{Kind: obj.Type(), Default: -1}
+	switch obj := obj.(type) {
+	case *Integer:
+		v.Int, v.Big = obj.Value, obj.Big
+	case *Real:
+		v.Real, v.Dec = obj.Value, obj.Dec
+	case *String:
+		v.Str = obj.Value
+	case *Char:
+		v.Int = int64(obj.Value)
+	case *Boolean:
+		v.Bool = obj.Value
+	case *Date:
+		v.Date = *obj
+	case *Null:
+	case *Array:
+		w.seen[obj] = idx
+		v.Dims = obj.Dimensions
+		var err error
+		if v.Default, err = w.add(obj.Default); err != nil {
+			return 0, err
+		}
+		v.Elements = map[int64]int{}
+		for offset, elem := range obj.elements {
+			if elem != nil {
+				if v.Elements[int64(offset)], err = w.add(elem); err != nil {
+					return 0, err
+				}
+			}
+		}
+		for offset, elem := range obj.sparse {
+			if v.Elements[offset], err = w.add(elem); err != nil {
+				return 0, err
+			}
+		}
+		v.Outside = map[string]int{}
+		for key, elem := range obj.outside {
+			if v.Outside[key], err = w.add(elem); err != nil {
+				return 0, err
+			}
+		}
+	case *Record:
+		w.seen[obj] = idx
+		v.TypeName = obj.TypeName
+		if err := w.addFields(&v, obj.Fields); err != nil {
+			return 0, err
+		}
+	case *Instance:
+		w.seen[obj] = idx
+		v.TypeName = obj.Class.Name
+		if err := w.addFields(&v, obj.Fields); err != nil {
+			return 0, err
+		}
+	default:
+		return 0, fmt.Errorf("%s values cannot be kept in a snapshot", obj.Type())
+	}
+	w.values[idx] = v
+	return idx, nil
+}
+
+func (w *snapshotWriter) addFields(v *snapshotValue, fields map[string]Object) error {
+	v.Fields = map[string]int{}
+	for name, field := range fields {
+		idx, err := w.add(field)
+		if err != nil {
+			return err
+		}
+		v.Fields[name] = idx
+	}
+	return nil
+}
+
+// snapshotReader rebuilds the values of a snapshot
+type snapshotReader struct {
+	data    *snapshotData
+	env     *Environment // holding the classes instances belong to
+	objects []Object     // rebuilt so far, by index
+}
+
+func (r *snapshotReader) object(idx int) (Object, error) {
+	if idx < 0 {
+		return nil, nil
+	}
+	if idx >= len(r.data.Values) {
+		return nil, fmt.Errorf("damaged snapshot")
+	}
+	if obj := r.objects[idx]; obj != nil {
+		return obj, nil
+	}
+
+	v := r.data.Values[idx]
+	var obj Object
+	switch v.Kind {
+	case INTEGER_OBJ:
+		if v.Big != nil {
+			obj = NewBigInteger(v.Big)
+		} else {
+			obj = NewInteger(v.Int)
+		}
+	case REAL_OBJ:
+		obj = &Real{Value: v.Real, Dec: v.Dec}
+	case STRING_OBJ:
+		obj = &String{Value: v.Str}
+	case CHAR_OBJ:
+		obj = &Char{Value: rune(v.Int)}
+	case BOOLEAN_OBJ:
+		obj = NewBoolean(v.Bool)
+	case DATE_OBJ:
+		date := v.Date
+		obj = &date
+	case NULL_OBJ:
+		obj = &Null{}
+	case ARRAY_OBJ:
+		arr := NewArray(v.Dims, nil)
+		r.objects[idx] = arr
+		def, err := r.object(v.Default)
+		if err != nil {
+			return nil, err
+		}
+		arr.Default = def
+		for offset, elemIdx := range v.Elements {
+			elem, err := r.object(elemIdx)
+			if err != nil {
+				return nil, err
+			}
+			if offset < 0 || offset >= arr.size {
+				return nil, fmt.Errorf("damaged snapshot")
+			}
+			arr.setOffset(offset, elem)
+		}
+		for key, elemIdx := range v.Outside {
+			elem, err := r.object(elemIdx)
+			if err != nil {
+				return nil, err
+			}
+			if arr.outside == nil {
+				arr.outside = make(map[string]Object)
+			}
+			arr.outside[key] = elem
+		}
+		return arr, nil
+	case RECORD_OBJ:
+		rec := &Record{TypeName: v.TypeName}
+		r.objects[idx] = rec
+		fields, err := r.fields(v.Fields)
+		rec.Fields = fields
+		return rec, err
+	case INSTANCE_OBJ:
+		class, ok := r.env.Get(v.TypeName)
+		if !ok || class.Type() != CLASS_OBJ {
+			return nil, fmt.Errorf("class %s is not defined before the snapshot", v.TypeName)
+		}
+		inst := &Instance{Class: class.(*Class)}
+		r.objects[idx] = inst
+		fields, err := r.fields(v.Fields)
+		inst.Fields = fields
+		return inst, err
+	default:
+		return nil, fmt.Errorf("damaged snapshot")
+	}
+	r.objects[idx] = obj
+	return obj, nil
+}
+
+func (r *snapshotReader) fields(indices map[string]int) (map[string]Object, error) {
+	fields := make(map[string]Object, len(indices))
+	for name, idx := range indices {
+		field, err := r.object(idx)
+		if err != nil {
+			return nil, err
+		}
+		fields[name] = field
+	}
+	return fields, nil
+}
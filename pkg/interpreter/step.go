@@ -0,0 +1,128 @@
+package interpreter
+
+import (
+	"iter"
+	"sort"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+)
+
+// Step is the state of a program just after one of its statements ran
+type Step struct {
+	Statement    ast.Statement
+	Line, Column int
+	Changed      []Variable // variables in scope that are new or hold a different value, by name
+	Calls        []Call     // outermost first
+	Input        []string   // lines INPUT read while the statement ran
+	Output       string     // what OUTPUT wrote while the statement ran
+	Err          *Error     // set on the last step if the statement failed
+}
+
+// Variable is a named value in scope at a step
+type Variable struct {
+	Name  string
+	Value Object
+}
+
+// stepper reports each statement to the iterator running the program
+type stepper struct {
+	yield  func(Step) bool
+	values map[string]string // what each variable in scope showed at the last step
+	input  []string          // read since the last step
+	output strings.Builder   // written since the last step
+	halted bool              // the iterator's caller stopped asking for steps
+}
+
+// Steps returns an iterator that runs program, yielding after every
+// statement that does something itself, so visualizers, trace tables and
+// debuggers can follow along
+// IF, CASE and loop statements are not steps, as the statements inside them
+// are. The program stops where it is if the loop over the steps breaks, and
+// runs no further once a step reports an error. Values are compared by how
+// they are shown, so a step costs time in proportion to the data in scope.
+func (i *Interpreter) Steps(program *ast.Program) iter.Seq[Step] {
+	return i.steps(program, i.Eval)
+}
+
+// ResumeSteps is Steps for Resume, carrying on from a restored snapshot
+func (i *Interpreter) ResumeSteps(program *ast.Program) iter.Seq[Step] {
+	return i.steps(program, i.Resume)
+}
+
+func (i *Interpreter) steps(program *ast.Program, run func(*ast.Program) Object) iter.Seq[Step] {
+	return func(yield func(Step) bool) {
+		i.stepper = &stepper{yield: yield, values: map[string]string{}}
+		defer func() { i.stepper = nil }()
+		run(program)
+	}
+}
+
+// step reports stmt, which has just run in env with result, and reports
+// whether the program should go on
+func (i *Interpreter) step(stmt ast.Statement, env *Environment, result Object) bool {
+	s := i.stepper
+	if s.halted {
+		return false
+	}
+	err, failed := result.(*Error)
+	if !failed && compound(stmt) {
+		return true
+	}
+
+	step := Step{Statement: stmt, Calls: i.CallStack(), Input: s.input, Output: s.output.String()}
+	step.Line, step.Column = ast.Position(stmt)
+	s.input = nil
+	s.output.Reset()
+	if failed {
+		step.Err = err
+	}
+	for name, value := range visible(env) {
+		shown := value.Inspect()
+		if previous, ok := s.values[name]; !ok || previous != shown {
+			step.Changed = append(step.Changed, Variable{Name: name, Value: value})
+			s.values[name] = shown
+		}
+	}
+	sort.Slice(step.Changed, func(a, b int) bool { return step.Changed[a].Name < step.Changed[b].Name })
+
+	if !s.yield(step) || failed {
+		s.halted = true
+		return false
+	}
+	return true
+}
+
+// compound reports whether stmt only runs the statements inside it
+func compound(stmt ast.Statement) bool {
+	switch stmt.(type) {
+	case *ast.IfStatement, *ast.CaseStatement, *ast.ForStatement, *ast.WhileStatement, *ast.RepeatStatement:
+		return true
+	}
+	return false
+}
+
+// visible returns the data a statement running in env can see by name,
+// leaving out routines and classes
+func visible(env *Environment) map[string]Object {
+	vars := map[string]Object{}
+	add := func(name string, value Object) {
+		if _, shadowed := vars[name]; shadowed || value == nil {
+			return
+		}
+		switch value.Type() {
+		case FUNCTION_OBJ, PROCEDURE_OBJ, BUILTIN_OBJ, CLASS_OBJ:
+			return
+		}
+		vars[name] = value
+	}
+	for e := env; e != nil; e = e.outer {
+		for idx, name := range e.names {
+			add(name, e.slots[idx])
+		}
+		for name, value := range e.store {
+			add(name, value)
+		}
+	}
+	return vars
+}
@@ -6,9 +6,12 @@ import (
 	"strings"
 	"unicode"
 
+	"github.com/andrinoff/cambridge-lang/pkg/logging"
 	"github.com/andrinoff/cambridge-lang/pkg/token"
 )
 
+var logger = logging.For("lexer")
+
 // Lexer performs lexical analysis on source code
 type Lexer struct {
 	input   string
@@ -17,11 +20,25 @@ type Lexer struct {
 	ch      byte // current char under examination
 	line    int  // current line number
 	column  int  // current column number
+
+	symbols map[string]symbol // identifiers and keywords seen so far
+}
+
+// symbol is an interned identifier or keyword and its token type
+type symbol struct {
+	literal string
+	typ     token.Type
 }
 
 // New creates a new Lexer instance
 func New(input string) *Lexer {
-	l := &Lexer{input: input, line: 1, column: 0}
+	return NewAt(input, 1)
+}
+
+// NewAt creates a Lexer that numbers the first line of input line rather
+// than 1, for a file parsed as part of a larger program
+func NewAt(input string, line int) *Lexer {
+	l := &Lexer{input: input, line: line, column: 0, symbols: make(map[string]symbol)}
 	l.readChar()
 	return l
 }
@@ -132,18 +149,14 @@ func (l *Lexer) NextToken() token.Token {
 		tok.Column = l.column
 		return tok
 	default:
-		// Check for Unicode arrow ←
-		if l.isArrow() {
-			tok = token.Token{Type: token.ASSIGN, Literal: "←", Line: l.line, Column: l.column}
-			l.readChar() // ← is multi-byte, need to skip remaining bytes
-			l.readChar()
+		// Check for Unicode operators such as ← and ≠
+		if tok, ok := l.readUnicodeOperator(); ok {
 			return tok
 		}
 		if isLetter(l.ch) {
 			tok.Column = l.column
 			tok.Line = l.line
-			tok.Literal = l.readIdentifier()
-			tok.Type = token.LookupIdent(strings.ToUpper(tok.Literal))
+			tok.Literal, tok.Type = l.lookupSymbol(l.readIdentifier())
 			return tok
 		} else if isDigit(l.ch) {
 			tok.Column = l.column
@@ -158,6 +171,7 @@ func (l *Lexer) NextToken() token.Token {
 			return tok
 		} else {
 			tok = l.newToken(token.ILLEGAL, l.ch)
+			logger.Debug("illegal character", "char", fmt.Sprintf("%q", l.ch), "line", l.line, "column", l.column)
 		}
 	}
 
@@ -165,13 +179,31 @@ func (l *Lexer) NextToken() token.Token {
 	return tok
 }
 
-// isArrow checks if current position starts with Unicode arrow ←
-func (l *Lexer) isArrow() bool {
-	if l.pos+2 < len(l.input) {
-		// ← is encoded as E2 86 90 in UTF-8
-		return l.input[l.pos] == 0xE2 && l.input[l.pos+1] == 0x86 && l.input[l.pos+2] == 0x90
+// unicodeOperators maps the operator glyphs used in printed pseudocode to
+// the tokens they stand for
+var unicodeOperators = []struct {
+	glyph   string
+	tokType token.Type
+}{
+	{"←", token.ASSIGN},
+	{"≠", token.NOT_EQ},
+	{"≤", token.LT_EQ},
+	{"≥", token.GT_EQ},
+}
+
+// readUnicodeOperator reads a Unicode operator glyph at the current position
+func (l *Lexer) readUnicodeOperator() (token.Token, bool) {
+	for _, op := range unicodeOperators {
+		if strings.HasPrefix(l.input[l.pos:], op.glyph) {
+			tok := token.Token{Type: op.tokType, Literal: op.glyph, Line: l.line, Column: l.column}
+			// Glyphs are multi-byte, so skip every byte of the encoding
+			for i := 0; i < len(op.glyph); i++ {
+				l.readChar()
+			}
+			return tok, true
+		}
 	}
-	return false
+	return token.Token{}, false
 }
 
 // newToken creates a new token
@@ -202,6 +234,18 @@ func (l *Lexer) readIdentifier() string {
 	return l.input[start:l.pos]
 }
 
+// lookupSymbol interns an identifier or keyword, working out its token type
+// only the first time each spelling appears
+func (l *Lexer) lookupSymbol(text string) (string, token.Type) {
+	if sym, ok := l.symbols[text]; ok {
+		return sym.literal, sym.typ
+	}
+	sym := symbol{literal: token.Intern(text)}
+	sym.typ = token.LookupIdent(strings.ToUpper(sym.literal))
+	l.symbols[sym.literal] = sym
+	return sym.literal, sym.typ
+}
+
 // readNumber reads a number (integer or real)
 func (l *Lexer) readNumber() (string, bool) {
 	start := l.pos
@@ -232,7 +276,7 @@ func (l *Lexer) readString() string {
 		l.readChar()
 	}
 
-	str := l.input[start:l.pos]
+	str := token.Intern(l.input[start:l.pos])
 	if l.ch == '"' {
 		l.readChar() // skip closing quote
 	}
@@ -2,6 +2,7 @@ package lexer
 
 import (
 	"testing"
+	"unsafe"
 
 	"github.com/andrinoff/cambridge-lang/pkg/token"
 )
@@ -80,16 +81,21 @@ func TestNextToken_MultiCharOperators(t *testing.T) {
 }
 
 func TestNextToken_UnicodeArrow(t *testing.T) {
-	// Test with ASCII arrow instead since Unicode handling is complex
-	input := `x <- 5`
+	input := `x ← 5 ≠ y ≤ z ≥ 1`
 
 	tests := []struct {
 		expectedType    token.Type
 		expectedLiteral string
 	}{
 		{token.IDENT, "x"},
-		{token.ASSIGN, "<-"},
+		{token.ASSIGN, "←"},
 		{token.INTEGER_LIT, "5"},
+		{token.NOT_EQ, "≠"},
+		{token.IDENT, "y"},
+		{token.LT_EQ, "≤"},
+		{token.IDENT, "z"},
+		{token.GT_EQ, "≥"},
+		{token.INTEGER_LIT, "1"},
 		{token.EOF, ""},
 	}
 
@@ -501,6 +507,26 @@ y <- 10`
 	}
 }
 
+func TestNewAtNumbersLinesFrom(t *testing.T) {
+	l := NewAt("INCLUDE \"sort.cam\"\nx <- 5", 40)
+
+	expected := []struct {
+		typ  token.Type
+		line int
+	}{
+		{token.INCLUDE, 40},
+		{token.STRING_LIT, 40},
+		{token.NEWLINE, 40},
+		{token.IDENT, 41},
+	}
+	for i, tt := range expected {
+		tok := l.NextToken()
+		if tok.Type != tt.typ || tok.Line != tt.line {
+			t.Errorf("token %d: expected %s on line %d, got %s on line %d", i, tt.typ, tt.line, tok.Type, tok.Line)
+		}
+	}
+}
+
 func TestNextToken_ArrayDeclaration(t *testing.T) {
 	input := `DECLARE arr : ARRAY[1:10] OF INTEGER`
 
@@ -716,3 +742,28 @@ OPENFILE "log.txt" FOR APPEND`
 		}
 	}
 }
+
+func TestNextToken_InternedIdentifiers(t *testing.T) {
+	first := New(`Total <- Total + "pence"`)
+	second := New(`OUTPUT "pence", Total`)
+
+	var literals []string
+	for _, l := range []*Lexer{first, second} {
+		for tok := l.NextToken(); tok.Type != token.EOF; tok = l.NextToken() {
+			if tok.Type == token.IDENT || tok.Type == token.STRING_LIT {
+				literals = append(literals, tok.Literal)
+			}
+		}
+	}
+
+	// Total, Total, pence from the first source; pence, Total from the second
+	if len(literals) != 5 {
+		t.Fatalf("expected 5 literals, got %v", literals)
+	}
+	for _, pair := range [][2]int{{0, 1}, {0, 4}, {2, 3}} {
+		a, b := literals[pair[0]], literals[pair[1]]
+		if unsafe.StringData(a) != unsafe.StringData(b) {
+			t.Errorf("expected %q at %d and %d to share storage", a, pair[0], pair[1])
+		}
+	}
+}
@@ -0,0 +1,133 @@
+// Package logging provides the structured logger shared by the lexer,
+// parser, interpreter and language server
+//
+// Logging is off by default. It is enabled with --verbose or by setting
+// CAMBRIDGE_LOG to a level (debug, info, warn or error), and goes to stderr
+// unless a log file is given with --log-file or CAMBRIDGE_LOG_FILE.
+package logging
+
+import (
+	"context"
+	"fmt"
+	"io"
+	"log/slog"
+	"os"
+	"strings"
+	"sync/atomic"
+)
+
+// Environment variables that control logging
+const (
+	EnvLevel = "CAMBRIDGE_LOG"
+	EnvFile  = "CAMBRIDGE_LOG_FILE"
+)
+
+// Options selects what is logged and where
+type Options struct {
+	Verbose bool   // log at debug level whatever CAMBRIDGE_LOG says
+	File    string // append to this file instead of writing to stderr
+}
+
+// off discards every record
+var off slog.Handler = slog.NewTextHandler(io.Discard, &slog.HandlerOptions{Level: slog.LevelError + 1})
+
+// current is the handler every component logger forwards to, so loggers
+// created at package initialisation pick up the configuration from Setup
+var current atomic.Pointer[slog.Handler]
+
+func init() {
+	current.Store(&off)
+}
+
+// For returns the logger for a component such as "parser"
+func For(component string) *slog.Logger {
+	return slog.New(forwarder{}).With("component", component)
+}
+
+// Setup configures logging from opts and the environment
+// The returned function closes the log file, if one was opened.
+func Setup(opts Options) (func() error, error) {
+	level, enabled := slog.LevelDebug, opts.Verbose
+	if !enabled {
+		if env := os.Getenv(EnvLevel); env != "" {
+			var err error
+			if level, err = ParseLevel(env); err != nil {
+				return nil, err
+			}
+			enabled = !strings.EqualFold(env, "off")
+		}
+	}
+
+	if !enabled {
+		current.Store(&off)
+		return func() error { return nil }, nil
+	}
+
+	var w io.Writer = os.Stderr
+	closeFn := func() error { return nil }
+
+	path := opts.File
+	if path == "" {
+		path = os.Getenv(EnvFile)
+	}
+	if path != "" {
+		f, err := os.OpenFile(path, os.O_APPEND|os.O_CREATE|os.O_WRONLY, 0644)
+		if err != nil {
+			return nil, err
+		}
+		w, closeFn = f, f.Close
+	}
+
+	var h slog.Handler = slog.NewTextHandler(w, &slog.HandlerOptions{Level: level})
+	current.Store(&h)
+	return closeFn, nil
+}
+
+// ParseLevel maps a CAMBRIDGE_LOG value onto a level
+func ParseLevel(s string) (slog.Level, error) {
+	switch strings.ToLower(s) {
+	case "debug", "trace":
+		return slog.LevelDebug, nil
+	case "info":
+		return slog.LevelInfo, nil
+	case "warn", "warning":
+		return slog.LevelWarn, nil
+	case "error", "off":
+		return slog.LevelError, nil
+	}
+	return 0, fmt.Errorf("invalid %s level %q (expected debug, info, warn, error or off)", EnvLevel, s)
+}
+
+// forwarder passes records on to the current handler, replaying any
+// attributes and groups added to the logger along the way
+type forwarder struct {
+	wrap []func(slog.Handler) slog.Handler
+}
+
+func (f forwarder) handler() slog.Handler {
+	h := *current.Load()
+	for _, wrap := range f.wrap {
+		h = wrap(h)
+	}
+	return h
+}
+
+func (f forwarder) Enabled(ctx context.Context, level slog.Level) bool {
+	return (*current.Load()).Enabled(ctx, level)
+}
+
+func (f forwarder) Handle(ctx context.Context, r slog.Record) error {
+	return f.handler().Handle(ctx, r)
+}
+
+func (f forwarder) WithAttrs(attrs []slog.Attr) slog.Handler {
+	return f.with(func(h slog.Handler) slog.Handler { return h.WithAttrs(attrs) })
+}
+
+func (f forwarder) WithGroup(name string) slog.Handler {
+	return f.with(func(h slog.Handler) slog.Handler { return h.WithGroup(name) })
+}
+
+func (f forwarder) with(wrap func(slog.Handler) slog.Handler) forwarder {
+	return forwarder{wrap: append(f.wrap[:len(f.wrap):len(f.wrap)], wrap)}
+}
@@ -0,0 +1,61 @@
+package logging
+
+import (
+	"context"
+	"log/slog"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+)
+
+func TestLoggersFollowSetup(t *testing.T) {
+	// Created before Setup, as package-level loggers are
+	log := For("parser")
+
+	path := filepath.Join(t.TempDir(), "cambridge.log")
+	t.Setenv(EnvLevel, "info")
+	closeLog, err := Setup(Options{File: path})
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	t.Cleanup(func() { Setup(Options{}) })
+
+	log.Debug("hidden")
+	log.Info("parsed program", "statements", 3)
+	closeLog()
+
+	content, err := os.ReadFile(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	out := string(content)
+
+	if strings.Contains(out, "hidden") {
+		t.Errorf("debug record logged at info level:\n%s", out)
+	}
+	for _, want := range []string{"component=parser", `msg="parsed program"`, "statements=3"} {
+		if !strings.Contains(out, want) {
+			t.Errorf("log missing %q:\n%s", want, out)
+		}
+	}
+}
+
+func TestDisabledByDefault(t *testing.T) {
+	t.Setenv(EnvLevel, "")
+	if _, err := Setup(Options{}); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if For("lexer").Enabled(context.Background(), slog.LevelError) {
+		t.Errorf("expected logging to be off")
+	}
+}
+
+func TestParseLevel(t *testing.T) {
+	if _, err := ParseLevel("DEBUG"); err != nil {
+		t.Errorf("unexpected error: %v", err)
+	}
+	if _, err := ParseLevel("loud"); err == nil {
+		t.Errorf("expected an error for an unknown level")
+	}
+}
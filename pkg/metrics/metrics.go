@@ -0,0 +1,143 @@
+// Package metrics computes complexity metrics for pseudocode routines
+package metrics
+
+import (
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+)
+
+// MainRoutine is the name reported for top-level program statements
+const MainRoutine = "(main)"
+
+// Routine holds the metrics for a single procedure, function or method
+type Routine struct {
+	Name       string `json:"name"`
+	Kind       string `json:"kind"` // "PROCEDURE", "FUNCTION" or "PROGRAM"
+	Line       int    `json:"line"`
+	Statements int    `json:"statements"`
+	Cyclomatic int    `json:"cyclomatic"`
+	Nesting    int    `json:"nesting"`
+	Parameters int    `json:"parameters"`
+}
+
+// Analyze returns metrics for the main program and every routine in it
+// Class methods are reported as Class.Method. The program is expected to
+// have parsed without errors.
+func Analyze(program *ast.Program) []Routine {
+	top := Routine{Name: MainRoutine, Kind: "PROGRAM", Line: 1}
+	var topLevel []ast.Statement
+	var routines []Routine
+
+	for _, stmt := range program.Statements {
+		switch s := stmt.(type) {
+		case *ast.ProcedureStatement, *ast.FunctionStatement:
+			if r, ok := analyzeRoutine(s, ""); ok {
+				routines = append(routines, r)
+			}
+		case *ast.ClassStatement:
+			for _, member := range s.Members {
+				if r, ok := analyzeRoutine(member, s.Name+"."); ok {
+					routines = append(routines, r)
+				}
+			}
+		case *ast.TypeStatement:
+			// Type definitions carry no executable statements
+		default:
+			topLevel = append(topLevel, stmt)
+		}
+	}
+
+	fillMetrics(&top, topLevel)
+	return append([]Routine{top}, routines...)
+}
+
+func analyzeRoutine(stmt ast.Statement, prefix string) (Routine, bool) {
+	switch s := stmt.(type) {
+	case *ast.ProcedureStatement:
+		r := Routine{
+			Name:       prefix + s.Name,
+			Kind:       "PROCEDURE",
+			Line:       s.Token.Line,
+			Parameters: len(s.Parameters),
+		}
+		fillMetrics(&r, s.Body)
+		return r, true
+	case *ast.FunctionStatement:
+		r := Routine{
+			Name:       prefix + s.Name,
+			Kind:       "FUNCTION",
+			Line:       s.Token.Line,
+			Parameters: len(s.Parameters),
+		}
+		fillMetrics(&r, s.Body)
+		return r, true
+	}
+	return Routine{}, false
+}
+
+func fillMetrics(r *Routine, body []ast.Statement) {
+	r.Statements = countStatements(body)
+	r.Cyclomatic = 1 + decisions(body)
+	r.Nesting = nestingDepth(body)
+}
+
+// countStatements counts every statement in body, including nested ones
+func countStatements(body []ast.Statement) int {
+	count := 0
+	for _, stmt := range body {
+		ast.Inspect(stmt, func(n ast.Node) bool {
+			if _, ok := n.(ast.Statement); ok {
+				count++
+			}
+			return true
+		})
+	}
+	return count
+}
+
+// decisions counts the branch points in body: each IF, loop and CASE
+// clause adds one path, as does every AND/OR in a condition
+func decisions(body []ast.Statement) int {
+	count := 0
+	for _, stmt := range body {
+		ast.Inspect(stmt, func(n ast.Node) bool {
+			switch n := n.(type) {
+			case *ast.IfStatement, *ast.ForStatement, *ast.WhileStatement, *ast.RepeatStatement:
+				count++
+			case *ast.CaseStatement:
+				count += len(n.Cases)
+			case *ast.InfixExpression:
+				if n.Operator == "AND" || n.Operator == "OR" {
+					count++
+				}
+			}
+			return true
+		})
+	}
+	return count
+}
+
+// nestingDepth returns the deepest level of nested blocks in body
+func nestingDepth(body []ast.Statement) int {
+	deepest := 0
+	for _, stmt := range body {
+		var depth int
+		switch s := stmt.(type) {
+		case *ast.IfStatement:
+			depth = 1 + max(nestingDepth(s.Consequence), nestingDepth(s.Alternative))
+		case *ast.CaseStatement:
+			inner := nestingDepth(s.Otherwise)
+			for _, c := range s.Cases {
+				inner = max(inner, nestingDepth(c.Body))
+			}
+			depth = 1 + inner
+		case *ast.ForStatement:
+			depth = 1 + nestingDepth(s.Body)
+		case *ast.WhileStatement:
+			depth = 1 + nestingDepth(s.Body)
+		case *ast.RepeatStatement:
+			depth = 1 + nestingDepth(s.Body)
+		}
+		deepest = max(deepest, depth)
+	}
+	return deepest
+}
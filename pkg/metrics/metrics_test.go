@@ -0,0 +1,107 @@
+package metrics
+
+import (
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+func analyze(t *testing.T, input string) []Routine {
+	t.Helper()
+	p := parser.New(lexer.New(input))
+	program := p.ParseProgram()
+	if len(p.Errors()) > 0 {
+		t.Fatalf("parser errors: %v", p.Errors())
+	}
+	return Analyze(program)
+}
+
+func find(t *testing.T, routines []Routine, name string) Routine {
+	t.Helper()
+	for _, r := range routines {
+		if r.Name == name {
+			return r
+		}
+	}
+	t.Fatalf("routine %s not found in %+v", name, routines)
+	return Routine{}
+}
+
+func TestStraightLineProcedure(t *testing.T) {
+	routines := analyze(t, `PROCEDURE Greet(Name : STRING, Times : INTEGER)
+    OUTPUT "Hello"
+    OUTPUT Name
+ENDPROCEDURE`)
+
+	r := find(t, routines, "Greet")
+	if r.Kind != "PROCEDURE" {
+		t.Errorf("expected PROCEDURE, got %s", r.Kind)
+	}
+	if r.Statements != 2 {
+		t.Errorf("expected 2 statements, got %d", r.Statements)
+	}
+	if r.Cyclomatic != 1 {
+		t.Errorf("expected cyclomatic complexity 1, got %d", r.Cyclomatic)
+	}
+	if r.Nesting != 0 {
+		t.Errorf("expected nesting 0, got %d", r.Nesting)
+	}
+	if r.Parameters != 2 {
+		t.Errorf("expected 2 parameters, got %d", r.Parameters)
+	}
+}
+
+func TestBranchingFunction(t *testing.T) {
+	routines := analyze(t, `FUNCTION Grade(Score : INTEGER) RETURNS CHAR
+    FOR i <- 1 TO 3
+        IF Score > 90 AND Score <= 100 THEN
+            RETURN 'A'
+        ENDIF
+    NEXT i
+    CASE OF Score
+        1 : RETURN 'B'
+        2 : RETURN 'C'
+        OTHERWISE : RETURN 'U'
+    ENDCASE
+ENDFUNCTION`)
+
+	r := find(t, routines, "Grade")
+	// 1 + FOR + IF + AND + 2 CASE clauses
+	if r.Cyclomatic != 6 {
+		t.Errorf("expected cyclomatic complexity 6, got %d", r.Cyclomatic)
+	}
+	if r.Nesting != 2 {
+		t.Errorf("expected nesting 2, got %d", r.Nesting)
+	}
+	if r.Statements != 7 {
+		t.Errorf("expected 7 statements, got %d", r.Statements)
+	}
+}
+
+func TestMainProgramAndMethods(t *testing.T) {
+	routines := analyze(t, `CLASS Counter
+    PRIVATE Value : INTEGER
+    PUBLIC PROCEDURE Increment()
+        Value <- Value + 1
+    ENDPROCEDURE
+ENDCLASS
+
+DECLARE x : INTEGER
+x <- 1
+WHILE x < 10
+    x <- x + 1
+ENDWHILE`)
+
+	if routines[0].Name != MainRoutine {
+		t.Fatalf("expected main program first, got %s", routines[0].Name)
+	}
+	if routines[0].Statements != 4 {
+		t.Errorf("expected 4 main statements, got %d", routines[0].Statements)
+	}
+	if routines[0].Nesting != 1 {
+		t.Errorf("expected main nesting 1, got %d", routines[0].Nesting)
+	}
+
+	find(t, routines, "Counter.Increment")
+}
@@ -0,0 +1,382 @@
+// Package optimize simplifies a parsed program before it is run
+// Constant expressions are folded, CONSTANT values computed once and
+// substituted where they are used, and branches that can never run are
+// removed. The rewritten program behaves exactly like the original: anything
+// that would fail at run time, such as a division by zero, is left in place.
+package optimize
+
+import (
+	"strconv"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// optimizer holds the CONSTANT values that may be substituted
+type optimizer struct {
+	constants map[string]ast.Expression
+}
+
+// Program optimizes program in place and returns it
+func Program(program *ast.Program) *ast.Program {
+	reassigned := reassignedNames(program)
+
+	// Routines can run as soon as the main program starts calling them, so
+	// they only see the constants defined before any other code
+	early := &optimizer{constants: map[string]ast.Expression{}}
+	main := &optimizer{constants: map[string]ast.Expression{}}
+	preamble := true
+
+	var out []ast.Statement
+	for _, stmt := range program.Statements {
+		switch s := stmt.(type) {
+		case *ast.ConstantStatement:
+			s.Value = main.expression(s.Value)
+			if isLiteral(s.Value) && !reassigned[s.Name.Value] {
+				main.constants[s.Name.Value] = s.Value
+				if preamble {
+					early.constants[s.Name.Value] = s.Value
+				}
+			}
+			out = append(out, s)
+			continue
+		case *ast.ProcedureStatement, *ast.FunctionStatement, *ast.ClassStatement:
+			out = append(out, stmt)
+			continue
+		case *ast.DeclareStatement, *ast.TypeStatement:
+		default:
+			preamble = false
+		}
+		out = append(out, main.statement(stmt)...)
+	}
+
+	for _, stmt := range out {
+		switch s := stmt.(type) {
+		case *ast.ProcedureStatement:
+			s.Body = early.statements(s.Body)
+		case *ast.FunctionStatement:
+			s.Body = early.statements(s.Body)
+		case *ast.ClassStatement:
+			for _, member := range s.Members {
+				switch m := member.(type) {
+				case *ast.ProcedureStatement:
+					m.Body = early.statements(m.Body)
+				case *ast.FunctionStatement:
+					m.Body = early.statements(m.Body)
+				}
+			}
+		}
+	}
+
+	program.Statements = out
+	return program
+}
+
+// Expression folds expr, which must not refer to any variables to be
+// reduced to a single value
+func Expression(expr ast.Expression) ast.Expression {
+	o := &optimizer{}
+	return o.expression(expr)
+}
+
+// Constants returns the value of every CONSTANT in program that can be
+// worked out before it runs, by name, folding program in place as Program
+// does
+func Constants(program *ast.Program) map[string]ast.Expression {
+	reassigned := reassignedNames(program)
+	values := map[string]ast.Expression{}
+	for _, stmt := range Program(program).Statements {
+		if c, ok := stmt.(*ast.ConstantStatement); ok && isLiteral(c.Value) && !reassigned[c.Name.Value] {
+			values[c.Name.Value] = c.Value
+		}
+	}
+	return values
+}
+
+// Fold folds expr with the given CONSTANT values substituted, and reports
+// whether that reduced it to a single value
+func Fold(expr ast.Expression, constants map[string]ast.Expression) (ast.Expression, bool) {
+	o := &optimizer{constants: constants}
+	folded := o.expression(expr)
+	return folded, isLiteral(folded)
+}
+
+// reassignedNames returns every name that is given a value anywhere other
+// than a single CONSTANT statement; substituting those would be unsafe
+func reassignedNames(program *ast.Program) map[string]bool {
+	names := map[string]bool{}
+	constants := map[string]int{}
+
+	target := func(expr ast.Expression) {
+		if id, ok := expr.(*ast.Identifier); ok {
+			names[id.Value] = true
+		}
+	}
+	params := func(ps []ast.Parameter) {
+		for _, p := range ps {
+			names[p.Name] = true
+		}
+	}
+
+	for _, stmt := range program.Statements {
+		ast.Inspect(stmt, func(n ast.Node) bool {
+			switch n := n.(type) {
+			case *ast.ConstantStatement:
+				constants[n.Name.Value]++
+			case *ast.DeclareStatement:
+				names[n.Name.Value] = true
+			case *ast.AssignmentStatement:
+				target(n.Name)
+			case *ast.InputStatement:
+				target(n.Variable)
+			case *ast.ReadFileStatement:
+				target(n.Variable)
+			case *ast.ForStatement:
+				names[n.Variable.Value] = true
+			case *ast.ProcedureStatement:
+				names[n.Name] = true
+				params(n.Parameters)
+			case *ast.FunctionStatement:
+				names[n.Name] = true
+				params(n.Parameters)
+			}
+			return true
+		})
+	}
+
+	for name, count := range constants {
+		if count > 1 {
+			names[name] = true
+		}
+	}
+	return names
+}
+
+func (o *optimizer) statements(stmts []ast.Statement) []ast.Statement {
+	var out []ast.Statement
+	for _, stmt := range stmts {
+		out = append(out, o.statement(stmt)...)
+	}
+	return out
+}
+
+// statement optimizes stmt, returning the statements that replace it
+func (o *optimizer) statement(stmt ast.Statement) []ast.Statement {
+	switch s := stmt.(type) {
+	case *ast.ConstantStatement:
+		s.Value = o.expression(s.Value)
+	case *ast.AssignmentStatement:
+		s.Name = o.target(s.Name)
+		s.Value = o.expression(s.Value)
+	case *ast.IfStatement:
+		s.Condition = o.expression(s.Condition)
+		s.Consequence = o.statements(s.Consequence)
+		s.Alternative = o.statements(s.Alternative)
+		if b, ok := s.Condition.(*ast.BooleanLiteral); ok {
+			// IF does not open a scope, so the chosen branch can stand in
+			// for the whole statement
+			if b.Value {
+				return s.Consequence
+			}
+			return s.Alternative
+		}
+	case *ast.CaseStatement:
+		s.Expr = o.expression(s.Expr)
+		for i := range s.Cases {
+			s.Cases[i].Values = o.expressions(s.Cases[i].Values)
+			s.Cases[i].Body = o.statements(s.Cases[i].Body)
+		}
+		s.Otherwise = o.statements(s.Otherwise)
+	case *ast.ForStatement:
+		s.Start = o.expression(s.Start)
+		s.End = o.expression(s.End)
+		if s.Step != nil {
+			s.Step = o.expression(s.Step)
+		}
+		s.Body = o.statements(s.Body)
+	case *ast.WhileStatement:
+		s.Condition = o.expression(s.Condition)
+		s.Body = o.statements(s.Body)
+		if b, ok := s.Condition.(*ast.BooleanLiteral); ok && !b.Value {
+			return nil
+		}
+	case *ast.RepeatStatement:
+		s.Body = o.statements(s.Body)
+		s.Condition = o.expression(s.Condition)
+		if b, ok := s.Condition.(*ast.BooleanLiteral); ok && b.Value {
+			// The body runs exactly once
+			return s.Body
+		}
+	case *ast.CallStatement:
+		s.Arguments = o.expressions(s.Arguments)
+	case *ast.ReturnStatement:
+		if s.Value != nil {
+			s.Value = o.expression(s.Value)
+		}
+	case *ast.InputStatement:
+		s.Variable = o.target(s.Variable)
+	case *ast.OutputStatement:
+		s.Values = o.expressions(s.Values)
+	case *ast.OpenFileStatement:
+		s.Filename = o.expression(s.Filename)
+	case *ast.CloseFileStatement:
+		s.Filename = o.expression(s.Filename)
+	case *ast.ReadFileStatement:
+		s.Filename = o.expression(s.Filename)
+		s.Variable = o.target(s.Variable)
+	case *ast.WriteFileStatement:
+		s.Filename = o.expression(s.Filename)
+		s.Data = o.expression(s.Data)
+	case *ast.ExpressionStatement:
+		s.Expression = o.expression(s.Expression)
+	}
+	return []ast.Statement{stmt}
+}
+
+// target optimizes the indices of an assignment target, leaving the
+// variable itself alone
+func (o *optimizer) target(expr ast.Expression) ast.Expression {
+	switch t := expr.(type) {
+	case *ast.ArrayAccess:
+		t.Indices = o.expressions(t.Indices)
+	case *ast.MemberAccess:
+		t.Object = o.target(t.Object)
+	}
+	return expr
+}
+
+func (o *optimizer) expressions(exprs []ast.Expression) []ast.Expression {
+	for i, e := range exprs {
+		exprs[i] = o.expression(e)
+	}
+	return exprs
+}
+
+func (o *optimizer) expression(expr ast.Expression) ast.Expression {
+	switch e := expr.(type) {
+	case *ast.Identifier:
+		if value, ok := o.constants[e.Value]; ok {
+			line, column := ast.Position(e)
+			return at(value, line, column)
+		}
+	case *ast.PrefixExpression:
+		e.Right = o.expression(e.Right)
+		if right, ok := value(e.Right); ok {
+			if lit, ok := fold(interpreter.UnaryOp(e.Operator, right), e); ok {
+				return lit
+			}
+		}
+	case *ast.InfixExpression:
+		e.Left = o.expression(e.Left)
+		e.Right = o.expression(e.Right)
+		left, lok := value(e.Left)
+		right, rok := value(e.Right)
+		if lok && rok {
+			// A result that depends on whether REAL values are decimal,
+			// such as 0.1 + 0.2, is left to be computed at run time
+			result := interpreter.BinaryOp(e.Operator, left, right)
+			if !interpreter.ObjectsEqual(result, decimal.Binary(e.Operator, left, right)) {
+				break
+			}
+			if lit, ok := fold(result, e); ok {
+				return lit
+			}
+		}
+	case *ast.ArrayAccess:
+		e.Array = o.expression(e.Array)
+		e.Indices = o.expressions(e.Indices)
+	case *ast.MemberAccess:
+		e.Object = o.expression(e.Object)
+	case *ast.CallExpression:
+		e.Arguments = o.expressions(e.Arguments)
+	case *ast.NewExpression:
+		e.Arguments = o.expressions(e.Arguments)
+	case *ast.RangeExpression:
+		e.Start = o.expression(e.Start)
+		e.End = o.expression(e.End)
+	}
+	return expr
+}
+
+var decimal = interpreter.Arithmetic{DecimalReals: true}
+
+// value returns the runtime value of a literal
+func value(expr ast.Expression) (interpreter.Object, bool) {
+	switch e := expr.(type) {
+	case *ast.IntegerLiteral:
+		if e.Big != nil {
+			// Left for the run, which knows whether --bigint allows it
+			return nil, false
+		}
+		return &interpreter.Integer{Value: e.Value}, true
+	case *ast.RealLiteral:
+		return &interpreter.Real{Value: e.Value}, true
+	case *ast.StringLiteral:
+		return &interpreter.String{Value: e.Value}, true
+	case *ast.BooleanLiteral:
+		return &interpreter.Boolean{Value: e.Value}, true
+	}
+	return nil, false
+}
+
+func isLiteral(expr ast.Expression) bool {
+	if _, ok := expr.(*ast.CharLiteral); ok {
+		return true
+	}
+	_, ok := value(expr)
+	return ok
+}
+
+// fold turns the result of evaluating original into a literal at the same
+// position; errors are not folded so that they still happen at run time
+func fold(result interpreter.Object, original ast.Expression) (ast.Expression, bool) {
+	line, column := ast.Position(original)
+	tok := token.Token{Line: line, Column: column}
+
+	switch r := result.(type) {
+	case *interpreter.Integer:
+		tok.Type, tok.Literal = token.INTEGER_LIT, strconv.FormatInt(r.Value, 10)
+		return &ast.IntegerLiteral{Token: tok, Value: r.Value}, true
+	case *interpreter.Real:
+		tok.Type, tok.Literal = token.REAL_LIT, strconv.FormatFloat(r.Value, 'g', -1, 64)
+		return &ast.RealLiteral{Token: tok, Value: r.Value}, true
+	case *interpreter.String:
+		tok.Type, tok.Literal = token.STRING_LIT, r.Value
+		return &ast.StringLiteral{Token: tok, Value: r.Value}, true
+	case *interpreter.Boolean:
+		tok.Type, tok.Literal = token.FALSE, "FALSE"
+		if r.Value {
+			tok.Type, tok.Literal = token.TRUE, "TRUE"
+		}
+		return &ast.BooleanLiteral{Token: tok, Value: r.Value}, true
+	}
+	return nil, false
+}
+
+// at copies a substituted CONSTANT value to the position of its use
+func at(lit ast.Expression, line, column int) ast.Expression {
+	switch l := lit.(type) {
+	case *ast.IntegerLiteral:
+		c := *l
+		c.Token.Line, c.Token.Column = line, column
+		return &c
+	case *ast.RealLiteral:
+		c := *l
+		c.Token.Line, c.Token.Column = line, column
+		return &c
+	case *ast.StringLiteral:
+		c := *l
+		c.Token.Line, c.Token.Column = line, column
+		return &c
+	case *ast.CharLiteral:
+		c := *l
+		c.Token.Line, c.Token.Column = line, column
+		return &c
+	case *ast.BooleanLiteral:
+		c := *l
+		c.Token.Line, c.Token.Column = line, column
+		return &c
+	}
+	return lit
+}
@@ -0,0 +1,188 @@
+package optimize
+
+import (
+	"bytes"
+	"strings"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+func parse(t *testing.T, input string) *ast.Program {
+	t.Helper()
+	p := parser.New(lexer.New(input))
+	program := p.ParseProgram()
+	if len(p.Errors()) > 0 {
+		t.Fatalf("parser errors: %v", p.Errors())
+	}
+	return program
+}
+
+func run(program *ast.Program) string {
+	var out bytes.Buffer
+	interp := interpreter.New()
+	interp.SetBuiltins(builtins.GetBuiltins())
+	interp.SetOutput(&out)
+	if err, ok := interp.Eval(program).(*interpreter.Error); ok {
+		out.WriteString(err.Inspect())
+	}
+	return out.String()
+}
+
+func TestFoldExpressions(t *testing.T) {
+	tests := []struct {
+		input    string
+		expected string
+	}{
+		{"2 + 3 * 4", "14"},
+		{"(10 DIV 3) - -1", "4"},
+		{"5 / 2", "2.5"},
+		{"0.1 + 0.2", "(0.1 + 0.2)"},
+		{`"a" & "b" & 1`, `"ab1"`},
+		{"NOT (1 < 2) OR TRUE", "TRUE"},
+		// Errors are left for run time
+		{"1 DIV 0", "(1 DIV 0)"},
+		{"x + 1", "(x + 1)"},
+	}
+
+	for _, tt := range tests {
+		program := parse(t, "OUTPUT "+tt.input)
+		out := program.Statements[0].(*ast.OutputStatement)
+		if got := Expression(out.Values[0]).String(); got != tt.expected {
+			t.Errorf("%s: expected %s, got %s", tt.input, tt.expected, got)
+		}
+	}
+}
+
+func TestConstantsAndDeadBranches(t *testing.T) {
+	program := Program(parse(t, `CONSTANT Size = 4
+CONSTANT Debug = FALSE
+CONSTANT Double = Size * 2
+
+PROCEDURE Show()
+    OUTPUT Double + 1
+ENDPROCEDURE
+
+IF Debug THEN
+    OUTPUT "debugging"
+ENDIF
+WHILE Debug
+    OUTPUT "never"
+ENDWHILE
+FOR i <- 1 TO Size - 1
+    OUTPUT i
+NEXT i
+CALL Show()`))
+
+	if c := program.Statements[2].(*ast.ConstantStatement); c.Value.String() != "8" {
+		t.Errorf("expected Double to be computed as 8, got %s", c.Value.String())
+	}
+
+	show := program.Statements[3].(*ast.ProcedureStatement)
+	if got := show.Body[0].(*ast.OutputStatement).Values[0].String(); got != "9" {
+		t.Errorf("expected constant folded into routine, got %s", got)
+	}
+
+	// The IF and WHILE are gone, leaving the FOR with a folded bound
+	loop, ok := program.Statements[4].(*ast.ForStatement)
+	if !ok {
+		t.Fatalf("expected FOR after dead branches, got %T", program.Statements[4])
+	}
+	if loop.End.String() != "3" {
+		t.Errorf("expected loop bound 3, got %s", loop.End.String())
+	}
+	if len(program.Statements) != 6 {
+		t.Errorf("expected 6 statements, got %d", len(program.Statements))
+	}
+}
+
+func TestUnsafeConstantsKept(t *testing.T) {
+	program := Program(parse(t, `PROCEDURE Show(Limit : INTEGER)
+    OUTPUT Limit
+ENDPROCEDURE
+
+OUTPUT "start"
+CONSTANT Limit = 10
+CONSTANT Late = 5
+CALL Show(Limit + Late)`))
+
+	// Limit is also a parameter name, and Late is defined after code has
+	// started running, so the routine must still look them up
+	show := program.Statements[0].(*ast.ProcedureStatement)
+	if got := show.Body[0].(*ast.OutputStatement).Values[0].String(); got != "Limit" {
+		t.Errorf("expected parameter reference kept, got %s", got)
+	}
+	call := program.Statements[4].(*ast.CallStatement)
+	if got := call.Arguments[0].String(); got != "(Limit + 5)" {
+		t.Errorf("expected only Late substituted, got %s", got)
+	}
+}
+
+func TestConstantsAndFold(t *testing.T) {
+	constants := Constants(parse(t, `CONSTANT MaxSize = 10
+CONSTANT Total = MaxSize * 3
+CONSTANT Label = "Max " & MaxSize
+CONSTANT Count = 1
+Count <- 2`))
+
+	for name, expected := range map[string]string{"MaxSize": "10", "Total": "30", "Label": `"Max 10"`} {
+		if got, ok := constants[name]; !ok || got.String() != expected {
+			t.Errorf("expected %s = %s, got %v", name, expected, got)
+		}
+	}
+	if _, ok := constants["Count"]; ok {
+		t.Error("expected a reassigned constant to be left out")
+	}
+
+	tests := []struct {
+		input    string
+		expected string
+		folded   bool
+	}{
+		{"MaxSize * 2", "20", true},
+		{"Total DIV MaxSize", "3", true},
+		{"MaxSize + Size", "(10 + Size)", false},
+	}
+	for _, tt := range tests {
+		expr := parse(t, tt.input).Statements[0].(*ast.ExpressionStatement).Expression
+		got, folded := Fold(expr, constants)
+		if got.String() != tt.expected || folded != tt.folded {
+			t.Errorf("%s: expected %s (%v), got %s (%v)", tt.input, tt.expected, tt.folded, got, folded)
+		}
+	}
+}
+
+func TestSameOutput(t *testing.T) {
+	inputs := []string{
+		`CONSTANT Rate = 0.2
+DECLARE Total : REAL
+Total <- 100 * (1 + Rate)
+OUTPUT "Total: ", Total`,
+		`CONSTANT N = 3
+FOR i <- N TO 1 STEP -1
+    REPEAT
+        OUTPUT i * N
+    UNTIL TRUE
+NEXT i`,
+		`DECLARE x : INTEGER
+x <- 10 DIV (2 - 2)`,
+	}
+
+	for _, input := range inputs {
+		expected := run(parse(t, input))
+		if got := run(Program(parse(t, input))); got != expected {
+			t.Errorf("optimized program differs\ninput:\n%s\nexpected %q, got %q", input, expected, got)
+		}
+	}
+}
+
+func TestErrorPositionsKept(t *testing.T) {
+	out := run(Program(parse(t, "CONSTANT Zero = 0\nOUTPUT 1\nOUTPUT 5 DIV Zero")))
+	if !strings.Contains(out, "line 3, column 1") {
+		t.Errorf("expected error on line 3, got %q", out)
+	}
+}
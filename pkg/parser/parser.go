@@ -2,15 +2,22 @@
 package parser
 
 import (
+	"errors"
 	"fmt"
+	"math"
+	"math/big"
 	"strconv"
 	"strings"
 
 	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
 	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/logging"
 	"github.com/andrinoff/cambridge-lang/pkg/token"
 )
 
+var logger = logging.For("parser")
+
 // Operator precedence levels
 const (
 	_ int = iota
@@ -56,21 +63,31 @@ type (
 
 // Parser parses tokens into an AST
 type Parser struct {
-	l      *lexer.Lexer
-	errors []string
+	l           *lexer.Lexer
+	errors      []string
+	diagnostics []diagnostic.Diagnostic
 
 	curToken  token.Token
 	peekToken token.Token
 
+	arena *ast.Arena
+
 	prefixParseFns map[token.Type]prefixParseFn
 	infixParseFns  map[token.Type]infixParseFn
 }
 
 // New creates a new parser
 func New(l *lexer.Lexer) *Parser {
+	return NewWithArena(l, ast.NewArena())
+}
+
+// NewWithArena creates a parser that allocates nodes from arena, so that a
+// caller parsing many times can Reset and reuse the same memory
+func NewWithArena(l *lexer.Lexer, arena *ast.Arena) *Parser {
 	p := &Parser{
 		l:      l,
 		errors: []string{},
+		arena:  arena,
 	}
 
 	p.prefixParseFns = make(map[token.Type]prefixParseFn)
@@ -132,13 +149,38 @@ func (p *Parser) Errors() []string {
 	return p.errors
 }
 
-func (p *Parser) addError(msg string) {
-	p.errors = append(p.errors, fmt.Sprintf("line %d, column %d: %s", p.curToken.Line, p.curToken.Column, msg))
+// Diagnostics returns parser errors with their locations kept separate
+// from the message, for rendering
+func (p *Parser) Diagnostics() []diagnostic.Diagnostic {
+	return p.diagnostics
+}
+
+func (p *Parser) addError(code diagnostic.Code, args ...interface{}) {
+	p.addErrorAt(p.curToken, code, args...)
+}
+
+// addErrorAt reports an error found at tok rather than the current token
+func (p *Parser) addErrorAt(tok token.Token, code diagnostic.Code, args ...interface{}) {
+	d := diagnostic.New(diagnostic.Error, code, args...)
+	d.Line, d.Column = tok.Line, tok.Column
+
+	p.errors = append(p.errors, fmt.Sprintf("line %d, column %d: %s", d.Line, d.Column, d.Message))
+	p.diagnostics = append(p.diagnostics, d)
+	logger.Debug("syntax error", "code", code, "line", d.Line, "column", d.Column, "message", d.Message)
+}
+
+// expectEnd reports the block start opens if it ran to the end of the
+// input without reaching its closing keyword, end
+func (p *Parser) expectEnd(start token.Token, end token.Type) bool {
+	if !p.curTokenIs(token.EOF) {
+		return true
+	}
+	p.addErrorAt(start, diagnostic.MissingBlockEnd, start.Type, end)
+	return false
 }
 
 func (p *Parser) peekError(t token.Type) {
-	msg := fmt.Sprintf("expected next token to be %s, got %s instead", t, p.peekToken.Type)
-	p.addError(msg)
+	p.addError(diagnostic.ExpectedToken, t, p.peekToken.Type)
 }
 
 func (p *Parser) curTokenIs(t token.Type) bool {
@@ -196,6 +238,7 @@ func (p *Parser) ParseProgram() *ast.Program {
 		p.nextToken()
 	}
 
+	logger.Debug("parsed program", "statements", len(program.Statements), "errors", len(p.errors))
 	return program
 }
 
@@ -235,6 +278,8 @@ func (p *Parser) parseStatement() ast.Statement {
 		return p.parseReadFileStatement()
 	case token.WRITEFILE:
 		return p.parseWriteFileStatement()
+	case token.INCLUDE:
+		return p.parseIncludeStatement()
 	case token.TYPE:
 		return p.parseTypeStatement()
 	case token.CLASS:
@@ -253,7 +298,7 @@ func (p *Parser) parseDeclareStatement() *ast.DeclareStatement {
 		return nil
 	}
 
-	stmt.Name = &ast.Identifier{Token: p.curToken, Value: p.curToken.Literal}
+	stmt.Name = p.arena.Identifier(ast.Identifier{Token: p.curToken, Value: p.curToken.Literal})
 
 	if !p.expectPeek(token.COLON) {
 		return nil
@@ -272,7 +317,7 @@ func (p *Parser) parseConstantStatement() *ast.ConstantStatement {
 		return nil
 	}
 
-	stmt.Name = &ast.Identifier{Token: p.curToken, Value: p.curToken.Literal}
+	stmt.Name = p.arena.Identifier(ast.Identifier{Token: p.curToken, Value: p.curToken.Literal})
 
 	if !p.expectPeek(token.EQ) {
 		return nil
@@ -304,6 +349,7 @@ func (p *Parser) parseIfStatement() *ast.IfStatement {
 		p.skipNewlines()
 		stmt.Alternative = p.parseBlockStatements(token.ENDIF)
 	}
+	p.expectEnd(stmt.Token, token.ENDIF)
 
 	return stmt
 }
@@ -335,6 +381,7 @@ func (p *Parser) parseCaseStatement() *ast.CaseStatement {
 		p.skipNewlines()
 		stmt.Otherwise = p.parseBlockStatements(token.ENDCASE)
 	}
+	p.expectEnd(stmt.Token, token.ENDCASE)
 
 	return stmt
 }
@@ -408,7 +455,7 @@ func (p *Parser) parseForStatement() *ast.ForStatement {
 		return nil
 	}
 
-	stmt.Variable = &ast.Identifier{Token: p.curToken, Value: p.curToken.Literal}
+	stmt.Variable = p.arena.Identifier(ast.Identifier{Token: p.curToken, Value: p.curToken.Literal})
 
 	if !p.expectPeek(token.ASSIGN) {
 		return nil
@@ -434,6 +481,7 @@ func (p *Parser) parseForStatement() *ast.ForStatement {
 	p.skipNewlines()
 
 	stmt.Body = p.parseBlockStatements(token.NEXT)
+	p.expectEnd(stmt.Token, token.NEXT)
 
 	// Expect NEXT variable
 	if p.curTokenIs(token.NEXT) {
@@ -453,6 +501,7 @@ func (p *Parser) parseWhileStatement() *ast.WhileStatement {
 	p.skipNewlines()
 
 	stmt.Body = p.parseBlockStatements(token.ENDWHILE)
+	p.expectEnd(stmt.Token, token.ENDWHILE)
 
 	return stmt
 }
@@ -464,6 +513,9 @@ func (p *Parser) parseRepeatStatement() *ast.RepeatStatement {
 	p.skipNewlines()
 
 	stmt.Body = p.parseBlockStatements(token.UNTIL)
+	if !p.expectEnd(stmt.Token, token.UNTIL) {
+		return stmt
+	}
 
 	p.nextToken()
 	stmt.Condition = p.parseExpression(LOWEST)
@@ -497,6 +549,7 @@ func (p *Parser) parseProcedureStatement() *ast.ProcedureStatement {
 	p.skipNewlines()
 
 	stmt.Body = p.parseBlockStatements(token.ENDPROCEDURE)
+	p.expectEnd(stmt.Token, token.ENDPROCEDURE)
 
 	return stmt
 }
@@ -534,6 +587,7 @@ func (p *Parser) parseFunctionStatement() *ast.FunctionStatement {
 	p.skipNewlines()
 
 	stmt.Body = p.parseBlockStatements(token.ENDFUNCTION)
+	p.expectEnd(stmt.Token, token.ENDFUNCTION)
 
 	return stmt
 }
@@ -559,7 +613,7 @@ func (p *Parser) parseParameters() []ast.Parameter {
 		}
 
 		if !p.curTokenIs(token.IDENT) {
-			p.addError("expected parameter name")
+			p.addError(diagnostic.ExpectedParameterName)
 			return params
 		}
 
@@ -622,7 +676,7 @@ func (p *Parser) parseInputStatement() *ast.InputStatement {
 }
 
 func (p *Parser) parseOutputStatement() *ast.OutputStatement {
-	stmt := &ast.OutputStatement{Token: p.curToken}
+	stmt := p.arena.OutputStatement(ast.OutputStatement{Token: p.curToken})
 
 	p.nextToken()
 
@@ -659,7 +713,7 @@ func (p *Parser) parseOpenFileStatement() *ast.OpenFileStatement {
 	case token.APPEND:
 		stmt.Mode = "APPEND"
 	default:
-		p.addError("expected READ, WRITE, or APPEND after FOR")
+		p.addError(diagnostic.ExpectedFileMode)
 		return nil
 	}
 
@@ -675,6 +729,17 @@ func (p *Parser) parseCloseFileStatement() *ast.CloseFileStatement {
 	return stmt
 }
 
+func (p *Parser) parseIncludeStatement() *ast.IncludeStatement {
+	stmt := &ast.IncludeStatement{Token: p.curToken}
+
+	if !p.expectPeek(token.STRING_LIT) {
+		return nil
+	}
+	stmt.Path = p.curToken.Literal
+
+	return stmt
+}
+
 func (p *Parser) parseReadFileStatement() *ast.ReadFileStatement {
 	stmt := &ast.ReadFileStatement{Token: p.curToken}
 
@@ -734,6 +799,7 @@ func (p *Parser) parseTypeStatement() *ast.TypeStatement {
 		p.nextToken()
 		p.skipNewlines()
 		stmt.Definition = p.parseRecordType()
+		p.expectEnd(stmt.Token, token.ENDTYPE)
 	}
 
 	return stmt
@@ -746,7 +812,7 @@ func (p *Parser) parseRecordType() *ast.RecordType {
 		if p.curTokenIs(token.DECLARE) {
 			p.nextToken()
 			if !p.curTokenIs(token.IDENT) {
-				p.addError("expected field name")
+				p.addError(diagnostic.ExpectedFieldName)
 				return record
 			}
 			fieldName := p.curToken.Literal
@@ -820,6 +886,7 @@ func (p *Parser) parseClassStatement() *ast.ClassStatement {
 		p.nextToken()
 		p.skipNewlines()
 	}
+	p.expectEnd(stmt.Token, token.ENDCLASS)
 
 	return stmt
 }
@@ -856,7 +923,7 @@ func (p *Parser) parseAccessModifiedStatement() ast.Statement {
 		}
 		return stmt
 	default:
-		p.addError("expected PROCEDURE, FUNCTION, DECLARE, or property name after access modifier")
+		p.addError(diagnostic.ExpectedMemberAfterModifier)
 		return nil
 	}
 }
@@ -864,7 +931,7 @@ func (p *Parser) parseAccessModifiedStatement() ast.Statement {
 // parsePropertyDeclaration parses: Name : TYPE (used in class definitions)
 func (p *Parser) parsePropertyDeclaration() *ast.DeclareStatement {
 	stmt := &ast.DeclareStatement{Token: p.curToken}
-	stmt.Name = &ast.Identifier{Token: p.curToken, Value: p.curToken.Literal}
+	stmt.Name = p.arena.Identifier(ast.Identifier{Token: p.curToken, Value: p.curToken.Literal})
 
 	if !p.expectPeek(token.COLON) {
 		return nil
@@ -882,13 +949,13 @@ func (p *Parser) parseAssignmentOrExpressionStatement() ast.Statement {
 	if p.peekTokenIs(token.ASSIGN) {
 		// This is an assignment
 		p.nextToken()
-		stmt := &ast.AssignmentStatement{Token: p.curToken, Name: expr}
+		stmt := p.arena.AssignmentStatement(ast.AssignmentStatement{Token: p.curToken, Name: expr})
 		p.nextToken()
 		stmt.Value = p.parseExpression(LOWEST)
 		return stmt
 	}
 
-	return &ast.ExpressionStatement{Token: p.curToken, Expression: expr}
+	return p.arena.ExpressionStatement(ast.ExpressionStatement{Token: p.curToken, Expression: expr})
 }
 
 func (p *Parser) parseBlockStatements(endTokens ...token.Type) []ast.Statement {
@@ -927,7 +994,7 @@ func (p *Parser) parseDataType() ast.DataType {
 	case token.IDENT:
 		return &ast.CustomType{Name: p.curToken.Literal}
 	default:
-		p.addError(fmt.Sprintf("unexpected data type: %s", p.curToken.Literal))
+		p.addError(diagnostic.UnexpectedDataType, p.curToken.Literal)
 		return &ast.PrimitiveType{Name: "UNKNOWN"}
 	}
 }
@@ -944,7 +1011,7 @@ func (p *Parser) parseArrayType() *ast.ArrayType {
 		p.nextToken()
 		lower, err := strconv.Atoi(p.curToken.Literal)
 		if err != nil {
-			p.addError("expected integer for array lower bound")
+			p.addError(diagnostic.ExpectedLowerBound)
 			return arrType
 		}
 
@@ -955,7 +1022,7 @@ func (p *Parser) parseArrayType() *ast.ArrayType {
 		p.nextToken()
 		upper, err := strconv.Atoi(p.curToken.Literal)
 		if err != nil {
-			p.addError("expected integer for array upper bound")
+			p.addError(diagnostic.ExpectedUpperBound)
 			return arrType
 		}
 
@@ -987,7 +1054,7 @@ func (p *Parser) parseArrayType() *ast.ArrayType {
 func (p *Parser) parseExpression(precedence int) ast.Expression {
 	prefix := p.prefixParseFns[p.curToken.Type]
 	if prefix == nil {
-		p.addError(fmt.Sprintf("no prefix parse function for %s", p.curToken.Type))
+		p.addError(diagnostic.UnexpectedToken, p.curToken.Type)
 		return nil
 	}
 	leftExp := prefix()
@@ -1005,15 +1072,21 @@ func (p *Parser) parseExpression(precedence int) ast.Expression {
 }
 
 func (p *Parser) parseIdentifier() ast.Expression {
-	return &ast.Identifier{Token: p.curToken, Value: p.curToken.Literal}
+	return p.arena.Identifier(ast.Identifier{Token: p.curToken, Value: p.curToken.Literal})
 }
 
 func (p *Parser) parseIntegerLiteral() ast.Expression {
-	lit := &ast.IntegerLiteral{Token: p.curToken}
+	lit := p.arena.IntegerLiteral(ast.IntegerLiteral{Token: p.curToken})
 
 	value, err := strconv.ParseInt(p.curToken.Literal, 0, 64)
+	if errors.Is(err, strconv.ErrRange) {
+		if n, ok := new(big.Int).SetString(p.curToken.Literal, 0); ok {
+			lit.Value, lit.Big = math.MaxInt64, n
+			return lit
+		}
+	}
 	if err != nil {
-		p.addError(fmt.Sprintf("could not parse %q as integer", p.curToken.Literal))
+		p.addError(diagnostic.InvalidInteger, p.curToken.Literal)
 		return nil
 	}
 
@@ -1022,11 +1095,11 @@ func (p *Parser) parseIntegerLiteral() ast.Expression {
 }
 
 func (p *Parser) parseRealLiteral() ast.Expression {
-	lit := &ast.RealLiteral{Token: p.curToken}
+	lit := p.arena.RealLiteral(ast.RealLiteral{Token: p.curToken})
 
 	value, err := strconv.ParseFloat(p.curToken.Literal, 64)
 	if err != nil {
-		p.addError(fmt.Sprintf("could not parse %q as real", p.curToken.Literal))
+		p.addError(diagnostic.InvalidReal, p.curToken.Literal)
 		return nil
 	}
 
@@ -1035,25 +1108,25 @@ func (p *Parser) parseRealLiteral() ast.Expression {
 }
 
 func (p *Parser) parseStringLiteral() ast.Expression {
-	return &ast.StringLiteral{Token: p.curToken, Value: p.curToken.Literal}
+	return p.arena.StringLiteral(ast.StringLiteral{Token: p.curToken, Value: p.curToken.Literal})
 }
 
 func (p *Parser) parseCharLiteral() ast.Expression {
-	return &ast.CharLiteral{Token: p.curToken, Value: p.curToken.Literal}
+	return p.arena.CharLiteral(ast.CharLiteral{Token: p.curToken, Value: p.curToken.Literal})
 }
 
 func (p *Parser) parseBooleanLiteral() ast.Expression {
-	return &ast.BooleanLiteral{
+	return p.arena.BooleanLiteral(ast.BooleanLiteral{
 		Token: p.curToken,
 		Value: strings.ToUpper(p.curToken.Literal) == "TRUE",
-	}
+	})
 }
 
 func (p *Parser) parsePrefixExpression() ast.Expression {
-	expression := &ast.PrefixExpression{
+	expression := p.arena.PrefixExpression(ast.PrefixExpression{
 		Token:    p.curToken,
 		Operator: p.curToken.Literal,
-	}
+	})
 
 	p.nextToken()
 	expression.Right = p.parseExpression(PREFIX)
@@ -1062,11 +1135,11 @@ func (p *Parser) parsePrefixExpression() ast.Expression {
 }
 
 func (p *Parser) parseInfixExpression(left ast.Expression) ast.Expression {
-	expression := &ast.InfixExpression{
+	expression := p.arena.InfixExpression(ast.InfixExpression{
 		Token:    p.curToken,
 		Operator: p.curToken.Literal,
 		Left:     left,
-	}
+	})
 
 	precedence := p.curPrecedence()
 	p.nextToken()
@@ -1088,7 +1161,7 @@ func (p *Parser) parseGroupedExpression() ast.Expression {
 }
 
 func (p *Parser) parseCallExpression(function ast.Expression) ast.Expression {
-	exp := &ast.CallExpression{Token: p.curToken, Function: function}
+	exp := p.arena.CallExpression(ast.CallExpression{Token: p.curToken, Function: function})
 	exp.Arguments = p.parseExpressionList(token.RPAREN)
 	return exp
 }
@@ -1118,7 +1191,7 @@ func (p *Parser) parseExpressionList(end token.Type) []ast.Expression {
 }
 
 func (p *Parser) parseArrayAccess(array ast.Expression) ast.Expression {
-	exp := &ast.ArrayAccess{Token: p.curToken, Array: array}
+	exp := p.arena.ArrayAccess(ast.ArrayAccess{Token: p.curToken, Array: array})
 	exp.Indices = p.parseExpressionList(token.RBRACKET)
 	return exp
 }
@@ -1,6 +1,8 @@
 package parser
 
 import (
+	"encoding/json"
+	"strings"
 	"testing"
 
 	"github.com/andrinoff/cambridge-lang/pkg/ast"
@@ -191,6 +193,14 @@ func TestParseIntegerLiteral(t *testing.T) {
 	if lit.Value != 42 {
 		t.Errorf("lit.Value not 42. got=%d", lit.Value)
 	}
+
+	p = New(lexer.New(`x <- 99999999999999999999`))
+	program = p.ParseProgram()
+	checkParserErrors(t, p)
+	lit = program.Statements[0].(*ast.AssignmentStatement).Value.(*ast.IntegerLiteral)
+	if lit.Big == nil || lit.Big.String() != "99999999999999999999" {
+		t.Errorf("expected a literal beyond 64 bits to be kept whole, got %v", lit.Big)
+	}
 }
 
 func TestParseRealLiteral(t *testing.T) {
@@ -888,6 +898,31 @@ func TestParseWriteFileStatement(t *testing.T) {
 	}
 }
 
+func TestParseIncludeStatement(t *testing.T) {
+	input := `INCLUDE "lib/sort.cam"`
+
+	l := lexer.New(input)
+	p := New(l)
+	program := p.ParseProgram()
+	checkParserErrors(t, p)
+
+	stmt, ok := program.Statements[0].(*ast.IncludeStatement)
+	if !ok {
+		t.Fatalf("program.Statements[0] is not *ast.IncludeStatement. got=%T",
+			program.Statements[0])
+	}
+
+	if stmt.Path != "lib/sort.cam" {
+		t.Errorf("stmt.Path wrong. expected=%q, got=%q", "lib/sort.cam", stmt.Path)
+	}
+
+	p = New(lexer.New("INCLUDE Sort"))
+	p.ParseProgram()
+	if len(p.Errors()) == 0 {
+		t.Error("expected an error for INCLUDE without a file name")
+	}
+}
+
 func TestParseTypeStatement(t *testing.T) {
 	input := `TYPE Person
     DECLARE name : STRING
@@ -1160,6 +1195,98 @@ func TestParserErrors(t *testing.T) {
 	}
 }
 
+func TestMissingBlockEnd(t *testing.T) {
+	tests := []struct {
+		input    string
+		expected []string
+	}{
+		{"IF TRUE THEN\n    OUTPUT 1\n", []string{"line 1, column 1: IF is never closed with ENDIF"}},
+		{"DECLARE X : INTEGER\nWHILE X < 3\n    X <- X + 1\n", []string{"line 2, column 1: WHILE is never closed with ENDWHILE"}},
+		{"REPEAT\n    OUTPUT 1\n", []string{"line 1, column 1: REPEAT is never closed with UNTIL"}},
+		{"PROCEDURE P()\n    FOR I <- 1 TO 3\n        OUTPUT I\n", []string{
+			"line 2, column 5: FOR is never closed with NEXT",
+			"line 1, column 1: PROCEDURE is never closed with ENDPROCEDURE",
+		}},
+		{"IF TRUE THEN\n    OUTPUT 1\nENDIF\n", nil},
+	}
+
+	for _, tt := range tests {
+		p := New(lexer.New(tt.input))
+		p.ParseProgram()
+		if strings.Join(p.Errors(), "; ") != strings.Join(tt.expected, "; ") {
+			t.Errorf("%q: expected errors %q, got %q", tt.input, tt.expected, p.Errors())
+		}
+	}
+}
+
+func TestArenaReuse(t *testing.T) {
+	// Enough nodes to need several blocks of each type
+	first := strings.Repeat("x <- x + 1\nOUTPUT x * 2, \"a\"\n", 200)
+	second := strings.Repeat("y <- Fn(y)[1]\n", 150)
+
+	arena := ast.NewArena()
+	p := NewWithArena(lexer.New(first), arena)
+	program := p.ParseProgram()
+	checkParserErrors(t, p)
+	if len(program.Statements) != 400 {
+		t.Fatalf("expected 400 statements, got %d", len(program.Statements))
+	}
+
+	arena.Reset()
+	p = NewWithArena(lexer.New(second), arena)
+	reused := p.ParseProgram()
+	checkParserErrors(t, p)
+
+	fresh := New(lexer.New(second)).ParseProgram()
+	if reused.String() != fresh.String() {
+		t.Errorf("program parsed into a reset arena differs:\n%s\nexpected:\n%s", reused.String(), fresh.String())
+	}
+}
+
+func TestJSONRoundTrip(t *testing.T) {
+	input := `TYPE Point
+    DECLARE X : INTEGER
+ENDTYPE
+DECLARE Grid : ARRAY[1:3, 1:3] OF REAL
+CLASS Pet
+    PRIVATE DECLARE Name : STRING
+    PUBLIC FUNCTION Speak(n : INTEGER) RETURNS STRING
+        RETURN LEFT(Name, n) & 'x'
+    ENDFUNCTION
+ENDCLASS
+CASE OF Grid[1, 2]
+    1 TO 5 : OUTPUT -1.5
+    OTHERWISE : CALL Done(TRUE)
+ENDCASE`
+
+	p := New(lexer.New(input))
+	program := p.ParseProgram()
+	checkParserErrors(t, p)
+
+	data, err := json.Marshal(program)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if !strings.HasPrefix(string(data), `{"statements":[{"kind":"TypeStatement","token":{"type":"TYPE"`) {
+		t.Errorf("unexpected JSON: %.80s", data)
+	}
+
+	var decoded ast.Program
+	if err := json.Unmarshal(data, &decoded); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if decoded.String() != program.String() {
+		t.Errorf("expected the same program back\nwritten:\n%s\nread:\n%s", program.String(), decoded.String())
+	}
+	if line, column := ast.Position(decoded.Statements[3]); line != 11 || column != 1 {
+		t.Errorf("expected the CASE at 11:1, got %d:%d", line, column)
+	}
+
+	if err := json.Unmarshal([]byte(`{"statements":[{"kind":"CustomType"}]}`), &decoded); err == nil {
+		t.Error("expected an error for a data type where a statement belongs")
+	}
+}
+
 // Helper functions
 
 func checkParserErrors(t *testing.T, p *Parser) {
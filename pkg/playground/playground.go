@@ -0,0 +1,325 @@
+// Package playground serves a web page for writing and running programs,
+// for `cambridge serve`, so that a class can use the language from a
+// browser with nothing installed
+// The page checks the program as it is typed, over HTTP, and runs it over
+// a WebSocket that carries its output to the page and asks the page for
+// each line INPUT reads. A run keeps its files in memory, is stopped after
+// a number of statements and may only make strings and arrays of a bounded
+// size, so no program can reach the disk of the machine serving it, keep
+// it busy for ever or use up its memory.
+package playground
+
+import (
+	_ "embed"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"net/http"
+	"runtime"
+	"sync"
+
+	"github.com/andrinoff/cambridge-lang/pkg/cambridge"
+	"github.com/andrinoff/cambridge-lang/pkg/check"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/websocket"
+)
+
+//go:embed page.html
+var page []byte
+
+// Defaults for the limits in Options
+// DefaultDepth is well below interpreter.DefaultMaxDepth, as every run
+// nesting calls that deep would hold its own share of the Go stack.
+const (
+	DefaultSteps  = 10_000_000
+	DefaultOutput = 1 << 20
+	DefaultSize   = 1 << 20
+	DefaultDepth  = 1000
+)
+
+// maxSource bounds the programs sent, so a page cannot make the server
+// read without limit
+const maxSource = 1 << 20
+
+// fuel is how many statements a run takes at a time between checks on
+// whether it should stop
+const fuel = 10_000
+
+// Options limit what the programs run for the page may do
+type Options struct {
+	Steps  int // statements a run may take, DefaultSteps if 0
+	Output int // bytes of output a run may show, DefaultOutput if 0
+	Size   int // characters in a STRING or elements in an ARRAY, DefaultSize if 0
+	Depth  int // routine calls that may nest, DefaultDepth if 0
+	Runs   int // programs running at once, the others waiting, one per CPU if 0
+}
+
+// Handler serves the page at /, checks programs at /check and runs them
+// over a WebSocket at /run
+func Handler(opts Options) http.Handler {
+	if opts.Steps <= 0 {
+		opts.Steps = DefaultSteps
+	}
+	if opts.Output <= 0 {
+		opts.Output = DefaultOutput
+	}
+	if opts.Size <= 0 {
+		opts.Size = DefaultSize
+	}
+	if opts.Depth <= 0 {
+		opts.Depth = DefaultDepth
+	}
+	if opts.Runs <= 0 {
+		opts.Runs = runtime.NumCPU()
+	}
+	s := &server{opts: opts, slots: make(chan struct{}, opts.Runs)}
+
+	mux := http.NewServeMux()
+	mux.HandleFunc("GET /{$}", func(w http.ResponseWriter, r *http.Request) {
+		w.Header().Set("Content-Type", "text/html; charset=utf-8")
+		w.Write(page)
+	})
+	mux.HandleFunc("POST /check", s.check)
+	mux.HandleFunc("GET /run", s.run)
+	return mux
+}
+
+type server struct {
+	opts  Options
+	slots chan struct{} // one held by each program running, but not while it waits for input
+}
+
+// message is what the page and a run send each other over the WebSocket:
+// the page sends "run" with the source, then "input" with each line asked
+// for or "stop"; the run sends "output", "input" to ask for a line and
+// finally "done"
+type message struct {
+	Type        string                  `json:"type"`
+	Source      string                  `json:"source,omitempty"`
+	Text        string                  `json:"text,omitempty"`
+	Diagnostics []diagnostic.Diagnostic `json:"diagnostics,omitempty"`
+}
+
+// check answers with the diagnostics for the program in the request,
+// those the parser reports and, if it parses, those checking it finds
+func (s *server) check(w http.ResponseWriter, r *http.Request) {
+	if !websocket.SameOrigin(r) {
+		http.Error(w, "only the playground's own page may check programs", http.StatusForbidden)
+		return
+	}
+	var req message
+	if err := json.NewDecoder(http.MaxBytesReader(w, r.Body, maxSource)).Decode(&req); err != nil {
+		http.Error(w, "expected a program as JSON", http.StatusBadRequest)
+		return
+	}
+	prog := cambridge.Parse("main.cam", req.Source)
+	found := append([]diagnostic.Diagnostic{}, prog.Diagnostics()...)
+	if prog.OK() {
+		found = append(found, check.Program(prog.AST(), req.Source)...)
+	}
+	w.Header().Set("Content-Type", "application/json")
+	json.NewEncoder(w).Encode(map[string]interface{}{"diagnostics": found})
+}
+
+// run runs the program the page sends over a WebSocket
+func (s *server) run(w http.ResponseWriter, r *http.Request) {
+	if !websocket.SameOrigin(r) {
+		http.Error(w, "only the playground's own page may run programs", http.StatusForbidden)
+		return
+	}
+	conn, err := websocket.Upgrade(w, r)
+	if err != nil {
+		return
+	}
+	defer conn.Close()
+
+	send := func(msg message) error {
+		data, err := json.Marshal(msg)
+		if err != nil {
+			return err
+		}
+		return conn.Write(data)
+	}
+	data, err := conn.Read()
+	if err != nil {
+		return
+	}
+	var req message
+	if err := json.Unmarshal(data, &req); err != nil || req.Type != "run" || len(req.Source) > maxSource {
+		send(message{Type: "done", Text: "expected a program to run"})
+		return
+	}
+
+	rn := newRun(send, s.opts, s.slots)
+	go func() {
+		for {
+			data, err := conn.Read()
+			if err != nil {
+				rn.stop()
+				return
+			}
+			var msg message
+			if json.Unmarshal(data, &msg) != nil {
+				continue
+			}
+			switch msg.Type {
+			case "input":
+				select {
+				case rn.lines <- msg.Text:
+				case <-rn.stopped:
+					return
+				}
+			case "stop":
+				rn.stop()
+			}
+		}
+	}()
+	rn.execute(req.Source)
+}
+
+// run is one program running for a page, as the input and output of its
+// interpreter
+type run struct {
+	send    func(message) error
+	opts    Options
+	slots   chan struct{} // shared by the runs, as server.slots
+	held    bool          // the run holds one of slots
+	lines   chan string   // typed on the page
+	stopped chan struct{} // closed once the page stops the run or goes away
+	once    sync.Once
+	pending string // read from the page but not yet by INPUT
+	written int    // bytes of output shown
+	cut     bool   // output past Options.Output was dropped
+}
+
+func newRun(send func(message) error, opts Options, slots chan struct{}) *run {
+	return &run{send: send, opts: opts, slots: slots, lines: make(chan string, 16), stopped: make(chan struct{})}
+}
+
+// acquire waits for a slot to run in, reporting false if the run is
+// stopped first
+func (r *run) acquire() bool {
+	select {
+	case r.slots <- struct{}{}:
+		r.held = true
+		return true
+	case <-r.stopped:
+		return false
+	}
+}
+
+// release gives up the run's slot, if it holds one
+func (r *run) release() {
+	if r.held {
+		<-r.slots
+		r.held = false
+	}
+}
+
+func (r *run) stop() {
+	r.once.Do(func() { close(r.stopped) })
+}
+
+func (r *run) isStopped() bool {
+	select {
+	case <-r.stopped:
+		return true
+	default:
+		return false
+	}
+}
+
+// Read asks the page for a line each time INPUT needs one, giving up the
+// run's slot while the page is waiting for someone to type it
+func (r *run) Read(p []byte) (int, error) {
+	if r.pending == "" {
+		if r.isStopped() {
+			return 0, io.EOF
+		}
+		if err := r.send(message{Type: "input"}); err != nil {
+			return 0, err
+		}
+		r.release()
+		select {
+		case line := <-r.lines:
+			r.pending = line + "\n"
+		case <-r.stopped:
+			return 0, io.EOF
+		}
+		if !r.acquire() {
+			return 0, io.EOF
+		}
+	}
+	n := copy(p, r.pending)
+	r.pending = r.pending[n:]
+	return n, nil
+}
+
+// Write shows what OUTPUT writes on the page, up to Options.Output bytes
+func (r *run) Write(p []byte) (int, error) {
+	shown := p
+	if r.written+len(p) > r.opts.Output {
+		shown = p[:max(r.opts.Output-r.written, 0)]
+		r.cut = true
+	}
+	if len(shown) > 0 {
+		r.written += len(shown)
+		if err := r.send(message{Type: "output", Text: string(shown)}); err != nil {
+			return 0, err
+		}
+	}
+	return len(p), nil
+}
+
+// execute runs source, sending "done" with the diagnostics that stopped
+// it, if any, and why it was cut short, if it was
+func (r *run) execute(source string) {
+	done := message{Type: "done"}
+	defer func() { r.send(done) }()
+
+	prog := cambridge.Parse("main.cam", source)
+	if !prog.OK() {
+		done.Diagnostics = prog.Diagnostics()
+		return
+	}
+	if !r.acquire() {
+		done.Text = "stopped"
+		return
+	}
+	defer r.release()
+	interp := cambridge.New()
+	interp.SetInput(r)
+	interp.SetOutput(r)
+	interp.SetFiles(interpreter.NewMemoryFiles(nil))
+	interp.SetMaxSize(r.opts.Size)
+	interp.SetMaxDepth(r.opts.Depth)
+
+	exec := interp.Start(prog)
+	for left := r.opts.Steps; ; {
+		n := min(fuel, left)
+		finished, err := exec.Continue(n)
+		left -= n
+		if finished {
+			var runErr *cambridge.Error
+			if errors.As(err, &runErr) {
+				done.Diagnostics = []diagnostic.Diagnostic{runErr.Diagnostic}
+			}
+			break
+		}
+		if r.isStopped() {
+			exec.Stop()
+			done.Text = "stopped"
+			break
+		}
+		if left <= 0 {
+			exec.Stop()
+			done.Text = fmt.Sprintf("stopped after %d statements, in case it runs for ever", r.opts.Steps)
+			break
+		}
+	}
+	if r.cut && done.Text == "" {
+		done.Text = fmt.Sprintf("only the first %d bytes of output are shown", r.opts.Output)
+	}
+}
@@ -0,0 +1,230 @@
+package playground
+
+import (
+	"encoding/json"
+	"net/http"
+	"net/http/httptest"
+	"reflect"
+	"strings"
+	"testing"
+	"time"
+
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+)
+
+func TestPage(t *testing.T) {
+	rec := httptest.NewRecorder()
+	Handler(Options{}).ServeHTTP(rec, httptest.NewRequest("GET", "/", nil))
+	if rec.Code != http.StatusOK || !strings.Contains(rec.Body.String(), "<textarea") {
+		t.Errorf("expected the page, got %d %q", rec.Code, rec.Body.String())
+	}
+}
+
+func TestCheck(t *testing.T) {
+	tests := []struct {
+		source string
+		code   diagnostic.Code // of the first diagnostic, "" if there are none
+	}{
+		{"OUTPUT 1", ""},
+		{"IF TRUE\n    OUTPUT 1\nENDIF", diagnostic.ExpectedToken},
+		{"DECLARE Score : INTEGER\nOUTPUT Score", diagnostic.UnassignedVariable},
+	}
+
+	handler := Handler(Options{})
+	for _, tt := range tests {
+		body, _ := json.Marshal(message{Type: "check", Source: tt.source})
+		rec := httptest.NewRecorder()
+		handler.ServeHTTP(rec, httptest.NewRequest("POST", "/check", strings.NewReader(string(body))))
+		var resp struct {
+			Diagnostics []diagnostic.Diagnostic `json:"diagnostics"`
+		}
+		if err := json.Unmarshal(rec.Body.Bytes(), &resp); err != nil {
+			t.Fatalf("%q: unexpected response %q", tt.source, rec.Body.String())
+		}
+		var code diagnostic.Code
+		if len(resp.Diagnostics) > 0 {
+			code = resp.Diagnostics[0].Code
+		}
+		if code != tt.code {
+			t.Errorf("%q: expected %q first, got %v", tt.source, tt.code, resp.Diagnostics)
+		}
+	}
+
+	rec := httptest.NewRecorder()
+	handler.ServeHTTP(rec, httptest.NewRequest("POST", "/check", strings.NewReader("not json")))
+	if rec.Code != http.StatusBadRequest {
+		t.Errorf("expected a bad request, got %d", rec.Code)
+	}
+}
+
+func TestExecute(t *testing.T) {
+	tests := []struct {
+		name     string
+		source   string
+		input    []string
+		opts     Options
+		expected []message
+	}{
+		{
+			"input and output",
+			"DECLARE Name : STRING\nOUTPUT \"Name?\"\nINPUT Name\nOUTPUT \"Hello, \", Name",
+			[]string{"Ada"},
+			Options{Steps: DefaultSteps, Output: DefaultOutput},
+			[]message{
+				{Type: "output", Text: "Name?\n"},
+				{Type: "input"},
+				{Type: "output", Text: "Hello, Ada\n"},
+				{Type: "done"},
+			},
+		},
+		{
+			"output cut short",
+			"OUTPUT \"0123456789\"\nOUTPUT \"more\"",
+			nil,
+			Options{Steps: DefaultSteps, Output: 8},
+			[]message{
+				{Type: "output", Text: "01234567"},
+				{Type: "done", Text: "only the first 8 bytes of output are shown"},
+			},
+		},
+		{
+			"step limit",
+			"WHILE TRUE\nENDWHILE",
+			nil,
+			Options{Steps: 50, Output: DefaultOutput},
+			[]message{
+				{Type: "done", Text: "stopped after 50 statements, in case it runs for ever"},
+			},
+		},
+	}
+
+	for _, tt := range tests {
+		var sent []message
+		rn := newRun(func(msg message) error {
+			sent = append(sent, msg)
+			return nil
+		}, tt.opts, make(chan struct{}, 1))
+		for _, line := range tt.input {
+			rn.lines <- line
+		}
+		rn.execute(tt.source)
+		if !reflect.DeepEqual(sent, tt.expected) {
+			t.Errorf("%s: expected %+v, got %+v", tt.name, tt.expected, sent)
+		}
+	}
+}
+
+func TestExecuteErrors(t *testing.T) {
+	tests := []struct {
+		source string
+		code   diagnostic.Code
+	}{
+		{"OUTPUT 1 DIV 0", diagnostic.DivisionByZero},
+		{"OUTPUT (1", diagnostic.ExpectedToken},
+		{"DECLARE S : STRING\nS <- \"ab\"\nWHILE TRUE\n    S <- S & S\nENDWHILE", diagnostic.StringTooLong},
+		{"DECLARE A : ARRAY[1:10] OF INTEGER\nDECLARE N : INTEGER\nN <- 0\nWHILE TRUE\n    N <- N + 1\n    A[N] <- N\nENDWHILE", diagnostic.ArrayTooLarge},
+		{"PROCEDURE Forever(N : INTEGER)\n    CALL Forever(N + 1)\nENDPROCEDURE\nCALL Forever(1)", diagnostic.RecursionLimit},
+	}
+
+	for _, tt := range tests {
+		var sent []message
+		rn := newRun(func(msg message) error {
+			sent = append(sent, msg)
+			return nil
+		}, Options{Steps: DefaultSteps, Output: DefaultOutput, Size: DefaultSize, Depth: DefaultDepth}, make(chan struct{}, 1))
+		rn.execute(tt.source)
+		last := sent[len(sent)-1]
+		if last.Type != "done" || len(last.Diagnostics) == 0 || last.Diagnostics[0].Code != tt.code {
+			t.Errorf("%q: expected done with %s, got %+v", tt.source, tt.code, sent)
+		}
+	}
+}
+
+func TestDepthLimit(t *testing.T) {
+	var sent []message
+	rn := newRun(func(msg message) error {
+		sent = append(sent, msg)
+		return nil
+	}, Options{Steps: DefaultSteps, Output: DefaultOutput, Depth: 20}, make(chan struct{}, 1))
+	rn.execute("FUNCTION Sum(N : INTEGER) RETURNS INTEGER\n    RETURN N + Sum(N - 1)\nENDFUNCTION\nOUTPUT Sum(5)")
+	last := sent[len(sent)-1]
+	if len(last.Diagnostics) == 0 || last.Diagnostics[0].Code != diagnostic.RecursionLimit {
+		t.Fatalf("expected the recursion limit, got %+v", sent)
+	}
+	if depth := last.Diagnostics[0].Args[0]; depth != 20 {
+		t.Errorf("expected to stop 20 calls deep, got %v", depth)
+	}
+}
+
+func TestStop(t *testing.T) {
+	var sent []message
+	rn := newRun(func(msg message) error {
+		sent = append(sent, msg)
+		return nil
+	}, Options{Steps: DefaultSteps, Output: DefaultOutput}, make(chan struct{}, 1))
+	rn.stop()
+	rn.execute("DECLARE Name : STRING\nWHILE TRUE\n    INPUT Name\nENDWHILE")
+	expected := []message{{Type: "done", Text: "stopped"}}
+	if !reflect.DeepEqual(sent, expected) {
+		t.Errorf("expected %+v, got %+v", expected, sent)
+	}
+}
+
+func TestInputFreesSlot(t *testing.T) {
+	slots := make(chan struct{}, 1)
+	asked := make(chan struct{}, 1)
+	var sent []message
+	rn := newRun(func(msg message) error {
+		sent = append(sent, msg)
+		if msg.Type == "input" {
+			asked <- struct{}{}
+		}
+		return nil
+	}, Options{Steps: DefaultSteps, Output: DefaultOutput}, slots)
+	finished := make(chan struct{})
+	go func() {
+		rn.execute("DECLARE Name : STRING\nINPUT Name\nOUTPUT Name")
+		close(finished)
+	}()
+
+	<-asked
+	select {
+	case slots <- struct{}{}:
+		<-slots
+	case <-time.After(5 * time.Second):
+		t.Fatal("expected the slot to be free while the run waits for input")
+	}
+	rn.lines <- "Ada"
+	<-finished
+	if len(sent) < 2 || sent[len(sent)-1].Type != "done" || sent[len(sent)-2].Text != "Ada\n" {
+		t.Errorf("expected Ada then done, got %+v", sent)
+	}
+	if len(slots) != 0 {
+		t.Errorf("expected the slot to be given back, got %d held", len(slots))
+	}
+}
+
+func TestOrigin(t *testing.T) {
+	tests := []struct {
+		method, path, origin string
+		status               int
+	}{
+		{"POST", "/check", "", http.StatusOK},
+		{"POST", "/check", "http://example.com", http.StatusOK},
+		{"POST", "/check", "http://elsewhere.example", http.StatusForbidden},
+		{"GET", "/run", "http://elsewhere.example", http.StatusForbidden},
+	}
+
+	handler := Handler(Options{})
+	for _, tt := range tests {
+		req := httptest.NewRequest(tt.method, tt.path, strings.NewReader(`{"type":"check","source":"OUTPUT 1"}`))
+		if tt.origin != "" {
+			req.Header.Set("Origin", tt.origin)
+		}
+		rec := httptest.NewRecorder()
+		handler.ServeHTTP(rec, req)
+		if rec.Code != tt.status {
+			t.Errorf("%s %s from %q: expected %d, got %d", tt.method, tt.path, tt.origin, tt.status, rec.Code)
+		}
+	}
+}
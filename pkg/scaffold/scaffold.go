@@ -0,0 +1,104 @@
+// Package scaffold creates starter project folders
+package scaffold
+
+import (
+	"fmt"
+	"os"
+	"path/filepath"
+	"strconv"
+	"strings"
+)
+
+// EntryFile is the program created in every new project
+const EntryFile = "main.cam"
+
+// Files returns the starter files for a project called name, keyed by their
+// path relative to the project root
+// The name is quoted in cambridge.toml, so a quote or backslash in it is
+// read back as written.
+func Files(name string) map[string]string {
+	return map[string]string{
+		EntryFile: `// ` + name + `
+// Cambridge International AS & A Level Computer Science (9618) pseudocode
+
+DECLARE Name : STRING
+
+OUTPUT "Hello, World!"
+OUTPUT "What is your name?"
+INPUT Name
+OUTPUT "Welcome, ", Name
+`,
+
+		"cambridge.toml": `# Project settings for the cambridge CLI and language server
+[project]
+name = ` + strconv.Quote(name) + `
+entry = "` + EntryFile + `"
+
+[language]
+# Syllabus the program is written against: "9618" or "0478"
+dialect = "9618"
+# Year of the exams whose insert lists the builtins a program may use (defaults to the latest)
+# insert = 2023
+# "decimal" makes REAL sums exact, so 0.1 + 0.2 is 0.3 (default "float")
+# real = "decimal"
+
+[diagnostics]
+# Language for error messages: "en", "es" or "fr" (defaults to the system locale)
+# locale = "en"
+`,
+
+		// Each test case pairs the INPUT lines it supplies with the
+		// OUTPUT the program must produce
+		"tests/hello.in": "Ada\n",
+
+		"tests/hello.out": `Hello, World!
+What is your name?
+Welcome, Ada
+`,
+
+		".vscode/settings.json": `{
+  "files.associations": {
+    "*.cam": "cambridge-pseudo"
+  },
+  "editor.tabSize": 4,
+  "editor.insertSpaces": true
+}
+`,
+
+		".zed/settings.json": `{
+  "file_types": {
+    "Cambridge Pseudocode": ["cam"]
+  },
+  "tab_size": 4,
+  "hard_tabs": false
+}
+`,
+	}
+}
+
+// Create writes a new project into dir, which must not already exist
+// The project is named after the last element of dir
+func Create(dir string) error {
+	if _, err := os.Stat(dir); err == nil {
+		return fmt.Errorf("%s already exists", dir)
+	} else if !os.IsNotExist(err) {
+		return err
+	}
+
+	name := filepath.Base(filepath.Clean(dir))
+	if name == "." || name == string(filepath.Separator) || strings.TrimSpace(name) == "" {
+		return fmt.Errorf("invalid project name %q", dir)
+	}
+
+	for rel, content := range Files(name) {
+		path := filepath.Join(dir, filepath.FromSlash(rel))
+		if err := os.MkdirAll(filepath.Dir(path), 0755); err != nil {
+			return err
+		}
+		if err := os.WriteFile(path, []byte(content), 0644); err != nil {
+			return err
+		}
+	}
+
+	return nil
+}
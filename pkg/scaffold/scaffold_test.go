@@ -0,0 +1,82 @@
+package scaffold
+
+import (
+	"bytes"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/config"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+func TestCreateWritesAllFiles(t *testing.T) {
+	dir := filepath.Join(t.TempDir(), "coursework")
+
+	if err := Create(dir); err != nil {
+		t.Fatalf("Create failed: %v", err)
+	}
+
+	for rel := range Files("coursework") {
+		if _, err := os.Stat(filepath.Join(dir, filepath.FromSlash(rel))); err != nil {
+			t.Errorf("expected %s to exist: %v", rel, err)
+		}
+	}
+
+	config, err := os.ReadFile(filepath.Join(dir, "cambridge.toml"))
+	if err != nil {
+		t.Fatalf("reading config: %v", err)
+	}
+	if !strings.Contains(string(config), `name = "coursework"`) {
+		t.Errorf("config does not name the project:\n%s", config)
+	}
+}
+
+func TestNameQuoted(t *testing.T) {
+	for _, name := range []string{`say "hi"`, `back\slash`, `tag "#1"`} {
+		settings, err := config.Parse(Files(name)["cambridge.toml"])
+		if err != nil {
+			t.Errorf("%s: the config does not parse: %v", name, err)
+			continue
+		}
+		if settings.Project.Name != name {
+			t.Errorf("expected the project named %q, got %q", name, settings.Project.Name)
+		}
+	}
+}
+
+func TestCreateRefusesExistingDirectory(t *testing.T) {
+	dir := t.TempDir()
+
+	if err := Create(dir); err == nil {
+		t.Fatalf("expected error creating project in existing directory")
+	}
+}
+
+func TestExampleTestCasePasses(t *testing.T) {
+	files := Files("demo")
+
+	p := parser.New(lexer.New(files[EntryFile]))
+	program := p.ParseProgram()
+	if len(p.Errors()) > 0 {
+		t.Fatalf("starter program has parse errors: %v", p.Errors())
+	}
+
+	var out bytes.Buffer
+	interp := interpreter.New()
+	interp.SetBuiltins(builtins.GetBuiltins())
+	interp.SetInput(strings.NewReader(files["tests/hello.in"]))
+	interp.SetOutput(&out)
+
+	if result, ok := interp.Eval(program).(*interpreter.Error); ok {
+		t.Fatalf("starter program failed: %s", result.Inspect())
+	}
+
+	if out.String() != files["tests/hello.out"] {
+		t.Errorf("expected output %q, got %q", files["tests/hello.out"], out.String())
+	}
+}
@@ -0,0 +1,244 @@
+// Package selfupdate replaces the installed binaries with the latest
+// GitHub release
+package selfupdate
+
+import (
+	"bufio"
+	"context"
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"fmt"
+	"io"
+	"net/http"
+	"os"
+	"path/filepath"
+	"runtime"
+	"strconv"
+	"strings"
+)
+
+// ChecksumsAsset is the release asset listing the SHA-256 of every binary
+const ChecksumsAsset = "checksums.txt"
+
+// Release is a published GitHub release
+type Release struct {
+	TagName string  `json:"tag_name"`
+	Assets  []Asset `json:"assets"`
+}
+
+// Asset is a file attached to a release
+type Asset struct {
+	Name string `json:"name"`
+	URL  string `json:"browser_download_url"`
+}
+
+// Version returns the release version without its leading "v"
+func (r *Release) Version() string {
+	return strings.TrimPrefix(r.TagName, "v")
+}
+
+func (r *Release) asset(name string) (Asset, bool) {
+	for _, a := range r.Assets {
+		if a.Name == name {
+			return a, true
+		}
+	}
+	return Asset{}, false
+}
+
+// Updater talks to the GitHub releases API
+type Updater struct {
+	Client  *http.Client
+	BaseURL string // API root, overridable for testing
+	Repo    string // owner/name
+}
+
+// New returns an updater for the official releases
+func New() *Updater {
+	return &Updater{
+		Client:  http.DefaultClient,
+		BaseURL: "https://api.github.com",
+		Repo:    "andrinoff/cambridge-lang",
+	}
+}
+
+// Latest fetches the most recent release
+func (u *Updater) Latest(ctx context.Context) (*Release, error) {
+	url := fmt.Sprintf("%s/repos/%s/releases/latest", u.BaseURL, u.Repo)
+	body, err := u.get(ctx, url)
+	if err != nil {
+		return nil, err
+	}
+	defer body.Close()
+
+	var release Release
+	if err := json.NewDecoder(body).Decode(&release); err != nil {
+		return nil, fmt.Errorf("cannot decode release: %v", err)
+	}
+	return &release, nil
+}
+
+// AssetName returns the release asset holding binary ("cambridge" or
+// "cambridge-lsp") built for the given platform
+func AssetName(binary, goos, goarch string) (string, error) {
+	names := map[string]map[string]string{
+		"cambridge": {
+			"linux/amd64":   "cambridge-linux-amd64",
+			"linux/arm64":   "cambridge-linux-arm64",
+			"darwin/amd64":  "cambridge-macos-intel",
+			"darwin/arm64":  "cambridge-macos-arm64",
+			"windows/amd64": "cambridge-windows.exe",
+		},
+		"cambridge-lsp": {
+			"linux/amd64":   "cambridge-lsp-linux",
+			"linux/arm64":   "cambridge-lsp-linux-arm64",
+			"darwin/amd64":  "cambridge-lsp-macos-intel",
+			"darwin/arm64":  "cambridge-lsp-macos-arm64",
+			"windows/amd64": "cambridge-lsp.exe",
+			"windows/arm64": "cambridge-lsp-windows-arm64.exe",
+		},
+	}
+
+	name, ok := names[binary][goos+"/"+goarch]
+	if !ok {
+		return "", fmt.Errorf("no %s release for %s/%s", binary, goos, goarch)
+	}
+	return name, nil
+}
+
+// Newer reports whether version latest is later than current
+// Versions are compared as dotted numbers, ignoring a leading "v".
+func Newer(current, latest string) bool {
+	a := strings.Split(strings.TrimPrefix(current, "v"), ".")
+	b := strings.Split(strings.TrimPrefix(latest, "v"), ".")
+	for n := 0; n < max(len(a), len(b)); n++ {
+		x, y := part(a, n), part(b, n)
+		if x != y {
+			return y > x
+		}
+	}
+	return false
+}
+
+func part(parts []string, n int) int {
+	if n >= len(parts) {
+		return 0
+	}
+	v, _ := strconv.Atoi(parts[n])
+	return v
+}
+
+// Install downloads binary from release, checks it against the release
+// checksums and replaces the executable at path with it
+func (u *Updater) Install(ctx context.Context, release *Release, binary, path string) error {
+	name, err := AssetName(binary, runtime.GOOS, runtime.GOARCH)
+	if err != nil {
+		return err
+	}
+	asset, ok := release.asset(name)
+	if !ok {
+		return fmt.Errorf("release %s has no asset %s", release.TagName, name)
+	}
+	sums, ok := release.asset(ChecksumsAsset)
+	if !ok {
+		return fmt.Errorf("release %s has no %s, refusing to install unverified binary", release.TagName, ChecksumsAsset)
+	}
+
+	want, err := u.checksum(ctx, sums.URL, name)
+	if err != nil {
+		return err
+	}
+
+	// Download next to the target so the final rename stays on one filesystem
+	tmp, err := os.CreateTemp(filepath.Dir(path), "."+filepath.Base(path)+".new-*")
+	if err != nil {
+		return err
+	}
+	defer os.Remove(tmp.Name())
+
+	body, err := u.get(ctx, asset.URL)
+	if err != nil {
+		tmp.Close()
+		return err
+	}
+	hash := sha256.New()
+	_, err = io.Copy(io.MultiWriter(tmp, hash), body)
+	body.Close()
+	if closeErr := tmp.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return fmt.Errorf("cannot download %s: %v", name, err)
+	}
+
+	if got := hex.EncodeToString(hash.Sum(nil)); got != want {
+		return fmt.Errorf("checksum mismatch for %s: expected %s, got %s", name, want, got)
+	}
+	if err := os.Chmod(tmp.Name(), 0755); err != nil {
+		return err
+	}
+
+	return replace(tmp.Name(), path)
+}
+
+// replace moves the file at src over dst
+// A running executable cannot be overwritten on Windows, but it can be
+// renamed, so the old binary is moved aside first.
+func replace(src, dst string) error {
+	if runtime.GOOS != "windows" {
+		return os.Rename(src, dst)
+	}
+
+	old := dst + ".old"
+	os.Remove(old)
+	if err := os.Rename(dst, old); err != nil {
+		return err
+	}
+	if err := os.Rename(src, dst); err != nil {
+		os.Rename(old, dst)
+		return err
+	}
+	return nil
+}
+
+// checksum finds the SHA-256 for name in a sha256sum-style checksums file
+func (u *Updater) checksum(ctx context.Context, url, name string) (string, error) {
+	body, err := u.get(ctx, url)
+	if err != nil {
+		return "", err
+	}
+	defer body.Close()
+
+	scanner := bufio.NewScanner(body)
+	for scanner.Scan() {
+		fields := strings.Fields(scanner.Text())
+		if len(fields) == 2 && strings.TrimPrefix(fields[1], "*") == name {
+			return strings.ToLower(fields[0]), nil
+		}
+	}
+	if err := scanner.Err(); err != nil {
+		return "", err
+	}
+	return "", fmt.Errorf("%s has no entry for %s", ChecksumsAsset, name)
+}
+
+func (u *Updater) get(ctx context.Context, url string) (io.ReadCloser, error) {
+	req, err := http.NewRequestWithContext(ctx, http.MethodGet, url, nil)
+	if err != nil {
+		return nil, err
+	}
+	if strings.HasPrefix(url, u.BaseURL) {
+		req.Header.Set("Accept", "application/vnd.github+json")
+	}
+
+	resp, err := u.Client.Do(req)
+	if err != nil {
+		return nil, err
+	}
+	if resp.StatusCode != http.StatusOK {
+		resp.Body.Close()
+		return nil, fmt.Errorf("GET %s: %s", url, resp.Status)
+	}
+	return resp.Body, nil
+}
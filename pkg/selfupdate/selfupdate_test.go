@@ -0,0 +1,129 @@
+package selfupdate
+
+import (
+	"context"
+	"crypto/sha256"
+	"encoding/hex"
+	"fmt"
+	"net/http"
+	"net/http/httptest"
+	"os"
+	"path/filepath"
+	"runtime"
+	"testing"
+)
+
+func TestNewer(t *testing.T) {
+	tests := []struct {
+		current, latest string
+		expected        bool
+	}{
+		{"0.2.0", "0.2.1", true},
+		{"0.2.0", "v0.10.0", true},
+		{"1.0.0", "0.9.9", false},
+		{"0.2.0", "0.2.0", false},
+		{"0.2", "0.2.0", false},
+	}
+
+	for _, tt := range tests {
+		if got := Newer(tt.current, tt.latest); got != tt.expected {
+			t.Errorf("Newer(%q, %q) = %t, want %t", tt.current, tt.latest, got, tt.expected)
+		}
+	}
+}
+
+func TestAssetName(t *testing.T) {
+	tests := []struct {
+		binary, goos, goarch string
+		expected             string
+	}{
+		{"cambridge", "darwin", "arm64", "cambridge-macos-arm64"},
+		{"cambridge-lsp", "linux", "arm64", "cambridge-lsp-linux-arm64"},
+		{"cambridge-lsp", "windows", "arm64", "cambridge-lsp-windows-arm64.exe"},
+	}
+
+	for _, tt := range tests {
+		if name, err := AssetName(tt.binary, tt.goos, tt.goarch); err != nil || name != tt.expected {
+			t.Errorf("%s for %s/%s: expected %q, got %q (%v)", tt.binary, tt.goos, tt.goarch, tt.expected, name, err)
+		}
+	}
+	if _, err := AssetName("cambridge-lsp", "plan9", "386"); err == nil {
+		t.Errorf("expected no plan9/386 language server build")
+	}
+}
+
+// releaseServer serves a release whose binary is content, with checksums
+// listing sum for it
+func releaseServer(t *testing.T, content, sum string) *Updater {
+	t.Helper()
+	name, err := AssetName("cambridge", runtime.GOOS, runtime.GOARCH)
+	if err != nil {
+		t.Skip(err)
+	}
+
+	mux := http.NewServeMux()
+	var server *httptest.Server
+	mux.HandleFunc("/repos/test/repo/releases/latest", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprintf(w, `{"tag_name": "v9.9.9", "assets": [
+			{"name": %q, "browser_download_url": "%s/download/bin"},
+			{"name": "checksums.txt", "browser_download_url": "%s/download/sums"}
+		]}`, name, server.URL, server.URL)
+	})
+	mux.HandleFunc("/download/bin", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, content)
+	})
+	mux.HandleFunc("/download/sums", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprintf(w, "%s  %s\n", sum, name)
+	})
+	server = httptest.NewServer(mux)
+	t.Cleanup(server.Close)
+
+	return &Updater{Client: server.Client(), BaseURL: server.URL, Repo: "test/repo"}
+}
+
+func TestInstall(t *testing.T) {
+	content := "new binary"
+	hash := sha256.Sum256([]byte(content))
+	u := releaseServer(t, content, hex.EncodeToString(hash[:]))
+
+	release, err := u.Latest(context.Background())
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if release.Version() != "9.9.9" {
+		t.Errorf("expected version 9.9.9, got %s", release.Version())
+	}
+
+	path := filepath.Join(t.TempDir(), "cambridge")
+	if err := os.WriteFile(path, []byte("old binary"), 0755); err != nil {
+		t.Fatal(err)
+	}
+
+	if err := u.Install(context.Background(), release, "cambridge", path); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if got, _ := os.ReadFile(path); string(got) != content {
+		t.Errorf("expected binary to be replaced, got %q", got)
+	}
+}
+
+func TestInstallRejectsBadChecksum(t *testing.T) {
+	u := releaseServer(t, "tampered binary", "0000")
+
+	release, err := u.Latest(context.Background())
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	path := filepath.Join(t.TempDir(), "cambridge")
+	if err := os.WriteFile(path, []byte("old binary"), 0755); err != nil {
+		t.Fatal(err)
+	}
+
+	if err := u.Install(context.Background(), release, "cambridge", path); err == nil {
+		t.Fatalf("expected checksum error")
+	}
+	if got, _ := os.ReadFile(path); string(got) != "old binary" {
+		t.Errorf("binary was replaced despite bad checksum")
+	}
+}
@@ -0,0 +1,120 @@
+// Package similarity detects structurally similar submissions
+// Programs are compared on their normalized token streams, so renaming
+// variables or changing whitespace and comments does not hide copying
+package similarity
+
+import (
+	"sort"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// DefaultShingleSize is the number of consecutive tokens compared at once
+const DefaultShingleSize = 5
+
+// Submission is a single normalized program
+type Submission struct {
+	Name   string
+	Tokens []string
+}
+
+// Pair is the similarity score between two submissions
+type Pair struct {
+	A     string
+	B     string
+	Score float64 // 0.0 (nothing shared) to 1.0 (identical structure)
+}
+
+// Normalize tokenizes source and replaces identifiers and literal values
+// with placeholders, keeping keywords and operators as they are
+func Normalize(source string) []string {
+	l := lexer.New(source)
+	var tokens []string
+
+	for tok := l.NextToken(); tok.Type != token.EOF; tok = l.NextToken() {
+		switch tok.Type {
+		case token.NEWLINE:
+			continue
+		case token.IDENT:
+			tokens = append(tokens, "ID")
+		case token.INTEGER_LIT, token.REAL_LIT:
+			tokens = append(tokens, "NUM")
+		case token.STRING_LIT, token.CHAR_LIT:
+			tokens = append(tokens, "STR")
+		default:
+			tokens = append(tokens, string(tok.Type))
+		}
+	}
+
+	return tokens
+}
+
+// NewSubmission normalizes source into a named submission
+func NewSubmission(name, source string) Submission {
+	return Submission{Name: name, Tokens: Normalize(source)}
+}
+
+// Score returns the Jaccard similarity of the token shingles of a and b
+func Score(a, b []string, size int) float64 {
+	sa := shingles(a, size)
+	sb := shingles(b, size)
+
+	if len(sa) == 0 && len(sb) == 0 {
+		return 0
+	}
+
+	shared := 0
+	for s := range sa {
+		if sb[s] {
+			shared++
+		}
+	}
+
+	union := len(sa) + len(sb) - shared
+	return float64(shared) / float64(union)
+}
+
+// Compare scores every pair of submissions, most similar first
+func Compare(subs []Submission, size int) []Pair {
+	var pairs []Pair
+
+	for i := 0; i < len(subs); i++ {
+		for j := i + 1; j < len(subs); j++ {
+			pairs = append(pairs, Pair{
+				A:     subs[i].Name,
+				B:     subs[j].Name,
+				Score: Score(subs[i].Tokens, subs[j].Tokens, size),
+			})
+		}
+	}
+
+	sort.SliceStable(pairs, func(i, j int) bool {
+		return pairs[i].Score > pairs[j].Score
+	})
+
+	return pairs
+}
+
+// shingles returns the set of all runs of size consecutive tokens
+// Programs shorter than size contribute a single shingle of everything
+func shingles(tokens []string, size int) map[string]bool {
+	set := make(map[string]bool)
+	if len(tokens) == 0 {
+		return set
+	}
+	if size < 1 {
+		size = 1
+	}
+	if len(tokens) < size {
+		set[strings.Join(tokens, " ")] = true
+		return set
+	}
+
+	for i := 0; i+size <= len(tokens); i++ {
+		set[strings.Join(tokens[i:i+size], " ")] = true
+	}
+
+	return set
+}
@@ -0,0 +1,86 @@
+package similarity
+
+import (
+	"testing"
+)
+
+const original = `DECLARE Total : INTEGER
+Total <- 0
+FOR i <- 1 TO 10
+    Total <- Total + i
+NEXT i
+OUTPUT "Sum: ", Total`
+
+// Same structure with renamed variables, a different message and comments
+const renamed = `// my own work
+DECLARE Sum : INTEGER
+Sum <- 0
+FOR Count <- 1 TO 20
+    Sum <- Sum + Count
+NEXT Count
+OUTPUT "Total is ", Sum`
+
+const different = `DECLARE Name : STRING
+INPUT Name
+IF Name = "Alice" THEN
+    OUTPUT "Hello Alice"
+ELSE
+    OUTPUT "Who are you?"
+ENDIF`
+
+func TestNormalizeIgnoresNamesAndLiterals(t *testing.T) {
+	a := Normalize(original)
+	b := Normalize(renamed)
+
+	if len(a) != len(b) {
+		t.Fatalf("expected equal token counts, got %d and %d", len(a), len(b))
+	}
+	for i := range a {
+		if a[i] != b[i] {
+			t.Errorf("token %d differs: %q vs %q", i, a[i], b[i])
+		}
+	}
+}
+
+func TestScoreIdenticalStructure(t *testing.T) {
+	score := Score(Normalize(original), Normalize(renamed), DefaultShingleSize)
+	if score != 1.0 {
+		t.Errorf("expected score 1.0 for renamed copy, got %f", score)
+	}
+}
+
+func TestScoreDifferentPrograms(t *testing.T) {
+	score := Score(Normalize(original), Normalize(different), DefaultShingleSize)
+	if score > 0.2 {
+		t.Errorf("expected low score for unrelated programs, got %f", score)
+	}
+}
+
+func TestScoreEmpty(t *testing.T) {
+	if score := Score(nil, nil, DefaultShingleSize); score != 0 {
+		t.Errorf("expected 0 for empty submissions, got %f", score)
+	}
+}
+
+func TestCompareOrdersBySimilarity(t *testing.T) {
+	subs := []Submission{
+		NewSubmission("alice", original),
+		NewSubmission("bob", different),
+		NewSubmission("carol", renamed),
+	}
+
+	pairs := Compare(subs, DefaultShingleSize)
+	if len(pairs) != 3 {
+		t.Fatalf("expected 3 pairs, got %d", len(pairs))
+	}
+
+	if pairs[0].A != "alice" || pairs[0].B != "carol" {
+		t.Errorf("expected alice/carol to be most similar, got %s/%s", pairs[0].A, pairs[0].B)
+	}
+
+	for i := 1; i < len(pairs); i++ {
+		if pairs[i].Score > pairs[i-1].Score {
+			t.Errorf("pairs not sorted: %f after %f", pairs[i].Score, pairs[i-1].Score)
+		}
+	}
+}
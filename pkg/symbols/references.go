@@ -0,0 +1,110 @@
+package symbols
+
+import (
+	"sort"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// typeContexts are the tokens a type name follows, as in DECLARE P : Point,
+// ARRAY[1:3] OF Point, RETURNS Point, INHERITS Point or ^Point
+var typeContexts = map[token.Type]bool{
+	token.COLON:    true,
+	token.OF:       true,
+	token.RETURNS:  true,
+	token.INHERITS: true,
+	token.CARET:    true,
+}
+
+// references finds every use in program of the symbols declared in root
+// Names in expressions come from the syntax tree; type names, which it
+// keeps only as strings, come from the tokens.
+func (b *builder) references(root *Scope, program *ast.Program) []Reference {
+	var refs []Reference
+	seen := map[Position]bool{}
+	writes := map[Position]bool{}
+	add := func(pos Position, sym *Symbol) {
+		if sym != nil && !seen[pos] && pos != sym.Pos {
+			seen[pos] = true
+			refs = append(refs, Reference{Pos: pos, Symbol: sym, Write: writes[pos]})
+		}
+	}
+
+	ast.Inspect(program, func(node ast.Node) bool {
+		switch n := node.(type) {
+		case *ast.AssignmentStatement:
+			b.written(n.Name, writes)
+		case *ast.InputStatement:
+			b.written(n.Variable, writes)
+		case *ast.ReadFileStatement:
+			b.written(n.Variable, writes)
+		}
+		return true
+	})
+
+	ast.Inspect(program, func(node ast.Node) bool {
+		switch n := node.(type) {
+		case *ast.Identifier:
+			pos := Position{n.Token.Line, n.Token.Column}
+			add(pos, root.At(pos).Lookup(n.Value))
+		case *ast.MemberAccess:
+			pos, ok := b.member(n)
+			if !ok {
+				break
+			}
+			scope := root.At(pos)
+			if custom, ok := scope.TypeOf(n.Object).(*ast.CustomType); ok {
+				add(pos, scope.Member(custom.Name, n.Member))
+			}
+		case *ast.NewExpression:
+			idx := b.index(n.Token) + 1
+			if idx < len(b.tokens) && b.tokens[idx].Literal == n.ClassName {
+				pos := b.pos(idx)
+				add(pos, root.At(pos).Lookup(n.ClassName))
+			}
+		}
+		return true
+	})
+
+	for idx := 1; idx < len(b.tokens); idx++ {
+		tok := b.tokens[idx]
+		if tok.Type != token.IDENT || !typeContexts[b.tokens[idx-1].Type] {
+			continue
+		}
+		pos := b.pos(idx)
+		if sym := root.At(pos).Lookup(tok.Literal); sym != nil && (sym.Kind == Type || sym.Kind == Class) {
+			add(pos, sym)
+		}
+	}
+
+	sort.Slice(refs, func(i, j int) bool { return refs[i].Pos.Before(refs[j].Pos) })
+	return refs
+}
+
+// member returns where the name after the dot of n is written
+func (b *builder) member(n *ast.MemberAccess) (Position, bool) {
+	idx := b.index(n.Token) + 1
+	if idx >= len(b.tokens) || b.tokens[idx].Literal != n.Member {
+		return Position{}, false
+	}
+	return b.pos(idx), true
+}
+
+// written marks where the name given a value by assigning to target is
+// written: a variable, or the array or field an element of it belongs to
+func (b *builder) written(target ast.Expression, writes map[Position]bool) {
+	if ast.IsNil(target) {
+		return
+	}
+	switch t := target.(type) {
+	case *ast.Identifier:
+		writes[Position{t.Token.Line, t.Token.Column}] = true
+	case *ast.ArrayAccess:
+		b.written(t.Array, writes)
+	case *ast.MemberAccess:
+		if pos, ok := b.member(t); ok {
+			writes[pos] = true
+		}
+	}
+}
@@ -0,0 +1,569 @@
+// Package symbols finds the names a program declares and the scopes they
+// are visible in, for editor features such as completion
+package symbols
+
+import (
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/token"
+)
+
+// Kind is what sort of thing a symbol names
+type Kind string
+
+const (
+	Variable  Kind = "variable"
+	Constant  Kind = "constant"
+	Parameter Kind = "parameter"
+	Procedure Kind = "procedure"
+	Function  Kind = "function"
+	Type      Kind = "type"
+	Class     Kind = "class"
+	Field     Kind = "field"
+	EnumValue Kind = "enumerated value"
+)
+
+// Position is a 1-based line and column, as the lexer counts them
+type Position struct {
+	Line   int
+	Column int
+}
+
+// Before reports whether p comes before q
+func (p Position) Before(q Position) bool {
+	return p.Line < q.Line || p.Line == q.Line && p.Column < q.Column
+}
+
+// Symbol is a declared name
+type Symbol struct {
+	Name     string
+	Kind     Kind
+	Detail   string       // the declared type, or the heading of a routine, type or class
+	Pos      Position     // where the name itself is written
+	Start    Position     // where the declaration starts
+	End      Position     // just after it ends: the keyword closing a block, or else the name
+	DataType ast.DataType // the type of a variable, parameter or field, or what a function returns
+	Access   string       // "PUBLIC" or "PRIVATE" for class members
+	Parent   string       // the class a class inherits from
+	Members  []*Symbol    // the fields of a record type or the members of a class
+	Params   []*Symbol    // the parameters of a routine, in order
+}
+
+// Scope is a part of the program with names of its own: the program, a
+// routine, a class or a FOR loop, whose variable belongs to the loop
+type Scope struct {
+	Owner    *Symbol  // the routine or class, nil for the program and loops
+	Start    Position // the keyword opening the scope
+	End      Position // the keyword closing it, or the end of the source
+	Parent   *Scope
+	Children []*Scope
+	Symbols  []*Symbol
+}
+
+// Table is what Build finds in a program: its scopes, with the symbols
+// declared in each, and every place a symbol is used
+type Table struct {
+	Program    *Scope
+	References []Reference
+}
+
+// Reference is a use of a symbol's name
+type Reference struct {
+	Pos    Position
+	Symbol *Symbol
+	Write  bool // whether it is given a value there, not read
+}
+
+// Build collects the symbols program declares and the uses of them, with
+// source the text it was parsed from, which gives the positions the syntax
+// tree leaves out
+func Build(program *ast.Program, source string) *Table {
+	b := &builder{at: map[Position]int{}}
+	l := lexer.New(source)
+	for tok := l.NextToken(); ; tok = l.NextToken() {
+		b.at[Position{tok.Line, tok.Column}] = len(b.tokens)
+		b.tokens = append(b.tokens, tok)
+		if tok.Type == token.EOF {
+			break
+		}
+	}
+
+	root := &Scope{Start: Position{1, 1}, End: b.pos(len(b.tokens) - 1)}
+	b.statements(root, program.Statements)
+	return &Table{Program: root, References: b.references(root, program)}
+}
+
+// At returns the innermost scope containing pos
+func (t *Table) At(pos Position) *Scope {
+	return t.Program.At(pos)
+}
+
+// SymbolAt returns the symbol whose name is written at pos, where it is
+// declared or used, or nil
+func (t *Table) SymbolAt(pos Position) *Symbol {
+	if sym := declaredAt(t.Program, pos); sym != nil {
+		return sym
+	}
+	for _, ref := range t.References {
+		if covers(ref.Pos, ref.Symbol.Name, pos) {
+			return ref.Symbol
+		}
+	}
+	return nil
+}
+
+// Symbols returns every symbol declared in the program, members included
+func (t *Table) Symbols() []*Symbol {
+	var all []*Symbol
+	var walk func(scope *Scope)
+	walk = func(scope *Scope) {
+		for _, sym := range scope.Symbols {
+			all = append(all, sym)
+			if sym.Kind == Type {
+				all = append(all, sym.Members...) // a class's are in its scope
+			}
+		}
+		for _, child := range scope.Children {
+			walk(child)
+		}
+	}
+	walk(t.Program)
+	return all
+}
+
+// Uses returns the references to sym, in the order they appear
+func (t *Table) Uses(sym *Symbol) []Reference {
+	var uses []Reference
+	for _, ref := range t.References {
+		if ref.Symbol == sym {
+			uses = append(uses, ref)
+		}
+	}
+	return uses
+}
+
+// Conflict returns a symbol that calling sym name instead would clash
+// with, or nil if there is none: another member of the same type, one
+// visible where sym is declared or used, which the new name would hide or
+// be hidden by, or one used where sym would capture the use
+func (t *Table) Conflict(sym *Symbol, name string) *Symbol {
+	if owner := ownerOf(t.Program, sym); owner != nil {
+		if other := t.Program.Member(owner.Name, name); other != nil {
+			return other
+		}
+	}
+	uses := append([]Reference{{Pos: sym.Pos, Symbol: sym}}, t.Uses(sym)...)
+	for _, use := range uses {
+		if other := t.At(use.Pos).Lookup(name); other != nil && other != sym {
+			return other
+		}
+	}
+	for _, ref := range t.References {
+		if ref.Symbol.Name == name && t.At(ref.Pos).Lookup(sym.Name) == sym {
+			return ref.Symbol
+		}
+	}
+	return nil
+}
+
+// ownerOf returns the type or class in scope that member belongs to, or nil
+func ownerOf(scope *Scope, member *Symbol) *Symbol {
+	for _, sym := range scope.Symbols {
+		for _, m := range sym.Members {
+			if m == member {
+				return sym
+			}
+		}
+	}
+	for _, child := range scope.Children {
+		if owner := ownerOf(child, member); owner != nil {
+			return owner
+		}
+	}
+	return nil
+}
+
+// declaredAt returns the symbol declared in scope or within it whose name
+// is written at pos, or nil
+func declaredAt(scope *Scope, pos Position) *Symbol {
+	for _, sym := range scope.Symbols {
+		if covers(sym.Pos, sym.Name, pos) {
+			return sym
+		}
+		for _, m := range sym.Members {
+			if covers(m.Pos, m.Name, pos) {
+				return m
+			}
+		}
+	}
+	for _, child := range scope.Children {
+		if sym := declaredAt(child, pos); sym != nil {
+			return sym
+		}
+	}
+	return nil
+}
+
+// covers reports whether name written at start spans pos
+func covers(start Position, name string, pos Position) bool {
+	return pos.Line == start.Line && start.Column <= pos.Column && pos.Column < start.Column+len(name)
+}
+
+// Declaration writes how sym is declared, as pseudocode
+func (sym *Symbol) Declaration() string {
+	switch sym.Kind {
+	case Variable, Field:
+		return "DECLARE " + sym.Name + " : " + sym.Detail
+	case Constant:
+		return "CONSTANT " + sym.Name + " " + sym.Detail
+	case Parameter:
+		if typ, ok := strings.CutPrefix(sym.Detail, "BYREF "); ok {
+			return "BYREF " + sym.Name + " : " + typ
+		}
+		return sym.Name + " : " + sym.Detail
+	}
+	return sym.Detail
+}
+
+// At returns the innermost scope containing pos
+func (s *Scope) At(pos Position) *Scope {
+	for _, child := range s.Children {
+		if !pos.Before(child.Start) && !child.End.Before(pos) {
+			return child.At(pos)
+		}
+	}
+	return s
+}
+
+// Visible returns the symbols that can be used in s, innermost first,
+// leaving out those hidden by a closer declaration of the same name
+func (s *Scope) Visible() []*Symbol {
+	var visible []*Symbol
+	seen := map[string]bool{}
+	for scope := s; scope != nil; scope = scope.Parent {
+		for _, sym := range scope.Symbols {
+			if !seen[sym.Name] {
+				seen[sym.Name] = true
+				visible = append(visible, sym)
+			}
+		}
+	}
+	return visible
+}
+
+// Lookup returns the symbol name refers to in s, or nil
+func (s *Scope) Lookup(name string) *Symbol {
+	for scope := s; scope != nil; scope = scope.Parent {
+		for _, sym := range scope.Symbols {
+			if sym.Name == name {
+				return sym
+			}
+		}
+	}
+	return nil
+}
+
+// Members returns the fields of the record type or the members of the
+// class typeName refers to in s, with those a class inherits after its own,
+// or nil if it names neither
+func (s *Scope) Members(typeName string) []*Symbol {
+	var members []*Symbol
+	seen := map[string]bool{}
+	for depth := 0; typeName != "" && depth < 32; depth++ { // bounded in case classes inherit in a circle
+		typ := s.Lookup(typeName)
+		if typ == nil || typ.Kind != Type && typ.Kind != Class {
+			break
+		}
+		for _, m := range typ.Members {
+			if !seen[m.Name] {
+				seen[m.Name] = true
+				members = append(members, m)
+			}
+		}
+		typeName = typ.Parent
+	}
+	return members
+}
+
+// Member returns the member called name of the type typeName refers to in
+// s, or nil
+func (s *Scope) Member(typeName, name string) *Symbol {
+	for _, m := range s.Members(typeName) {
+		if m.Name == name {
+			return m
+		}
+	}
+	return nil
+}
+
+// TypeOf returns the declared type of expr in s, as far as it can be told
+// from declarations alone: a name, an element of an array, a field or
+// member, a call or a NEW; nil if it cannot
+func (s *Scope) TypeOf(expr ast.Expression) ast.DataType {
+	if ast.IsNil(expr) {
+		return nil
+	}
+	switch e := expr.(type) {
+	case *ast.Identifier:
+		if sym := s.Lookup(e.Value); sym != nil {
+			return sym.DataType
+		}
+	case *ast.ArrayAccess:
+		if array, ok := s.TypeOf(e.Array).(*ast.ArrayType); ok {
+			return array.ElementType
+		}
+	case *ast.MemberAccess:
+		if custom, ok := s.TypeOf(e.Object).(*ast.CustomType); ok {
+			if m := s.Member(custom.Name, e.Member); m != nil {
+				return m.DataType
+			}
+		}
+	case *ast.CallExpression:
+		return s.TypeOf(e.Function)
+	case *ast.NewExpression:
+		return &ast.CustomType{Name: e.ClassName}
+	}
+	return nil
+}
+
+// builder walks a program alongside the tokens of its source
+type builder struct {
+	tokens []token.Token
+	at     map[Position]int // the index of the token starting at each position
+}
+
+func (b *builder) pos(idx int) Position {
+	return Position{b.tokens[idx].Line, b.tokens[idx].Column}
+}
+
+// after returns the position just after the token at idx
+func (b *builder) after(idx int) Position {
+	return Position{b.tokens[idx].Line, b.tokens[idx].Column + len(b.tokens[idx].Literal)}
+}
+
+// span sets where sym's declaration starts and ends, from the tokens at
+// start and end
+func (b *builder) span(sym *Symbol, start, end int) {
+	sym.Start, sym.End = b.pos(start), b.after(end)
+}
+
+// index returns the index of the token tok, which starts a statement
+func (b *builder) index(tok token.Token) int {
+	return b.at[Position{tok.Line, tok.Column}]
+}
+
+// find returns the index of the first name at or after from spelled name,
+// or -1
+func (b *builder) find(from int, name string) int {
+	for idx := from; idx < len(b.tokens); idx++ {
+		tok := b.tokens[idx]
+		if tok.Literal == name && (tok.Type == token.IDENT || tok.Type == token.NEW) {
+			return idx
+		}
+	}
+	return -1
+}
+
+// closing returns the index of the close keyword ending the block whose
+// open keyword is at or after from, or of the end of the source
+func (b *builder) closing(from int, open, close token.Type) int {
+	depth := 0
+	for idx := from; idx < len(b.tokens); idx++ {
+		switch b.tokens[idx].Type {
+		case open:
+			depth++
+		case close:
+			depth--
+			if depth == 0 {
+				return idx
+			}
+		}
+	}
+	return len(b.tokens) - 1
+}
+
+// declare adds a symbol named by the token at idx, or at from if the name
+// was not found, declared from the token at from up to its name
+func (b *builder) declare(scope *Scope, name string, kind Kind, detail string, idx, from int) *Symbol {
+	if idx < 0 {
+		idx = from
+	}
+	sym := &Symbol{Name: name, Kind: kind, Detail: detail, Pos: b.pos(idx)}
+	b.span(sym, min(from, idx), idx)
+	scope.Symbols = append(scope.Symbols, sym)
+	return sym
+}
+
+// named adds a symbol declared by the statement starting with tok, whose
+// name is the identifier id
+func (b *builder) named(scope *Scope, tok token.Token, id *ast.Identifier, kind Kind, detail string) *Symbol {
+	return b.declare(scope, id.Value, kind, detail, b.index(id.Token), b.index(tok))
+}
+
+// child opens a scope from the token at start to the one at end
+func (b *builder) child(parent *Scope, owner *Symbol, start, end int) *Scope {
+	scope := &Scope{Owner: owner, Start: b.pos(start), End: b.pos(end), Parent: parent}
+	parent.Children = append(parent.Children, scope)
+	return scope
+}
+
+func (b *builder) statements(scope *Scope, stmts []ast.Statement) {
+	for _, stmt := range stmts {
+		b.statement(scope, stmt)
+	}
+}
+
+func (b *builder) statement(scope *Scope, stmt ast.Statement) {
+	if ast.IsNil(stmt) {
+		return
+	}
+	switch s := stmt.(type) {
+	case *ast.DeclareStatement:
+		sym := b.named(scope, s.Token, s.Name, Variable, typeName(s.DataType))
+		sym.DataType, sym.Access = s.DataType, s.Access
+	case *ast.ConstantStatement:
+		sym := b.named(scope, s.Token, s.Name, Constant, "")
+		if !ast.IsNil(s.Value) {
+			sym.Detail = "= " + s.Value.String()
+		}
+	case *ast.ProcedureStatement:
+		b.routine(scope, s.Token, Procedure, s.Name, s.Parameters, nil, s.Body).Access = s.Access
+	case *ast.FunctionStatement:
+		sym := b.routine(scope, s.Token, Function, s.Name, s.Parameters, s.ReturnType, s.Body)
+		sym.Access, sym.DataType = s.Access, s.ReturnType
+	case *ast.TypeStatement:
+		b.typeStatement(scope, s)
+	case *ast.ClassStatement:
+		b.class(scope, s)
+	case *ast.ForStatement:
+		start := b.index(s.Token)
+		loop := b.child(scope, nil, start, b.closing(start, token.FOR, token.NEXT))
+		b.named(loop, s.Token, s.Variable, Variable, "INTEGER").DataType = &ast.PrimitiveType{Name: "INTEGER"}
+		b.statements(loop, s.Body)
+	case *ast.IfStatement:
+		b.statements(scope, s.Consequence)
+		b.statements(scope, s.Alternative)
+	case *ast.CaseStatement:
+		for _, c := range s.Cases {
+			b.statements(scope, c.Body)
+		}
+		b.statements(scope, s.Otherwise)
+	case *ast.WhileStatement:
+		b.statements(scope, s.Body)
+	case *ast.RepeatStatement:
+		b.statements(scope, s.Body)
+	}
+}
+
+// routine adds a procedure or function and the scope of its body, and
+// returns its symbol
+func (b *builder) routine(scope *Scope, tok token.Token, kind Kind, name string, params []ast.Parameter, returns ast.DataType, body []ast.Statement) *Symbol {
+	start := b.index(tok)
+	open, close := token.PROCEDURE, token.ENDPROCEDURE
+	if kind == Function {
+		open, close = token.FUNCTION, token.ENDFUNCTION
+	}
+	at := b.find(start+1, name)
+	sym := b.declare(scope, name, kind, signature(kind, name, params, returns), at, start)
+	end := b.closing(start, open, close)
+	b.span(sym, start, end)
+	inner := b.child(scope, sym, start, end)
+
+	from := max(at, start) + 1
+	for _, p := range params {
+		detail := typeName(p.DataType)
+		if p.ByRef {
+			detail = "BYREF " + detail
+		}
+		idx := b.find(from, p.Name)
+		param := b.declare(inner, p.Name, Parameter, detail, idx, max(idx, start))
+		param.DataType = p.DataType
+		sym.Params = append(sym.Params, param)
+		from = max(idx, start) + 1
+	}
+	b.statements(inner, body)
+	return sym
+}
+
+func (b *builder) typeStatement(scope *Scope, s *ast.TypeStatement) {
+	start := b.index(s.Token)
+	at := b.find(start+1, s.Name)
+	detail := "TYPE " + s.Name
+	switch def := s.Definition.(type) {
+	case *ast.EnumType, *ast.PointerType:
+		detail += " = " + def.String()
+	}
+	sym := b.declare(scope, s.Name, Type, detail, at, start)
+
+	// The values of an enumerated type are names in their own right
+	if enum, ok := s.Definition.(*ast.EnumType); ok {
+		from := max(at, start) + 1
+		for _, v := range enum.Values {
+			idx := max(b.find(from, v), start)
+			value := &Symbol{Name: v, Kind: EnumValue, Detail: detail, Pos: b.pos(idx), DataType: &ast.CustomType{Name: s.Name}}
+			b.span(value, idx, idx)
+			scope.Symbols = append(scope.Symbols, value)
+			from = idx + 1
+		}
+	}
+
+	if record, ok := s.Definition.(*ast.RecordType); ok {
+		b.span(sym, start, b.closing(start, token.TYPE, token.ENDTYPE))
+		from := max(at, start) + 1
+		for _, f := range record.Fields {
+			idx := max(b.find(from, f.Name), start)
+			field := &Symbol{Name: f.Name, Kind: Field, Detail: typeName(f.DataType), Pos: b.pos(idx), DataType: f.DataType}
+			b.span(field, idx, idx)
+			sym.Members = append(sym.Members, field)
+			from = idx + 1
+		}
+	}
+}
+
+func (b *builder) class(scope *Scope, s *ast.ClassStatement) {
+	start := b.index(s.Token)
+	detail := "CLASS " + s.Name
+	if s.Parent != "" {
+		detail += " INHERITS " + s.Parent
+	}
+	sym := b.declare(scope, s.Name, Class, detail, b.find(start+1, s.Name), start)
+	sym.Parent = s.Parent
+	end := b.closing(start, token.CLASS, token.ENDCLASS)
+	b.span(sym, start, end)
+	inner := b.child(scope, sym, start, end)
+	b.statements(inner, s.Members)
+	for _, member := range inner.Symbols {
+		if member.Kind == Variable {
+			member.Kind = Field
+		}
+	}
+	sym.Members = inner.Symbols
+}
+
+// signature is the heading a routine is declared with
+func signature(kind Kind, name string, params []ast.Parameter, returns ast.DataType) string {
+	var parts []string
+	for _, p := range params {
+		part := p.Name + " : " + typeName(p.DataType)
+		if p.ByRef {
+			part = "BYREF " + part
+		}
+		parts = append(parts, part)
+	}
+	heading := strings.ToUpper(string(kind)) + " " + name + "(" + strings.Join(parts, ", ") + ")"
+	if returns != nil {
+		heading += " RETURNS " + typeName(returns)
+	}
+	return heading
+}
+
+// typeName renders a data type, which a parser recovering from an error
+// may have left out
+func typeName(t ast.DataType) string {
+	if t == nil {
+		return ""
+	}
+	return t.String()
+}
@@ -0,0 +1,373 @@
+package symbols
+
+import (
+	"fmt"
+	"strings"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+const program = `DECLARE StudentMarks : ARRAY[1:30] OF INTEGER
+CONSTANT Pass = 40
+TYPE Point
+    DECLARE X : INTEGER
+    DECLARE Y : INTEGER
+ENDTYPE
+FUNCTION Average(BYREF Marks : ARRAY[1:30] OF INTEGER, Count : INTEGER) RETURNS REAL
+    DECLARE Total : INTEGER
+    Total <- 0
+    FOR I <- 1 TO Count
+        Total <- Total + Marks[I]
+    NEXT I
+    RETURN Total / Count
+ENDFUNCTION
+CLASS Pet
+    PRIVATE DECLARE Name : STRING
+    PUBLIC PROCEDURE NEW(N : STRING)
+        Name <- N
+    ENDPROCEDURE
+ENDCLASS
+OUTPUT Average(StudentMarks, 30)
+`
+
+func build(t *testing.T, src string) *Table {
+	t.Helper()
+	p := parser.New(lexer.New(src))
+	prog := p.ParseProgram()
+	if len(p.Errors()) > 0 {
+		t.Fatalf("parser errors: %v", p.Errors())
+	}
+	return Build(prog, src)
+}
+
+func TestBuild(t *testing.T) {
+	root := build(t, program).Program
+
+	tests := []struct {
+		name   string
+		kind   Kind
+		detail string
+		pos    Position
+	}{
+		{"StudentMarks", Variable, "ARRAY[1:30] OF INTEGER", Position{1, 9}},
+		{"Pass", Constant, "= 40", Position{2, 10}},
+		{"Point", Type, "TYPE Point", Position{3, 6}},
+		{"Average", Function, "FUNCTION Average(BYREF Marks : ARRAY[1:30] OF INTEGER, Count : INTEGER) RETURNS REAL", Position{7, 10}},
+		{"Pet", Class, "CLASS Pet", Position{15, 7}},
+	}
+	if len(root.Symbols) != len(tests) {
+		t.Fatalf("expected %d symbols, got %d", len(tests), len(root.Symbols))
+	}
+	for i, tt := range tests {
+		sym := root.Symbols[i]
+		if sym.Name != tt.name || sym.Kind != tt.kind || sym.Detail != tt.detail || sym.Pos != tt.pos {
+			t.Errorf("symbol %d: expected %s %s %q at %v, got %s %s %q at %v",
+				i, tt.kind, tt.name, tt.detail, tt.pos, sym.Kind, sym.Name, sym.Detail, sym.Pos)
+		}
+	}
+
+	spans := []struct {
+		sym        *Symbol
+		start, end Position
+	}{
+		{root.Symbols[0], Position{1, 1}, Position{1, 21}},
+		{root.Symbols[2], Position{3, 1}, Position{6, 8}},
+		{root.Symbols[3], Position{7, 1}, Position{14, 12}},
+	}
+	for _, span := range spans {
+		if span.sym.Start != span.start || span.sym.End != span.end {
+			t.Errorf("%s: expected %v to %v, got %v to %v", span.sym.Name, span.start, span.end, span.sym.Start, span.sym.End)
+		}
+	}
+
+	point := root.Symbols[2]
+	if len(point.Members) != 2 || point.Members[1].Name != "Y" || point.Members[1].Kind != Field || point.Members[1].Pos != (Position{5, 13}) {
+		t.Errorf("unexpected record fields: %+v", point.Members)
+	}
+	pet := root.Symbols[4]
+	if len(pet.Members) != 2 || pet.Members[0].Kind != Field || pet.Members[1].Name != "NEW" {
+		t.Errorf("unexpected class members: %+v", pet.Members)
+	}
+}
+
+func TestScopes(t *testing.T) {
+	root := build(t, program).Program
+
+	tests := []struct {
+		name    string
+		pos     Position
+		visible []string
+	}{
+		{"top level", Position{21, 1}, []string{"StudentMarks", "Pass", "Point", "Average", "Pet"}},
+		{"routine body", Position{9, 5}, []string{"Marks", "Count", "Total", "StudentMarks", "Pass", "Point", "Average", "Pet"}},
+		{"loop body", Position{11, 9}, []string{"I", "Marks", "Count", "Total", "StudentMarks", "Pass", "Point", "Average", "Pet"}},
+		{"method body", Position{18, 9}, []string{"N", "Name", "NEW", "StudentMarks", "Pass", "Point", "Average", "Pet"}},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			var got []string
+			for _, sym := range root.At(tt.pos).Visible() {
+				got = append(got, sym.Name)
+			}
+			if len(got) != len(tt.visible) {
+				t.Fatalf("expected %v, got %v", tt.visible, got)
+			}
+			for i := range got {
+				if got[i] != tt.visible[i] {
+					t.Fatalf("expected %v, got %v", tt.visible, got)
+				}
+			}
+		})
+	}
+}
+
+func TestLookupShadowing(t *testing.T) {
+	src := "DECLARE N : STRING\nPROCEDURE Show(N : INTEGER)\n    OUTPUT N\nENDPROCEDURE\n"
+	root := build(t, src).Program
+
+	if sym := root.At(Position{3, 12}).Lookup("N"); sym == nil || sym.Kind != Parameter || sym.Pos != (Position{2, 16}) {
+		t.Errorf("expected the parameter, got %+v", sym)
+	}
+	if sym := root.At(Position{4, 1}).Lookup("N"); sym == nil || sym.Kind != Parameter {
+		t.Errorf("expected ENDPROCEDURE to be inside the routine, got %+v", sym)
+	}
+	if sym := root.Lookup("N"); sym == nil || sym.Kind != Variable {
+		t.Errorf("expected the variable, got %+v", sym)
+	}
+	if sym := root.Lookup("Missing"); sym != nil {
+		t.Errorf("expected nil, got %+v", sym)
+	}
+}
+
+func TestTypeOf(t *testing.T) {
+	src := `TYPE Address
+    DECLARE Town : STRING
+ENDTYPE
+TYPE Student
+    DECLARE Name : STRING
+    DECLARE Home : Address
+ENDTYPE
+CLASS Person
+    PUBLIC DECLARE Age : INTEGER
+    PUBLIC FUNCTION Older() RETURNS Person
+        RETURN NEW Person()
+    ENDFUNCTION
+ENDCLASS
+CLASS Teacher INHERITS Person
+    PRIVATE DECLARE Salary : REAL
+ENDCLASS
+DECLARE Students : ARRAY[1:30] OF Student
+DECLARE Head : Teacher
+`
+	root := build(t, src).Program
+
+	tests := []struct {
+		expr     string
+		expected string
+	}{
+		{"Students", "ARRAY[1:30] OF Student"},
+		{"Students[1]", "Student"},
+		{"Students[1].Home", "Address"},
+		{"Students[1].Home.Town", "STRING"},
+		{"Head.Age", "INTEGER"},
+		{"Head.Older().Age", "INTEGER"},
+		{"NEW Person()", "Person"},
+		{"Students.Name", ""},
+		{"Missing", ""},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.expr, func(t *testing.T) {
+			p := parser.New(lexer.New(tt.expr))
+			prog := p.ParseProgram()
+			if len(prog.Statements) != 1 {
+				t.Fatalf("expected one statement, got %d: %v", len(prog.Statements), p.Errors())
+			}
+			stmt, ok := prog.Statements[0].(*ast.ExpressionStatement)
+			if !ok {
+				t.Fatalf("expected an expression, got %T", prog.Statements[0])
+			}
+			got := ""
+			if typ := root.TypeOf(stmt.Expression); typ != nil {
+				got = typ.String()
+			}
+			if got != tt.expected {
+				t.Errorf("expected %q, got %q", tt.expected, got)
+			}
+		})
+	}
+
+	var names []string
+	for _, m := range root.Members("Teacher") {
+		names = append(names, m.Name+" "+m.Access)
+	}
+	if strings.Join(names, ", ") != "Salary PRIVATE, Age PUBLIC, Older PUBLIC" {
+		t.Errorf("unexpected members of Teacher: %v", names)
+	}
+}
+
+func TestBuildWithErrors(t *testing.T) {
+	src := "DECLARE\nDECLARE Total : INTEGER\nCONSTANT X =\nFOR\nPROCEDURE Show(N : INTEGER)\n    OUTPUT Total.\n"
+	root := Build(parser.New(lexer.New(src)).ParseProgram(), src).Program
+	if sym := root.At(Position{6, 12}).Lookup("Total"); sym == nil || sym.Kind != Variable {
+		t.Errorf("expected Total to survive the errors around it, got %+v", sym)
+	}
+}
+
+func TestSymbolAt(t *testing.T) {
+	table := build(t, program)
+
+	tests := []struct {
+		name        string
+		pos         Position
+		declaration string
+	}{
+		{"declaration", Position{1, 12}, "DECLARE StudentMarks : ARRAY[1:30] OF INTEGER"},
+		{"use", Position{21, 20}, "DECLARE StudentMarks : ARRAY[1:30] OF INTEGER"},
+		{"call", Position{21, 8}, "FUNCTION Average(BYREF Marks : ARRAY[1:30] OF INTEGER, Count : INTEGER) RETURNS REAL"},
+		{"parameter", Position{11, 26}, "BYREF Marks : ARRAY[1:30] OF INTEGER"},
+		{"loop variable", Position{11, 32}, "DECLARE I : INTEGER"},
+		{"constant", Position{2, 11}, "CONSTANT Pass = 40"},
+		{"field", Position{18, 9}, "DECLARE Name : STRING"},
+		{"keyword", Position{21, 2}, ""},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			got := ""
+			if sym := table.SymbolAt(tt.pos); sym != nil {
+				got = sym.Declaration()
+			}
+			if got != tt.declaration {
+				t.Errorf("expected %q, got %q", tt.declaration, got)
+			}
+		})
+	}
+}
+
+func TestReferences(t *testing.T) {
+	src := `TYPE Point
+    DECLARE X : INTEGER
+ENDTYPE
+DECLARE P : Point
+DECLARE Path : ARRAY[1:2] OF Point
+P.X <- 1
+Path[1].X <- P.X
+DECLARE N : INTEGER
+INPUT N
+OUTPUT N
+`
+	table := build(t, src)
+
+	var got []string
+	for _, ref := range table.References {
+		use := fmt.Sprintf("%s %d:%d", ref.Symbol.Name, ref.Pos.Line, ref.Pos.Column)
+		if ref.Write {
+			use += " write"
+		}
+		got = append(got, use)
+	}
+	expected := "Point 4:13, Point 5:30, P 6:1, X 6:3 write, Path 7:1, X 7:9 write, P 7:14, X 7:16, N 9:7 write, N 10:8"
+	if strings.Join(got, ", ") != expected {
+		t.Errorf("expected %s\ngot      %s", expected, strings.Join(got, ", "))
+	}
+	n := table.Program.Lookup("N")
+	if uses := table.Uses(n); len(uses) != 2 || !uses[0].Write || uses[1].Write {
+		t.Errorf("unexpected uses of N: %+v", uses)
+	}
+}
+
+func TestConflict(t *testing.T) {
+	src := `TYPE Point
+    DECLARE X : INTEGER
+    DECLARE Y : INTEGER
+ENDTYPE
+DECLARE Total : INTEGER
+DECLARE Count : INTEGER
+PROCEDURE Add(N : INTEGER)
+    DECLARE Step : INTEGER
+    Total <- Total + N
+ENDPROCEDURE
+`
+	table := build(t, src)
+	point := table.Program.Lookup("Point")
+	add := table.Program.Lookup("Add")
+	step := table.At(Position{8, 13}).Lookup("Step")
+
+	tests := []struct {
+		name     string
+		sym      *Symbol
+		rename   string
+		conflict string
+	}{
+		{"free name", table.Program.Lookup("Total"), "Sum", ""},
+		{"same scope", table.Program.Lookup("Total"), "Count", "Count"},
+		{"field of the same record", point.Members[0], "Y", "Y"},
+		{"field of another name", point.Members[0], "Z", ""},
+		{"use would refer to a local", table.Program.Lookup("Total"), "Step", "Step"},
+		{"local would hide a global in use", step, "Total", "Total"},
+		{"local hiding a global", step, "Count", "Count"},
+		{"local with a free name", step, "Index", ""},
+		{"routine", add, "Count", "Count"},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			got := ""
+			if other := table.Conflict(tt.sym, tt.rename); other != nil {
+				got = other.Name
+			}
+			if got != tt.conflict {
+				t.Errorf("expected conflict %q, got %q", tt.conflict, got)
+			}
+		})
+	}
+}
+
+func TestEnumValues(t *testing.T) {
+	table := build(t, "TYPE Season = (Spring, Summer)\nDECLARE S : Season\nS <- Summer\n")
+
+	summer := table.Program.Lookup("Summer")
+	if summer == nil || summer.Kind != EnumValue || summer.Pos != (Position{1, 24}) || summer.Declaration() != "TYPE Season = (Spring, Summer)" {
+		t.Fatalf("unexpected symbol for Summer: %+v", summer)
+	}
+	if uses := table.Uses(summer); len(uses) != 1 || uses[0].Pos != (Position{3, 6}) {
+		t.Errorf("unexpected uses of Summer: %+v", uses)
+	}
+
+	var names []string
+	for _, sym := range table.Symbols() {
+		names = append(names, sym.Name)
+	}
+	if strings.Join(names, ", ") != "Season, Spring, Summer, S" {
+		t.Errorf("unexpected symbols: %v", names)
+	}
+}
+
+func TestParams(t *testing.T) {
+	table := build(t, "PROCEDURE Swap(BYREF A : INTEGER, BYREF B : INTEGER, Times : INTEGER)\nENDPROCEDURE\n")
+
+	swap := table.Program.Lookup("Swap")
+	if swap == nil || len(swap.Params) != 3 {
+		t.Fatalf("unexpected parameters of Swap: %+v", swap)
+	}
+	tests := []struct {
+		name   string
+		detail string
+		pos    Position
+	}{
+		{"A", "BYREF INTEGER", Position{1, 22}},
+		{"B", "BYREF INTEGER", Position{1, 41}},
+		{"Times", "INTEGER", Position{1, 54}},
+	}
+	for i, tt := range tests {
+		p := swap.Params[i]
+		if p.Name != tt.name || p.Kind != Parameter || p.Detail != tt.detail || p.Pos != tt.pos {
+			t.Errorf("parameter %d: got %s %s %q at %v, want %s %q at %v", i, p.Kind, p.Name, p.Detail, p.Pos, tt.name, tt.detail, tt.pos)
+		}
+	}
+}
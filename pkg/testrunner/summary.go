@@ -0,0 +1,192 @@
+package testrunner
+
+import (
+	"encoding/csv"
+	"fmt"
+	"html/template"
+	"io"
+	"sort"
+	"strings"
+	"time"
+
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+)
+
+// Summary is how a class did as a whole on each case, naming no one, so a
+// teacher can see which questions, and so which ideas, most of it failed
+type Summary struct {
+	Submissions int           `json:"submissions"`
+	Cases       []CaseSummary `json:"cases"`
+	Errors      []ErrorCount  `json:"errors"` // over every case, most common first
+}
+
+// CaseSummary is how the class did on one case
+type CaseSummary struct {
+	Case    string        `json:"case"`
+	Passed  int           `json:"passed"`
+	Total   int           `json:"total"`
+	Average time.Duration `json:"average"`          // time taken, over every submission
+	Errors  []ErrorCount  `json:"errors,omitempty"` // how the rest failed, most common first
+}
+
+// Rate is the percentage of submissions passing the case
+func (c CaseSummary) Rate() float64 {
+	if c.Total == 0 {
+		return 0
+	}
+	return 100 * float64(c.Passed) / float64(c.Total)
+}
+
+// ErrorCount is one way submissions failed and how many did
+type ErrorCount struct {
+	Code    diagnostic.Code `json:"code,omitempty"` // none if the failure was not a runtime error
+	Message string          `json:"message"`        // as the first to fail this way was told
+	Count   int             `json:"count"`
+}
+
+func (e ErrorCount) String() string {
+	if e.Code == "" {
+		return e.Message
+	}
+	return string(e.Code) + " " + e.Message
+}
+
+// Summarize totals reports, which Grade made from the same cases
+func Summarize(reports []Report) Summary {
+	s := Summary{Submissions: len(reports)}
+	if len(reports) == 0 {
+		return s
+	}
+
+	overall := tally{}
+	for c, first := range reports[0].Results {
+		summary := CaseSummary{Case: first.Case}
+		failures := tally{}
+		var taken time.Duration
+		for _, report := range reports {
+			if c >= len(report.Results) {
+				continue
+			}
+			r := report.Results[c]
+			summary.Total++
+			taken += r.Duration
+			if r.Passed {
+				summary.Passed++
+				continue
+			}
+			failures.add(failure(r))
+			overall.add(failure(r))
+		}
+		if summary.Total > 0 {
+			summary.Average = taken / time.Duration(summary.Total)
+		}
+		summary.Errors = failures.sorted()
+		s.Cases = append(s.Cases, summary)
+	}
+	s.Errors = overall.sorted()
+	return s
+}
+
+// failure says how r failed: by the code of its runtime error, or else as
+// not parsing, the interpreter failing or the output being wrong
+func failure(r Result) ErrorCount {
+	switch {
+	case r.Code != "":
+		// The message follows where the error happened, as in
+		// "ERROR at line 3, column 5: division by zero"
+		_, message, _ := strings.Cut(r.Error, ": ")
+		return ErrorCount{Code: r.Code, Message: message}
+	case strings.HasPrefix(r.Error, "parse error"):
+		return ErrorCount{Message: "the program does not parse"}
+	case r.Error != "":
+		return ErrorCount{Message: "the interpreter failed"}
+	}
+	return ErrorCount{Message: "wrong output"}
+}
+
+// tally counts failures of each kind in the order they were first seen
+type tally []ErrorCount
+
+func (t *tally) add(e ErrorCount) {
+	for n := range *t {
+		if (*t)[n].Code == e.Code && (e.Code != "" || (*t)[n].Message == e.Message) {
+			(*t)[n].Count++
+			return
+		}
+	}
+	e.Count = 1
+	*t = append(*t, e)
+}
+
+func (t tally) sorted() []ErrorCount {
+	sort.SliceStable(t, func(i, j int) bool { return t[i].Count > t[j].Count })
+	return t
+}
+
+// WriteCSV writes a row for each case: its pass rate, the time it took on
+// average and the most common way it was failed
+func (s Summary) WriteCSV(w io.Writer) error {
+	out := csv.NewWriter(w)
+	out.Write([]string{"case", "passed", "total", "pass rate (%)", "average time (ms)", "most common error", "failed that way"})
+	for _, c := range s.Cases {
+		common, count := "", ""
+		if len(c.Errors) > 0 {
+			common, count = c.Errors[0].String(), fmt.Sprint(c.Errors[0].Count)
+		}
+		out.Write([]string{
+			c.Case,
+			fmt.Sprint(c.Passed),
+			fmt.Sprint(c.Total),
+			fmt.Sprintf("%.1f", c.Rate()),
+			fmt.Sprintf("%.2f", milliseconds(c.Average)),
+			common,
+			count,
+		})
+	}
+	out.Flush()
+	return out.Error()
+}
+
+func milliseconds(d time.Duration) float64 {
+	return float64(d) / float64(time.Millisecond)
+}
+
+// summaryPage is a Summary as HTML: a table of the cases, then of the
+// ways they were failed
+var summaryPage = template.Must(template.New("summary").Funcs(template.FuncMap{
+	"percent": func(rate float64) string { return fmt.Sprintf("%.0f%%", rate) },
+	"ms":      func(d time.Duration) string { return fmt.Sprintf("%.2f ms", milliseconds(d)) },
+}).Parse(`<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Class summary</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #999; padding: 0.3em 0.8em; text-align: left; }
+.low { background: #fdd; }
+</style>
+</head>
+<body>
+<h1>Class summary</h1>
+<p>{{.Submissions}} submissions.</p>
+<h2>Cases</h2>
+<table>
+<tr><th>Case</th><th>Passed</th><th>Pass rate</th><th>Average time</th><th>Most common error</th></tr>
+{{range .Cases}}<tr{{if lt .Rate 50.0}} class="low"{{end}}><td>{{.Case}}</td><td>{{.Passed}}/{{.Total}}</td><td>{{percent .Rate}}</td><td>{{ms .Average}}</td><td>{{with .Errors}}{{index . 0}} ({{(index . 0).Count}}){{end}}</td></tr>
+{{end}}</table>
+{{with .Errors}}<h2>Errors</h2>
+<table>
+<tr><th>Error</th><th>Failed cases</th></tr>
+{{range .}}<tr><td>{{.}}</td><td>{{.Count}}</td></tr>
+{{end}}</table>
+{{end}}</body>
+</html>
+`))
+
+// WriteHTML writes s as a page, with the cases fewer than half the class
+// passed picked out
+func (s Summary) WriteHTML(w io.Writer) error {
+	return summaryPage.Execute(w, s)
+}
@@ -0,0 +1,306 @@
+// Package testrunner runs programs against recorded input and expected
+// output, for `cambridge test` and for batch grading of submissions
+package testrunner
+
+import (
+	"bytes"
+	"fmt"
+	"os"
+	"path/filepath"
+	"runtime"
+	"sort"
+	"strings"
+	"sync"
+	"time"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+// Defaults for the limits on a Program
+const (
+	DefaultSteps   = 10_000_000
+	DefaultTimeout = 10 * time.Second
+)
+
+// fuel is how many statements a case takes at a time between checks on
+// the clock
+const fuel = 10_000
+
+// Case is one test: the lines fed to INPUT and the OUTPUT expected back
+type Case struct {
+	Name     string
+	Input    string
+	Expected string
+
+	// Files are the only files OPENFILE can see, kept in memory so that
+	// the case cannot touch the disk; nil leaves it the disk
+	Files map[string]string
+}
+
+// LoadCases reads every NAME.in and NAME.out pair in dir, sorted by name
+// A NAME.out without a matching NAME.in runs with no input. The files in a
+// NAME.files folder, if there is one, are the case's virtual files.
+func LoadCases(dir string) ([]Case, error) {
+	outs, err := filepath.Glob(filepath.Join(dir, "*.out"))
+	if err != nil {
+		return nil, err
+	}
+	sort.Strings(outs)
+
+	var cases []Case
+	for _, out := range outs {
+		expected, err := os.ReadFile(out)
+		if err != nil {
+			return nil, err
+		}
+
+		base := strings.TrimSuffix(out, ".out")
+		input, err := os.ReadFile(base + ".in")
+		if err != nil && !os.IsNotExist(err) {
+			return nil, err
+		}
+
+		files, err := loadFiles(base + ".files")
+		if err != nil {
+			return nil, err
+		}
+
+		cases = append(cases, Case{
+			Name:     filepath.Base(base),
+			Input:    string(input),
+			Expected: string(expected),
+			Files:    files,
+		})
+	}
+	return cases, nil
+}
+
+// loadFiles reads the files directly inside dir, or returns nil if there
+// is no such folder
+func loadFiles(dir string) (map[string]string, error) {
+	entries, err := os.ReadDir(dir)
+	if os.IsNotExist(err) {
+		return nil, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+
+	files := make(map[string]string, len(entries))
+	for _, entry := range entries {
+		if !entry.Type().IsRegular() {
+			continue
+		}
+		contents, err := os.ReadFile(filepath.Join(dir, entry.Name()))
+		if err != nil {
+			return nil, err
+		}
+		files[entry.Name()] = string(contents)
+	}
+	return files, nil
+}
+
+// Program is a parsed program ready to be run against cases
+// The syntax tree is only read while running, so one Program can be run
+// by several workers at once.
+type Program struct {
+	Name        string
+	AST         *ast.Program
+	ParseErrors []string
+	Arithmetic  interpreter.Arithmetic // how numbers behave when it runs
+	Files       interpreter.FileSystem // where OPENFILE looks, the disk if nil
+	Disabled    []interpreter.Capability
+	Steps       int           // statements a case may take, DefaultSteps if 0
+	Timeout     time.Duration // how long a case may take, DefaultTimeout if 0
+
+	// Builtins returns the builtin functions for one case, the standard
+	// set if nil; it is called afresh for each case
+	Builtins func() map[string]*interpreter.Builtin
+}
+
+// Compile parses source under the given name
+func Compile(name, source string) *Program {
+	p := parser.New(lexer.New(source))
+	prog := Prepare(name, p.ParseProgram())
+	prog.ParseErrors = p.Errors()
+	return prog
+}
+
+// Prepare readies a program that has already been parsed without errors,
+// such as one read back from a cache
+func Prepare(name string, tree *ast.Program) *Program {
+	interpreter.Resolve(tree)
+	return &Program{Name: name, AST: tree}
+}
+
+// Result is the outcome of running one case
+type Result struct {
+	Case     string          `json:"case"`
+	Passed   bool            `json:"passed"`
+	Output   string          `json:"output,omitempty"`
+	Expected string          `json:"expected,omitempty"`
+	Error    string          `json:"error,omitempty"`
+	Code     diagnostic.Code `json:"code,omitempty"` // of the runtime error, if there was one
+	Duration time.Duration   `json:"duration"`
+}
+
+// RunCase runs prog on the input of c in a fresh interpreter
+// Output and expected output are compared ignoring trailing whitespace on
+// each line and trailing blank lines. A runtime error or panic fails only
+// this case, as does running past the program's Steps or Timeout, so one
+// that loops for ever cannot hold up the rest.
+func RunCase(prog *Program, c Case) (result Result) {
+	result = Result{Case: c.Name}
+	if len(prog.ParseErrors) > 0 {
+		result.Error = "parse error: " + prog.ParseErrors[0]
+		return result
+	}
+
+	start := time.Now()
+	var out bytes.Buffer
+	defer func() {
+		result.Duration = time.Since(start)
+		if r := recover(); r != nil {
+			result.Passed = false
+			result.Output = out.String()
+			result.Error = fmt.Sprintf("interpreter panic: %v", r)
+		}
+	}()
+
+	interp := interpreter.New()
+	if prog.Builtins != nil {
+		interp.SetBuiltins(prog.Builtins())
+	} else {
+		interp.SetBuiltins(builtins.GetBuiltins())
+	}
+	interp.SetInput(strings.NewReader(c.Input))
+	interp.SetOutput(&out)
+	interp.SetArithmetic(prog.Arithmetic)
+	if c.Files != nil {
+		interp.SetFiles(interpreter.NewMemoryFiles(c.Files))
+	} else if prog.Files != nil {
+		interp.SetFiles(prog.Files)
+	}
+	interp.Disable(prog.Disabled...)
+
+	steps, timeout := prog.Steps, prog.Timeout
+	if steps <= 0 {
+		steps = DefaultSteps
+	}
+	if timeout <= 0 {
+		timeout = DefaultTimeout
+	}
+	exec := interp.Start(prog.AST)
+	deadline := start.Add(timeout)
+	for left := steps; ; {
+		n := min(fuel, left)
+		if exec.Continue(n) {
+			break
+		}
+		left -= n
+		if left <= 0 {
+			exec.Stop()
+			result.Error = fmt.Sprintf("still running after %d steps", steps)
+			break
+		}
+		if time.Now().After(deadline) {
+			exec.Stop()
+			result.Error = fmt.Sprintf("still running after %s", timeout)
+			break
+		}
+	}
+	if err, ok := exec.Result().(*interpreter.Error); ok {
+		result.Error = err.Inspect()
+		result.Code = err.Code
+	}
+
+	result.Passed = result.Error == "" && normalize(out.String()) == normalize(c.Expected)
+	if !result.Passed {
+		result.Output = out.String()
+		result.Expected = c.Expected
+	}
+	return result
+}
+
+// Run runs every case against prog using up to workers goroutines
+// Results are in the same order as cases.
+func Run(prog *Program, cases []Case, workers int) []Result {
+	results := make([]Result, len(cases))
+	parallel(len(cases), workers, func(n int) {
+		results[n] = RunCase(prog, cases[n])
+	})
+	return results
+}
+
+// Report is the grade for one submission
+type Report struct {
+	Name    string   `json:"name"`
+	Passed  int      `json:"passed"`
+	Total   int      `json:"total"`
+	Results []Result `json:"results"`
+}
+
+// Grade runs every case against every program, sharing one pool of
+// workers across all submissions. Reports are in the same order as progs.
+func Grade(progs []*Program, cases []Case, workers int) []Report {
+	reports := make([]Report, len(progs))
+	for n, prog := range progs {
+		reports[n] = Report{Name: prog.Name, Total: len(cases), Results: make([]Result, len(cases))}
+	}
+
+	parallel(len(progs)*len(cases), workers, func(job int) {
+		s, c := job/len(cases), job%len(cases)
+		reports[s].Results[c] = RunCase(progs[s], cases[c])
+	})
+
+	for n := range reports {
+		for _, r := range reports[n].Results {
+			if r.Passed {
+				reports[n].Passed++
+			}
+		}
+	}
+	return reports
+}
+
+// parallel calls fn for every index below n on up to workers goroutines
+// A worker count below one uses one worker per CPU.
+func parallel(n, workers int, fn func(int)) {
+	if workers < 1 {
+		workers = runtime.NumCPU()
+	}
+	workers = min(workers, n)
+
+	jobs := make(chan int)
+	var wg sync.WaitGroup
+	for w := 0; w < workers; w++ {
+		wg.Add(1)
+		go func() {
+			defer wg.Done()
+			for job := range jobs {
+				fn(job)
+			}
+		}()
+	}
+
+	for job := 0; job < n; job++ {
+		jobs <- job
+	}
+	close(jobs)
+	wg.Wait()
+}
+
+// normalize strips trailing whitespace from each line and trailing blank
+// lines, and turns CRLF line endings into LF
+func normalize(s string) string {
+	lines := strings.Split(strings.ReplaceAll(s, "\r\n", "\n"), "\n")
+	for n, line := range lines {
+		lines[n] = strings.TrimRight(line, " \t")
+	}
+	return strings.TrimRight(strings.Join(lines, "\n"), "\n")
+}
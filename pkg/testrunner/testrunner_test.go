@@ -0,0 +1,234 @@
+package testrunner
+
+import (
+	"bytes"
+	"fmt"
+	"math"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+	"time"
+
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+)
+
+const doubler = `DECLARE n : STRING
+INPUT n
+OUTPUT STR_TO_NUM(n) * 2`
+
+func TestLoadCases(t *testing.T) {
+	dir := t.TempDir()
+	files := map[string]string{
+		"b.in":  "2\n",
+		"b.out": "4\n",
+		"a.in":  "1\n",
+		"a.out": "2\n",
+		"c.out": "no input\n",
+
+		"b.files/scores.txt": "10\n",
+	}
+	if err := os.Mkdir(filepath.Join(dir, "b.files"), 0755); err != nil {
+		t.Fatal(err)
+	}
+	for name, content := range files {
+		if err := os.WriteFile(filepath.Join(dir, name), []byte(content), 0644); err != nil {
+			t.Fatal(err)
+		}
+	}
+
+	cases, err := LoadCases(dir)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if len(cases) != 3 || cases[0].Name != "a" || cases[1].Name != "b" || cases[2].Name != "c" {
+		t.Fatalf("unexpected cases: %+v", cases)
+	}
+	if cases[1].Input != "2\n" || cases[1].Expected != "4\n" || cases[2].Input != "" {
+		t.Errorf("unexpected case contents: %+v", cases)
+	}
+	if cases[0].Files != nil || cases[1].Files["scores.txt"] != "10\n" {
+		t.Errorf("unexpected case files: %+v", cases)
+	}
+}
+
+func TestRunCaseFiles(t *testing.T) {
+	prog := Compile("summer", `DECLARE Line : STRING
+OPENFILE "scores.txt" FOR READ
+READFILE "scores.txt", Line
+CLOSEFILE "scores.txt"
+OPENFILE "out.txt" FOR WRITE
+WRITEFILE "out.txt", Line
+CLOSEFILE "out.txt"
+OUTPUT STR_TO_NUM(Line) + 1`)
+
+	files := map[string]string{"scores.txt": "41\n"}
+	for n := 0; n < 2; n++ {
+		result := RunCase(prog, Case{Name: "virtual", Expected: "42\n", Files: files})
+		if !result.Passed {
+			t.Errorf("run %d: expected the case to pass, got %+v", n+1, result)
+		}
+	}
+	if _, err := os.Stat("out.txt"); err == nil {
+		os.Remove("out.txt")
+		t.Error("expected out.txt to be written in memory, not on disk")
+	}
+	if len(files) != 1 {
+		t.Errorf("expected the case's files to be left as they were, got %v", files)
+	}
+}
+
+func TestRunCase(t *testing.T) {
+	prog := Compile("doubler", doubler)
+
+	tests := []struct {
+		c      Case
+		passed bool
+	}{
+		{Case{Name: "pass", Input: "21\n", Expected: "42\n"}, true},
+		{Case{Name: "trailing space", Input: "21\n", Expected: "42  \n\n"}, true},
+		{Case{Name: "fail", Input: "21\n", Expected: "43\n"}, false},
+	}
+
+	for _, tt := range tests {
+		result := RunCase(prog, tt.c)
+		if result.Passed != tt.passed {
+			t.Errorf("%s: expected passed=%t, got %+v", tt.c.Name, tt.passed, result)
+		}
+	}
+}
+
+func TestRunCaseErrors(t *testing.T) {
+	result := RunCase(Compile("broken", "DECLARE : INTEGER"), Case{Name: "syntax"})
+	if result.Passed || result.Error == "" {
+		t.Errorf("expected a parse error, got %+v", result)
+	}
+
+	result = RunCase(Compile("crash", "OUTPUT 1 DIV 0"), Case{Name: "runtime"})
+	if result.Passed || result.Error == "" {
+		t.Errorf("expected a runtime error, got %+v", result)
+	}
+
+	forever := Compile("forever", "OUTPUT \"start\"\nWHILE TRUE\nENDWHILE")
+	forever.Steps = 1000
+	result = RunCase(forever, Case{Name: "steps", Expected: "start\n"})
+	if result.Passed || result.Error != "still running after 1000 steps" || result.Output != "start\n" {
+		t.Errorf("expected the loop stopped after 1000 steps, got %+v", result)
+	}
+
+	forever.Steps, forever.Timeout = math.MaxInt, 50*time.Millisecond
+	result = RunCase(forever, Case{Name: "timeout", Expected: "start\n"})
+	if result.Passed || result.Error != "still running after 50ms" {
+		t.Errorf("expected the loop stopped after 50ms, got %+v", result)
+	}
+}
+
+func TestRunCaseBuiltins(t *testing.T) {
+	prog := Compile("dice", "OUTPUT RAND(100), TODAY()")
+	prog.Builtins = func() map[string]*interpreter.Builtin { return builtins.Deterministic(3) }
+
+	first := RunCase(prog, Case{Name: "first"})
+	again := RunCase(prog, Case{Name: "again", Expected: first.Output})
+	if first.Error != "" || !again.Passed {
+		t.Errorf("expected a seeded program to repeat its output, got %+v and %+v", first, again)
+	}
+}
+
+func TestGradeKeepsOrder(t *testing.T) {
+	var progs []*Program
+	for n := 0; n < 20; n++ {
+		source := doubler
+		if n%2 == 1 {
+			source = "DECLARE n : STRING\nINPUT n\nOUTPUT n"
+		}
+		progs = append(progs, Compile(fmt.Sprintf("student%02d", n), source))
+	}
+
+	var cases []Case
+	for n := 1; n <= 5; n++ {
+		cases = append(cases, Case{
+			Name:     fmt.Sprintf("case%d", n),
+			Input:    fmt.Sprintf("%d\n", n),
+			Expected: fmt.Sprintf("%d\n", n*2),
+		})
+	}
+
+	reports := Grade(progs, cases, 8)
+
+	for n, report := range reports {
+		if report.Name != progs[n].Name {
+			t.Fatalf("report %d is for %s, expected %s", n, report.Name, progs[n].Name)
+		}
+		for c, result := range report.Results {
+			if result.Case != cases[c].Name {
+				t.Errorf("%s: result %d is for %s", report.Name, c, result.Case)
+			}
+		}
+
+		// Odd submissions echo their input, which only matches for no case
+		expected := 5
+		if n%2 == 1 {
+			expected = 0
+		}
+		if report.Passed != expected || report.Total != 5 {
+			t.Errorf("%s: expected %d/5, got %d/%d", report.Name, expected, report.Passed, report.Total)
+		}
+	}
+}
+
+func TestSummarize(t *testing.T) {
+	var progs []*Program
+	for n, source := range []string{
+		doubler,
+		"DECLARE n : STRING\nINPUT n\nOUTPUT n",
+		"OUTPUT 1 DIV 0",
+		"OUTPUT 2 DIV 0",
+		"DECLARE : INTEGER",
+	} {
+		progs = append(progs, Compile(fmt.Sprintf("student%d", n), source))
+	}
+	cases := []Case{
+		{Name: "one", Input: "1\n", Expected: "2\n"},
+		{Name: "two", Input: "2\n", Expected: "4\n"},
+	}
+
+	summary := Summarize(Grade(progs, cases, 2))
+	if summary.Submissions != 5 || len(summary.Cases) != 2 {
+		t.Fatalf("unexpected summary: %+v", summary)
+	}
+	describe := func(errors []ErrorCount) string {
+		var parts []string
+		for _, e := range errors {
+			parts = append(parts, fmt.Sprintf("%s:%d", e, e.Count))
+		}
+		return strings.Join(parts, ", ")
+	}
+	for _, c := range summary.Cases {
+		if c.Passed != 1 || c.Total != 5 || c.Rate() != 20 {
+			t.Errorf("%s: expected 1/5 passed, got %d/%d", c.Case, c.Passed, c.Total)
+		}
+		expected := "E0218 division by zero:2, wrong output:1, the program does not parse:1"
+		if got := describe(c.Errors); got != expected {
+			t.Errorf("%s: expected errors %s, got %s", c.Case, expected, got)
+		}
+	}
+	expected := "E0218 division by zero:4, wrong output:2, the program does not parse:2"
+	if got := describe(summary.Errors); got != expected {
+		t.Errorf("expected errors %s, got %s", expected, got)
+	}
+
+	var csv, page bytes.Buffer
+	if err := summary.WriteCSV(&csv); err != nil {
+		t.Fatal(err)
+	}
+	if !strings.Contains(csv.String(), "one,1,5,20.0,") || !strings.Contains(csv.String(), ",E0218 division by zero,2\n") {
+		t.Errorf("unexpected CSV:\n%s", csv.String())
+	}
+	if err := summary.WriteHTML(&page); err != nil {
+		t.Fatal(err)
+	}
+	if strings.Contains(page.String(), "student") {
+		t.Errorf("expected the summary not to name submissions:\n%s", page.String())
+	}
+}
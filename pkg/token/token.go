@@ -2,6 +2,8 @@
 // Based on Cambridge International AS & A Level Computer Science 9618 specification
 package token
 
+import "unique"
+
 // Type represents the type of a token
 type Type string
 
@@ -110,6 +112,9 @@ const (
 	WRITE     Type = "WRITE"
 	APPEND    Type = "APPEND"
 
+	// Programs in several files
+	INCLUDE Type = "INCLUDE"
+
 	// OOP Keywords
 	CLASS    Type = "CLASS"
 	ENDCLASS Type = "ENDCLASS"
@@ -214,6 +219,9 @@ var Keywords = map[string]Type{
 	"WRITE":     WRITE,
 	"APPEND":    APPEND,
 
+	// Several files
+	"INCLUDE": INCLUDE,
+
 	// OOP
 	"CLASS":    CLASS,
 	"ENDCLASS": ENDCLASS,
@@ -231,3 +239,11 @@ func LookupIdent(ident string) Type {
 	}
 	return IDENT
 }
+
+// Intern returns the canonical copy of s
+// Every interned copy of the same text shares one allocation, so a name
+// repeated throughout a program is stored once, no longer keeps the whole
+// source alive, and compares equal to itself without reading its bytes.
+func Intern(s string) string {
+	return unique.Make(s).Value()
+}
@@ -0,0 +1,178 @@
+// Package trace records what a run did, step by step, to a file that can be
+// replayed later without running the program again
+// A trace is gzipped JSON lines: a header holding the program's source,
+// then one event per step with the line that ran, the variables it changed
+// and the input and output on the way.
+package trace
+
+import (
+	"bufio"
+	"compress/gzip"
+	"encoding/json"
+	"fmt"
+	"io"
+
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+)
+
+// Version is the trace format Writer produces and Read accepts
+const Version = 1
+
+// Header identifies the run a trace was recorded from
+type Header struct {
+	Version int    `json:"version"`
+	Program string `json:"program"`
+	Source  string `json:"source"`
+}
+
+// Event is one step of a run
+type Event struct {
+	Line    int               `json:"line"`
+	Column  int               `json:"column,omitempty"`
+	Changed map[string]string `json:"changed,omitempty"` // as OUTPUT would show each value
+	Calls   []string          `json:"calls,omitempty"`   // e.g. "FUNCTION Factorial", outermost first
+	Input   []string          `json:"input,omitempty"`
+	Output  string            `json:"output,omitempty"`
+	Error   string            `json:"error,omitempty"`
+}
+
+// Trace is a recorded run
+type Trace struct {
+	Header
+	Events []Event
+}
+
+// Writer records the steps of a run
+type Writer struct {
+	gz  *gzip.Writer
+	enc *json.Encoder
+}
+
+// NewWriter starts a trace of program, whose source is kept in the trace
+// so that it can be shown on replay
+func NewWriter(w io.Writer, program, source string) (*Writer, error) {
+	gz := gzip.NewWriter(w)
+	enc := json.NewEncoder(gz)
+	if err := enc.Encode(Header{Version: Version, Program: program, Source: source}); err != nil {
+		return nil, err
+	}
+	return &Writer{gz: gz, enc: enc}, nil
+}
+
+// Record adds a step
+func (w *Writer) Record(step interpreter.Step) error {
+	event := Event{Line: step.Line, Column: step.Column, Input: step.Input, Output: step.Output}
+	for _, v := range step.Changed {
+		if event.Changed == nil {
+			event.Changed = make(map[string]string, len(step.Changed))
+		}
+		event.Changed[v.Name] = v.Value.Inspect()
+	}
+	for _, c := range step.Calls {
+		event.Calls = append(event.Calls, c.Kind+" "+c.Name)
+	}
+	if step.Err != nil {
+		event.Error = step.Err.Message
+	}
+	return w.enc.Encode(event)
+}
+
+// Close finishes the trace; it does not close the underlying writer
+func (w *Writer) Close() error {
+	return w.gz.Close()
+}
+
+// Read reads a whole trace
+func Read(r io.Reader) (*Trace, error) {
+	gz, err := gzip.NewReader(bufio.NewReader(r))
+	if err != nil {
+		return nil, fmt.Errorf("not a trace file: %w", err)
+	}
+	defer gz.Close()
+
+	dec := json.NewDecoder(gz)
+	var t Trace
+	if err := dec.Decode(&t.Header); err != nil {
+		return nil, fmt.Errorf("not a trace file: %w", err)
+	}
+	if t.Version != Version {
+		return nil, fmt.Errorf("trace format %d is not supported (expected %d)", t.Version, Version)
+	}
+	for {
+		var event Event
+		if err := dec.Decode(&event); err == io.EOF {
+			return &t, nil
+		} else if err != nil {
+			return nil, fmt.Errorf("damaged trace: %w", err)
+		}
+		t.Events = append(t.Events, event)
+	}
+}
+
+// Cursor is a position in a trace that moves backwards as well as forwards,
+// so a run can be followed back from a wrong value to where it was set
+type Cursor struct {
+	trace *Trace
+	pos   int // the current event, -1 before the first
+}
+
+// Cursor returns a cursor before the first event of t
+func (t *Trace) Cursor() *Cursor {
+	return &Cursor{trace: t, pos: -1}
+}
+
+// Position returns the index of the current event, -1 before the first
+func (c *Cursor) Position() int {
+	return c.pos
+}
+
+// Event returns the current event
+func (c *Cursor) Event() (Event, bool) {
+	if c.pos < 0 || c.pos >= len(c.trace.Events) {
+		return Event{}, false
+	}
+	return c.trace.Events[c.pos], true
+}
+
+// Next moves to the following event, reporting false at the end
+func (c *Cursor) Next() bool {
+	if c.pos+1 >= len(c.trace.Events) {
+		return false
+	}
+	c.pos++
+	return true
+}
+
+// Back moves to the previous event, reporting false at the first
+func (c *Cursor) Back() bool {
+	if c.pos <= 0 {
+		return false
+	}
+	c.pos--
+	return true
+}
+
+// Values returns every variable recorded up to the current event, with
+// the value it was last seen holding
+// Variables whose routine or loop has finished keep their last value.
+func (c *Cursor) Values() map[string]string {
+	values := map[string]string{}
+	for _, event := range c.trace.Events[:c.pos+1] {
+		for name, value := range event.Changed {
+			values[name] = value
+		}
+	}
+	return values
+}
+
+// LastChange moves back to the latest event, up to the current one, that
+// changed name, reporting false and staying put if there is none
+func (c *Cursor) LastChange(name string) bool {
+	for pos := c.pos; pos >= 0; pos-- {
+		if _, ok := c.trace.Events[pos].Changed[name]; ok {
+			c.pos = pos
+			return true
+		}
+	}
+	return false
+}
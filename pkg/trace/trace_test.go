@@ -0,0 +1,103 @@
+package trace
+
+import (
+	"bytes"
+	"io"
+	"reflect"
+	"strings"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+const program = `DECLARE Name : STRING
+FUNCTION Half(n : INTEGER) RETURNS INTEGER
+    RETURN n DIV 2
+ENDFUNCTION
+INPUT Name
+OUTPUT "Hi ", Name
+OUTPUT Half(4) DIV 0`
+
+func TestRecordAndRead(t *testing.T) {
+	p := parser.New(lexer.New(program))
+	tree := p.ParseProgram()
+	interpreter.Resolve(tree)
+	interp := interpreter.New()
+	interp.SetInput(strings.NewReader("Ada\n"))
+	interp.SetOutput(io.Discard)
+
+	var file bytes.Buffer
+	w, err := NewWriter(&file, "main.cam", program)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	for step := range interp.Steps(tree) {
+		if err := w.Record(step); err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+	}
+	if err := w.Close(); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	got, err := Read(&file)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if got.Program != "main.cam" || got.Source != program {
+		t.Errorf("unexpected header %+v", got.Header)
+	}
+	expected := []Event{
+		{Line: 1, Column: 1, Changed: map[string]string{"Name": ""}},
+		{Line: 2, Column: 1},
+		{Line: 5, Column: 1, Changed: map[string]string{"Name": "Ada"}, Input: []string{"Ada"}},
+		{Line: 6, Column: 1, Output: "Hi Ada\n"},
+		{Line: 3, Column: 5, Changed: map[string]string{"n": "4"}, Calls: []string{"FUNCTION Half"}},
+		{Line: 7, Column: 1, Error: "division by zero"},
+	}
+	if !reflect.DeepEqual(got.Events, expected) {
+		t.Errorf("expected events\n%+v\ngot\n%+v", expected, got.Events)
+	}
+}
+
+func TestReadRejects(t *testing.T) {
+	if _, err := Read(strings.NewReader("not gzip")); err == nil {
+		t.Error("expected an error for a file that is not a trace")
+	}
+}
+
+func TestCursor(t *testing.T) {
+	tr := &Trace{Events: []Event{
+		{Line: 1, Changed: map[string]string{"x": "0"}},
+		{Line: 2, Changed: map[string]string{"x": "5", "y": "1"}},
+		{Line: 3, Changed: map[string]string{"y": "2"}},
+		{Line: 4},
+	}}
+	c := tr.Cursor()
+	if c.Back() {
+		t.Error("expected nothing before the first event")
+	}
+	for c.Next() {
+	}
+	if event, _ := c.Event(); event.Line != 4 {
+		t.Fatalf("expected to end on line 4, got %d", event.Line)
+	}
+	if got := c.Values(); !reflect.DeepEqual(got, map[string]string{"x": "5", "y": "2"}) {
+		t.Errorf("unexpected values %v", got)
+	}
+
+	if !c.LastChange("x") || c.Position() != 1 {
+		t.Errorf("expected x to have last changed at event 1, got %d", c.Position())
+	}
+	if got := c.Values(); !reflect.DeepEqual(got, map[string]string{"x": "5", "y": "1"}) {
+		t.Errorf("unexpected values going back %v", got)
+	}
+	if !c.Back() || c.Values()["x"] != "0" {
+		t.Errorf("expected x = 0 one step back, got %v", c.Values())
+	}
+	if c.LastChange("z") || c.Position() != 0 {
+		t.Error("expected no change to z and the cursor to stay put")
+	}
+}
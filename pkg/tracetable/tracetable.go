@@ -0,0 +1,505 @@
+// Package tracetable makes trace table exercises, for `cambridge
+// tracetable`: it gives a short program new values for its constants and
+// input, runs it, and lays out what each variable held as a trace table,
+// once blank as a worksheet and once filled in as the answers
+package tracetable
+
+import (
+	"fmt"
+	"html/template"
+	"io"
+	"math/rand"
+	"sort"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/fuzz"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+// OutputColumn heads the column of what the program writes, which comes
+// after those of its variables
+const OutputColumn = "OUTPUT"
+
+// maxElements is the largest array given a column for each element; the
+// elements of a larger one are left out
+const maxElements = 100
+
+// Options say how a variant of a program is made
+type Options struct {
+	Vary   map[string]fuzz.Range // new values for constants and variables, by name
+	Inputs map[string]fuzz.Range // the values read into each variable, by name
+	Seed   int64                 // the same seed makes the same variant
+	Steps  int                   // statements the program may take, 0 for no limit
+}
+
+// Table is a trace table of one run of a program
+type Table struct {
+	Source  string   // the program as varied
+	Input   []string // the lines INPUT read, in order
+	Columns []string
+	Rows    [][]string // a cell for each column, empty where nothing changed
+}
+
+// Vary returns source with each constant or variable named in ranges given
+// a value drawn from its range
+// A constant's value is replaced where it is defined, and a variable's where
+// it is first assigned a literal; naming anything else is an error.
+func Vary(source string, ranges map[string]fuzz.Range, rng *rand.Rand) (string, error) {
+	if len(ranges) == 0 {
+		return source, nil
+	}
+	program, err := parse(source)
+	if err != nil {
+		return "", err
+	}
+
+	names := make([]string, 0, len(ranges))
+	for name := range ranges {
+		names = append(names, name)
+	}
+	sort.Strings(names) // so the same seed draws the same values
+
+	type replacement struct {
+		start, end int
+		text       string
+	}
+	var replacements []replacement
+	starts := lineStarts(source)
+	for _, name := range names {
+		value := literalFor(program, name)
+		start, end, ok := span(source, starts, value)
+		if !ok {
+			return "", fmt.Errorf("cannot vary %s, as it is not a constant or given a literal value", name)
+		}
+		replacements = append(replacements, replacement{start, end, literal(ranges[name], value, rng)})
+	}
+
+	sort.Slice(replacements, func(i, j int) bool { return replacements[i].start > replacements[j].start })
+	for _, r := range replacements {
+		source = source[:r.start] + r.text + source[r.end:]
+	}
+	return source, nil
+}
+
+// literalFor returns the literal value of the constant name, or else the
+// first literal assigned to the variable name, or nil if there is neither
+func literalFor(program *ast.Program, name string) ast.Expression {
+	var constant, assigned ast.Expression
+	ast.Inspect(program, func(node ast.Node) bool {
+		switch n := node.(type) {
+		case *ast.ConstantStatement:
+			if !ast.IsNil(n.Name) && n.Name.Value == name && constant == nil && isLiteral(n.Value) {
+				constant = n.Value
+			}
+		case *ast.AssignmentStatement:
+			if id, ok := n.Name.(*ast.Identifier); ok && id.Value == name && assigned == nil && isLiteral(n.Value) {
+				assigned = n.Value
+			}
+		}
+		return true
+	})
+	if constant != nil {
+		return constant
+	}
+	return assigned
+}
+
+func isLiteral(expr ast.Expression) bool {
+	switch e := expr.(type) {
+	case *ast.IntegerLiteral, *ast.RealLiteral, *ast.StringLiteral, *ast.CharLiteral, *ast.BooleanLiteral:
+		return true
+	case *ast.PrefixExpression:
+		return e.Operator == "-" && isLiteral(e.Right)
+	}
+	return false
+}
+
+// lineStarts returns the offset in source of the start of each line
+func lineStarts(source string) []int {
+	starts := []int{0}
+	for n := 0; n < len(source); n++ {
+		if source[n] == '\n' {
+			starts = append(starts, n+1)
+		}
+	}
+	return starts
+}
+
+// span returns where the literal expr is written in source, as the offsets
+// of its first byte and the byte after it
+func span(source string, starts []int, expr ast.Expression) (start, end int, ok bool) {
+	offset := func(line, column int) int {
+		if line < 1 || line > len(starts) {
+			return -1
+		}
+		return starts[line-1] + column - 1
+	}
+	switch e := expr.(type) {
+	case *ast.IntegerLiteral:
+		start = offset(e.Token.Line, e.Token.Column)
+		end = start + len(e.Token.Literal)
+	case *ast.RealLiteral:
+		start = offset(e.Token.Line, e.Token.Column)
+		end = start + len(e.Token.Literal)
+	case *ast.BooleanLiteral:
+		start = offset(e.Token.Line, e.Token.Column)
+		end = start + len(e.Token.Literal)
+	case *ast.StringLiteral:
+		// The lexer places a string or character at the byte after it
+		end = offset(e.Token.Line, e.Token.Column)
+		start = end - len(e.Value) - 2
+	case *ast.CharLiteral:
+		end = offset(e.Token.Line, e.Token.Column)
+		start = end - len(e.Value) - 2
+	case *ast.PrefixExpression:
+		if _, end, ok = span(source, starts, e.Right); !ok {
+			return 0, 0, false
+		}
+		start = offset(e.Token.Line, e.Token.Column)
+	default:
+		return 0, 0, false
+	}
+	if start < 0 || end > len(source) || start >= end {
+		return 0, 0, false
+	}
+	return start, end, true
+}
+
+// literal returns a value drawn from r, written as a literal of the kind it
+// replaces
+func literal(r fuzz.Range, old ast.Expression, rng *rand.Rand) string {
+	value := r.Generate(rng)
+	for {
+		prefix, ok := old.(*ast.PrefixExpression)
+		if !ok {
+			break
+		}
+		old = prefix.Right
+	}
+	switch old.(type) {
+	case *ast.StringLiteral:
+		return `"` + value + `"`
+	case *ast.CharLiteral:
+		return "'" + value + "'"
+	case *ast.RealLiteral:
+		if r.Kind == fuzz.Real && !strings.Contains(value, ".") {
+			return value + ".0" // still a REAL
+		}
+	}
+	return value
+}
+
+// Generate makes a variant of source as opts say and traces a run of it
+// The run has RAND and RANDOM seeded with opts.Seed, so the same options
+// always give the same table. It fails if the program does not parse,
+// stops with an error or is still running after opts.Steps statements.
+func Generate(source string, opts Options) (*Table, error) {
+	varied, err := Vary(source, opts.Vary, rand.New(rand.NewSource(opts.Seed)))
+	if err != nil {
+		return nil, err
+	}
+	program, err := parse(varied)
+	if err != nil {
+		return nil, err
+	}
+	interpreter.Resolve(program)
+	ranges, err := fuzz.InputRanges(program, opts.Inputs)
+	if err != nil {
+		return nil, err
+	}
+
+	feeder := fuzz.NewFeeder(ranges, opts.Seed)
+	feeder.Limit = opts.Steps
+	interp := interpreter.New()
+	interp.SetBuiltins(builtins.Deterministic(opts.Seed))
+	interp.SetInput(feeder)
+	interp.SetOutput(io.Discard)
+	interp.SetDebugger(feeder)
+
+	r := newRecorder(program)
+	for step := range interp.Steps(program) {
+		if step.Err != nil {
+			if feeder.Expired() {
+				break
+			}
+			return nil, fmt.Errorf("line %d: %s", step.Err.Line, step.Err.Message)
+		}
+		r.record(step)
+	}
+	if feeder.Expired() {
+		return nil, fmt.Errorf("the program was still running after %d steps", opts.Steps)
+	}
+
+	table := r.table()
+	table.Source = varied
+	table.Input = feeder.Lines()
+	return table, nil
+}
+
+func parse(source string) (*ast.Program, error) {
+	p := parser.New(lexer.New(source))
+	program := p.ParseProgram()
+	if errs := p.Errors(); len(errs) > 0 {
+		return nil, fmt.Errorf("cannot parse the program: %s", errs[0])
+	}
+	return program, nil
+}
+
+// recorder lays out the steps of a run as the rows of a trace table,
+// starting a new row when a cell of the current one would be written twice
+type recorder struct {
+	constants map[string]bool
+	loops     map[string]map[ast.Statement]bool // the statements inside each FOR, by its variable
+	arrays    map[string]*interpreter.Array
+	latest    map[string]string // what each column's variable holds now
+	written   map[string]string // what was last written in each column
+	columns   []string
+	rows      []map[string]string
+	output    bool
+}
+
+func newRecorder(program *ast.Program) *recorder {
+	r := &recorder{
+		constants: map[string]bool{},
+		loops:     map[string]map[ast.Statement]bool{},
+		arrays:    map[string]*interpreter.Array{},
+		latest:    map[string]string{},
+		written:   map[string]string{},
+	}
+	ast.Inspect(program, func(node ast.Node) bool {
+		switch n := node.(type) {
+		case *ast.ConstantStatement:
+			if !ast.IsNil(n.Name) {
+				r.constants[n.Name.Value] = true
+			}
+		case *ast.ForStatement:
+			if ast.IsNil(n.Variable) {
+				break
+			}
+			inside := r.loops[n.Variable.Value]
+			if inside == nil {
+				inside = map[ast.Statement]bool{}
+				r.loops[n.Variable.Value] = inside
+			}
+			for _, stmt := range n.Body {
+				ast.Inspect(stmt, func(node ast.Node) bool {
+					if s, ok := node.(ast.Statement); ok {
+						inside[s] = true
+					}
+					return true
+				})
+			}
+		}
+		return true
+	})
+	return r
+}
+
+// record adds what changed at step
+// What a DECLARE sets a variable to is not written, only remembered, and a
+// FOR variable is only written inside its loop, as outside it the name is
+// the variable the loop hid.
+func (r *recorder) record(step interpreter.Step) {
+	for _, v := range step.Changed {
+		if r.constants[v.Name] {
+			continue
+		}
+		if array, ok := v.Value.(*interpreter.Array); ok {
+			r.arrays[v.Name] = array
+			continue
+		}
+		r.latest[v.Name] = v.Value.Inspect()
+	}
+	for _, name := range sortedKeys(r.arrays) {
+		elements(name, r.arrays[name], func(column, value string) {
+			r.latest[column] = value
+		})
+	}
+
+	if d, ok := step.Statement.(*ast.DeclareStatement); ok && !ast.IsNil(d.Name) {
+		for column, value := range r.latest {
+			if column == d.Name.Value || strings.HasPrefix(column, d.Name.Value+"[") {
+				r.written[column] = value
+			}
+		}
+	}
+
+	done := map[string]bool{}
+	for _, column := range r.order() {
+		if r.latest[column] == r.written[column] {
+			continue
+		}
+		if inside, loop := r.loops[column]; loop && !inside[step.Statement] && target(step.Statement) != column {
+			continue
+		}
+		r.write(column, r.latest[column])
+		done[column] = true
+	}
+	// Assigning a variable the value it already holds is still a step of the
+	// trace, such as setting a total to 0 that was declared as 0
+	if name := target(step.Statement); name != "" && !done[name] && !r.constants[name] {
+		if value, ok := r.latest[name]; ok {
+			r.write(name, value)
+		}
+	}
+
+	if step.Output != "" {
+		r.output = true
+		for _, line := range strings.Split(strings.TrimSuffix(step.Output, "\n"), "\n") {
+			r.write(OutputColumn, line)
+		}
+	}
+}
+
+// order returns the columns already in the table followed by any new ones
+// in name order, which is the order they will be added in
+func (r *recorder) order() []string {
+	seen := map[string]bool{}
+	order := make([]string, 0, len(r.latest))
+	for _, column := range r.columns {
+		seen[column] = true
+		order = append(order, column)
+	}
+	var added []string
+	for column := range r.latest {
+		if !seen[column] {
+			added = append(added, column)
+		}
+	}
+	sort.Strings(added)
+	return append(order, added...)
+}
+
+func (r *recorder) write(column, value string) {
+	r.written[column] = value
+	if column != OutputColumn && !contains(r.columns, column) {
+		r.columns = append(r.columns, column)
+	}
+	if len(r.rows) == 0 {
+		r.rows = append(r.rows, map[string]string{})
+	}
+	if _, filled := r.rows[len(r.rows)-1][column]; filled {
+		r.rows = append(r.rows, map[string]string{})
+	}
+	r.rows[len(r.rows)-1][column] = value
+}
+
+// table returns the rows recorded so far
+func (r *recorder) table() *Table {
+	t := &Table{Columns: append([]string(nil), r.columns...)}
+	if r.output {
+		t.Columns = append(t.Columns, OutputColumn)
+	}
+	for _, row := range r.rows {
+		cells := make([]string, len(t.Columns))
+		for n, column := range t.Columns {
+			cells[n] = row[column]
+		}
+		t.Rows = append(t.Rows, cells)
+	}
+	return t
+}
+
+// target returns the variable stmt assigns or reads input into, or "" if it
+// does neither or assigns part of one
+func target(stmt ast.Statement) string {
+	var expr ast.Expression
+	switch s := stmt.(type) {
+	case *ast.AssignmentStatement:
+		expr = s.Name
+	case *ast.InputStatement:
+		expr = s.Variable
+	}
+	if id, ok := expr.(*ast.Identifier); ok {
+		return id.Value
+	}
+	return ""
+}
+
+// elements calls visit with the column heading each element of the array
+// name, such as Scores[3] or Grid[1,2], and what it holds, in index order
+func elements(name string, array *interpreter.Array, visit func(column, value string)) {
+	size := 1
+	for _, d := range array.Dimensions {
+		size *= d.Upper - d.Lower + 1
+	}
+	if len(array.Dimensions) == 0 || size <= 0 || size > maxElements {
+		return
+	}
+	indices := make([]int64, len(array.Dimensions))
+	var walk func(dim int)
+	walk = func(dim int) {
+		if dim == len(indices) {
+			value := ""
+			if element := array.Get(indices...); element != nil {
+				value = element.Inspect()
+			}
+			visit(name+"["+array.GetIndex(indices...)+"]", value)
+			return
+		}
+		for idx := array.Dimensions[dim].Lower; idx <= array.Dimensions[dim].Upper; idx++ {
+			indices[dim] = int64(idx)
+			walk(dim + 1)
+		}
+	}
+	walk(0)
+}
+
+func sortedKeys(m map[string]*interpreter.Array) []string {
+	keys := make([]string, 0, len(m))
+	for key := range m {
+		keys = append(keys, key)
+	}
+	sort.Strings(keys)
+	return keys
+}
+
+func contains(list []string, s string) bool {
+	for _, item := range list {
+		if item == s {
+			return true
+		}
+	}
+	return false
+}
+
+// page is an exercise as HTML: the program, the input it is run on and the
+// trace table to complete
+var page = template.Must(template.New("page").Parse(`<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{.Title}}</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+pre { font-size: 1.05em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid black; min-width: 5em; height: 1.6em; padding: 0 0.5em; text-align: center; }
+</style>
+</head>
+<body>
+<h1>{{.Title}}</h1>
+<pre>{{.Table.Source}}</pre>
+{{if .Table.Input}}<p>The program is run with the input: {{range $n, $line := .Table.Input}}{{if $n}}, {{end}}<code>{{$line}}</code>{{end}}</p>
+{{end}}<p>Complete the trace table.</p>
+<table>
+<tr>{{range .Table.Columns}}<th>{{.}}</th>{{end}}</tr>
+{{range .Table.Rows}}<tr>{{range .}}<td>{{if $.Answers}}{{.}}{{end}}</td>{{end}}</tr>
+{{end}}</table>
+</body>
+</html>
+`))
+
+// WriteHTML writes t as a page to print: blank as a worksheet, or filled in
+// if answers is true
+func (t *Table) WriteHTML(w io.Writer, title string, answers bool) error {
+	return page.Execute(w, struct {
+		Title   string
+		Table   *Table
+		Answers bool
+	}{title, t, answers})
+}
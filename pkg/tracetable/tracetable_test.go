@@ -0,0 +1,126 @@
+package tracetable
+
+import (
+	"bytes"
+	"math/rand"
+	"strings"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/fuzz"
+)
+
+func TestVary(t *testing.T) {
+	src := "CONSTANT Rate = 2.5\nDECLARE Name : STRING\nDECLARE Low : INTEGER\nName <- \"Ada\"\nLow <- -4\nName <- \"Bob\"\n"
+	tests := []struct {
+		name     string
+		ranges   map[string]fuzz.Range
+		expected string
+		err      bool
+	}{
+		{
+			"constant, string and negative number",
+			map[string]fuzz.Range{
+				"Rate": {Kind: fuzz.Real, Low: 3, High: 3},
+				"Name": {Kind: fuzz.Choice, Choices: []string{"Eve"}},
+				"Low":  {Kind: fuzz.Integer, Low: 7, High: 7},
+			},
+			"CONSTANT Rate = 3.0\nDECLARE Name : STRING\nDECLARE Low : INTEGER\nName <- \"Eve\"\nLow <- 7\nName <- \"Bob\"\n",
+			false,
+		},
+		{"nothing to vary", nil, src, false},
+		{"unknown name", map[string]fuzz.Range{"Missing": {Kind: fuzz.Integer}}, "", true},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			got, err := Vary(src, tt.ranges, rand.New(rand.NewSource(1)))
+			if (err != nil) != tt.err {
+				t.Fatalf("expected error %t, got %v", tt.err, err)
+			}
+			if got != tt.expected {
+				t.Errorf("expected\n%s\ngot\n%s", tt.expected, got)
+			}
+		})
+	}
+}
+
+func TestGenerate(t *testing.T) {
+	tests := []struct {
+		name     string
+		src      string
+		opts     Options
+		columns  string
+		expected []string // rows, cells joined by commas
+	}{
+		{
+			"loop with input",
+			"DECLARE Total : INTEGER\nDECLARE Count : INTEGER\nDECLARE N : STRING\nCONSTANT Limit = 3\nTotal <- 0\nFOR Count <- 1 TO Limit\n    INPUT N\n    Total <- Total + STR_TO_NUM(N)\nNEXT Count\nOUTPUT Total\n",
+			Options{Inputs: map[string]fuzz.Range{"N": {Kind: fuzz.Choice, Choices: []string{"4"}}}},
+			"Total, Count, N, OUTPUT",
+			[]string{"0,1,4,", "4,2,4,", "8,3,4,", "12,,,12"},
+		},
+		{
+			"array elements",
+			"DECLARE Scores : ARRAY[1:3] OF INTEGER\nDECLARE Index : INTEGER\nFOR Index <- 1 TO 3\n    Scores[Index] <- Index * 2\nNEXT Index\nOUTPUT Scores[3]\n",
+			Options{},
+			"Index, Scores[1], Scores[2], Scores[3], OUTPUT",
+			[]string{"1,2,,,", "2,,4,,", "3,,,6,6"},
+		},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			table, err := Generate(tt.src, tt.opts)
+			if err != nil {
+				t.Fatalf("unexpected error: %v", err)
+			}
+			if got := strings.Join(table.Columns, ", "); got != tt.columns {
+				t.Errorf("expected columns %s, got %s", tt.columns, got)
+			}
+			var rows []string
+			for _, row := range table.Rows {
+				rows = append(rows, strings.Join(row, ","))
+			}
+			if strings.Join(rows, "\n") != strings.Join(tt.expected, "\n") {
+				t.Errorf("expected rows\n%s\ngot\n%s", strings.Join(tt.expected, "\n"), strings.Join(rows, "\n"))
+			}
+		})
+	}
+}
+
+func TestGenerateFails(t *testing.T) {
+	tests := []struct {
+		name string
+		src  string
+		err  string
+	}{
+		{"runtime error", "DECLARE N : INTEGER\nN <- 1 DIV 0\n", "line 2"},
+		{"endless loop", "DECLARE N : INTEGER\nWHILE TRUE\n    N <- N + 1\nENDWHILE\n", "still running after 100 steps"},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			_, err := Generate(tt.src, Options{Steps: 100})
+			if err == nil || !strings.Contains(err.Error(), tt.err) {
+				t.Errorf("expected an error containing %q, got %v", tt.err, err)
+			}
+		})
+	}
+}
+
+func TestWriteHTML(t *testing.T) {
+	table := &Table{Source: "OUTPUT 1 < 2\n", Columns: []string{OutputColumn}, Rows: [][]string{{"TRUE"}}}
+	var worksheet, answers bytes.Buffer
+	if err := table.WriteHTML(&worksheet, "Question 1", false); err != nil {
+		t.Fatal(err)
+	}
+	if err := table.WriteHTML(&answers, "Question 1", true); err != nil {
+		t.Fatal(err)
+	}
+	if !strings.Contains(worksheet.String(), "OUTPUT 1 &lt; 2") || strings.Contains(worksheet.String(), "<td>TRUE</td>") {
+		t.Errorf("expected the worksheet to show the program but not the answer:\n%s", worksheet.String())
+	}
+	if !strings.Contains(answers.String(), "<td>TRUE</td>") {
+		t.Errorf("expected the answers to fill in the table:\n%s", answers.String())
+	}
+}
@@ -0,0 +1,113 @@
+// Package transcript records what a run read and wrote, line by line in the
+// order it did so, to a file that can be checked later: it holds the
+// program's source and a hash over everything in it, so a run can be shown
+// to be the one recorded and be made again with the same input
+package transcript
+
+import (
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"fmt"
+	"io"
+)
+
+// Version is the transcript format Write produces and Read accepts
+const Version = 1
+
+// Kinds of Event
+const (
+	Input  = "input"
+	Output = "output"
+)
+
+// Event is a line read with INPUT or written with OUTPUT
+type Event struct {
+	Kind string `json:"kind"`
+	Text string `json:"text"`
+}
+
+// Transcript is a recorded run
+// It is an interpreter.Transcript, adding an event for each line the run
+// reads and writes.
+type Transcript struct {
+	Version int     `json:"version"`
+	Program string  `json:"program"`
+	Source  string  `json:"source"`
+	Seed    *int64  `json:"seed,omitempty"` // of RAND and RANDOM, if the run was deterministic
+	Events  []Event `json:"events"`
+	Hash    string  `json:"hash"` // of the rest, as Sum gives it
+}
+
+// New starts a transcript of a run of program, whose source is kept so the
+// run can be made again
+func New(program, source string) *Transcript {
+	return &Transcript{Version: Version, Program: program, Source: source}
+}
+
+func (t *Transcript) Input(line string) {
+	t.Events = append(t.Events, Event{Kind: Input, Text: line})
+}
+
+func (t *Transcript) Output(line string) {
+	t.Events = append(t.Events, Event{Kind: Output, Text: line})
+}
+
+// Inputs returns the lines the run read, in order
+func (t *Transcript) Inputs() []string {
+	var lines []string
+	for _, e := range t.Events {
+		if e.Kind == Input {
+			lines = append(lines, e.Text)
+		}
+	}
+	return lines
+}
+
+// Sum returns the SHA-256 hash of everything in t but its Hash, as
+// "sha256:" and the hash in hex
+func (t *Transcript) Sum() string {
+	unhashed := *t
+	unhashed.Hash = ""
+	data, _ := json.Marshal(unhashed) // a Transcript always encodes
+	sum := sha256.Sum256(data)
+	return "sha256:" + hex.EncodeToString(sum[:])
+}
+
+// Write sets t's Hash and writes t as indented JSON
+func (t *Transcript) Write(w io.Writer) error {
+	t.Hash = t.Sum()
+	data, err := json.MarshalIndent(t, "", "  ")
+	if err != nil {
+		return err
+	}
+	_, err = w.Write(append(data, '\n'))
+	return err
+}
+
+// Read parses a transcript, failing if it is not one or has been changed
+// since it was written
+func Read(r io.Reader) (*Transcript, error) {
+	var t Transcript
+	if err := json.NewDecoder(r).Decode(&t); err != nil {
+		return nil, fmt.Errorf("not a transcript: %w", err)
+	}
+	if t.Version != Version {
+		return nil, fmt.Errorf("transcript format %d is not supported (expected %d)", t.Version, Version)
+	}
+	if sum := t.Sum(); t.Hash != sum {
+		return nil, fmt.Errorf("the transcript has been changed since it was recorded, as its hash is %s but its contents hash to %s", t.Hash, sum)
+	}
+	return &t, nil
+}
+
+// Diff returns the index of the first event where replayed differs from
+// recorded, or -1 if they are the same
+func Diff(recorded, replayed []Event) int {
+	for n := 0; n < len(recorded) || n < len(replayed); n++ {
+		if n >= len(recorded) || n >= len(replayed) || recorded[n] != replayed[n] {
+			return n
+		}
+	}
+	return -1
+}
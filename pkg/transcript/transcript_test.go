@@ -0,0 +1,86 @@
+package transcript
+
+import (
+	"bytes"
+	"io"
+	"reflect"
+	"strings"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+const program = `DECLARE Name : STRING
+OUTPUT "What is your name?"
+INPUT Name
+OUTPUT "Hello, ", Name`
+
+func record(t *testing.T, input string) *Transcript {
+	t.Helper()
+	p := parser.New(lexer.New(program))
+	tree := p.ParseProgram()
+	interpreter.Resolve(tree)
+	interp := interpreter.New()
+	interp.SetInput(strings.NewReader(input))
+	interp.SetOutput(io.Discard)
+	tr := New("main.cam", program)
+	interp.SetTranscript(tr)
+	interp.Eval(tree)
+	return tr
+}
+
+func TestRecordAndRead(t *testing.T) {
+	tr := record(t, "Ada\nextra\n")
+	expected := []Event{
+		{Output, "What is your name?"},
+		{Input, "Ada"},
+		{Output, "Hello, Ada"},
+	}
+	if !reflect.DeepEqual(tr.Events, expected) {
+		t.Fatalf("expected events %+v, got %+v", expected, tr.Events)
+	}
+
+	var file bytes.Buffer
+	if err := tr.Write(&file); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	got, err := Read(bytes.NewReader(file.Bytes()))
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if got.Source != program || !reflect.DeepEqual(got.Events, expected) || got.Hash != tr.Hash {
+		t.Errorf("expected the transcript back as written, got %+v", got)
+	}
+	if inputs := got.Inputs(); !reflect.DeepEqual(inputs, []string{"Ada"}) {
+		t.Errorf("expected the input Ada, got %v", inputs)
+	}
+
+	tampered := strings.Replace(file.String(), `"Ada"`, `"Bob"`, 1)
+	if _, err := Read(strings.NewReader(tampered)); err == nil || !strings.Contains(err.Error(), "changed since it was recorded") {
+		t.Errorf("expected a changed transcript to be refused, got %v", err)
+	}
+	if _, err := Read(strings.NewReader("not json")); err == nil {
+		t.Error("expected an error for a file that is not a transcript")
+	}
+}
+
+func TestDiff(t *testing.T) {
+	recorded := record(t, "Ada\n").Events
+	tests := []struct {
+		input    string
+		expected int
+	}{
+		{"Ada\n", -1},
+		{"Bob\n", 1},
+	}
+	for _, tt := range tests {
+		if got := Diff(recorded, record(t, tt.input).Events); got != tt.expected {
+			t.Errorf("%q: expected %d, got %d", tt.input, tt.expected, got)
+		}
+	}
+	if got := Diff(recorded, recorded[:2]); got != 2 {
+		t.Errorf("expected a shorter run to differ at 2, got %d", got)
+	}
+}
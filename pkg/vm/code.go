@@ -0,0 +1,220 @@
+// Package vm compiles programs to bytecode and runs them on a stack machine
+// It shares value types and operator semantics with the interpreter package,
+// so the two backends produce the same output for the programs both accept.
+package vm
+
+import (
+	"encoding/binary"
+	"fmt"
+)
+
+// Instructions is a sequence of encoded instructions
+type Instructions []byte
+
+// Opcode identifies an instruction
+type Opcode byte
+
+const (
+	OpConstant Opcode = iota
+	OpPop
+	OpNull
+	OpTrue
+	OpFalse
+
+	// Arithmetic, comparison and logic
+	OpAdd
+	OpSub
+	OpMul
+	OpDiv
+	OpIntDiv
+	OpMod
+	OpConcat
+	OpEqual
+	OpNotEqual
+	OpLess
+	OpGreater
+	OpLessEqual
+	OpGreaterEqual
+	OpAnd
+	OpOr
+	OpMinus
+	OpNot
+
+	// Control flow
+	OpJump
+	OpJumpFalse
+	OpJumpTrue
+
+	// Variables
+	OpGetGlobal
+	OpSetGlobal
+	OpGetLocal
+	OpSetLocal
+	OpGetBuiltin
+
+	// Arrays
+	OpNewArray
+	OpIndex
+	OpSetIndex
+
+	// Statements with dedicated semantics
+	OpForInit
+	OpForTest
+	OpCaseMatch
+	OpCaseRange
+	OpInput
+	OpOutput
+	OpFail
+
+	// Routines
+	OpCall
+	OpReturnValue
+	OpReturn
+)
+
+// Definition describes an opcode for encoding and disassembly
+type Definition struct {
+	Name          string
+	OperandWidths []int
+}
+
+var definitions = map[Opcode]*Definition{
+	OpConstant: {"CONSTANT", []int{2}},
+	OpPop:      {"POP", nil},
+	OpNull:     {"NULL", nil},
+	OpTrue:     {"TRUE", nil},
+	OpFalse:    {"FALSE", nil},
+
+	OpAdd:          {"ADD", nil},
+	OpSub:          {"SUB", nil},
+	OpMul:          {"MUL", nil},
+	OpDiv:          {"DIV", nil},
+	OpIntDiv:       {"INTDIV", nil},
+	OpMod:          {"MOD", nil},
+	OpConcat:       {"CONCAT", nil},
+	OpEqual:        {"EQUAL", nil},
+	OpNotEqual:     {"NOT_EQUAL", nil},
+	OpLess:         {"LESS", nil},
+	OpGreater:      {"GREATER", nil},
+	OpLessEqual:    {"LESS_EQUAL", nil},
+	OpGreaterEqual: {"GREATER_EQUAL", nil},
+	OpAnd:          {"AND", nil},
+	OpOr:           {"OR", nil},
+	OpMinus:        {"MINUS", nil},
+	OpNot:          {"NOT", nil},
+
+	OpJump:      {"JUMP", []int{2}},
+	OpJumpFalse: {"JUMP_FALSE", []int{2}},
+	OpJumpTrue:  {"JUMP_TRUE", []int{2}},
+
+	OpGetGlobal:  {"GET_GLOBAL", []int{2}},
+	OpSetGlobal:  {"SET_GLOBAL", []int{2}},
+	OpGetLocal:   {"GET_LOCAL", []int{1}},
+	OpSetLocal:   {"SET_LOCAL", []int{1}},
+	OpGetBuiltin: {"GET_BUILTIN", []int{1}},
+
+	OpNewArray: {"NEW_ARRAY", []int{2}},
+	OpIndex:    {"INDEX", []int{1}},
+	OpSetIndex: {"SET_INDEX", []int{1}},
+
+	OpForInit:   {"FOR_INIT", nil},
+	OpForTest:   {"FOR_TEST", nil},
+	OpCaseMatch: {"CASE_MATCH", nil},
+	OpCaseRange: {"CASE_RANGE", nil},
+	OpInput:     {"INPUT", nil},
+	OpOutput:    {"OUTPUT", []int{1}},
+	OpFail:      {"FAIL", []int{2}},
+
+	OpCall:        {"CALL", []int{1}},
+	OpReturnValue: {"RETURN_VALUE", nil},
+	OpReturn:      {"RETURN", nil},
+}
+
+// infixOps maps pseudocode operators to the opcode implementing them
+var infixOps = map[string]Opcode{
+	"+":   OpAdd,
+	"-":   OpSub,
+	"*":   OpMul,
+	"/":   OpDiv,
+	"DIV": OpIntDiv,
+	"MOD": OpMod,
+	"&":   OpConcat,
+	"=":   OpEqual,
+	"<>":  OpNotEqual,
+	"<":   OpLess,
+	">":   OpGreater,
+	"<=":  OpLessEqual,
+	">=":  OpGreaterEqual,
+	"AND": OpAnd,
+	"OR":  OpOr,
+}
+
+// operators is the reverse of infixOps, used when executing
+var operators = map[Opcode]string{}
+
+func init() {
+	for op, code := range infixOps {
+		operators[code] = op
+	}
+}
+
+// Lookup returns the definition of op
+func Lookup(op Opcode) (*Definition, error) {
+	def, ok := definitions[op]
+	if !ok {
+		return nil, fmt.Errorf("opcode %d undefined", op)
+	}
+	return def, nil
+}
+
+// Make encodes an instruction
+func Make(op Opcode, operands ...int) []byte {
+	def, ok := definitions[op]
+	if !ok {
+		return []byte{}
+	}
+
+	length := 1
+	for _, w := range def.OperandWidths {
+		length += w
+	}
+
+	instruction := make([]byte, length)
+	instruction[0] = byte(op)
+
+	offset := 1
+	for i, o := range operands {
+		switch def.OperandWidths[i] {
+		case 2:
+			binary.BigEndian.PutUint16(instruction[offset:], uint16(o))
+		case 1:
+			instruction[offset] = byte(o)
+		}
+		offset += def.OperandWidths[i]
+	}
+
+	return instruction
+}
+
+// ReadOperands decodes the operands following an instruction and returns
+// them with the number of bytes read
+func ReadOperands(def *Definition, ins Instructions) ([]int, int) {
+	operands := make([]int, len(def.OperandWidths))
+	offset := 0
+
+	for i, width := range def.OperandWidths {
+		switch width {
+		case 2:
+			operands[i] = int(readUint16(ins[offset:]))
+		case 1:
+			operands[i] = int(ins[offset])
+		}
+		offset += width
+	}
+
+	return operands, offset
+}
+
+func readUint16(ins Instructions) uint16 {
+	return binary.BigEndian.Uint16(ins)
+}
@@ -0,0 +1,646 @@
+package vm
+
+import (
+	"errors"
+	"fmt"
+	"math"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+)
+
+// ErrUnsupported is wrapped by compile errors for constructs the bytecode
+// backend does not handle yet; such programs should be run by the interpreter
+var ErrUnsupported = errors.New("not supported by the bytecode compiler")
+
+// Position maps the instruction at Offset back to the statement it came from
+type Position struct {
+	Offset int
+	Line   int
+	Column int
+}
+
+// CompiledFunction is a procedure, function or the main program as bytecode
+type CompiledFunction struct {
+	Name         string
+	Kind         string // "PROCEDURE", "FUNCTION" or "PROGRAM"
+	Instructions Instructions
+	NumParams    int
+	NumLocals    int
+	Locals       []string   // variable name held by each local slot
+	Positions    []Position // sorted by offset
+}
+
+func (f *CompiledFunction) Type() interpreter.ObjectType {
+	if f.Kind == "FUNCTION" {
+		return interpreter.FUNCTION_OBJ
+	}
+	return interpreter.PROCEDURE_OBJ
+}
+
+func (f *CompiledFunction) Inspect() string { return f.Kind + " " + f.Name }
+
+// position returns the source position of the statement containing ip
+func (f *CompiledFunction) position(ip int) (int, int) {
+	line, column := 0, 0
+	for _, p := range f.Positions {
+		if p.Offset > ip {
+			break
+		}
+		line, column = p.Line, p.Column
+	}
+	return line, column
+}
+
+// Bytecode is a compiled program ready to run
+type Bytecode struct {
+	Main      *CompiledFunction
+	Constants []interpreter.Object
+	Globals   []string // variable name held by each global slot
+	Builtins  []string // names referenced by GET_BUILTIN, by index
+}
+
+// symbol is a resolved variable
+type symbol struct {
+	global   bool
+	index    int
+	constant bool
+}
+
+// scope is a block of declarations; FOR loops open a new one, as the
+// interpreter gives each loop its own environment
+type scope struct {
+	symbols map[string]*symbol
+	outer   *scope
+}
+
+// routine is the function currently being compiled
+type routine struct {
+	fn    *CompiledFunction
+	scope *scope
+}
+
+// Compiler translates a parsed program into bytecode
+type Compiler struct {
+	constants []interpreter.Object
+	globals   *scope
+	names     []string
+	builtins  map[string]bool
+	builtinAt map[string]int
+	used      []string
+	current   *routine
+	err       error
+}
+
+// Compile compiles program; builtins are the names callable without a
+// definition, as passed to the interpreter
+func Compile(program *ast.Program, builtins map[string]*interpreter.Builtin) (*Bytecode, error) {
+	c := &Compiler{
+		globals:   &scope{symbols: map[string]*symbol{}},
+		builtins:  map[string]bool{},
+		builtinAt: map[string]int{},
+	}
+	for name := range builtins {
+		c.builtins[name] = true
+	}
+
+	main := &CompiledFunction{Name: "(main)", Kind: "PROGRAM"}
+	c.current = &routine{fn: main, scope: c.globals}
+
+	// Routines may use globals declared further down the file, so every
+	// top-level name gets its slot before any code is compiled
+	c.declareGlobals(program.Statements)
+
+	c.statements(program.Statements)
+	c.emit(OpReturn)
+
+	if c.err != nil {
+		return nil, c.err
+	}
+	return &Bytecode{
+		Main:      main,
+		Constants: c.constants,
+		Globals:   c.names,
+		Builtins:  c.used,
+	}, nil
+}
+
+func (c *Compiler) declareGlobals(stmts []ast.Statement) {
+	for _, stmt := range stmts {
+		ast.Inspect(stmt, func(n ast.Node) bool {
+			switch n := n.(type) {
+			case *ast.ProcedureStatement:
+				c.globalSymbol(n.Name)
+				return false
+			case *ast.FunctionStatement:
+				c.globalSymbol(n.Name)
+				return false
+			case *ast.ForStatement:
+				// Loop bodies have their own scope
+				return false
+			case *ast.DeclareStatement:
+				c.globalSymbol(n.Name.Value)
+			case *ast.ConstantStatement:
+				c.globalSymbol(n.Name.Value).constant = true
+			case *ast.AssignmentStatement:
+				if id, ok := n.Name.(*ast.Identifier); ok {
+					c.globalSymbol(id.Value)
+				}
+			case *ast.InputStatement:
+				if id, ok := n.Variable.(*ast.Identifier); ok {
+					c.globalSymbol(id.Value)
+				}
+			}
+			return true
+		})
+	}
+}
+
+// globalSymbol returns the global called name, creating it if needed
+func (c *Compiler) globalSymbol(name string) *symbol {
+	if sym, ok := c.globals.symbols[name]; ok {
+		return sym
+	}
+	sym := &symbol{global: true, index: len(c.names)}
+	c.names = append(c.names, name)
+	c.globals.symbols[name] = sym
+	if len(c.names) > math.MaxUint16 {
+		c.fail(fmt.Errorf("too many variables: %w", ErrUnsupported))
+	}
+	return sym
+}
+
+// define creates name in the innermost scope, reusing its slot if the scope
+// already has one
+func (c *Compiler) define(name string) *symbol {
+	s := c.current.scope
+	if sym, ok := s.symbols[name]; ok {
+		return sym
+	}
+	if s == c.globals {
+		return c.globalSymbol(name)
+	}
+
+	fn := c.current.fn
+	var sym *symbol
+	if fn.Kind == "PROGRAM" {
+		// Block scopes in the main program still live in global slots
+		sym = &symbol{global: true, index: len(c.names)}
+		c.names = append(c.names, name)
+	} else {
+		sym = &symbol{index: fn.NumLocals}
+		fn.NumLocals++
+		fn.Locals = append(fn.Locals, name)
+		if fn.NumLocals > math.MaxUint8 {
+			c.fail(fmt.Errorf("too many local variables in %s: %w", fn.Name, ErrUnsupported))
+		}
+	}
+	s.symbols[name] = sym
+	return sym
+}
+
+// resolve finds name in the enclosing scopes, falling back to globals
+func (c *Compiler) resolve(name string) (*symbol, bool) {
+	for s := c.current.scope; s != nil; s = s.outer {
+		if sym, ok := s.symbols[name]; ok {
+			return sym, true
+		}
+	}
+	sym, ok := c.globals.symbols[name]
+	return sym, ok
+}
+
+func (c *Compiler) pushScope() {
+	c.current.scope = &scope{symbols: map[string]*symbol{}, outer: c.current.scope}
+}
+
+func (c *Compiler) popScope() {
+	c.current.scope = c.current.scope.outer
+}
+
+func (c *Compiler) fail(err error) {
+	if c.err == nil {
+		c.err = err
+	}
+}
+
+func (c *Compiler) unsupported(what string, node ast.Node) {
+	line, column := ast.Position(node)
+	c.fail(fmt.Errorf("line %d, column %d: %s is %w", line, column, what, ErrUnsupported))
+}
+
+func (c *Compiler) addConstant(obj interpreter.Object) int {
+	c.constants = append(c.constants, obj)
+	if len(c.constants) > math.MaxUint16+1 {
+		c.fail(fmt.Errorf("too many constants: %w", ErrUnsupported))
+	}
+	return len(c.constants) - 1
+}
+
+func (c *Compiler) emit(op Opcode, operands ...int) int {
+	fn := c.current.fn
+	pos := len(fn.Instructions)
+	fn.Instructions = append(fn.Instructions, Make(op, operands...)...)
+	return pos
+}
+
+// mark records that the code emitted next belongs to node
+func (c *Compiler) mark(node ast.Node) {
+	line, column := ast.Position(node)
+	if line == 0 {
+		return
+	}
+	fn := c.current.fn
+	fn.Positions = append(fn.Positions, Position{Offset: len(fn.Instructions), Line: line, Column: column})
+}
+
+// jumpTarget returns the offset of the next instruction
+func (c *Compiler) jumpTarget() int {
+	target := len(c.current.fn.Instructions)
+	if target > math.MaxUint16 {
+		c.fail(fmt.Errorf("routine %s is too long: %w", c.current.fn.Name, ErrUnsupported))
+	}
+	return target
+}
+
+// patch points the jump at pos to the next instruction
+func (c *Compiler) patch(pos int) {
+	target := c.jumpTarget()
+	op := Opcode(c.current.fn.Instructions[pos])
+	copy(c.current.fn.Instructions[pos:], Make(op, target))
+}
+
+// raise emits an instruction that fails with err when reached
+func (c *Compiler) raise(err *interpreter.Error) {
+	c.emit(OpFail, c.addConstant(err))
+}
+
+func (c *Compiler) statements(stmts []ast.Statement) {
+	for _, stmt := range stmts {
+		c.statement(stmt)
+	}
+}
+
+func (c *Compiler) statement(stmt ast.Statement) {
+	c.mark(stmt)
+
+	switch stmt := stmt.(type) {
+	case *ast.DeclareStatement:
+		c.declare(stmt)
+	case *ast.ConstantStatement:
+		c.expression(stmt.Value)
+		sym := c.define(stmt.Name.Value)
+		sym.constant = true
+		c.store(sym)
+	case *ast.AssignmentStatement:
+		c.expression(stmt.Value)
+		c.assign(stmt.Name)
+	case *ast.IfStatement:
+		c.ifStatement(stmt)
+	case *ast.CaseStatement:
+		c.caseStatement(stmt)
+	case *ast.ForStatement:
+		c.forStatement(stmt)
+	case *ast.WhileStatement:
+		start := c.jumpTarget()
+		c.expression(stmt.Condition)
+		exit := c.emit(OpJumpFalse, 0)
+		c.statements(stmt.Body)
+		c.emit(OpJump, start)
+		c.patch(exit)
+	case *ast.RepeatStatement:
+		start := c.jumpTarget()
+		c.statements(stmt.Body)
+		c.mark(stmt)
+		c.expression(stmt.Condition)
+		c.emit(OpJumpFalse, start)
+	case *ast.ProcedureStatement:
+		c.routine(stmt.Name, "PROCEDURE", stmt.Parameters, stmt.Body, stmt)
+	case *ast.FunctionStatement:
+		c.routine(stmt.Name, "FUNCTION", stmt.Parameters, stmt.Body, stmt)
+	case *ast.CallStatement:
+		c.call(stmt.Name, stmt.Arguments)
+		c.emit(OpPop)
+	case *ast.ReturnStatement:
+		if stmt.Value == nil {
+			c.emit(OpReturn)
+		} else {
+			c.expression(stmt.Value)
+			c.emit(OpReturnValue)
+		}
+	case *ast.InputStatement:
+		switch stmt.Variable.(type) {
+		case *ast.Identifier, *ast.ArrayAccess:
+			c.emit(OpInput)
+			c.assign(stmt.Variable)
+		default:
+			// The interpreter reads the line and discards it
+			c.emit(OpInput)
+			c.emit(OpPop)
+		}
+	case *ast.OutputStatement:
+		for _, value := range stmt.Values {
+			c.expression(value)
+		}
+		if len(stmt.Values) > math.MaxUint8 {
+			c.unsupported("an OUTPUT of more than 255 values", stmt)
+		}
+		c.emit(OpOutput, len(stmt.Values))
+	case *ast.ExpressionStatement:
+		c.expression(stmt.Expression)
+		c.emit(OpPop)
+	case *ast.OpenFileStatement, *ast.CloseFileStatement, *ast.ReadFileStatement, *ast.WriteFileStatement:
+		c.unsupported("file handling", stmt)
+	case *ast.TypeStatement:
+		c.unsupported("TYPE", stmt)
+	case *ast.ClassStatement:
+		c.unsupported("CLASS", stmt)
+	default:
+		c.unsupported(fmt.Sprintf("%T", stmt), stmt)
+	}
+}
+
+func (c *Compiler) declare(stmt *ast.DeclareStatement) {
+	switch dt := stmt.DataType.(type) {
+	case *ast.PrimitiveType:
+		// Primitive values are never modified in place, so every
+		// declaration can share one default
+		c.emit(OpConstant, c.addConstant(interpreter.DefaultValue(dt.Name)))
+	case *ast.ArrayType:
+		proto := &interpreter.Array{Dimensions: dt.Dimensions, Default: interpreter.ElementDefault(dt.ElementType)}
+		c.emit(OpNewArray, c.addConstant(proto))
+	default:
+		c.unsupported("a DECLARE of a user-defined type", stmt)
+		return
+	}
+	c.store(c.define(stmt.Name.Value))
+}
+
+// store pops the top of the stack into sym
+func (c *Compiler) store(sym *symbol) {
+	if sym.global {
+		c.emit(OpSetGlobal, sym.index)
+	} else {
+		c.emit(OpSetLocal, sym.index)
+	}
+}
+
+func (c *Compiler) load(sym *symbol) {
+	if sym.global {
+		c.emit(OpGetGlobal, sym.index)
+	} else {
+		c.emit(OpGetLocal, sym.index)
+	}
+}
+
+// assign pops the value on top of the stack into target
+func (c *Compiler) assign(target ast.Expression) {
+	switch target := target.(type) {
+	case *ast.Identifier:
+		sym, ok := c.resolve(target.Value)
+		if !ok {
+			// Assigning an undeclared name creates it in the current scope
+			sym = c.define(target.Value)
+		}
+		if sym.constant {
+			c.emit(OpPop)
+			c.raise(interpreter.NewError(diagnostic.ConstantModified, target.Value))
+			return
+		}
+		c.store(sym)
+	case *ast.ArrayAccess:
+		c.expression(target.Array)
+		c.indices(target.Indices)
+		c.emit(OpSetIndex, len(target.Indices))
+	case *ast.MemberAccess:
+		c.unsupported("record and object fields", target)
+	default:
+		c.raise(interpreter.NewError(diagnostic.InvalidAssignmentTarget))
+	}
+}
+
+func (c *Compiler) indices(indices []ast.Expression) {
+	if len(indices) > math.MaxUint8 {
+		c.fail(fmt.Errorf("too many array dimensions: %w", ErrUnsupported))
+	}
+	for _, idx := range indices {
+		c.expression(idx)
+	}
+}
+
+func (c *Compiler) ifStatement(stmt *ast.IfStatement) {
+	c.expression(stmt.Condition)
+	skip := c.emit(OpJumpFalse, 0)
+	c.statements(stmt.Consequence)
+
+	if stmt.Alternative == nil {
+		c.patch(skip)
+		return
+	}
+
+	end := c.emit(OpJump, 0)
+	c.patch(skip)
+	c.statements(stmt.Alternative)
+	c.patch(end)
+}
+
+func (c *Compiler) caseStatement(stmt *ast.CaseStatement) {
+	c.pushScope()
+	defer c.popScope()
+
+	// The subject is evaluated once and kept in a hidden variable
+	subject := c.define(" case")
+	c.expression(stmt.Expr)
+	c.store(subject)
+
+	var ends []int
+	for _, clause := range stmt.Cases {
+		var matches []int
+		for _, value := range clause.Values {
+			c.load(subject)
+			if r, ok := value.(*ast.RangeExpression); ok {
+				c.expression(r.Start)
+				c.expression(r.End)
+				c.emit(OpCaseRange)
+			} else {
+				c.expression(value)
+				c.emit(OpCaseMatch)
+			}
+			matches = append(matches, c.emit(OpJumpTrue, 0))
+		}
+		next := c.emit(OpJump, 0)
+
+		for _, m := range matches {
+			c.patch(m)
+		}
+		c.statements(clause.Body)
+		ends = append(ends, c.emit(OpJump, 0))
+		c.patch(next)
+	}
+
+	c.statements(stmt.Otherwise)
+	for _, end := range ends {
+		c.patch(end)
+	}
+}
+
+func (c *Compiler) forStatement(stmt *ast.ForStatement) {
+	c.expression(stmt.Start)
+	c.expression(stmt.End)
+	if stmt.Step != nil {
+		c.expression(stmt.Step)
+	} else {
+		c.emit(OpConstant, c.addConstant(&interpreter.Integer{Value: 1}))
+	}
+	c.emit(OpForInit)
+
+	c.pushScope()
+	defer c.popScope()
+
+	// The counter is kept apart from the loop variable, so assigning to the
+	// variable in the body does not change how many times the loop runs
+	step := c.define(" step")
+	end := c.define(" end")
+	counter := c.define(" counter")
+	variable := c.define(stmt.Variable.Value)
+	c.store(step)
+	c.store(end)
+	c.store(counter)
+
+	start := c.jumpTarget()
+	c.load(counter)
+	c.load(end)
+	c.load(step)
+	c.emit(OpForTest)
+	exit := c.emit(OpJumpFalse, 0)
+
+	c.load(counter)
+	c.store(variable)
+	c.statements(stmt.Body)
+
+	c.load(counter)
+	c.load(step)
+	c.emit(OpAdd)
+	c.store(counter)
+	c.emit(OpJump, start)
+	c.patch(exit)
+}
+
+func (c *Compiler) routine(name, kind string, params []ast.Parameter, body []ast.Statement, node ast.Node) {
+	if c.current.fn.Kind != "PROGRAM" {
+		c.unsupported("a routine defined inside another routine", node)
+		return
+	}
+
+	fn := &CompiledFunction{Name: name, Kind: kind, NumParams: len(params)}
+	outer := c.current
+	c.current = &routine{fn: fn, scope: &scope{symbols: map[string]*symbol{}}}
+
+	for _, param := range params {
+		c.define(param.Name)
+	}
+	c.statements(body)
+	c.emit(OpReturn)
+
+	c.current = outer
+	c.emit(OpConstant, c.addConstant(fn))
+	c.store(c.define(name))
+}
+
+func (c *Compiler) call(callee ast.Expression, args []ast.Expression) {
+	c.expression(callee)
+	for _, arg := range args {
+		c.expression(arg)
+	}
+	if len(args) > math.MaxUint8 {
+		c.unsupported("a call with more than 255 arguments", callee)
+	}
+	c.emit(OpCall, len(args))
+}
+
+func (c *Compiler) expression(expr ast.Expression) {
+	switch expr := expr.(type) {
+	case *ast.IntegerLiteral:
+		if expr.Big != nil {
+			c.unsupported("an INTEGER literal beyond 64 bits", expr)
+		}
+		c.emit(OpConstant, c.addConstant(&interpreter.Integer{Value: expr.Value}))
+	case *ast.RealLiteral:
+		c.emit(OpConstant, c.addConstant(&interpreter.Real{Value: expr.Value}))
+	case *ast.StringLiteral:
+		c.emit(OpConstant, c.addConstant(&interpreter.String{Value: expr.Value}))
+	case *ast.CharLiteral:
+		value := ' '
+		if len(expr.Value) > 0 {
+			value = rune(expr.Value[0])
+		}
+		c.emit(OpConstant, c.addConstant(&interpreter.Char{Value: value}))
+	case *ast.BooleanLiteral:
+		if expr.Value {
+			c.emit(OpTrue)
+		} else {
+			c.emit(OpFalse)
+		}
+	case *ast.Identifier:
+		c.identifier(expr.Value)
+	case *ast.PrefixExpression:
+		c.expression(expr.Right)
+		switch expr.Operator {
+		case "-":
+			c.emit(OpMinus)
+		case "NOT":
+			c.emit(OpNot)
+		default:
+			c.raise(interpreter.NewError(diagnostic.UnknownOperator, expr.Operator))
+		}
+	case *ast.InfixExpression:
+		c.expression(expr.Left)
+		c.expression(expr.Right)
+		op, ok := infixOps[expr.Operator]
+		if !ok {
+			c.unsupported("operator "+expr.Operator, expr)
+			return
+		}
+		c.emit(op)
+	case *ast.ArrayAccess:
+		c.expression(expr.Array)
+		c.indices(expr.Indices)
+		c.emit(OpIndex, len(expr.Indices))
+	case *ast.CallExpression:
+		c.call(expr.Function, expr.Arguments)
+	case *ast.MemberAccess:
+		c.unsupported("record and object fields", expr)
+	case *ast.NewExpression:
+		c.unsupported("NEW", expr)
+	case *ast.SuperExpression:
+		c.unsupported("SUPER", expr)
+	default:
+		c.unsupported(fmt.Sprintf("%T", expr), expr)
+	}
+}
+
+func (c *Compiler) identifier(name string) {
+	if sym, ok := c.resolve(name); ok {
+		c.load(sym)
+		return
+	}
+
+	if c.builtins[name] {
+		idx, ok := c.builtinAt[name]
+		if !ok {
+			idx = len(c.used)
+			c.builtinAt[name] = idx
+			c.used = append(c.used, name)
+			if idx > math.MaxUint8 {
+				c.fail(fmt.Errorf("too many builtins: %w", ErrUnsupported))
+			}
+		}
+		c.emit(OpGetBuiltin, idx)
+		return
+	}
+
+	// Reading a name nothing defines fails when it runs, as it does in
+	// the interpreter; an empty global slot reports it
+	c.load(c.globalSymbol(name))
+}
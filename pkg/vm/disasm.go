@@ -0,0 +1,114 @@
+package vm
+
+import (
+	"fmt"
+	"io"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+)
+
+// Disassemble writes a readable listing of bytecode to w
+// The main program comes first, then each routine in the order it was
+// defined. When source is given, each run of instructions is headed by the
+// line it was compiled from.
+func Disassemble(w io.Writer, bytecode *Bytecode, source string) {
+	var lines []string
+	if source != "" {
+		lines = strings.Split(source, "\n")
+	}
+
+	routines := []*CompiledFunction{bytecode.Main}
+	for _, c := range bytecode.Constants {
+		if fn, ok := c.(*CompiledFunction); ok {
+			routines = append(routines, fn)
+		}
+	}
+
+	for n, fn := range routines {
+		if n > 0 {
+			fmt.Fprintln(w)
+		}
+		fmt.Fprintf(w, "== %s %s ==\n", fn.Kind, fn.Name)
+		if fn.NumLocals > 0 {
+			fmt.Fprintf(w, "locals: %s\n", strings.Join(fn.Locals, ", "))
+		}
+		disassembleFunction(w, bytecode, fn, lines)
+	}
+}
+
+func disassembleFunction(w io.Writer, bytecode *Bytecode, fn *CompiledFunction, lines []string) {
+	ins := fn.Instructions
+	positions := fn.Positions
+	lastLine := 0
+
+	for ip := 0; ip < len(ins); {
+		// Several statements can start at the same offset; the last one
+		// is the innermost
+		for len(positions) > 0 && positions[0].Offset <= ip {
+			if line := positions[0].Line; line != lastLine {
+				lastLine = line
+				text := ""
+				if line-1 < len(lines) {
+					text = ": " + strings.TrimSpace(lines[line-1])
+				}
+				fmt.Fprintf(w, "  line %d%s\n", line, text)
+			}
+			positions = positions[1:]
+		}
+
+		def, err := Lookup(Opcode(ins[ip]))
+		if err != nil {
+			fmt.Fprintf(w, "    %04d ERROR: %s\n", ip, err)
+			ip++
+			continue
+		}
+
+		operands, read := ReadOperands(def, ins[ip+1:])
+		text := def.Name
+		for _, o := range operands {
+			text += fmt.Sprintf(" %d", o)
+		}
+		if note := annotate(bytecode, fn, Opcode(ins[ip]), operands); note != "" {
+			text = fmt.Sprintf("%-20s ; %s", text, note)
+		}
+		fmt.Fprintf(w, "    %04d %s\n", ip, text)
+
+		ip += 1 + read
+	}
+}
+
+// annotate explains what an instruction's operand refers to
+func annotate(bytecode *Bytecode, fn *CompiledFunction, op Opcode, operands []int) string {
+	if len(operands) == 0 {
+		return ""
+	}
+	o := operands[0]
+
+	switch op {
+	case OpConstant, OpNewArray, OpFail:
+		switch c := bytecode.Constants[o].(type) {
+		case *interpreter.String:
+			return fmt.Sprintf("%q", c.Value)
+		case *interpreter.Char:
+			return fmt.Sprintf("'%c'", c.Value)
+		case *interpreter.Array:
+			dims := make([]string, len(c.Dimensions))
+			for i, d := range c.Dimensions {
+				dims[i] = fmt.Sprintf("%d:%d", d.Lower, d.Upper)
+			}
+			return "ARRAY[" + strings.Join(dims, ", ") + "]"
+		case *interpreter.Error:
+			return c.Message
+		default:
+			return c.Inspect()
+		}
+	case OpGetGlobal, OpSetGlobal:
+		return strings.TrimSpace(bytecode.Globals[o])
+	case OpGetLocal, OpSetLocal:
+		return strings.TrimSpace(fn.Locals[o])
+	case OpGetBuiltin:
+		return bytecode.Builtins[o]
+	}
+	return ""
+}
@@ -0,0 +1,477 @@
+package vm
+
+import (
+	"bufio"
+	"context"
+	"fmt"
+	"io"
+	"log/slog"
+	"os"
+	"strings"
+
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/logging"
+)
+
+var logger = logging.For("vm")
+
+// initialStack is the starting size of the value stack, which grows as needed
+const initialStack = 1024
+
+// maxReportedCalls is how many of the innermost calls a recursion error
+// lists, as in the interpreter
+const maxReportedCalls = 5
+
+// Booleans and NULL are never modified in place, so every result can share these
+var (
+	trueObj  = interpreter.NewBoolean(true)
+	falseObj = interpreter.NewBoolean(false)
+	nullObj  = &interpreter.Null{}
+)
+
+// frame is an active call
+type frame struct {
+	fn   *CompiledFunction
+	ip   int
+	base int // stack index of the first local
+}
+
+// VM executes compiled bytecode
+type VM struct {
+	constants []interpreter.Object
+	globals   []interpreter.Object
+	names     []string
+	builtins  []*interpreter.Builtin
+	wanted    []string
+
+	stack    []interpreter.Object
+	sp       int // next free slot
+	frames   []frame
+	maxDepth int
+	arith    interpreter.Arithmetic
+
+	input  *bufio.Reader
+	output io.Writer
+}
+
+// New creates a VM for bytecode, reading from stdin and writing to stdout
+func New(bytecode *Bytecode) *VM {
+	vm := &VM{
+		constants: bytecode.Constants,
+		globals:   make([]interpreter.Object, len(bytecode.Globals)),
+		names:     bytecode.Globals,
+		wanted:    bytecode.Builtins,
+		stack:     make([]interpreter.Object, initialStack),
+		maxDepth:  interpreter.DefaultMaxDepth,
+		input:     bufio.NewReader(os.Stdin),
+		output:    os.Stdout,
+	}
+	vm.frames = append(vm.frames, frame{fn: bytecode.Main})
+	return vm
+}
+
+// SetBuiltins supplies the built-in functions the program was compiled
+// against
+func (vm *VM) SetBuiltins(builtins map[string]*interpreter.Builtin) {
+	vm.builtins = make([]*interpreter.Builtin, len(vm.wanted))
+	for i, name := range vm.wanted {
+		vm.builtins[i] = builtins[name]
+	}
+}
+
+// SetMaxDepth sets how deeply routine calls may nest, the interpreter's
+// default unless changed
+func (vm *VM) SetMaxDepth(depth int) {
+	vm.maxDepth = depth
+}
+
+// SetArithmetic selects how numbers behave, as in the interpreter
+func (vm *VM) SetArithmetic(a interpreter.Arithmetic) {
+	vm.arith = a
+}
+
+// SetInput sets the input reader
+func (vm *VM) SetInput(r io.Reader) {
+	vm.input = bufio.NewReader(r)
+}
+
+// SetOutput sets the output writer
+func (vm *VM) SetOutput(w io.Writer) {
+	vm.output = w
+}
+
+func (vm *VM) push(obj interpreter.Object) {
+	if vm.sp >= len(vm.stack) {
+		vm.stack = append(vm.stack, make([]interpreter.Object, len(vm.stack))...)
+	}
+	vm.stack[vm.sp] = obj
+	vm.sp++
+}
+
+func (vm *VM) pop() interpreter.Object {
+	vm.sp--
+	return vm.stack[vm.sp]
+}
+
+// Run executes the program and returns the runtime error that stopped it,
+// if any
+func (vm *VM) Run() *interpreter.Error {
+	logger.Debug("run started", "globals", len(vm.globals))
+
+	err := vm.run()
+	if err != nil {
+		if err.Line == 0 {
+			f := &vm.frames[len(vm.frames)-1]
+			err.Line, err.Column = f.fn.position(f.ip)
+		}
+		logger.Debug("runtime error", "code", err.Code, "line", err.Line, "column", err.Column, "message", err.Message)
+		return err
+	}
+
+	logger.Debug("run finished")
+	return nil
+}
+
+func (vm *VM) run() *interpreter.Error {
+	f := &vm.frames[len(vm.frames)-1]
+	ins := f.fn.Instructions
+
+	for f.ip < len(ins) {
+		ip := f.ip
+		op := Opcode(ins[ip])
+
+		switch op {
+		case OpConstant:
+			vm.push(vm.constants[readUint16(ins[ip+1:])])
+			f.ip += 3
+
+		case OpPop:
+			vm.sp--
+			f.ip++
+
+		case OpNull:
+			vm.push(nullObj)
+			f.ip++
+
+		case OpTrue:
+			vm.push(trueObj)
+			f.ip++
+
+		case OpFalse:
+			vm.push(falseObj)
+			f.ip++
+
+		case OpAdd, OpSub, OpMul, OpDiv, OpIntDiv, OpMod, OpConcat,
+			OpEqual, OpNotEqual, OpLess, OpGreater, OpLessEqual, OpGreaterEqual,
+			OpAnd, OpOr:
+			right := vm.pop()
+			left := vm.pop()
+			result := vm.infix(op, left, right)
+			if err, ok := result.(*interpreter.Error); ok {
+				return err
+			}
+			vm.push(result)
+			f.ip++
+
+		case OpMinus, OpNot:
+			operator := "-"
+			if op == OpNot {
+				operator = "NOT"
+			}
+			result := vm.arith.Unary(operator, vm.pop())
+			if err, ok := result.(*interpreter.Error); ok {
+				return err
+			}
+			vm.push(result)
+			f.ip++
+
+		case OpJump:
+			f.ip = int(readUint16(ins[ip+1:]))
+
+		case OpJumpFalse:
+			if interpreter.IsTruthy(vm.pop()) {
+				f.ip += 3
+			} else {
+				f.ip = int(readUint16(ins[ip+1:]))
+			}
+
+		case OpJumpTrue:
+			if interpreter.IsTruthy(vm.pop()) {
+				f.ip = int(readUint16(ins[ip+1:]))
+			} else {
+				f.ip += 3
+			}
+
+		case OpGetGlobal:
+			idx := readUint16(ins[ip+1:])
+			value := vm.globals[idx]
+			if value == nil {
+				return interpreter.NewError(diagnostic.UndefinedIdentifier, vm.names[idx])
+			}
+			vm.push(value)
+			f.ip += 3
+
+		case OpSetGlobal:
+			vm.globals[readUint16(ins[ip+1:])] = vm.pop()
+			f.ip += 3
+
+		case OpGetLocal:
+			idx := int(ins[ip+1])
+			value := vm.stack[f.base+idx]
+			if value == nil {
+				return interpreter.NewError(diagnostic.UndefinedIdentifier, f.fn.Locals[idx])
+			}
+			vm.push(value)
+			f.ip += 2
+
+		case OpSetLocal:
+			vm.stack[f.base+int(ins[ip+1])] = vm.pop()
+			f.ip += 2
+
+		case OpGetBuiltin:
+			idx := int(ins[ip+1])
+			if idx >= len(vm.builtins) || vm.builtins[idx] == nil {
+				return interpreter.NewError(diagnostic.UndefinedIdentifier, vm.wanted[idx])
+			}
+			vm.push(vm.builtins[idx])
+			f.ip += 2
+
+		case OpNewArray:
+			proto := vm.constants[readUint16(ins[ip+1:])].(*interpreter.Array)
+			vm.push(interpreter.NewArray(proto.Dimensions, proto.Default))
+			f.ip += 3
+
+		case OpIndex:
+			n := int(ins[ip+1])
+			array, indices, err := vm.element(n)
+			if err != nil {
+				return err
+			}
+			if value := array.Get(indices...); value != nil {
+				vm.push(value)
+			} else {
+				vm.push(nullObj)
+			}
+			f.ip += 2
+
+		case OpSetIndex:
+			n := int(ins[ip+1])
+			value := vm.pop()
+			array, indices, err := vm.element(n)
+			if err != nil {
+				return err
+			}
+			array.Set(value, indices...)
+			f.ip += 2
+
+		case OpForInit:
+			step := vm.pop()
+			end := vm.pop()
+			start := vm.pop()
+			if _, ok := start.(*interpreter.Integer); !ok {
+				return interpreter.NewError(diagnostic.ForStartNotInteger)
+			}
+			if _, ok := end.(*interpreter.Integer); !ok {
+				return interpreter.NewError(diagnostic.ForEndNotInteger)
+			}
+			if _, ok := step.(*interpreter.Integer); !ok {
+				step = &interpreter.Integer{Value: 1}
+			}
+			vm.push(start)
+			vm.push(end)
+			vm.push(step)
+			f.ip++
+
+		case OpForTest:
+			step := vm.pop().(*interpreter.Integer).Value
+			end := vm.pop().(*interpreter.Integer).Value
+			current := vm.pop().(*interpreter.Integer).Value
+			if (step > 0 && current > end) || (step < 0 && current < end) {
+				vm.push(falseObj)
+			} else {
+				vm.push(trueObj)
+			}
+			f.ip++
+
+		case OpCaseMatch:
+			value := vm.pop()
+			subject := vm.pop()
+			vm.push(boolean(interpreter.ObjectsEqual(subject, value)))
+			f.ip++
+
+		case OpCaseRange:
+			end := vm.pop()
+			start := vm.pop()
+			subject := vm.pop()
+			vm.push(boolean(interpreter.ValueInRange(subject, start, end)))
+			f.ip++
+
+		case OpInput:
+			line, err := vm.input.ReadString('\n')
+			if err != nil && err != io.EOF {
+				return interpreter.NewError(diagnostic.InputFailed, err)
+			}
+			vm.push(&interpreter.String{Value: strings.TrimRight(line, "\r\n")})
+			f.ip++
+
+		case OpOutput:
+			n := int(ins[ip+1])
+			var out strings.Builder
+			for _, value := range vm.stack[vm.sp-n : vm.sp] {
+				out.WriteString(value.Inspect())
+			}
+			vm.sp -= n
+			fmt.Fprintln(vm.output, out.String())
+			f.ip += 2
+
+		case OpFail:
+			// Each failure gets its own copy so that its position can be set
+			err := *vm.constants[readUint16(ins[ip+1:])].(*interpreter.Error)
+			return &err
+
+		case OpCall:
+			n := int(ins[ip+1])
+			f.ip += 2
+			callee := vm.stack[vm.sp-1-n]
+
+			switch callee := callee.(type) {
+			case *CompiledFunction:
+				// The main program's frame is not a call
+				if len(vm.frames) > vm.maxDepth {
+					line, _ := f.fn.position(ip)
+					return vm.recursionLimit(callee, line)
+				}
+
+				// Arguments become the first locals of the new frame
+				base := vm.sp - n
+				for vm.sp < base+callee.NumLocals {
+					vm.push(nil)
+				}
+				for idx := base + min(n, callee.NumParams); idx < base+callee.NumLocals; idx++ {
+					vm.stack[idx] = nil
+				}
+				vm.sp = base + callee.NumLocals
+
+				// Checked first, as building the record would slow every call
+				if logger.Enabled(context.Background(), slog.LevelDebug) {
+					logger.Debug("call", strings.ToLower(callee.Kind), callee.Name, "args", n)
+				}
+				vm.frames = append(vm.frames, frame{fn: callee, base: base})
+				f = &vm.frames[len(vm.frames)-1]
+				ins = f.fn.Instructions
+
+			case *interpreter.Builtin:
+				args := make([]interpreter.Object, n)
+				copy(args, vm.stack[vm.sp-n:vm.sp])
+				vm.sp -= n + 1
+				result := vm.arith.Fit(callee.Call(args))
+				if err, ok := result.(*interpreter.Error); ok {
+					// Builtins are reported from the statement that called them
+					f.ip = ip
+					return err
+				}
+				vm.push(result)
+
+			default:
+				f.ip = ip
+				return interpreter.NewError(diagnostic.NotAFunction, callee.Type())
+			}
+
+		case OpReturnValue, OpReturn:
+			result := interpreter.Object(nullObj)
+			if op == OpReturnValue {
+				result = vm.pop()
+			}
+
+			if len(vm.frames) == 1 {
+				// RETURN in the main program ends it
+				return nil
+			}
+
+			vm.sp = f.base - 1
+			vm.frames = vm.frames[:len(vm.frames)-1]
+			f = &vm.frames[len(vm.frames)-1]
+			ins = f.fn.Instructions
+			vm.push(result)
+
+		default:
+			return &interpreter.Error{Message: fmt.Sprintf("unknown opcode %d", op)}
+		}
+	}
+
+	return nil
+}
+
+// recursionLimit reports a call to callee from line that would nest too
+// deeply, listing the innermost calls already made
+func (vm *VM) recursionLimit(callee *CompiledFunction, line int) *interpreter.Error {
+	err := interpreter.NewError(diagnostic.RecursionLimit, vm.maxDepth, callee.Kind, callee.Name, line)
+	for idx := len(vm.frames) - 1; idx > 0 && len(err.Calls) < maxReportedCalls; idx-- {
+		caller := vm.frames[idx-1]
+		line, column := caller.fn.position(caller.ip - 1)
+		fn := vm.frames[idx].fn
+		err.Calls = append(err.Calls, interpreter.Call{Kind: fn.Kind, Name: fn.Name, Line: line, Column: column})
+	}
+	return err
+}
+
+// element pops n indices and the array they index
+func (vm *VM) element(n int) (*interpreter.Array, []int64, *interpreter.Error) {
+	indices := make([]int64, n)
+	for i := n - 1; i >= 0; i-- {
+		idx, ok := vm.pop().(*interpreter.Integer)
+		if !ok {
+			vm.sp -= i + 1
+			return nil, nil, interpreter.NewError(diagnostic.ArrayIndexNotInteger)
+		}
+		indices[i] = idx.Value
+	}
+
+	array, ok := vm.pop().(*interpreter.Array)
+	if !ok {
+		return nil, nil, interpreter.NewError(diagnostic.NotAnArray)
+	}
+	return array, indices, nil
+}
+
+// infix applies an infix operator, handling the common INTEGER cases
+// without going through the shared operator table
+// Sums and differences that overflow are left to the table, which reports
+// them or promotes them to arbitrary precision.
+func (vm *VM) infix(op Opcode, left, right interpreter.Object) interpreter.Object {
+	if l, ok := left.(*interpreter.Integer); ok && l.Big == nil {
+		if r, ok := right.(*interpreter.Integer); ok && r.Big == nil {
+			switch op {
+			case OpAdd:
+				if sum := l.Value + r.Value; (l.Value^sum)&(r.Value^sum) >= 0 {
+					return interpreter.NewInteger(sum)
+				}
+			case OpSub:
+				if diff := l.Value - r.Value; (l.Value^r.Value)&(l.Value^diff) >= 0 {
+					return interpreter.NewInteger(diff)
+				}
+			case OpLess:
+				return boolean(l.Value < r.Value)
+			case OpGreater:
+				return boolean(l.Value > r.Value)
+			case OpLessEqual:
+				return boolean(l.Value <= r.Value)
+			case OpGreaterEqual:
+				return boolean(l.Value >= r.Value)
+			case OpEqual:
+				return boolean(l.Value == r.Value)
+			case OpNotEqual:
+				return boolean(l.Value != r.Value)
+			}
+		}
+	}
+	return vm.arith.Binary(operators[op], left, right)
+}
+
+func boolean(b bool) *interpreter.Boolean {
+	if b {
+		return trueObj
+	}
+	return falseObj
+}
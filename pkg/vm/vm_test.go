@@ -0,0 +1,255 @@
+package vm
+
+import (
+	"bytes"
+	"errors"
+	"strings"
+	"testing"
+
+	"github.com/andrinoff/cambridge-lang/pkg/ast"
+	"github.com/andrinoff/cambridge-lang/pkg/builtins"
+	"github.com/andrinoff/cambridge-lang/pkg/diagnostic"
+	"github.com/andrinoff/cambridge-lang/pkg/interpreter"
+	"github.com/andrinoff/cambridge-lang/pkg/lexer"
+	"github.com/andrinoff/cambridge-lang/pkg/parser"
+)
+
+func parse(t *testing.T, input string) *ast.Program {
+	t.Helper()
+	p := parser.New(lexer.New(input))
+	program := p.ParseProgram()
+	if len(p.Errors()) > 0 {
+		t.Fatalf("parser errors: %v", p.Errors())
+	}
+	return program
+}
+
+// runVM compiles and runs input, returning its output and runtime error
+func runVM(t *testing.T, input, stdin string) (string, *interpreter.Error) {
+	t.Helper()
+	bytecode, err := Compile(parse(t, input), builtins.GetBuiltins())
+	if err != nil {
+		t.Fatalf("compile error: %v", err)
+	}
+
+	var out bytes.Buffer
+	machine := New(bytecode)
+	machine.SetBuiltins(builtins.GetBuiltins())
+	machine.SetInput(strings.NewReader(stdin))
+	machine.SetOutput(&out)
+	return out.String(), machine.Run()
+}
+
+// runInterpreter runs input on the tree-walking interpreter for comparison
+func runInterpreter(t *testing.T, input, stdin string) string {
+	t.Helper()
+	var out bytes.Buffer
+	interp := interpreter.New()
+	interp.SetBuiltins(builtins.GetBuiltins())
+	interp.SetInput(strings.NewReader(stdin))
+	interp.SetOutput(&out)
+	if result, ok := interp.Eval(parse(t, input)).(*interpreter.Error); ok {
+		t.Fatalf("interpreter error: %s", result.Inspect())
+	}
+	return out.String()
+}
+
+func TestMatchesInterpreter(t *testing.T) {
+	tests := []struct {
+		name  string
+		input string
+		stdin string
+	}{
+		{"arithmetic", `OUTPUT 2 + 3 * 4, " ", 10 DIV 3, " ", 10 MOD 3, " ", 5 / 2, " ", -7, " ", 1.5 + 1`, ""},
+		{"strings", `DECLARE s : STRING
+s <- "ab"
+OUTPUT s & "cd" & 1, " ", LENGTH(s), " ", s = "ab", " ", 'x'`, ""},
+		{"logic", `OUTPUT 1 < 2 AND NOT (3 > 4), " ", TRUE OR FALSE, " ", 2 <> 2`, ""},
+		{"if", `DECLARE x : INTEGER
+x <- 7
+IF x > 5 THEN
+    OUTPUT "big"
+ELSE
+    OUTPUT "small"
+ENDIF
+IF x < 5 THEN
+    OUTPUT "never"
+ENDIF`, ""},
+		{"case", `FOR i <- 1 TO 5
+    CASE OF i
+        1 : OUTPUT "one"
+        2, 3 : OUTPUT "two or three"
+        4 TO 4 : OUTPUT "four"
+        OTHERWISE : OUTPUT "other"
+    ENDCASE
+NEXT i`, ""},
+		{"for", `DECLARE Total : INTEGER
+Total <- 0
+FOR i <- 10 TO 1 STEP -3
+    Total <- Total + i
+    i <- 100
+NEXT i
+OUTPUT Total`, ""},
+		{"while and repeat", `DECLARE n : INTEGER
+n <- 0
+WHILE n < 3
+    n <- n + 1
+ENDWHILE
+REPEAT
+    n <- n - 2
+UNTIL n < 0
+OUTPUT n`, ""},
+		{"arrays", `DECLARE A : ARRAY[1:5] OF INTEGER
+DECLARE G : ARRAY[1:2, 1:2] OF STRING
+FOR i <- 1 TO 5
+    A[i] <- i * i
+NEXT i
+G[1, 2] <- "x"
+OUTPUT A[3], A[5], G[1, 2]`, ""},
+		{"routines", `FUNCTION Fact(N : INTEGER) RETURNS INTEGER
+    IF N <= 1 THEN
+        RETURN 1
+    ENDIF
+    RETURN N * Fact(N - 1)
+ENDFUNCTION
+
+PROCEDURE Show(Label : STRING, Value : INTEGER)
+    OUTPUT Label, ": ", Value
+    Count <- Count + 1
+ENDPROCEDURE
+
+DECLARE Count : INTEGER
+Count <- 0
+CALL Show("5!", Fact(5))
+CALL Show("10!", Fact(10))
+OUTPUT Count`, ""},
+		{"input", `DECLARE Name : STRING
+DECLARE Names : ARRAY[1:2] OF STRING
+INPUT Name
+INPUT Names[2]
+OUTPUT Name, " and ", Names[2]`, "Ada\nGrace\n"},
+		{"constants", `CONSTANT Rate = 3
+OUTPUT Rate * 2`, ""},
+		{"top-level return", `OUTPUT "before"
+RETURN
+OUTPUT "after"`, ""},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			expected := runInterpreter(t, tt.input, tt.stdin)
+			got, err := runVM(t, tt.input, tt.stdin)
+			if err != nil {
+				t.Fatalf("runtime error: %s", err.Inspect())
+			}
+			if got != expected {
+				t.Errorf("output differs from the interpreter\nvm:          %q\ninterpreter: %q", got, expected)
+			}
+		})
+	}
+}
+
+func TestRuntimeErrors(t *testing.T) {
+	tests := []struct {
+		input  string
+		code   diagnostic.Code
+		line   int
+		column int
+	}{
+		{"DECLARE x : INTEGER\nx <- 1\nx <- x DIV 0", diagnostic.DivisionByZero, 3, 1},
+		{"OUTPUT Missing", diagnostic.UndefinedIdentifier, 1, 1},
+		{"CONSTANT Max = 3\nMax <- 4", diagnostic.ConstantModified, 2, 1},
+		{"PROCEDURE P()\n    OUTPUT 1\n    OUTPUT 1 / 0\nENDPROCEDURE\nCALL P()", diagnostic.DivisionByZero, 3, 5},
+		{"DECLARE x : INTEGER\nx <- 1\nCALL x()", diagnostic.NotAFunction, 3, 1},
+		{"FOR i <- \"a\" TO 3\nNEXT i", diagnostic.ForStartNotInteger, 1, 1},
+		{"PROCEDURE P()\n    CALL P()\nENDPROCEDURE\nCALL P()", diagnostic.RecursionLimit, 2, 5},
+		{"DECLARE x : INTEGER\nx <- 9223372036854775807\nOUTPUT x + 1", diagnostic.IntegerOverflow, 3, 1},
+	}
+
+	for _, tt := range tests {
+		_, err := runVM(t, tt.input, "")
+		if err == nil {
+			t.Errorf("%q: expected error %s", tt.input, tt.code)
+			continue
+		}
+		if err.Code != tt.code {
+			t.Errorf("%q: expected %s, got %s (%s)", tt.input, tt.code, err.Code, err.Message)
+		}
+		if err.Line != tt.line || err.Column != tt.column {
+			t.Errorf("%q: expected error at %d:%d, got %d:%d", tt.input, tt.line, tt.column, err.Line, err.Column)
+		}
+	}
+}
+
+func TestUnsupported(t *testing.T) {
+	for _, input := range []string{
+		"TYPE Point\n    DECLARE X : INTEGER\nENDTYPE",
+		"CLASS Pet\nENDCLASS",
+		`OPENFILE "data.txt" FOR READ`,
+	} {
+		_, err := Compile(parse(t, input), builtins.GetBuiltins())
+		if !errors.Is(err, ErrUnsupported) {
+			t.Errorf("%q: expected ErrUnsupported, got %v", input, err)
+		}
+	}
+}
+
+func TestMake(t *testing.T) {
+	tests := []struct {
+		op       Opcode
+		operands []int
+		expected []byte
+	}{
+		{OpConstant, []int{65534}, []byte{byte(OpConstant), 255, 254}},
+		{OpGetLocal, []int{255}, []byte{byte(OpGetLocal), 255}},
+		{OpAdd, nil, []byte{byte(OpAdd)}},
+	}
+
+	for _, tt := range tests {
+		ins := Make(tt.op, tt.operands...)
+		if !bytes.Equal(ins, tt.expected) {
+			t.Errorf("Make(%d, %v) = %v, want %v", tt.op, tt.operands, ins, tt.expected)
+		}
+
+		def, err := Lookup(tt.op)
+		if err != nil {
+			t.Fatal(err)
+		}
+		operands, n := ReadOperands(def, ins[1:])
+		if n != len(tt.expected)-1 {
+			t.Errorf("%s: read %d bytes, want %d", def.Name, n, len(tt.expected)-1)
+		}
+		for i, want := range tt.operands {
+			if operands[i] != want {
+				t.Errorf("%s: operand %d = %d, want %d", def.Name, i, operands[i], want)
+			}
+		}
+	}
+}
+
+func TestDisassemble(t *testing.T) {
+	input := `DECLARE x : INTEGER
+x <- x + 1
+OUTPUT "x is ", x`
+	bytecode, err := Compile(parse(t, input), builtins.GetBuiltins())
+	if err != nil {
+		t.Fatal(err)
+	}
+
+	var out bytes.Buffer
+	Disassemble(&out, bytecode, input)
+	listing := out.String()
+
+	for _, want := range []string{
+		"== PROGRAM (main) ==",
+		"  line 2: x <- x + 1",
+		"0000 CONSTANT 0           ; 0",
+		"SET_GLOBAL 0         ; x",
+		`CONSTANT 2           ; "x is "`,
+		"OUTPUT 2",
+	} {
+		if !strings.Contains(listing, want) {
+			t.Errorf("listing missing %q:\n%s", want, listing)
+		}
+	}
+}
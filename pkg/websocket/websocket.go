@@ -0,0 +1,174 @@
+// Package websocket is the server's side of a WebSocket, as RFC 6455 sets
+// it out, as far as the language server and playground need one: text
+// messages both ways, with pings answered
+package websocket
+
+import (
+	"bufio"
+	"crypto/sha1"
+	"encoding/base64"
+	"encoding/binary"
+	"errors"
+	"fmt"
+	"io"
+	"net"
+	"net/http"
+	"net/url"
+	"strings"
+	"sync"
+)
+
+// websocketGUID is appended to the key a client sends to accept its
+// handshake, as RFC 6455 sets out
+const websocketGUID = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11"
+
+// WebSocket frame opcodes
+const (
+	opText  = 0x1
+	opClose = 0x8
+	opPing  = 0x9
+	opPong  = 0xA
+)
+
+// maxMessage bounds the messages read, so that a bad length
+// cannot make the server allocate without limit
+const maxMessage = 64 << 20
+
+// Conn is an open WebSocket
+type Conn struct {
+	conn   net.Conn
+	reader *bufio.Reader
+	mu     sync.Mutex // keeps pongs from interleaving with messages
+}
+
+// SameOrigin reports whether r comes from a page served by the host it was
+// sent to, or from no page at all, as an editor or other program connects
+// A browser names the page making a request in its Origin header, even for
+// a WebSocket, which is otherwise exempt from the same-origin rules; a
+// server checks it so that another site cannot reach it from a browser.
+func SameOrigin(r *http.Request) bool {
+	origin := r.Header.Get("Origin")
+	if origin == "" {
+		return true
+	}
+	u, err := url.Parse(origin)
+	return err == nil && strings.EqualFold(u.Host, r.Host)
+}
+
+// Upgrade completes the handshake opening a WebSocket on the request r
+func Upgrade(w http.ResponseWriter, r *http.Request) (*Conn, error) {
+	key := r.Header.Get("Sec-WebSocket-Key")
+	if !strings.EqualFold(r.Header.Get("Upgrade"), "websocket") || key == "" {
+		http.Error(w, "expected a WebSocket handshake", http.StatusBadRequest)
+		return nil, errors.New("not a WebSocket handshake")
+	}
+	hijacker, ok := w.(http.Hijacker)
+	if !ok {
+		http.Error(w, "cannot take over the connection", http.StatusInternalServerError)
+		return nil, errors.New("connection cannot be hijacked")
+	}
+	conn, rw, err := hijacker.Hijack()
+	if err != nil {
+		return nil, err
+	}
+
+	accept := sha1.Sum([]byte(key + websocketGUID))
+	fmt.Fprintf(rw, "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: %s\r\n\r\n",
+		base64.StdEncoding.EncodeToString(accept[:]))
+	if err := rw.Flush(); err != nil {
+		conn.Close()
+		return nil, err
+	}
+	return &Conn{conn: conn, reader: rw.Reader}, nil
+}
+
+// Read returns the next message, put together from the frames it was
+// split into, answering pings on the way
+func (ws *Conn) Read() ([]byte, error) {
+	var msg []byte
+	for {
+		var head [2]byte
+		if _, err := io.ReadFull(ws.reader, head[:]); err != nil {
+			return nil, err
+		}
+		final, opcode := head[0]&0x80 != 0, head[0]&0x0f
+		masked, length := head[1]&0x80 != 0, uint64(head[1]&0x7f)
+		switch length {
+		case 126:
+			var ext [2]byte
+			if _, err := io.ReadFull(ws.reader, ext[:]); err != nil {
+				return nil, err
+			}
+			length = uint64(binary.BigEndian.Uint16(ext[:]))
+		case 127:
+			var ext [8]byte
+			if _, err := io.ReadFull(ws.reader, ext[:]); err != nil {
+				return nil, err
+			}
+			length = binary.BigEndian.Uint64(ext[:])
+		}
+		if length+uint64(len(msg)) > maxMessage {
+			return nil, fmt.Errorf("message longer than %d bytes", maxMessage)
+		}
+
+		var mask [4]byte
+		if masked {
+			if _, err := io.ReadFull(ws.reader, mask[:]); err != nil {
+				return nil, err
+			}
+		}
+		payload := make([]byte, length)
+		if _, err := io.ReadFull(ws.reader, payload); err != nil {
+			return nil, err
+		}
+		if masked {
+			for i := range payload {
+				payload[i] ^= mask[i%4]
+			}
+		}
+
+		switch opcode {
+		case opClose:
+			ws.frame(opClose, payload)
+			return nil, io.EOF
+		case opPing:
+			if err := ws.frame(opPong, payload); err != nil {
+				return nil, err
+			}
+		case opPong:
+		default:
+			msg = append(msg, payload...)
+			if final {
+				return msg, nil
+			}
+		}
+	}
+}
+
+// Write sends msg as a text message
+// It may be called from several goroutines at once.
+func (ws *Conn) Write(msg []byte) error {
+	return ws.frame(opText, msg)
+}
+
+// Close closes the connection without a closing handshake
+func (ws *Conn) Close() error {
+	return ws.conn.Close()
+}
+
+// frame sends payload in a single unmasked frame, as a server's are
+func (ws *Conn) frame(opcode byte, payload []byte) error {
+	head := []byte{0x80 | opcode}
+	switch n := len(payload); {
+	case n < 126:
+		head = append(head, byte(n))
+	case n <= 0xffff:
+		head = binary.BigEndian.AppendUint16(append(head, 126), uint16(n))
+	default:
+		head = binary.BigEndian.AppendUint64(append(head, 127), uint64(n))
+	}
+	ws.mu.Lock()
+	defer ws.mu.Unlock()
+	_, err := ws.conn.Write(append(head, payload...))
+	return err
+}
@@ -54,6 +54,7 @@ const KEYWORDS = [
   "READ",
   "WRITE",
   "APPEND",
+  "INCLUDE",
   "CLASS",
   "ENDCLASS",
   "INHERITS",
@@ -92,6 +93,7 @@ module.exports = grammar({
         $.class_declaration,
         $.type_declaration,
         $.file_operation,
+        $.include_statement,
       ),
 
     // Comments
@@ -429,6 +431,8 @@ module.exports = grammar({
     readfile: ($) => seq(kw("READFILE"), $._expression, ",", $.identifier),
 
     writefile: ($) => seq(kw("WRITEFILE"), $._expression, ",", $._expression),
+
+    include_statement: ($) => seq(kw("INCLUDE"), field("path", $.string)),
   },
 });
 
@@ -0,0 +1,20 @@
+(procedure_declaration
+  name: (identifier) @name) @definition.function
+
+(function_declaration
+  name: (identifier) @name) @definition.function
+
+(class_declaration
+  name: (identifier) @name) @definition.class
+
+(type_declaration
+  name: (identifier) @name) @definition.type
+
+(procedure_call
+  (identifier) @name) @reference.call
+
+(function_call
+  (identifier) @name) @reference.call
+
+(new_expression
+  (identifier) @name) @reference.class